@@ -1,5 +1,5 @@
 import { describe, expect, test, beforeEach, mock, afterEach } from 'bun:test';
-import { IpcServer, IpcClient, serializeMessage, deserializeMessage, FrameReader } from './ipc-client';
+import { IpcServer, IpcClient, serializeMessage, deserializeMessage, FrameReader, decodeRequestBody } from './ipc-client';
 import { createServer, Server } from 'net';
 import { unlinkSync, existsSync } from 'fs';
 
@@ -303,3 +303,27 @@ describe('IpcClient', () => {
     expect(promise).toBeInstanceOf(Promise);
   }, 35000);
 });
+
+describe('decodeRequestBody', () => {
+  const base = {
+    request_id: 'req-1',
+    method: 'POST',
+    path: '/upload',
+    path_only: '/upload',
+    query: {},
+    params: {},
+    headers: {},
+    cookies: {},
+  };
+
+  test('decodes base64 bodies into rawBody', () => {
+    const req = decodeRequestBody({ ...base, body: '/wD+', body_encoding: 'base64' });
+    expect(Array.from(req.rawBody!)).toEqual([0xff, 0x00, 0xfe]);
+  });
+
+  test('leaves text bodies alone', () => {
+    const req = decodeRequestBody({ ...base, body: 'hello', body_encoding: 'utf8' });
+    expect(req.body).toBe('hello');
+    expect(req.rawBody).toBeUndefined();
+  });
+});
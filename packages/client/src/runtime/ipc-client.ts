@@ -68,6 +68,30 @@ function deserializeMessage(data: Buffer): IpcMessage {
   return decode(data) as IpcMessage;
 }
 
+/**
+ * Decode a base64-encoded request body into `rawBody`
+ *
+ * Rust forwards bodies that are not valid UTF-8, or that declare a binary
+ * content type, base64-encoded with `body_encoding: "base64"`.
+ */
+function decodeRequestBody(req: ZapRequest): ZapRequest {
+  if (req.body_encoding !== "base64") {
+    return req;
+  }
+  return { ...req, rawBody: new Uint8Array(Buffer.from(req.body, "base64")) };
+}
+
+/**
+ * Retry hint carried by a retryable `RpcError` (e.g. a reloading worker)
+ */
+function retryAfterOf(error: unknown): number | undefined {
+  if (error && typeof error === "object" && "retryAfterMs" in error) {
+    const retryAfterMs = (error as { retryAfterMs?: unknown }).retryAfterMs;
+    return typeof retryAfterMs === "number" ? retryAfterMs : undefined;
+  }
+  return undefined;
+}
+
 /**
  * Write a length-prefixed message to a socket
  */
@@ -336,7 +360,7 @@ export class IpcServer {
 
       try {
         console.log(`[IPC] Invoking handler: ${handler_id} for ${request.method} ${request.path}`);
-        const result = handler(request);
+        const result = handler(decodeRequestBody(request));
 
         // Check if this is a streaming response (async iterable)
         if (isAsyncIterable<StreamChunk>(result)) {
@@ -356,6 +380,20 @@ export class IpcServer {
         }
       } catch (error: unknown) {
         const errorMessage = error instanceof Error ? error.message : String(error);
+        const retryAfterMs = retryAfterOf(error);
+        if (retryAfterMs !== undefined) {
+          // An RPC hit a reloading worker; let the client retry instead of failing
+          console.warn(`[IPC] Handler ${handler_id} unavailable, retry after ${retryAfterMs}ms`);
+          writeFramedMessage(socket, {
+            type: "error",
+            code: "SERVICE_UNAVAILABLE",
+            message: errorMessage,
+            status: 503,
+            digest: crypto.randomUUID(),
+            details: { retryAfterMs },
+          }, this.encoding);
+          return;
+        }
         console.error(`[IPC] Error executing handler ${handler_id}:`, errorMessage);
         writeFramedMessage(socket, {
           type: "error",
@@ -795,4 +833,4 @@ export class IpcClient extends EventEmitter {
 }
 
 // Export serialization utilities for testing
-export { serializeMessage, deserializeMessage, FrameReader };
+export { serializeMessage, deserializeMessage, FrameReader, decodeRequestBody };
@@ -16,7 +16,10 @@ const pendingRequests = new Map<string, PendingRequest>();
 export class RpcError extends Error {
   constructor(
     public readonly errorType: string,
-    message: string
+    message: string,
+    public readonly code?: number,
+    public readonly kind?: string,
+    public readonly retryAfterMs?: number
   ) {
     super(message);
     this.name = 'RpcError';
@@ -58,7 +61,10 @@ export async function initRpcClient(socketPath: string): Promise<void> {
         clearTimeout(pending.timeout);
         const error = new RpcError(
           msg.error_type || 'UnknownError',
-          msg.error || 'Unknown error'
+          msg.error || 'Unknown error',
+          msg.code,
+          msg.kind,
+          msg.retry_after_ms
         );
         pending.reject(error);
         pendingRequests.delete(msg.request_id);
@@ -96,11 +102,15 @@ export async function initRpcClient(socketPath: string): Promise<void> {
 
 /**
  * Call a Rust server function via RPC
+ *
+ * Pass the incoming request's `traceparent`/`tracestate` headers as `trace`
+ * to continue the same distributed trace inside the Rust function.
  */
 export async function rpcCall<T = unknown>(
   functionName: string,
   params: Record<string, unknown> = {},
-  timeoutMs: number = 30000
+  timeoutMs: number = 30000,
+  trace?: { traceparent?: string; tracestate?: string }
 ): Promise<T> {
   if (!ipcClient) {
     throw new Error('RPC client not initialized. Call initRpcClient() first.');
@@ -116,6 +126,8 @@ export async function rpcCall<T = unknown>(
     function_name: functionName,
     params,
     request_id: requestId,
+    ...(trace?.traceparent && { traceparent: trace.traceparent }),
+    ...(trace?.tracestate && { tracestate: trace.tracestate }),
   };
 
   return new Promise<T>((resolve, reject) => {
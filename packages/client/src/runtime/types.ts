@@ -36,8 +36,12 @@ export interface ZapRequest<
   params: TParams;
   /** HTTP headers */
   headers: Record<string, string>;
-  /** Request body as string */
+  /** Request body as string (base64 when `body_encoding` is "base64") */
   body: string;
+  /** "utf8" for text bodies, "base64" for bodies that were not valid UTF-8 */
+  body_encoding?: 'utf8' | 'base64';
+  /** Decoded body bytes, set when `body_encoding` is "base64" */
+  rawBody?: Uint8Array;
   /** Parsed cookies */
   cookies: Record<string, string>;
 }
@@ -267,6 +271,10 @@ export interface RpcCallMessage {
   function_name: string;
   params: Record<string, unknown>;
   request_id: string;
+  /** W3C traceparent of the calling request, continued by the Rust function */
+  traceparent?: string;
+  /** W3C tracestate forwarded alongside traceparent */
+  tracestate?: string;
 }
 
 /**
@@ -286,6 +294,12 @@ export interface RpcErrorMessage {
   request_id: string;
   error: string;
   error_type: string;
+  /** Splice protocol error code (`ERR_*`), when known */
+  code?: number;
+  /** Splice error kind (`user`, `system`, `timeout`, `cancelled`), when known */
+  kind?: string;
+  /** Milliseconds to wait before retrying, for retryable errors such as a reloading worker */
+  retry_after_ms?: number;
 }
 
 /**
@@ -454,6 +468,8 @@ export interface ZapConfig {
   port: number;
   hostname: string;
   ipc_socket_path: string;
+  /** Persistent IPC connections shared by all TypeScript routes (default: 4) */
+  ipc_pool_size?: number;
   max_request_body_size?: number;
   request_timeout_secs?: number;
   routes: RouteConfig[];
@@ -5,7 +5,7 @@
 //! - Serialization overhead: MessagePack vs JSON
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
-use zap_server::ipc::{IpcMessage, IpcRequest, IpcEncoding, serialize_message, deserialize_message};
+use zap_server::ipc::{BodyEncoding, IpcMessage, IpcRequest, IpcEncoding, serialize_message, deserialize_message};
 use std::collections::HashMap;
 
 /// Benchmark IPC message serialization
@@ -37,6 +37,7 @@ fn bench_ipc_serialization(c: &mut Criterion) {
                 h
             },
             body: String::new(),
+            body_encoding: BodyEncoding::Utf8,
             cookies: HashMap::new(),
         },
     };
@@ -199,6 +200,7 @@ fn bench_ipc_request_creation(c: &mut Criterion) {
                 params: black_box(HashMap::new()),
                 headers: black_box(HashMap::new()),
                 body: black_box(String::new()),
+                body_encoding: black_box(BodyEncoding::Utf8),
                 cookies: black_box(HashMap::new()),
             };
             black_box(req)
@@ -221,6 +223,7 @@ fn bench_ipc_request_creation(c: &mut Criterion) {
                     h
                 }),
                 body: black_box(r#"{"name":"John Doe","email":"john@example.com"}"#.to_string()),
+                body_encoding: black_box(BodyEncoding::Utf8),
                 cookies: black_box(HashMap::new()),
             };
             black_box(req)
@@ -245,6 +248,7 @@ fn bench_ipc_request_creation(c: &mut Criterion) {
                     h
                 }),
                 body: black_box(String::new()),
+                body_encoding: black_box(BodyEncoding::Utf8),
                 cookies: black_box(HashMap::new()),
             };
             black_box(req)
@@ -286,6 +290,7 @@ fn bench_ipc_round_trip(c: &mut Criterion) {
                 params: HashMap::new(),
                 headers: HashMap::new(),
                 body: String::new(),
+                body_encoding: BodyEncoding::Utf8,
                 cookies: HashMap::new(),
             },
         }),
@@ -409,6 +414,7 @@ fn bench_ipc_message_types(c: &mut Criterion) {
             params: HashMap::new(),
             headers: HashMap::new(),
             body: String::new(),
+            body_encoding: BodyEncoding::Utf8,
             cookies: HashMap::new(),
         },
     };
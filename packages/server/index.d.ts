@@ -70,8 +70,12 @@ export interface ZapRequest {
   params: Record<string, string>;
   /** HTTP headers */
   headers: Record<string, string>;
-  /** Request body as string */
+  /** Request body as string (base64 when `body_encoding` is "base64") */
   body: string;
+  /** "utf8" for text bodies, "base64" for bodies that were not valid UTF-8 */
+  body_encoding?: 'utf8' | 'base64';
+  /** Decoded body bytes, set when `body_encoding` is "base64" */
+  rawBody?: Uint8Array;
   /** Parsed cookies */
   cookies: Record<string, string>;
   /** Unique request ID for tracing */
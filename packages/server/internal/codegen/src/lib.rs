@@ -1,6 +1,8 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
+use std::str::FromStr;
 use syn::{Attribute, Fields, FnArg, ItemFn, ItemStruct, Pat, ReturnType, Type, Visibility};
 use walkdir::WalkDir;
 
@@ -45,8 +47,10 @@ pub enum ExportedType {
     U128,
     F32,
     F64,
-    Option(Box<ExportedType>),
-    Vec(Box<ExportedType>),
+    Option(#[serde(with = "nested")] Box<ExportedType>),
+    Vec(#[serde(with = "nested")] Box<ExportedType>),
+    /// `HashSet` or `BTreeSet`, sent as an array of unique elements
+    Set(#[serde(with = "nested")] Box<ExportedType>),
     HashMap {
         key: Box<ExportedType>,
         value: Box<ExportedType>,
@@ -60,6 +64,67 @@ pub enum ExportedType {
         ok: Box<ExportedType>,
         err: Box<ExportedType>,
     },
+    /// Non-empty tuple; `()` is `Unit`
+    Tuple {
+        elements: Vec<ExportedType>,
+    },
+    /// `Duration`, in serde's `{ secs, nanos }` form
+    Duration,
+    /// `SystemTime`, in serde's `{ secs_since_epoch, nanos_since_epoch }` form
+    SystemTime,
+    /// chrono `DateTime<Tz>`
+    Timestamp {
+        format: TimestampFormat,
+    },
+}
+
+/// How chrono `DateTime` values cross the wire, chosen with `--timestamps`
+///
+/// chrono serializes them as RFC 3339 strings; fields using its
+/// `ts_milliseconds` serde adapter need [`TimestampFormat::EpochMillis`].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// ISO-8601 string, e.g. `2024-01-31T12:00:00Z`
+    #[default]
+    Iso8601,
+    /// Milliseconds since the Unix epoch
+    EpochMillis,
+}
+
+impl FromStr for TimestampFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iso8601" | "iso" => Ok(TimestampFormat::Iso8601),
+            "epoch-ms" | "epoch_millis" => Ok(TimestampFormat::EpochMillis),
+            other => Err(format!("unknown timestamp format '{}' (expected iso8601 or epoch-ms)", other)),
+        }
+    }
+}
+
+/// `Option`, `Vec` and `Set` serialize as `{"type": "vec", "inner": {...}}`
+///
+/// A newtype variant of an internally tagged enum is serialized by wrapping
+/// the serializer, so a recursive enum never stops instantiating wrappers.
+/// Putting the inner type in a field avoids that.
+mod nested {
+    use super::ExportedType;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Nested<T> {
+        inner: T,
+    }
+
+    pub fn serialize<S: Serializer>(inner: &ExportedType, serializer: S) -> Result<S::Ok, S::Error> {
+        Nested { inner }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Box<ExportedType>, D::Error> {
+        Nested::<Box<ExportedType>>::deserialize(deserializer).map(|n| n.inner)
+    }
 }
 
 /// Metadata about an exported struct
@@ -97,10 +162,17 @@ impl ExportedType {
             | ExportedType::U128
             | ExportedType::F32
             | ExportedType::F64 => "number".to_string(),
+            ExportedType::Duration => "{ secs: number; nanos: number }".to_string(),
+            ExportedType::SystemTime => {
+                "{ secs_since_epoch: number; nanos_since_epoch: number }".to_string()
+            }
+            ExportedType::Timestamp { format: TimestampFormat::Iso8601 } => "string".to_string(),
+            ExportedType::Timestamp { format: TimestampFormat::EpochMillis } => "number".to_string(),
             ExportedType::Option(inner) => {
                 format!("{} | null", inner.to_typescript())
             }
-            ExportedType::Vec(inner) => {
+            // Sets arrive as JSON arrays; a `Set<T>` would need converting
+            ExportedType::Vec(inner) | ExportedType::Set(inner) => {
                 format!("{}[]", inner.to_typescript())
             }
             ExportedType::HashMap { key, value } => {
@@ -111,6 +183,14 @@ impl ExportedType {
                 )
             }
             ExportedType::Unit => "void".to_string(),
+            ExportedType::Tuple { elements } => {
+                let elements = elements
+                    .iter()
+                    .map(|e| e.to_typescript())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("[{}]", elements)
+            }
             ExportedType::Result { ok, err } => {
                 // Generate union type: T | E
                 format!("{} | {}", ok.to_typescript(), err.to_typescript())
@@ -130,21 +210,205 @@ impl ExportedType {
         }
     }
 
+    /// Convert Rust type to a Zod schema expression
+    ///
+    /// Map keys are validated as strings, since JSON object keys always are.
+    /// Custom types refer to their `<Name>Schema` lazily, so schemas may be
+    /// declared in any order and be recursive; generic arguments are not
+    /// checked. Types with no known shape (`serde_json::Value`, unparsed
+    /// types) are `z.unknown()`.
+    pub fn to_zod(&self) -> String {
+        self.to_zod_with(&|name| name != "unknown")
+    }
+
+    /// [`to_zod`](Self::to_zod) for output that declares a `<Name>Schema`
+    /// only for the custom types `has_schema` accepts
+    ///
+    /// Any other custom type, such as an enum or an external type, is
+    /// `z.unknown()` instead of a reference to a schema that does not exist.
+    pub fn to_zod_with(&self, has_schema: &dyn Fn(&str) -> bool) -> String {
+        match self {
+            ExportedType::String => "z.string()".to_string(),
+            ExportedType::Bool => "z.boolean()".to_string(),
+            ExportedType::I8
+            | ExportedType::I16
+            | ExportedType::I32
+            | ExportedType::I64
+            | ExportedType::I128
+            | ExportedType::U8
+            | ExportedType::U16
+            | ExportedType::U32
+            | ExportedType::U64
+            | ExportedType::U128
+            | ExportedType::F32
+            | ExportedType::F64 => "z.number()".to_string(),
+            ExportedType::Duration => {
+                "z.object({ secs: z.number(), nanos: z.number() })".to_string()
+            }
+            ExportedType::SystemTime => {
+                "z.object({ secs_since_epoch: z.number(), nanos_since_epoch: z.number() })".to_string()
+            }
+            ExportedType::Timestamp { format: TimestampFormat::Iso8601 } => "z.string()".to_string(),
+            ExportedType::Timestamp { format: TimestampFormat::EpochMillis } => "z.number()".to_string(),
+            ExportedType::Option(inner) => format!("z.nullable({})", inner.to_zod_with(has_schema)),
+            ExportedType::Vec(inner) => format!("z.array({})", inner.to_zod_with(has_schema)),
+            // `Set` compares objects by reference, so only primitives are checked
+            ExportedType::Set(inner) if inner.is_primitive() => format!(
+                "z.array({}).refine((items) => new Set(items).size === items.length, 'Expected unique items')",
+                inner.to_zod_with(has_schema)
+            ),
+            ExportedType::Set(inner) => format!("z.array({})", inner.to_zod_with(has_schema)),
+            ExportedType::HashMap { value, .. } => {
+                format!("z.record(z.string(), {})", value.to_zod_with(has_schema))
+            }
+            ExportedType::Unit => "z.void()".to_string(),
+            ExportedType::Tuple { elements } => {
+                let elements = elements.iter().map(|e| e.to_zod_with(has_schema)).collect::<Vec<_>>().join(", ");
+                format!("z.tuple([{}])", elements)
+            }
+            ExportedType::Result { ok, err } => {
+                format!("z.union([{}, {}])", ok.to_zod_with(has_schema), err.to_zod_with(has_schema))
+            }
+            ExportedType::Custom { name, .. } if has_schema(name) => {
+                format!("z.lazy(() => {})", zod_schema_name(name))
+            }
+            ExportedType::Custom { .. } => "z.unknown()".to_string(),
+        }
+    }
+
+    /// Unit of a time value, for the JSDoc of anything carrying one
+    ///
+    /// Looks through `Option`, `Vec`, map values and `Result`'s ok type, so
+    /// `Option<Vec<Duration>>` is still documented.
+    pub fn unit_note(&self) -> Option<&'static str> {
+        match self {
+            ExportedType::Duration => Some("Duration in whole seconds plus nanoseconds"),
+            ExportedType::SystemTime => {
+                Some("Time since the Unix epoch in whole seconds plus nanoseconds")
+            }
+            ExportedType::Timestamp { format: TimestampFormat::Iso8601 } => {
+                Some("Timestamp as an ISO-8601 string")
+            }
+            ExportedType::Timestamp { format: TimestampFormat::EpochMillis } => {
+                Some("Timestamp in milliseconds since the Unix epoch")
+            }
+            ExportedType::Option(inner) | ExportedType::Vec(inner) | ExportedType::Set(inner) => {
+                inner.unit_note()
+            }
+            ExportedType::HashMap { value, .. } => value.unit_note(),
+            ExportedType::Result { ok, .. } => ok.unit_note(),
+            _ => None,
+        }
+    }
+
+    /// Switch every timestamp in this type to `format`
+    pub fn set_timestamp_format(&mut self, format: TimestampFormat) {
+        match self {
+            ExportedType::Timestamp { format: current } => *current = format,
+            ExportedType::Option(inner) | ExportedType::Vec(inner) | ExportedType::Set(inner) => {
+                inner.set_timestamp_format(format)
+            }
+            ExportedType::HashMap { key, value } => {
+                key.set_timestamp_format(format);
+                value.set_timestamp_format(format);
+            }
+            ExportedType::Result { ok, err } => {
+                ok.set_timestamp_format(format);
+                err.set_timestamp_format(format);
+            }
+            ExportedType::Custom { generics: types, .. } | ExportedType::Tuple { elements: types } => {
+                for ty in types {
+                    ty.set_timestamp_format(format);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// TypeScript type of a parameter or return value
+    ///
+    /// The runtime converts binary data only at the call boundary, so
+    /// `Vec<u8>` surfaces as `Uint8Array` here but stays `number[]` inside
+    /// other types.
+    fn to_boundary_typescript(&self) -> String {
+        match self {
+            ExportedType::Option(inner) if self.is_bytes() => {
+                format!("{} | null", inner.to_boundary_typescript())
+            }
+            _ if self.is_bytes() => "Uint8Array".to_string(),
+            ty => ty.to_typescript(),
+        }
+    }
+
+    /// Zod schema of a parameter or return value, see
+    /// [`to_boundary_typescript`](Self::to_boundary_typescript)
+    fn to_boundary_zod(&self, has_schema: &dyn Fn(&str) -> bool) -> String {
+        match self {
+            ExportedType::Option(inner) if self.is_bytes() => {
+                format!("z.nullable({})", inner.to_boundary_zod(has_schema))
+            }
+            _ if self.is_bytes() => "z.instanceof(Uint8Array)".to_string(),
+            ty => ty.to_zod_with(has_schema),
+        }
+    }
+
+    /// Whether this is `Vec<u8>` or `Option<Vec<u8>>`, which cross the wire
+    /// base64-encoded and surface as `Uint8Array`
+    fn is_bytes(&self) -> bool {
+        match self {
+            ExportedType::Vec(inner) => **inner == ExportedType::U8,
+            ExportedType::Option(inner) => inner.is_bytes(),
+            _ => false,
+        }
+    }
+
+    /// Whether values of this type arrive as JS primitives
+    fn is_primitive(&self) -> bool {
+        matches!(
+            self,
+            ExportedType::String
+                | ExportedType::Bool
+                | ExportedType::I8
+                | ExportedType::I16
+                | ExportedType::I32
+                | ExportedType::I64
+                | ExportedType::I128
+                | ExportedType::U8
+                | ExportedType::U16
+                | ExportedType::U32
+                | ExportedType::U64
+                | ExportedType::U128
+                | ExportedType::F32
+                | ExportedType::F64
+                | ExportedType::Timestamp { .. }
+        )
+    }
+
     /// Convert parameter name to camelCase
+    ///
+    /// Leading and trailing underscores are kept as-is (`_internal`,
+    /// `type_`), and each interior run of underscores capitalizes the next
+    /// letter (`get__user` becomes `getUser`). Converting an already
+    /// camelCased name returns it unchanged.
     pub fn to_camel_case(snake_str: &str) -> String {
-        let mut result = String::new();
-        let mut capitalize_next = false;
-
-        for (i, c) in snake_str.chars().enumerate() {
-            if c == '_' {
-                capitalize_next = true;
-            } else if capitalize_next && i > 0 {
-                result.push(c.to_uppercase().next().unwrap());
-                capitalize_next = false;
-            } else {
-                result.push(c);
+        let start = snake_str.len() - snake_str.trim_start_matches('_').len();
+        let end = snake_str.trim_end_matches('_').len().max(start);
+        let (prefix, rest) = snake_str.split_at(start);
+        let (body, suffix) = rest.split_at(end - start);
+
+        let mut result = String::from(prefix);
+        for (i, word) in body.split('_').filter(|w| !w.is_empty()).enumerate() {
+            if i == 0 {
+                result.push_str(word);
+                continue;
+            }
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                result.extend(first.to_uppercase());
+                result.push_str(chars.as_str());
             }
         }
+        result.push_str(suffix);
 
         result
     }
@@ -161,6 +425,7 @@ fn collect_custom_types(ty: &ExportedType, types: &mut std::collections::HashSet
         }
         ExportedType::Option(inner) => collect_custom_types(inner, types),
         ExportedType::Vec(inner) => collect_custom_types(inner, types),
+        ExportedType::Set(inner) => collect_custom_types(inner, types),
         ExportedType::HashMap { key, value } => {
             collect_custom_types(key, types);
             collect_custom_types(value, types);
@@ -169,18 +434,386 @@ fn collect_custom_types(ty: &ExportedType, types: &mut std::collections::HashSet
             collect_custom_types(ok, types);
             collect_custom_types(err, types);
         }
+        ExportedType::Tuple { elements } => {
+            for e in elements {
+                collect_custom_types(e, types);
+            }
+        }
         _ => {}
     }
 }
 
+/// Type a call resolves with: the ok side of a `Result`
+///
+/// The `Err` side rejects the promise instead (see [`error_type`]).
+fn resolved_type(ty: &ExportedType) -> &ExportedType {
+    match ty {
+        ExportedType::Result { ok, .. } => ok,
+        ty => ty,
+    }
+}
+
+/// Error type a call rejects with, for `Result` returns
+fn error_type(ty: &ExportedType) -> Option<&ExportedType> {
+    match ty {
+        ExportedType::Result { err, .. } => Some(err),
+        _ => None,
+    }
+}
+
+/// Name of a custom error type, which surfaces as `ZapTypedError<Name>`
+///
+/// Plain `String` errors only carry a message, so they get no companion
+/// type.
+fn typed_error_name(ty: &ExportedType) -> Option<&str> {
+    match error_type(ty)? {
+        ExportedType::Custom { name, .. } if name != "unknown" => Some(name),
+        _ => None,
+    }
+}
+
+/// `<Name>Rejection` aliases for every custom error type, with their import
+///
+/// Lets callers narrow a caught error, e.g.
+/// `if (isZapTypedError(e)) { const err = e as ApiErrorRejection; }`.
+fn rejection_aliases(functions: &[&ExportedFunction]) -> String {
+    let names: std::collections::BTreeSet<_> = functions
+        .iter()
+        .filter_map(|f| typed_error_name(&f.return_type))
+        .collect();
+    if names.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("import type { ZapTypedError } from './errors';\n\n");
+    for name in names {
+        output.push_str(&format!(
+            "/** Rejection of a call that returned `Err({})` */\nexport type {}Rejection = ZapTypedError<{}>;\n\n",
+            name, name, name
+        ));
+    }
+    output
+}
+
+/// Functions in emission order: by namespace, then name
+///
+/// Discovery order follows the filesystem walk, so sorting keeps generated
+/// files stable between runs. Parameters keep their declared order.
+fn sorted_functions(functions: &[ExportedFunction]) -> Vec<&ExportedFunction> {
+    let mut sorted: Vec<_> = functions.iter().collect();
+    sorted.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    sorted
+}
+
+/// Words TypeScript rejects as parameter names (reserved, strict-mode
+/// reserved, and bindings strict mode forbids)
+const TS_RESERVED_WORDS: &[&str] = &[
+    "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger",
+    "default", "delete", "do", "else", "enum", "eval", "export", "extends", "false", "finally",
+    "for", "function", "if", "implements", "import", "in", "instanceof", "interface", "let",
+    "new", "null", "package", "private", "protected", "public", "return", "static", "super",
+    "switch", "this", "throw", "true", "try", "typeof", "var", "void", "while", "with", "yield",
+];
+
+/// TypeScript identifier for a parameter: camelCased, with a trailing
+/// underscore when that would be a reserved word (`class` becomes `class_`)
+///
+/// Only the TypeScript binding is renamed; the wire field keeps the Rust
+/// name.
+fn ts_param_name(name: &str) -> String {
+    let camel = ExportedType::to_camel_case(name);
+    if TS_RESERVED_WORDS.contains(&camel.as_str()) {
+        format!("{}_", camel)
+    } else {
+        camel
+    }
+}
+
+/// Positional parameter list for a TypeScript signature
+///
+/// Trailing `Option` parameters become optional (`x?: T`) so callers can omit
+/// them. An `Option` followed by a required parameter keeps `T | null`, since
+/// skipping it would shift the arguments after it.
+fn typescript_params(params: &[ExportedParam]) -> String {
+    let first_optional = params
+        .iter()
+        .rposition(|p| !matches!(p.ty, ExportedType::Option(_)))
+        .map_or(0, |last_required| last_required + 1);
+
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let name = ts_param_name(&p.name);
+            match &p.ty {
+                ExportedType::Option(inner) if i >= first_optional => {
+                    format!("{}?: {}", name, inner.to_boundary_typescript())
+                }
+                ty => format!("{}: {}", name, ty.to_boundary_typescript()),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Use `format` for every timestamp in functions and struct fields
+///
+/// Types are extracted with [`TimestampFormat::default`]; apply the
+/// configured format before generating.
+pub fn apply_timestamp_format(
+    functions: &mut [ExportedFunction],
+    structs: &mut [ExportedStruct],
+    format: TimestampFormat,
+) {
+    for func in functions {
+        func.return_type.set_timestamp_format(format);
+        for param in &mut func.params {
+            param.ty.set_timestamp_format(format);
+        }
+    }
+    for s in structs {
+        for field in &mut s.fields {
+            field.ty.set_timestamp_format(format);
+        }
+    }
+}
+
+/// Doc comment split into its summary and the rustdoc `# Arguments` and
+/// `# Returns` sections
+#[derive(Debug, Default, PartialEq)]
+struct ParsedDocs {
+    /// Everything outside the two sections, in order
+    description: Vec<String>,
+    /// Argument name as written in the docs, and its description
+    params: Vec<(String, String)>,
+    returns: Option<String>,
+}
+
+impl ParsedDocs {
+    /// Description documented for a parameter, matching snake_case or
+    /// camelCase spellings
+    fn param(&self, name: &str) -> Option<&str> {
+        let name = ExportedType::to_camel_case(name);
+        self.params
+            .iter()
+            .find(|(doc_name, _)| ExportedType::to_camel_case(doc_name) == name)
+            .map(|(_, desc)| desc.as_str())
+    }
+}
+
+/// Split doc comment lines following the rustdoc convention:
+///
+/// ```text
+/// Fetch a user
+///
+/// # Arguments
+/// * `id` - The user's ID
+///
+/// # Returns
+/// The user, if found
+/// ```
+///
+/// Argument entries may use `*` or `-` bullets, with or without backticks,
+/// and `-` or `:` before the description; indented lines continue the
+/// previous entry. Other headings stay in the description.
+fn parse_doc_comments(lines: &[String]) -> ParsedDocs {
+    enum Section {
+        Description,
+        Arguments,
+        Returns,
+    }
+
+    let mut docs = ParsedDocs::default();
+    let mut section = Section::Description;
+    let mut returns: Vec<&str> = Vec::new();
+    // `# ` inside a code block is a shell prompt or comment, not a heading
+    let mut in_fence = false;
+
+    for line in lines {
+        let line = line.trim();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if let Some(heading) = line.strip_prefix("# ").filter(|_| !in_fence) {
+            section = match heading.trim().to_lowercase().as_str() {
+                "arguments" | "parameters" | "params" => Section::Arguments,
+                "returns" => Section::Returns,
+                _ => {
+                    docs.description.push(line.to_string());
+                    Section::Description
+                }
+            };
+            continue;
+        }
+
+        match section {
+            Section::Description => docs.description.push(line.to_string()),
+            Section::Returns if !line.is_empty() => returns.push(line),
+            Section::Returns => {}
+            Section::Arguments => {
+                let entry = line.strip_prefix("* ").or_else(|| line.strip_prefix("- "));
+                match entry {
+                    Some(entry) => {
+                        let (name, desc) = entry
+                            .split_once(" - ")
+                            .or_else(|| entry.split_once(':'))
+                            .unwrap_or((entry, ""));
+                        docs.params.push((name.trim().trim_matches('`').to_string(), desc.trim().to_string()));
+                    }
+                    None if !line.is_empty() => {
+                        if let Some((_, desc)) = docs.params.last_mut() {
+                            if !desc.is_empty() {
+                                desc.push(' ');
+                            }
+                            desc.push_str(line);
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    while docs.description.last().is_some_and(|line| line.is_empty()) {
+        docs.description.pop();
+    }
+    if !returns.is_empty() {
+        docs.returns = Some(returns.join(" "));
+    }
+    docs
+}
+
+/// A documented description, with the unit of a time value appended
+fn describe(desc: Option<&str>, note: Option<&str>) -> Option<String> {
+    match (desc.filter(|d| !d.is_empty()), note) {
+        (Some(desc), Some(note)) => Some(format!("{} ({})", desc, note)),
+        (Some(desc), None) => Some(desc.to_string()),
+        (None, note) => note.map(str::to_string),
+    }
+}
+
+/// JSDoc block for a function
+///
+/// With `full`, the doc comments are included: the description, a
+/// `@param` tag for every parameter (a bare stub when undocumented) and
+/// `@returns` from `# Returns` or else the return type. Without it, only
+/// the unit of time values and what a `Result` rejects with are noted.
+///
+/// `param_prefix` qualifies parameter names (`params.` for object-style
+/// parameters). Returns an empty string when there is nothing to say.
+fn function_jsdoc(func: &ExportedFunction, full: bool, param_prefix: &str, indent: &str) -> String {
+    let docs = if full { parse_doc_comments(&func.doc_comments) } else { ParsedDocs::default() };
+    let mut lines = docs.description.clone();
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    for param in &func.params {
+        // Object-style parameters are property names, which may be reserved words
+        let name = if param_prefix.is_empty() {
+            ts_param_name(&param.name)
+        } else {
+            format!("{}{}", param_prefix, ExportedType::to_camel_case(&param.name))
+        };
+        match describe(docs.param(&param.name), param.ty.unit_note()) {
+            Some(desc) => lines.push(format!("@param {} - {}", name, desc)),
+            None if full => lines.push(format!("@param {}", name)),
+            None => {}
+        }
+    }
+
+    let returned = resolved_type(&func.return_type);
+    match describe(docs.returns.as_deref(), func.return_type.unit_note()) {
+        Some(desc) => lines.push(format!("@returns {}", desc)),
+        None if full && *returned != ExportedType::Unit => {
+            lines.push(format!("@returns `{}`", returned.to_boundary_typescript()))
+        }
+        None => {}
+    }
+    match (typed_error_name(&func.return_type), error_type(&func.return_type)) {
+        (Some(name), _) => lines.push(format!(
+            "@throws {{ZapTypedError<{}>}} When the function returns `Err`; the error is in `data`",
+            name
+        )),
+        (None, Some(_)) => lines.push("@throws {ZapError} When the function returns `Err`".to_string()),
+        (None, None) => {}
+    }
+
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    let mut output = format!("{}/**\n", indent);
+    for line in lines {
+        if line.is_empty() {
+            output.push_str(&format!("{} *\n", indent));
+        } else {
+            output.push_str(&format!("{} * {}\n", indent, line));
+        }
+    }
+    output.push_str(&format!("{} */\n", indent));
+    output
+}
+
+/// Base64 helpers emitted into runtime bindings that carry `Vec<u8>`
+const BYTES_HELPERS: &str = r#"function encodeBytes(bytes: Uint8Array): string {
+  let binary = '';
+  for (let i = 0; i < bytes.length; i++) {
+    binary += String.fromCharCode(bytes[i]);
+  }
+  return btoa(binary);
+}
+
+function decodeBytes(encoded: string): Uint8Array {
+  const binary = atob(encoded);
+  const bytes = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) {
+    bytes[i] = binary.charCodeAt(i);
+  }
+  return bytes;
+}
+
+"#;
+
+fn uses_bytes(functions: &[&ExportedFunction]) -> bool {
+    functions
+        .iter()
+        .any(|f| f.return_type.is_bytes() || f.params.iter().any(|p| p.ty.is_bytes()))
+}
+
+/// Wire value for a parameter expression, base64-encoding binary data
+fn encode_param(ty: &ExportedType, expr: &str) -> String {
+    match ty {
+        ExportedType::Option(_) if ty.is_bytes() => {
+            format!("{} == null ? null : encodeBytes({})", expr, expr)
+        }
+        _ if ty.is_bytes() => format!("encodeBytes({})", expr),
+        _ => expr.to_string(),
+    }
+}
+
+/// RPC result type and the `.then` step decoding it, for binary returns
+fn decode_result(ty: &ExportedType) -> (String, String) {
+    match ty {
+        ExportedType::Option(_) if ty.is_bytes() => (
+            "string | null".to_string(),
+            ".then((value) => (value == null ? null : decodeBytes(value)))".to_string(),
+        ),
+        _ if ty.is_bytes() => ("string".to_string(), ".then(decodeBytes)".to_string()),
+        _ => (ty.to_typescript(), String::new()),
+    }
+}
+
 /// Generate TypeScript type definitions
 pub fn generate_typescript_definitions(functions: &[ExportedFunction]) -> String {
+    let functions = sorted_functions(functions);
     let mut output = String::from("// Auto-generated TypeScript definitions\n");
     output.push_str("// DO NOT EDIT MANUALLY\n\n");
 
     // Collect all custom types used by functions
     let mut custom_types: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for func in functions {
+    for func in &functions {
         collect_custom_types(&func.return_type, &mut custom_types);
         for param in &func.params {
             collect_custom_types(&param.ty, &mut custom_types);
@@ -200,33 +833,17 @@ pub fn generate_typescript_definitions(functions: &[ExportedFunction]) -> String
     // Re-export all types for convenience
     output.push_str("// Re-export types for consumers\n");
     output.push_str("export * from './types';\n\n");
+    output.push_str(&rejection_aliases(&functions));
 
     // Generate JSDoc and function signatures
-    for func in functions {
+    for func in &functions {
         // Generate JSDoc comment
-        if !func.doc_comments.is_empty() {
-            output.push_str("/**\n");
-            for comment in &func.doc_comments {
-                output.push_str(&format!(" * {}\n", comment));
-            }
-            output.push_str(" */\n");
-        }
+        output.push_str(&function_jsdoc(func, true, "", ""));
 
         // Generate function signature
-        let params = func
-            .params
-            .iter()
-            .map(|p| {
-                format!(
-                    "{}: {}",
-                    ExportedType::to_camel_case(&p.name),
-                    p.ty.to_typescript()
-                )
-            })
-            .collect::<Vec<_>>()
-            .join(", ");
+        let params = typescript_params(&func.params);
 
-        let return_type = func.return_type.to_typescript();
+        let return_type = resolved_type(&func.return_type).to_boundary_typescript();
         let async_keyword = if func.is_async { "async " } else { "" };
 
         output.push_str(&format!(
@@ -237,21 +854,10 @@ pub fn generate_typescript_definitions(functions: &[ExportedFunction]) -> String
 
     // Generate backend object interface
     output.push_str("export interface ZapBackend {\n");
-    for func in functions {
-        let params = func
-            .params
-            .iter()
-            .map(|p| {
-                format!(
-                    "{}: {}",
-                    ExportedType::to_camel_case(&p.name),
-                    p.ty.to_typescript()
-                )
-            })
-            .collect::<Vec<_>>()
-            .join(", ");
+    for func in &functions {
+        let params = typescript_params(&func.params);
 
-        let return_type = func.return_type.to_typescript();
+        let return_type = resolved_type(&func.return_type).to_boundary_typescript();
 
         output.push_str(&format!(
             "  {}({}): Promise<{}>;\n",
@@ -270,13 +876,15 @@ pub fn generate_typescript_definitions(functions: &[ExportedFunction]) -> String
 
 /// Generate TypeScript runtime bindings (flat style)
 pub fn generate_typescript_runtime(functions: &[ExportedFunction]) -> String {
+    let functions = sorted_functions(functions);
     let mut output = String::from("// Auto-generated TypeScript runtime bindings\n");
     output.push_str("// DO NOT EDIT MANUALLY\n\n");
     output.push_str("import { rpcCall } from './rpc-client';\n");
+    output.push_str("import { toZapError } from './errors';\n");
 
     // Collect all custom types used by functions
     let mut custom_types: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for func in functions {
+    for func in &functions {
         collect_custom_types(&func.return_type, &mut custom_types);
         for param in &func.params {
             collect_custom_types(&param.ty, &mut custom_types);
@@ -295,53 +903,52 @@ pub fn generate_typescript_runtime(functions: &[ExportedFunction]) -> String {
 
     // Re-export all types for convenience
     output.push_str("// Re-export types for consumers\n");
-    output.push_str("export * from './types';\n\n");
+    output.push_str("export * from './types';\n");
+    output.push_str("export * from './errors';\n\n");
+
+    output.push_str(&rejection_aliases(&functions));
+
+    if uses_bytes(&functions) {
+        output.push_str(BYTES_HELPERS);
+    }
 
     // Generate backend object
     output.push_str("export const backend = {\n");
 
-    for func in functions {
+    for func in &functions {
         let fn_name = ExportedType::to_camel_case(&func.name);
         let rust_name = &func.name;
 
         // Generate typed parameters
-        let typed_params = func
-            .params
-            .iter()
-            .map(|p| {
-                let camel = ExportedType::to_camel_case(&p.name);
-                let ts_type = p.ty.to_typescript();
-                format!("{}: {}", camel, ts_type)
-            })
-            .collect::<Vec<_>>()
-            .join(", ");
+        let typed_params = typescript_params(&func.params);
 
         let param_mapping = func
             .params
             .iter()
-            .map(|p| {
-                let camel = ExportedType::to_camel_case(&p.name);
-                format!("{}: {}", p.name, camel)
-            })
+            .map(|p| format!("{}: {}", p.name, encode_param(&p.ty, &ts_param_name(&p.name))))
             .collect::<Vec<_>>()
             .join(", ");
 
-        let return_type = func.return_type.to_typescript();
+        let return_type = resolved_type(&func.return_type).to_boundary_typescript();
+        let (wire_type, decode) = decode_result(resolved_type(&func.return_type));
 
+        output.push_str(&function_jsdoc(func, false, "", "  "));
         output.push_str(&format!(
             r#"  async {}({}): Promise<{}> {{
-    return rpcCall<{}>('{}', {{ {} }});
+    return rpcCall<{}>('{}', {{ {} }}){}.catch((error: unknown) => {{
+      throw toZapError(error);
+    }});
   }},
 
 "#,
-            fn_name, typed_params, return_type, return_type, rust_name, param_mapping
+            fn_name, typed_params, return_type, wire_type, rust_name, param_mapping, decode
         ));
     }
 
     output.push_str("};\n\n");
 
     // Generate individual exports with proper types
-    for func in functions {
+    for func in &functions {
         let fn_name = ExportedType::to_camel_case(&func.name);
         output.push_str(&format!("export const {} = backend.{};\n", fn_name, fn_name));
     }
@@ -349,13 +956,13 @@ pub fn generate_typescript_runtime(functions: &[ExportedFunction]) -> String {
     output
 }
 
-/// Group functions by namespace
+/// Group functions by namespace, sorted by namespace and then function name
 pub fn group_by_namespace(functions: &[ExportedFunction]) -> Vec<FunctionNamespace> {
-    use std::collections::HashMap;
+    use std::collections::BTreeMap;
 
-    let mut groups: HashMap<String, Vec<ExportedFunction>> = HashMap::new();
+    let mut groups: BTreeMap<String, Vec<ExportedFunction>> = BTreeMap::new();
 
-    for func in functions {
+    for func in sorted_functions(functions) {
         let ns = func.namespace.clone().unwrap_or_else(|| "default".to_string());
         groups.entry(ns).or_default().push(func.clone());
     }
@@ -371,6 +978,7 @@ pub fn generate_namespaced_server(functions: &[ExportedFunction]) -> String {
     let mut output = String::from("// Auto-generated server client\n");
     output.push_str("// DO NOT EDIT MANUALLY\n\n");
     output.push_str("import { rpcCall } from './rpc-client';\n");
+    output.push_str("import { toZapError } from './errors';\n");
 
     // Collect all custom types used by functions
     let mut custom_types: std::collections::HashSet<String> = std::collections::HashSet::new();
@@ -393,7 +1001,12 @@ pub fn generate_namespaced_server(functions: &[ExportedFunction]) -> String {
 
     // Re-export all types for convenience
     output.push_str("// Re-export types for consumers\n");
-    output.push_str("export * from './types';\n\n");
+    output.push_str("export * from './types';\n");
+    output.push_str("export * from './errors';\n\n");
+
+    if uses_bytes(&functions.iter().collect::<Vec<_>>()) {
+        output.push_str(BYTES_HELPERS);
+    }
 
     let namespaces = group_by_namespace(functions);
 
@@ -424,7 +1037,7 @@ pub fn generate_namespaced_server(functions: &[ExportedFunction]) -> String {
                         format!(
                             "{}: {}",
                             ExportedType::to_camel_case(&p.name),
-                            p.ty.to_typescript()
+                            p.ty.to_boundary_typescript()
                         )
                     })
                     .collect::<Vec<_>>()
@@ -432,51 +1045,362 @@ pub fn generate_namespaced_server(functions: &[ExportedFunction]) -> String {
                 format!("params: {{ {} }}", params)
             };
 
-            let return_type = func.return_type.to_typescript();
+            let return_type = resolved_type(&func.return_type).to_boundary_typescript();
+            let (wire_type, decode) = decode_result(resolved_type(&func.return_type));
+
+            // Build RPC call params
+            let rpc_params = if func.params.is_empty() {
+                "{}".to_string()
+            } else {
+                let mappings = func
+                    .params
+                    .iter()
+                    .map(|p| {
+                        let camel = ExportedType::to_camel_case(&p.name);
+                        format!("{}: {}", p.name, encode_param(&p.ty, &format!("params.{}", camel)))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{{ {} }}", mappings)
+            };
+
+            output.push_str(&function_jsdoc(func, false, "params.", "    "));
+            output.push_str(&format!(
+                "    async {}({}): Promise<{}> {{\n",
+                fn_name, typed_params, return_type
+            ));
+            output.push_str(&format!(
+                "      return rpcCall<{}>('{}', {}){}.catch((error: unknown) => {{\n",
+                wire_type, rpc_name, rpc_params, decode
+            ));
+            output.push_str("        throw toZapError(error);\n");
+            output.push_str("      });\n");
+            output.push_str("    },\n");
+        }
+
+        output.push_str("  },\n");
+    }
+
+    output.push_str("} as const;\n\n");
+
+    // Generate types
+    output.push_str("export type Server = typeof server;\n");
+
+    output
+}
+
+/// A protocol error code and the TypeScript class it surfaces as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorClassMapping {
+    /// Splice protocol error code (`ERR_*`)
+    pub code: u16,
+    /// Generated class name
+    pub class_name: &'static str,
+    /// Class the generated class extends
+    pub parent: &'static str,
+}
+
+/// Base class every generated error extends
+pub const ZAP_ERROR_CLASS: &str = "ZapError";
+
+/// Errors caused by the caller or the function's own logic (`ErrorKind::User`)
+pub const ZAP_USER_ERROR_CLASS: &str = "ZapUserError";
+
+/// Errors in the runtime rather than the function (`ErrorKind::System`)
+pub const ZAP_SYSTEM_ERROR_CLASS: &str = "ZapSystemError";
+
+/// Known protocol error codes, in code order
+pub const ERROR_CLASS_MAPPINGS: &[ErrorClassMapping] = &[
+    ErrorClassMapping { code: splice::protocol::ERR_INVALID_REQUEST, class_name: "ZapInvalidRequestError", parent: ZAP_USER_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_INVALID_PARAMS, class_name: "ZapInvalidParamsError", parent: ZAP_USER_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_FUNCTION_NOT_FOUND, class_name: "ZapFunctionNotFoundError", parent: ZAP_USER_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_UNAUTHORIZED, class_name: "ZapUnauthorizedError", parent: ZAP_USER_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_FRAME_TOO_LARGE, class_name: "ZapFrameTooLargeError", parent: ZAP_USER_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_EXECUTION_FAILED, class_name: "ZapExecutionError", parent: ZAP_USER_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_TIMEOUT, class_name: "ZapTimeoutError", parent: ZAP_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_CANCELLED, class_name: "ZapCancelledError", parent: ZAP_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_PANIC, class_name: "ZapPanicError", parent: ZAP_SYSTEM_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_INTERNAL_ERROR, class_name: "ZapInternalError", parent: ZAP_SYSTEM_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_UNAVAILABLE, class_name: "ZapUnavailableError", parent: ZAP_SYSTEM_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_OVERLOADED, class_name: "ZapOverloadedError", parent: ZAP_SYSTEM_ERROR_CLASS },
+    ErrorClassMapping { code: splice::protocol::ERR_RELOADING, class_name: "ZapReloadingError", parent: ZAP_SYSTEM_ERROR_CLASS },
+];
+
+/// Class generated for a protocol error code, if the code is known
+pub fn error_class_for_code(code: u16) -> Option<&'static str> {
+    ERROR_CLASS_MAPPINGS
+        .iter()
+        .find(|mapping| mapping.code == code)
+        .map(|mapping| mapping.class_name)
+}
+
+/// Class for an `ErrorKind` (numeric or lowercase name), used when an error
+/// carries a kind but no known code
+pub fn error_class_for_kind(kind: &str) -> &'static str {
+    match kind {
+        "1" | "user" => ZAP_USER_ERROR_CLASS,
+        "2" | "system" => ZAP_SYSTEM_ERROR_CLASS,
+        "3" | "timeout" => "ZapTimeoutError",
+        "4" | "cancelled" => "ZapCancelledError",
+        _ => ZAP_ERROR_CLASS,
+    }
+}
+
+/// Generate the TypeScript error-class hierarchy (`errors.ts`)
+///
+/// Callers can `catch` and `instanceof`-check specific failures;
+/// `toZapError` turns an RPC error payload into the matching class.
+pub fn generate_typescript_errors() -> String {
+    let mut output = String::from("// Auto-generated TypeScript error classes\n");
+    output.push_str("// DO NOT EDIT MANUALLY\n\n");
+
+    output.push_str(
+        r#"export type ZapErrorKind = 'user' | 'system' | 'timeout' | 'cancelled';
+
+/**
+ * Base class for errors raised by Rust server functions
+ */
+export class ZapError extends Error {
+  constructor(
+    message: string,
+    public readonly code?: number,
+    public readonly kind?: ZapErrorKind,
+    public readonly errorType?: string,
+    public readonly data?: unknown
+  ) {
+    super(message);
+    this.name = new.target.name;
+
+    // Restore prototype chain for proper instanceof checks
+    Object.setPrototypeOf(this, new.target.prototype);
+  }
+}
+
+export class ZapUserError extends ZapError {}
+
+export class ZapSystemError extends ZapError {}
+
+/**
+ * Error from a function returning `Result<T, E>` with a serializable `E`
+ *
+ * `data` holds the deserialized `E`.
+ */
+export type ZapTypedError<E> = ZapError & { readonly data: E };
+
+export function isZapTypedError(error: unknown): error is ZapTypedError<unknown> {
+  return error instanceof ZapError && error.data !== undefined;
+}
+
+"#,
+    );
+
+    for mapping in ERROR_CLASS_MAPPINGS {
+        output.push_str(&format!(
+            "export class {} extends {} {{}}\n\n",
+            mapping.class_name, mapping.parent
+        ));
+    }
+
+    output.push_str("type ZapErrorClass = new (\n");
+    output.push_str("  message: string,\n");
+    output.push_str("  code?: number,\n");
+    output.push_str("  kind?: ZapErrorKind,\n");
+    output.push_str("  errorType?: string,\n");
+    output.push_str("  data?: unknown\n");
+    output.push_str(") => ZapError;\n\n");
+
+    output.push_str("const ERROR_CLASSES_BY_CODE: Record<number, ZapErrorClass> = {\n");
+    for mapping in ERROR_CLASS_MAPPINGS {
+        output.push_str(&format!("  {}: {},\n", mapping.code, mapping.class_name));
+    }
+    output.push_str("};\n\n");
+
+    output.push_str("const ERROR_CLASSES_BY_KIND: Record<ZapErrorKind, ZapErrorClass> = {\n");
+    for kind in ["user", "system", "timeout", "cancelled"] {
+        output.push_str(&format!("  {}: {},\n", kind, error_class_for_kind(kind)));
+    }
+    output.push_str("};\n\n");
+
+    output.push_str(
+        r#"const KIND_NAMES: Record<number, ZapErrorKind> = {
+  1: 'user',
+  2: 'system',
+  3: 'timeout',
+  4: 'cancelled',
+};
+
+// Marks an error message carrying a serialized `Err` value
+const TYPED_ERROR_PREFIX = '__TYPED_ERROR__:';
+
+function parseTypedError(message: string): { message: string; data?: unknown } {
+  if (!message.startsWith(TYPED_ERROR_PREFIX)) {
+    return { message };
+  }
+  const json = message.slice(TYPED_ERROR_PREFIX.length);
+  try {
+    return { message: json, data: JSON.parse(json) };
+  } catch {
+    return { message: json };
+  }
+}
+
+function normalizeKind(kind: unknown): ZapErrorKind | undefined {
+  if (typeof kind === 'number') {
+    return KIND_NAMES[kind];
+  }
+  if (typeof kind === 'string') {
+    const lower = kind.toLowerCase();
+    return lower in ERROR_CLASSES_BY_KIND ? (lower as ZapErrorKind) : undefined;
+  }
+  return undefined;
+}
+
+/**
+ * Build the matching error class from an RPC error payload
+ *
+ * The `code` picks the class when known, then the `kind`; client-side
+ * timeouts (`errorType: 'TimeoutError'`) become `ZapTimeoutError`. A
+ * serialized `Err` value in the message is parsed into `data`.
+ */
+export function createZapError(payload: {
+  message: string;
+  code?: number;
+  kind?: string | number;
+  errorType?: string;
+}): ZapError {
+  const kind = normalizeKind(payload.kind);
+  const ErrorClass =
+    (payload.code !== undefined ? ERROR_CLASSES_BY_CODE[payload.code] : undefined) ??
+    (kind !== undefined ? ERROR_CLASSES_BY_KIND[kind] : undefined) ??
+    (payload.errorType === 'TimeoutError' ? ZapTimeoutError : ZapError);
+  const { message, data } = parseTypedError(payload.message);
+  return new ErrorClass(message, payload.code, kind, payload.errorType, data);
+}
+
+/**
+ * Convert a rejected RPC call into a `ZapError`
+ *
+ * Errors that did not come from the server (e.g. a lost connection) are
+ * returned unchanged.
+ */
+export function toZapError(error: unknown): unknown {
+  if (error instanceof ZapError || !(error instanceof Error)) {
+    return error;
+  }
+  const rpc = error as Error & { errorType?: unknown; code?: unknown; kind?: unknown };
+  if (typeof rpc.errorType !== 'string') {
+    return error;
+  }
+  return createZapError({
+    message: rpc.message,
+    code: typeof rpc.code === 'number' ? rpc.code : undefined,
+    kind: typeof rpc.kind === 'string' || typeof rpc.kind === 'number' ? rpc.kind : undefined,
+    errorType: rpc.errorType,
+  });
+}
+"#,
+    );
+
+    output
+}
+
+/// Name of the exported Zod schema for a struct
+fn zod_schema_name(type_name: &str) -> String {
+    format!("{}Schema", type_name)
+}
+
+/// Zod schema for an object member (struct field or parameter)
+///
+/// `Option` members may be left out as well as sent as `null`: serde reads
+/// a missing `Option` field as `None`, and `#[export]` wrappers do the same
+/// for a missing `Option` parameter. `schema` converts the member's type.
+fn zod_member(ty: &ExportedType, schema: &dyn Fn(&ExportedType) -> String) -> String {
+    match ty {
+        ExportedType::Option(inner) => format!("{}.optional().nullable()", schema(inner)),
+        ty => schema(ty),
+    }
+}
+
+impl ExportedStruct {
+    /// Zod object schema for the struct, keyed like the generated interface
+    pub fn to_zod(&self) -> String {
+        self.to_zod_with(&|name| name != "unknown")
+    }
+
+    /// [`to_zod`](Self::to_zod), see [`ExportedType::to_zod_with`]
+    pub fn to_zod_with(&self, has_schema: &dyn Fn(&str) -> bool) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| {
+                let ts_name = field.ts_name.as_ref().unwrap_or(&field.name);
+                let member = zod_member(&field.ty, &|ty| ty.to_zod_with(has_schema));
+                format!("  {}: {},\n", ts_name, member)
+            })
+            .collect::<String>();
+        format!("z.object({{\n{}}})", fields)
+    }
+}
 
-            // Build RPC call params
-            let rpc_params = if func.params.is_empty() {
-                "{}".to_string()
-            } else {
-                let mappings = func
-                    .params
-                    .iter()
-                    .map(|p| {
-                        let camel = ExportedType::to_camel_case(&p.name);
-                        format!("{}: params.{}", p.name, camel)
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                format!("{{ {} }}", mappings)
-            };
+/// Generate Zod schemas for runtime validation
+///
+/// Emits `<Name>Schema` for every struct, plus `<fn>ParamsSchema` (an
+/// object keyed by the wire parameter names) and `<fn>ReturnSchema` for
+/// every function. Namespaced functions are prefixed with their namespace,
+/// e.g. `usersGetParamsSchema`.
+pub fn generate_zod_schemas(functions: &[ExportedFunction], structs: &[ExportedStruct]) -> String {
+    let mut output = String::from("// Auto-generated Zod schemas\n");
+    output.push_str("// DO NOT EDIT MANUALLY\n\n");
+    output.push_str("import { z } from 'zod';\n\n");
 
-            output.push_str(&format!(
-                "    async {}({}): Promise<{}> {{\n",
-                fn_name, typed_params, return_type
-            ));
-            output.push_str(&format!(
-                "      return rpcCall<{}>('{}', {});\n",
-                return_type, rpc_name, rpc_params
-            ));
-            output.push_str("    },\n");
-        }
+    let mut structs: Vec<_> = structs.iter().collect();
+    structs.sort_by(|a, b| a.name.cmp(&b.name));
+    let declared: HashSet<&str> = structs.iter().map(|s| s.name.as_str()).collect();
+    let has_schema = |name: &str| declared.contains(name);
 
-        output.push_str("  },\n");
+    for s in &structs {
+        output.push_str(&format!(
+            "export const {}: z.ZodType<any> = {};\n\n",
+            zod_schema_name(&s.name),
+            s.to_zod_with(&has_schema)
+        ));
     }
 
-    output.push_str("} as const;\n\n");
+    for func in sorted_functions(functions) {
+        let prefix = match &func.namespace {
+            Some(ns) => ExportedType::to_camel_case(&format!("{}_{}", ns, func.name)),
+            None => ExportedType::to_camel_case(&func.name),
+        };
 
-    // Generate types
-    output.push_str("export type Server = typeof server;\n");
+        let params = func
+            .params
+            .iter()
+            .map(|p| format!("  {}: {},\n", p.name, zod_member(&p.ty, &|ty| ty.to_boundary_zod(&has_schema))))
+            .collect::<String>();
+        output.push_str(&format!(
+            "export const {}ParamsSchema = z.object({{\n{}}});\n",
+            prefix, params
+        ));
+        output.push_str(&format!(
+            "export const {}ReturnSchema = {};\n\n",
+            prefix,
+            resolved_type(&func.return_type).to_boundary_zod(&has_schema)
+        ));
+    }
 
     output
 }
 
 /// Generate TypeScript interfaces from Rust structs
+///
+/// Interfaces are sorted by name; fields keep their declared order.
 pub fn generate_typescript_interfaces(structs: &[ExportedStruct]) -> String {
     let mut output = String::from("// Auto-generated TypeScript interfaces\n");
     output.push_str("// DO NOT EDIT MANUALLY\n\n");
 
+    let mut structs: Vec<_> = structs.iter().collect();
+    structs.sort_by(|a, b| a.name.cmp(&b.name));
+
     for s in structs {
         // Generate JSDoc comment
         if !s.doc_comments.is_empty() {
@@ -493,6 +1417,9 @@ pub fn generate_typescript_interfaces(structs: &[ExportedStruct]) -> String {
             let ts_name = field.ts_name.as_ref().unwrap_or(&field.name);
             let ts_type = field.ty.to_typescript();
 
+            if let Some(note) = field.ty.unit_note() {
+                output.push_str(&format!("  /** {} */\n", note));
+            }
             if field.optional {
                 output.push_str(&format!("  {}?: {};\n", ts_name, ts_type));
             } else {
@@ -603,6 +1530,7 @@ pub fn find_exported_structs(project_dir: &Path) -> anyhow::Result<Vec<ExportedS
 
     for entry in WalkDir::new(&search_dir)
         .into_iter()
+        .filter_entry(|e| e.file_name() != "target")
         .filter_map(Result::ok)
         .filter(|e| e.path().extension().is_some_and(|ext| ext == "rs"))
     {
@@ -717,6 +1645,12 @@ fn parse_type(ty: &Type) -> ExportedType {
                 "usize" => ExportedType::U64, // Map to u64
                 "f32" => ExportedType::F32,
                 "f64" => ExportedType::F64,
+                "Duration" => ExportedType::Duration,
+                "SystemTime" => ExportedType::SystemTime,
+                // The time zone parameter does not change the wire form
+                "DateTime" => ExportedType::Timestamp {
+                    format: TimestampFormat::default(),
+                },
                 "Option" => {
                     if let Some(inner) = generics.into_iter().next() {
                         ExportedType::Option(Box::new(inner))
@@ -731,6 +1665,10 @@ fn parse_type(ty: &Type) -> ExportedType {
                         ExportedType::Vec(Box::new(ExportedType::Unit))
                     }
                 }
+                "HashSet" | "BTreeSet" => {
+                    let inner = generics.into_iter().next().unwrap_or(ExportedType::Unit);
+                    ExportedType::Set(Box::new(inner))
+                }
                 "HashMap" | "BTreeMap" => {
                     let mut iter = generics.into_iter();
                     let key = iter.next().unwrap_or(ExportedType::String);
@@ -740,6 +1678,8 @@ fn parse_type(ty: &Type) -> ExportedType {
                         value: Box::new(value),
                     }
                 }
+                // Smart pointers serialize as their contents
+                "Box" | "Arc" | "Rc" => generics.into_iter().next().unwrap_or(ExportedType::Unit),
                 "Result" => {
                     let mut iter = generics.into_iter();
                     let ok = iter.next().unwrap_or(ExportedType::Unit);
@@ -762,6 +1702,9 @@ fn parse_type(ty: &Type) -> ExportedType {
         }
         Type::Reference(type_ref) => parse_type(&type_ref.elem),
         Type::Tuple(tuple) if tuple.elems.is_empty() => ExportedType::Unit,
+        Type::Tuple(tuple) => ExportedType::Tuple {
+            elements: tuple.elems.iter().map(parse_type).collect(),
+        },
         _ => ExportedType::Custom {
             name: "unknown".to_string(),
             generics: vec![],
@@ -862,26 +1805,39 @@ pub fn find_exported_functions(project_dir: &Path) -> anyhow::Result<Vec<Exporte
         };
 
         // Find all functions with #[export] attribute
-        for item in syntax.items {
-            if let syn::Item::Fn(func) = item {
-                if let Some(exported) = parse_function(&func) {
-                    eprintln!(
-                        "Found exported function: {} in {}",
-                        exported.name,
-                        entry.path().display()
-                    );
-                    functions.push(exported);
-                }
-            }
+        let mut found = Vec::new();
+        collect_exported_functions(&syntax.items, &mut found);
+        for exported in found {
+            eprintln!(
+                "Found exported function: {} in {}",
+                exported.name,
+                entry.path().display()
+            );
+            functions.push(exported);
         }
     }
 
     Ok(functions)
 }
 
+/// Collect exported functions from `items`, including inline `mod` blocks
+fn collect_exported_functions(items: &[syn::Item], functions: &mut Vec<ExportedFunction>) {
+    for item in items {
+        match item {
+            syn::Item::Fn(func) => functions.extend(parse_function(func)),
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_exported_functions(items, functions);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Convert Splice ExportMetadata to ExportedFunction
 pub fn convert_splice_exports_to_exported_functions(
-    exports: Vec<splice::ExportMetadata>,
+    exports: Vec<splice::protocol::ExportMetadata>,
 ) -> anyhow::Result<Vec<ExportedFunction>> {
     let mut functions = Vec::new();
 
@@ -947,11 +1903,20 @@ fn parse_type_from_schema(schema: &serde_json::Value) -> anyhow::Result<Exported
         Some("number") => Ok(ExportedType::F64),
         Some("null") => Ok(ExportedType::Unit),
         Some("array") => {
+            // Tuples list one schema per position
+            if let Some(items) = schema.get("prefixItems").or_else(|| schema.get("items")).and_then(|i| i.as_array()) {
+                let elements = items.iter().map(parse_type_from_schema).collect::<anyhow::Result<_>>()?;
+                return Ok(ExportedType::Tuple { elements });
+            }
             let items = schema
                 .get("items")
                 .ok_or_else(|| anyhow::anyhow!("Array schema missing 'items'"))?;
-            let inner = parse_type_from_schema(items)?;
-            Ok(ExportedType::Vec(Box::new(inner)))
+            let inner = Box::new(parse_type_from_schema(items)?);
+            if schema.get("uniqueItems").and_then(|u| u.as_bool()) == Some(true) {
+                Ok(ExportedType::Set(inner))
+            } else {
+                Ok(ExportedType::Vec(inner))
+            }
         }
         Some("object") => {
             // Check for additionalProperties (HashMap) or named properties (Custom struct)
@@ -1002,6 +1967,25 @@ fn parse_type_from_schema(schema: &serde_json::Value) -> anyhow::Result<Exported
 mod tests {
     use super::*;
 
+    fn parse(src: &str) -> ExportedType {
+        parse_type(&syn::parse_str::<Type>(src).unwrap())
+    }
+
+    /// Synchronous, undocumented top-level function
+    fn function(name: &str, params: Vec<(&str, ExportedType)>, return_type: ExportedType) -> ExportedFunction {
+        ExportedFunction {
+            name: name.to_string(),
+            namespace: None,
+            is_async: false,
+            params: params
+                .into_iter()
+                .map(|(name, ty)| ExportedParam { name: name.to_string(), ty })
+                .collect(),
+            return_type,
+            doc_comments: vec![],
+        }
+    }
+
     #[test]
     fn test_camel_case() {
         assert_eq!(ExportedType::to_camel_case("get_user"), "getUser");
@@ -1009,6 +1993,23 @@ mod tests {
         assert_eq!(ExportedType::to_camel_case("user"), "user");
     }
 
+    #[test]
+    fn test_camel_case_underscores() {
+        assert_eq!(ExportedType::to_camel_case("_internal"), "_internal");
+        assert_eq!(ExportedType::to_camel_case("_internal_id"), "_internalId");
+        assert_eq!(ExportedType::to_camel_case("get__user"), "getUser");
+        assert_eq!(ExportedType::to_camel_case("trailing_"), "trailing_");
+        assert_eq!(ExportedType::to_camel_case("__dunder"), "__dunder");
+        assert_eq!(ExportedType::to_camel_case("__dunder_init__"), "__dunderInit__");
+        assert_eq!(ExportedType::to_camel_case("___"), "___");
+        assert_eq!(ExportedType::to_camel_case(""), "");
+
+        for name in ["_internal", "get__user", "trailing_", "__dunder", "created_at"] {
+            let once = ExportedType::to_camel_case(name);
+            assert_eq!(ExportedType::to_camel_case(&once), once);
+        }
+    }
+
     #[test]
     fn test_type_to_typescript() {
         assert_eq!(ExportedType::String.to_typescript(), "string");
@@ -1023,6 +2024,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bytes_map_to_uint8array() {
+        let bytes = ExportedType::Vec(Box::new(ExportedType::U8));
+        assert_eq!(bytes.to_boundary_typescript(), "Uint8Array");
+        assert_eq!(bytes.to_boundary_zod(&|_| true), "z.instanceof(Uint8Array)");
+
+        // Only parameters and returns are converted; nested bytes stay JSON arrays
+        assert_eq!(bytes.to_typescript(), "number[]");
+        let nested = ExportedType::Vec(Box::new(bytes.clone()));
+        assert_eq!(nested.to_boundary_typescript(), "number[][]");
+        assert_eq!(nested.to_boundary_zod(&|_| true), "z.array(z.array(z.number()))");
+        let attachment = ExportedStruct {
+            name: "Attachment".to_string(),
+            fields: vec![StructField { name: "data".to_string(), ts_name: None, ty: bytes.clone(), optional: false }],
+            doc_comments: vec![],
+        };
+        assert!(attachment.to_zod().contains("data: z.array(z.number())"));
+
+        let func = ExportedFunction {
+            name: "resize_image".to_string(),
+            namespace: Some("images".to_string()),
+            is_async: true,
+            params: vec![
+                ExportedParam { name: "image".to_string(), ty: bytes.clone() },
+                ExportedParam {
+                    name: "watermark".to_string(),
+                    ty: ExportedType::Option(Box::new(bytes.clone())),
+                },
+            ],
+            return_type: bytes,
+            doc_comments: vec![],
+        };
+
+        let runtime = generate_typescript_runtime(std::slice::from_ref(&func));
+        assert!(runtime.contains("function encodeBytes(bytes: Uint8Array): string"));
+        assert!(runtime.contains("async resizeImage(image: Uint8Array, watermark?: Uint8Array): Promise<Uint8Array>"));
+        assert!(runtime.contains(
+            "rpcCall<string>('resize_image', { image: encodeBytes(image), watermark: watermark == null ? null : encodeBytes(watermark) }).then(decodeBytes)"
+        ));
+
+        let server = generate_namespaced_server(&[func]);
+        assert!(server.contains("image: encodeBytes(params.image)"));
+        assert!(server.contains(".then(decodeBytes)"));
+
+        let plain = generate_typescript_runtime(&[]);
+        assert!(!plain.contains("encodeBytes"));
+    }
+
     #[test]
     fn test_generate_definitions() {
         let func = ExportedFunction {
@@ -1045,6 +2094,32 @@ mod tests {
         assert!(defs.contains("Promise<User>"));
     }
 
+    #[test]
+    fn test_trailing_option_params_are_optional() {
+        let param = |name: &str, ty: ExportedType| ExportedParam { name: name.to_string(), ty };
+        let optional = |ty: ExportedType| ExportedType::Option(Box::new(ty));
+        let func = ExportedFunction {
+            name: "search_users".to_string(),
+            namespace: None,
+            is_async: true,
+            params: vec![
+                param("query", ExportedType::String),
+                param("team_id", optional(ExportedType::U64)),
+                param("limit", ExportedType::U32),
+                param("cursor", optional(ExportedType::String)),
+                param("include_deleted", optional(ExportedType::Bool)),
+            ],
+            return_type: ExportedType::Vec(Box::new(ExportedType::String)),
+            doc_comments: vec![],
+        };
+
+        let expected = "query: string, teamId: number | null, limit: number, cursor?: string, includeDeleted?: boolean";
+        let defs = generate_typescript_definitions(std::slice::from_ref(&func));
+        assert!(defs.contains(&format!("export async function search_users({}): Promise<string[]>;", expected)));
+        assert!(defs.contains(&format!("  searchUsers({}): Promise<string[]>;", expected)));
+        assert!(generate_typescript_runtime(&[func]).contains(&format!("async searchUsers({})", expected)));
+    }
+
     #[test]
     fn test_generate_namespaced_server() {
         let func = ExportedFunction {
@@ -1069,4 +2144,591 @@ mod tests {
         // Check RPC call uses namespaced name
         assert!(server.contains("'users.get'"));
     }
+
+    #[test]
+    fn test_error_code_maps_to_class() {
+        assert_eq!(error_class_for_code(splice::protocol::ERR_TIMEOUT), Some("ZapTimeoutError"));
+        assert_eq!(error_class_for_code(splice::protocol::ERR_UNAUTHORIZED), Some("ZapUnauthorizedError"));
+        assert_eq!(error_class_for_code(splice::protocol::ERR_OVERLOADED), Some("ZapOverloadedError"));
+        assert_eq!(error_class_for_code(9999), None);
+        assert_eq!(error_class_for_kind("2"), "ZapSystemError");
+        assert_eq!(error_class_for_kind("cancelled"), "ZapCancelledError");
+
+        let errors = generate_typescript_errors();
+        // Lookup tables map the wire values to the classes
+        assert!(errors.contains("  2001: ZapTimeoutError,\n"));
+        assert!(errors.contains("  1003: ZapUnauthorizedError,\n"));
+        assert!(errors.contains("  system: ZapSystemError,\n"));
+        // Hierarchy supports instanceof checks at each level
+        assert!(errors.contains("export class ZapUnauthorizedError extends ZapUserError {}"));
+        assert!(errors.contains("export class ZapPanicError extends ZapSystemError {}"));
+        assert!(errors.contains("export class ZapTimeoutError extends ZapError {}"));
+        assert!(errors.contains("export function toZapError("));
+
+        // Every class referenced in a table is declared
+        for mapping in ERROR_CLASS_MAPPINGS {
+            assert!(errors.contains(&format!("export class {} ", mapping.class_name)));
+        }
+    }
+
+    #[test]
+    fn test_runtime_converts_rpc_errors() {
+        let func = ExportedFunction {
+            name: "get_user".to_string(),
+            namespace: Some("users".to_string()),
+            is_async: true,
+            params: vec![],
+            return_type: ExportedType::String,
+            doc_comments: vec![],
+        };
+
+        for output in [generate_typescript_runtime(std::slice::from_ref(&func)), generate_namespaced_server(&[func])] {
+            assert!(output.contains("import { toZapError } from './errors';"));
+            assert!(output.contains("export * from './errors';"));
+            assert!(output.contains("throw toZapError(error);"));
+        }
+    }
+
+    #[test]
+    fn test_output_independent_of_discovery_order() {
+        let func = |namespace: Option<&str>, name: &str| ExportedFunction {
+            name: name.to_string(),
+            namespace: namespace.map(str::to_string),
+            is_async: true,
+            params: vec![
+                ExportedParam { name: "user_id".to_string(), ty: ExportedType::U64 },
+                ExportedParam { name: "active".to_string(), ty: ExportedType::Bool },
+            ],
+            return_type: ExportedType::Custom { name: format!("{}Result", name), generics: vec![] },
+            doc_comments: vec![],
+        };
+        let functions = vec![
+            func(Some("users"), "get"),
+            func(Some("users"), "delete"),
+            func(Some("billing"), "charge"),
+            func(None, "ping"),
+            func(None, "health"),
+        ];
+        let structs: Vec<ExportedStruct> = ["User", "Account", "Invoice"]
+            .iter()
+            .map(|name| ExportedStruct {
+                name: name.to_string(),
+                fields: vec![
+                    StructField { name: "zeta".to_string(), ts_name: None, ty: ExportedType::String, optional: false },
+                    StructField { name: "alpha".to_string(), ts_name: None, ty: ExportedType::U32, optional: true },
+                ],
+                doc_comments: vec![],
+            })
+            .collect();
+
+        let generate = |functions: &[ExportedFunction], structs: &[ExportedStruct]| {
+            [
+                generate_typescript_definitions(functions),
+                generate_typescript_runtime(functions),
+                generate_namespaced_server(functions),
+                generate_typescript_interfaces(structs),
+            ]
+        };
+        let expected = generate(&functions, &structs);
+
+        for shift in 1..functions.len() {
+            let mut shuffled = functions.clone();
+            shuffled.rotate_left(shift);
+            shuffled.swap(0, shift);
+            let mut shuffled_structs = structs.clone();
+            shuffled_structs.rotate_left(shift % structs.len());
+            assert_eq!(generate(&shuffled, &shuffled_structs), expected);
+        }
+
+        let mut reversed = functions.clone();
+        reversed.reverse();
+        assert_eq!(generate(&reversed, &structs), expected);
+
+        // Parameters and struct fields keep their declared order
+        assert!(expected[0].contains("userId: number, active: boolean"));
+        let interfaces = &expected[3];
+        assert!(interfaces.find("interface Account").unwrap() < interfaces.find("interface Invoice").unwrap());
+        assert!(interfaces.find("zeta: string").unwrap() < interfaces.find("alpha?: number").unwrap());
+    }
+
+    #[test]
+    fn test_type_to_zod() {
+        assert_eq!(ExportedType::String.to_zod(), "z.string()");
+        assert_eq!(ExportedType::Bool.to_zod(), "z.boolean()");
+        assert_eq!(ExportedType::U64.to_zod(), "z.number()");
+        assert_eq!(ExportedType::F32.to_zod(), "z.number()");
+        assert_eq!(
+            ExportedType::Option(Box::new(ExportedType::String)).to_zod(),
+            "z.nullable(z.string())"
+        );
+        assert_eq!(
+            ExportedType::Vec(Box::new(ExportedType::U32)).to_zod(),
+            "z.array(z.number())"
+        );
+        assert_eq!(
+            ExportedType::HashMap {
+                key: Box::new(ExportedType::String),
+                value: Box::new(ExportedType::Vec(Box::new(ExportedType::Bool))),
+            }
+            .to_zod(),
+            "z.record(z.string(), z.array(z.boolean()))"
+        );
+        assert_eq!(
+            ExportedType::Custom { name: "User".to_string(), generics: vec![] }.to_zod(),
+            "z.lazy(() => UserSchema)"
+        );
+    }
+
+    #[test]
+    fn test_tuple_types() {
+        let pair = ExportedType::Tuple { elements: vec![ExportedType::U64, ExportedType::String] };
+        assert_eq!(pair.to_typescript(), "[number, string]");
+        assert_eq!(pair.to_zod(), "z.tuple([z.number(), z.string()])");
+        assert_eq!(
+            ExportedType::Tuple { elements: vec![ExportedType::F64; 3] }.to_typescript(),
+            "[number, number, number]"
+        );
+
+        let json = serde_json::to_value(&pair).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "tuple", "elements": [{"type": "u64"}, {"type": "string"}]})
+        );
+        assert_eq!(serde_json::from_value::<ExportedType>(json).unwrap(), pair);
+
+        let nested = ExportedType::Option(Box::new(ExportedType::Vec(Box::new(pair.clone()))));
+        let json = serde_json::to_value(&nested).unwrap();
+        assert_eq!(json["inner"]["inner"]["elements"][1]["type"], "string");
+        assert_eq!(serde_json::from_value::<ExportedType>(json).unwrap(), nested);
+
+        assert_eq!(parse("(u64, String)"), pair);
+        assert_eq!(parse("()"), ExportedType::Unit);
+        assert_eq!(parse("()").to_typescript(), "void");
+
+        let func = function("centroid", vec![], parse("(f64, f64, f64)"));
+        let defs = generate_typescript_definitions(&[func]);
+        assert!(defs.contains("export function centroid(): Promise<[number, number, number]>;"));
+    }
+
+    #[test]
+    fn test_struct_to_zod_object() {
+        let user = ExportedStruct {
+            name: "User".to_string(),
+            fields: vec![
+                StructField { name: "id".to_string(), ty: ExportedType::U64, ts_name: None, optional: false },
+                StructField {
+                    name: "display_name".to_string(),
+                    ty: ExportedType::Option(Box::new(ExportedType::String)),
+                    ts_name: Some("displayName".to_string()),
+                    optional: true,
+                },
+            ],
+            doc_comments: vec![],
+        };
+        assert_eq!(
+            user.to_zod(),
+            "z.object({\n  id: z.number(),\n  displayName: z.string().optional().nullable(),\n})"
+        );
+
+        let func = ExportedFunction {
+            name: "get_user".to_string(),
+            namespace: Some("users".to_string()),
+            is_async: true,
+            params: vec![ExportedParam { name: "user_id".to_string(), ty: ExportedType::U64 }],
+            return_type: ExportedType::Custom { name: "User".to_string(), generics: vec![] },
+            doc_comments: vec![],
+        };
+        let schemas = generate_zod_schemas(&[func], &[user]);
+        assert!(schemas.contains("import { z } from 'zod';"));
+        assert!(schemas.contains("export const UserSchema: z.ZodType<any> = z.object({"));
+        assert!(schemas.contains("export const usersGetUserParamsSchema = z.object({\n  user_id: z.number(),\n});"));
+        assert!(schemas.contains("export const usersGetUserReturnSchema = z.lazy(() => UserSchema);"));
+    }
+
+    #[test]
+    fn test_zod_schemas_without_declared_schema_are_unknown() {
+        let custom = |name: &str| ExportedType::Custom { name: name.to_string(), generics: vec![] };
+        let func = ExportedFunction {
+            name: "set_role".to_string(),
+            namespace: None,
+            is_async: true,
+            // An enum and serde_json::Value, neither of which gets a schema
+            params: vec![
+                ExportedParam { name: "role".to_string(), ty: custom("Role") },
+                ExportedParam { name: "extra".to_string(), ty: custom("unknown") },
+            ],
+            return_type: ExportedType::Vec(Box::new(custom("Role"))),
+            doc_comments: vec![],
+        };
+
+        let schemas = generate_zod_schemas(&[func], &[]);
+        assert!(schemas.contains("export const setRoleParamsSchema = z.object({\n  role: z.unknown(),\n  extra: z.unknown(),\n});"));
+        assert!(schemas.contains("export const setRoleReturnSchema = z.array(z.unknown());"));
+        assert!(!schemas.contains("RoleSchema"));
+        assert_eq!(custom("unknown").to_zod(), "z.unknown()");
+    }
+
+    #[test]
+    fn test_zod_schemas_optional_members() {
+        let tags = ExportedType::Option(Box::new(ExportedType::Vec(Box::new(ExportedType::String))));
+        let post = ExportedStruct {
+            name: "Post".to_string(),
+            fields: vec![StructField { name: "tags".to_string(), ty: tags.clone(), ts_name: None, optional: true }],
+            doc_comments: vec![],
+        };
+        let func = ExportedFunction {
+            name: "list_posts".to_string(),
+            namespace: None,
+            is_async: true,
+            params: vec![
+                ExportedParam { name: "tags".to_string(), ty: tags },
+                ExportedParam {
+                    name: "counts".to_string(),
+                    ty: ExportedType::HashMap {
+                        key: Box::new(ExportedType::String),
+                        value: Box::new(ExportedType::U32),
+                    },
+                },
+            ],
+            return_type: ExportedType::Vec(Box::new(ExportedType::Custom { name: "Post".to_string(), generics: vec![] })),
+            doc_comments: vec![],
+        };
+
+        let schemas = generate_zod_schemas(&[func], &[post]);
+        assert!(schemas.contains("export const PostSchema: z.ZodType<any> = z.object({\n  tags: z.array(z.string()).optional().nullable(),\n});"));
+        assert!(schemas.contains(
+            "export const listPostsParamsSchema = z.object({\n  tags: z.array(z.string()).optional().nullable(),\n  counts: z.record(z.string(), z.number()),\n});"
+        ));
+        assert!(schemas.contains("export const listPostsReturnSchema = z.array(z.lazy(() => PostSchema));"));
+
+        // Every declaration is a complete statement with balanced delimiters
+        for statement in schemas.split(";\n").filter(|s| s.contains("export const")) {
+            let mut depth = Vec::new();
+            for c in statement.chars() {
+                match c {
+                    '(' | '{' | '[' => depth.push(c),
+                    ')' => assert_eq!(depth.pop(), Some('('), "{}", statement),
+                    '}' => assert_eq!(depth.pop(), Some('{'), "{}", statement),
+                    ']' => assert_eq!(depth.pop(), Some('['), "{}", statement),
+                    _ => {}
+                }
+            }
+            assert!(depth.is_empty(), "{}", statement);
+        }
+    }
+
+    #[test]
+    fn test_find_exported_functions_parses_source() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("users.rs"),
+            r#"
+            use std::collections::HashMap;
+
+            /// Look up a user by id
+            ///
+            /// Returns `None` when missing.
+            #[zap::export]
+            pub async fn get_user(id: u64, fields: Option<Vec<String>>) -> Option<User> {
+                None
+            }
+
+            #[zap::export]
+            pub fn tags(counts: &HashMap<String, u32>, name: &str) -> Box<Vec<u8>> {
+                Box::new(vec![])
+            }
+
+            pub fn not_exported() {}
+
+            #[zap::export]
+            fn private_export() {}
+
+            mod admin {
+                #[zap::export]
+                pub fn reset() {}
+            }
+            "#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.rs"), "fn {").unwrap();
+
+        let mut functions = find_exported_functions(dir.path()).unwrap();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<_> = functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["get_user", "reset", "tags"]);
+
+        let get_user = &functions[0];
+        assert!(get_user.is_async);
+        assert_eq!(get_user.doc_comments, ["Look up a user by id", "", "Returns `None` when missing."]);
+        assert_eq!(get_user.params[0].name, "id");
+        assert_eq!(get_user.params[0].ty, ExportedType::U64);
+        assert_eq!(
+            get_user.params[1].ty,
+            ExportedType::Option(Box::new(ExportedType::Vec(Box::new(ExportedType::String))))
+        );
+        assert_eq!(
+            get_user.return_type,
+            ExportedType::Option(Box::new(ExportedType::Custom { name: "User".to_string(), generics: vec![] }))
+        );
+
+        let tags = &functions[2];
+        assert!(!tags.is_async);
+        assert_eq!(
+            tags.params[0].ty,
+            ExportedType::HashMap { key: Box::new(ExportedType::String), value: Box::new(ExportedType::U32) }
+        );
+        assert_eq!(tags.params[1].ty, ExportedType::String);
+        assert_eq!(tags.return_type, ExportedType::Vec(Box::new(ExportedType::U8)));
+
+        let definitions = generate_typescript_definitions(&functions);
+        assert!(definitions.contains("getUser"));
+    }
+
+    #[test]
+    fn test_time_types_map_to_their_serde_forms() {
+        assert_eq!(parse("std::time::Duration"), ExportedType::Duration);
+        assert_eq!(parse("SystemTime"), ExportedType::SystemTime);
+        assert_eq!(parse("chrono::DateTime<Utc>"), ExportedType::Timestamp { format: TimestampFormat::Iso8601 });
+
+        // What serde_json makes of them
+        let duration = serde_json::to_value(std::time::Duration::from_millis(1500)).unwrap();
+        assert_eq!(duration, serde_json::json!({ "secs": 1, "nanos": 500_000_000 }));
+        assert_eq!(ExportedType::Duration.to_typescript(), "{ secs: number; nanos: number }");
+        let time = serde_json::to_value(std::time::UNIX_EPOCH + std::time::Duration::from_secs(60)).unwrap();
+        assert_eq!(time, serde_json::json!({ "secs_since_epoch": 60, "nanos_since_epoch": 0 }));
+        assert_eq!(
+            ExportedType::SystemTime.to_zod(),
+            "z.object({ secs_since_epoch: z.number(), nanos_since_epoch: z.number() })"
+        );
+        assert_eq!(parse("DateTime<Utc>").to_typescript(), "string");
+        assert_eq!(parse("Option<DateTime<Utc>>").to_zod(), "z.nullable(z.string())");
+
+        let mut functions = vec![ExportedFunction {
+            namespace: Some("jobs".to_string()),
+            is_async: true,
+            doc_comments: vec!["Schedule a job".to_string()],
+            ..function(
+                "schedule",
+                vec![("run_at", parse("DateTime<Utc>")), ("timeout", parse("Option<Duration>"))],
+                parse("Vec<DateTime<Utc>>"),
+            )
+        }];
+        let mut structs = vec![ExportedStruct {
+            name: "Job".to_string(),
+            fields: vec![StructField {
+                name: "elapsed".to_string(),
+                ty: ExportedType::Duration,
+                ts_name: None,
+                optional: false,
+            }],
+            doc_comments: vec![],
+        }];
+
+        let defs = generate_typescript_definitions(&functions);
+        assert!(defs.contains(
+            "/**\n * Schedule a job\n *\n * @param runAt - Timestamp as an ISO-8601 string\n * @param timeout - Duration in whole seconds plus nanoseconds\n * @returns Timestamp as an ISO-8601 string\n */\n"
+        ));
+        assert!(defs.contains(
+            "schedule(runAt: string, timeout?: { secs: number; nanos: number }): Promise<string[]>"
+        ));
+
+        let runtime = generate_typescript_runtime(&functions);
+        assert!(runtime.contains("  /**\n   * @param runAt - Timestamp as an ISO-8601 string\n"));
+        let server = generate_namespaced_server(&functions);
+        assert!(server.contains("     * @param params.timeout - Duration in whole seconds plus nanoseconds\n"));
+
+        let interfaces = generate_typescript_interfaces(&structs);
+        assert!(interfaces.contains(
+            "  /** Duration in whole seconds plus nanoseconds */\n  elapsed: { secs: number; nanos: number };\n"
+        ));
+
+        // Epoch milliseconds when configured
+        apply_timestamp_format(&mut functions, &mut structs, TimestampFormat::EpochMillis);
+        let defs = generate_typescript_definitions(&functions);
+        assert!(defs.contains("@param runAt - Timestamp in milliseconds since the Unix epoch"));
+        assert!(defs.contains(
+            "schedule(runAt: number, timeout?: { secs: number; nanos: number }): Promise<number[]>"
+        ));
+        assert_eq!(functions[0].return_type.to_zod(), "z.array(z.number())");
+
+        // Functions without time values get no extra JSDoc
+        let plain = function("ping", vec![], ExportedType::Unit);
+        assert!(!generate_typescript_runtime(&[plain]).contains("/**"));
+    }
+
+    #[test]
+    fn test_result_error_type_in_output() {
+        let get_user = ExportedFunction {
+            namespace: Some("users".to_string()),
+            is_async: true,
+            ..function("get_user", vec![("id", ExportedType::U64)], parse("Result<User, ApiError>"))
+        };
+        let rename = function("rename", vec![], parse("Result<(), String>"));
+        let functions = [get_user.clone(), rename];
+
+        // Promises resolve with the ok side only
+        let defs = generate_typescript_definitions(&functions);
+        assert!(defs.contains("export async function get_user(id: number): Promise<User>;"));
+        assert!(defs.contains("  ApiError,\n"));
+        assert!(defs.contains(
+            " * @throws {ZapTypedError<ApiError>} When the function returns `Err`; the error is in `data`\n"
+        ));
+        assert!(defs.contains(" * @throws {ZapError} When the function returns `Err`\n"));
+        assert!(defs.contains("import type { ZapTypedError } from './errors';"));
+        assert!(defs.contains("export type ApiErrorRejection = ZapTypedError<ApiError>;"));
+
+        let runtime = generate_typescript_runtime(&functions);
+        assert!(runtime.contains("async getUser(id: number): Promise<User> {"));
+        assert!(runtime.contains("rpcCall<User>('get_user'"));
+        assert!(runtime.contains("export type ApiErrorRejection = ZapTypedError<ApiError>;"));
+        assert!(!runtime.contains("StringRejection"));
+
+        let server = generate_namespaced_server(std::slice::from_ref(&get_user));
+        assert!(server.contains("     * @throws {ZapTypedError<ApiError>}"));
+
+        let user = ExportedStruct { name: "User".to_string(), fields: vec![], doc_comments: vec![] };
+        let schemas = generate_zod_schemas(&[get_user], &[user]);
+        assert!(schemas.contains("export const usersGetUserReturnSchema = z.lazy(() => UserSchema);"));
+
+        // The error classes unpack the serialized `Err` value into `data`
+        let errors = generate_typescript_errors();
+        assert!(errors.contains("export type ZapTypedError<E> = ZapError & { readonly data: E };"));
+        assert!(errors.contains("const TYPED_ERROR_PREFIX = '__TYPED_ERROR__:';"));
+        assert!(errors.contains("return new ErrorClass(message, payload.code, kind, payload.errorType, data);"));
+
+        // No Result returns, no aliases
+        assert!(!generate_typescript_runtime(&[]).contains("ZapTypedError"));
+    }
+
+    #[test]
+    fn test_doc_comments_become_param_and_returns_tags() {
+        let source = r#"
+            /// Fetch a user by ID
+            ///
+            /// # Arguments
+            /// * `id` - The user's numeric ID
+            /// * `include_posts` - Also load the user's posts,
+            ///   newest first
+            ///
+            /// # Example
+            /// let user = get_user(1, None, false);
+            #[export]
+            pub fn get_user(id: u64, include_posts: bool, fields: Option<Vec<String>>) -> User {}
+
+            /// Count users
+            ///
+            /// # Returns
+            /// Number of active users
+            #[export]
+            pub fn count_users() -> u64 {}
+        "#;
+        let file: syn::File = syn::parse_str(source).unwrap();
+        let functions: Vec<_> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Fn(func) => parse_function(func),
+                _ => None,
+            })
+            .collect();
+        let defs = generate_typescript_definitions(&functions);
+
+        assert!(defs.contains(
+            "/**\n * Fetch a user by ID\n *\n * # Example\n * let user = get_user(1, None, false);\n *\n \
+             * @param id - The user's numeric ID\n \
+             * @param includePosts - Also load the user's posts, newest first\n \
+             * @param fields\n \
+             * @returns `User`\n */\n"
+        ));
+        assert!(defs.contains("/**\n * Count users\n *\n * @returns Number of active users\n */\n"));
+
+        // Undocumented functions still get stubs
+        let bare = function("touch", vec![("user_id", ExportedType::U64)], ExportedType::Unit);
+        let defs = generate_typescript_definitions(&[bare]);
+        assert!(defs.contains("/**\n * @param userId\n */\nexport function touch(userId: number): Promise<void>;"));
+    }
+
+    #[test]
+    fn test_parse_doc_comments_bullet_styles() {
+        let lines: Vec<String> = ["Summary", "", "# Parameters", "- userId: Who to notify", "* `body` - Text"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let docs = parse_doc_comments(&lines);
+        assert_eq!(docs.description, vec!["Summary".to_string()]);
+        assert_eq!(docs.param("user_id"), Some("Who to notify"));
+        assert_eq!(docs.param("body"), Some("Text"));
+        assert_eq!(docs.param("missing"), None);
+        assert_eq!(docs.returns, None);
+    }
+
+    #[test]
+    fn test_parse_doc_comments_ignores_headings_in_code_blocks() {
+        let lines: Vec<String> = ["Run a job", "", "```sh", "# Returns", "zap run job", "```", "# Returns", "The job id"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let docs = parse_doc_comments(&lines);
+        assert_eq!(docs.description, vec!["Run a job", "", "```sh", "# Returns", "zap run job", "```"]);
+        assert_eq!(docs.returns.as_deref(), Some("The job id"));
+    }
+
+    #[test]
+    fn test_reserved_word_params_are_renamed() {
+        let func = ExportedFunction {
+            namespace: Some("students".to_string()),
+            is_async: true,
+            ..function(
+                "assign",
+                vec![("class", ExportedType::String), ("default", parse("Option<bool>"))],
+                ExportedType::Unit,
+            )
+        };
+
+        let defs = generate_typescript_definitions(std::slice::from_ref(&func));
+        assert!(defs.contains("export async function assign(class_: string, default_?: boolean): Promise<void>;"));
+        assert!(defs.contains(" * @param class_\n"));
+
+        // The wire fields keep the Rust names
+        let runtime = generate_typescript_runtime(std::slice::from_ref(&func));
+        assert!(runtime.contains("async assign(class_: string, default_?: boolean): Promise<void> {"));
+        assert!(runtime.contains("rpcCall<void>('assign', { class: class_, default: default_ })"));
+
+        // Object-style parameters are property names, where reserved words are fine
+        let server = generate_namespaced_server(&[func]);
+        assert!(server.contains("async assign(params: { class: string, default: boolean | null })"));
+        assert!(server.contains("{ class: params.class, default: params.default }"));
+
+        assert_eq!(ts_param_name("new"), "new_");
+        assert_eq!(ts_param_name("new_user"), "newUser");
+        assert_eq!(ts_param_name("function"), "function_");
+    }
+
+    #[test]
+    fn test_sets_and_ordered_maps() {
+        let set = parse("HashSet<String>");
+        assert_eq!(set, ExportedType::Set(Box::new(ExportedType::String)));
+        assert_eq!(set.to_typescript(), "string[]");
+        assert_eq!(parse("std::collections::BTreeSet<User>").to_typescript(), "User[]");
+        assert!(set.to_zod().starts_with("z.array(z.string()).refine("));
+        // Object elements would never compare equal, so they go unchecked
+        assert_eq!(parse("HashSet<Tag>").to_zod(), "z.array(z.lazy(() => TagSchema))");
+
+        let ordered = parse("BTreeMap<String, u64>");
+        assert_eq!(ordered, parse("HashMap<String, u64>"));
+        assert_eq!(ordered.to_typescript(), "Record<string, number>");
+
+        // The serde tag round-trips
+        let json = serde_json::to_value(&set).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "set", "inner": {"type": "string"}}));
+        assert_eq!(serde_json::from_value::<ExportedType>(json).unwrap(), set);
+
+        // Element types are imported like any other
+        let mut types = std::collections::HashSet::new();
+        collect_custom_types(&parse("BTreeSet<Tag>"), &mut types);
+        assert!(types.contains("Tag"));
+
+        let schema = serde_json::json!({"type": "array", "items": {"type": "string"}, "uniqueItems": true});
+        assert_eq!(parse_type_from_schema(&schema).unwrap(), set);
+    }
 }
@@ -2,15 +2,15 @@ use clap::Parser;
 use std::fs;
 use std::path::PathBuf;
 use zap_codegen::{
-    find_exported_functions, find_exported_structs, generate_namespaced_server,
-    generate_typescript_definitions, generate_typescript_interfaces, generate_typescript_runtime,
-    ExportedFunction,
+    apply_timestamp_format, find_exported_functions, find_exported_structs, generate_namespaced_server,
+    generate_typescript_definitions, generate_typescript_errors, generate_typescript_interfaces,
+    generate_typescript_runtime, generate_zod_schemas, ExportedFunction, TimestampFormat,
 };
 use anyhow::{Context as _, Result};
 use tokio::net::UnixStream;
 use tokio_util::codec::Framed;
-use futures::StreamExt;
-use splice::{Message, Role, SpliceCodec, PROTOCOL_VERSION, DEFAULT_MAX_FRAME_SIZE};
+use futures::{SinkExt, StreamExt};
+use splice::protocol::{Message, Role, SpliceCodec, PROTOCOL_VERSION, DEFAULT_MAX_FRAME_SIZE};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -45,6 +45,15 @@ struct Args {
     /// Generate namespaced server client (server.users.get() style)
     #[arg(long, default_value_t = true)]
     server: bool,
+
+    /// Generate Zod validation schemas (schemas.ts)
+    #[arg(long)]
+    zod: bool,
+
+    /// Wire form of chrono DateTime values: iso8601 (chrono's default string)
+    /// or epoch-ms (for fields using chrono::serde::ts_milliseconds)
+    #[arg(long, default_value = "iso8601")]
+    timestamps: TimestampFormat,
 }
 
 #[tokio::main]
@@ -55,7 +64,7 @@ async fn main() -> anyhow::Result<()> {
     fs::create_dir_all(&args.output_dir)?;
 
     // Load exported functions from Splice socket, input file, or scan Rust source
-    let functions = if let Some(socket_path) = args.splice_socket {
+    let mut functions = if let Some(socket_path) = args.splice_socket {
         println!("Connecting to Splice at {}...", socket_path.display());
         load_exports_from_splice(&socket_path).await?
     } else if let Some(input_path) = args.input {
@@ -69,7 +78,8 @@ async fn main() -> anyhow::Result<()> {
 
     // Scan for serializable structs
     println!("Scanning {} for serializable structs...", args.project_dir.display());
-    let structs = find_exported_structs(&args.project_dir)?;
+    let mut structs = find_exported_structs(&args.project_dir)?;
+    apply_timestamp_format(&mut functions, &mut structs, args.timestamps);
 
     // Generate TypeScript interfaces from Rust structs
     if !structs.is_empty() {
@@ -87,6 +97,14 @@ async fn main() -> anyhow::Result<()> {
         println!("Generated: {}", defs_path.display());
     }
 
+    // Generate the error classes the runtime and server clients throw
+    if args.runtime || args.server {
+        let errors = generate_typescript_errors();
+        let errors_path = args.output_dir.join("errors.ts");
+        fs::write(&errors_path, errors)?;
+        println!("Generated: {}", errors_path.display());
+    }
+
     // Generate runtime bindings
     if args.runtime {
         let runtime = generate_typescript_runtime(&functions);
@@ -103,6 +121,14 @@ async fn main() -> anyhow::Result<()> {
         println!("Generated: {}", server_path.display());
     }
 
+    // Generate Zod validation schemas
+    if args.zod {
+        let schemas = generate_zod_schemas(&functions, &structs);
+        let schemas_path = args.output_dir.join("schemas.ts");
+        fs::write(&schemas_path, schemas)?;
+        println!("Generated: {}", schemas_path.display());
+    }
+
     println!("Successfully generated TypeScript bindings for {} functions and {} types", functions.len(), structs.len());
     Ok(())
 }
@@ -164,4 +190,13 @@ mod tests {
         assert_eq!(args.project_dir, PathBuf::from("."));
         assert_eq!(args.output_dir, PathBuf::from("./src/api"));
     }
+
+    #[test]
+    fn test_timestamps_flag() {
+        let args = Args::parse_from(["zap-codegen"]);
+        assert_eq!(args.timestamps, TimestampFormat::Iso8601);
+        let args = Args::parse_from(["zap-codegen", "--timestamps", "epoch-ms"]);
+        assert_eq!(args.timestamps, TimestampFormat::EpochMillis);
+        assert!(Args::try_parse_from(["zap-codegen", "--timestamps", "unix"]).is_err());
+    }
 }
@@ -3,27 +3,47 @@
 //! Provides token-based CSRF protection for state-changing requests.
 //!
 //! ## How it works
-//! 1. Generates a random CSRF token per session
+//! 1. Generates a random CSRF token per session, HMAC-signed together with
+//!    its issue time
 //! 2. Stores token in a secure, HTTP-only, SameSite cookie
 //! 3. Validates token on POST, PUT, DELETE, PATCH requests
 //! 4. Tokens must be sent in X-CSRF-Token header or _csrf form field
 //!
+//! In [`CsrfMode::DoubleSubmit`] the token is instead derived from the
+//! session cookie, so any node sharing the secret can validate it. Its
+//! cookie is readable by scripts, which copy it into the header.
+//!
+//! With `trusted_origins` set, state-changing requests must also come from an
+//! allowed `Origin` (or `Referer`); APIs without cookies can turn the token
+//! check off with `require_token(false)` and rely on the origin alone. With
+//! neither a token nor trusted origins, requests must be same-origin: their
+//! `Origin` (or `Referer`) must name the request's own `Host`.
+//!
 //! ## Security Features
 //! - Cryptographically secure random token generation (32 bytes)
 //! - Constant-time token comparison (prevents timing attacks)
 //! - SameSite=Strict cookie (prevents CSRF from cross-site requests)
-//! - HTTP-only cookie (prevents XSS token theft)
+//! - HTTP-only cookie in synchronizer-token mode (prevents XSS token theft)
 //! - Secure flag for HTTPS (prevents MITM token theft)
-//! - Configurable token lifetime
+//! - Configurable token lifetime, enforced server-side from the signed
+//!   issue time
 
 use crate::middleware::{Context, Middleware, MiddlewareFuture, MiddlewareError, MiddlewareResult};
 use crate::method::Method;
 use rand::Rng;
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+use subtle::ConstantTimeEq;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Seconds a token's issue time may lie in the future (clock skew between
+/// instances sharing a secret)
+const MAX_CLOCK_SKEW_SECS: u64 = 60;
+
 /// CSRF protection configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct CsrfConfig {
     /// Cookie name for CSRF token (default: "csrf_token")
     pub cookie_name: String,
@@ -43,6 +63,64 @@ pub struct CsrfConfig {
     pub same_site: SameSitePolicy,
     /// Skip CSRF validation for specific paths (e.g., webhooks)
     pub skip_paths: Vec<String>,
+    /// Key tokens are signed with (default: 32 random bytes per process, so
+    /// instances behind a load balancer need a shared secret)
+    pub secret: Vec<u8>,
+    /// How tokens are issued and validated (default: SynchronizerToken)
+    pub mode: CsrfMode,
+    /// Require a valid token on state-changing requests (default: true)
+    pub require_token: bool,
+    /// Origins (`scheme://host[:port]`) state-changing requests must come
+    /// from, checked against `Origin` or else `Referer`; empty disables the
+    /// check, unless `require_token` is off too, in which case requests
+    /// must be same-origin (default: empty)
+    pub trusted_origins: Vec<String>,
+}
+
+/// How CSRF tokens are issued and validated
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CsrfMode {
+    /// A random, signed and timestamped token is issued in a cookie on safe
+    /// requests and must be echoed back in the header or form field
+    #[default]
+    SynchronizerToken,
+    /// Stateless double submit: the expected token is an HMAC of the session
+    /// identifier in `session_cookie`, so it is the same on every node and
+    /// every response and needs no per-request cookie churn.
+    ///
+    /// Tradeoffs: the token lives exactly as long as the session (rotate the
+    /// session to rotate the token; `token_lifetime` does not apply), it is
+    /// only as secret as the session identifier and the signing secret, and
+    /// every node must share `secret`. Because it is signed, an attacker who
+    /// can plant cookies (e.g. from a sibling subdomain) still cannot mint a
+    /// matching token for a session of their choosing.
+    ///
+    /// The token cookie is not `HttpOnly`: client scripts read it to send
+    /// it back in the header.
+    DoubleSubmit {
+        /// Cookie holding the session identifier
+        session_cookie: String,
+    },
+}
+
+impl fmt::Debug for CsrfConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsrfConfig")
+            .field("cookie_name", &self.cookie_name)
+            .field("header_name", &self.header_name)
+            .field("form_field_name", &self.form_field_name)
+            .field("token_lifetime", &self.token_lifetime)
+            .field("cookie_path", &self.cookie_path)
+            .field("cookie_domain", &self.cookie_domain)
+            .field("secure", &self.secure)
+            .field("same_site", &self.same_site)
+            .field("skip_paths", &self.skip_paths)
+            .field("secret", &"<redacted>")
+            .field("mode", &self.mode)
+            .field("require_token", &self.require_token)
+            .field("trusted_origins", &self.trusted_origins)
+            .finish()
+    }
 }
 
 /// SameSite cookie policy
@@ -68,6 +146,10 @@ impl Default for CsrfConfig {
             secure: true,
             same_site: SameSitePolicy::Strict,
             skip_paths: Vec::new(),
+            secret: rand::thread_rng().gen::<[u8; 32]>().to_vec(),
+            mode: CsrfMode::default(),
+            require_token: true,
+            trusted_origins: Vec::new(),
         }
     }
 }
@@ -116,6 +198,70 @@ impl CsrfConfig {
         self.skip_paths = paths;
         self
     }
+
+    /// Builder: Set the token signing secret
+    pub fn secret(mut self, secret: impl Into<Vec<u8>>) -> Self {
+        self.secret = secret.into();
+        self
+    }
+
+    /// Builder: Set how tokens are issued and validated
+    pub fn mode(mut self, mode: CsrfMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Builder: Require (or stop requiring) a token on state-changing requests
+    ///
+    /// Without a token, requests are checked against `trusted_origins`, or
+    /// must be same-origin if there are none.
+    pub fn require_token(mut self, require: bool) -> Self {
+        self.require_token = require;
+        self
+    }
+
+    /// Builder: Only accept state-changing requests from these origins
+    pub fn trusted_origins(mut self, origins: Vec<String>) -> Self {
+        self.trusted_origins = origins;
+        self
+    }
+}
+
+/// `scheme://host[:port]` of an origin or URL, lowercased
+///
+/// Returns `None` for the opaque `null` origin and anything without a
+/// scheme and host.
+fn origin_of(url: &str) -> Option<String> {
+    let url = url.trim();
+    let (scheme, rest) = url.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    if scheme.is_empty() || host.is_empty() {
+        return None;
+    }
+    Some(format!("{}://{}", scheme, host).to_ascii_lowercase())
+}
+
+/// HMAC-SHA256 keyed with `key`, over `message`
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac
+}
+
+/// Whether `signature` (URL-safe base64) is the HMAC-SHA256 of `message`,
+/// checked in constant time
+fn hmac_sha256_verify(key: &[u8], message: &[u8], signature: &str) -> bool {
+    URL_SAFE_NO_PAD
+        .decode(signature)
+        .is_ok_and(|signature| hmac_sha256(key, message).verify_slice(&signature).is_ok())
+}
+
+/// Current Unix time in seconds
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 /// CSRF protection middleware
@@ -143,28 +289,68 @@ impl CsrfMiddleware {
         }
     }
 
-    /// Generate a cryptographically secure CSRF token
-    fn generate_token() -> String {
+    /// Generate a cryptographically secure CSRF token issued now
+    fn generate_token(&self) -> String {
+        self.issue_token(unix_now())
+    }
+
+    /// Signed token `<random>.<issued_at>.<signature>` issued at
+    /// `issued_at` (Unix seconds)
+    fn issue_token(&self, issued_at: u64) -> String {
         let mut rng = rand::thread_rng();
         let token_bytes: [u8; 32] = rng.gen();
-        URL_SAFE_NO_PAD.encode(token_bytes)
+        let payload = format!("{}.{}", URL_SAFE_NO_PAD.encode(token_bytes), issued_at);
+        let signature = hmac_sha256(&self.config.secret, payload.as_bytes()).finalize().into_bytes();
+        format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(signature))
     }
 
-    /// Constant-time token comparison (prevents timing attacks)
-    fn tokens_equal(a: &str, b: &str) -> bool {
-        if a.len() != b.len() {
-            return false;
+    /// Token expected for a session in [`CsrfMode::DoubleSubmit`], e.g. for
+    /// rendering into a form
+    pub fn token_for_session(&self, session_id: &str) -> String {
+        let mac = hmac_sha256(&self.config.secret, Self::session_message(session_id).as_bytes());
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    }
+
+    /// What a session's token signs
+    fn session_message(session_id: &str) -> String {
+        format!("csrf-session:{}", session_id)
+    }
+
+    /// Check a token's signature and age
+    fn verify_token(&self, token: &str) -> Result<(), MiddlewareError> {
+        let invalid = || MiddlewareError::Unauthorized("CSRF token invalid".to_string());
+
+        let (payload, signature) = token.rsplit_once('.').ok_or_else(invalid)?;
+        if !hmac_sha256_verify(&self.config.secret, payload.as_bytes(), signature) {
+            return Err(invalid());
         }
 
-        let mut result = 0u8;
-        for (byte_a, byte_b) in a.bytes().zip(b.bytes()) {
-            result |= byte_a ^ byte_b;
+        let issued_at: u64 = payload
+            .rsplit_once('.')
+            .and_then(|(_, issued_at)| issued_at.parse().ok())
+            .ok_or_else(invalid)?;
+        let now = unix_now();
+        if issued_at > now + MAX_CLOCK_SKEW_SECS {
+            return Err(invalid());
+        }
+        if now.saturating_sub(issued_at) > self.config.token_lifetime {
+            return Err(MiddlewareError::Unauthorized("CSRF token expired".to_string()));
         }
-        result == 0
+        Ok(())
+    }
+
+    /// Constant-time token comparison (prevents timing attacks)
+    fn tokens_equal(a: &str, b: &str) -> bool {
+        a.as_bytes().ct_eq(b.as_bytes()).into()
     }
 
     /// Extract CSRF token from cookie
     fn extract_cookie_token<'a>(&self, ctx: &Context<'a>) -> Option<&'a str> {
+        Self::extract_cookie(ctx, &self.config.cookie_name)
+    }
+
+    /// Extract a cookie's value by name
+    fn extract_cookie<'a>(ctx: &Context<'a>, cookie_name: &str) -> Option<&'a str> {
         ctx.headers().get("Cookie").and_then(|cookie_header| {
             cookie_header
                 .split(';')
@@ -173,7 +359,7 @@ impl CsrfMiddleware {
                     let mut parts = cookie.splitn(2, '=');
                     let name = parts.next()?;
                     let value = parts.next()?;
-                    if name == self.config.cookie_name {
+                    if name == cookie_name {
                         Some(value)
                     } else {
                         None
@@ -226,8 +412,10 @@ impl CsrfMiddleware {
         let max_age = self.config.token_lifetime;
         cookie.push_str(&format!("; Max-Age={}", max_age));
 
-        // Security flags
-        cookie.push_str("; HttpOnly");
+        // Security flags; a double-submit token is read by client scripts
+        if !matches!(self.config.mode, CsrfMode::DoubleSubmit { .. }) {
+            cookie.push_str("; HttpOnly");
+        }
 
         if self.config.secure {
             cookie.push_str("; Secure");
@@ -248,8 +436,53 @@ impl CsrfMiddleware {
         cookie
     }
 
+    /// Check that a state-changing request comes from a trusted origin
+    ///
+    /// `Origin` is used when present; `Referer` is only a fallback when it
+    /// is missing. The opaque `null` origin is never trusted.
+    fn validate_origin<'a>(&self, ctx: &Context<'a>) -> Result<(), MiddlewareError> {
+        let source = match ctx.headers().get("Origin") {
+            Some(origin) => origin,
+            None => ctx.headers().get("Referer").unwrap_or(""),
+        };
+        let trusted = origin_of(source).is_some_and(|origin| {
+            self.config
+                .trusted_origins
+                .iter()
+                .any(|allowed| origin_of(allowed).as_deref() == Some(origin.as_str()))
+        });
+        if !trusted {
+            return Err(MiddlewareError::Unauthorized("Origin not allowed".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Check that a state-changing request comes from the origin it is sent
+    /// to, for configurations with neither a token nor trusted origins
+    ///
+    /// The scheme is unknown behind a proxy, so only `host[:port]` of
+    /// `Origin` (or else `Referer`) is compared with `Host`.
+    fn validate_same_origin<'a>(&self, ctx: &Context<'a>) -> Result<(), MiddlewareError> {
+        let source = match ctx.headers().get("Origin") {
+            Some(origin) => origin,
+            None => ctx.headers().get("Referer").unwrap_or(""),
+        };
+        let host = ctx.headers().get("Host").map(|host| host.trim().to_ascii_lowercase());
+        let same = origin_of(source)
+            .zip(host)
+            .is_some_and(|(origin, host)| origin.split_once("://").is_some_and(|(_, authority)| authority == host));
+        if !same {
+            return Err(MiddlewareError::Unauthorized("Cross-origin request not allowed".to_string()));
+        }
+        Ok(())
+    }
+
     /// Validate CSRF token for state-changing requests
     fn validate_token<'a>(&self, ctx: &Context<'a>) -> Result<(), MiddlewareError> {
+        if let CsrfMode::DoubleSubmit { session_cookie } = &self.config.mode {
+            return self.validate_session_token(ctx, session_cookie);
+        }
+
         let cookie_token = self.extract_cookie_token(ctx).ok_or_else(|| {
             MiddlewareError::Unauthorized("CSRF token missing from cookie".to_string())
         })?;
@@ -267,8 +500,51 @@ impl CsrfMiddleware {
             ));
         }
 
+        self.verify_token(cookie_token)
+    }
+
+    /// Validate the request token against the one derived from the session
+    fn validate_session_token<'a>(&self, ctx: &Context<'a>, session_cookie: &str) -> Result<(), MiddlewareError> {
+        let session_id = Self::extract_cookie(ctx, session_cookie).ok_or_else(|| {
+            MiddlewareError::Unauthorized("CSRF session cookie missing".to_string())
+        })?;
+
+        let request_token = self.extract_request_token(ctx).ok_or_else(|| {
+            MiddlewareError::Unauthorized(format!(
+                "CSRF token missing. Include token in {} header or {} form field",
+                self.config.header_name, self.config.form_field_name
+            ))
+        })?;
+
+        let message = Self::session_message(session_id);
+        if !hmac_sha256_verify(&self.config.secret, message.as_bytes(), &request_token) {
+            return Err(MiddlewareError::Unauthorized(
+                "CSRF token mismatch".to_string(),
+            ));
+        }
+
         Ok(())
     }
+
+    /// Token to set in the CSRF cookie on a safe request, if the current one
+    /// needs issuing or replacing
+    fn cookie_token_to_issue(&self, ctx: &Context<'_>) -> Option<String> {
+        let current = self.extract_cookie_token(ctx);
+        match &self.config.mode {
+            // Generate token if not present (for initial page load), or
+            // replace one that expired or no longer verifies
+            CsrfMode::SynchronizerToken => {
+                let valid = current.is_some_and(|token| self.verify_token(token).is_ok());
+                (!valid).then(|| self.generate_token())
+            }
+            // Only once per session: the derived token never changes
+            CsrfMode::DoubleSubmit { session_cookie } => {
+                let session_id = Self::extract_cookie(ctx, session_cookie)?;
+                let token = self.token_for_session(session_id);
+                (current != Some(token.as_str())).then_some(token)
+            }
+        }
+    }
 }
 
 impl Default for CsrfMiddleware {
@@ -285,9 +561,12 @@ impl Middleware for CsrfMiddleware {
 
             // Skip CSRF validation for safe methods (GET, HEAD, OPTIONS)
             if matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
-                // Generate token if not present (for initial page load)
-                if self.extract_cookie_token(&ctx).is_none() {
-                    let token = Self::generate_token();
+                let token = if self.config.require_token {
+                    self.cookie_token_to_issue(&ctx)
+                } else {
+                    None
+                };
+                if let Some(token) = token {
                     let cookie_header = self.build_cookie_header(&token);
 
                     let mut new_ctx = ctx;
@@ -309,7 +588,14 @@ impl Middleware for CsrfMiddleware {
                 method,
                 Method::POST | Method::PUT | Method::DELETE | Method::PATCH
             ) {
-                self.validate_token(&ctx)?;
+                if !self.config.trusted_origins.is_empty() {
+                    self.validate_origin(&ctx)?;
+                } else if !self.config.require_token {
+                    self.validate_same_origin(&ctx)?;
+                }
+                if self.config.require_token {
+                    self.validate_token(&ctx)?;
+                }
             }
 
             Ok((ctx, MiddlewareResult::Continue))
@@ -324,15 +610,234 @@ mod tests {
 
     #[tokio::test]
     async fn test_generate_token() {
-        let token1 = CsrfMiddleware::generate_token();
-        let token2 = CsrfMiddleware::generate_token();
+        let csrf = CsrfMiddleware::new();
+        let token1 = csrf.generate_token();
+        let token2 = csrf.generate_token();
 
         // Tokens should be different
         assert_ne!(token1, token2);
-        // Tokens should be non-empty
-        assert!(!token1.is_empty());
-        // Tokens should be URL-safe base64 (43 chars for 32 bytes)
-        assert_eq!(token1.len(), 43);
+        // Random part, issue time and signature, each URL-safe base64
+        // (43 chars for 32 bytes)
+        let parts: Vec<&str> = token1.split('.').collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].len(), 43);
+        assert!(parts[1].parse::<u64>().is_ok());
+        assert_eq!(parts[2].len(), 43);
+        assert!(csrf.verify_token(&token1).is_ok());
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?").finalize().into_bytes();
+        let hex: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+        let signature = URL_SAFE_NO_PAD.encode(mac);
+        assert!(hmac_sha256_verify(b"Jefe", b"what do ya want for nothing?", &signature));
+        assert!(!hmac_sha256_verify(b"Jefe", b"what do ya want for something?", &signature));
+        assert!(!hmac_sha256_verify(b"Jefe", b"what do ya want for nothing?", "not base64!"));
+    }
+
+    fn post_with_token(token: &str) -> String {
+        format!(
+            "POST /test HTTP/1.1\r\nHost: example.com\r\nCookie: csrf_token={}\r\nX-CSRF-Token: {}\r\n\r\n",
+            token, token
+        )
+    }
+
+    fn unauthorized_message(result: Result<(Context<'_>, MiddlewareResult), MiddlewareError>) -> String {
+        match result {
+            Err(MiddlewareError::Unauthorized(message)) => message,
+            Err(other) => panic!("Expected Unauthorized, got {:?}", other),
+            Ok(_) => panic!("Expected the request to be rejected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fresh_token_passes_and_aged_token_expires() {
+        let csrf = CsrfMiddleware::with_config(CsrfConfig::development().token_lifetime(3600));
+        let parser = HttpParser::new();
+
+        let fresh = post_with_token(&csrf.issue_token(unix_now() - 60));
+        let parsed = parser.parse_request(fresh.as_bytes()).unwrap();
+        let (_, result) = csrf.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(matches!(result, MiddlewareResult::Continue));
+
+        let aged = post_with_token(&csrf.issue_token(unix_now() - 3601));
+        let parsed = parser.parse_request(aged.as_bytes()).unwrap();
+        let message = unauthorized_message(csrf.call(Context::new(&parsed, &[])).await);
+        assert_eq!(message, "CSRF token expired");
+    }
+
+    #[tokio::test]
+    async fn test_token_signed_with_other_secret_rejected() {
+        let csrf = CsrfMiddleware::with_config(CsrfConfig::development().secret("server secret"));
+        let other = CsrfMiddleware::with_config(CsrfConfig::development().secret("other secret"));
+        let parser = HttpParser::new();
+
+        // Pushing the issue time forward invalidates the signature too
+        let token = other.generate_token();
+        let mut parts: Vec<String> = csrf.generate_token().split('.').map(str::to_string).collect();
+        parts[1] = (unix_now() + 86400 * 365).to_string();
+        for forged in [token, parts.join(".")] {
+            let request = post_with_token(&forged);
+            let parsed = parser.parse_request(request.as_bytes()).unwrap();
+            let message = unauthorized_message(csrf.call(Context::new(&parsed, &[])).await);
+            assert_eq!(message, "CSRF token invalid");
+        }
+    }
+
+    fn double_submit() -> CsrfMiddleware {
+        let mode = CsrfMode::DoubleSubmit { session_cookie: "sid".to_string() };
+        CsrfMiddleware::with_config(CsrfConfig::development().secret("cluster secret").mode(mode))
+    }
+
+    #[tokio::test]
+    async fn test_both_modes_accept_matching_post() {
+        let parser = HttpParser::new();
+
+        let synchronizer = CsrfMiddleware::development();
+        let request = post_with_token(&synchronizer.generate_token());
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        let (_, result) = synchronizer.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(matches!(result, MiddlewareResult::Continue));
+
+        // Any node with the same secret derives the same token
+        let (node_a, node_b) = (double_submit(), double_submit());
+        let token = node_a.token_for_session("session-1");
+        assert_eq!(token, node_b.token_for_session("session-1"));
+        let request = format!(
+            "POST /test HTTP/1.1\r\nHost: example.com\r\nCookie: sid=session-1\r\nX-CSRF-Token: {}\r\n\r\n",
+            token
+        );
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        let (_, result) = node_b.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(matches!(result, MiddlewareResult::Continue));
+    }
+
+    #[tokio::test]
+    async fn test_double_submit_rejects_other_sessions_token() {
+        let csrf = double_submit();
+        let parser = HttpParser::new();
+
+        let request = format!(
+            "POST /test HTTP/1.1\r\nHost: example.com\r\nCookie: sid=victim\r\nX-CSRF-Token: {}\r\n\r\n",
+            csrf.token_for_session("attacker")
+        );
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        assert_eq!(unauthorized_message(csrf.call(Context::new(&parsed, &[])).await), "CSRF token mismatch");
+
+        // A synchronizer token is no substitute for the session
+        let request = post_with_token(&csrf.generate_token());
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        assert_eq!(unauthorized_message(csrf.call(Context::new(&parsed, &[])).await), "CSRF session cookie missing");
+    }
+
+    #[tokio::test]
+    async fn test_double_submit_sets_cookie_once_per_session() {
+        let csrf = double_submit();
+        let parser = HttpParser::new();
+        let set_cookie = |ctx: &Context<'_>| ctx.response.headers.iter().any(|(k, _)| k == "Set-Cookie");
+
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\nCookie: sid=s1\r\n\r\n";
+        let parsed = parser.parse_request(request).unwrap();
+        let (ctx, _) = csrf.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(set_cookie(&ctx));
+        let cookie = ctx.response.headers.iter().find(|(k, _)| k == "Set-Cookie").unwrap().1.clone();
+        assert!(!cookie.contains("HttpOnly"), "scripts must be able to read the token: {}", cookie);
+
+        let request = format!(
+            "GET / HTTP/1.1\r\nHost: example.com\r\nCookie: sid=s1; csrf_token={}\r\n\r\n",
+            csrf.token_for_session("s1")
+        );
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        let (ctx, _) = csrf.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(!set_cookie(&ctx));
+
+        // No session yet, nothing to derive from
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let parsed = parser.parse_request(request).unwrap();
+        let (ctx, _) = csrf.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(!set_cookie(&ctx));
+    }
+
+    #[test]
+    fn test_origin_parsing() {
+        assert_eq!(origin_of("https://App.example.com").as_deref(), Some("https://app.example.com"));
+        assert_eq!(origin_of("https://example.com:8443/form?x=1").as_deref(), Some("https://example.com:8443"));
+        assert_eq!(origin_of("null"), None);
+        assert_eq!(origin_of(""), None);
+        assert_eq!(origin_of("https:///path"), None);
+    }
+
+    async fn post_from(csrf: &CsrfMiddleware, headers: &str) -> Result<(), String> {
+        let request = format!("POST /test HTTP/1.1\r\nHost: example.com\r\n{}\r\n", headers);
+        let parser = HttpParser::new();
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        match csrf.call(Context::new(&parsed, &[])).await {
+            Ok(_) => Ok(()),
+            Err(MiddlewareError::Unauthorized(message)) => Err(message),
+            Err(other) => panic!("Expected Unauthorized, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trusted_origins_without_token() {
+        let config = CsrfConfig::development()
+            .require_token(false)
+            .trusted_origins(vec!["https://app.example.com".to_string()]);
+        let csrf = CsrfMiddleware::with_config(config);
+        let denied = Err("Origin not allowed".to_string());
+
+        assert_eq!(post_from(&csrf, "Origin: https://app.example.com\r\n").await, Ok(()));
+        assert_eq!(post_from(&csrf, "Referer: https://app.example.com/settings\r\n").await, Ok(()));
+        assert_eq!(post_from(&csrf, "Origin: https://evil.example.com\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "Origin: http://app.example.com\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "Origin: null\r\nReferer: https://app.example.com/\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "").await, denied);
+    }
+
+    #[tokio::test]
+    async fn test_no_token_and_no_trusted_origins_requires_same_origin() {
+        let csrf = CsrfMiddleware::with_config(CsrfConfig::development().require_token(false));
+        let denied = Err("Cross-origin request not allowed".to_string());
+
+        assert_eq!(post_from(&csrf, "Origin: https://example.com\r\n").await, Ok(()));
+        assert_eq!(post_from(&csrf, "Referer: http://EXAMPLE.com/form\r\n").await, Ok(()));
+        assert_eq!(post_from(&csrf, "Origin: https://evil.com\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "Origin: https://example.com.evil.com\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "Origin: https://example.com:8443\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "Origin: null\r\n").await, denied);
+        assert_eq!(post_from(&csrf, "").await, denied);
+    }
+
+    #[tokio::test]
+    async fn test_trusted_origins_combined_with_token() {
+        let config = CsrfConfig::development().trusted_origins(vec!["https://app.example.com".to_string()]);
+        let csrf = CsrfMiddleware::with_config(config);
+        let token = csrf.generate_token();
+        let with_token = format!("Cookie: csrf_token={}\r\nX-CSRF-Token: {}\r\n", token, token);
+
+        let allowed = format!("Origin: https://app.example.com\r\n{}", with_token);
+        assert_eq!(post_from(&csrf, &allowed).await, Ok(()));
+        let other_origin = format!("Origin: https://evil.example.com\r\n{}", with_token);
+        assert_eq!(post_from(&csrf, &other_origin).await, Err("Origin not allowed".to_string()));
+        let no_token = post_from(&csrf, "Origin: https://app.example.com\r\n").await;
+        assert_eq!(no_token, Err("CSRF token missing from cookie".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_safe_method_replaces_expired_cookie() {
+        let csrf = CsrfMiddleware::with_config(CsrfConfig::development().token_lifetime(60));
+        let request = format!(
+            "GET /test HTTP/1.1\r\nHost: example.com\r\nCookie: csrf_token={}\r\n\r\n",
+            csrf.issue_token(unix_now() - 120)
+        );
+        let parser = HttpParser::new();
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+
+        let (new_ctx, _) = csrf.call(Context::new(&parsed, &[])).await.unwrap();
+        assert!(new_ctx.response.headers.iter().any(|(k, _)| k == "Set-Cookie"));
     }
 
     #[test]
@@ -381,7 +886,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_post_with_valid_token_succeeds() {
-        let token = CsrfMiddleware::generate_token();
+        let csrf = CsrfMiddleware::development();
+        let token = csrf.generate_token();
         let request = format!(
             "POST /test HTTP/1.1\r\nHost: example.com\r\nCookie: csrf_token={}\r\nX-CSRF-Token: {}\r\n\r\n",
             token, token
@@ -393,7 +899,6 @@ mod tests {
         let body = &request_bytes[parsed.body_offset..];
 
         let ctx = Context::new(&parsed, body);
-        let csrf = CsrfMiddleware::development();
 
         let (_, result) = csrf.call(ctx).await.unwrap();
         assert!(matches!(result, MiddlewareResult::Continue));
@@ -401,8 +906,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_post_with_mismatched_token_fails() {
-        let token1 = CsrfMiddleware::generate_token();
-        let token2 = CsrfMiddleware::generate_token();
+        let csrf = CsrfMiddleware::development();
+        let token1 = csrf.generate_token();
+        let token2 = csrf.generate_token();
         let request = format!(
             "POST /test HTTP/1.1\r\nHost: example.com\r\nCookie: csrf_token={}\r\nX-CSRF-Token: {}\r\n\r\n",
             token1, token2
@@ -414,7 +920,6 @@ mod tests {
         let body = &request_bytes[parsed.body_offset..];
 
         let ctx = Context::new(&parsed, body);
-        let csrf = CsrfMiddleware::development();
 
         let result = csrf.call(ctx).await;
         assert!(result.is_err());
@@ -422,7 +927,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_form_field_token() {
-        let token = CsrfMiddleware::generate_token();
+        let csrf = CsrfMiddleware::development();
+        let token = csrf.generate_token();
         let body = format!("username=test&_csrf={}&password=secret", token);
         let request = format!(
             "POST /login HTTP/1.1\r\nHost: example.com\r\nCookie: csrf_token={}\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\n\r\n{}",
@@ -437,7 +943,6 @@ mod tests {
         let body_bytes = &request_bytes[parsed.body_offset..];
 
         let ctx = Context::new(&parsed, body_bytes);
-        let csrf = CsrfMiddleware::development();
 
         let (_, result) = csrf.call(ctx).await.unwrap();
         assert!(matches!(result, MiddlewareResult::Continue));
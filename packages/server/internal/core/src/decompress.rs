@@ -0,0 +1,325 @@
+//! Request Body Decompression Middleware
+//!
+//! Transparently decodes request bodies sent with a `Content-Encoding`
+//! header so handlers and later middleware always see plain bytes.
+//!
+//! ## Supported encodings
+//! - `gzip` / `x-gzip`
+//! - `deflate` (zlib-wrapped, per RFC 9110)
+//! - `br` (Brotli)
+//! - `identity`
+//!
+//! ## Security Features
+//! - Decompressed output is capped (default 10 MiB) to prevent zip-bomb
+//!   amplification; oversized bodies are rejected with 413
+//! - Unknown encodings are rejected with 415 and an `Accept-Encoding`
+//!   header listing what the server understands (RFC 7694)
+//! - Malformed compressed data is rejected with 400
+
+use crate::middleware::{Context, Middleware, MiddlewareFuture, MiddlewareResult, ResponseBuilder};
+use std::io::Read;
+
+/// Default decompressed body limit (10 MiB)
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 10 * 1024 * 1024;
+
+/// Content codings understood by the middleware
+const SUPPORTED_ENCODINGS: &str = "gzip, deflate, br";
+
+/// Supported content coding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentCoding {
+    /// gzip (RFC 1952)
+    Gzip,
+    /// zlib-wrapped deflate (RFC 1950)
+    Deflate,
+    /// Brotli (RFC 7932)
+    Brotli,
+    /// No transformation
+    Identity,
+}
+
+impl ContentCoding {
+    /// Parse a single content-coding token (case-insensitive)
+    pub fn parse(token: &str) -> Option<Self> {
+        let token = token.trim();
+        if token.eq_ignore_ascii_case("gzip") || token.eq_ignore_ascii_case("x-gzip") {
+            Some(Self::Gzip)
+        } else if token.eq_ignore_ascii_case("deflate") {
+            Some(Self::Deflate)
+        } else if token.eq_ignore_ascii_case("br") {
+            Some(Self::Brotli)
+        } else if token.eq_ignore_ascii_case("identity") {
+            Some(Self::Identity)
+        } else {
+            None
+        }
+    }
+}
+
+/// Decompression errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecompressError {
+    /// Content-Encoding not supported
+    UnsupportedEncoding(String),
+    /// Decompressed body exceeded the configured limit
+    TooLarge(usize),
+    /// Compressed data was malformed
+    InvalidData(String),
+}
+
+impl std::fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecompressError::UnsupportedEncoding(enc) => write!(f, "Unsupported Content-Encoding: {}", enc),
+            DecompressError::TooLarge(limit) => write!(f, "Decompressed body exceeds {} bytes", limit),
+            DecompressError::InvalidData(msg) => write!(f, "Invalid compressed body: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DecompressError {}
+
+/// Request body decompression middleware
+pub struct DecompressMiddleware {
+    /// Maximum size of the decompressed body in bytes
+    max_size: usize,
+}
+
+impl DecompressMiddleware {
+    /// Create middleware with the default 10 MiB limit
+    pub fn new() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
+    }
+
+    /// Builder: Set maximum decompressed body size in bytes
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Decode a body according to a `Content-Encoding` header value
+    ///
+    /// Codings are listed in the order they were applied, so they are
+    /// undone from right to left. The size limit applies to every stage.
+    pub fn decode(&self, encoding: &str, body: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        let codings = encoding
+            .split(',')
+            .filter(|token| !token.trim().is_empty())
+            .map(|token| {
+                ContentCoding::parse(token)
+                    .ok_or_else(|| DecompressError::UnsupportedEncoding(token.trim().to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut data = body.to_vec();
+        for coding in codings.into_iter().rev() {
+            data = match coding {
+                ContentCoding::Identity => continue,
+                ContentCoding::Gzip => self.read_bounded(flate2::read::MultiGzDecoder::new(&data[..]))?,
+                ContentCoding::Deflate => self.read_bounded(flate2::read::ZlibDecoder::new(&data[..]))?,
+                ContentCoding::Brotli => self.read_bounded(brotli::Decompressor::new(&data[..], 4096))?,
+            };
+        }
+
+        Ok(data)
+    }
+
+    /// Read a decoder to the end, stopping one byte past the limit
+    fn read_bounded<R: Read>(&self, decoder: R) -> Result<Vec<u8>, DecompressError> {
+        let mut out = Vec::new();
+        decoder
+            .take(self.max_size as u64 + 1)
+            .read_to_end(&mut out)
+            .map_err(|e| DecompressError::InvalidData(e.to_string()))?;
+
+        if out.len() > self.max_size {
+            return Err(DecompressError::TooLarge(self.max_size));
+        }
+        Ok(out)
+    }
+
+    /// Build the rejection response for a decompression error
+    fn error_response(error: &DecompressError) -> crate::middleware::Response {
+        let (status, code) = match error {
+            DecompressError::UnsupportedEncoding(_) => (415, "UNSUPPORTED_ENCODING"),
+            DecompressError::TooLarge(_) => (413, "PAYLOAD_TOO_LARGE"),
+            DecompressError::InvalidData(_) => (400, "INVALID_ENCODING"),
+        };
+
+        let mut response = ResponseBuilder::new()
+            .status(status)
+            .header("Content-Type", "application/json");
+        if status == 415 {
+            response = response.header("Accept-Encoding", SUPPORTED_ENCODINGS);
+        }
+
+        response
+            .body(
+                serde_json::json!({ "error": error.to_string(), "code": code })
+                    .to_string()
+                    .into_bytes(),
+            )
+            .finish()
+    }
+}
+
+impl Default for DecompressMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware for DecompressMiddleware {
+    fn call<'a>(&'a self, ctx: Context<'a>) -> MiddlewareFuture<'a> {
+        Box::pin(async move {
+            let encoding = match ctx.headers().get("content-encoding") {
+                Some(encoding) => encoding,
+                None => return Ok((ctx, MiddlewareResult::Continue)),
+            };
+
+            match self.decode(encoding, ctx.body()) {
+                Ok(decoded) => {
+                    let mut new_ctx = ctx;
+                    new_ctx.set_body(decoded);
+
+                    // The body is now plain; the original length no longer applies
+                    let headers = new_ctx.headers_mut();
+                    headers.remove("content-encoding");
+                    headers.remove("content-length");
+
+                    Ok((new_ctx, MiddlewareResult::Continue))
+                }
+                Err(e) => {
+                    let response = Self::error_response(&e);
+                    Ok((ctx, MiddlewareResult::Response(response)))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpParser;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut encoder = brotli::CompressorWriter::new(&mut out, 4096, 5, 22);
+            encoder.write_all(data).unwrap();
+        }
+        out
+    }
+
+    fn request_with_body(encoding: &str, body: &[u8]) -> Vec<u8> {
+        let mut request = format!(
+            "POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n",
+            encoding,
+            body.len()
+        )
+        .into_bytes();
+        request.extend_from_slice(body);
+        request
+    }
+
+    #[tokio::test]
+    async fn test_gzip_decompression() {
+        let request_bytes = request_with_body("gzip", &gzip(b"hello gzip"));
+        let parser = HttpParser::new();
+        let parsed = parser.parse_request(&request_bytes).unwrap();
+        let body = &request_bytes[parsed.body_offset..];
+
+        let ctx = Context::new(&parsed, body);
+        let middleware = DecompressMiddleware::new();
+
+        let (new_ctx, result) = middleware.call(ctx).await.unwrap();
+        assert!(matches!(result, MiddlewareResult::Continue));
+        assert_eq!(new_ctx.body(), b"hello gzip");
+        assert!(new_ctx.headers().get("Content-Encoding").is_none());
+        assert!(new_ctx.headers().get("Content-Length").is_none());
+        assert_eq!(new_ctx.headers().get("Host"), Some("example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_brotli_decompression() {
+        let request_bytes = request_with_body("br", &brotli(b"hello brotli"));
+        let parser = HttpParser::new();
+        let parsed = parser.parse_request(&request_bytes).unwrap();
+        let body = &request_bytes[parsed.body_offset..];
+
+        let ctx = Context::new(&parsed, body);
+        let middleware = DecompressMiddleware::new();
+
+        let (new_ctx, result) = middleware.call(ctx).await.unwrap();
+        assert!(matches!(result, MiddlewareResult::Continue));
+        assert_eq!(new_ctx.body(), b"hello brotli");
+        assert!(new_ctx.headers().get("Content-Encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zip_bomb_rejected_at_size_cap() {
+        // 1 MiB of zeros compresses to about 1 KiB
+        let bomb = gzip(&vec![0u8; 1024 * 1024]);
+        assert!(bomb.len() < 8 * 1024);
+
+        let request_bytes = request_with_body("gzip", &bomb);
+        let parser = HttpParser::new();
+        let parsed = parser.parse_request(&request_bytes).unwrap();
+        let body = &request_bytes[parsed.body_offset..];
+
+        let ctx = Context::new(&parsed, body);
+        let middleware = DecompressMiddleware::new().max_size(64 * 1024);
+
+        let (_new_ctx, result) = middleware.call(ctx).await.unwrap();
+        match result {
+            MiddlewareResult::Response(response) => assert_eq!(response.status, 413),
+            _ => panic!("Expected 413 for oversized body"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_encoding_rejected() {
+        let request_bytes = request_with_body("zstd", b"whatever");
+        let parser = HttpParser::new();
+        let parsed = parser.parse_request(&request_bytes).unwrap();
+        let body = &request_bytes[parsed.body_offset..];
+
+        let ctx = Context::new(&parsed, body);
+        let middleware = DecompressMiddleware::new();
+
+        let (_new_ctx, result) = middleware.call(ctx).await.unwrap();
+        match result {
+            MiddlewareResult::Response(response) => {
+                assert_eq!(response.status, 415);
+                assert!(response.headers.iter().any(|(k, _)| k == "Accept-Encoding"));
+            }
+            _ => panic!("Expected 415 for unknown encoding"),
+        }
+    }
+
+    #[test]
+    fn test_stacked_encodings_decoded_in_reverse() {
+        let middleware = DecompressMiddleware::new();
+        let encoded = brotli(&gzip(b"layered"));
+        assert_eq!(middleware.decode("gzip, br", &encoded).unwrap(), b"layered");
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let middleware = DecompressMiddleware::new();
+        assert!(matches!(
+            middleware.decode("gzip", b"not gzip"),
+            Err(DecompressError::InvalidData(_))
+        ));
+    }
+}
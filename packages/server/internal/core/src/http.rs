@@ -11,12 +11,21 @@ use memchr::memchr;
 use ahash::AHashMap;
 use std::str;
 
+/// Default maximum request-target (URI) length
+pub const DEFAULT_MAX_URI_LENGTH: usize = 8 * 1024;
+
+/// Room in the request line for the method, version and separators
+/// (`OPTIONS` + two spaces + `HTTP/1.1` fits with margin)
+const REQUEST_LINE_OVERHEAD: usize = 32;
+
 /// HTTP request parser optimized for performance
 pub struct HttpParser {
     /// Maximum header size to prevent DoS attacks
     max_header_size: usize,
     /// Maximum number of headers allowed
     max_headers: usize,
+    /// Maximum request-target length; longer URIs fail with `UriTooLong`
+    max_uri_length: usize,
 }
 
 impl HttpParser {
@@ -25,6 +34,7 @@ impl HttpParser {
         Self {
             max_header_size: 8 * 1024, // 8KB default
             max_headers: 100,
+            max_uri_length: DEFAULT_MAX_URI_LENGTH,
         }
     }
 
@@ -33,12 +43,24 @@ impl HttpParser {
         Self {
             max_header_size,
             max_headers,
+            max_uri_length: DEFAULT_MAX_URI_LENGTH,
         }
     }
 
+    /// Set the maximum request-target (URI) length
+    pub fn max_uri_length(mut self, max_uri_length: usize) -> Self {
+        self.max_uri_length = max_uri_length;
+        self
+    }
+
+    /// Maximum request-target (URI) length
+    pub fn uri_limit(&self) -> usize {
+        self.max_uri_length
+    }
+
     /// Parse HTTP request from bytes with zero-copy optimization
     pub fn parse_request<'a>(&self, input: &'a [u8]) -> Result<ParsedRequest<'a>, ParseError> {
-        let mut parser = RequestParser::new(input, self.max_header_size, self.max_headers);
+        let mut parser = RequestParser::new(input, self.max_header_size, self.max_headers, self.max_uri_length);
         parser.parse()
     }
 }
@@ -67,7 +89,7 @@ pub struct ParsedRequest<'a> {
 }
 
 /// Zero-copy header storage optimized for lookups
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Headers<'a> {
     /// Fast lookup map for headers
     map: AHashMap<&'a str, &'a str>,
@@ -107,6 +129,14 @@ impl<'a> Headers<'a> {
         None
     }
 
+    /// Remove a header by name (case-insensitive), returning its value
+    pub fn remove(&mut self, name: &str) -> Option<&'a str> {
+        let key = *self.map.keys().find(|k| k.eq_ignore_ascii_case(name))?;
+        let value = self.map.remove(key)?;
+        self.count -= 1;
+        Some(value)
+    }
+
     /// Get header value as specific type
     #[inline]
     pub fn get_parsed<T>(&self, name: &str) -> Option<T>
@@ -155,15 +185,17 @@ struct RequestParser<'a> {
     position: usize,
     max_header_size: usize,
     max_headers: usize,
+    max_uri_length: usize,
 }
 
 impl<'a> RequestParser<'a> {
-    fn new(input: &'a [u8], max_header_size: usize, max_headers: usize) -> Self {
+    fn new(input: &'a [u8], max_header_size: usize, max_headers: usize, max_uri_length: usize) -> Self {
         Self {
             input,
             position: 0,
             max_header_size,
             max_headers,
+            max_uri_length,
         }
     }
 
@@ -191,7 +223,17 @@ impl<'a> RequestParser<'a> {
 
     /// Parse request line: "GET /path HTTP/1.1\r\n"
     fn parse_request_line(&mut self) -> Result<(Method, &'a str, &'a str), ParseError> {
-        let line_end = self.find_line_end()?;
+        // Bound the search so an oversized request line is rejected without
+        // scanning (or waiting for) the rest of it
+        let max_line = self.max_uri_length.saturating_add(REQUEST_LINE_OVERHEAD);
+        let window_end = self.input.len().min(self.position.saturating_add(max_line + 2));
+        let line_end = match self.find_line_end_before(window_end) {
+            Ok(end) => end,
+            Err(_) if self.input.len() - self.position > max_line + 1 => {
+                return Err(ParseError::UriTooLong);
+            }
+            Err(e) => return Err(e),
+        };
         let line = &self.input[self.position..line_end];
         
         // Find spaces using SIMD-optimized search
@@ -206,6 +248,10 @@ impl<'a> RequestParser<'a> {
         let path_bytes = &line[first_space + 1..second_space];
         let version_bytes = &line[second_space + 1..];
 
+        if path_bytes.len() > self.max_uri_length {
+            return Err(ParseError::UriTooLong);
+        }
+
         // Parse method
         let method = Method::from_bytes(method_bytes)
             .ok_or(ParseError::InvalidMethod)?;
@@ -231,6 +277,8 @@ impl<'a> RequestParser<'a> {
     fn parse_headers(&mut self) -> Result<Headers<'a>, ParseError> {
         let mut headers = Headers::with_capacity(16); // Typical header count
         let headers_start = self.position;
+        let mut content_length: Option<u64> = None;
+        let mut transfer_encoding = false;
         
         loop {
             // Check for end of headers (\r\n\r\n)
@@ -248,6 +296,23 @@ impl<'a> RequestParser<'a> {
 
             // Parse single header
             let (name, value) = self.parse_header_line()?;
+
+            // Message framing headers must be unambiguous (RFC 7230 3.3.3)
+            if name.eq_ignore_ascii_case("content-length") {
+                let length = parse_content_length(value)?;
+                match content_length {
+                    Some(existing) if existing != length => {
+                        return Err(ParseError::AmbiguousMessageLength);
+                    }
+                    // Identical repeats are harmless; keep a single copy
+                    Some(_) => continue,
+                    None => content_length = Some(length),
+                }
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                validate_transfer_encoding(value)?;
+                transfer_encoding = true;
+            }
+
             headers.insert(name, value);
             
             // Check header size limit after parsing (DoS protection)
@@ -257,6 +322,10 @@ impl<'a> RequestParser<'a> {
             }
         }
 
+        if transfer_encoding && content_length.is_some() {
+            return Err(ParseError::AmbiguousMessageLength);
+        }
+
         Ok(headers)
     }
 
@@ -273,12 +342,24 @@ impl<'a> RequestParser<'a> {
         let value_bytes = &line[colon_pos + 1..];
 
         // Convert to strings and trim whitespace
-        let name = str::from_utf8(name_bytes)
-            .map_err(|_| ParseError::InvalidHeader)?
-            .trim();
-        let value = str::from_utf8(value_bytes)
-            .map_err(|_| ParseError::InvalidHeader)?
-            .trim();
+        let raw_name = str::from_utf8(name_bytes)
+            .map_err(|_| ParseError::InvalidHeader)?;
+        let name = raw_name.trim();
+
+        // Padding a framing header's name is a classic way to make one hop
+        // honor it while another ignores it
+        if name.len() != raw_name.len() && is_framing_header(name) {
+            return Err(ParseError::InvalidHeader);
+        }
+        let raw_value = str::from_utf8(value_bytes)
+            .map_err(|_| ParseError::InvalidHeader)?;
+        // Framing values only shed optional whitespace (SP / HTAB), so stray
+        // control characters reach validation instead of being trimmed away
+        let value = if is_framing_header(name) {
+            raw_value.trim_matches(|c| c == ' ' || c == '\t')
+        } else {
+            raw_value.trim()
+        };
 
         // Move past this line
         self.position = line_end + 2; // Skip \r\n
@@ -288,7 +369,12 @@ impl<'a> RequestParser<'a> {
 
     /// Find end of current line using SIMD
     fn find_line_end(&self) -> Result<usize, ParseError> {
-        let remaining = &self.input[self.position..];
+        self.find_line_end_before(self.input.len())
+    }
+
+    /// Find the next `\r\n` starting before `end`
+    fn find_line_end_before(&self, end: usize) -> Result<usize, ParseError> {
+        let remaining = &self.input[self.position..end];
         
         // Use SIMD to find \r\n quickly
         let mut search_pos = 0;
@@ -308,6 +394,41 @@ impl<'a> RequestParser<'a> {
     }
 }
 
+/// Whether `name` determines where the message body ends
+fn is_framing_header(name: &str) -> bool {
+    name.eq_ignore_ascii_case("content-length") || name.eq_ignore_ascii_case("transfer-encoding")
+}
+
+/// Parse a `Content-Length` value, which must be plain decimal digits
+fn parse_content_length(value: &str) -> Result<u64, ParseError> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseError::InvalidContentLength);
+    }
+    value.parse().map_err(|_| ParseError::InvalidContentLength)
+}
+
+/// Validate a `Transfer-Encoding` value: every coding must be a plain token
+/// and `chunked` must be the final one
+fn validate_transfer_encoding(value: &str) -> Result<(), ParseError> {
+    let mut last = None;
+    for coding in value.split(',') {
+        let coding = coding.trim_matches(|c| c == ' ' || c == '\t');
+        if coding.is_empty() || !coding.bytes().all(is_token_byte) {
+            return Err(ParseError::InvalidTransferEncoding);
+        }
+        last = Some(coding);
+    }
+    match last {
+        Some(coding) if coding.eq_ignore_ascii_case("chunked") => Ok(()),
+        _ => Err(ParseError::InvalidTransferEncoding),
+    }
+}
+
+/// RFC 7230 `tchar`
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
 /// HTTP parsing errors
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
@@ -327,6 +448,26 @@ pub enum ParseError {
     TooManyHeaders,
     /// Headers too large (DoS protection)
     HeadersTooLarge,
+    /// Request-target longer than the configured limit (DoS protection)
+    UriTooLong,
+    /// `Content-Length` is not a plain decimal number
+    InvalidContentLength,
+    /// `Transfer-Encoding` is malformed or does not end in `chunked`
+    InvalidTransferEncoding,
+    /// Conflicting `Content-Length` values, or both `Content-Length` and
+    /// `Transfer-Encoding` (request smuggling protection)
+    AmbiguousMessageLength,
+}
+
+impl ParseError {
+    /// HTTP status to answer the client with
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ParseError::UriTooLong => 414,
+            ParseError::TooManyHeaders | ParseError::HeadersTooLarge => 431,
+            _ => 400,
+        }
+    }
 }
 
 impl std::fmt::Display for ParseError {
@@ -340,6 +481,10 @@ impl std::fmt::Display for ParseError {
             ParseError::InvalidHeader => write!(f, "Invalid header format"),
             ParseError::TooManyHeaders => write!(f, "Too many headers"),
             ParseError::HeadersTooLarge => write!(f, "Headers too large"),
+            ParseError::UriTooLong => write!(f, "Request URI too long"),
+            ParseError::InvalidContentLength => write!(f, "Invalid Content-Length"),
+            ParseError::InvalidTransferEncoding => write!(f, "Invalid Transfer-Encoding"),
+            ParseError::AmbiguousMessageLength => write!(f, "Ambiguous message length"),
         }
     }
 }
@@ -623,4 +768,95 @@ mod tests {
         let parsed2 = parser.parse_request(request2).unwrap();
         assert_eq!(parsed2.headers.get_parsed::<usize>("X-Invalid-Number"), None);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_uri_at_limit_parses() {
+        let path = format!("/{}", "a".repeat(63));
+        let request = format!("GET {} HTTP/1.1\r\nHost: example.com\r\n\r\n", path);
+        let parser = HttpParser::new().max_uri_length(64);
+
+        let parsed = parser.parse_request(request.as_bytes()).unwrap();
+        assert_eq!(parsed.path, path);
+    }
+
+    #[test]
+    fn test_uri_over_limit_rejected_with_414() {
+        let parser = HttpParser::new().max_uri_length(64);
+
+        let request = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(64));
+        let err = parser.parse_request(request.as_bytes()).unwrap_err();
+        assert_eq!(err, ParseError::UriTooLong);
+        assert_eq!(err.status_code(), 414);
+
+        // A huge request line is rejected without a line terminator in sight
+        let request = format!("GET /{}", "a".repeat(1024 * 1024));
+        assert_eq!(parser.parse_request(request.as_bytes()).unwrap_err(), ParseError::UriTooLong);
+
+        // A short incomplete request line still asks for more data
+        assert_eq!(parser.parse_request(b"GET /abc").unwrap_err(), ParseError::IncompleteRequest);
+    }
+
+    #[test]
+    fn test_content_length_with_transfer_encoding_rejected() {
+        let parser = HttpParser::new();
+
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 4\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let err = parser.parse_request(request).unwrap_err();
+        assert_eq!(err, ParseError::AmbiguousMessageLength);
+        assert_eq!(err.status_code(), 400);
+
+        // Order does not matter
+        let request = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\ncontent-length: 4\r\n\r\n";
+        assert_eq!(parser.parse_request(request).unwrap_err(), ParseError::AmbiguousMessageLength);
+
+        // Either one alone is fine
+        let request = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n";
+        assert!(parser.parse_request(request).is_ok());
+    }
+
+    #[test]
+    fn test_conflicting_content_length_rejected() {
+        let parser = HttpParser::new();
+
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 4\r\nContent-Length: 40\r\n\r\n";
+        assert_eq!(parser.parse_request(request).unwrap_err(), ParseError::AmbiguousMessageLength);
+
+        // Identical duplicates collapse to one header
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 4\r\nContent-Length: 4\r\n\r\ntest";
+        let parsed = parser.parse_request(request).unwrap();
+        assert_eq!(parsed.headers.content_length(), Some(4));
+        assert_eq!(parsed.headers.len(), 1);
+
+        for value in ["4, 4", "+4", "-1", "0x10", ""] {
+            let request = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", value);
+            assert_eq!(
+                parser.parse_request(request.as_bytes()).unwrap_err(),
+                ParseError::InvalidContentLength,
+                "value {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_obfuscated_transfer_encoding_rejected() {
+        let parser = HttpParser::new();
+
+        for value in ["xchunked", "chunked, identity", "chunked\x0b", "\"chunked\"", "chunked,", "identity"] {
+            let request = format!("POST / HTTP/1.1\r\nTransfer-Encoding: {}\r\n\r\n", value);
+            assert_eq!(
+                parser.parse_request(request.as_bytes()).unwrap_err(),
+                ParseError::InvalidTransferEncoding,
+                "value {:?}",
+                value
+            );
+        }
+
+        // Whitespace before the colon hides the header from lenient parsers
+        let request = b"POST / HTTP/1.1\r\nTransfer-Encoding : chunked\r\n\r\n";
+        assert_eq!(parser.parse_request(request).unwrap_err(), ParseError::InvalidHeader);
+
+        let request = b"POST / HTTP/1.1\r\nTransfer-Encoding: Chunked\r\n\r\n";
+        assert!(parser.parse_request(request).is_ok());
+    }
+}
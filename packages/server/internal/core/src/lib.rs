@@ -35,6 +35,7 @@ pub mod http;
 pub mod middleware;
 pub mod rate_limit;
 pub mod csrf;
+pub mod decompress;
 pub mod request;
 pub mod response;
 pub mod security_headers;
@@ -42,14 +43,15 @@ pub mod security_headers;
 pub use method::Method;
 pub use params::{Params, ParamError};
 pub use radix::RadixTree;
-pub use http::{HttpParser, ParsedRequest, Headers, ParseError};
+pub use http::{HttpParser, ParsedRequest, Headers, ParseError, DEFAULT_MAX_URI_LENGTH};
 pub use middleware::{
     Context, ResponseBuilder, Response as MiddlewareResponse, Extensions, MiddlewareResult,
     Middleware, MiddlewareChain, MiddlewareError,
     LoggerMiddleware, CorsMiddleware, CorsConfig, CorsError
 };
-pub use csrf::{CsrfMiddleware, CsrfConfig, SameSitePolicy};
-pub use rate_limit::{RateLimitMiddleware, RateLimitConfig, RateLimitStore, InMemoryStore, RateLimitError};
+pub use csrf::{CsrfMiddleware, CsrfConfig, CsrfMode, SameSitePolicy};
+pub use decompress::{DecompressMiddleware, DecompressError, ContentCoding};
+pub use rate_limit::{RateLimitMiddleware, RateLimitConfig, RateLimitAlgorithm, BucketState, RateLimitStorage, RateLimitStore, InMemoryStore, RedisStore, RateLimitError, KeyExtractor};
 pub use request::{Request, FormParseError};
 pub use response::{Response, StatusCode, ResponseBody, CookieOptions};
 pub use security_headers::{SecurityHeadersMiddleware, SecurityHeadersConfig, HstsConfig};
@@ -8,6 +8,7 @@
 
 use crate::http::{ParsedRequest, Headers};
 use crate::method::Method;
+use std::borrow::Cow;
 use std::future::Future;
 use std::pin::Pin;
 
@@ -17,11 +18,13 @@ pub struct Context<'a> {
     /// Parsed HTTP request
     pub request: &'a ParsedRequest<'a>,
     /// Request body (if available)
-    pub body: &'a [u8],
+    pub body: Cow<'a, [u8]>,
     /// Response builder
     pub response: ResponseBuilder,
     /// Extension storage for middleware data
     pub extensions: Extensions,
+    /// Headers modified by middleware (copied from the request on first write)
+    headers: Option<Headers<'a>>,
 }
 
 impl<'a> Context<'a> {
@@ -29,9 +32,10 @@ impl<'a> Context<'a> {
     pub fn new(request: &'a ParsedRequest<'a>, body: &'a [u8]) -> Self {
         Self {
             request,
-            body,
+            body: Cow::Borrowed(body),
             response: ResponseBuilder::new(),
             extensions: Extensions::new(),
+            headers: None,
         }
     }
 
@@ -50,18 +54,45 @@ impl<'a> Context<'a> {
     /// Get request headers
     #[inline]
     pub fn headers(&self) -> &Headers<'a> {
-        &self.request.headers
+        self.headers.as_ref().unwrap_or(&self.request.headers)
+    }
+
+    /// Get mutable request headers for middleware that rewrites them
+    pub fn headers_mut(&mut self) -> &mut Headers<'a> {
+        let request = self.request;
+        self.headers.get_or_insert_with(|| request.headers.clone())
     }
 
     /// Get request body as bytes
     #[inline]
     pub fn body(&self) -> &[u8] {
-        self.body
+        &self.body
+    }
+
+    /// Replace the request body (e.g. after decoding)
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = Cow::Owned(body);
     }
 
     /// Get request body as string (if valid UTF-8)
     pub fn body_string(&self) -> Result<&str, std::str::Utf8Error> {
-        std::str::from_utf8(self.body)
+        std::str::from_utf8(&self.body)
+    }
+
+    /// Store a value for later middleware and the handler, replacing (and
+    /// returning) any earlier value of the same type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.extensions.insert(value)
+    }
+
+    /// Value stored by earlier middleware
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+
+    /// Mutable access to a value stored by earlier middleware
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.extensions.get_mut::<T>()
     }
 }
 
@@ -150,9 +181,12 @@ impl Extensions {
         Self::default()
     }
 
-    /// Insert typed data
-    pub fn insert<T: Send + Sync + 'static>(&mut self, data: T) {
-        self.data.insert(std::any::TypeId::of::<T>(), Box::new(data));
+    /// Insert typed data, returning the previous value of that type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, data: T) -> Option<T> {
+        self.data
+            .insert(std::any::TypeId::of::<T>(), Box::new(data))
+            .and_then(|previous| previous.downcast::<T>().ok())
+            .map(|boxed| *boxed)
     }
 
     /// Get typed data
@@ -162,6 +196,18 @@ impl Extensions {
             .and_then(|data| data.downcast_ref::<T>())
     }
 
+    /// Get mutable typed data
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.data
+            .get_mut(&std::any::TypeId::of::<T>())
+            .and_then(|data| data.downcast_mut::<T>())
+    }
+
+    /// Whether a value of type `T` is stored
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.data.contains_key(&std::any::TypeId::of::<T>())
+    }
+
     /// Remove typed data
     pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
         self.data
@@ -211,19 +257,28 @@ impl MiddlewareChain {
     }
 
     /// Execute middleware chain
-    pub async fn execute<'a>(&self, mut ctx: Context<'a>) -> Result<Response, MiddlewareError> {
+    pub async fn execute<'a>(&'a self, ctx: Context<'a>) -> Result<Response, MiddlewareError> {
+        match self.run(ctx).await? {
+            (_, Some(response)) => Ok(response),
+            // If no middleware returned a response, return the built response
+            (ctx, None) => Ok(ctx.response.finish()),
+        }
+    }
+
+    /// Run the chain, returning the context (with whatever middleware
+    /// stored in it) for the handler, and the response if a middleware
+    /// answered the request itself
+    pub async fn run<'a>(&'a self, mut ctx: Context<'a>) -> Result<(Context<'a>, Option<Response>), MiddlewareError> {
         for middleware in &self.middleware {
             let (new_ctx, result) = middleware.call(ctx).await?;
             ctx = new_ctx;
-            
+
             match result {
                 MiddlewareResult::Continue => continue,
-                MiddlewareResult::Response(response) => return Ok(response),
+                MiddlewareResult::Response(response) => return Ok((ctx, Some(response))),
             }
         }
-
-        // If no middleware returned a response, return the built response
-        Ok(ctx.response.finish())
+        Ok((ctx, None))
     }
 }
 
@@ -620,6 +675,35 @@ mod tests {
         assert_eq!(extensions.get::<u32>(), None);
     }
 
+    #[tokio::test]
+    async fn test_context_extensions_are_type_keyed() {
+        #[derive(Debug, PartialEq)]
+        struct UserId(u64);
+        #[derive(Debug, PartialEq)]
+        struct SessionId(u64);
+
+        let request_bytes = b"GET /me HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let parsed = HttpParser::new().parse_request(request_bytes).unwrap();
+        let mut ctx = Context::new(&parsed, &request_bytes[parsed.body_offset..]);
+
+        assert_eq!(ctx.insert(UserId(7)), None);
+        ctx.insert(SessionId(99));
+
+        // Same inner type, different wrapper: no collision
+        assert_eq!(ctx.get::<UserId>(), Some(&UserId(7)));
+        assert_eq!(ctx.get::<SessionId>(), Some(&SessionId(99)));
+        assert_eq!(ctx.get::<u64>(), None);
+
+        ctx.get_mut::<UserId>().unwrap().0 = 8;
+        assert_eq!(ctx.insert(UserId(9)), Some(UserId(8)));
+        assert!(ctx.extensions.contains::<SessionId>());
+
+        // Handlers see what middleware stored
+        let request = crate::Request::new(&parsed, ctx.body(), crate::Params::new()).with_extensions(&ctx.extensions);
+        assert_eq!(request.extension::<UserId>(), Some(&UserId(9)));
+        assert_eq!(request.extension::<String>(), None);
+    }
+
     #[tokio::test]
     async fn test_logger_middleware() {
         let request_bytes = b"GET /test HTTP/1.1\r\nHost: example.com\r\n\r\n";
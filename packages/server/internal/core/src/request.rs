@@ -4,6 +4,7 @@
 //! with convenient methods for accessing parameters, headers, body, and query strings.
 
 use crate::http::{ParsedRequest, Headers};
+use crate::middleware::Extensions;
 use crate::params::Params;
 use crate::method::Method;
 use std::collections::HashMap;
@@ -18,6 +19,8 @@ pub struct Request<'a> {
     body: &'a [u8],
     /// Route parameters (e.g., from "/users/:id")
     params: Params<'a>,
+    /// Data stored by middleware, if the request went through a chain
+    extensions: Option<&'a Extensions>,
 }
 
 impl<'a> Request<'a> {
@@ -27,9 +30,23 @@ impl<'a> Request<'a> {
             parsed,
             body,
             params,
+            extensions: None,
         }
     }
 
+    /// Attach the extensions filled in by middleware (see [`Context::insert`])
+    ///
+    /// [`Context::insert`]: crate::middleware::Context::insert
+    pub fn with_extensions(mut self, extensions: &'a Extensions) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Value of type `T` stored by middleware
+    pub fn extension<T: Send + Sync + 'static>(&self) -> Option<&'a T> {
+        self.extensions.and_then(|extensions| extensions.get::<T>())
+    }
+
     /// Get HTTP method
     #[inline]
     pub fn method(&self) -> Method {
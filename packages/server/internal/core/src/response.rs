@@ -50,6 +50,8 @@ impl StatusCode {
     pub const METHOD_NOT_ALLOWED: StatusCode = StatusCode(405);
     pub const NOT_ACCEPTABLE: StatusCode = StatusCode(406);
     pub const CONFLICT: StatusCode = StatusCode(409);
+    pub const URI_TOO_LONG: StatusCode = StatusCode(414);
+    pub const RANGE_NOT_SATISFIABLE: StatusCode = StatusCode(416);
     pub const UNPROCESSABLE_ENTITY: StatusCode = StatusCode(422);
     pub const TOO_MANY_REQUESTS: StatusCode = StatusCode(429);
     
@@ -109,6 +111,8 @@ impl StatusCode {
             405 => "Method Not Allowed",
             406 => "Not Acceptable",
             409 => "Conflict",
+            414 => "URI Too Long",
+            416 => "Range Not Satisfiable",
             422 => "Unprocessable Entity",
             429 => "Too Many Requests",
             500 => "Internal Server Error",
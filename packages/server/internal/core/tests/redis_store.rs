@@ -0,0 +1,98 @@
+//! RedisStore against a live Redis server
+//!
+//! Run with `cargo test -p zap-core --features redis-tests`; set `REDIS_URL`
+//! to use a server other than `redis://127.0.0.1:6379`.
+
+#![cfg(feature = "redis-tests")]
+
+use zap_core::{RateLimitStore, RedisStore};
+
+async fn store() -> RedisStore {
+    let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+    RedisStore::connect(&url).await.expect("Redis server for redis-tests")
+}
+
+/// Key unique to this run so parallel runs don't share counters
+fn key(name: &str) -> String {
+    format!("zap-test:{}:{}:{}", name, std::process::id(), unique_suffix())
+}
+
+fn unique_suffix() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos()
+}
+
+#[tokio::test]
+async fn test_increment_counts_within_window() {
+    let store = store().await;
+    let key = key("increment");
+
+    assert_eq!(store.get(&key).await.unwrap(), None);
+    let (count, ttl) = store.increment(&key, 60).await.unwrap();
+    assert_eq!(count, 1);
+    assert!(ttl > 0 && ttl <= 60);
+    let (count, _) = store.increment(&key, 60).await.unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(store.get(&key).await.unwrap(), Some(2));
+
+    store.reset(&key).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_window_expires() {
+    let store = store().await;
+    let key = key("expiry");
+
+    let (_, ttl) = store.increment(&key, 1).await.unwrap();
+    assert_eq!(ttl, 1);
+    tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+    assert_eq!(store.get(&key).await.unwrap(), None);
+    let (count, _) = store.increment(&key, 1).await.unwrap();
+    assert_eq!(count, 1);
+
+    store.reset(&key).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reset_clears_counter() {
+    let store = store().await;
+    let key = key("reset");
+
+    store.increment(&key, 60).await.unwrap();
+    store.increment(&key, 60).await.unwrap();
+    store.reset(&key).await.unwrap();
+    assert_eq!(store.get(&key).await.unwrap(), None);
+    let (count, _) = store.increment(&key, 60).await.unwrap();
+    assert_eq!(count, 1);
+
+    store.reset(&key).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_instances_share_counters() {
+    let (first, second) = (store().await, store().await);
+    let key = key("shared");
+
+    first.increment(&key, 60).await.unwrap();
+    let (count, _) = second.increment(&key, 60).await.unwrap();
+    assert_eq!(count, 2);
+
+    first.reset(&key).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_token_bucket_refills() {
+    let store = store().await;
+    let key = key("bucket");
+
+    for _ in 0..3 {
+        assert!(store.take_token(&key, 10.0, 3).await.unwrap().allowed);
+    }
+    assert!(!store.take_token(&key, 10.0, 3).await.unwrap().allowed);
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    assert!(store.take_token(&key, 10.0, 3).await.unwrap().allowed);
+
+    store.reset(&key).await.unwrap();
+}
@@ -1,7 +1,7 @@
 mod metadata;
 mod types;
 
-use metadata::{FunctionMetadata, ParamMetadata};
+use metadata::{FunctionMetadata, ParamMetadata, TypeMetadata};
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{parse_macro_input, FnArg, ItemFn, PatType};
@@ -124,10 +124,11 @@ fn extract_metadata(func: &ItemFn) -> FunctionMetadata {
                 if let syn::Pat::Ident(pat_ident) = &**pat {
                     let param_name = pat_ident.ident.to_string();
                     let param_type = parse_type(ty);
+                    let is_optional = matches!(param_type, TypeMetadata::Option(_));
                     return Some(ParamMetadata {
                         name: param_name,
                         ty: param_type,
-                        is_optional: false,
+                        is_optional,
                     });
                 }
             }
@@ -189,7 +190,10 @@ fn generate_wrapper(func: &ItemFn, metadata: &FunctionMetadata) -> proc_macro2::
             }
         }).collect();
 
-    // Generate parameter deserialization code with proper type conversion
+    // Generate parameter deserialization code with proper type conversion.
+    // JSON drops `undefined` members, so an omitted `Option` parameter
+    // (optional in the TypeScript signature) is read as `null`. Binary
+    // parameters arrive base64-encoded from the generated client.
     let param_deserialize: Vec<_> = metadata
         .params
         .iter()
@@ -197,13 +201,27 @@ fn generate_wrapper(func: &ItemFn, metadata: &FunctionMetadata) -> proc_macro2::
         .map(|(p, ty)| {
             let param_name = format_ident!("{}", p.name);
             let param_name_str = &p.name;
+            let missing = if p.is_optional {
+                quote! { serde_json::Value::Null }
+            } else {
+                quote! { return Err(format!("Missing parameter: {}", #param_name_str)) }
+            };
+
+            let decode = if p.ty.is_bytes() {
+                quote! { ::zap_server::__private::bytes_from_value(value) }
+            } else if p.ty.is_optional_bytes() {
+                quote! { ::zap_server::__private::optional_bytes_from_value(value) }
+            } else {
+                quote! { serde_json::from_value(value) }
+            };
 
             quote! {
                 let #param_name: #ty = {
-                    let value = params.get(#param_name_str)
-                        .ok_or_else(|| format!("Missing parameter: {}", #param_name_str))?
-                        .clone();
-                    serde_json::from_value(value)
+                    let value = match params.get(#param_name_str) {
+                        Some(value) => value.clone(),
+                        None => #missing,
+                    };
+                    #decode
                         .map_err(|e| format!("Failed to deserialize parameter '{}': {}", #param_name_str, e))?
                 };
             }
@@ -233,13 +251,26 @@ fn generate_wrapper(func: &ItemFn, metadata: &FunctionMetadata) -> proc_macro2::
         }
     };
 
+    // Binary results are base64-encoded to match the generated client
+    let success_type = match &metadata.return_type {
+        TypeMetadata::Result { ok, .. } => ok.as_ref(),
+        other => other,
+    };
+    let encode_result = if success_type.is_bytes() {
+        quote! { Ok(::zap_server::__private::bytes_to_value(&result)) }
+    } else if success_type.is_optional_bytes() {
+        quote! { Ok(::zap_server::__private::optional_bytes_to_value(result.as_deref())) }
+    } else {
+        quote! { serde_json::to_value(result).map_err(|e| e.to_string()) }
+    };
+
     // Handle Result types - if the return type is a Result, handle both Ok and Err cases
     // The Err case should serialize the error as JSON for type-safe TypeScript consumption
     let result_handling = if metadata.return_type.is_result() {
         quote! {
             match #call_expr {
                 Ok(result) => {
-                    serde_json::to_value(result).map_err(|e| e.to_string())
+                    #encode_result
                 }
                 Err(e) => {
                     // Serialize the error as JSON - TypeScript will receive it as the error type
@@ -254,7 +285,7 @@ fn generate_wrapper(func: &ItemFn, metadata: &FunctionMetadata) -> proc_macro2::
     } else {
         quote! {
             let result = #call_expr;
-            serde_json::to_value(result).map_err(|e| e.to_string())
+            #encode_result
         }
     };
 
@@ -134,6 +134,16 @@ impl TypeMetadata {
         matches!(self, TypeMetadata::Result { .. })
     }
 
+    /// Check if this is `Vec<u8>`, which crosses the wire base64-encoded
+    pub fn is_bytes(&self) -> bool {
+        matches!(self, TypeMetadata::Vec(inner) if **inner == TypeMetadata::U8)
+    }
+
+    /// Check if this is `Option<Vec<u8>>`
+    pub fn is_optional_bytes(&self) -> bool {
+        matches!(self, TypeMetadata::Option(inner) if inner.is_bytes())
+    }
+
     /// Get the inner type if this is an Option
     #[allow(dead_code)]
     pub fn inner_option(&self) -> Option<&TypeMetadata> {
@@ -1,20 +1,32 @@
+use bytes::Bytes;
 use clap::Parser;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::net::UnixListener;
-use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::{JoinError, JoinHandle};
+use tokio::time::Sleep;
+use tracing::{debug, error, info, warn, Level};
 use tracing_subscriber::{fmt, EnvFilter};
 use splice::{
-    protocol::{Message, Role, SpliceCodec, PROTOCOL_VERSION, CAP_STREAMING, CAP_CANCELLATION, DEFAULT_MAX_FRAME_SIZE},
-    supervisor::{Supervisor, SupervisorConfig, WorkerState},
-    router::{Router, RouterConfig},
-    reload::ReloadManager,
+    admin::AdminReply,
+    protocol::{
+        ErrorKind, Message, PayloadFormat, ProtocolError, Role, SpliceCodec, PROTOCOL_VERSION, CAP_STREAMING, CAP_CANCELLATION, CAP_FORMAT_CBOR, CAP_PRIORITY, CAP_BATCH, CAP_COMPRESSION, Compression, DEFAULT_MAX_FRAME_SIZE,
+        ERR_CANCELLED, ERR_FRAME_TOO_LARGE, ERR_FUNCTION_NOT_FOUND, ERR_INVALID_PARAMS, ERR_INVALID_REQUEST, ERR_OVERLOADED, ERR_TIMEOUT, ERR_RELOADING, ERR_UNAUTHORIZED, ERR_UNAVAILABLE,
+        retry_after_details,
+    },
+    supervisor::{ResourceLimits, Supervisor, SupervisorConfig, SupervisorError, WorkerState},
+    router::{ExportPolicy, LoadShedConfig, ResponseRelay, Router, RouterConfig, RouterError, UploadOptions},
+    reload::{PendingReload, ReadyWorker, ReloadConfig, ReloadError, ReloadManager, WatchConfig},
     metrics::Metrics,
+    outbound::{self, OutboundConfig, DEFAULT_OUTBOUND_BUFFER},
+    precision::IntegerPolicy,
+    upload::{UploadError, UploadReceiver},
 };
-use tokio_util::codec::Framed;
-use futures::stream::StreamExt;
+use futures::stream::{Stream, StreamExt};
 use futures::sink::SinkExt;
 
 #[derive(Parser)]
@@ -30,11 +42,319 @@ struct Cli {
     #[arg(long, help = "Watch paths for hot reload (comma-separated)")]
     watch: Option<String>,
 
+    #[arg(long, help = "Milliseconds without further changes before a hot reload starts", default_value = "500")]
+    reload_debounce_ms: u64,
+
+    #[arg(long, help = "File extensions under the watch paths that trigger a hot reload (comma-separated)", default_value = "rs,toml")]
+    watch_extensions: String,
+
     #[arg(long, help = "Maximum concurrent requests", default_value = "1024")]
     max_concurrency: usize,
 
+    #[arg(long, help = "Invokes that may wait for a free slot, highest priority first", default_value = "0")]
+    max_queued_requests: usize,
+
+    #[arg(long, help = "Milliseconds a queued invoke may wait for a free slot before it is rejected as overloaded")]
+    admission_grace_ms: Option<u64>,
+
     #[arg(long, help = "Default timeout in seconds", default_value = "30")]
     timeout: u64,
+
+    #[arg(long, help = "Maximum queued outbound messages per connection", default_value_t = DEFAULT_OUTBOUND_BUFFER)]
+    outbound_buffer: usize,
+
+    #[arg(long, help = "Only allow these exports to be invoked (comma-separated, `*` wildcards)", conflicts_with = "deny_exports")]
+    allow_exports: Option<String>,
+
+    #[arg(long, help = "Block these exports from being invoked (comma-separated, `*` wildcards)")]
+    deny_exports: Option<String>,
+
+    #[arg(long, help = "Hide blocked exports from the host's export list")]
+    hide_blocked_exports: bool,
+
+    #[arg(long, help = "Integers outside the JS-safe range: allow, string or reject", default_value = "allow")]
+    unsafe_integers: IntegerPolicy,
+
+    #[arg(long, help = "Largest frame in bytes; lowered to what each peer advertises", default_value_t = DEFAULT_MAX_FRAME_SIZE)]
+    max_frame_size: u32,
+
+    #[arg(long, help = "Drop host frames that fail to decode instead of closing the connection")]
+    skip_malformed_frames: bool,
+
+    #[arg(long, help = "Compress large frames with gzip or zstd when the peer supports it")]
+    compression: Option<Compression>,
+
+    #[arg(long, help = "Shed new invokes while the worker reports at least this many active requests")]
+    shed_active_requests: Option<u32>,
+
+    #[arg(long, help = "Per-export timeouts in milliseconds, e.g. `cache.get=200,report.build=120000`")]
+    function_timeouts: Option<String>,
+
+    #[arg(long, help = "While the worker restarts, answer invokes with a retryable error carrying this retry hint in milliseconds")]
+    reload_retry_after_ms: Option<u64>,
+
+    #[arg(long = "worker-env", value_name = "KEY=VALUE", help = "Extra environment variable for the worker (repeatable)")]
+    worker_env: Vec<String>,
+
+    #[arg(long, help = "Let the worker write straight to this process's stdout/stderr instead of forwarding its output as log events")]
+    inherit_worker_output: bool,
+
+    #[arg(long, help = "Working directory for the worker")]
+    worker_dir: Option<PathBuf>,
+
+    #[arg(long, help = "Worker address-space limit in megabytes (Unix only)")]
+    worker_max_memory_mb: Option<u64>,
+
+    #[arg(long, help = "Worker open file descriptor limit (Unix only)")]
+    worker_max_open_files: Option<u64>,
+
+    #[arg(long, help = "Worker scheduling niceness, -20 to 19 (Unix only)", allow_hyphen_values = true)]
+    worker_nice: Option<i32>,
+
+    #[arg(long, help = "Consecutive failed health checks before the worker is restarted", default_value = "3")]
+    health_failure_threshold: u32,
+
+    #[arg(long, help = "Consecutive passed health checks that clear earlier failures", default_value = "1")]
+    health_success_threshold: u32,
+
+    #[arg(long, help = "Milliseconds between heartbeats sent to the worker (0 disables)", default_value = "5000")]
+    heartbeat_interval_ms: u64,
+
+    #[arg(long, help = "Milliseconds the worker has to answer a heartbeat", default_value = "2000")]
+    heartbeat_timeout_ms: u64,
+
+    #[arg(long, help = "Consecutive missed heartbeats before the worker is restarted", default_value = "3")]
+    heartbeat_miss_threshold: u32,
+
+    #[arg(long, help = "Least severe worker log event to re-emit: error, warn, info, debug or trace", default_value = "info")]
+    worker_log_level: Level,
+}
+
+/// Capabilities this runtime supports on both host and worker connections
+const RUNTIME_CAPABILITIES: u32 = CAP_STREAMING | CAP_CANCELLATION | CAP_FORMAT_CBOR | CAP_PRIORITY | CAP_BATCH;
+
+/// Host acks buffered per streamed response; acks are cumulative, so a
+/// few suffice
+const RESPONSE_ACK_CAPACITY: usize = 8;
+
+/// Upload from the host being forwarded to the worker
+struct HostUpload {
+    receiver: UploadReceiver,
+    window: u32,
+    body_tx: mpsc::Sender<Result<Bytes, UploadError>>,
+    /// Taken when the matching Invoke arrives
+    body_rx: Option<mpsc::Receiver<Result<Bytes, UploadError>>>,
+}
+
+/// Build the reply to the host for a routed invocation
+fn invoke_response(request_id: u64, result: Result<Bytes, RouterError>) -> Message {
+    match result {
+        Ok(result) => Message::InvokeResult {
+            request_id,
+            result,
+            duration_us: 0,
+        },
+        Err(e) => {
+            let details = match &e {
+                RouterError::Reloading { retry_after } => Some(retry_after_details(*retry_after)),
+                _ => None,
+            };
+            let (code, kind, message) = match e {
+                RouterError::Timeout => (ERR_TIMEOUT, ErrorKind::Timeout, "Request timeout".to_string()),
+                RouterError::Overloaded => (ERR_OVERLOADED, ErrorKind::System, "System overloaded".to_string()),
+                RouterError::Cancelled => (ERR_CANCELLED, ErrorKind::System, "Request cancelled".to_string()),
+                RouterError::WorkerUnavailable => (ERR_UNAVAILABLE, ErrorKind::System, "Worker not available".to_string()),
+                RouterError::Reloading { .. } => (ERR_RELOADING, ErrorKind::System, e.to_string()),
+                RouterError::ExecutionError(msg) => (2000, ErrorKind::User, msg),
+                RouterError::Unauthorized(name) => (ERR_UNAUTHORIZED, ErrorKind::User, format!("Function not allowed: {}", name)),
+                RouterError::FunctionNotFound(name) => (ERR_FUNCTION_NOT_FOUND, ErrorKind::User, format!("Function not found: {}", name)),
+                RouterError::InvalidParams(msg) => (ERR_INVALID_PARAMS, ErrorKind::User, msg),
+                RouterError::DuplicateRequestId(_) => (ERR_INVALID_REQUEST, ErrorKind::User, e.to_string()),
+                RouterError::FrameTooLarge { .. } => (ERR_FRAME_TOO_LARGE, ErrorKind::User, e.to_string()),
+            };
+            Message::InvokeError {
+                request_id,
+                code,
+                kind,
+                message,
+                details,
+            }
+        }
+    }
+}
+
+/// How the runtime reaches its worker: the socket workers connect to and
+/// the settings every worker connection is made with
+struct WorkerLink {
+    listener: UnixListener,
+    outbound_config: OutboundConfig,
+    max_frame_size: u32,
+    compression: Option<Compression>,
+    runtime_capabilities: u32,
+    /// Told when the active worker's connection closes
+    lost_tx: mpsc::Sender<()>,
+}
+
+impl WorkerLink {
+    /// Accept a worker's connection, complete its handshake and fetch its
+    /// exports
+    ///
+    /// Outbound messages are written by a task of their own; the read half
+    /// is returned to be read once the worker is active.
+    async fn connect(
+        &self,
+    ) -> Result<ReadyWorker<impl Stream<Item = Result<Message, ProtocolError>> + Unpin + Send + 'static>, ReloadError> {
+        let (worker_stream, _) = self.listener.accept().await?;
+        let mut worker_framed = self.outbound_config.framed(worker_stream, SpliceCodec::new(self.max_frame_size));
+
+        let (capabilities, peer_max_frame_size) = match worker_framed.next().await {
+            Some(Ok(Message::Handshake { protocol_version, role, capabilities, max_frame_size })) => {
+                if protocol_version != PROTOCOL_VERSION {
+                    return Err(ReloadError::NotReady(format!("protocol version mismatch ({})", protocol_version)));
+                }
+                if role != Role::Worker {
+                    return Err(ReloadError::NotReady("expected Worker role".to_string()));
+                }
+                (capabilities, max_frame_size)
+            }
+            Some(Err(e)) => return Err(e.into()),
+            other => return Err(ReloadError::NotReady(format!("invalid handshake: {:?}", other))),
+        };
+
+        let server_id = *uuid::Uuid::new_v4().as_bytes();
+        worker_framed.send(Message::HandshakeAck {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: capabilities & self.runtime_capabilities,
+            server_id,
+            export_count: 0,
+        }).await?;
+        worker_framed.codec_mut().set_format(PayloadFormat::negotiate(self.runtime_capabilities, capabilities));
+        worker_framed.codec_mut().set_compression(Compression::negotiate(self.compression, self.runtime_capabilities, capabilities));
+        worker_framed.codec_mut().set_priority(self.runtime_capabilities & capabilities & CAP_PRIORITY != 0);
+        let max_frame_size = worker_framed.codec_mut().negotiate_max_frame_size(peer_max_frame_size);
+        info!("Worker handshake complete");
+
+        worker_framed.send(Message::ListExports).await?;
+        let exports = match worker_framed.next().await {
+            Some(Ok(Message::ListExportsResult { exports })) => exports,
+            Some(Err(e)) => return Err(e.into()),
+            other => return Err(ReloadError::NotReady(format!("expected ListExportsResult, got {:?}", other))),
+        };
+        info!("Received {} exports from worker", exports.len());
+
+        // Supervisor→Worker bridge (bounded mpsc → worker socket)
+        let (worker_write, worker_read) = worker_framed.split();
+        let (worker_tx, worker_rx) = self.outbound_config.channel();
+        tokio::spawn(async move {
+            if let Err(e) = outbound::write_loop(worker_rx, worker_write).await {
+                error!("Failed to send message to worker: {}", e);
+            }
+            debug!("Supervisor→Worker bridge terminated");
+        });
+
+        Ok(ReadyWorker { worker_tx, worker_read, exports, max_frame_size })
+    }
+
+    /// Make a connected worker the active one and start reading from it
+    async fn activate<S>(&self, router: &Arc<Router>, supervisor: &mut Supervisor, worker: ReadyWorker<S>)
+    where
+        S: Stream<Item = Result<Message, ProtocolError>> + Unpin + Send + 'static,
+    {
+        router.update_exports(worker.exports).await;
+        router.set_worker_max_frame_size(worker.max_frame_size);
+        router.swap_worker(worker.worker_tx.clone());
+        supervisor.set_worker_tx(worker.worker_tx);
+        supervisor.update_state(WorkerState::Ready);
+
+        // Worker→Supervisor bridge (worker socket → Router)
+        let reader = {
+            let router = Arc::clone(router);
+            let shutdown_ack = supervisor.shutdown_ack_notifier();
+            tokio::spawn(async move { router.read_worker_messages(worker.worker_read, shutdown_ack).await })
+        };
+        self.watch(reader);
+    }
+
+    /// Report the loss of the active worker's connection, so the main loop
+    /// restarts it without waiting for the health tick. In-flight requests
+    /// have already failed by then
+    fn watch(&self, reader: JoinHandle<bool>) {
+        let lost_tx = self.lost_tx.clone();
+        tokio::spawn(async move {
+            if let Ok(true) = reader.await {
+                warn!("Worker→Supervisor bridge terminated");
+                let _ = lost_tx.send(()).await;
+            }
+        });
+    }
+}
+
+/// Stop the worker and return the timer its replacement is spawned on,
+/// failing only once the restart budget is exhausted
+///
+/// The main loop keeps serving during the backoff. Invokes arriving until
+/// the replacement connects get the `--reload-retry-after-ms` hint, if set.
+async fn begin_restart(router: &Router, supervisor: &mut Supervisor) -> Result<Pin<Box<Sleep>>, SupervisorError> {
+    router.begin_reload();
+    match supervisor.begin_restart().await {
+        Ok(backoff) => Ok(Box::pin(tokio::time::sleep(backoff))),
+        Err(e) => {
+            error!("Restart budget exhausted, shutting down");
+            router.end_reload();
+            Err(e)
+        }
+    }
+}
+
+/// Wait for an armed timer; pending while there is none
+async fn expired(timer: &mut Option<Pin<Box<Sleep>>>) {
+    match timer {
+        Some(timer) => timer.as_mut().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Wait for a reload's new worker; pending while no reload is under way
+async fn reload_ready<S>(reload: &mut Option<PendingReload<S>>) -> Result<ReadyWorker<S>, ReloadError> {
+    match reload {
+        Some(reload) => reload.ready().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Wait for a running task; pending while there is none
+async fn finished<T>(task: &mut Option<JoinHandle<T>>) -> Result<T, JoinError> {
+    match task {
+        Some(task) => task.await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Parse `name=ms` pairs from a comma-separated CLI list
+fn parse_function_timeouts(value: &str) -> Result<HashMap<String, Duration>, String> {
+    parse_list(value)
+        .into_iter()
+        .map(|entry| {
+            let (name, ms) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=ms, got '{}'", entry))?;
+            let ms: u64 = ms
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid timeout for '{}': '{}'", name.trim(), ms))?;
+            Ok((name.trim().to_string(), Duration::from_millis(ms)))
+        })
+        .collect()
+}
+
+/// Split a comma-separated CLI list
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 #[tokio::main]
@@ -44,23 +364,75 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
         .init();
 
+    let started_at = Instant::now();
     let cli = Cli::parse();
+    let runtime_capabilities = RUNTIME_CAPABILITIES | cli.compression.map_or(0, |_| CAP_COMPRESSION);
 
     info!("Starting Splice runtime");
     info!("Socket: {}", cli.socket.display());
     info!("Worker: {}", cli.worker.display());
 
     // Create runtime components
-    let supervisor_config = SupervisorConfig::default();
+    let supervisor_config = SupervisorConfig {
+        env: cli
+            .worker_env
+            .iter()
+            .map(|entry| {
+                entry
+                    .split_once('=')
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .ok_or_else(|| format!("expected KEY=VALUE for --worker-env, got '{}'", entry))
+            })
+            .collect::<Result<_, _>>()?,
+        working_dir: cli.worker_dir.clone(),
+        capture_output: !cli.inherit_worker_output,
+        limits: ResourceLimits {
+            max_memory_bytes: cli.worker_max_memory_mb.map(|mb| mb * 1024 * 1024),
+            max_open_files: cli.worker_max_open_files,
+            nice: cli.worker_nice,
+        },
+        health_failure_threshold: cli.health_failure_threshold,
+        health_success_threshold: cli.health_success_threshold,
+        heartbeat_interval: Some(Duration::from_millis(cli.heartbeat_interval_ms)).filter(|d| !d.is_zero()),
+        heartbeat_timeout: Duration::from_millis(cli.heartbeat_timeout_ms),
+        heartbeat_miss_threshold: cli.heartbeat_miss_threshold,
+        ..Default::default()
+    };
     let router_config = RouterConfig {
         max_concurrent_requests: cli.max_concurrency,
+        max_queued_requests: cli.max_queued_requests,
+        admission_grace: cli.admission_grace_ms.map(Duration::from_millis),
         max_concurrent_per_function: 256, // Increased to handle test load
         default_timeout: Duration::from_secs(cli.timeout),
+        export_policy: match (&cli.allow_exports, &cli.deny_exports) {
+            (Some(allow), _) => ExportPolicy::Allow(parse_list(allow)),
+            (None, Some(deny)) => ExportPolicy::Deny(parse_list(deny)),
+            (None, None) => ExportPolicy::AllowAll,
+        },
+        hide_blocked_exports: cli.hide_blocked_exports,
+        integer_policy: cli.unsafe_integers,
+        load_shed: cli.shed_active_requests.map(LoadShedConfig::new),
+        function_timeouts: match &cli.function_timeouts {
+            Some(value) => parse_function_timeouts(value)?,
+            None => HashMap::new(),
+        },
+        reload_retry_after: cli.reload_retry_after_ms.map(Duration::from_millis),
+        worker_log_level: cli.worker_log_level,
+        ..Default::default()
+    };
+    let outbound_config = OutboundConfig {
+        buffer: cli.outbound_buffer,
+        ..Default::default()
     };
 
     let worker_socket = cli.socket.parent()
         .unwrap_or(&cli.socket)
         .join("worker.sock");
+    let reload_config = ReloadConfig {
+        ready_timeout: supervisor_config.connect_timeout,
+        drain_timeout: supervisor_config.drain_timeout,
+        shutdown_grace: supervisor_config.shutdown_grace,
+    };
 
     let mut supervisor = Supervisor::new(
         supervisor_config,
@@ -68,25 +440,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         worker_socket.clone(),
     );
 
-    // Create router and wire up worker channel BEFORE wrapping in Arc
     let mut router = Router::new(router_config);
-    let (supervisor_tx, mut supervisor_rx) = mpsc::channel::<Message>(100);
-    router.set_worker_tx(supervisor_tx);
+    router.set_heartbeat(supervisor.heartbeat());
     let router = Arc::new(router);
     let metrics = Metrics::new();
-    let mut reload_manager = ReloadManager::new(cli.worker.clone());
+    let mut reload_manager = ReloadManager::new(cli.worker.clone()).with_watch(WatchConfig {
+        paths: cli.watch.as_deref().map(parse_list).unwrap_or_default().into_iter().map(PathBuf::from).collect(),
+        debounce: Duration::from_millis(cli.reload_debounce_ms),
+        watch_extensions: parse_list(&cli.watch_extensions),
+    });
 
-    // Create worker listener socket BEFORE starting worker
+    // Create worker listener socket BEFORE starting worker. It stays open
+    // so restarted and reloaded workers can connect too
     if worker_socket.exists() {
         tokio::fs::remove_file(&worker_socket).await?;
     }
-    let worker_listener = UnixListener::bind(&worker_socket)?;
-    info!("Worker socket listening on: {}", worker_socket.display());
-
-    // Start accepting connections in background
-    let accept_handle = tokio::spawn(async move {
-        worker_listener.accept().await
+    let (worker_lost_tx, mut worker_lost_rx) = mpsc::channel::<()>(1);
+    let worker_link = Arc::new(WorkerLink {
+        listener: UnixListener::bind(&worker_socket)?,
+        outbound_config: outbound_config.clone(),
+        max_frame_size: cli.max_frame_size,
+        compression: cli.compression,
+        runtime_capabilities,
+        lost_tx: worker_lost_tx,
     });
+    info!("Worker socket listening on: {}", worker_socket.display());
 
     // Start worker
     match supervisor.start().await {
@@ -99,74 +477,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // Wait for worker connection
-    let (worker_stream, _) = accept_handle.await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))??;
-    let mut worker_framed = Framed::new(worker_stream, SpliceCodec::default());
-
-    // Worker handshake
-    if let Some(Ok(Message::Handshake { protocol_version, role, capabilities, .. })) = worker_framed.next().await {
-        if protocol_version != PROTOCOL_VERSION {
-            error!("Protocol version mismatch");
-            return Ok(());
-        }
-        if role != Role::Worker {
-            error!("Expected Worker role");
+    // Wait for worker connection, handshake and exports
+    match worker_link.connect().await {
+        Ok(worker) => worker_link.activate(&router, &mut supervisor, worker).await,
+        Err(e) => {
+            error!("Worker failed to connect: {}", e);
             return Ok(());
         }
-
-        let server_id = uuid::Uuid::new_v4().as_bytes().clone();
-        worker_framed.send(Message::HandshakeAck {
-            protocol_version: PROTOCOL_VERSION,
-            capabilities: capabilities & (CAP_STREAMING | CAP_CANCELLATION),
-            server_id,
-            export_count: 0,
-        }).await?;
-
-        supervisor.update_state(WorkerState::Ready);
-        info!("Worker handshake complete");
-    } else {
-        error!("Invalid worker handshake");
-        return Ok(());
-    }
-
-    // Request exports from worker
-    worker_framed.send(Message::ListExports).await?;
-    if let Some(Ok(Message::ListExportsResult { exports })) = worker_framed.next().await {
-        info!("Received {} exports from worker", exports.len());
-        router.update_exports(exports).await;
     }
 
-    // Split worker_framed into separate read/write halves
-    let (mut worker_write, mut worker_read) = worker_framed.split();
+    // Task 3: Worker health polling for load shedding
+    router.spawn_health_poller();
 
-    // Task 1: Supervisor→Worker bridge (mpsc → worker socket)
-    tokio::spawn(async move {
-        while let Some(msg) = supervisor_rx.recv().await {
-            if let Err(e) = worker_write.send(msg).await {
-                error!("Failed to send message to worker: {}", e);
-                break;
-            }
-        }
-        warn!("Supervisor→Worker bridge terminated");
-    });
+    // Task 4: Heartbeat, catching a worker that is alive but hung
+    let heartbeat_interval = cli.heartbeat_interval_ms.max(1);
+    let mut heartbeat_ticker = tokio::time::interval(Duration::from_millis(heartbeat_interval));
+    heartbeat_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-    // Task 2: Worker→Supervisor bridge (worker socket → Router)
-    let router_for_worker = Arc::clone(&router);
-    tokio::spawn(async move {
-        while let Some(result) = worker_read.next().await {
-            match result {
-                Ok(msg) => {
-                    router_for_worker.handle_worker_message(msg).await;
-                }
-                Err(e) => {
-                    error!("Worker frame decode error: {}", e);
-                    break;
-                }
-            }
-        }
-        warn!("Worker→Supervisor bridge terminated");
-    });
+    // Task 5: Process health checks, on their own schedule so other events
+    // cannot keep postponing them
+    let mut health_ticker = tokio::time::interval(Duration::from_secs(5));
+    health_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    health_ticker.reset();
 
     // Create host listener socket
     if cli.socket.exists() {
@@ -175,79 +507,259 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let host_listener = UnixListener::bind(&cli.socket)?;
     info!("Host socket listening on: {}", cli.socket.display());
 
+    // A restart under way: the backoff before the replacement is spawned,
+    // then the wait for it to connect
+    let mut restart_due: Option<Pin<Box<Sleep>>> = None;
+    let mut reconnecting = None;
+    // A hot reload waiting for its new worker
+    let mut reloading = None;
+
     // Main loop - accept host connections
     loop {
+        // Restarts and reloads wait while a worker is being replaced
+        let replacing = restart_due.is_some() || reconnecting.is_some() || reloading.is_some();
         tokio::select! {
             // Accept host connection
             accept_result = host_listener.accept() => {
                 match accept_result {
                     Ok((host_stream, _)) => {
                         info!("Host connected");
-                        let mut host_framed = Framed::new(host_stream, SpliceCodec::default());
+                        let mut host_framed = outbound_config.framed(
+                            host_stream,
+                            SpliceCodec::new(cli.max_frame_size).with_skip_malformed(cli.skip_malformed_frames),
+                        );
 
                         // Host handshake
-                        if let Some(Ok(Message::Handshake { protocol_version, role, capabilities, .. })) = host_framed.next().await {
+                        if let Some(Ok(Message::Handshake { protocol_version, role, capabilities, max_frame_size })) = host_framed.next().await {
                             if protocol_version == PROTOCOL_VERSION && role == Role::Host {
-                                let server_id = uuid::Uuid::new_v4().as_bytes().clone();
+                                let server_id = *uuid::Uuid::new_v4().as_bytes();
                                 let exports = router.get_exports().await;
+                                let negotiated = capabilities & runtime_capabilities;
                                 let _ = host_framed.send(Message::HandshakeAck {
                                     protocol_version: PROTOCOL_VERSION,
-                                    capabilities: capabilities & (CAP_STREAMING | CAP_CANCELLATION),
+                                    capabilities: negotiated,
                                     server_id,
                                     export_count: exports.len() as u32,
                                 }).await;
+                                host_framed.codec_mut().set_format(PayloadFormat::negotiate(runtime_capabilities, capabilities));
+                                host_framed.codec_mut().set_compression(Compression::negotiate(cli.compression, runtime_capabilities, capabilities));
+                                host_framed.codec_mut().negotiate_max_frame_size(max_frame_size);
 
                                 info!("Host handshake complete");
 
                                 // Handle host connection in separate task
-                                let exports_for_task = exports.clone();
                                 let router_for_task = Arc::clone(&router);
+                                let host_id = router.register_host();
+                                let (host_write, mut host_read) = host_framed.split();
+                                let (host_tx, host_rx) = outbound_config.channel();
+                                tokio::spawn(async move {
+                                    if let Err(e) = outbound::write_loop(host_rx, host_write).await {
+                                        error!("Failed to send message to host: {}", e);
+                                    }
+                                });
+
+                                // Worker output, for as long as the host is connected
+                                let mut worker_logs = supervisor.subscribe_logs();
+                                let log_tx = host_tx.downgrade();
+                                tokio::spawn(async move {
+                                    loop {
+                                        match worker_logs.recv().await {
+                                            Ok(event) => match log_tx.upgrade() {
+                                                Some(tx) if tx.send(event).await.is_ok() => {}
+                                                _ => break,
+                                            },
+                                            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                                                debug!("Host fell behind on worker logs, skipped {}", skipped);
+                                            }
+                                            Err(broadcast::error::RecvError::Closed) => break,
+                                        }
+                                    }
+                                });
                                 tokio::spawn(async move {
-                                    while let Some(Ok(msg)) = host_framed.next().await {
+                                    let mut uploads: HashMap<u64, HostUpload> = HashMap::new();
+                                    // Ack channels of responses being streamed to the host
+                                    let mut response_streams: HashMap<u64, mpsc::Sender<Message>> = HashMap::new();
+                                    while let Some(Ok(msg)) = host_read.next().await {
                                         match msg {
                                             Message::ListExports => {
                                                 info!("Host requested exports list");
-                                                let _ = host_framed.send(Message::ListExportsResult {
-                                                    exports: exports_for_task.clone(),
+                                                // Current exports, which a reload may have changed
+                                                // since the handshake
+                                                let _ = host_tx.send(Message::ListExportsResult {
+                                                    exports: router_for_task.get_exports().await,
                                                 }).await;
                                             }
-                                            Message::Invoke { request_id, function_name, params, deadline_ms, context } => {
+                                            Message::Invoke { request_id, function_name, params, deadline_ms, context, priority } => {
                                                 info!("Host invoked: {}", function_name);
-                                                match router_for_task.invoke(
-                                                    function_name.clone(),
-                                                    params.clone(),
-                                                    deadline_ms,
-                                                    context,
-                                                ).await {
-                                                    Ok(result) => {
-                                                        let _ = host_framed.send(Message::InvokeResult {
-                                                            request_id,
-                                                            result,
-                                                            duration_us: 0,
-                                                        }).await;
+                                                // Reusing an in-flight id must not steal the
+                                                // first caller's reply (or its upload body)
+                                                let claim = match router_for_task.claim_request_id(host_id, request_id) {
+                                                    Ok(claim) => claim,
+                                                    Err(e) => {
+                                                        let _ = host_tx.send(invoke_response(request_id, Err(e))).await;
+                                                        continue;
                                                     }
+                                                };
+                                                // A streamed body was announced with StreamStart
+                                                if let Some(body_rx) = uploads.get_mut(&request_id).and_then(|u| u.body_rx.take()) {
+                                                    let window = uploads[&request_id].window;
+                                                    let router = Arc::clone(&router_for_task);
+                                                    let host_tx = host_tx.clone();
+                                                    tokio::spawn(async move {
+                                                        let _claim = claim;
+                                                        let result = router.invoke_upload(
+                                                            function_name,
+                                                            params,
+                                                            deadline_ms,
+                                                            context,
+                                                            UploadOptions { priority, window },
+                                                            body_rx,
+                                                        ).await;
+                                                        let _ = host_tx.send(invoke_response(request_id, result)).await;
+                                                    });
+                                                    continue;
+                                                }
+
+                                                let router = Arc::clone(&router_for_task);
+                                                let host_tx = host_tx.clone();
+                                                // Hosts with streaming get the function's
+                                                // response stream relayed ahead of its result
+                                                if negotiated & CAP_STREAMING != 0 {
+                                                    let (ack_tx, acks) = mpsc::channel(RESPONSE_ACK_CAPACITY);
+                                                    response_streams.retain(|_, acks| !acks.is_closed());
+                                                    response_streams.insert(request_id, ack_tx);
+                                                    tokio::spawn(async move {
+                                                        let _claim = claim;
+                                                        let relay = ResponseRelay { request_id, frames: host_tx.clone(), acks };
+                                                        let result = router.invoke_streaming(function_name, params, deadline_ms, context, priority, relay).await;
+                                                        let _ = host_tx.send(invoke_response(request_id, result)).await;
+                                                    });
+                                                    continue;
+                                                }
+                                                tokio::spawn(async move {
+                                                    let _claim = claim;
+                                                    let result = router.invoke_with_priority(function_name, params, deadline_ms, context, priority).await;
+                                                    let _ = host_tx.send(invoke_response(request_id, result)).await;
+                                                });
+                                            }
+                                            Message::InvokeBatch { request_id, calls } => {
+                                                info!("Host invoked a batch of {} calls", calls.len());
+                                                let claim = match router_for_task.claim_request_id(host_id, request_id) {
+                                                    Ok(claim) => claim,
                                                     Err(e) => {
-                                                        let (code, kind, message) = match e {
-                                                            splice::router::RouterError::Timeout => (splice::protocol::ERR_TIMEOUT, splice::protocol::ErrorKind::System, "Request timeout".to_string()),
-                                                            splice::router::RouterError::Overloaded => (splice::protocol::ERR_OVERLOADED, splice::protocol::ErrorKind::System, "System overloaded".to_string()),
-                                                            splice::router::RouterError::Cancelled => (splice::protocol::ERR_CANCELLED, splice::protocol::ErrorKind::System, "Request cancelled".to_string()),
-                                                            splice::router::RouterError::WorkerUnavailable => (2004, splice::protocol::ErrorKind::System, "Worker not available".to_string()),
-                                                            splice::router::RouterError::ExecutionError(msg) => (2000, splice::protocol::ErrorKind::User, msg),
-                                                        };
-                                                        let _ = host_framed.send(Message::InvokeError {
-                                                            request_id,
-                                                            code,
-                                                            kind,
-                                                            message,
-                                                            details: None,
-                                                        }).await;
+                                                        let _ = host_tx.send(invoke_response(request_id, Err(e))).await;
+                                                        continue;
                                                     }
+                                                };
+                                                let router = Arc::clone(&router_for_task);
+                                                let host_tx = host_tx.clone();
+                                                tokio::spawn(async move {
+                                                    let _claim = claim;
+                                                    let reply = match router.invoke_batch(calls, 0).await {
+                                                        Ok(results) => Message::InvokeBatchResult { request_id, results },
+                                                        Err(e) => invoke_response(request_id, Err(e)),
+                                                    };
+                                                    let _ = host_tx.send(reply).await;
+                                                });
+                                            }
+                                            Message::StreamStart { request_id, window } => {
+                                                let window = window.max(1);
+                                                let (body_tx, body_rx) = mpsc::channel(window as usize);
+                                                uploads.insert(request_id, HostUpload {
+                                                    receiver: UploadReceiver::new(request_id, window),
+                                                    window,
+                                                    body_tx,
+                                                    body_rx: Some(body_rx),
+                                                });
+                                            }
+                                            Message::StreamChunk { request_id, sequence, data } => {
+                                                let Some(upload) = uploads.get_mut(&request_id) else {
+                                                    warn!("Chunk for unknown upload {}", request_id);
+                                                    continue;
+                                                };
+                                                // Forwarding waits while the worker is behind, so
+                                                // acks to the host reflect real progress
+                                                let accepted = upload.receiver.accept(sequence);
+                                                let forwarded = match accepted {
+                                                    Ok(ack) => upload.body_tx.send(Ok(data)).await.map(|_| ack).map_err(|_| UploadError::Closed),
+                                                    Err(e) => {
+                                                        let _ = upload.body_tx.send(Err(e.clone())).await;
+                                                        Err(e)
+                                                    }
+                                                };
+                                                match forwarded {
+                                                    Ok(Some(ack)) => {
+                                                        let _ = host_tx.send(ack).await;
+                                                    }
+                                                    Ok(None) => {}
+                                                    Err(e) => {
+                                                        uploads.remove(&request_id);
+                                                        let _ = host_tx.send(e.to_stream_error(request_id)).await;
+                                                    }
+                                                }
+                                            }
+                                            Message::StreamEnd { request_id, total_chunks } => {
+                                                // Dropping the body sender ends the upload to the
+                                                // worker; a short upload is aborted instead
+                                                if let Some(upload) = uploads.remove(&request_id) {
+                                                    if let Err(e) = upload.receiver.finish(total_chunks) {
+                                                        warn!("Upload {} ended early: {}", request_id, e);
+                                                        let _ = upload.body_tx.send(Err(e.clone())).await;
+                                                        let _ = host_tx.send(e.to_stream_error(request_id)).await;
+                                                    }
+                                                }
+                                            }
+                                            Message::StreamAck { request_id, .. } | Message::StreamError { request_id, .. } => {
+                                                // Never block reading the host on a stream
+                                                // that is not sending, nor drop an ack it
+                                                // would wait for
+                                                match response_streams.get(&request_id) {
+                                                    Some(acks) => {
+                                                        if let Err(mpsc::error::TrySendError::Full(msg)) = acks.try_send(msg) {
+                                                            let acks = acks.clone();
+                                                            tokio::spawn(async move {
+                                                                let _ = acks.send(msg).await;
+                                                            });
+                                                        }
+                                                    }
+                                                    None => debug!("Ack for unknown response stream {}", request_id),
                                                 }
                                             }
+                                            Message::AdminCommand { request_id, command, args } => {
+                                                info!("Host admin command: {}", command);
+                                                let router = Arc::clone(&router_for_task);
+                                                let host_tx = host_tx.clone();
+                                                tokio::spawn(async move {
+                                                    let data = router
+                                                        .admin(command, args)
+                                                        .await
+                                                        .unwrap_or_else(|e| AdminReply::Refused(e.to_string()).encode());
+                                                    let _ = host_tx.send(Message::AdminResult { request_id, data }).await;
+                                                });
+                                            }
+                                            Message::HealthCheck => {
+                                                let _ = host_tx.send(Message::HealthStatus {
+                                                    uptime_ms: started_at.elapsed().as_millis() as u64,
+                                                    active_requests: router_for_task.active_requests() as u32,
+                                                    total_requests: router_for_task.total_requests(),
+                                                }).await;
+                                            }
                                             Message::Shutdown => {
-                                                let _ = host_framed.send(Message::ShutdownAck).await;
+                                                let _ = host_tx.send(Message::ShutdownAck).await;
                                                 break;
                                             }
+                                            Message::Unknown { msg_type, .. } => {
+                                                debug!("Ignoring unknown message type 0x{:02x} from host", msg_type);
+                                            }
+                                            Message::Malformed { request_id, error, .. } => {
+                                                let _ = host_tx.send(Message::InvokeError {
+                                                    request_id,
+                                                    code: ERR_INVALID_REQUEST,
+                                                    kind: ErrorKind::User,
+                                                    message: format!("Malformed request: {}", error),
+                                                    details: None,
+                                                }).await;
+                                            }
                                             _ => {}
                                         }
                                     }
@@ -261,23 +773,83 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
+            // Worker connection closed
+            Some(()) = worker_lost_rx.recv(), if !replacing => {
+                supervisor.update_state(WorkerState::Failed);
+                warn!("Worker connection lost, restarting worker");
+                restart_due = Some(begin_restart(&router, &mut supervisor).await?);
+            }
+
+            // Heartbeat over the worker connection
+            _ = heartbeat_ticker.tick(), if cli.heartbeat_interval_ms > 0 && !replacing => {
+                if supervisor.heartbeat_tick() {
+                    warn!("Worker stopped answering heartbeats, restarting");
+                    restart_due = Some(begin_restart(&router, &mut supervisor).await?);
+                }
+            }
+
             // Health check interval
-            _ = tokio::time::sleep(Duration::from_secs(5)) => {
-                if !supervisor.is_ready() {
-                    warn!("Worker not ready, attempting restart");
-                    if let Err(e) = supervisor.restart().await {
+            _ = health_ticker.tick(), if !replacing => {
+                if supervisor.check_health() {
+                    warn!("Worker failed consecutive health checks, attempting restart");
+                    restart_due = Some(begin_restart(&router, &mut supervisor).await?);
+                }
+            }
+
+            // Restart backoff over: spawn the replacement and wait for it
+            // to connect in the background
+            () = expired(&mut restart_due) => {
+                restart_due = None;
+                match supervisor.respawn().await {
+                    Ok(info) => {
+                        info!("Worker restarted: PID {}", info.pid);
+                        let link = Arc::clone(&worker_link);
+                        let connect_timeout = supervisor.connect_timeout();
+                        reconnecting = Some(tokio::spawn(async move {
+                            tokio::time::timeout(connect_timeout, link.connect()).await
+                        }));
+                    }
+                    Err(e) => {
                         error!("Failed to restart worker: {}", e);
+                        router.end_reload();
                     }
                 }
             }
 
-            // Hot reload check
-            _ = tokio::time::sleep(Duration::from_secs(1)), if cli.watch.is_some() => {
+            // Restarted worker connected, or gave up
+            connected = finished(&mut reconnecting) => {
+                reconnecting = None;
+                match connected {
+                    Ok(Ok(Ok(worker))) => worker_link.activate(&router, &mut supervisor, worker).await,
+                    Ok(Ok(Err(e))) => error!("Restarted worker failed to connect: {}", e),
+                    Ok(Err(_)) => error!("Restarted worker did not connect within {:?}", supervisor.connect_timeout()),
+                    Err(e) => error!("Restarted worker connection task failed: {}", e),
+                }
+                router.end_reload();
+            }
+
+            // Hot reload check: the current worker serves until its
+            // replacement is ready
+            _ = tokio::time::sleep(Duration::from_secs(1)), if cli.watch.is_some() && !replacing => {
                 if let Ok(true) = reload_manager.check_for_changes().await {
                     info!("Initiating hot reload");
-                    if let Err(e) = reload_manager.perform_reload(&mut supervisor, Duration::from_secs(30)).await {
-                        error!("Hot reload failed: {}", e);
+                    let link = Arc::clone(&worker_link);
+                    match reload_manager.begin_reload(&mut supervisor, &reload_config, async move { link.connect().await }).await {
+                        Ok(pending) => reloading = Some(pending),
+                        Err(e) => error!("Hot reload failed, keeping the current worker: {}", e),
+                    }
+                }
+            }
+
+            // Reloaded worker connected, or gave up
+            ready = reload_ready(&mut reloading) => {
+                let Some(pending) = reloading.take() else { continue };
+                match reload_manager.finish_reload(&mut supervisor, &router, &reload_config, pending.standby, ready).await {
+                    Ok(reloaded) => {
+                        info!("Worker reloaded: PID {}", reloaded.worker.pid);
+                        worker_link.watch(reloaded.reader);
                     }
+                    Err(e) => error!("Hot reload failed, keeping the current worker: {}", e),
                 }
             }
         }
@@ -0,0 +1,134 @@
+//! Codec read-buffer benchmarks
+//!
+//! Decodes a run of large `StreamChunk` frames arriving in socket-sized
+//! reads, the way `Framed` drives the codec, and compares the default read
+//! buffer against one pre-sized for large frames. The heap allocations and
+//! bytes read per decode pass for each configuration are printed before
+//! timing.
+
+use bytes::{Bytes, BytesMut};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use splice::protocol::{Message, SpliceCodec};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Counts allocations and reallocations made through the global allocator
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Bytes delivered per simulated socket read
+const READ_SIZE: usize = 64 * 1024;
+
+/// Frames decoded per iteration
+const FRAMES: usize = 32;
+
+fn encoded_stream(payload_size: usize) -> Vec<u8> {
+    let mut codec = SpliceCodec::default();
+    let mut buf = BytesMut::new();
+    for sequence in 0..FRAMES as u64 {
+        let msg = Message::StreamChunk {
+            request_id: 1,
+            sequence,
+            data: Bytes::from(vec![0xAB; payload_size]),
+        };
+        codec.encode(msg, &mut buf).unwrap();
+    }
+    buf.to_vec()
+}
+
+/// Feed `stream` through `codec` like `Framed` does, returning the number of
+/// decoded frames and simulated socket reads
+fn decode_stream(mut codec: SpliceCodec, stream: &[u8]) -> (usize, usize) {
+    let mut buf = BytesMut::with_capacity(codec.initial_capacity());
+    let mut reads = 0;
+    let mut decoded = 0;
+    let mut offset = 0;
+
+    while offset < stream.len() {
+        // `Framed` reserves at least one byte, then reads into spare capacity
+        buf.reserve(1);
+        let read = (buf.capacity() - buf.len()).min(READ_SIZE).min(stream.len() - offset);
+        buf.extend_from_slice(&stream[offset..offset + read]);
+        offset += read;
+        reads += 1;
+
+        loop {
+            let frame = codec.decode(&mut buf).unwrap();
+            match frame {
+                Some(msg) => {
+                    black_box(msg);
+                    decoded += 1;
+                }
+                None => break,
+            }
+        }
+    }
+
+    (decoded, reads)
+}
+
+/// A named way of building the codec under test
+type CodecConfig = (&'static str, fn(usize) -> SpliceCodec);
+
+fn bench_large_frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("codec_large_frames");
+
+    for payload_size in [256 * 1024, 1024 * 1024] {
+        let stream = encoded_stream(payload_size);
+        group.throughput(Throughput::Bytes(stream.len() as u64));
+
+        let configs: [CodecConfig; 2] = [
+            ("default", |_| SpliceCodec::default()),
+            ("tuned", |payload_size| {
+                SpliceCodec::default()
+                    .with_initial_capacity(2 * payload_size)
+                    .with_read_chunk_size(payload_size)
+            }),
+        ];
+
+        for (name, make) in configs {
+            let before = ALLOCATIONS.load(Ordering::Relaxed);
+            let (frames, reads) = decode_stream(make(payload_size), &stream);
+            let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+            assert_eq!(frames, FRAMES);
+            println!(
+                "{} frames of {} KiB, {} buffer: {} allocations, {} reads",
+                FRAMES,
+                payload_size / 1024,
+                name,
+                allocations,
+                reads
+            );
+
+            group.bench_with_input(BenchmarkId::new(name, payload_size), &stream, |b, stream| {
+                b.iter(|| decode_stream(make(payload_size), stream))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_large_frames);
+criterion_main!(benches);
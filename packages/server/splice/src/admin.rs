@@ -0,0 +1,116 @@
+//! Worker admin commands
+//!
+//! `AdminCommand` / `AdminResult` give operators a structured channel to a
+//! live worker over the existing connection. Only the read-only
+//! introspection commands listed in [`SAFE_COMMANDS`] are ever executed;
+//! anything else is answered with [`AdminReply::Refused`]. Workers must also
+//! opt in, so a worker that does not enable the channel refuses everything.
+//!
+//! Replies are MessagePack-encoded [`AdminReply`] values carried in
+//! `AdminResult::data`. Admin messages carry no request ID: replies come back
+//! in command order, so callers should keep one command outstanding at a time.
+
+use crate::protocol::{Message, ProtocolError};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Report uptime, request counters and memory use
+pub const CMD_STATS: &str = "stats";
+
+/// List the functions currently executing
+pub const CMD_HANDLERS: &str = "handlers";
+
+/// Commands a worker may execute
+pub const SAFE_COMMANDS: &[&str] = &[CMD_STATS, CMD_HANDLERS];
+
+/// Whether `command` is a known, safe introspection command
+pub fn is_safe(command: &str) -> bool {
+    SAFE_COMMANDS.contains(&command)
+}
+
+/// Runtime statistics reported by `stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerStats {
+    pub uptime_ms: u64,
+    pub active_requests: u32,
+    pub total_requests: u64,
+    /// Resident set size, where the platform reports it
+    pub memory_rss_bytes: Option<u64>,
+}
+
+/// Outcome of an admin command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminReply {
+    Stats(WorkerStats),
+    /// Names of the functions in flight, one entry per request
+    Handlers(Vec<String>),
+    /// The command was not executed
+    Refused(String),
+}
+
+impl AdminReply {
+    /// Refusal for a command outside [`SAFE_COMMANDS`]
+    pub fn unknown_command(command: &str) -> Self {
+        AdminReply::Refused(format!("Unknown admin command '{}'", command))
+    }
+
+    /// Refusal sent by workers that have not enabled the admin channel
+    pub fn disabled() -> Self {
+        AdminReply::Refused("Admin commands are disabled on this worker".to_string())
+    }
+
+    /// MessagePack payload for `AdminResult::data`
+    pub fn encode(&self) -> Bytes {
+        Bytes::from(rmp_serde::to_vec(self).unwrap_or_default())
+    }
+
+    /// Wrap the reply to command `request_id` in an `AdminResult` message
+    pub fn to_message(&self, request_id: u64) -> Message {
+        Message::AdminResult {
+            request_id,
+            data: self.encode(),
+        }
+    }
+
+    /// Decode an `AdminResult` payload
+    pub fn decode(data: &[u8]) -> Result<Self, ProtocolError> {
+        rmp_serde::from_slice(data).map_err(|e| ProtocolError::Serialization(e.to_string()))
+    }
+}
+
+/// Resident set size of this process, read from `/proc` on Linux
+pub fn memory_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_safe_commands_allowed() {
+        assert!(is_safe("stats"));
+        assert!(is_safe("handlers"));
+        assert!(!is_safe("exec"));
+        assert!(!is_safe("STATS"));
+        assert!(!is_safe(""));
+    }
+
+    #[test]
+    fn test_reply_roundtrip() {
+        let reply = AdminReply::Stats(WorkerStats {
+            uptime_ms: 1500,
+            active_requests: 2,
+            total_requests: 40,
+            memory_rss_bytes: Some(1 << 20),
+        });
+        match reply.to_message(3) {
+            Message::AdminResult { request_id: 3, data } => assert_eq!(AdminReply::decode(&data).unwrap(), reply),
+            other => panic!("Expected AdminResult, got {:?}", other),
+        }
+        assert!(AdminReply::decode(b"\xc1").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+//! Worker selection for hosts running several workers
+//!
+//! The [`Router`](crate::router::Router) uses this to pick among the workers
+//! added with [`Router::add_worker`](crate::router::Router::add_worker).
+//! Functions covered by a [`StickyConfig`] are routed by consistent hashing
+//! on a per-request key (a context header or the client IP), so the same key
+//! keeps landing on the same worker and adding or removing a worker only
+//! moves the keys that worker owns. Everything else, and sticky requests
+//! that carry no key, is spread round-robin.
+//!
+//! With a slow-start window, a newly added worker's share of the
+//! round-robin traffic ramps linearly from zero to a full share over the
+//! window, so its caches warm up before it takes full load. Sticky keys are
+//! not ramped: moving them would break the affinity they exist for.
+
+use crate::protocol::RequestContext;
+use crate::router::glob_match;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Default number of ring positions per worker
+pub const DEFAULT_VIRTUAL_NODES: usize = 160;
+
+/// Where the sticky key of a request comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StickyKey {
+    /// Value of a `RequestContext` header (case-insensitive name)
+    Header(String),
+    /// First address in `x-forwarded-for`
+    ClientIp,
+}
+
+impl StickyKey {
+    /// Key for `context`, if the request carries one
+    pub fn extract<'a>(&self, context: &'a RequestContext) -> Option<&'a str> {
+        let value = match self {
+            StickyKey::Header(name) => context.header(name)?,
+            StickyKey::ClientIp => context.forwarded_for()?.split(',').next()?,
+        };
+        let value = value.trim();
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// Sticky routing settings
+///
+/// `functions` are export names in which `*` matches any run of characters;
+/// an empty list makes every function sticky.
+#[derive(Debug, Clone)]
+pub struct StickyConfig {
+    pub key: StickyKey,
+    pub functions: Vec<String>,
+    pub virtual_nodes: usize,
+}
+
+impl StickyConfig {
+    pub fn new(key: StickyKey) -> Self {
+        Self {
+            key,
+            functions: Vec::new(),
+            virtual_nodes: DEFAULT_VIRTUAL_NODES,
+        }
+    }
+
+    /// Restrict sticky routing to matching functions
+    pub fn functions(mut self, patterns: Vec<String>) -> Self {
+        self.functions = patterns;
+        self
+    }
+
+    /// Ring positions per worker; more positions spread keys more evenly
+    pub fn virtual_nodes(mut self, virtual_nodes: usize) -> Self {
+        self.virtual_nodes = virtual_nodes.max(1);
+        self
+    }
+
+    /// Whether `function_name` is routed stickily
+    pub fn applies_to(&self, function_name: &str) -> bool {
+        self.functions.is_empty() || self.functions.iter().any(|p| glob_match(p, function_name))
+    }
+}
+
+/// 64-bit FNV-1a with a final avalanche step
+///
+/// Stable across processes and Rust versions, unlike `DefaultHasher`, so a
+/// key keeps its worker when the host restarts.
+struct RingHasher(u64);
+
+impl Default for RingHasher {
+    fn default() -> Self {
+        RingHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for RingHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        // FNV alone clusters similar short keys on the ring
+        let mut x = self.0;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+}
+
+fn ring_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = RingHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Consistent-hash ring mapping keys to workers
+#[derive(Debug, Clone)]
+pub struct HashRing<W> {
+    ring: BTreeMap<u64, W>,
+    virtual_nodes: usize,
+}
+
+impl<W: Hash + Eq + Clone> HashRing<W> {
+    pub fn new(virtual_nodes: usize) -> Self {
+        Self {
+            ring: BTreeMap::new(),
+            virtual_nodes: virtual_nodes.max(1),
+        }
+    }
+
+    /// Place `worker` on the ring
+    pub fn add(&mut self, worker: W) {
+        for replica in 0..self.virtual_nodes {
+            self.ring.insert(ring_hash(&(&worker, replica)), worker.clone());
+        }
+    }
+
+    /// Take `worker` off the ring; its keys move to the next workers along
+    pub fn remove(&mut self, worker: &W) {
+        self.ring.retain(|_, w| w != worker);
+    }
+
+    /// Worker owning `key`
+    pub fn get(&self, key: &str) -> Option<&W> {
+        let hash = ring_hash(key);
+        self.ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, worker)| worker)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
+
+/// Fractional part of the golden ratio, stepping an evenly spread sequence
+const GOLDEN_RATIO_FRACTION: f64 = 0.618_033_988_749_894_9;
+
+/// Picks the worker for each invoke
+#[derive(Debug)]
+pub struct WorkerBalancer<W> {
+    workers: Vec<W>,
+    /// When each worker in `workers` was added
+    added_at: Vec<Instant>,
+    ring: HashRing<W>,
+    sticky: Option<StickyConfig>,
+    slow_start: Option<Duration>,
+    next: AtomicUsize,
+}
+
+impl<W: Hash + Eq + Clone> WorkerBalancer<W> {
+    /// Round-robin only, or sticky for the functions `sticky` covers
+    pub fn new(sticky: Option<StickyConfig>) -> Self {
+        let virtual_nodes = sticky
+            .as_ref()
+            .map_or(DEFAULT_VIRTUAL_NODES, |config| config.virtual_nodes);
+        Self {
+            workers: Vec::new(),
+            added_at: Vec::new(),
+            ring: HashRing::new(virtual_nodes),
+            sticky,
+            slow_start: None,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Ramp new workers' share of round-robin traffic up over `window`
+    pub fn with_slow_start(mut self, window: Duration) -> Self {
+        self.slow_start = (!window.is_zero()).then_some(window);
+        self
+    }
+
+    /// Add a worker once it is ready; its slow-start window begins now
+    ///
+    /// A restarted worker should be removed and added again so it warms up
+    /// like a new one.
+    pub fn add_worker(&mut self, worker: W) {
+        self.add_worker_at(worker, Instant::now());
+    }
+
+    fn add_worker_at(&mut self, worker: W, added_at: Instant) {
+        if self.workers.contains(&worker) {
+            return;
+        }
+        self.ring.add(worker.clone());
+        self.workers.push(worker);
+        self.added_at.push(added_at);
+    }
+
+    pub fn remove_worker(&mut self, worker: &W) {
+        if let Some(index) = self.workers.iter().position(|w| w == worker) {
+            self.workers.remove(index);
+            self.added_at.remove(index);
+        }
+        self.ring.remove(worker);
+    }
+
+    pub fn workers(&self) -> &[W] {
+        &self.workers
+    }
+
+    /// Share of full traffic `worker` currently receives, from 0.0 to 1.0
+    pub fn weight(&self, worker: &W) -> Option<f64> {
+        let index = self.workers.iter().position(|w| w == worker)?;
+        Some(self.weight_at(index, Instant::now()))
+    }
+
+    fn weight_at(&self, index: usize, now: Instant) -> f64 {
+        match self.slow_start {
+            Some(window) => {
+                let age = now.saturating_duration_since(self.added_at[index]);
+                (age.as_secs_f64() / window.as_secs_f64()).min(1.0)
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Worker to handle an invoke of `function_name`
+    pub fn select(&self, function_name: &str, context: &RequestContext) -> Option<&W> {
+        self.select_at(function_name, context, Instant::now())
+    }
+
+    fn select_at(&self, function_name: &str, context: &RequestContext, now: Instant) -> Option<&W> {
+        if let Some(sticky) = &self.sticky {
+            if sticky.applies_to(function_name) {
+                if let Some(key) = sticky.key.extract(context) {
+                    return self.ring.get(key);
+                }
+            }
+        }
+
+        if self.workers.is_empty() {
+            return None;
+        }
+        let turn = self.next.fetch_add(1, Ordering::Relaxed);
+
+        let weights: Vec<f64> = (0..self.workers.len()).map(|i| self.weight_at(i, now)).collect();
+        let total: f64 = weights.iter().sum();
+        // Plain round-robin when nobody is warming up, or when everybody is
+        // (a fresh pool ramps together, so shares are equal anyway)
+        if weights.iter().all(|&w| w >= 1.0) || total <= 0.0 {
+            return self.workers.get(turn % self.workers.len());
+        }
+
+        let mut point = (turn as f64 * GOLDEN_RATIO_FRACTION).fract() * total;
+        for (worker, weight) in self.workers.iter().zip(&weights) {
+            if point < *weight {
+                return Some(worker);
+            }
+            point -= weight;
+        }
+        self.workers.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balancer(workers: u32, sticky: StickyConfig) -> WorkerBalancer<u32> {
+        let mut balancer = WorkerBalancer::new(Some(sticky));
+        for worker in 0..workers {
+            balancer.add_worker(worker);
+        }
+        balancer
+    }
+
+    fn session(id: &str) -> RequestContext {
+        RequestContext::builder().header("x-session", id).build()
+    }
+
+    #[test]
+    fn test_same_key_routes_to_same_worker() {
+        let sessions = balancer(4, StickyConfig::new(StickyKey::Header("X-Session".into())));
+
+        for id in ["alice", "bob", "carol"] {
+            let first = *sessions.select("cart.add", &session(id)).unwrap();
+            for _ in 0..50 {
+                assert_eq!(sessions.select("cart.add", &session(id)), Some(&first));
+            }
+        }
+
+        let by_ip = balancer(4, StickyConfig::new(StickyKey::ClientIp));
+        let client = RequestContext::builder().forwarded_for("10.0.0.7, 172.16.0.1").build();
+        let same_client = RequestContext::builder().forwarded_for("10.0.0.7").build();
+        assert_eq!(by_ip.select("f", &client), by_ip.select("f", &same_client));
+    }
+
+    #[test]
+    fn test_removing_worker_moves_only_its_keys() {
+        let mut balancer = balancer(4, StickyConfig::new(StickyKey::Header("x-session".into())));
+        let keys: Vec<String> = (0..2000).map(|i| format!("session-{}", i)).collect();
+        let before: Vec<u32> = keys
+            .iter()
+            .map(|k| *balancer.select("f", &session(k)).unwrap())
+            .collect();
+
+        balancer.remove_worker(&2);
+        let mut moved = 0;
+        for (key, &owner) in keys.iter().zip(&before) {
+            let now = *balancer.select("f", &session(key)).unwrap();
+            assert_ne!(now, 2);
+            if owner == 2 {
+                moved += 1;
+            } else {
+                assert_eq!(now, owner, "key {} moved off a surviving worker", key);
+            }
+        }
+
+        // Worker 2 owned roughly a quarter of the keys
+        assert!(moved > 300 && moved < 700, "worker 2 owned {} keys", moved);
+    }
+
+    #[test]
+    fn test_non_sticky_functions_round_robin() {
+        let sticky = StickyConfig::new(StickyKey::Header("x-session".into()))
+            .functions(vec!["cart.*".into()]);
+        let balancer = balancer(3, sticky);
+        let context = session("alice");
+
+        let picks: Vec<u32> = (0..6)
+            .map(|_| *balancer.select("search", &context).unwrap())
+            .collect();
+        assert_eq!(picks, vec![0, 1, 2, 0, 1, 2]);
+
+        // Sticky function without a key falls back to round-robin
+        let anonymous = RequestContext::default();
+        let a = *balancer.select("cart.add", &anonymous).unwrap();
+        let b = *balancer.select("cart.add", &anonymous).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_slow_start_ramps_new_worker() {
+        let window = Duration::from_secs(10);
+        let mut balancer = WorkerBalancer::new(None).with_slow_start(window);
+        let start = Instant::now();
+        for worker in 0..3u32 {
+            balancer.add_worker_at(worker, start);
+        }
+        let joined = start + window * 2;
+        balancer.add_worker_at(3, joined);
+
+        let context = RequestContext::default();
+        let share_at = |elapsed: Duration| {
+            let now = joined + elapsed;
+            let hits = (0..4000)
+                .filter(|_| balancer.select_at("f", &context, now) == Some(&3))
+                .count();
+            hits as f64 / 4000.0
+        };
+
+        let shares: Vec<f64> = [0, 1, 5, 10]
+            .iter()
+            .map(|&secs| share_at(Duration::from_secs(secs)))
+            .collect();
+
+        assert_eq!(shares[0], 0.0);
+        assert!(shares[1] > 0.01 && shares[1] < 0.06, "{:?}", shares);
+        assert!(shares[2] > 0.11 && shares[2] < 0.18, "{:?}", shares);
+        assert!((shares[3] - 0.25).abs() < 0.01, "{:?}", shares);
+        assert!(shares.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", shares);
+    }
+}
@@ -1,7 +1,14 @@
 pub mod protocol;
+pub mod admin;
 pub mod supervisor;
 pub mod router;
 pub mod reload;
 pub mod metrics;
+pub mod outbound;
+pub mod precision;
+pub mod upload;
+pub mod window;
+pub mod balancer;
+pub mod priority;
 
 pub use protocol::{Message, Role, ErrorKind};
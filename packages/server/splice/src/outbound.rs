@@ -0,0 +1,139 @@
+//! Bounded outbound message path
+//!
+//! Messages destined for a peer go through a bounded channel into a single
+//! writer task that owns the `Framed` sink. The writer awaits every flush, so
+//! when the peer reads slowly the socket fills, the writer stalls, the channel
+//! fills, and senders block in `send().await` instead of buffering without
+//! bound.
+
+use crate::protocol::{Message, SpliceCodec};
+use futures::sink::{Sink, SinkExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tokio_util::codec::Framed;
+use tracing::debug;
+
+/// Default number of messages queued for the writer
+pub const DEFAULT_OUTBOUND_BUFFER: usize = 256;
+
+/// Default bytes buffered in the codec before the sink applies backpressure
+pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 128 * 1024;
+
+/// Outbound buffering limits
+#[derive(Debug, Clone)]
+pub struct OutboundConfig {
+    /// Messages queued between senders and the writer task
+    pub buffer: usize,
+    /// Encoded bytes held in the write buffer before a flush is forced
+    pub max_buffered_bytes: usize,
+}
+
+impl Default for OutboundConfig {
+    fn default() -> Self {
+        Self {
+            buffer: DEFAULT_OUTBOUND_BUFFER,
+            max_buffered_bytes: DEFAULT_MAX_BUFFERED_BYTES,
+        }
+    }
+}
+
+impl OutboundConfig {
+    /// Create the bounded channel feeding the writer
+    pub fn channel(&self) -> (mpsc::Sender<Message>, mpsc::Receiver<Message>) {
+        mpsc::channel(self.buffer.max(1))
+    }
+
+    /// Wrap a stream in a `Framed` sink that honours `max_buffered_bytes`
+    /// and the codec's read buffer sizing
+    pub fn framed<T>(&self, io: T, codec: SpliceCodec) -> Framed<T, SpliceCodec>
+    where
+        T: AsyncRead + AsyncWrite,
+    {
+        let mut framed = codec.framed(io);
+        framed.set_backpressure_boundary(self.max_buffered_bytes);
+        framed
+    }
+}
+
+/// Drain `rx` into `sink` until the channel closes or the sink fails
+///
+/// Messages already queued are written as one batch, then flushed; the next
+/// batch is not taken until the flush completes.
+pub async fn write_loop<S>(mut rx: mpsc::Receiver<Message>, mut sink: S) -> Result<(), S::Error>
+where
+    S: Sink<Message> + Unpin,
+{
+    while let Some(msg) = rx.recv().await {
+        sink.feed(msg).await?;
+        while let Ok(msg) = rx.try_recv() {
+            sink.feed(msg).await?;
+        }
+        sink.flush().await?;
+    }
+
+    debug!("Outbound channel closed");
+    sink.close().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use futures::stream::StreamExt;
+    use std::time::Duration;
+
+    fn chunk(sequence: u64) -> Message {
+        Message::StreamChunk {
+            request_id: 1,
+            sequence,
+            data: Bytes::from(vec![0u8; 16 * 1024]),
+        }
+    }
+
+    #[test]
+    fn test_outbound_config_default() {
+        let config = OutboundConfig::default();
+        assert_eq!(config.buffer, DEFAULT_OUTBOUND_BUFFER);
+        assert_eq!(config.max_buffered_bytes, DEFAULT_MAX_BUFFERED_BYTES);
+    }
+
+    #[tokio::test]
+    async fn test_slow_reader_blocks_sender() {
+        let config = OutboundConfig {
+            buffer: 4,
+            max_buffered_bytes: 32 * 1024,
+        };
+        let (writer_io, reader_io) = tokio::io::duplex(64 * 1024);
+        let (tx, rx) = config.channel();
+        let writer = tokio::spawn(write_loop(rx, config.framed(writer_io, SpliceCodec::default())));
+
+        // Nobody reads yet: the sender must stall once socket, codec buffer
+        // and channel are full
+        let mut accepted = 0u64;
+        while let Ok(result) = tokio::time::timeout(Duration::from_millis(100), tx.send(chunk(accepted))).await {
+            result.unwrap();
+            accepted += 1;
+            assert!(accepted < 64, "sender never blocked");
+        }
+        // Socket (64 KiB) + codec buffer (32 KiB) + channel (4) of 16 KiB chunks
+        assert!(accepted <= 12, "accepted {} messages", accepted);
+
+        // Once the reader catches up everything is delivered in order
+        let mut reader = Framed::new(reader_io, SpliceCodec::default());
+        let sender = tokio::spawn(async move {
+            for sequence in accepted..accepted + 8 {
+                tx.send(chunk(sequence)).await.unwrap();
+            }
+        });
+
+        for expected in 0..accepted + 8 {
+            match reader.next().await {
+                Some(Ok(Message::StreamChunk { sequence, .. })) => assert_eq!(sequence, expected),
+                other => panic!("Expected StreamChunk, got {:?}", other),
+            }
+        }
+
+        sender.await.unwrap();
+        writer.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! JS-safe integer handling
+//!
+//! Hosts are usually JavaScript, where every number is an f64: integers
+//! beyond ±(2^53 - 1) silently lose precision. Params and results are
+//! MessagePack-encoded JSON values, so the router can inspect them and apply
+//! an [`IntegerPolicy`] before they reach the other side.
+
+use serde_json::{Number, Value};
+
+/// Largest integer JavaScript represents exactly (`Number.MAX_SAFE_INTEGER`)
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// What to do with integers outside the JS-safe range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegerPolicy {
+    /// Pass values through unchanged
+    #[default]
+    Allow,
+    /// Encode unsafe integers in results as decimal strings
+    Stringify,
+    /// Reject params (and fail results) containing unsafe integers
+    Reject,
+}
+
+impl std::str::FromStr for IntegerPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "string" | "stringify" => Ok(Self::Stringify),
+            "reject" => Ok(Self::Reject),
+            other => Err(format!("unknown integer policy '{}' (expected allow, string or reject)", other)),
+        }
+    }
+}
+
+/// Whether a number survives conversion to a JS number
+///
+/// Floats are already f64 and always count as safe.
+pub fn is_safe(number: &Number) -> bool {
+    if let Some(n) = number.as_u64() {
+        n <= MAX_SAFE_INTEGER
+    } else if let Some(n) = number.as_i64() {
+        n.unsigned_abs() <= MAX_SAFE_INTEGER
+    } else {
+        true
+    }
+}
+
+/// Path (e.g. `$.user.ids[2]`) of the first unsafe integer, if any
+pub fn find_unsafe(value: &Value) -> Option<String> {
+    fn walk(value: &Value, path: &mut String) -> bool {
+        match value {
+            Value::Number(n) => !is_safe(n),
+            Value::Array(items) => items.iter().enumerate().any(|(i, item)| {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                let found = walk(item, path);
+                if !found {
+                    path.truncate(len);
+                }
+                found
+            }),
+            Value::Object(map) => map.iter().any(|(key, item)| {
+                let len = path.len();
+                path.push('.');
+                path.push_str(key);
+                let found = walk(item, path);
+                if !found {
+                    path.truncate(len);
+                }
+                found
+            }),
+            _ => false,
+        }
+    }
+
+    let mut path = String::from("$");
+    walk(value, &mut path).then_some(path)
+}
+
+/// Replace unsafe integers with their decimal string, returning how many
+/// were replaced
+pub fn stringify_unsafe(value: &mut Value) -> usize {
+    match value {
+        Value::Number(n) if !is_safe(n) => {
+            *value = Value::String(n.to_string());
+            1
+        }
+        Value::Array(items) => items.iter_mut().map(stringify_unsafe).sum(),
+        Value::Object(map) => map.values_mut().map(stringify_unsafe).sum(),
+        _ => 0,
+    }
+}
+
+/// Check MessagePack params against the policy
+///
+/// Returns a description of the offending value when params must be
+/// rejected. Params that are not a JSON value are left to the worker.
+pub fn check_params(params: &[u8], policy: IntegerPolicy) -> Result<(), String> {
+    if policy != IntegerPolicy::Reject {
+        return Ok(());
+    }
+
+    match rmp_serde::from_slice::<Value>(params).ok().as_ref().and_then(find_unsafe) {
+        Some(path) => Err(format!(
+            "Integer at {} is outside the JS-safe range (±{})",
+            path, MAX_SAFE_INTEGER
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Apply the policy to a MessagePack result
+///
+/// Results that are not a JSON value, or contain no unsafe integers, are
+/// returned untouched.
+pub fn apply_to_result(result: bytes::Bytes, policy: IntegerPolicy) -> Result<bytes::Bytes, String> {
+    if policy == IntegerPolicy::Allow {
+        return Ok(result);
+    }
+
+    let mut value: Value = match rmp_serde::from_slice(&result) {
+        Ok(value) => value,
+        Err(_) => return Ok(result),
+    };
+
+    match policy {
+        IntegerPolicy::Reject => match find_unsafe(&value) {
+            Some(path) => Err(format!(
+                "Result integer at {} is outside the JS-safe range (±{})",
+                path, MAX_SAFE_INTEGER
+            )),
+            None => Ok(result),
+        },
+        _ => {
+            if stringify_unsafe(&mut value) == 0 {
+                return Ok(result);
+            }
+            rmp_serde::to_vec(&value)
+                .map(bytes::Bytes::from)
+                .map_err(|e| format!("Failed to re-encode result: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(value: &Value) -> bytes::Bytes {
+        bytes::Bytes::from(rmp_serde::to_vec(value).unwrap())
+    }
+
+    #[test]
+    fn test_safe_range_boundaries() {
+        assert!(is_safe(&Number::from(MAX_SAFE_INTEGER)));
+        assert!(!is_safe(&Number::from(MAX_SAFE_INTEGER + 1)));
+        assert!(is_safe(&Number::from(-(MAX_SAFE_INTEGER as i64))));
+        assert!(!is_safe(&Number::from(-(MAX_SAFE_INTEGER as i64) - 1)));
+        assert!(is_safe(&Number::from_f64(1e300).unwrap()));
+    }
+
+    #[test]
+    fn test_large_u64_survives_round_trip_as_string() {
+        let id = u64::MAX - 7;
+        let result = encode(&json!({ "user": { "id": id, "age": 42 } }));
+
+        let converted = apply_to_result(result, IntegerPolicy::Stringify).unwrap();
+        let value: Value = rmp_serde::from_slice(&converted).unwrap();
+
+        // What a JS host sees after JSON round-tripping
+        let through_json: Value = serde_json::from_str(&value.to_string()).unwrap();
+        assert_eq!(through_json["user"]["id"], json!(id.to_string()));
+        assert_eq!(through_json["user"]["id"].as_str().unwrap().parse::<u64>().unwrap(), id);
+        assert_eq!(through_json["user"]["age"], json!(42));
+    }
+
+    #[test]
+    fn test_reject_mode_flags_unsafe_params() {
+        let params = encode(&json!({ "ids": [1, 2, 9007199254740993u64] }));
+
+        let err = check_params(&params, IntegerPolicy::Reject).unwrap_err();
+        assert!(err.contains("$.ids[2]"), "{}", err);
+        assert!(check_params(&params, IntegerPolicy::Stringify).is_ok());
+        assert!(check_params(&encode(&json!({ "id": 9007199254740991u64 })), IntegerPolicy::Reject).is_ok());
+    }
+
+    #[test]
+    fn test_safe_results_untouched() {
+        let result = encode(&json!({ "n": 1 }));
+        let converted = apply_to_result(result.clone(), IntegerPolicy::Stringify).unwrap();
+        assert_eq!(converted, result);
+    }
+
+    #[test]
+    fn test_policy_from_str() {
+        assert_eq!("string".parse::<IntegerPolicy>().unwrap(), IntegerPolicy::Stringify);
+        assert_eq!("reject".parse::<IntegerPolicy>().unwrap(), IntegerPolicy::Reject);
+        assert!("bogus".parse::<IntegerPolicy>().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Priority-aware admission
+//!
+//! [`AdmissionQueue`] hands out up to `capacity` concurrent slots. Once they
+//! are taken, invokes wait in a queue ordered by [`Message::Invoke`]
+//! priority (higher first, FIFO within a priority) and each released slot
+//! goes straight to the best waiter, so interactive work is not starved
+//! behind queued batch jobs.
+//!
+//! When the queue itself is full, the lowest-priority waiter is shed to make
+//! room for a more important arrival; an arrival that outranks nobody is
+//! rejected instead.
+//!
+//! [`Message::Invoke`]: crate::protocol::Message::Invoke
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Admission was refused: every slot is busy and the queue has no room
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+/// Waiters keyed so the last entry is the highest priority, oldest request
+type WaiterKey = (u8, Reverse<u64>);
+
+#[derive(Debug, Default)]
+struct QueueState {
+    active: usize,
+    waiters: BTreeMap<WaiterKey, oneshot::Sender<AdmissionPermit>>,
+    next_seq: u64,
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    max_queued: usize,
+    state: Mutex<QueueState>,
+}
+
+impl Shared {
+    /// Pass a released slot to the best live waiter, or free it
+    fn release(self: &Arc<Self>) {
+        let next = {
+            let mut state = self.state.lock().unwrap();
+            match state.waiters.pop_last() {
+                Some((_, tx)) => Some(tx),
+                None => {
+                    state.active = state.active.saturating_sub(1);
+                    None
+                }
+            }
+        };
+        // A waiter that gave up returns the permit, whose drop releases the
+        // slot again for the next one
+        if let Some(tx) = next {
+            let _ = tx.send(AdmissionPermit { shared: Arc::clone(self) });
+        }
+    }
+}
+
+/// Concurrency slots handed out in priority order
+#[derive(Debug, Clone)]
+pub struct AdmissionQueue {
+    shared: Arc<Shared>,
+}
+
+/// A held slot, released when dropped
+#[derive(Debug)]
+pub struct AdmissionPermit {
+    shared: Arc<Shared>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.shared.release();
+    }
+}
+
+impl AdmissionQueue {
+    /// `capacity` concurrent slots with up to `max_queued` waiters
+    ///
+    /// With `max_queued` of zero nothing waits: admission fails as soon as
+    /// every slot is taken.
+    pub fn new(capacity: usize, max_queued: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                capacity,
+                max_queued,
+                state: Mutex::new(QueueState::default()),
+            }),
+        }
+    }
+
+    /// Slots currently held
+    pub fn active(&self) -> usize {
+        self.shared.state.lock().unwrap().active
+    }
+
+    /// Invokes waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().waiters.len()
+    }
+
+    /// Wait for a slot at `priority`
+    ///
+    /// Fails with [`QueueFull`] when the queue has no room for this priority,
+    /// or when the request is later shed by a higher-priority arrival.
+    pub async fn acquire(&self, priority: u8) -> Result<AdmissionPermit, QueueFull> {
+        let rx = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.waiters.retain(|_, tx| !tx.is_closed());
+
+            if state.active < self.shared.capacity && state.waiters.is_empty() {
+                state.active += 1;
+                return Ok(AdmissionPermit { shared: Arc::clone(&self.shared) });
+            }
+
+            if state.waiters.len() >= self.shared.max_queued {
+                // Dropping the sender fails the shed waiter
+                match state.waiters.first_key_value() {
+                    Some(((lowest, _), _)) if *lowest < priority => {
+                        state.waiters.pop_first();
+                    }
+                    _ => return Err(QueueFull),
+                }
+            }
+
+            let seq = state.next_seq;
+            state.next_seq = state.next_seq.wrapping_add(1);
+            let (tx, rx) = oneshot::channel();
+            state.waiters.insert((priority, Reverse(seq)), tx);
+            rx
+        };
+
+        rx.await.map_err(|_| QueueFull)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_admitted_before_queued_low() {
+        let queue = AdmissionQueue::new(1, 8);
+        let held = queue.acquire(100).await.unwrap();
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        for (name, priority) in [("batch-1", 10), ("batch-2", 10), ("interactive", 200)] {
+            let queue = queue.clone();
+            let order_tx = order_tx.clone();
+            tokio::spawn(async move {
+                let _permit = queue.acquire(priority).await.unwrap();
+                order_tx.send(name).unwrap();
+                settle().await;
+            });
+            settle().await;
+        }
+        assert_eq!(queue.queued(), 3);
+
+        drop(held);
+        let mut order = Vec::new();
+        for _ in 0..3 {
+            order.push(order_rx.recv().await.unwrap());
+        }
+        assert_eq!(order, ["interactive", "batch-1", "batch-2"]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(queue.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_sheds_lowest_priority() {
+        let queue = AdmissionQueue::new(1, 1);
+        let _held = queue.acquire(100).await.unwrap();
+
+        let low = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(10).await.map(|_| ()) }
+        });
+        settle().await;
+
+        // An equal or lower priority cannot displace the queued waiter
+        assert_eq!(queue.acquire(10).await.unwrap_err(), QueueFull);
+
+        let high = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(200).await.map(|_| ()) }
+        });
+        assert_eq!(low.await.unwrap(), Err(QueueFull));
+        assert_eq!(queue.queued(), 1);
+        high.abort();
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter_does_not_leak_slot() {
+        let queue = AdmissionQueue::new(1, 4);
+        let held = queue.acquire(100).await.unwrap();
+
+        let abandoned = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(100).await.map(|_| ()) }
+        });
+        settle().await;
+        abandoned.abort();
+        let _ = abandoned.await;
+
+        drop(held);
+        assert_eq!(queue.active(), 0);
+        let _permit = queue.acquire(100).await.unwrap();
+        assert_eq!(queue.active(), 1);
+    }
+}
@@ -1,8 +1,10 @@
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
-use std::io;
+use std::io::{self, Read, Write};
+use std::time::Duration;
 use thiserror::Error;
-use tokio_util::codec::{Decoder, Encoder};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::{Decoder, Encoder, Framed};
 
 /// Protocol version 1.0
 pub const PROTOCOL_VERSION: u32 = 0x00010000;
@@ -10,10 +12,34 @@ pub const PROTOCOL_VERSION: u32 = 0x00010000;
 /// Default maximum frame size (100MB)
 pub const DEFAULT_MAX_FRAME_SIZE: u32 = 100 * 1024 * 1024;
 
+/// Default initial read buffer capacity (matches `Framed`'s own default)
+pub const DEFAULT_READ_CAPACITY: usize = 8 * 1024;
+
+/// Payloads at least this large are compressed when compression is enabled
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 4 * 1024;
+
+/// Type-byte bit marking a compressed payload once compression is
+/// negotiated; message types are then limited to the low 7 bits
+pub const FRAME_FLAG_COMPRESSED: u8 = 0x80;
+
 /// Capability flags
 pub const CAP_STREAMING: u32 = 1 << 0;
 pub const CAP_CANCELLATION: u32 = 1 << 1;
 pub const CAP_COMPRESSION: u32 = 1 << 2;
+pub const CAP_FORMAT_CBOR: u32 = 1 << 3;
+/// Invokes are admitted by `priority` (see [`crate::priority`])
+pub const CAP_PRIORITY: u32 = 1 << 4;
+/// Worker accepts `InvokeBatch`
+pub const CAP_BATCH: u32 = 1 << 5;
+
+/// `Invoke` priorities: higher values are admitted first and shed last
+pub const PRIORITY_LOW: u8 = 64;
+pub const PRIORITY_NORMAL: u8 = 128;
+pub const PRIORITY_HIGH: u8 = 192;
+
+fn default_priority() -> u8 {
+    PRIORITY_NORMAL
+}
 
 // Message type codes
 pub const MSG_HANDSHAKE: u8 = 0x01;
@@ -25,6 +51,8 @@ pub const MSG_LIST_EXPORTS_RESULT: u8 = 0x11;
 pub const MSG_INVOKE: u8 = 0x20;
 pub const MSG_INVOKE_RESULT: u8 = 0x21;
 pub const MSG_INVOKE_ERROR: u8 = 0x22;
+pub const MSG_INVOKE_BATCH: u8 = 0x23;
+pub const MSG_INVOKE_BATCH_RESULT: u8 = 0x24;
 pub const MSG_STREAM_START: u8 = 0x30;
 pub const MSG_STREAM_CHUNK: u8 = 0x31;
 pub const MSG_STREAM_END: u8 = 0x32;
@@ -35,6 +63,8 @@ pub const MSG_CANCEL_ACK: u8 = 0x41;
 pub const MSG_LOG_EVENT: u8 = 0x50;
 pub const MSG_HEALTH_CHECK: u8 = 0x60;
 pub const MSG_HEALTH_STATUS: u8 = 0x61;
+pub const MSG_ADMIN_COMMAND: u8 = 0x70;
+pub const MSG_ADMIN_RESULT: u8 = 0x71;
 
 #[derive(Debug, Error)]
 pub enum ProtocolError {
@@ -70,6 +100,29 @@ pub enum ErrorKind {
     Cancelled = 4,
 }
 
+impl ErrorKind {
+    /// Lowercase name used in JSON error payloads
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::User => "user",
+            ErrorKind::System => "system",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Cancelled => "cancelled",
+        }
+    }
+
+    /// Inverse of [`ErrorKind::as_str`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "user" => Some(ErrorKind::User),
+            "system" => Some(ErrorKind::System),
+            "timeout" => Some(ErrorKind::Timeout),
+            "cancelled" => Some(ErrorKind::Cancelled),
+            _ => None,
+        }
+    }
+}
+
 // Error codes
 pub const ERR_INVALID_REQUEST: u16 = 1000;
 pub const ERR_INVALID_PARAMS: u16 = 1001;
@@ -83,6 +136,23 @@ pub const ERR_PANIC: u16 = 2003;
 pub const ERR_INTERNAL_ERROR: u16 = 3000;
 pub const ERR_UNAVAILABLE: u16 = 3001;
 pub const ERR_OVERLOADED: u16 = 3002;
+/// The worker is being reloaded; retry after the hint in `details`
+/// (see [`retry_after_details`])
+pub const ERR_RELOADING: u16 = 3003;
+
+/// `InvokeError::details` telling the caller when to retry
+///
+/// Encoded as JSON, `{"retryAfterMs": 1500}`.
+pub fn retry_after_details(retry_after: Duration) -> Bytes {
+    let retry_after_ms = retry_after.as_millis().min(u64::MAX as u128) as u64;
+    Bytes::from(serde_json::json!({ "retryAfterMs": retry_after_ms }).to_string())
+}
+
+/// Retry hint from `InvokeError::details`, if it carries one
+pub fn parse_retry_after(details: &[u8]) -> Option<Duration> {
+    let value: serde_json::Value = serde_json::from_slice(details).ok()?;
+    value.get("retryAfterMs")?.as_u64().map(Duration::from_millis)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthContext {
@@ -90,7 +160,7 @@ pub struct AuthContext {
     pub roles: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RequestContext {
     pub trace_id: u64,
     pub span_id: u64,
@@ -98,6 +168,143 @@ pub struct RequestContext {
     pub auth: Option<AuthContext>,
 }
 
+/// Well-known `RequestContext` header names (lowercase)
+pub const HEADER_REQUEST_ID: &str = "x-request-id";
+pub const HEADER_FORWARDED_FOR: &str = "x-forwarded-for";
+pub const HEADER_TRACEPARENT: &str = "traceparent";
+pub const HEADER_TRACESTATE: &str = "tracestate";
+
+impl RequestContext {
+    /// Start building a context
+    pub fn builder() -> RequestContextBuilder {
+        RequestContextBuilder::default()
+    }
+
+    /// Header value by name (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set a header, replacing every existing value regardless of case
+    ///
+    /// The name is stored lowercase.
+    pub fn set_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into().to_ascii_lowercase();
+        self.headers.retain(|(k, _)| !k.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+    }
+
+    /// Remove a header (case-insensitive), returning its last value
+    pub fn remove_header(&mut self, name: &str) -> Option<String> {
+        let mut removed = None;
+        self.headers.retain(|(k, v)| {
+            if k.eq_ignore_ascii_case(name) {
+                removed = Some(v.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// `x-request-id` header
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(HEADER_REQUEST_ID)
+    }
+
+    /// `x-forwarded-for` header
+    pub fn forwarded_for(&self) -> Option<&str> {
+        self.header(HEADER_FORWARDED_FOR)
+    }
+
+    /// W3C `traceparent` header
+    pub fn traceparent(&self) -> Option<&str> {
+        self.header(HEADER_TRACEPARENT)
+    }
+
+    /// W3C `tracestate` header
+    pub fn tracestate(&self) -> Option<&str> {
+        self.header(HEADER_TRACESTATE)
+    }
+}
+
+/// Builder for [`RequestContext`]
+///
+/// Header setters overwrite any earlier value for the same name, compared
+/// case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContextBuilder {
+    context: RequestContext,
+}
+
+impl RequestContextBuilder {
+    /// Numeric trace ID (low 64 bits of the W3C trace ID)
+    pub fn trace_id(mut self, trace_id: u64) -> Self {
+        self.context.trace_id = trace_id;
+        self
+    }
+
+    /// Numeric ID of the current span
+    pub fn span_id(mut self, span_id: u64) -> Self {
+        self.context.span_id = span_id;
+        self
+    }
+
+    /// Authenticated caller
+    pub fn auth(mut self, auth: AuthContext) -> Self {
+        self.context.auth = Some(auth);
+        self
+    }
+
+    /// Set a header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context.set_header(name, value);
+        self
+    }
+
+    /// Set several headers, later entries winning
+    pub fn headers<I, K, V>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (name, value) in headers {
+            self.context.set_header(name, value);
+        }
+        self
+    }
+
+    /// Set `x-request-id`
+    pub fn request_id(self, request_id: impl Into<String>) -> Self {
+        self.header(HEADER_REQUEST_ID, request_id)
+    }
+
+    /// Set `x-forwarded-for`
+    pub fn forwarded_for(self, forwarded_for: impl Into<String>) -> Self {
+        self.header(HEADER_FORWARDED_FOR, forwarded_for)
+    }
+
+    /// Set the W3C `traceparent` header
+    pub fn traceparent(self, traceparent: impl Into<String>) -> Self {
+        self.header(HEADER_TRACEPARENT, traceparent)
+    }
+
+    /// Set the W3C `tracestate` header
+    pub fn tracestate(self, tracestate: impl Into<String>) -> Self {
+        self.header(HEADER_TRACESTATE, tracestate)
+    }
+
+    /// Finish building
+    pub fn build(self) -> RequestContext {
+        self.context
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportMetadata {
     pub name: String,
@@ -136,12 +343,20 @@ pub enum Message {
     Invoke {
         request_id: u64,
         function_name: String,
+        #[serde(deserialize_with = "zero_copy::bytes")]
         params: Bytes,
         deadline_ms: u32,
         context: RequestContext,
+        /// Admission priority; peers that predate it send none and get
+        /// [`PRIORITY_NORMAL`]. Only encoded once `CAP_PRIORITY` is
+        /// negotiated (see [`SpliceCodec::set_priority`]), since older peers
+        /// reject the extra field.
+        #[serde(default = "default_priority")]
+        priority: u8,
     },
     InvokeResult {
         request_id: u64,
+        #[serde(deserialize_with = "zero_copy::bytes")]
         result: Bytes,
         duration_us: u64,
     },
@@ -150,8 +365,20 @@ pub enum Message {
         code: u16,
         kind: ErrorKind,
         message: String,
+        #[serde(deserialize_with = "zero_copy::option_bytes")]
         details: Option<Bytes>,
     },
+    /// Several `(function_name, params)` calls in one frame, answered by a
+    /// single `InvokeBatchResult`; `Cancel` with `request_id` cancels them all
+    InvokeBatch {
+        request_id: u64,
+        calls: Vec<(String, Bytes)>,
+    },
+    /// One entry per call, in call order: the result, or `(code, message)`
+    InvokeBatchResult {
+        request_id: u64,
+        results: Vec<Result<Bytes, (u16, String)>>,
+    },
 
     // Streaming
     StreamStart {
@@ -161,6 +388,7 @@ pub enum Message {
     StreamChunk {
         request_id: u64,
         sequence: u64,
+        #[serde(deserialize_with = "zero_copy::bytes")]
         data: Bytes,
     },
     StreamEnd {
@@ -198,9 +426,73 @@ pub enum Message {
         active_requests: u32,
         total_requests: u64,
     },
+
+    // Operator introspection (see `crate::admin`)
+    //
+    // `request_id` is chosen by the sender and echoed in the reply, so
+    // concurrent commands cannot pick up each other's results.
+    AdminCommand {
+        request_id: u64,
+        command: String,
+        #[serde(deserialize_with = "zero_copy::bytes")]
+        args: Bytes,
+    },
+    AdminResult {
+        request_id: u64,
+        #[serde(deserialize_with = "zero_copy::bytes")]
+        data: Bytes,
+    },
+
+    /// Well-framed message of a type this version does not know, e.g. from
+    /// a newer peer. Receivers should log and ignore it.
+    #[serde(skip)]
+    Unknown {
+        msg_type: u8,
+        payload: Bytes,
+    },
+
+    /// `Invoke` or `InvokeBatch` frame that failed to decode, passed on by a
+    /// codec skipping malformed frames when the request id could still be
+    /// read. Receivers should answer it with `InvokeError`.
+    #[serde(skip)]
+    Malformed {
+        msg_type: u8,
+        request_id: u64,
+        error: String,
+    },
 }
 
 impl Message {
+    /// Whether `msg_type` is a message type of this protocol version
+    pub fn is_known_type(msg_type: u8) -> bool {
+        matches!(
+            msg_type,
+            MSG_HANDSHAKE
+                | MSG_HANDSHAKE_ACK
+                | MSG_SHUTDOWN
+                | MSG_SHUTDOWN_ACK
+                | MSG_LIST_EXPORTS
+                | MSG_LIST_EXPORTS_RESULT
+                | MSG_INVOKE
+                | MSG_INVOKE_RESULT
+                | MSG_INVOKE_ERROR
+                | MSG_INVOKE_BATCH
+                | MSG_INVOKE_BATCH_RESULT
+                | MSG_STREAM_START
+                | MSG_STREAM_CHUNK
+                | MSG_STREAM_END
+                | MSG_STREAM_ERROR
+                | MSG_STREAM_ACK
+                | MSG_CANCEL
+                | MSG_CANCEL_ACK
+                | MSG_LOG_EVENT
+                | MSG_HEALTH_CHECK
+                | MSG_HEALTH_STATUS
+                | MSG_ADMIN_COMMAND
+                | MSG_ADMIN_RESULT
+        )
+    }
+
     pub fn message_type(&self) -> u8 {
         match self {
             Message::Handshake { .. } => MSG_HANDSHAKE,
@@ -212,6 +504,8 @@ impl Message {
             Message::Invoke { .. } => MSG_INVOKE,
             Message::InvokeResult { .. } => MSG_INVOKE_RESULT,
             Message::InvokeError { .. } => MSG_INVOKE_ERROR,
+            Message::InvokeBatch { .. } => MSG_INVOKE_BATCH,
+            Message::InvokeBatchResult { .. } => MSG_INVOKE_BATCH_RESULT,
             Message::StreamStart { .. } => MSG_STREAM_START,
             Message::StreamChunk { .. } => MSG_STREAM_CHUNK,
             Message::StreamEnd { .. } => MSG_STREAM_END,
@@ -222,6 +516,158 @@ impl Message {
             Message::LogEvent { .. } => MSG_LOG_EVENT,
             Message::HealthCheck => MSG_HEALTH_CHECK,
             Message::HealthStatus { .. } => MSG_HEALTH_STATUS,
+            Message::AdminCommand { .. } => MSG_ADMIN_COMMAND,
+            Message::AdminResult { .. } => MSG_ADMIN_RESULT,
+            Message::Unknown { msg_type, .. } | Message::Malformed { msg_type, .. } => *msg_type,
+        }
+    }
+}
+
+/// Serialization format of frame payloads
+///
+/// Handshake frames are always msgpack so peers can agree on a format before
+/// switching to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PayloadFormat {
+    #[default]
+    MsgPack,
+    Cbor,
+}
+
+impl PayloadFormat {
+    /// Capability bit advertising support for this format
+    pub fn capability(self) -> u32 {
+        match self {
+            PayloadFormat::MsgPack => 0,
+            PayloadFormat::Cbor => CAP_FORMAT_CBOR,
+        }
+    }
+
+    /// Format to use once both sides have exchanged capabilities
+    ///
+    /// Falls back to msgpack unless both peers advertise an alternative.
+    pub fn negotiate(local: u32, remote: u32) -> Self {
+        if local & remote & CAP_FORMAT_CBOR != 0 {
+            PayloadFormat::Cbor
+        } else {
+            PayloadFormat::MsgPack
+        }
+    }
+
+    fn serialize<T: Serialize>(self, item: &T) -> Result<Vec<u8>, ProtocolError> {
+        match self {
+            PayloadFormat::MsgPack => rmp_serde::to_vec(item)
+                .map_err(|e| ProtocolError::Serialization(e.to_string())),
+            PayloadFormat::Cbor => {
+                let mut payload = Vec::new();
+                ciborium::into_writer(item, &mut payload)
+                    .map_err(|e| ProtocolError::Serialization(e.to_string()))?;
+                Ok(payload)
+            }
+        }
+    }
+
+    /// Leading `request_id` of a request message, read without decoding the
+    /// rest of the payload
+    fn recover_request_id(self, payload: &Bytes) -> Option<u64> {
+        let head = match self {
+            PayloadFormat::MsgPack => rmp_serde::from_slice::<request_head::RequestHead>(payload).ok(),
+            PayloadFormat::Cbor => ciborium::from_reader::<request_head::RequestHead, _>(payload.as_ref()).ok(),
+        };
+        head.map(|head| head.0)
+    }
+
+    fn deserialize(self, payload: &Bytes) -> Result<Message, ProtocolError> {
+        match self {
+            // Binary fields become slices of `payload`
+            PayloadFormat::MsgPack => zero_copy::with_frame(payload, || rmp_serde::from_slice(payload))
+                .map_err(|e| ProtocolError::Serialization(e.to_string())),
+            PayloadFormat::Cbor => ciborium::from_reader(payload.as_ref())
+                .map_err(|e| ProtocolError::Serialization(e.to_string())),
+        }
+    }
+}
+
+/// Compression of large frame payloads
+///
+/// Peers advertise `CAP_COMPRESSION` when they have an algorithm configured,
+/// and compress only once both have. A compressed payload starts with the
+/// algorithm's id byte, so each side may use its own algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn id(self) -> u8 {
+        match self {
+            Compression::Gzip => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    /// Compression to use once both sides have exchanged capabilities
+    ///
+    /// `preferred` applies only when both peers advertise `CAP_COMPRESSION`.
+    pub fn negotiate(preferred: Option<Self>, local: u32, remote: u32) -> Option<Self> {
+        preferred.filter(|_| local & remote & CAP_COMPRESSION != 0)
+    }
+
+    fn compress(self, payload: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        let mut compressed = vec![self.id()];
+        match self {
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(compressed, flate2::Compression::fast());
+                encoder.write_all(payload)?;
+                compressed = encoder.finish()?;
+            }
+            Compression::Zstd => zstd::stream::copy_encode(payload, &mut compressed, 0)?,
+        }
+        Ok(compressed)
+    }
+
+    /// Decompress a flagged payload, refusing output larger than `limit`
+    fn decompress(payload: &[u8], limit: usize) -> Result<Vec<u8>, ProtocolError> {
+        let (&id, data) = payload
+            .split_first()
+            .ok_or_else(|| ProtocolError::Serialization("empty compressed payload".to_string()))?;
+        let reader: Box<dyn Read + '_> = match id {
+            1 => Box::new(flate2::read::GzDecoder::new(data)),
+            2 => Box::new(zstd::stream::read::Decoder::new(data)?),
+            other => {
+                return Err(ProtocolError::Serialization(format!("unknown compression id {}", other)))
+            }
+        };
+        let mut decompressed = Vec::new();
+        reader.take(limit as u64 + 1).read_to_end(&mut decompressed)?;
+        if decompressed.len() > limit {
+            return Err(ProtocolError::FrameTooLarge(decompressed.len()));
+        }
+        Ok(decompressed)
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(format!("unknown compression '{}' (expected gzip or zstd)", other)),
+        }
+    }
+}
+
+impl std::str::FromStr for PayloadFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "msgpack" => Ok(PayloadFormat::MsgPack),
+            "cbor" => Ok(PayloadFormat::Cbor),
+            other => Err(format!("unknown payload format '{}' (expected msgpack or cbor)", other)),
         }
     }
 }
@@ -230,16 +676,165 @@ impl Message {
 ///
 /// Frame format:
 /// ┌──────────────┬──────────────┬─────────────────────────┐
-/// │ Length (4B)  │ Type (1B)    │ Payload (msgpack)       │
+/// │ Length (4B)  │ Type (1B)    │ Payload (msgpack/CBOR)  │
 /// │ big-endian   │              │                         │
 /// └──────────────┴──────────────┴─────────────────────────┘
+///
+/// With compression enabled, the high bit of the type byte
+/// ([`FRAME_FLAG_COMPRESSED`]) marks a compressed payload, which is
+/// decompressed transparently on decode. Without it the type byte is read
+/// as-is, so unknown types from newer peers still pass through.
+///
+/// With [`with_skip_malformed`](Self::with_skip_malformed), a complete frame
+/// whose payload fails to decompress or deserialize is dropped and decoding
+/// resumes at the next frame, since the length prefix still locates it. A
+/// dropped request whose id can still be read comes through as
+/// [`Message::Malformed`] so the caller isn't left waiting for a reply.
+///
+/// `Invoke::priority` is only encoded after [`set_priority`](Self::set_priority)
+/// confirms the peer negotiated `CAP_PRIORITY`; until then invokes are sent in
+/// the pre-priority layout and the peer admits them at [`PRIORITY_NORMAL`].
 pub struct SpliceCodec {
     max_frame_size: u32,
+    format: PayloadFormat,
+    initial_capacity: usize,
+    read_chunk_size: usize,
+    compression: Option<Compression>,
+    compression_threshold: usize,
+    priority: bool,
+    skip_malformed: bool,
+    skipped_frames: u64,
 }
 
 impl SpliceCodec {
     pub fn new(max_frame_size: u32) -> Self {
-        Self { max_frame_size }
+        Self::with_format(max_frame_size, PayloadFormat::default())
+    }
+
+    pub fn with_format(max_frame_size: u32, format: PayloadFormat) -> Self {
+        Self {
+            max_frame_size,
+            format,
+            initial_capacity: DEFAULT_READ_CAPACITY,
+            read_chunk_size: 0,
+            compression: None,
+            compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            priority: false,
+            skip_malformed: false,
+            skipped_frames: 0,
+        }
+    }
+
+    /// Compress outgoing payloads of at least the compression threshold and
+    /// accept compressed frames
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Smallest payload worth compressing (default: 4KB)
+    pub fn with_compression_threshold(mut self, threshold: usize) -> Self {
+        self.compression_threshold = threshold;
+        self
+    }
+
+    /// Drop frames with undecodable payloads instead of failing the stream
+    pub fn with_skip_malformed(mut self, skip: bool) -> Self {
+        self.skip_malformed = skip;
+        self
+    }
+
+    /// Malformed frames dropped so far
+    pub fn skipped_frames(&self) -> u64 {
+        self.skipped_frames
+    }
+
+    /// Pre-size the read buffer of streams wrapped with [`framed`](Self::framed)
+    pub fn with_initial_capacity(mut self, capacity: usize) -> Self {
+        self.initial_capacity = capacity;
+        self
+    }
+
+    /// Grow the read buffer by at least `size` bytes whenever more data is
+    /// needed, so a run of large frames settles on one allocation instead of
+    /// growing frame by frame (0 = reserve only what the current frame needs)
+    pub fn with_read_chunk_size(mut self, size: usize) -> Self {
+        self.read_chunk_size = size;
+        self
+    }
+
+    pub fn initial_capacity(&self) -> usize {
+        self.initial_capacity
+    }
+
+    pub fn read_chunk_size(&self) -> usize {
+        self.read_chunk_size
+    }
+
+    /// Wrap `io` in a `Framed` whose read buffer starts at `initial_capacity`
+    pub fn framed<T>(self, io: T) -> Framed<T, Self>
+    where
+        T: AsyncRead + AsyncWrite,
+    {
+        let capacity = self.initial_capacity;
+        Framed::with_capacity(io, self, capacity)
+    }
+
+    pub fn format(&self) -> PayloadFormat {
+        self.format
+    }
+
+    /// Switch payload format, typically right after the handshake
+    pub fn set_format(&mut self, format: PayloadFormat) {
+        self.format = format;
+    }
+
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    /// Largest frame accepted in either direction
+    pub fn max_frame_size(&self) -> u32 {
+        self.max_frame_size
+    }
+
+    /// Adopt the smaller of our limit and the one the peer advertised in its
+    /// `Handshake`, returning the limit now in force
+    ///
+    /// A peer advertising 0 states no limit and leaves ours unchanged.
+    pub fn negotiate_max_frame_size(&mut self, peer_max: u32) -> u32 {
+        if peer_max > 0 {
+            self.max_frame_size = self.max_frame_size.min(peer_max);
+        }
+        self.max_frame_size
+    }
+
+    /// Enable or disable compression, typically right after the handshake
+    /// (see [`Compression::negotiate`])
+    pub fn set_compression(&mut self, compression: Option<Compression>) {
+        self.compression = compression;
+    }
+
+    /// Encode `Invoke::priority`, once both sides advertised `CAP_PRIORITY`
+    pub fn with_priority(mut self, enabled: bool) -> Self {
+        self.priority = enabled;
+        self
+    }
+
+    /// Encode `Invoke::priority` or not, typically right after the handshake
+    pub fn set_priority(&mut self, enabled: bool) {
+        self.priority = enabled;
+    }
+
+    pub fn priority(&self) -> bool {
+        self.priority
+    }
+
+    fn format_for(&self, msg_type: u8) -> PayloadFormat {
+        match msg_type {
+            MSG_HANDSHAKE | MSG_HANDSHAKE_ACK => PayloadFormat::MsgPack,
+            _ => self.format,
+        }
     }
 }
 
@@ -254,8 +849,37 @@ impl Decoder for SpliceCodec {
     type Error = ProtocolError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            match self.decode_frame(src)? {
+                Some(Err(e)) if self.skip_malformed => {
+                    self.skipped_frames += 1;
+                    tracing::warn!("Skipping malformed frame: {}", e);
+                }
+                Some(Ok(Message::Malformed { msg_type, request_id, error })) => {
+                    self.skipped_frames += 1;
+                    tracing::warn!("Skipping malformed frame of request {}: {}", request_id, error);
+                    return Ok(Some(Message::Malformed { msg_type, request_id, error }));
+                }
+                Some(result) => return result.map(Some),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Outcome of decoding one consumed frame's payload
+type PayloadResult = Result<Message, ProtocolError>;
+
+impl SpliceCodec {
+    /// Decode the next frame
+    ///
+    /// The outer error means the stream itself can't continue (oversized
+    /// frame); the inner one is a bad payload in a frame that has already
+    /// been consumed, so the next call starts at the following frame.
+    fn decode_frame(&mut self, src: &mut BytesMut) -> Result<Option<PayloadResult>, ProtocolError> {
         // Need at least 5 bytes for header (4 length + 1 type)
         if src.len() < 5 {
+            src.reserve(self.read_chunk_size);
             return Ok(None);
         }
 
@@ -271,22 +895,215 @@ impl Decoder for SpliceCodec {
 
         // Wait for complete frame
         if src.len() < 5 + length {
-            src.reserve(5 + length - src.len());
+            src.reserve((5 + length - src.len()).max(self.read_chunk_size));
             return Ok(None);
         }
 
         // Consume header
         src.advance(4);
-        let _msg_type = src.get_u8();
+        let type_byte = src.get_u8();
+        let msg_type = match self.compression {
+            Some(_) => type_byte & !FRAME_FLAG_COMPRESSED,
+            None => type_byte,
+        };
 
         // Consume payload
         let payload = src.split_to(length).freeze();
+        Ok(Some(self.decode_payload(type_byte, msg_type, payload)))
+    }
+
+    fn decode_payload(&self, type_byte: u8, msg_type: u8, mut payload: Bytes) -> Result<Message, ProtocolError> {
+        if self.compression.is_some() && type_byte & FRAME_FLAG_COMPRESSED != 0 {
+            payload = Bytes::from(Compression::decompress(&payload, self.max_frame_size as usize)?);
+        }
+
+        // Skip over types from newer protocol versions instead of failing
+        if !Message::is_known_type(msg_type) {
+            return Ok(Message::Unknown { msg_type, payload });
+        }
+
+        // Deserialize message, keeping the request id of a request that
+        // fails so it can still be answered
+        let format = self.format_for(msg_type);
+        match format.deserialize(&payload) {
+            Err(e) if self.skip_malformed && matches!(msg_type, MSG_INVOKE | MSG_INVOKE_BATCH) => {
+                match format.recover_request_id(&payload) {
+                    Some(request_id) => Ok(Message::Malformed { msg_type, request_id, error: e.to_string() }),
+                    None => Err(e),
+                }
+            }
+            result => result,
+        }
+    }
+}
+
+/// `Message::Invoke` as written to peers that haven't negotiated `CAP_PRIORITY`
+///
+/// Carries the same variant tag and fields as `Message::Invoke` minus
+/// `priority`, so it decodes as an `Invoke` on either side.
+#[derive(Serialize)]
+enum InvokeWithoutPriority<'a> {
+    Invoke {
+        request_id: u64,
+        function_name: &'a str,
+        params: &'a Bytes,
+        deadline_ms: u32,
+        context: &'a RequestContext,
+    },
+}
+
+/// Request id recovery from payloads that fail to deserialize
+mod request_head {
+    use serde::de::{self, Deserialize, Deserializer, EnumAccess, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor};
+    use std::fmt;
+
+    /// `request_id` of a request message, found even when a later part of
+    /// the payload is corrupt
+    pub struct RequestHead(pub u64);
+
+    impl<'de> Deserialize<'de> for RequestHead {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let mut request_id = None;
+            let result = deserializer.deserialize_enum("Message", &[], RequestIdVisitor(&mut request_id));
+            match request_id {
+                // Whatever follows the id doesn't matter
+                Some(request_id) => Ok(RequestHead(request_id)),
+                None => result.and_then(|()| Err(de::Error::missing_field("request_id"))),
+            }
+        }
+    }
+
+    struct RequestIdVisitor<'a>(&'a mut Option<u64>);
+
+    impl<'de> Visitor<'de> for RequestIdVisitor<'_> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a request message")
+        }
+
+        fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<(), A::Error> {
+            let (IgnoredAny, variant) = data.variant()?;
+            variant.struct_variant(&["request_id"], self)
+        }
+
+        // MessagePack writes struct fields as an array, `request_id` first
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            *self.0 = seq.next_element()?;
+            Ok(())
+        }
+
+        // CBOR writes them as a map
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            while let Some(key) = map.next_key::<String>()? {
+                if key == "request_id" {
+                    *self.0 = Some(map.next_value()?);
+                    return Ok(());
+                }
+                map.next_value::<IgnoredAny>()?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Zero-copy deserialization of `Bytes` fields
+///
+/// While a frame is being decoded it is registered here, and binary fields
+/// borrowed from it are returned as `Bytes` slices sharing the frame's
+/// allocation instead of being copied out. A decoded payload therefore keeps
+/// its whole frame alive. Outside `with_frame` (or for data not borrowed from
+/// the frame) the bytes are copied as usual.
+mod zero_copy {
+    use bytes::Bytes;
+    use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+    use std::cell::RefCell;
+    use std::fmt;
+
+    thread_local! {
+        static FRAME: RefCell<Option<Bytes>> = const { RefCell::new(None) };
+    }
+
+    /// Run `decode` with `frame` registered as the source buffer
+    pub fn with_frame<T>(frame: &Bytes, decode: impl FnOnce() -> T) -> T {
+        struct Reset(Option<Bytes>);
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                FRAME.with(|f| *f.borrow_mut() = previous);
+            }
+        }
+
+        let _reset = Reset(FRAME.with(|f| f.borrow_mut().replace(frame.clone())));
+        decode()
+    }
+
+    /// Slice `data` out of the current frame, or copy it if it lies elsewhere
+    fn share(data: &[u8]) -> Bytes {
+        FRAME.with(|f| match f.borrow().as_ref() {
+            Some(frame) if is_within(frame, data) => frame.slice_ref(data),
+            _ => Bytes::copy_from_slice(data),
+        })
+    }
+
+    fn is_within(frame: &[u8], data: &[u8]) -> bool {
+        let start = frame.as_ptr() as usize;
+        let ptr = data.as_ptr() as usize;
+        !data.is_empty() && ptr >= start && ptr + data.len() <= start + frame.len()
+    }
+
+    struct FrameBytes(Bytes);
+
+    impl<'de> Deserialize<'de> for FrameBytes {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_bytes(FrameBytesVisitor)
+        }
+    }
+
+    struct FrameBytesVisitor;
+
+    impl<'de> Visitor<'de> for FrameBytesVisitor {
+        type Value = FrameBytes;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a byte array")
+        }
+
+        fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+            Ok(FrameBytes(share(v)))
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(FrameBytes(Bytes::copy_from_slice(v)))
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(FrameBytes(Bytes::from(v)))
+        }
+
+        fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+            Ok(FrameBytes(share(v.as_bytes())))
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(FrameBytes(Bytes::copy_from_slice(v.as_bytes())))
+        }
 
-        // Deserialize message
-        let message = rmp_serde::from_slice(&payload)
-            .map_err(|e| ProtocolError::Serialization(e.to_string()))?;
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut data = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                data.push(byte);
+            }
+            Ok(FrameBytes(Bytes::from(data)))
+        }
+    }
+
+    pub fn bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        FrameBytes::deserialize(deserializer).map(|b| b.0)
+    }
 
-        Ok(Some(message))
+    pub fn option_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Bytes>, D::Error> {
+        Option::<FrameBytes>::deserialize(deserializer).map(|b| b.map(|b| b.0))
     }
 }
 
@@ -294,19 +1111,44 @@ impl Encoder<Message> for SpliceCodec {
     type Error = ProtocolError;
 
     fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        // Serialize payload
-        let payload = rmp_serde::to_vec(&item)
-            .map_err(|e| ProtocolError::Serialization(e.to_string()))?;
+        // Serialize payload; unknown messages are forwarded verbatim
+        let format = self.format_for(item.message_type());
+        let mut payload = match &item {
+            Message::Unknown { payload, .. } => payload.to_vec(),
+            Message::Invoke { request_id, function_name, params, deadline_ms, context, .. } if !self.priority => {
+                format.serialize(&InvokeWithoutPriority::Invoke {
+                    request_id: *request_id,
+                    function_name,
+                    params,
+                    deadline_ms: *deadline_ms,
+                    context,
+                })?
+            }
+            _ => format.serialize(&item)?,
+        };
 
         // Check frame size
         if payload.len() > self.max_frame_size as usize {
             return Err(ProtocolError::FrameTooLarge(payload.len()));
         }
 
+        // Compress large payloads, keeping the original if it doesn't shrink
+        let mut type_byte = item.message_type();
+        let handshake = matches!(type_byte, MSG_HANDSHAKE | MSG_HANDSHAKE_ACK);
+        if let (Some(compression), false) = (self.compression, handshake) {
+            if payload.len() >= self.compression_threshold {
+                let compressed = compression.compress(&payload)?;
+                if compressed.len() < payload.len() {
+                    payload = compressed;
+                    type_byte |= FRAME_FLAG_COMPRESSED;
+                }
+            }
+        }
+
         // Write frame
         dst.reserve(5 + payload.len());
         dst.put_u32(payload.len() as u32);
-        dst.put_u8(item.message_type());
+        dst.put_u8(type_byte);
         dst.put_slice(&payload);
 
         Ok(())
@@ -357,7 +1199,11 @@ mod tests {
         }
 
         pub fn roundtrip(msg: Message) -> Message {
-            let mut codec = SpliceCodec::default();
+            roundtrip_with(PayloadFormat::MsgPack, msg)
+        }
+
+        pub fn roundtrip_with(format: PayloadFormat, msg: Message) -> Message {
+            let mut codec = SpliceCodec::with_format(DEFAULT_MAX_FRAME_SIZE, format);
             let mut buf = BytesMut::new();
             codec.encode(msg, &mut buf).unwrap();
             codec.decode(&mut buf).unwrap().unwrap()
@@ -387,6 +1233,7 @@ mod tests {
                     params: Bytes::new(),
                     deadline_ms: 1000,
                     context: create_minimal_context(),
+                    priority: PRIORITY_NORMAL,
                 },
                 Message::InvokeResult {
                     request_id: 1,
@@ -400,6 +1247,14 @@ mod tests {
                     message: "error".to_string(),
                     details: None,
                 },
+                Message::InvokeBatch {
+                    request_id: 1,
+                    calls: vec![("test".to_string(), Bytes::from_static(b"\x80"))],
+                },
+                Message::InvokeBatchResult {
+                    request_id: 1,
+                    results: vec![Ok(Bytes::new()), Err((ERR_EXECUTION_FAILED, "error".to_string()))],
+                },
                 Message::StreamStart {
                     request_id: 1,
                     window: 100,
@@ -436,11 +1291,20 @@ mod tests {
                     active_requests: 0,
                     total_requests: 100,
                 },
+                Message::AdminCommand {
+                    request_id: 1,
+                    command: "stats".to_string(),
+                    args: Bytes::new(),
+                },
+                Message::AdminResult {
+                    request_id: 1,
+                    data: Bytes::from_static(b"\x80"),
+                },
             ]
         }
     }
 
-    // ========== Category A: Message Type Code Tests (18 tests) ==========
+    // ========== Category A: Message Type Code Tests (20 tests) ==========
 
     #[test]
     fn test_handshake_message_type() {
@@ -493,6 +1357,7 @@ mod tests {
             params: Bytes::from_static(b"{}"),
             deadline_ms: 5000,
             context: helpers::create_minimal_context(),
+            priority: PRIORITY_NORMAL,
         };
         assert_eq!(msg.message_type(), MSG_INVOKE);
     }
@@ -519,6 +1384,15 @@ mod tests {
         assert_eq!(msg.message_type(), MSG_INVOKE_ERROR);
     }
 
+    #[test]
+    fn test_invoke_batch_message_types() {
+        let msg = Message::InvokeBatch { request_id: 1, calls: vec![] };
+        assert_eq!(msg.message_type(), MSG_INVOKE_BATCH);
+
+        let msg = Message::InvokeBatchResult { request_id: 1, results: vec![] };
+        assert_eq!(msg.message_type(), MSG_INVOKE_BATCH_RESULT);
+    }
+
     #[test]
     fn test_stream_start_message_type() {
         let msg = Message::StreamStart {
@@ -604,7 +1478,23 @@ mod tests {
         assert_eq!(msg.message_type(), MSG_HEALTH_STATUS);
     }
 
-    // ========== Category B: Codec Roundtrip Tests (18 tests) ==========
+    #[test]
+    fn test_admin_command_message_type() {
+        let msg = Message::AdminCommand {
+            request_id: 1,
+            command: "stats".to_string(),
+            args: Bytes::new(),
+        };
+        assert_eq!(msg.message_type(), MSG_ADMIN_COMMAND);
+    }
+
+    #[test]
+    fn test_admin_result_message_type() {
+        let msg = Message::AdminResult { request_id: 1, data: Bytes::new() };
+        assert_eq!(msg.message_type(), MSG_ADMIN_RESULT);
+    }
+
+    // ========== Category B: Codec Roundtrip Tests (20 tests) ==========
 
     #[test]
     fn test_roundtrip_handshake() {
@@ -718,6 +1608,7 @@ mod tests {
             params: Bytes::from_static(b"{\"key\":\"value\"}"),
             deadline_ms: 30000,
             context: helpers::create_full_context(),
+            priority: PRIORITY_NORMAL,
         };
 
         codec.encode(original.clone(), &mut buf).unwrap();
@@ -725,10 +1616,11 @@ mod tests {
 
         match (original, decoded) {
             (
-                Message::Invoke { request_id: r1, function_name: f1, params: p1, deadline_ms: d1, context: c1 },
-                Message::Invoke { request_id: r2, function_name: f2, params: p2, deadline_ms: d2, context: c2 },
+                Message::Invoke { request_id: r1, function_name: f1, params: p1, deadline_ms: d1, context: c1, priority: pr1 },
+                Message::Invoke { request_id: r2, function_name: f2, params: p2, deadline_ms: d2, context: c2, priority: pr2 },
             ) => {
                 assert_eq!(r1, r2);
+                assert_eq!(pr1, pr2);
                 assert_eq!(f1, f2);
                 assert_eq!(p1, p2);
                 assert_eq!(d1, d2);
@@ -741,6 +1633,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_invoke_priority_only_encoded_when_negotiated() {
+        /// `Invoke` as peers that predate `CAP_PRIORITY` decode it
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        enum PrePriority {
+            Invoke {
+                request_id: u64,
+                function_name: String,
+                params: Bytes,
+                deadline_ms: u32,
+                context: RequestContext,
+            },
+        }
+
+        let invoke = || Message::Invoke {
+            request_id: 1,
+            function_name: "f".to_string(),
+            params: Bytes::new(),
+            deadline_ms: 0,
+            context: helpers::create_minimal_context(),
+            priority: PRIORITY_HIGH,
+        };
+
+        // Not negotiated: the old layout, admitted at normal priority
+        let mut codec = SpliceCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(invoke(), &mut buf).unwrap();
+        assert!(rmp_serde::from_slice::<PrePriority>(&buf[5..]).is_ok());
+        match codec.decode(&mut buf).unwrap().unwrap() {
+            Message::Invoke { priority, .. } => assert_eq!(priority, PRIORITY_NORMAL),
+            other => panic!("expected Invoke, got {:?}", other),
+        }
+
+        // Negotiated: the priority goes through
+        let mut codec = SpliceCodec::default().with_priority(true);
+        codec.encode(invoke(), &mut buf).unwrap();
+        assert!(rmp_serde::from_slice::<PrePriority>(&buf[5..]).is_err());
+        match codec.decode(&mut buf).unwrap().unwrap() {
+            Message::Invoke { priority, .. } => assert_eq!(priority, PRIORITY_HIGH),
+            other => panic!("expected Invoke, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_roundtrip_invoke_result() {
         let mut codec = SpliceCodec::default();
@@ -1023,6 +1959,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_roundtrip_invoke_batch() {
+        let params = Bytes::from((0..=255u8).collect::<Vec<_>>());
+        let decoded = helpers::roundtrip(Message::InvokeBatch {
+            request_id: 77,
+            calls: vec![
+                ("add".to_string(), Bytes::from_static(&[0x92, 0x01, 0x02])),
+                ("upload".to_string(), params.clone()),
+            ],
+        });
+        match decoded {
+            Message::InvokeBatch { request_id, calls } => {
+                assert_eq!(request_id, 77);
+                assert_eq!(calls.len(), 2);
+                assert_eq!(calls[0].0, "add");
+                assert_eq!(&calls[0].1[..], &[0x92, 0x01, 0x02]);
+                assert_eq!(calls[1], ("upload".to_string(), params));
+            }
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_invoke_batch_result_keeps_order() {
+        let results = vec![
+            Ok(Bytes::from_static(b"\x03")),
+            Err((ERR_FUNCTION_NOT_FOUND, "Function not found: nope".to_string())),
+            Ok(Bytes::new()),
+        ];
+        for format in [PayloadFormat::MsgPack, PayloadFormat::Cbor] {
+            let decoded = helpers::roundtrip_with(format, Message::InvokeBatchResult {
+                request_id: 78,
+                results: results.clone(),
+            });
+            match decoded {
+                Message::InvokeBatchResult { request_id, results: decoded } => {
+                    assert_eq!(request_id, 78);
+                    assert_eq!(decoded, results);
+                }
+                _ => panic!("Message type mismatch"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_admin_command() {
+        let decoded = helpers::roundtrip(Message::AdminCommand {
+            request_id: 7,
+            command: "handlers".to_string(),
+            args: Bytes::from_static(&[0x91, 0x01]),
+        });
+        match decoded {
+            Message::AdminCommand { request_id, command, args } => {
+                assert_eq!(request_id, 7);
+                assert_eq!(command, "handlers");
+                assert_eq!(&args[..], &[0x91, 0x01]);
+            }
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_admin_result() {
+        let data = Bytes::from((0..=255u8).collect::<Vec<_>>());
+        match helpers::roundtrip(Message::AdminResult { request_id: 7, data: data.clone() }) {
+            Message::AdminResult { request_id, data: decoded } => {
+                assert_eq!(request_id, 7);
+                assert_eq!(decoded, data);
+            }
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
     // ========== Category C: Framing Structure Tests (8 tests) ==========
 
     #[test]
@@ -1052,6 +2061,7 @@ mod tests {
             params: Bytes::new(),
             deadline_ms: 1000,
             context: helpers::create_minimal_context(),
+            priority: PRIORITY_NORMAL,
         };
 
         let expected_type = msg.message_type();
@@ -1179,6 +2189,15 @@ mod tests {
 
     // ========== Category D: Edge Cases & Boundaries (12 tests) ==========
 
+    #[test]
+    fn test_negotiate_max_frame_size() {
+        let mut codec = SpliceCodec::new(1024);
+        assert_eq!(codec.negotiate_max_frame_size(4096), 1024);
+        assert_eq!(codec.negotiate_max_frame_size(0), 1024);
+        assert_eq!(codec.negotiate_max_frame_size(512), 512);
+        assert_eq!(codec.max_frame_size(), 512);
+    }
+
     #[test]
     fn test_max_frame_size_exact() {
         let max_size = 10000u32;
@@ -1225,6 +2244,7 @@ mod tests {
             params: Bytes::new(),
             deadline_ms: 1000,
             context: helpers::create_minimal_context(),
+            priority: PRIORITY_NORMAL,
         };
 
         codec.encode(msg.clone(), &mut buf).unwrap();
@@ -1269,6 +2289,7 @@ mod tests {
                 headers: vec![],
                 auth: None,
             },
+            priority: PRIORITY_NORMAL,
         };
 
         let decoded = helpers::roundtrip(msg);
@@ -1294,6 +2315,7 @@ mod tests {
                 headers: vec![],
                 auth: None,
             },
+            priority: PRIORITY_NORMAL,
         };
 
         let decoded = helpers::roundtrip(msg);
@@ -1385,6 +2407,7 @@ mod tests {
                 headers: headers.clone(),
                 auth: None,
             },
+            priority: PRIORITY_NORMAL,
         };
 
         let decoded = helpers::roundtrip(msg);
@@ -1602,6 +2625,7 @@ mod tests {
             params: Bytes::from_static(b"{\"data\":\"value\"}"),
             deadline_ms: 5000,
             context: helpers::create_minimal_context(),
+            priority: PRIORITY_NORMAL,
         }, &mut buf).unwrap();
 
         // Large message
@@ -1719,6 +2743,7 @@ mod tests {
             ERR_INTERNAL_ERROR,
             ERR_UNAVAILABLE,
             ERR_OVERLOADED,
+            ERR_RELOADING,
         ];
 
         let mut codec = SpliceCodec::default();
@@ -1744,4 +2769,455 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_retry_after_details_roundtrip() {
+        let details = retry_after_details(Duration::from_millis(1500));
+        assert_eq!(&details[..], br#"{"retryAfterMs":1500}"#);
+        assert_eq!(parse_retry_after(&details), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_retry_after(b"extra details"), None);
+        assert_eq!(parse_retry_after(br#"{"other":1}"#), None);
+    }
+
+    // ========== Category G: Zero-Copy Decode ==========
+
+    fn encode_large_invoke(size: usize) -> BytesMut {
+        let mut buf = BytesMut::new();
+        let msg = Message::Invoke {
+            request_id: 1,
+            function_name: "upload".to_string(),
+            params: Bytes::from(vec![7u8; size]),
+            deadline_ms: 1000,
+            context: helpers::create_minimal_context(),
+            priority: PRIORITY_NORMAL,
+        };
+        SpliceCodec::default().encode(msg, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_decode_params_share_frame_buffer() {
+        let mut buf = encode_large_invoke(64 * 1024);
+        let frame_start = buf.as_ptr() as usize;
+        let frame_end = frame_start + buf.len();
+
+        let decoded = SpliceCodec::default().decode(&mut buf).unwrap().unwrap();
+
+        match decoded {
+            Message::Invoke { params, .. } => {
+                assert_eq!(params.len(), 64 * 1024);
+                assert!(params.iter().all(|&b| b == 7));
+                let ptr = params.as_ptr() as usize;
+                assert!(ptr >= frame_start && ptr + params.len() <= frame_end);
+            }
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_zero_copy_optional_details() {
+        let mut codec = SpliceCodec::default();
+        let mut buf = BytesMut::new();
+        let msg = Message::InvokeError {
+            request_id: 1,
+            code: ERR_EXECUTION_FAILED,
+            kind: ErrorKind::User,
+            message: "failed".to_string(),
+            details: Some(Bytes::from_static(b"details")),
+        };
+
+        codec.encode(msg, &mut buf).unwrap();
+        match codec.decode(&mut buf).unwrap().unwrap() {
+            Message::InvokeError { details, .. } => {
+                assert_eq!(details.as_deref(), Some(&b"details"[..]));
+            }
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
+    // ========== Category H: Payload Formats ==========
+
+    #[test]
+    fn test_cbor_roundtrip_all_variants() {
+        for msg in helpers::create_all_message_variants() {
+            let expected = format!("{:?}", msg);
+            let decoded = helpers::roundtrip_with(PayloadFormat::Cbor, msg);
+            assert_eq!(format!("{:?}", decoded), expected);
+        }
+    }
+
+    #[test]
+    fn test_cbor_roundtrip_invoke_with_binary_params() {
+        let params = Bytes::from((0..=255u8).collect::<Vec<_>>());
+        let msg = Message::Invoke {
+            request_id: u64::MAX,
+            function_name: "upload.put".to_string(),
+            params: params.clone(),
+            deadline_ms: 5000,
+            context: helpers::create_full_context(),
+            priority: PRIORITY_NORMAL,
+        };
+
+        match helpers::roundtrip_with(PayloadFormat::Cbor, msg) {
+            Message::Invoke { request_id, params: decoded, context, .. } => {
+                assert_eq!(request_id, u64::MAX);
+                assert_eq!(decoded, params);
+                assert_eq!(context.auth.unwrap().user_id, "test-user");
+            }
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_cbor_payload_differs_from_msgpack() {
+        let msg = Message::Cancel { request_id: 7 };
+        let mut msgpack = BytesMut::new();
+        let mut cbor = BytesMut::new();
+        SpliceCodec::default().encode(msg.clone(), &mut msgpack).unwrap();
+        SpliceCodec::with_format(DEFAULT_MAX_FRAME_SIZE, PayloadFormat::Cbor)
+            .encode(msg, &mut cbor)
+            .unwrap();
+        assert_ne!(msgpack[5..], cbor[5..]);
+
+        // A msgpack decoder cannot read a CBOR payload
+        assert!(SpliceCodec::default().decode(&mut cbor).is_err());
+    }
+
+    #[test]
+    fn test_handshake_always_msgpack() {
+        let handshake = Message::Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            role: Role::Host,
+            capabilities: CAP_FORMAT_CBOR,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        };
+        let mut buf = BytesMut::new();
+        SpliceCodec::with_format(DEFAULT_MAX_FRAME_SIZE, PayloadFormat::Cbor)
+            .encode(handshake, &mut buf)
+            .unwrap();
+
+        // Readable by a peer that has not switched formats yet
+        match SpliceCodec::default().decode(&mut buf).unwrap().unwrap() {
+            Message::Handshake { capabilities, .. } => assert_eq!(capabilities, CAP_FORMAT_CBOR),
+            _ => panic!("Message type mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_format_negotiation() {
+        let cbor = PayloadFormat::Cbor.capability();
+        assert_eq!(PayloadFormat::negotiate(cbor | CAP_STREAMING, cbor), PayloadFormat::Cbor);
+        assert_eq!(PayloadFormat::negotiate(cbor, CAP_STREAMING), PayloadFormat::MsgPack);
+        assert_eq!(PayloadFormat::negotiate(0, cbor), PayloadFormat::MsgPack);
+        assert_eq!("cbor".parse::<PayloadFormat>().unwrap(), PayloadFormat::Cbor);
+        assert!("protobuf".parse::<PayloadFormat>().is_err());
+    }
+
+    // ========== Category I: Forward Compatibility ==========
+
+    #[test]
+    fn test_unknown_type_decodes_to_unknown_variant() {
+        let mut buf = BytesMut::new();
+        buf.put_u32(3);
+        buf.put_u8(0x7f);
+        buf.put_slice(&[0xc0, 0x01, 0x02]);
+
+        // A known message following it is still decoded
+        let mut codec = SpliceCodec::default();
+        codec.encode(Message::HealthCheck, &mut buf).unwrap();
+
+        match codec.decode(&mut buf).unwrap().unwrap() {
+            Message::Unknown { msg_type, payload } => {
+                assert_eq!(msg_type, 0x7f);
+                assert_eq!(&payload[..], &[0xc0, 0x01, 0x02]);
+            }
+            other => panic!("Expected Unknown, got {:?}", other),
+        }
+        assert!(matches!(codec.decode(&mut buf).unwrap().unwrap(), Message::HealthCheck));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_message_reencodes_verbatim() {
+        let msg = Message::Unknown {
+            msg_type: 0x99,
+            payload: Bytes::from_static(b"future"),
+        };
+        assert_eq!(msg.message_type(), 0x99);
+
+        match helpers::roundtrip(msg) {
+            Message::Unknown { msg_type, payload } => {
+                assert_eq!(msg_type, 0x99);
+                assert_eq!(&payload[..], b"future");
+            }
+            other => panic!("Expected Unknown, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_known_types() {
+        for msg in helpers::create_all_message_variants() {
+            assert!(Message::is_known_type(msg.message_type()));
+        }
+        assert!(!Message::is_known_type(0x00));
+        assert!(!Message::is_known_type(0xff));
+    }
+
+    // ========== Category J: RequestContext Headers ==========
+
+    #[test]
+    fn test_context_header_lookup_is_case_insensitive() {
+        let context = helpers::create_full_context();
+        assert_eq!(context.header("X-Request-Id"), Some("req-123"));
+        assert_eq!(context.header("X-REQUEST-ID"), Some("req-123"));
+        assert_eq!(context.request_id(), Some("req-123"));
+        assert_eq!(context.forwarded_for(), Some("1.2.3.4"));
+        assert_eq!(context.header("missing"), None);
+    }
+
+    #[test]
+    fn test_context_builder_overwrites_headers() {
+        let mut context = RequestContext::builder()
+            .header("X-Request-ID", "first")
+            .request_id("second")
+            .headers([("x-forwarded-for", "1.1.1.1"), ("X-Forwarded-For", "2.2.2.2")])
+            .build();
+
+        assert_eq!(
+            context.headers,
+            vec![
+                (HEADER_REQUEST_ID.to_string(), "second".to_string()),
+                (HEADER_FORWARDED_FOR.to_string(), "2.2.2.2".to_string()),
+            ]
+        );
+
+        context.set_header("X-Request-Id", "third");
+        assert_eq!(context.request_id(), Some("third"));
+        assert_eq!(context.headers.len(), 2);
+        assert_eq!(context.remove_header("x-REQUEST-id"), Some("third".to_string()));
+        assert_eq!(context.request_id(), None);
+    }
+
+    #[test]
+    fn test_context_builder_trace_and_request_id() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = RequestContext::builder()
+            .trace_id(0xa3ce929d0e0e4736)
+            .span_id(0x00f067aa0ba902b7)
+            .traceparent(traceparent)
+            .tracestate("vendor=1")
+            .request_id("req-9")
+            .build();
+
+        assert_eq!(context.trace_id, 0xa3ce929d0e0e4736);
+        assert_eq!(context.span_id, 0x00f067aa0ba902b7);
+        assert_eq!(context.traceparent(), Some(traceparent));
+        assert_eq!(context.tracestate(), Some("vendor=1"));
+        assert_eq!(context.request_id(), Some("req-9"));
+        assert!(context.auth.is_none());
+
+        match helpers::roundtrip(Message::Invoke {
+            request_id: 1,
+            function_name: "f".to_string(),
+            params: Bytes::new(),
+            deadline_ms: 0,
+            context,
+            priority: PRIORITY_NORMAL,
+        }) {
+            Message::Invoke { context, .. } => assert_eq!(context.header("TraceParent"), Some(traceparent)),
+            other => panic!("Expected Invoke, got {:?}", other),
+        }
+    }
+
+    // ========== Category K: Read Buffer Sizing ==========
+
+    #[test]
+    fn test_codec_read_buffer_defaults() {
+        let codec = SpliceCodec::default();
+        assert_eq!(codec.initial_capacity(), DEFAULT_READ_CAPACITY);
+        assert_eq!(codec.read_chunk_size(), 0);
+
+        let codec = SpliceCodec::default()
+            .with_initial_capacity(1 << 20)
+            .with_read_chunk_size(256 * 1024);
+        assert_eq!(codec.initial_capacity(), 1 << 20);
+        assert_eq!(codec.read_chunk_size(), 256 * 1024);
+    }
+
+    #[test]
+    fn test_codec_reserves_read_chunk() {
+        let mut codec = SpliceCodec::default().with_read_chunk_size(64 * 1024);
+        let mut buf = BytesMut::new();
+
+        // A partial header already reserves a full chunk
+        buf.extend_from_slice(&[0, 0]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert!(buf.capacity() >= 64 * 1024);
+
+        // A small frame does not shrink the reservation below the chunk
+        let mut encoded = BytesMut::new();
+        codec.encode(Message::HealthCheck, &mut encoded).unwrap();
+        let mut buf = BytesMut::from(&encoded[..encoded.len() - 1]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert!(buf.capacity() >= 64 * 1024);
+
+        buf.extend_from_slice(&encoded[encoded.len() - 1..]);
+        assert!(matches!(codec.decode(&mut buf).unwrap(), Some(Message::HealthCheck)));
+    }
+
+    #[tokio::test]
+    async fn test_framed_uses_initial_capacity() {
+        let (io, _peer) = tokio::io::duplex(64);
+        let framed = SpliceCodec::default().with_initial_capacity(512 * 1024).framed(io);
+        assert!(framed.read_buffer().capacity() >= 512 * 1024);
+    }
+
+    #[test]
+    fn test_compressed_large_invoke_result_roundtrip() {
+        let result = Bytes::from(
+            b"{\"id\":0,\"name\":\"splice\"}".iter().copied().cycle().take(1024 * 1024).collect::<Vec<u8>>(),
+        );
+        let msg = Message::InvokeResult { request_id: 9, result: result.clone(), duration_us: 1 };
+
+        let mut plain = BytesMut::new();
+        SpliceCodec::default().encode(msg.clone(), &mut plain).unwrap();
+
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let mut codec = SpliceCodec::default().with_compression(compression);
+            let mut wire = BytesMut::new();
+            codec.encode(msg.clone(), &mut wire).unwrap();
+            assert_ne!(wire[4] & FRAME_FLAG_COMPRESSED, 0);
+            assert!(wire.len() < plain.len() / 10, "{:?}: {} bytes", compression, wire.len());
+
+            // The peer may compress with a different algorithm
+            match SpliceCodec::default().with_compression(Compression::Gzip).decode(&mut wire).unwrap() {
+                Some(Message::InvokeResult { request_id, result: decoded, .. }) => {
+                    assert_eq!(request_id, 9);
+                    assert_eq!(decoded, result);
+                }
+                other => panic!("Expected InvokeResult, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_frames_stay_uncompressed() {
+        let mut codec = SpliceCodec::default().with_compression(Compression::Zstd);
+        let mut wire = BytesMut::new();
+        codec.encode(Message::HealthStatus { uptime_ms: 1, active_requests: 1, total_requests: 2 }, &mut wire).unwrap();
+        assert_eq!(wire[4], MSG_HEALTH_STATUS);
+    }
+
+    #[test]
+    fn test_every_variant_roundtrips_compressed() {
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let mut codec = SpliceCodec::default()
+                .with_compression(compression)
+                .with_compression_threshold(0);
+            for msg in helpers::create_all_message_variants() {
+                let expected = format!("{:?}", msg);
+                let mut buf = BytesMut::new();
+                codec.encode(msg, &mut buf).unwrap();
+                let decoded = codec.decode(&mut buf).unwrap().unwrap();
+                assert_eq!(format!("{:?}", decoded), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compressed_payload_bounded_by_max_frame_size() {
+        let msg = Message::InvokeResult { request_id: 1, result: Bytes::from(vec![0u8; 64 * 1024]), duration_us: 0 };
+        let mut wire = BytesMut::new();
+        SpliceCodec::default().with_compression(Compression::Gzip).encode(msg, &mut wire).unwrap();
+        assert!(wire.len() < 4 * 1024);
+
+        // A tiny frame that inflates past the limit is refused
+        let mut codec = SpliceCodec::new(8 * 1024).with_compression(Compression::Gzip);
+        assert!(matches!(codec.decode(&mut wire), Err(ProtocolError::FrameTooLarge(_))));
+    }
+
+    #[test]
+    fn test_skip_malformed_resyncs_on_next_frame() {
+        let ping = |id| Message::InvokeResult { request_id: id, result: Bytes::from_static(b"ok"), duration_us: 0 };
+        let mut wire = BytesMut::new();
+        let mut encoder = SpliceCodec::default();
+        encoder.encode(ping(1), &mut wire).unwrap();
+        let corrupt_start = wire.len();
+        encoder.encode(ping(2), &mut wire).unwrap();
+        let corrupt_end = wire.len();
+        encoder.encode(ping(3), &mut wire).unwrap();
+
+        // 0xc1 is never valid MessagePack
+        for byte in &mut wire[corrupt_start + 5..corrupt_end] {
+            *byte = 0xc1;
+        }
+
+        let mut strict = SpliceCodec::default();
+        let mut strict_wire = wire.clone();
+        assert!(strict.decode(&mut strict_wire).unwrap().is_some());
+        assert!(matches!(strict.decode(&mut strict_wire), Err(ProtocolError::Serialization(_))));
+
+        let mut codec = SpliceCodec::default().with_skip_malformed(true);
+        let mut ids = Vec::new();
+        while let Some(msg) = codec.decode(&mut wire).unwrap() {
+            match msg {
+                Message::InvokeResult { request_id, .. } => ids.push(request_id),
+                other => panic!("Expected InvokeResult, got {:?}", other),
+            }
+        }
+        assert_eq!(ids, [1, 3]);
+        assert_eq!(codec.skipped_frames(), 1);
+        assert!(wire.is_empty());
+    }
+
+    #[test]
+    fn test_skip_malformed_request_keeps_request_id() {
+        let invoke = |id| Message::Invoke {
+            request_id: id,
+            function_name: "echo".to_string(),
+            params: Bytes::from_static(b"{}"),
+            deadline_ms: 0,
+            context: helpers::create_minimal_context(),
+            priority: PRIORITY_NORMAL,
+        };
+
+        for format in [PayloadFormat::MsgPack, PayloadFormat::Cbor] {
+            // Cut the payload short after the request id
+            let mut frame = BytesMut::new();
+            SpliceCodec::with_format(DEFAULT_MAX_FRAME_SIZE, format).encode(invoke(7), &mut frame).unwrap();
+            let truncated = frame.len() - 5 - 4;
+            let mut wire = BytesMut::new();
+            wire.put_u32(truncated as u32);
+            wire.put_u8(MSG_INVOKE);
+            wire.extend_from_slice(&frame[5..5 + truncated]);
+            SpliceCodec::with_format(DEFAULT_MAX_FRAME_SIZE, format).encode(invoke(8), &mut wire).unwrap();
+
+            let mut codec = SpliceCodec::with_format(DEFAULT_MAX_FRAME_SIZE, format).with_skip_malformed(true);
+            match codec.decode(&mut wire).unwrap() {
+                Some(Message::Malformed { msg_type: MSG_INVOKE, request_id: 7, .. }) => {}
+                other => panic!("Expected Malformed for request 7, got {:?}", other),
+            }
+            assert!(matches!(codec.decode(&mut wire).unwrap(), Some(Message::Invoke { request_id: 8, .. })));
+            assert_eq!(codec.skipped_frames(), 1);
+        }
+
+        // Without a readable id the frame is dropped silently
+        let mut wire = BytesMut::new();
+        wire.put_u32(4);
+        wire.put_u8(MSG_INVOKE);
+        wire.extend_from_slice(&[0xc1; 4]);
+        let mut codec = SpliceCodec::default().with_skip_malformed(true);
+        assert!(codec.decode(&mut wire).unwrap().is_none());
+        assert_eq!(codec.skipped_frames(), 1);
+    }
+
+    #[test]
+    fn test_compression_negotiation() {
+        let both = CAP_STREAMING | CAP_COMPRESSION;
+        assert_eq!(Compression::negotiate(Some(Compression::Zstd), both, both), Some(Compression::Zstd));
+        assert_eq!(Compression::negotiate(Some(Compression::Zstd), both, CAP_STREAMING), None);
+        assert_eq!(Compression::negotiate(None, both, both), None);
+        assert_eq!("gzip".parse::<Compression>(), Ok(Compression::Gzip));
+        assert!("lz4".parse::<Compression>().is_err());
+    }
 }
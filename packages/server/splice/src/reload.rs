@@ -1,78 +1,358 @@
+use crate::protocol::{ExportMetadata, Message, ProtocolError};
+use crate::router::Router;
 use crate::supervisor::{Supervisor, WorkerInfo};
-use std::path::PathBuf;
-use std::time::Duration;
+use futures::Stream;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use thiserror::Error;
 use tokio::fs;
-use tracing::{info, warn};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
 
 #[derive(Debug, Error)]
 pub enum ReloadError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Protocol error: {0}")]
+    Protocol(#[from] ProtocolError),
+
     #[error("Worker spawn failed: {0}")]
     SpawnFailed(String),
 
+    #[error("New worker not ready: {0}")]
+    NotReady(String),
+
+    #[error("New worker not ready within {}ms", .0.as_millis())]
+    ReadyTimeout(Duration),
+
     #[error("Incompatible exports")]
     IncompatibleExports,
 }
 
+#[derive(Debug, Clone)]
+pub struct ReloadConfig {
+    /// Time the new worker has to connect, handshake and list its exports
+    pub ready_timeout: Duration,
+    /// Time requests already sent to the old worker get to finish
+    pub drain_timeout: Duration,
+    /// Time allowed at each step of stopping the old worker
+    pub shutdown_grace: Duration,
+}
+
+impl Default for ReloadConfig {
+    fn default() -> Self {
+        Self {
+            ready_timeout: Duration::from_secs(10),
+            drain_timeout: Duration::from_secs(30),
+            shutdown_grace: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Connection to a new worker that completed its handshake and listed its
+/// exports
+pub struct ReadyWorker<S> {
+    /// Outbound messages to the worker
+    pub worker_tx: mpsc::Sender<Message>,
+    /// Messages from the worker, read once it is active
+    pub worker_read: S,
+    pub exports: Vec<ExportMetadata>,
+    /// Frame limit negotiated in the handshake
+    pub max_frame_size: u32,
+}
+
+/// A completed reload
+pub struct Reloaded {
+    pub worker: WorkerInfo,
+    /// Reads the new worker's connection; resolves to `true` if it is lost
+    /// while still active (see [`Router::read_worker_messages`])
+    pub reader: JoinHandle<bool>,
+    /// Drains and stops the old worker
+    pub retirement: JoinHandle<()>,
+}
+
+/// A reload waiting for its new worker, from [`ReloadManager::begin_reload`]
+pub struct PendingReload<S> {
+    /// The new worker, running alongside the old one
+    pub standby: WorkerInfo,
+    ready: JoinHandle<Result<ReadyWorker<S>, ReloadError>>,
+}
+
+impl<S> PendingReload<S> {
+    /// Wait for the new worker's connection, or for it to miss
+    /// `ready_timeout`
+    ///
+    /// Cancel-safe, so it can be polled from a `select!` loop; pass the
+    /// result to [`ReloadManager::finish_reload`].
+    pub async fn ready(&mut self) -> Result<ReadyWorker<S>, ReloadError> {
+        match (&mut self.ready).await {
+            Ok(ready) => ready,
+            Err(e) => Err(ReloadError::NotReady(e.to_string())),
+        }
+    }
+}
+
+/// Directories never searched for watched files
+const IGNORED_DIRS: &[&str] = &["target", ".git"];
+
+/// What [`ReloadManager::check_for_changes`] looks at besides the worker
+/// binary
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Files, and directories searched recursively, whose changes trigger
+    /// a reload
+    pub paths: Vec<PathBuf>,
+    /// Quiet period required after the last change before a reload is
+    /// reported, so one save or build does not cause several reloads
+    pub debounce: Duration,
+    /// Extensions (without the dot) of watched files that count
+    pub watch_extensions: Vec<String>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            debounce: Duration::from_millis(500),
+            watch_extensions: vec!["rs".to_string(), "toml".to_string()],
+        }
+    }
+}
+
 pub struct ReloadManager {
     binary_path: PathBuf,
+    watch: WatchConfig,
     current_hash: Option<Vec<u8>>,
+    /// Latest unreported state and when it was first seen
+    pending: Option<(Vec<u8>, Instant)>,
 }
 
 impl ReloadManager {
     pub fn new(binary_path: PathBuf) -> Self {
         Self {
             binary_path,
+            watch: WatchConfig::default(),
             current_hash: None,
+            pending: None,
         }
     }
 
+    pub fn with_watch(mut self, watch: WatchConfig) -> Self {
+        self.watch = watch;
+        self
+    }
+
+    /// Poll the worker binary and watched files for changes
+    ///
+    /// The first call records the current state. A change is reported once,
+    /// after nothing has changed for `debounce`; call this periodically.
     pub async fn check_for_changes(&mut self) -> Result<bool, ReloadError> {
-        let new_hash = self.hash_binary().await?;
+        let new_hash = self.hash_watched().await?;
+        let now = Instant::now();
 
-        if let Some(ref current) = self.current_hash {
-            if &new_hash != current {
-                info!("Binary changed, hot reload triggered");
-                self.current_hash = Some(new_hash);
-                return Ok(true);
-            }
-        } else {
+        let Some(current) = &self.current_hash else {
             self.current_hash = Some(new_hash);
+            return Ok(false);
+        };
+        if *current == new_hash {
+            // Changed back before settling
+            self.pending = None;
+            return Ok(false);
         }
 
-        Ok(false)
+        let since = match &self.pending {
+            Some((seen, since)) if *seen == new_hash => *since,
+            _ => {
+                debug!("Change detected, waiting {:?} for quiet", self.watch.debounce);
+                self.pending = Some((new_hash.clone(), now));
+                now
+            }
+        };
+        if now.duration_since(since) < self.watch.debounce {
+            return Ok(false);
+        }
+
+        info!("Worker changed, hot reload triggered");
+        self.pending = None;
+        self.current_hash = Some(new_hash);
+        Ok(true)
+    }
+
+    /// Digest of the binary's contents and the watched files' metadata
+    async fn hash_watched(&self) -> Result<Vec<u8>, ReloadError> {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(fs::read(&self.binary_path).await?);
+
+        let paths = self.watch.paths.clone();
+        let extensions = self.watch.watch_extensions.clone();
+        let files = tokio::task::spawn_blocking(move || watched_files(&paths, &extensions))
+            .await
+            .map_err(std::io::Error::other)??;
+        for (path, modified, len) in files {
+            hasher.update(path.as_os_str().as_encoded_bytes());
+            let modified = modified.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+            hasher.update(modified.as_nanos().to_le_bytes());
+            hasher.update(len.to_le_bytes());
+        }
+        Ok(hasher.finalize().to_vec())
     }
 
-    async fn hash_binary(&self) -> Result<Vec<u8>, ReloadError> {
-        let data = fs::read(&self.binary_path).await?;
-        Ok(sha2::Sha256::digest(&data).to_vec())
+    /// Replace the worker without a gap in service (blue/green)
+    ///
+    /// 1. Spawn the new worker alongside the old one
+    /// 2. Wait up to `ready_timeout` for `connect` to yield its handshaken
+    ///    connection and exports
+    /// 3. Swap it in as the router's active worker
+    /// 4. In the background, drain the old worker's requests and stop it
+    ///
+    /// If the new worker fails to get ready, it is discarded and the old one
+    /// keeps serving; the error reports the failed reload.
+    ///
+    /// This waits for the new worker; an event loop that must keep going
+    /// meanwhile uses [`ReloadManager::begin_reload`] and
+    /// [`ReloadManager::finish_reload`] instead.
+    pub async fn perform_reload<S, F>(
+        &self,
+        supervisor: &mut Supervisor,
+        router: &Arc<Router>,
+        config: &ReloadConfig,
+        connect: F,
+    ) -> Result<Reloaded, ReloadError>
+    where
+        S: Stream<Item = Result<Message, ProtocolError>> + Unpin + Send + 'static,
+        F: Future<Output = Result<ReadyWorker<S>, ReloadError>> + Send + 'static,
+    {
+        let mut pending = self.begin_reload(supervisor, config, connect).await?;
+        let ready = pending.ready().await;
+        self.finish_reload(supervisor, router, config, pending.standby, ready).await
     }
 
-    pub async fn perform_reload(
+    /// Spawn the new worker and wait for `connect` in the background
+    ///
+    /// The old worker keeps serving until the returned reload is finished.
+    pub async fn begin_reload<S, F>(
         &self,
-        old_supervisor: &mut Supervisor,
-        drain_timeout: Duration,
-    ) -> Result<(), ReloadError> {
+        supervisor: &mut Supervisor,
+        config: &ReloadConfig,
+        connect: F,
+    ) -> Result<PendingReload<S>, ReloadError>
+    where
+        S: Send + 'static,
+        F: Future<Output = Result<ReadyWorker<S>, ReloadError>> + Send + 'static,
+    {
         info!("Starting hot reload sequence");
 
-        // Drain in-flight requests
-        // Note: This needs router integration which we'll handle in Phase 4
-        info!("Draining in-flight requests (max {:?})", drain_timeout);
-        tokio::time::sleep(Duration::from_millis(100)).await; // Placeholder
+        let standby = supervisor
+            .spawn_standby()
+            .await
+            .map_err(|e| ReloadError::SpawnFailed(e.to_string()))?;
 
-        // Graceful shutdown of old worker
-        if let Err(e) = old_supervisor.graceful_shutdown(Duration::from_secs(5)).await {
-            warn!("Error during graceful shutdown: {}", e);
-        }
+        let ready_timeout = config.ready_timeout;
+        let ready = tokio::spawn(async move {
+            tokio::time::timeout(ready_timeout, connect)
+                .await
+                .unwrap_or(Err(ReloadError::ReadyTimeout(ready_timeout)))
+        });
+        Ok(PendingReload { standby, ready })
+    }
+
+    /// Swap in the new worker once it is ready, or discard it
+    ///
+    /// `ready` is the outcome of [`PendingReload::ready`].
+    pub async fn finish_reload<S>(
+        &self,
+        supervisor: &mut Supervisor,
+        router: &Arc<Router>,
+        config: &ReloadConfig,
+        standby: WorkerInfo,
+        ready: Result<ReadyWorker<S>, ReloadError>,
+    ) -> Result<Reloaded, ReloadError>
+    where
+        S: Stream<Item = Result<Message, ProtocolError>> + Unpin + Send + 'static,
+    {
+        let ready = match ready {
+            Ok(ready) => ready,
+            Err(e) => {
+                warn!("New worker {} failed to get ready: {}", standby.pid, e);
+                supervisor.discard_standby().await;
+                return Err(e);
+            }
+        };
+
+        // From here on new invokes reach the new worker
+        router.update_exports(ready.exports).await;
+        router.set_worker_max_frame_size(ready.max_frame_size);
+        let retired_generation = router.swap_worker(ready.worker_tx.clone());
+        let retired = supervisor.promote_standby(ready.worker_tx);
+        let worker = supervisor.worker_info().cloned().unwrap_or(standby);
+
+        let reader = {
+            let router = Arc::clone(router);
+            let shutdown_ack = supervisor.shutdown_ack_notifier();
+            tokio::spawn(async move { router.read_worker_messages(ready.worker_read, shutdown_ack).await })
+        };
+
+        let retirement = {
+            let router = Arc::clone(router);
+            let config = config.clone();
+            tokio::spawn(async move {
+                let unfinished = router.drain_worker(retired_generation, config.drain_timeout).await;
+                if unfinished > 0 {
+                    warn!("Stopping old worker with {} requests unfinished", unfinished);
+                }
+                if let Some(retired) = retired {
+                    retired.shutdown(config.shutdown_grace).await;
+                }
+            })
+        };
+
+        info!("Hot reload complete: worker {} serving", worker.pid);
+        Ok(Reloaded { worker, reader, retirement })
+    }
+}
 
-        // Supervisor will be restarted by the main loop
-        info!("Hot reload complete");
+/// Watched files under `paths` with their modification time and size,
+/// sorted by path
+///
+/// Missing paths are skipped, so deleting a file counts as a change.
+fn watched_files(paths: &[PathBuf], extensions: &[String]) -> std::io::Result<Vec<(PathBuf, SystemTime, u64)>> {
+    fn visit(path: &Path, extensions: &[String], files: &mut Vec<(PathBuf, SystemTime, u64)>) -> std::io::Result<()> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
 
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                let entry = entry?;
+                let ignored = IGNORED_DIRS.iter().any(|dir| entry.file_name() == *dir);
+                if !ignored {
+                    visit(&entry.path(), extensions, files)?;
+                }
+            }
+        } else {
+            let watched = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.iter().any(|watched| watched == ext));
+            if watched {
+                files.push((path.to_path_buf(), metadata.modified()?, metadata.len()));
+            }
+        }
         Ok(())
     }
+
+    let mut files = Vec::new();
+    for path in paths {
+        visit(path, extensions, &mut files)?;
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
 }
 
 use sha2::Digest;
@@ -80,10 +360,277 @@ use sha2::Digest;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::RequestContext;
+    use crate::router::RouterConfig;
+    use crate::supervisor::SupervisorConfig;
+    use bytes::Bytes;
 
     #[tokio::test]
     async fn test_reload_manager_creation() {
         let manager = ReloadManager::new(PathBuf::from("/tmp/test"));
         assert!(manager.current_hash.is_none());
     }
+
+    /// A worker binary and an empty `src/` in a fresh directory
+    fn watched_project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("splice-watch-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+        std::fs::write(dir.join("worker"), "v1").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_changes_debounced_until_quiet() {
+        let dir = watched_project("debounce");
+        let mut manager = ReloadManager::new(dir.join("worker")).with_watch(WatchConfig {
+            paths: vec![dir.clone()],
+            debounce: Duration::from_millis(200),
+            ..Default::default()
+        });
+        assert!(!manager.check_for_changes().await.unwrap());
+
+        // A burst of writes, as from an editor save followed by a build
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}").unwrap();
+        assert!(!manager.check_for_changes().await.unwrap());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        std::fs::write(dir.join("worker"), "v2").unwrap();
+        assert!(!manager.check_for_changes().await.unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!manager.check_for_changes().await.unwrap());
+
+        // Quiet for the whole window: reported exactly once
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(manager.check_for_changes().await.unwrap());
+        assert!(!manager.check_for_changes().await.unwrap());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_only_watched_extensions_outside_target_count() {
+        let dir = watched_project("extensions");
+        let mut manager = ReloadManager::new(dir.join("worker")).with_watch(WatchConfig {
+            paths: vec![dir.clone()],
+            debounce: Duration::ZERO,
+            ..Default::default()
+        });
+        assert!(!manager.check_for_changes().await.unwrap());
+
+        std::fs::write(dir.join("src/notes.md"), "draft").unwrap();
+        std::fs::write(dir.join("target/debug/build.rs"), "fn main() {}").unwrap();
+        assert!(!manager.check_for_changes().await.unwrap());
+
+        std::fs::write(dir.join("Cargo.toml"), "[package]").unwrap();
+        assert!(manager.check_for_changes().await.unwrap());
+
+        std::fs::remove_file(dir.join("Cargo.toml")).unwrap();
+        assert!(manager.check_for_changes().await.unwrap());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    fn sleeping_worker(name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("splice-reload-{}-{}.sh", name, std::process::id()));
+        std::fs::write(&path, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn export(name: &str) -> ExportMetadata {
+        ExportMetadata {
+            name: name.to_string(),
+            is_async: true,
+            is_streaming: false,
+            params_schema: String::new(),
+            return_schema: String::new(),
+        }
+    }
+
+    type WorkerStream = std::pin::Pin<Box<dyn Stream<Item = Result<Message, ProtocolError>> + Send>>;
+
+    /// Router talking to the old worker over `tx`
+    async fn router_with_worker(tx: mpsc::Sender<Message>) -> Arc<Router> {
+        let mut router = Router::new(RouterConfig::default());
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("user.get")]).await;
+        router
+    }
+
+    fn invoke(router: &Arc<Router>) -> JoinHandle<Result<Bytes, crate::router::RouterError>> {
+        let router = router.clone();
+        tokio::spawn(async move { router.invoke("user.get".into(), Bytes::new(), 0, RequestContext::default()).await })
+    }
+
+    async fn next_invoke(rx: &mut mpsc::Receiver<Message>) -> u64 {
+        loop {
+            match rx.recv().await {
+                Some(Message::Invoke { request_id, .. }) => return request_id,
+                Some(_) => continue,
+                None => panic!("worker channel closed"),
+            }
+        }
+    }
+
+    fn reply(request_id: u64, body: &'static [u8]) -> Message {
+        Message::InvokeResult {
+            request_id,
+            result: Bytes::from_static(body),
+            duration_us: 0,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failed_reload_keeps_old_worker_serving() {
+        let worker = sleeping_worker("failed");
+        let mut supervisor = Supervisor::new(SupervisorConfig::default(), worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        let old_pid = supervisor.start().await.unwrap().pid;
+        let (old_tx, mut old_rx) = mpsc::channel(8);
+        let router = router_with_worker(old_tx).await;
+        let manager = ReloadManager::new(worker.clone());
+        let config = ReloadConfig {
+            ready_timeout: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        let refused = async { Err::<ReadyWorker<WorkerStream>, _>(ReloadError::NotReady("bad handshake".into())) };
+        let result = manager.perform_reload(&mut supervisor, &router, &config, refused).await;
+        assert!(matches!(result, Err(ReloadError::NotReady(_))));
+
+        let silent = futures::future::pending::<Result<ReadyWorker<WorkerStream>, ReloadError>>();
+        let result = manager.perform_reload(&mut supervisor, &router, &config, silent).await;
+        assert!(matches!(result, Err(ReloadError::ReadyTimeout(_))));
+
+        // Traffic still reaches the old worker
+        assert_eq!(supervisor.worker_info().unwrap().pid, old_pid);
+        assert_eq!(router.worker_generation(), 0);
+        let call = invoke(&router);
+        let request_id = next_invoke(&mut old_rx).await;
+        router.handle_worker_message(reply(request_id, b"old")).await;
+        assert_eq!(call.await.unwrap().unwrap(), Bytes::from_static(b"old"));
+
+        supervisor.stop().await.unwrap();
+        let _ = std::fs::remove_file(worker);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_begin_reload_leaves_old_worker_serving_until_ready() {
+        let worker = sleeping_worker("pending");
+        let supervisor_config = SupervisorConfig {
+            shutdown_grace: Duration::from_millis(300),
+            ..Default::default()
+        };
+        let mut supervisor = Supervisor::new(supervisor_config, worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        let old_pid = supervisor.start().await.unwrap().pid;
+        let (old_tx, mut old_rx) = mpsc::channel(8);
+        supervisor.set_worker_tx(old_tx.clone());
+        let router = router_with_worker(old_tx).await;
+        let manager = ReloadManager::new(worker.clone());
+        let config = ReloadConfig {
+            shutdown_grace: Duration::from_millis(300),
+            ..Default::default()
+        };
+
+        let (connected_tx, connected_rx) = tokio::sync::oneshot::channel::<ReadyWorker<WorkerStream>>();
+        let connect = async move { connected_rx.await.map_err(|_| ReloadError::NotReady("gone".into())) };
+        let mut pending = manager.begin_reload(&mut supervisor, &config, connect).await.unwrap();
+        assert_ne!(pending.standby.pid, old_pid);
+
+        // The new worker has not connected, and the old one still answers
+        let call = invoke(&router);
+        let request_id = next_invoke(&mut old_rx).await;
+        router.handle_worker_message(reply(request_id, b"old")).await;
+        assert_eq!(call.await.unwrap().unwrap(), Bytes::from_static(b"old"));
+        assert!(tokio::time::timeout(Duration::from_millis(20), pending.ready()).await.is_err());
+
+        let (new_tx, _new_rx) = mpsc::channel(8);
+        let sent = connected_tx.send(ReadyWorker {
+            worker_tx: new_tx,
+            worker_read: Box::pin(futures::stream::pending()),
+            exports: vec![export("user.get")],
+            max_frame_size: 1024 * 1024,
+        });
+        assert!(sent.is_ok());
+        let ready = pending.ready().await;
+        let reloaded = manager
+            .finish_reload(&mut supervisor, &router, &config, pending.standby, ready)
+            .await
+            .unwrap();
+        assert_eq!(router.worker_generation(), 1);
+        assert_eq!(supervisor.worker_info().unwrap().pid, reloaded.worker.pid);
+
+        reloaded.reader.abort();
+        tokio::time::timeout(Duration::from_secs(10), reloaded.retirement).await.unwrap().unwrap();
+        supervisor.stop().await.unwrap();
+        let _ = std::fs::remove_file(worker);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reload_swaps_worker_then_drains_old() {
+        let worker = sleeping_worker("swap");
+        let supervisor_config = SupervisorConfig {
+            shutdown_grace: Duration::from_millis(300),
+            ..Default::default()
+        };
+        let mut supervisor = Supervisor::new(supervisor_config, worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        let old_pid = supervisor.start().await.unwrap().pid;
+        let (old_tx, mut old_rx) = mpsc::channel(8);
+        supervisor.set_worker_tx(old_tx.clone());
+        let router = router_with_worker(old_tx).await;
+        let manager = ReloadManager::new(worker.clone());
+        let config = ReloadConfig {
+            shutdown_grace: Duration::from_millis(300),
+            ..Default::default()
+        };
+
+        // A request is in flight on the old worker when the reload starts
+        let old_call = invoke(&router);
+        let old_request = next_invoke(&mut old_rx).await;
+
+        let (new_tx, mut new_rx) = mpsc::channel(8);
+        let (reply_tx, mut reply_rx) = mpsc::channel::<Result<Message, ProtocolError>>(8);
+        let worker_read: WorkerStream = Box::pin(futures::stream::poll_fn(move |cx| reply_rx.poll_recv(cx)));
+        let ready = async move {
+            Ok(ReadyWorker {
+                worker_tx: new_tx,
+                worker_read,
+                exports: vec![export("user.get"), export("user.list")],
+                max_frame_size: 1024 * 1024,
+            })
+        };
+        let reloaded = manager.perform_reload(&mut supervisor, &router, &config, ready).await.unwrap();
+        assert_ne!(reloaded.worker.pid, old_pid);
+        assert_eq!(supervisor.worker_info().unwrap().pid, reloaded.worker.pid);
+        assert_eq!(router.worker_generation(), 1);
+        assert!(router.resolve_export("user.list").await.is_some());
+
+        // New invokes go to the new worker and are answered through its reader
+        let new_call = invoke(&router);
+        let new_request = next_invoke(&mut new_rx).await;
+        reply_tx.send(Ok(reply(new_request, b"new"))).await.unwrap();
+        assert_eq!(new_call.await.unwrap().unwrap(), Bytes::from_static(b"new"));
+
+        // The old worker is only stopped once its request is answered
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!reloaded.retirement.is_finished());
+        router.handle_worker_message(reply(old_request, b"old")).await;
+        assert_eq!(old_call.await.unwrap().unwrap(), Bytes::from_static(b"old"));
+        tokio::time::timeout(Duration::from_secs(5), reloaded.retirement).await.unwrap().unwrap();
+        assert!(matches!(old_rx.recv().await, Some(Message::Shutdown)));
+
+        // Losing the new worker's connection is reported as losing the active worker
+        drop(reply_tx);
+        assert!(tokio::time::timeout(Duration::from_secs(1), reloaded.reader).await.unwrap().unwrap());
+
+        supervisor.stop().await.unwrap();
+        let _ = std::fs::remove_file(worker);
+    }
 }
@@ -1,12 +1,52 @@
-use crate::protocol::{Message, ErrorKind, ExportMetadata, ERR_TIMEOUT, ERR_OVERLOADED, ERR_CANCELLED};
+use crate::protocol::{Message, ErrorKind, ExportMetadata, ProtocolError, ERR_TIMEOUT, ERR_OVERLOADED, ERR_CANCELLED, ERR_EXECUTION_FAILED, ERR_INVALID_REQUEST, PRIORITY_NORMAL};
+use crate::admin::{self, AdminReply};
+use crate::balancer::{StickyConfig, WorkerBalancer};
+use crate::precision::{self, IntegerPolicy};
+use crate::priority::{AdmissionPermit, AdmissionQueue};
+use crate::supervisor::Heartbeat;
+use crate::upload::{UploadError, UploadSender, DEFAULT_UPLOAD_WINDOW};
 use bytes::Bytes;
-use std::collections::HashMap;
+use futures::stream::{Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::sync::{mpsc, oneshot, Notify, RwLock};
 use tokio::time::timeout;
-use tracing::{debug, warn};
+use tracing::{debug, error, info, trace, warn, Level};
+
+/// `LogEvent` field keys recorded as tracing fields of the same name
+///
+/// `pid` and `stream` are what the supervisor attaches to worker output;
+/// `request_id` and `function` tie a worker's own events to an invoke.
+pub const WORKER_LOG_FIELDS: &[&str] = &["request_id", "function", "pid", "stream"];
+
+/// `msg`, a stream frame or ack, relabelled with `request_id`
+fn relabel_stream(msg: Message, request_id: u64) -> Message {
+    match msg {
+        Message::StreamStart { window, .. } => Message::StreamStart { request_id, window },
+        Message::StreamChunk { sequence, data, .. } => Message::StreamChunk { request_id, sequence, data },
+        Message::StreamEnd { total_chunks, .. } => Message::StreamEnd { request_id, total_chunks },
+        Message::StreamError { code, message, .. } => Message::StreamError { request_id, code, message },
+        Message::StreamAck { ack_sequence, window, .. } => Message::StreamAck { request_id, ack_sequence, window },
+        other => other,
+    }
+}
+
+/// Tracing level for a `LogEvent` level name, case-insensitively
+///
+/// `WARNING` is accepted for `WARN`; anything unrecognized is `INFO`.
+pub fn parse_log_level(level: &str) -> Level {
+    match level.trim().to_ascii_uppercase().as_str() {
+        "ERROR" => Level::ERROR,
+        "WARN" | "WARNING" => Level::WARN,
+        "DEBUG" => Level::DEBUG,
+        "TRACE" => Level::TRACE,
+        _ => Level::INFO,
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum RouterError {
@@ -22,69 +62,744 @@ pub enum RouterError {
     #[error("Worker not available")]
     WorkerUnavailable,
 
+    #[error("Worker reloading, retry after {}ms", retry_after.as_millis())]
+    Reloading { retry_after: Duration },
+
+    #[error("Function not allowed: {0}")]
+    Unauthorized(String),
+
+    #[error("Function not found: {0}")]
+    FunctionNotFound(String),
+
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+
+    #[error("Duplicate request id {0} is already in flight")]
+    DuplicateRequestId(u64),
+
+    #[error("Params of {size} bytes exceed the worker's {limit} byte frame limit")]
+    FrameTooLarge { size: usize, limit: u32 },
+
     #[error("Execution error: {0}")]
     ExecutionError(String),
 }
 
+/// Context header carrying the function name the caller asked for when an
+/// invoke was routed to a pattern export
+pub const INVOKED_NAME_HEADER: &str = "x-splice-invoked-name";
+
+/// Name batches are counted under for per-function limits and timeouts
+///
+/// Not a valid export name, so it never shares a limit with a real export.
+pub const BATCH_FUNCTION_NAME: &str = "<batch>";
+
+/// Which exports hosts may invoke
+///
+/// Entries are export names in which `*` matches any run of characters,
+/// e.g. `admin.*` or `debug_*`.
+#[derive(Debug, Clone, Default)]
+pub enum ExportPolicy {
+    /// Every export is callable
+    #[default]
+    AllowAll,
+    /// Only matching exports are callable
+    Allow(Vec<String>),
+    /// Matching exports are blocked
+    Deny(Vec<String>),
+}
+
+impl ExportPolicy {
+    /// Whether `name` may be invoked
+    pub fn is_allowed(&self, name: &str) -> bool {
+        match self {
+            ExportPolicy::AllowAll => true,
+            ExportPolicy::Allow(patterns) => patterns.iter().any(|p| glob_match(p, name)),
+            ExportPolicy::Deny(patterns) => !patterns.iter().any(|p| glob_match(p, name)),
+        }
+    }
+}
+
+/// Match `name` against a pattern where `*` matches any run of characters
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Load shedding driven by the worker's `HealthStatus` reports
+///
+/// Once `sustained_reports` consecutive reports show `active_requests` at or
+/// above `ceiling`, new invokes fail with `Overloaded` without reaching the
+/// worker. Admission resumes on the first report below `resume_below`.
+#[derive(Debug, Clone)]
+pub struct LoadShedConfig {
+    /// How often `HealthCheck` is sent to the worker
+    pub poll_interval: Duration,
+    /// Worker `active_requests` considered saturated
+    pub ceiling: u32,
+    /// Worker `active_requests` below which admission resumes
+    pub resume_below: u32,
+    /// Consecutive saturated reports before shedding starts
+    pub sustained_reports: u32,
+}
+
+impl LoadShedConfig {
+    /// Shed at `ceiling`, resuming below three quarters of it
+    pub fn new(ceiling: u32) -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            ceiling,
+            resume_below: ceiling - ceiling / 4,
+            sustained_reports: 3,
+        }
+    }
+}
+
+/// Shedding state derived from health reports
+#[derive(Debug, Default)]
+struct HealthGate {
+    saturated_reports: u32,
+    shedding: bool,
+}
+
+impl HealthGate {
+    /// Record a report, returning whether the shedding state changed
+    fn record(&mut self, config: &LoadShedConfig, active_requests: u32) -> bool {
+        let was_shedding = self.shedding;
+        if active_requests >= config.ceiling {
+            self.saturated_reports = self.saturated_reports.saturating_add(1);
+            if self.saturated_reports >= config.sustained_reports.max(1) {
+                self.shedding = true;
+            }
+        } else {
+            self.saturated_reports = 0;
+            if active_requests < config.resume_below {
+                self.shedding = false;
+            }
+        }
+        self.shedding != was_shedding
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RouterConfig {
     pub max_concurrent_requests: usize,
+    /// Invokes that may wait, highest priority first, for one of the
+    /// `max_concurrent_requests` slots; beyond this the lowest priority is
+    /// shed with `Overloaded` (0 rejects as soon as every slot is taken)
+    pub max_queued_requests: usize,
+    /// Longest a queued invoke waits for a slot before it is shed with
+    /// `Overloaded` (`None` waits until the request's own timeout)
+    pub admission_grace: Option<Duration>,
     pub max_concurrent_per_function: usize,
     pub default_timeout: Duration,
+    /// Exports hosts may invoke, checked before the worker is contacted
+    pub export_policy: ExportPolicy,
+    /// Omit blocked exports from `get_exports`
+    pub hide_blocked_exports: bool,
+    /// Handling of integers a JS host cannot represent exactly
+    pub integer_policy: IntegerPolicy,
+    /// Shed invokes while the worker reports saturation (disabled if `None`)
+    pub load_shed: Option<LoadShedConfig>,
+    /// Timeouts for individual exports, replacing `default_timeout`
+    pub function_timeouts: HashMap<String, Duration>,
+    /// During a reload, fail invokes fast with `Reloading` and this retry
+    /// hint instead of letting them reach a worker that is going away
+    /// (disabled if `None`)
+    pub reload_retry_after: Option<Duration>,
+    /// Least severe `LogEvent` from the worker that is re-emitted through
+    /// `tracing`; anything more verbose is dropped
+    pub worker_log_level: Level,
+    /// Route matching functions to pooled workers by a sticky key (see
+    /// [`Router::add_worker`])
+    pub sticky: Option<StickyConfig>,
+    /// Ramp a newly added pool worker's share of round-robin traffic up
+    /// over this window (disabled if `None`)
+    pub slow_start: Option<Duration>,
 }
 
 impl Default for RouterConfig {
     fn default() -> Self {
         Self {
             max_concurrent_requests: 1024,
+            max_queued_requests: 0,
+            admission_grace: None,
             max_concurrent_per_function: 100,
             default_timeout: Duration::from_secs(30),
+            export_policy: ExportPolicy::AllowAll,
+            hide_blocked_exports: false,
+            integer_policy: IntegerPolicy::Allow,
+            load_shed: None,
+            function_timeouts: HashMap::new(),
+            reload_retry_after: None,
+            worker_log_level: Level::INFO,
+            sticky: None,
+            slow_start: None,
+        }
+    }
+}
+
+impl RouterConfig {
+    /// How long an invoke of `function_name` may run
+    ///
+    /// A per-function override replaces `default_timeout`, and the caller's
+    /// `deadline_ms` wins only when it is stricter than the override. Without
+    /// an override a non-zero `deadline_ms` is used as given.
+    pub fn timeout_for(&self, function_name: &str, deadline_ms: u32) -> Duration {
+        let deadline = (deadline_ms > 0).then(|| Duration::from_millis(deadline_ms as u64));
+        match (self.function_timeouts.get(function_name), deadline) {
+            (Some(&limit), Some(deadline)) => limit.min(deadline),
+            (Some(&limit), None) => limit,
+            (None, Some(deadline)) => deadline,
+            (None, None) => self.default_timeout,
         }
     }
 }
 
+/// Worker added with [`Router::add_worker`]
+#[derive(Debug, Clone)]
+struct PoolWorker {
+    id: u64,
+    tx: mpsc::Sender<Message>,
+}
+
+impl PartialEq for PoolWorker {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for PoolWorker {}
+
+impl Hash for PoolWorker {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 #[derive(Debug)]
 struct PendingRequest {
     function_name: String,
     started_at: Instant,
+    /// Worker the request was sent to (see [`Router::swap_worker`])
+    generation: u64,
+    worker_tx: mpsc::Sender<Message>,
     response_tx: oneshot::Sender<Message>,
+    streams: Streams,
+    /// Set once the worker opened a response stream
+    response_started: bool,
+    /// Global concurrency slot, freed with the request
+    _permit: AdmissionPermit,
+}
+
+/// Stream channels registered with a pending request
+#[derive(Debug, Default)]
+struct Streams {
+    /// Receives `StreamAck`/`StreamError` for an upload
+    upload: Option<mpsc::Sender<Message>>,
+    /// Receives the worker's response stream frames; unbounded, as the
+    /// stream's window already limits what the worker sends ahead
+    response: Option<mpsc::UnboundedSender<Message>>,
+}
+
+/// One segment of a pattern export name
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Must match exactly
+    Literal(String),
+    /// `{name}`: matches any single segment
+    Param,
+}
+
+/// Export registered under a pattern name
+///
+/// Names are split on `.`. A segment written `{name}` matches any single
+/// segment, and a trailing `*` matches one or more remaining segments:
+/// `user.{id}` matches `user.get`, `user.*` matches `user.get` and
+/// `user.profile.get`.
+#[derive(Debug, Clone)]
+struct ExportPattern {
+    name: String,
+    segments: Vec<Segment>,
+    trailing_wildcard: bool,
+}
+
+impl ExportPattern {
+    /// Parse an export name, returning `None` for plain (exact) names
+    fn parse(name: &str) -> Option<Self> {
+        if !name.contains('*') && !name.contains('{') {
+            return None;
+        }
+
+        let mut parts: Vec<&str> = name.split('.').collect();
+        let trailing_wildcard = parts.last() == Some(&"*");
+        if trailing_wildcard {
+            parts.pop();
+        }
+
+        let mut segments = Vec::with_capacity(parts.len());
+        for part in parts {
+            if part.starts_with('{') && part.ends_with('}') && part.len() > 2 {
+                segments.push(Segment::Param);
+            } else if part.contains('*') || part.contains('{') || part.contains('}') {
+                warn!("Ignoring malformed export pattern '{}'", name);
+                return None;
+            } else {
+                segments.push(Segment::Literal(part.to_string()));
+            }
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            segments,
+            trailing_wildcard,
+        })
+    }
+
+    fn matches(&self, function_name: &str) -> bool {
+        let parts: Vec<&str> = function_name.split('.').collect();
+        let fixed = self.segments.len();
+
+        let length_ok = if self.trailing_wildcard {
+            parts.len() > fixed
+        } else {
+            parts.len() == fixed
+        };
+        if !length_ok {
+            return false;
+        }
+
+        self.segments.iter().zip(&parts).all(|(segment, part)| match segment {
+            Segment::Literal(literal) => literal == part,
+            Segment::Param => !part.is_empty(),
+        })
+    }
+
+    /// Ordering key: more literal segments first, then more fixed segments,
+    /// then parameters over a trailing wildcard, then literals further left
+    fn specificity(&self) -> (usize, usize, bool, Vec<bool>) {
+        let shape: Vec<bool> = self
+            .segments
+            .iter()
+            .map(|s| matches!(s, Segment::Literal(_)))
+            .collect();
+        let literals = shape.iter().filter(|&&literal| literal).count();
+        (literals, self.segments.len(), !self.trailing_wildcard, shape)
+    }
 }
 
 pub struct Router {
     config: RouterConfig,
     exports: Arc<RwLock<HashMap<String, ExportMetadata>>>,
+    /// Pattern exports, most specific first
+    patterns: Arc<RwLock<Vec<ExportPattern>>>,
     pending: Arc<RwLock<HashMap<u64, PendingRequest>>>,
     function_counts: Arc<RwLock<HashMap<String, usize>>>,
     next_request_id: Arc<RwLock<u64>>,
-    worker_tx: Option<mpsc::Sender<Message>>,
+    /// Connection to the active worker, replaced by a blue/green reload
+    worker_tx: std::sync::RwLock<Option<mpsc::Sender<Message>>>,
+    /// Workers sharing the invoke load; when non-empty, invokes go to one
+    /// of these instead of `worker_tx`
+    pool: std::sync::RwLock<WorkerBalancer<PoolWorker>>,
+    next_worker_id: AtomicU64,
+    /// Bumped whenever a new worker takes over
+    worker_generation: AtomicU64,
+    health: std::sync::Mutex<HealthGate>,
+    /// Host request IDs currently in flight, keyed by host connection
+    host_requests: Arc<std::sync::Mutex<HashSet<(u64, u64)>>>,
+    next_host_id: AtomicU64,
+    /// Callers awaiting `AdminResult`s, keyed by admin request ID
+    admin_waiters: std::sync::Mutex<HashMap<u64, oneshot::Sender<Bytes>>>,
+    next_admin_id: AtomicU64,
+    /// Cleared while the worker connection is down
+    worker_connected: AtomicBool,
+    /// Set between `begin_reload` and `end_reload`
+    reloading: AtomicBool,
+    /// Global concurrency slots, handed out by priority
+    admission: AdmissionQueue,
+    /// Invokes admitted since startup
+    total_requests: AtomicU64,
+    /// Frame limit negotiated with the worker
+    worker_max_frame_size: AtomicU32,
+    /// Told about every `HealthStatus`, so the supervisor can spot a hung
+    /// worker
+    heartbeat: Option<Arc<Heartbeat>>,
+}
+
+/// Where [`Router::invoke_streaming`] relays a worker's response stream
+///
+/// The worker's `StreamStart`, `StreamChunk`, `StreamEnd` and `StreamError`
+/// go to `frames` relabelled with `request_id`, so they can be written
+/// straight to the caller's connection. The caller's `StreamAck`s (and a
+/// `StreamError` aborting the stream) are fed in through `acks`.
+#[derive(Debug)]
+pub struct ResponseRelay {
+    /// Request ID the relayed frames carry
+    pub request_id: u64,
+    pub frames: mpsc::Sender<Message>,
+    pub acks: mpsc::Receiver<Message>,
+}
+
+/// How a [`Router::invoke_upload`] is admitted and paced
+#[derive(Debug, Clone, Copy)]
+pub struct UploadOptions {
+    /// Admission priority (see [`Router::invoke_with_priority`])
+    pub priority: u8,
+    /// Chunks the worker lets the host send ahead of its acks
+    pub window: u32,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            priority: PRIORITY_NORMAL,
+            window: DEFAULT_UPLOAD_WINDOW,
+        }
+    }
+}
+
+/// A request registered by [`Router::admit`], ready to send
+struct Admitted {
+    request_id: u64,
+    /// The export to invoke
+    function_name: String,
+    /// The caller's context, possibly annotated
+    context: crate::protocol::RequestContext,
+    response_rx: oneshot::Receiver<Message>,
+    /// The worker the request belongs to
+    worker_tx: mpsc::Sender<Message>,
+}
+
+/// Reservation of a host request ID, released when dropped
+#[derive(Debug)]
+pub struct RequestIdClaim {
+    host_id: u64,
+    request_id: u64,
+    host_requests: Arc<std::sync::Mutex<HashSet<(u64, u64)>>>,
+}
+
+impl RequestIdClaim {
+    /// The claimed host request ID
+    pub fn request_id(&self) -> u64 {
+        self.request_id
+    }
+}
+
+impl Drop for RequestIdClaim {
+    fn drop(&mut self) {
+        if let Ok(mut ids) = self.host_requests.lock() {
+            ids.remove(&(self.host_id, self.request_id));
+        }
+    }
 }
 
 impl Router {
     pub fn new(config: RouterConfig) -> Self {
+        let admission = AdmissionQueue::new(config.max_concurrent_requests, config.max_queued_requests);
+        let mut pool = WorkerBalancer::new(config.sticky.clone());
+        if let Some(window) = config.slow_start {
+            pool = pool.with_slow_start(window);
+        }
         Self {
             config,
             exports: Arc::new(RwLock::new(HashMap::new())),
+            patterns: Arc::new(RwLock::new(Vec::new())),
             pending: Arc::new(RwLock::new(HashMap::new())),
             function_counts: Arc::new(RwLock::new(HashMap::new())),
             next_request_id: Arc::new(RwLock::new(1)),
-            worker_tx: None,
+            worker_tx: std::sync::RwLock::new(None),
+            pool: std::sync::RwLock::new(pool),
+            next_worker_id: AtomicU64::new(1),
+            worker_generation: AtomicU64::new(0),
+            health: std::sync::Mutex::new(HealthGate::default()),
+            host_requests: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            next_host_id: AtomicU64::new(1),
+            admin_waiters: std::sync::Mutex::new(HashMap::new()),
+            next_admin_id: AtomicU64::new(1),
+            worker_connected: AtomicBool::new(true),
+            reloading: AtomicBool::new(false),
+            admission,
+            total_requests: AtomicU64::new(0),
+            worker_max_frame_size: AtomicU32::new(crate::protocol::DEFAULT_MAX_FRAME_SIZE),
+            heartbeat: None,
+        }
+    }
+
+    /// Forward an admin command to the worker and return its `AdminResult`
+    /// payload
+    ///
+    /// Commands outside [`admin::SAFE_COMMANDS`] are refused here without
+    /// reaching the worker. Each command carries its own request ID, so
+    /// concurrent callers get their own replies and a command the worker
+    /// never answers only times out its own caller.
+    pub async fn admin(&self, command: String, args: Bytes) -> Result<Bytes, RouterError> {
+        if !admin::is_safe(&command) {
+            warn!("Refused admin command '{}'", command);
+            return Ok(AdminReply::unknown_command(&command).encode());
+        }
+
+        let (_, worker_tx) = self.current_worker().ok_or(RouterError::WorkerUnavailable)?;
+        let request_id = self.next_admin_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.admin_waiters.lock().unwrap().insert(request_id, tx);
+
+        let sent = worker_tx
+            .send(Message::AdminCommand { request_id, command, args })
+            .await;
+        let result = match sent {
+            Err(_) => Err(RouterError::WorkerUnavailable),
+            Ok(()) => match timeout(self.config.default_timeout, rx).await {
+                Ok(Ok(data)) => Ok(data),
+                Ok(Err(_)) => Err(RouterError::WorkerUnavailable),
+                Err(_) => Err(RouterError::Timeout),
+            },
+        };
+        self.admin_waiters.lock().unwrap().remove(&request_id);
+        result
+    }
+
+    /// Invokes currently holding a concurrency slot, as reported in
+    /// `HealthStatus.active_requests`
+    pub fn active_requests(&self) -> usize {
+        self.admission.active()
+    }
+
+    /// Invokes admitted since the router was created
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests.load(Ordering::Relaxed)
+    }
+
+    /// Allocate an ID for a new host connection
+    ///
+    /// Hosts number their requests independently, so claims are scoped to
+    /// the connection they arrived on.
+    pub fn register_host(&self) -> u64 {
+        self.next_host_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Reserve a host request ID for the lifetime of its invocation
+    ///
+    /// Fails with `DuplicateRequestId` while another invocation from the
+    /// same host holds the ID, so a reused ID cannot take over the first
+    /// caller's reply. Other hosts may use the same ID concurrently. The ID
+    /// becomes reusable once the returned claim is dropped.
+    pub fn claim_request_id(&self, host_id: u64, request_id: u64) -> Result<RequestIdClaim, RouterError> {
+        if !self.host_requests.lock().unwrap().insert((host_id, request_id)) {
+            warn!("Rejected duplicate in-flight request id {} from host {}", request_id, host_id);
+            return Err(RouterError::DuplicateRequestId(request_id));
+        }
+        Ok(RequestIdClaim {
+            host_id,
+            request_id,
+            host_requests: self.host_requests.clone(),
+        })
+    }
+
+    /// Poll the worker's health so load shedding can react to it
+    ///
+    /// Returns `None` when load shedding is disabled. Checks go to whichever
+    /// worker is active; while no worker accepts them the poller keeps
+    /// ticking, so it survives a worker swap. It stops once the router is
+    /// dropped.
+    pub fn spawn_health_poller(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = self.config.load_shed.as_ref()?.poll_interval;
+        self.current_worker()?;
+        let router = Arc::downgrade(self);
+
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let Some(router) = router.upgrade() else {
+                    break;
+                };
+                let Some((_, worker_tx)) = router.current_worker() else {
+                    continue;
+                };
+                drop(router);
+                if worker_tx.send(Message::HealthCheck).await.is_err() {
+                    debug!("Health check skipped: worker channel closed");
+                }
+            }
+        }))
+    }
+
+    /// Whether invokes are currently being shed due to worker saturation
+    pub fn is_shedding(&self) -> bool {
+        self.health.lock().unwrap().shedding
+    }
+
+    fn record_health(&self, active_requests: u32) {
+        let Some(config) = &self.config.load_shed else {
+            return;
+        };
+
+        let mut gate = self.health.lock().unwrap();
+        if gate.record(config, active_requests) {
+            if gate.shedding {
+                warn!(
+                    "Worker saturated ({} active, ceiling {}), shedding new invokes",
+                    active_requests, config.ceiling
+                );
+            } else {
+                info!("Worker recovered ({} active), resuming admission", active_requests);
+            }
         }
     }
 
     pub fn set_worker_tx(&mut self, tx: mpsc::Sender<Message>) {
-        self.worker_tx = Some(tx);
+        *self.worker_tx.get_mut().unwrap() = Some(tx);
+    }
+
+    /// Make a newly connected worker the active one
+    ///
+    /// New invokes go to `tx` from now on, while requests already sent to
+    /// the previous worker keep waiting for its replies (see
+    /// [`Router::drain_worker`]). Shedding decided from the previous
+    /// worker's health is lifted. Returns the previous worker's generation.
+    pub fn swap_worker(&self, tx: mpsc::Sender<Message>) -> u64 {
+        let retired = {
+            let mut worker_tx = self.worker_tx.write().unwrap();
+            *worker_tx = Some(tx);
+            self.worker_generation.fetch_add(1, Ordering::AcqRel)
+        };
+        *self.health.lock().unwrap() = HealthGate::default();
+        self.worker_connected();
+        info!("Worker generation {} retired", retired);
+        retired
+    }
+
+    /// Generation of the active worker, bumped by [`Router::swap_worker`]
+    pub fn worker_generation(&self) -> u64 {
+        self.worker_generation.load(Ordering::Acquire)
+    }
+
+    fn current_worker(&self) -> Option<(u64, mpsc::Sender<Message>)> {
+        let worker_tx = self.worker_tx.read().unwrap();
+        let tx = worker_tx.clone()?;
+        Some((self.worker_generation.load(Ordering::Acquire), tx))
+    }
+
+    /// Add a worker to the pool invokes are spread over, returning its ID
+    ///
+    /// Once the pool has a worker, invokes are routed by the balancer
+    /// (sticky for the functions [`RouterConfig::sticky`] covers,
+    /// round-robin otherwise) instead of going to the active worker set
+    /// with [`Router::swap_worker`]. With [`RouterConfig::slow_start`] the
+    /// new worker's share ramps up from nothing; a restarted worker should
+    /// be removed and added again so it warms up the same way. Health
+    /// checks and admin commands still go to the active worker. The caller
+    /// feeds the worker's replies to [`Router::handle_worker_message`].
+    pub fn add_worker(&self, tx: mpsc::Sender<Message>) -> u64 {
+        let id = self.next_worker_id.fetch_add(1, Ordering::Relaxed);
+        self.pool.write().unwrap().add_worker(PoolWorker { id, tx });
+        info!("Worker {} joined the pool", id);
+        id
+    }
+
+    /// Take worker `id` out of the pool, failing the invokes still waiting
+    /// on it with `WorkerUnavailable`
+    ///
+    /// Sticky keys it owned move to the next workers on the ring; other
+    /// keys stay where they are. Returns the number of invokes failed.
+    pub async fn remove_worker(&self, id: u64) -> usize {
+        let removed = {
+            let mut pool = self.pool.write().unwrap();
+            let worker = pool.workers().iter().find(|worker| worker.id == id).cloned();
+            if let Some(worker) = &worker {
+                pool.remove_worker(worker);
+            }
+            worker
+        };
+        let Some(worker) = removed else {
+            return 0;
+        };
+
+        let failed = self.fail_pending(|pending| pending.worker_tx.same_channel(&worker.tx)).await;
+        info!("Worker {} left the pool ({} in-flight requests failed)", id, failed);
+        failed
+    }
+
+    /// Worker to send an invoke of `function_name` to: one picked from the
+    /// pool when it has workers, otherwise the active worker
+    fn select_worker(
+        &self,
+        function_name: &str,
+        context: &crate::protocol::RequestContext,
+    ) -> Option<(u64, mpsc::Sender<Message>)> {
+        {
+            let pool = self.pool.read().unwrap();
+            if !pool.workers().is_empty() {
+                let worker = pool.select(function_name, context)?;
+                return Some((self.worker_generation(), worker.tx.clone()));
+            }
+        }
+        self.current_worker().filter(|_| self.is_worker_connected())
+    }
+
+    /// Report worker `HealthStatus` replies to the supervisor's heartbeat
+    pub fn set_heartbeat(&mut self, heartbeat: Arc<Heartbeat>) {
+        self.heartbeat = Some(heartbeat);
     }
 
     pub async fn update_exports(&self, exports: Vec<ExportMetadata>) {
         let mut map = self.exports.write().await;
+        let mut patterns = self.patterns.write().await;
         map.clear();
+        patterns.clear();
         for export in exports {
+            if let Some(pattern) = ExportPattern::parse(&export.name) {
+                patterns.push(pattern);
+            }
             map.insert(export.name.clone(), export);
         }
+        patterns.sort_by_cached_key(|p| std::cmp::Reverse(p.specificity()));
+    }
+
+    /// Resolve the export that serves `function_name`
+    ///
+    /// An exact export always wins; otherwise the most specific matching
+    /// pattern export is used. Returns `None` when nothing matches.
+    pub async fn resolve_export(&self, function_name: &str) -> Option<String> {
+        if self.exports.read().await.contains_key(function_name) {
+            return Some(function_name.to_string());
+        }
+
+        self.patterns
+            .read()
+            .await
+            .iter()
+            .find(|pattern| pattern.matches(function_name))
+            .map(|pattern| pattern.name.clone())
     }
 
     pub async fn get_exports(&self) -> Vec<ExportMetadata> {
-        self.exports.read().await.values().cloned().collect()
+        self.exports
+            .read()
+            .await
+            .values()
+            .filter(|export| {
+                !self.config.hide_blocked_exports || self.config.export_policy.is_allowed(&export.name)
+            })
+            .cloned()
+            .collect()
     }
 
     pub async fn invoke(
@@ -94,117 +809,548 @@ impl Router {
         deadline_ms: u32,
         context: crate::protocol::RequestContext,
     ) -> Result<Bytes, RouterError> {
-        // Check global concurrency limit
-        let pending_count = self.pending.read().await.len();
-        if pending_count >= self.config.max_concurrent_requests {
-            warn!(
-                "Global concurrency limit exceeded: {}/{}",
-                pending_count, self.config.max_concurrent_requests
-            );
-            return Err(RouterError::Overloaded);
-        }
+        self.invoke_with_priority(function_name, params, deadline_ms, context, PRIORITY_NORMAL).await
+    }
 
-        // Check per-function concurrency limit
-        {
-            let counts = self.function_counts.read().await;
-            let func_count = counts.get(&function_name).copied().unwrap_or(0);
-            if func_count >= self.config.max_concurrent_per_function {
-                warn!(
-                    "Function concurrency limit exceeded for '{}': {}/{}",
-                    function_name, func_count, self.config.max_concurrent_per_function
-                );
-                return Err(RouterError::Overloaded);
-            }
-        }
+    /// Invoke a function at an admission priority
+    ///
+    /// When every concurrency slot is taken, higher priorities are admitted
+    /// first and lower ones are the first shed (see
+    /// [`RouterConfig::max_queued_requests`]).
+    pub async fn invoke_with_priority(
+        &self,
+        function_name: String,
+        params: Bytes,
+        deadline_ms: u32,
+        context: crate::protocol::RequestContext,
+        priority: u8,
+    ) -> Result<Bytes, RouterError> {
+        precision::check_params(&params, self.config.integer_policy)
+            .map_err(RouterError::InvalidParams)?;
+        self.check_frame_size(params.len())?;
+        let started = Instant::now();
+        let Admitted { request_id, function_name, context, response_rx, worker_tx } =
+            self.admit(function_name, context, priority, deadline_ms, Streams::default()).await?;
+        let timeout_duration = self.remaining_timeout(&function_name, deadline_ms, started);
 
-        // Allocate request ID
-        let request_id = {
-            let mut next_id = self.next_request_id.write().await;
-            let id = *next_id;
-            *next_id = next_id.wrapping_add(1);
-            id
+        // Send invoke message to worker
+        let invoke_msg = Message::Invoke {
+            request_id,
+            function_name,
+            params,
+            deadline_ms,
+            context,
+            priority,
         };
 
-        // Create response channel
-        let (response_tx, response_rx) = oneshot::channel();
-
-        // Register pending request
-        {
-            let mut pending = self.pending.write().await;
-            pending.insert(
-                request_id,
-                PendingRequest {
-                    function_name: function_name.clone(),
-                    started_at: Instant::now(),
-                    response_tx,
-                },
-            );
+        if worker_tx.send(invoke_msg).await.is_err() {
+            self.cleanup_request(request_id).await;
+            return Err(RouterError::WorkerUnavailable);
         }
 
-        // Increment function counter
-        {
-            let mut counts = self.function_counts.write().await;
-            *counts.entry(function_name.clone()).or_insert(0) += 1;
-        }
+        let response = async { response_rx.await.map_err(|_| RouterError::WorkerUnavailable) };
+        self.await_response(request_id, timeout_duration, response).await
+    }
 
-        // Send invoke message to worker
-        let worker_tx = self.worker_tx.as_ref()
-            .ok_or(RouterError::WorkerUnavailable)?;
+    /// Invoke a function whose response may be streamed back
+    ///
+    /// Like [`Router::invoke_with_priority`], but a response stream the
+    /// function opens (see [`crate::upload`] for the flow control) is
+    /// passed through `relay` while the call runs. Every relayed frame is
+    /// sent before this returns, so the caller can answer with the result
+    /// right after. A plain invoke instead refuses the stream, failing the
+    /// function's first send.
+    pub async fn invoke_streaming(
+        &self,
+        function_name: String,
+        params: Bytes,
+        deadline_ms: u32,
+        context: crate::protocol::RequestContext,
+        priority: u8,
+        relay: ResponseRelay,
+    ) -> Result<Bytes, RouterError> {
+        precision::check_params(&params, self.config.integer_policy)
+            .map_err(RouterError::InvalidParams)?;
+        self.check_frame_size(params.len())?;
+        let (frames_tx, mut frames_rx) = mpsc::unbounded_channel();
+        let streams = Streams { upload: None, response: Some(frames_tx) };
+        let started = Instant::now();
+        let Admitted { request_id, function_name, context, mut response_rx, worker_tx } =
+            self.admit(function_name, context, priority, deadline_ms, streams).await?;
+        let timeout_duration = self.remaining_timeout(&function_name, deadline_ms, started);
 
         let invoke_msg = Message::Invoke {
             request_id,
-            function_name: function_name.clone(),
+            function_name,
             params,
             deadline_ms,
             context,
+            priority,
         };
-
         if worker_tx.send(invoke_msg).await.is_err() {
             self.cleanup_request(request_id).await;
             return Err(RouterError::WorkerUnavailable);
         }
 
-        // Wait for response with timeout
-        let timeout_duration = if deadline_ms > 0 {
-            Duration::from_millis(deadline_ms as u64)
-        } else {
-            self.config.default_timeout
-        };
-
-        let result = timeout(timeout_duration, response_rx).await;
-
-        match result {
-            Ok(Ok(msg)) => {
-                self.cleanup_request(request_id).await;
-                match msg {
-                    Message::InvokeResult { result, .. } => Ok(result),
-                    Message::InvokeError { message, .. } => {
-                        Err(RouterError::ExecutionError(message))
+        let ResponseRelay { request_id: relay_id, frames, mut acks } = relay;
+        let response = async {
+            loop {
+                tokio::select! {
+                    biased;
+                    Some(frame) = frames_rx.recv() => {
+                        let _ = frames.send(relabel_stream(frame, relay_id)).await;
+                    }
+                    Some(ack) = acks.recv() => {
+                        let _ = worker_tx.send(relabel_stream(ack, request_id)).await;
+                    }
+                    response = &mut response_rx => {
+                        // The worker sent its frames ahead of the reply
+                        while let Ok(frame) = frames_rx.try_recv() {
+                            let _ = frames.send(relabel_stream(frame, relay_id)).await;
+                        }
+                        return response.map_err(|_| RouterError::WorkerUnavailable);
                     }
-                    _ => Err(RouterError::WorkerUnavailable),
                 }
             }
-            Ok(Err(_)) => {
-                // Response channel dropped
-                self.cleanup_request(request_id).await;
-                Err(RouterError::WorkerUnavailable)
-            }
-            Err(_) => {
-                // Timeout
-                self.send_cancel(request_id).await;
-                self.cleanup_request(request_id).await;
-                Err(RouterError::Timeout)
-            }
-        }
+        };
+        self.await_response(request_id, timeout_duration, response).await
     }
 
-    pub async fn handle_worker_message(&self, msg: Message) {
-        match msg {
-            Message::InvokeResult { request_id, .. }
-            | Message::InvokeError { request_id, .. } => {
-                if let Some(pending) = self.pending.write().await.remove(&request_id) {
-                    let _ = pending.response_tx.send(msg);
-                }
+    /// Invoke several functions in one `InvokeBatch` round trip
+    ///
+    /// Every call is checked before anything is sent, and the first one
+    /// refused by the export policy or not exported fails the whole batch.
+    /// So does a call only a pattern export serves: a batch carries no
+    /// per-call context to pass it [`INVOKED_NAME_HEADER`]. The batch takes
+    /// a single concurrency slot and is limited and timed under
+    /// [`BATCH_FUNCTION_NAME`].
+    ///
+    /// Results are in call order: each call's result, or the worker's
+    /// `(code, message)` for a call that failed.
+    pub async fn invoke_batch(
+        &self,
+        calls: Vec<(String, Bytes)>,
+        deadline_ms: u32,
+    ) -> Result<Vec<Result<Bytes, (u16, String)>>, RouterError> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut routed = Vec::with_capacity(calls.len());
+        let mut size = 0;
+        for (function_name, params) in calls {
+            precision::check_params(&params, self.config.integer_policy)
+                .map_err(RouterError::InvalidParams)?;
+            let mut context = crate::protocol::RequestContext::default();
+            let function_name = self.route_export(function_name, &mut context).await?;
+            if let Some(invoked) = context.header(INVOKED_NAME_HEADER) {
+                return Err(RouterError::InvalidParams(format!(
+                    "'{}' is served by pattern export '{}', which can't be batched",
+                    invoked, function_name
+                )));
+            }
+            size += function_name.len() + params.len();
+            routed.push((function_name, params));
+        }
+        self.check_frame_size(size)?;
+
+        let started = Instant::now();
+        let Admitted { request_id, response_rx, worker_tx, .. } = self
+            .admit_routed(
+                BATCH_FUNCTION_NAME.to_string(),
+                crate::protocol::RequestContext::default(),
+                PRIORITY_NORMAL,
+                deadline_ms,
+                Streams::default(),
+            )
+            .await?;
+        let timeout_duration = self.remaining_timeout(BATCH_FUNCTION_NAME, deadline_ms, started);
+
+        if worker_tx.send(Message::InvokeBatch { request_id, calls: routed }).await.is_err() {
+            self.cleanup_request(request_id).await;
+            return Err(RouterError::WorkerUnavailable);
+        }
+
+        let response = async { response_rx.await.map_err(|_| RouterError::WorkerUnavailable) };
+        match self.await_reply(request_id, timeout_duration, response).await? {
+            Message::InvokeBatchResult { results, .. } => Ok(results
+                .into_iter()
+                .map(|result| {
+                    result.and_then(|result| {
+                        precision::apply_to_result(result, self.config.integer_policy)
+                            .map_err(|e| (ERR_EXECUTION_FAILED, e))
+                    })
+                })
+                .collect()),
+            Message::InvokeError { message, .. } => Err(RouterError::ExecutionError(message)),
+            _ => Err(RouterError::WorkerUnavailable),
+        }
+    }
+
+    /// Invoke a function whose request body is streamed to the worker
+    ///
+    /// Chunks received from `body` are forwarded as an upload (see
+    /// [`crate::upload`]) until the channel closes. An `Err` from `body`
+    /// aborts the upload with `StreamError` instead of ending it. The
+    /// timeout (see [`RouterConfig::timeout_for`]) covers the whole upload
+    /// and the response.
+    pub async fn invoke_upload(
+        &self,
+        function_name: String,
+        params: Bytes,
+        deadline_ms: u32,
+        context: crate::protocol::RequestContext,
+        options: UploadOptions,
+        mut body: mpsc::Receiver<Result<Bytes, UploadError>>,
+    ) -> Result<Bytes, RouterError> {
+        let UploadOptions { priority, window } = options;
+        precision::check_params(&params, self.config.integer_policy)
+            .map_err(RouterError::InvalidParams)?;
+        self.check_frame_size(params.len())?;
+        // One ack per chunk in flight plus a closing StreamError
+        let (ack_tx, ack_rx) = mpsc::channel(window.max(1) as usize + 1);
+        let started = Instant::now();
+        let Admitted { request_id, function_name, context, mut response_rx, worker_tx } =
+            self.admit(function_name, context, priority, deadline_ms, Streams { upload: Some(ack_tx), response: None }).await?;
+        let timeout_duration = self.remaining_timeout(&function_name, deadline_ms, started);
+
+        let upload = async move {
+            let mut sender = UploadSender::start(request_id, window, worker_tx.clone(), ack_rx).await?;
+            worker_tx
+                .send(Message::Invoke {
+                    request_id,
+                    function_name,
+                    params,
+                    deadline_ms,
+                    context,
+                    priority,
+                })
+                .await
+                .map_err(|_| UploadError::Closed)?;
+
+            while let Some(chunk) = body.recv().await {
+                match chunk {
+                    Ok(chunk) => sender.send(chunk).await?,
+                    Err(e) => return Err(sender.abort(e).await),
+                }
+            }
+            sender.finish().await
+        };
+
+        // The worker may answer before the upload completes (e.g. on error)
+        let response = async {
+            tokio::select! {
+                uploaded = upload => {
+                    match uploaded {
+                        Ok(chunks) => debug!("Upload {} complete ({} chunks)", request_id, chunks),
+                        Err(UploadError::Closed) => return Err(RouterError::WorkerUnavailable),
+                        Err(e) => return Err(RouterError::ExecutionError(e.to_string())),
+                    }
+                    (&mut response_rx).await.map_err(|_| RouterError::WorkerUnavailable)
+                }
+                response = &mut response_rx => response.map_err(|_| RouterError::WorkerUnavailable),
+            }
+        };
+        self.await_response(request_id, timeout_duration, response).await
+    }
+
+    /// Apply the export policy and concurrency limits, then register a
+    /// pending request
+    ///
+    /// Waiting for a global concurrency slot counts against the request's
+    /// timeout.
+    async fn admit(
+        &self,
+        function_name: String,
+        mut context: crate::protocol::RequestContext,
+        priority: u8,
+        deadline_ms: u32,
+        streams: Streams,
+    ) -> Result<Admitted, RouterError> {
+        let function_name = self.route_export(function_name, &mut context).await?;
+        self.admit_routed(function_name, context, priority, deadline_ms, streams).await
+    }
+
+    /// Apply the export policy and resolve the export serving `function_name`
+    ///
+    /// A call routed to a pattern export has the invoked name recorded in
+    /// `context`.
+    async fn route_export(
+        &self,
+        function_name: String,
+        context: &mut crate::protocol::RequestContext,
+    ) -> Result<String, RouterError> {
+        if !self.config.export_policy.is_allowed(&function_name) {
+            warn!("Blocked invoke of '{}' by export policy", function_name);
+            return Err(RouterError::Unauthorized(function_name));
+        }
+
+        // Route to a pattern export when there is no exact match, telling
+        // the handler which name was invoked
+        let function_name = match self.resolve_export(&function_name).await {
+            Some(export) if export != function_name => {
+                if !self.config.export_policy.is_allowed(&export) {
+                    warn!("Blocked invoke of '{}' via export '{}'", function_name, export);
+                    return Err(RouterError::Unauthorized(function_name));
+                }
+                debug!("Routing '{}' to pattern export '{}'", function_name, export);
+                context.set_header(INVOKED_NAME_HEADER, function_name);
+                export
+            }
+            Some(_) => function_name,
+            None => {
+                debug!("Rejecting invoke of unknown function '{}'", function_name);
+                return Err(RouterError::FunctionNotFound(function_name));
+            }
+        };
+        Ok(function_name)
+    }
+
+    /// Apply the concurrency limits to a routed call, then register a
+    /// pending request
+    async fn admit_routed(
+        &self,
+        function_name: String,
+        context: crate::protocol::RequestContext,
+        priority: u8,
+        deadline_ms: u32,
+        streams: Streams,
+    ) -> Result<Admitted, RouterError> {
+        if let (true, Some(retry_after)) = (self.is_reloading(), self.config.reload_retry_after) {
+            debug!("Deferring invoke of '{}': worker reloading", function_name);
+            return Err(RouterError::Reloading { retry_after });
+        }
+
+        if !self.is_worker_connected() {
+            debug!("Rejecting invoke of '{}': worker disconnected", function_name);
+            return Err(RouterError::WorkerUnavailable);
+        }
+
+        if self.is_shedding() {
+            debug!("Shedding invoke of '{}': worker saturated", function_name);
+            return Err(RouterError::Overloaded);
+        }
+
+        // Take a global concurrency slot, queueing by priority when full
+        let queue_timeout = self.config.timeout_for(&function_name, deadline_ms);
+        let grace = self.config.admission_grace.filter(|grace| *grace < queue_timeout);
+        let permit = match timeout(grace.unwrap_or(queue_timeout), self.admission.acquire(priority)).await {
+            Ok(Ok(permit)) => permit,
+            Ok(Err(_)) => {
+                warn!(
+                    "Global concurrency limit exceeded: {}/{} ({} queued, priority {})",
+                    self.admission.active(),
+                    self.config.max_concurrent_requests,
+                    self.admission.queued(),
+                    priority
+                );
+                return Err(RouterError::Overloaded);
+            }
+            Err(_) if grace.is_some() => {
+                warn!(
+                    "No slot freed within {:?} for '{}', shedding ({} active)",
+                    self.config.admission_grace.unwrap_or_default(),
+                    function_name,
+                    self.admission.active()
+                );
+                return Err(RouterError::Overloaded);
+            }
+            Err(_) => {
+                debug!("Invoke of '{}' timed out waiting for a slot", function_name);
+                return Err(RouterError::Timeout);
+            }
+        };
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+
+        // The worker may have gone away (or been replaced) while this
+        // request was queued
+        let (generation, worker_tx) = self
+            .select_worker(&function_name, &context)
+            .ok_or(RouterError::WorkerUnavailable)?;
+
+        // Check per-function concurrency limit
+        {
+            let counts = self.function_counts.read().await;
+            let func_count = counts.get(&function_name).copied().unwrap_or(0);
+            if func_count >= self.config.max_concurrent_per_function {
+                warn!(
+                    "Function concurrency limit exceeded for '{}': {}/{}",
+                    function_name, func_count, self.config.max_concurrent_per_function
+                );
+                return Err(RouterError::Overloaded);
+            }
+        }
+
+        // Allocate request ID
+        let request_id = {
+            let mut next_id = self.next_request_id.write().await;
+            let id = *next_id;
+            *next_id = next_id.wrapping_add(1);
+            id
+        };
+
+        // Create response channel
+        let (response_tx, response_rx) = oneshot::channel();
+
+        // Register pending request
+        {
+            let mut pending = self.pending.write().await;
+            pending.insert(
+                request_id,
+                PendingRequest {
+                    function_name: function_name.clone(),
+                    started_at: Instant::now(),
+                    generation,
+                    worker_tx: worker_tx.clone(),
+                    response_tx,
+                    streams,
+                    response_started: false,
+                    _permit: permit,
+                },
+            );
+        }
+
+        // Increment function counter
+        {
+            let mut counts = self.function_counts.write().await;
+            *counts.entry(function_name.clone()).or_insert(0) += 1;
+        }
+
+        Ok(Admitted {
+            request_id,
+            function_name,
+            context,
+            response_rx,
+            worker_tx,
+        })
+    }
+
+    /// Time left for a request admitted at `started`
+    ///
+    /// The deadline runs from arrival, so time spent queued for a slot is
+    /// not granted again while waiting on the worker.
+    fn remaining_timeout(&self, function_name: &str, deadline_ms: u32, started: Instant) -> Duration {
+        self.config
+            .timeout_for(function_name, deadline_ms)
+            .saturating_sub(started.elapsed())
+    }
+
+    /// Wait for the worker's answer with the request deadline
+    async fn await_response(
+        &self,
+        request_id: u64,
+        timeout_duration: Duration,
+        response: impl std::future::Future<Output = Result<Message, RouterError>>,
+    ) -> Result<Bytes, RouterError> {
+        match self.await_reply(request_id, timeout_duration, response).await? {
+            Message::InvokeResult { result, .. } => {
+                precision::apply_to_result(result, self.config.integer_policy)
+                    .map_err(RouterError::ExecutionError)
+            }
+            Message::InvokeError { message, .. } => {
+                Err(RouterError::ExecutionError(message))
+            }
+            _ => Err(RouterError::WorkerUnavailable),
+        }
+    }
+
+    /// Wait for the worker's reply message with the request deadline,
+    /// forgetting the request either way
+    async fn await_reply(
+        &self,
+        request_id: u64,
+        timeout_duration: Duration,
+        response: impl std::future::Future<Output = Result<Message, RouterError>>,
+    ) -> Result<Message, RouterError> {
+        let result = timeout(timeout_duration, response).await;
+
+        match result {
+            Ok(Ok(msg)) => {
+                self.cleanup_request(request_id).await;
+                Ok(msg)
+            }
+            Ok(Err(e)) => {
+                // Response channel dropped or upload failed
+                self.cleanup_request(request_id).await;
+                Err(e)
+            }
+            Err(_) => {
+                // Timeout: tell the worker to stop, and forget the request
+                // so a late reply is dropped
+                debug!("Request {} timed out after {:?}", request_id, timeout_duration);
+                if let Some(worker_tx) = self.cleanup_request(request_id).await {
+                    let _ = worker_tx.send(Message::Cancel { request_id }).await;
+                }
+                Err(RouterError::Timeout)
+            }
+        }
+    }
+
+    pub async fn handle_worker_message(&self, msg: Message) {
+        match msg {
+            Message::InvokeResult { request_id, .. }
+            | Message::InvokeError { request_id, .. }
+            | Message::InvokeBatchResult { request_id, .. } => {
+                let pending = self.pending.write().await.remove(&request_id);
+                match pending {
+                    Some(pending) => {
+                        self.release_function_slot(&pending.function_name).await;
+                        let _ = pending.response_tx.send(msg);
+                    }
+                    None => debug!("Discarding reply to request {}, no longer pending", request_id),
+                }
+            }
+            Message::StreamStart { request_id, .. }
+            | Message::StreamChunk { request_id, .. }
+            | Message::StreamEnd { request_id, .. } => self.relay_response_frame(request_id, msg).await,
+            Message::StreamError { request_id, .. }
+                if self.pending.read().await.get(&request_id).is_some_and(|p| p.response_started) =>
+            {
+                self.relay_response_frame(request_id, msg).await
+            }
+            Message::StreamAck { request_id, .. }
+            | Message::StreamError { request_id, .. } => {
+                let stream_tx = self
+                    .pending
+                    .read()
+                    .await
+                    .get(&request_id)
+                    .and_then(|pending| pending.streams.upload.clone());
+                // Never block the worker connection on a slow uploader, but
+                // never drop an ack either: the uploader would wait for it
+                // forever. Acks are cumulative, so a late one is harmless.
+                match stream_tx {
+                    Some(tx) => match tx.try_send(msg) {
+                        Err(mpsc::error::TrySendError::Full(msg)) => {
+                            debug!("Ack channel for upload {} full, delivering later", request_id);
+                            tokio::spawn(async move {
+                                let _ = tx.send(msg).await;
+                            });
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) | Ok(()) => {}
+                    },
+                    None => debug!("Stream message for unknown upload {}", request_id),
+                }
+            }
+            Message::HealthStatus { active_requests, .. } => {
+                if let Some(heartbeat) = &self.heartbeat {
+                    heartbeat.record_reply();
+                }
+                self.record_health(active_requests);
+            }
+            Message::AdminResult { request_id, data } => {
+                match self.admin_waiters.lock().unwrap().remove(&request_id) {
+                    Some(waiter) => {
+                        let _ = waiter.send(data);
+                    }
+                    None => debug!("Discarding AdminResult {}, no longer waiting", request_id),
+                }
+            }
+            Message::LogEvent { level, message, fields } => {
+                self.emit_worker_log(&level, &message, &fields);
+            }
+            Message::Unknown { msg_type, .. } => {
+                debug!("Ignoring unknown message type 0x{:02x} from worker", msg_type);
             }
             _ => {
                 debug!("Unhandled worker message: {:?}", msg);
@@ -212,20 +1358,253 @@ impl Router {
         }
     }
 
-    async fn send_cancel(&self, request_id: u64) {
-        if let Some(ref worker_tx) = self.worker_tx {
-            let cancel_msg = Message::Cancel { request_id };
-            let _ = worker_tx.send(cancel_msg).await;
+    /// Pass a frame of the worker's response stream to the caller relaying
+    /// it
+    ///
+    /// A caller that did not ask for a stream (see
+    /// [`Router::invoke_streaming`]) has it refused with `StreamError`, so
+    /// the function fails instead of waiting for acks that never come.
+    async fn relay_response_frame(&self, request_id: u64, msg: Message) {
+        let mut pending = self.pending.write().await;
+        let Some(request) = pending.get_mut(&request_id) else {
+            debug!("Stream frame for request {}, no longer pending", request_id);
+            return;
+        };
+        let opening = matches!(msg, Message::StreamStart { .. });
+        match &request.streams.response {
+            Some(frames) => {
+                request.response_started |= opening;
+                let _ = frames.send(msg);
+            }
+            None if opening => {
+                debug!("Refusing response stream for request {}", request_id);
+                let refusal = Message::StreamError {
+                    request_id,
+                    code: ERR_INVALID_REQUEST,
+                    message: "Caller does not accept a streamed response".to_string(),
+                };
+                // Never block the worker connection on its own outbound queue
+                if let Err(mpsc::error::TrySendError::Full(refusal)) = request.worker_tx.try_send(refusal) {
+                    let worker_tx = request.worker_tx.clone();
+                    tokio::spawn(async move {
+                        let _ = worker_tx.send(refusal).await;
+                    });
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Re-emit a worker `LogEvent` under the `splice::worker` target
+    ///
+    /// Unrecognized levels are treated as `INFO`. Tracing field names are
+    /// fixed at compile time, so only the keys in [`WORKER_LOG_FIELDS`]
+    /// become fields of their own; any others are joined into a single
+    /// `fields` value of `key=value` pairs, and a subscriber filtering on
+    /// those keys will not see them.
+    fn emit_worker_log(&self, level: &str, message: &str, fields: &[(String, String)]) {
+        let level = parse_log_level(level);
+        // Levels order from ERROR (least verbose) up to TRACE
+        if level > self.config.worker_log_level {
+            return;
+        }
+
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| tracing::field::display(value.as_str()))
+        };
+        let (request_id, function, pid, stream) =
+            (field("request_id"), field("function"), field("pid"), field("stream"));
+        let other = fields
+            .iter()
+            .filter(|(key, _)| !WORKER_LOG_FIELDS.contains(&key.as_str()))
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>();
+        let other = (!other.is_empty()).then(|| tracing::field::display(other.join(" ")));
+
+        macro_rules! emit {
+            ($level:ident) => {
+                $level!(target: "splice::worker", request_id, function, pid, stream, fields = other, "{}", message)
+            };
+        }
+        match level {
+            Level::ERROR => emit!(error),
+            Level::WARN => emit!(warn),
+            Level::INFO => emit!(info),
+            Level::DEBUG => emit!(debug),
+            Level::TRACE => emit!(trace),
         }
     }
 
-    async fn cleanup_request(&self, request_id: u64) {
-        if let Some(pending) = self.pending.write().await.remove(&request_id) {
-            let mut counts = self.function_counts.write().await;
+    /// Dispatch messages from the worker connection until it closes
+    ///
+    /// `ShutdownAck` is signalled on `shutdown_ack`; everything else goes to
+    /// [`Router::handle_worker_message`]. Start reading once the worker is
+    /// active, as the connection is tied to the current generation.
+    ///
+    /// When the stream ends or fails to decode, every in-flight request sent
+    /// to this worker fails at once instead of waiting for its timeout. If it
+    /// was still the active worker, the router is marked disconnected and
+    /// `true` is returned: the caller should restart the worker. A worker
+    /// retired by [`Router::swap_worker`] returns `false`.
+    pub async fn read_worker_messages<S>(&self, mut worker_read: S, shutdown_ack: Arc<Notify>) -> bool
+    where
+        S: Stream<Item = Result<Message, ProtocolError>> + Unpin,
+    {
+        let generation = self.worker_generation();
+        while let Some(result) = worker_read.next().await {
+            match result {
+                Ok(Message::ShutdownAck) => shutdown_ack.notify_one(),
+                Ok(msg) => self.handle_worker_message(msg).await,
+                Err(e) => {
+                    warn!("Worker frame decode error: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if generation == self.worker_generation() {
+            self.worker_disconnected().await;
+            return true;
+        }
+        let failed = self.fail_pending(|pending| pending.generation == generation).await;
+        if failed > 0 {
+            warn!("Retired worker closed with {} requests unanswered", failed);
+        }
+        false
+    }
+
+    /// Start a reload window
+    ///
+    /// With `reload_retry_after` configured, new invokes fail with
+    /// `Reloading` until [`Router::end_reload`]; otherwise this only records
+    /// the state.
+    pub fn begin_reload(&self) {
+        self.reloading.store(true, Ordering::Release);
+    }
+
+    /// End the reload window and admit invokes again
+    pub fn end_reload(&self) {
+        self.reloading.store(false, Ordering::Release);
+    }
+
+    /// Record the frame limit negotiated in the worker handshake
+    ///
+    /// Invokes whose params alone exceed it are refused with
+    /// [`RouterError::FrameTooLarge`] rather than failing the worker
+    /// connection when the frame is encoded.
+    pub fn set_worker_max_frame_size(&self, limit: u32) {
+        self.worker_max_frame_size.store(limit, Ordering::Release);
+    }
+
+    pub fn worker_max_frame_size(&self) -> u32 {
+        self.worker_max_frame_size.load(Ordering::Acquire)
+    }
+
+    fn check_frame_size(&self, size: usize) -> Result<(), RouterError> {
+        let limit = self.worker_max_frame_size();
+        if size > limit as usize {
+            return Err(RouterError::FrameTooLarge { size, limit });
+        }
+        Ok(())
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reloading.load(Ordering::Acquire)
+    }
+
+    /// Whether the worker connection is up
+    pub fn is_worker_connected(&self) -> bool {
+        self.worker_connected.load(Ordering::Acquire)
+    }
+
+    /// Mark the worker connected again; done by [`Router::swap_worker`]
+    /// when a restarted or reloaded worker takes over
+    fn worker_connected(&self) {
+        self.worker_connected.store(true, Ordering::Release);
+    }
+
+    /// Mark the worker gone and fail everything waiting on it
+    ///
+    /// Pending invokes and admin commands complete with `WorkerUnavailable`,
+    /// and new invokes are rejected until a worker is connected again with
+    /// [`Router::swap_worker`].
+    /// Returns the number of invokes failed.
+    pub async fn worker_disconnected(&self) -> usize {
+        self.worker_connected.store(false, Ordering::Release);
+
+        let failed = self.fail_pending(|_| true).await;
+        self.admin_waiters.lock().unwrap().clear();
+
+        if failed > 0 {
+            warn!("Worker disconnected, failing {} in-flight requests", failed);
+        }
+        failed
+    }
+
+    /// Fail the pending requests matching `filter` with `WorkerUnavailable`
+    async fn fail_pending(&self, filter: impl Fn(&PendingRequest) -> bool) -> usize {
+        // Dropping the response senders wakes each caller with WorkerUnavailable
+        let failed: Vec<PendingRequest> = {
+            let mut pending = self.pending.write().await;
+            let ids: Vec<u64> = pending.iter().filter(|(_, p)| filter(p)).map(|(id, _)| *id).collect();
+            ids.iter().filter_map(|id| pending.remove(id)).collect()
+        };
+        let mut counts = self.function_counts.write().await;
+        for pending in &failed {
             if let Some(count) = counts.get_mut(&pending.function_name) {
                 *count = count.saturating_sub(1);
             }
         }
+        failed.len()
+    }
+
+    /// Wait for the requests sent to worker `generation` to complete
+    ///
+    /// Used after [`Router::swap_worker`] before stopping the old worker.
+    /// Returns how many were still pending when `timeout_duration` ran out.
+    pub async fn drain_worker(&self, generation: u64, timeout_duration: Duration) -> usize {
+        let start = Instant::now();
+
+        loop {
+            let remaining = self
+                .pending
+                .read()
+                .await
+                .values()
+                .filter(|pending| pending.generation == generation)
+                .count();
+            if remaining == 0 {
+                debug!("Worker generation {} drained", generation);
+                return 0;
+            }
+
+            if start.elapsed() > timeout_duration {
+                warn!(
+                    "Drain of worker generation {} timed out, {} requests still pending",
+                    generation, remaining
+                );
+                return remaining;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Forget a pending request, returning the connection of the worker it
+    /// was sent to
+    async fn cleanup_request(&self, request_id: u64) -> Option<mpsc::Sender<Message>> {
+        let pending = self.pending.write().await.remove(&request_id)?;
+        self.release_function_slot(&pending.function_name).await;
+        Some(pending.worker_tx)
+    }
+
+    async fn release_function_slot(&self, function_name: &str) {
+        if let Some(count) = self.function_counts.write().await.get_mut(function_name) {
+            *count = count.saturating_sub(1);
+        }
     }
 
     pub async fn drain(&self, timeout_duration: Duration) {
@@ -258,4 +1637,1186 @@ mod tests {
         assert_eq!(config.max_concurrent_requests, 1024);
         assert_eq!(config.max_concurrent_per_function, 100);
     }
+
+    fn export(name: &str) -> ExportMetadata {
+        ExportMetadata {
+            name: name.to_string(),
+            is_async: true,
+            is_streaming: false,
+            params_schema: String::new(),
+            return_schema: String::new(),
+        }
+    }
+
+    fn context() -> crate::protocol::RequestContext {
+        crate::protocol::RequestContext {
+            trace_id: 0,
+            span_id: 0,
+            headers: vec![],
+            auth: None,
+        }
+    }
+
+    /// Router whose worker answers every invoke with the function name
+    fn echo_router(config: RouterConfig) -> (Arc<Router>, tokio::task::JoinHandle<()>) {
+        let mut router = Router::new(config);
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(Message::Invoke { request_id, function_name, .. }) = rx.recv().await {
+                    let result = Bytes::from(function_name);
+                    router
+                        .handle_worker_message(Message::InvokeResult { request_id, result, duration_us: 0 })
+                        .await;
+                }
+            })
+        };
+        (router, worker)
+    }
+
+    #[tokio::test]
+    async fn test_health_saturation_sheds_then_recovers() {
+        let (router, worker) = echo_router(RouterConfig {
+            load_shed: Some(LoadShedConfig {
+                sustained_reports: 2,
+                ..LoadShedConfig::new(100)
+            }),
+            ..Default::default()
+        });
+        router.update_exports(vec![export("user.get")]).await;
+        let health = |active_requests| Message::HealthStatus {
+            uptime_ms: 1000,
+            active_requests,
+            total_requests: 500,
+        };
+
+        // A single spike is not sustained saturation
+        router.handle_worker_message(health(120)).await;
+        assert!(!router.is_shedding());
+        assert!(router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.is_ok());
+
+        router.handle_worker_message(health(110)).await;
+        assert!(router.is_shedding());
+        assert!(matches!(
+            router.invoke("user.get".into(), Bytes::new(), 1000, context()).await,
+            Err(RouterError::Overloaded)
+        ));
+
+        // Still above the resume threshold
+        router.handle_worker_message(health(90)).await;
+        assert!(router.is_shedding());
+
+        router.handle_worker_message(health(40)).await;
+        assert!(!router.is_shedding());
+        assert!(router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.is_ok());
+
+        // A replacement worker starts out admitting invokes
+        for _ in 0..2 {
+            router.handle_worker_message(health(120)).await;
+        }
+        assert!(router.is_shedding());
+        let (tx, _rx) = mpsc::channel(8);
+        router.swap_worker(tx);
+        assert!(!router.is_shedding());
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_health_poller_sends_health_checks() {
+        let mut router = Router::new(RouterConfig {
+            load_shed: Some(LoadShedConfig {
+                poll_interval: Duration::from_millis(10),
+                ..LoadShedConfig::new(10)
+            }),
+            ..Default::default()
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+
+        let poller = router.spawn_health_poller().unwrap();
+        for _ in 0..2 {
+            assert!(matches!(rx.recv().await, Some(Message::HealthCheck)));
+        }
+
+        // The poller outlives the worker and checks its replacement
+        drop(rx);
+        let (tx, mut rx) = mpsc::channel(8);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        router.swap_worker(tx);
+        for _ in 0..2 {
+            assert!(matches!(rx.recv().await, Some(Message::HealthCheck)));
+        }
+        assert!(!poller.is_finished());
+        poller.abort();
+
+        let disabled = Arc::new(Router::new(RouterConfig::default()));
+        assert!(disabled.spawn_health_poller().is_none());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("admin.*", "admin.reset"));
+        assert!(glob_match("debug_*", "debug_dump"));
+        assert!(glob_match("*.internal", "user.internal"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+        assert!(!glob_match("admin.*", "user.get"));
+        assert!(!glob_match("a*b*c", "acb"));
+    }
+
+    #[tokio::test]
+    async fn test_denylisted_export_is_unauthorized() {
+        let (router, worker) = echo_router(RouterConfig {
+            export_policy: ExportPolicy::Deny(vec!["admin.*".to_string()]),
+            ..Default::default()
+        });
+        router.update_exports(vec![export("admin.reset"), export("user.get")]).await;
+
+        let blocked = router.invoke("admin.reset".into(), Bytes::new(), 1000, context()).await;
+        assert!(matches!(blocked, Err(RouterError::Unauthorized(name)) if name == "admin.reset"));
+
+        let allowed = router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.unwrap();
+        assert_eq!(&allowed[..], b"user.get");
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_blocks_everything_else() {
+        let (router, worker) = echo_router(RouterConfig {
+            export_policy: ExportPolicy::Allow(vec!["user.*".to_string()]),
+            ..Default::default()
+        });
+        router.update_exports(vec![export("debug_dump"), export("user.get")]).await;
+
+        assert!(router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.is_ok());
+        assert!(matches!(
+            router.invoke("debug_dump".into(), Bytes::new(), 1000, context()).await,
+            Err(RouterError::Unauthorized(_))
+        ));
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_integer_params_rejected() {
+        let (router, worker) = echo_router(RouterConfig {
+            integer_policy: IntegerPolicy::Reject,
+            ..Default::default()
+        });
+        router.update_exports(vec![export("user.get")]).await;
+
+        let params = Bytes::from(rmp_serde::to_vec(&serde_json::json!({ "id": 1u64 << 60 })).unwrap());
+        let result = router.invoke("user.get".into(), params, 1000, context()).await;
+        assert!(matches!(result, Err(RouterError::InvalidParams(msg)) if msg.contains("$.id")));
+
+        let params = Bytes::from(rmp_serde::to_vec(&serde_json::json!({ "id": 42 })).unwrap());
+        assert!(router.invoke("user.get".into(), params, 1000, context()).await.is_ok());
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_integer_results_stringified() {
+        let mut router = Router::new(RouterConfig {
+            integer_policy: IntegerPolicy::Stringify,
+            ..Default::default()
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("user.get")]).await;
+
+        let id = u64::MAX - 1;
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(Message::Invoke { request_id, .. }) = rx.recv().await {
+                    let result = Bytes::from(rmp_serde::to_vec(&serde_json::json!({ "id": id })).unwrap());
+                    router
+                        .handle_worker_message(Message::InvokeResult { request_id, result, duration_us: 0 })
+                        .await;
+                }
+            })
+        };
+
+        let result = router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.unwrap();
+        let value: serde_json::Value = rmp_serde::from_slice(&result).unwrap();
+        assert_eq!(value["id"], serde_json::json!(id.to_string()));
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_get_exports_hides_blocked_names() {
+        let policy = ExportPolicy::Deny(vec!["admin.*".to_string()]);
+        let exports = vec![export("admin.reset"), export("user.get")];
+
+        let visible = Router::new(RouterConfig {
+            export_policy: policy.clone(),
+            ..Default::default()
+        });
+        visible.update_exports(exports.clone()).await;
+        assert_eq!(visible.get_exports().await.len(), 2);
+
+        let hidden = Router::new(RouterConfig {
+            export_policy: policy,
+            hide_blocked_exports: true,
+            ..Default::default()
+        });
+        hidden.update_exports(exports).await;
+        let names: Vec<String> = hidden.get_exports().await.into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["user.get".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_exact_export_wins_over_pattern() {
+        let router = Router::new(RouterConfig::default());
+        router
+            .update_exports(vec![export("user.*"), export("user.get")])
+            .await;
+
+        assert_eq!(router.resolve_export("user.get").await.as_deref(), Some("user.get"));
+        assert_eq!(router.resolve_export("user.delete").await.as_deref(), Some("user.*"));
+    }
+
+    #[tokio::test]
+    async fn test_pattern_fallback_without_exact_export() {
+        let router = Router::new(RouterConfig::default());
+        router.update_exports(vec![export("user.*")]).await;
+
+        assert_eq!(router.resolve_export("user.get").await.as_deref(), Some("user.*"));
+        assert_eq!(router.resolve_export("user.profile.get").await.as_deref(), Some("user.*"));
+        assert_eq!(router.resolve_export("user").await, None);
+        assert_eq!(router.resolve_export("order.get").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_most_specific_pattern_wins() {
+        let router = Router::new(RouterConfig::default());
+        router
+            .update_exports(vec![export("*"), export("{resource}.get"), export("user.*"), export("user.{action}")])
+            .await;
+
+        assert_eq!(router.resolve_export("user.get").await.as_deref(), Some("user.{action}"));
+        assert_eq!(router.resolve_export("user.profile.get").await.as_deref(), Some("user.*"));
+        assert_eq!(router.resolve_export("order.get").await.as_deref(), Some("{resource}.get"));
+        assert_eq!(router.resolve_export("anything").await.as_deref(), Some("*"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_passes_matched_name_to_pattern_export() {
+        let mut router = Router::new(RouterConfig::default());
+        router.update_exports(vec![export("user.*"), export("user.get")]).await;
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+
+        // Worker that echoes the export it was asked to run and the invoked name
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(Message::Invoke { request_id, function_name, context, .. }) = rx.recv().await {
+                    let invoked = context.header(INVOKED_NAME_HEADER).unwrap_or_default().to_string();
+                    let result = Bytes::from(format!("{}|{}", function_name, invoked));
+                    router
+                        .handle_worker_message(Message::InvokeResult { request_id, result, duration_us: 0 })
+                        .await;
+                }
+            })
+        };
+
+        let exact = router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.unwrap();
+        assert_eq!(&exact[..], b"user.get|");
+
+        let fallback = router.invoke("user.list".into(), Bytes::new(), 1000, context()).await.unwrap();
+        assert_eq!(&fallback[..], b"user.*|user.list");
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_in_flight_request_id_rejected() {
+        let mut router = Router::new(RouterConfig::default());
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("slow")]).await;
+
+        let host = router.register_host();
+        let first_claim = router.claim_request_id(host, 7).unwrap();
+        let first = tokio::spawn({
+            let router = router.clone();
+            async move {
+                let _claim = first_claim;
+                router.invoke("slow".into(), Bytes::new(), 1000, context()).await
+            }
+        });
+        let worker_id = match rx.recv().await {
+            Some(Message::Invoke { request_id, .. }) => request_id,
+            other => panic!("Expected Invoke, got {:?}", other),
+        };
+
+        // The second caller reusing id 7 is turned away; the first is untouched
+        assert!(matches!(router.claim_request_id(host, 7), Err(RouterError::DuplicateRequestId(7))));
+        assert_eq!(router.claim_request_id(host, 8).unwrap().request_id(), 8);
+
+        router
+            .handle_worker_message(Message::InvokeResult {
+                request_id: worker_id,
+                result: Bytes::from_static(b"done"),
+                duration_us: 0,
+            })
+            .await;
+        assert_eq!(first.await.unwrap().unwrap(), Bytes::from_static(b"done"));
+
+        // Completed IDs can be reused
+        assert!(router.claim_request_id(host, 7).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_hosts_may_reuse_each_others_request_ids() {
+        let router = Router::new(RouterConfig::default());
+        let (first, second) = (router.register_host(), router.register_host());
+        assert_ne!(first, second);
+
+        let _a = router.claim_request_id(first, 1).unwrap();
+        let _b = router.claim_request_id(second, 1).unwrap();
+        assert!(matches!(router.claim_request_id(second, 1), Err(RouterError::DuplicateRequestId(1))));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_function_not_found() {
+        let (router, worker) = echo_router(RouterConfig::default());
+        router.update_exports(vec![export("user.get")]).await;
+
+        assert!(matches!(
+            router.invoke("user.delete".into(), Bytes::new(), 1000, context()).await,
+            Err(RouterError::FunctionNotFound(name)) if name == "user.delete"
+        ));
+        assert!(router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.is_ok());
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_admin_commands_forwarded_in_order() {
+        let mut router = Router::new(RouterConfig::default());
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(Message::AdminCommand { request_id, command, .. }) = rx.recv().await {
+                    let reply = AdminReply::Handlers(vec![command]);
+                    router.handle_worker_message(reply.to_message(request_id)).await;
+                }
+            })
+        };
+
+        let data = router.admin("handlers".into(), Bytes::new()).await.unwrap();
+        assert_eq!(AdminReply::decode(&data).unwrap(), AdminReply::Handlers(vec!["handlers".into()]));
+
+        // Unsafe commands never reach the worker
+        let data = router.admin("exec".into(), Bytes::from_static(b"rm -rf /")).await.unwrap();
+        assert!(matches!(AdminReply::decode(&data).unwrap(), AdminReply::Refused(_)));
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_admin_replies_matched_by_request_id() {
+        let mut router = Router::new(RouterConfig {
+            default_timeout: Duration::from_millis(200),
+            ..Default::default()
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+
+        // Never answers the first command, then answers the next two in
+        // reverse order
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                let mut commands = Vec::new();
+                while let Some(Message::AdminCommand { request_id, args, .. }) = rx.recv().await {
+                    commands.push((request_id, args));
+                    if commands.len() == 3 {
+                        for (request_id, args) in commands.drain(1..).rev() {
+                            let reply = AdminReply::Refused(String::from_utf8(args.to_vec()).unwrap());
+                            router.handle_worker_message(reply.to_message(request_id)).await;
+                        }
+                    }
+                }
+            })
+        };
+
+        let ask = |caller: &'static str| {
+            let router = router.clone();
+            async move {
+                let data = router.admin("stats".into(), Bytes::from(caller)).await?;
+                Ok::<_, RouterError>(AdminReply::decode(&data).unwrap())
+            }
+        };
+        let silent = tokio::spawn(ask("silent"));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let (first, second) = tokio::join!(ask("first"), ask("second"));
+
+        assert_eq!(first.unwrap(), AdminReply::Refused("first".into()));
+        assert_eq!(second.unwrap(), AdminReply::Refused("second".into()));
+        assert!(matches!(silent.await.unwrap(), Err(RouterError::Timeout)));
+        assert!(router.admin_waiters.lock().unwrap().is_empty());
+
+        worker.abort();
+    }
+
+    #[test]
+    fn test_timeout_for_prefers_stricter_limit() {
+        let config = RouterConfig {
+            default_timeout: Duration::from_secs(30),
+            function_timeouts: HashMap::from([("cache.get".to_string(), Duration::from_millis(200))]),
+            ..Default::default()
+        };
+
+        assert_eq!(config.timeout_for("cache.get", 0), Duration::from_millis(200));
+        assert_eq!(config.timeout_for("cache.get", 5000), Duration::from_millis(200));
+        assert_eq!(config.timeout_for("cache.get", 50), Duration::from_millis(50));
+        assert_eq!(config.timeout_for("report.build", 0), Duration::from_secs(30));
+        assert_eq!(config.timeout_for("report.build", 5000), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_function_timeout_override() {
+        let mut router = Router::new(RouterConfig {
+            default_timeout: Duration::from_millis(400),
+            function_timeouts: HashMap::from([("cache.get".to_string(), Duration::from_millis(50))]),
+            ..Default::default()
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("cache.get"), export("report.build")]).await;
+
+        // Worker takes 150ms to answer anything
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    if let Message::Invoke { request_id, .. } = msg {
+                        let router = router.clone();
+                        tokio::spawn(async move {
+                            tokio::time::sleep(Duration::from_millis(150)).await;
+                            router
+                                .handle_worker_message(Message::InvokeResult {
+                                    request_id,
+                                    result: Bytes::from_static(b"ok"),
+                                    duration_us: 0,
+                                })
+                                .await;
+                        });
+                    }
+                }
+            })
+        };
+
+        let started = Instant::now();
+        assert!(matches!(
+            router.invoke("cache.get".into(), Bytes::new(), 0, context()).await,
+            Err(RouterError::Timeout)
+        ));
+        assert!(started.elapsed() < Duration::from_millis(150));
+
+        // Without an override the default applies and the call completes
+        assert_eq!(
+            router.invoke("report.build".into(), Bytes::new(), 0, context()).await.unwrap(),
+            Bytes::from_static(b"ok")
+        );
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cancels_slow_invoke_and_drops_late_reply() {
+        let mut router = Router::new(RouterConfig {
+            default_timeout: Duration::from_secs(30),
+            ..Default::default()
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("slow")]).await;
+
+        // Dispatcher sleeps well past the caller's deadline before answering
+        let (cancel_tx, mut cancel_rx) = mpsc::channel(1);
+        let (late_tx, mut late_rx) = mpsc::channel(1);
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    match msg {
+                        Message::Invoke { request_id, .. } => {
+                            let router = router.clone();
+                            let late_tx = late_tx.clone();
+                            tokio::spawn(async move {
+                                tokio::time::sleep(Duration::from_millis(200)).await;
+                                router
+                                    .handle_worker_message(Message::InvokeResult {
+                                        request_id,
+                                        result: Bytes::from_static(b"late"),
+                                        duration_us: 0,
+                                    })
+                                    .await;
+                                let _ = late_tx.send(()).await;
+                            });
+                        }
+                        Message::Cancel { request_id } => {
+                            let _ = cancel_tx.send(request_id).await;
+                        }
+                        _ => {}
+                    }
+                }
+            })
+        };
+
+        let started = Instant::now();
+        assert!(matches!(
+            router.invoke("slow".into(), Bytes::new(), 50, context()).await,
+            Err(RouterError::Timeout)
+        ));
+        assert!(started.elapsed() < Duration::from_millis(200));
+
+        // The worker is told to stop, and the request is forgotten
+        let cancelled = tokio::time::timeout(Duration::from_secs(1), cancel_rx.recv()).await.unwrap();
+        assert!(cancelled.is_some());
+        assert!(router.pending.read().await.is_empty());
+        assert_eq!(router.function_counts.read().await.get("slow"), Some(&0));
+
+        // The late reply is discarded without disturbing later requests
+        tokio::time::timeout(Duration::from_secs(1), late_rx.recv()).await.unwrap();
+        assert!(router.pending.read().await.is_empty());
+        assert_eq!(router.function_counts.read().await.get("slow"), Some(&0));
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_worker_disconnect_fails_in_flight_requests() {
+        let mut router = Router::new(RouterConfig::default());
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("slow")]).await;
+
+        let (worker_tx, mut worker_read) = mpsc::channel::<Result<Message, ProtocolError>>(8);
+        let reader = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                let stream = futures::stream::poll_fn(move |cx| worker_read.poll_recv(cx));
+                router.read_worker_messages(stream, Arc::new(Notify::new())).await;
+            })
+        };
+
+        let invokes: Vec<_> = (0..3)
+            .map(|_| {
+                let router = router.clone();
+                tokio::spawn(async move { router.invoke("slow".into(), Bytes::new(), 0, context()).await })
+            })
+            .collect();
+        for _ in 0..3 {
+            assert!(matches!(rx.recv().await, Some(Message::Invoke { .. })));
+        }
+
+        // The worker connection closes mid-session
+        drop(worker_tx);
+        tokio::time::timeout(Duration::from_secs(1), reader).await.unwrap().unwrap();
+
+        for invoke in invokes {
+            let result = tokio::time::timeout(Duration::from_secs(1), invoke).await.unwrap().unwrap();
+            assert!(matches!(result, Err(RouterError::WorkerUnavailable)));
+        }
+        assert!(!router.is_worker_connected());
+        assert!(router.pending.read().await.is_empty());
+        assert_eq!(router.function_counts.read().await.get("slow"), Some(&0));
+
+        // New invokes fail fast until the worker is back
+        assert!(matches!(
+            router.invoke("slow".into(), Bytes::new(), 0, context()).await,
+            Err(RouterError::WorkerUnavailable)
+        ));
+        let (tx, _rx) = mpsc::channel(8);
+        router.swap_worker(tx);
+        assert!(router.is_worker_connected());
+    }
+
+    #[tokio::test]
+    async fn test_retired_worker_disconnect_only_fails_its_requests() {
+        let mut router = Router::new(RouterConfig::default());
+        let (old_tx, mut old_rx) = mpsc::channel(8);
+        router.set_worker_tx(old_tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("slow")]).await;
+
+        let (old_conn, mut old_read) = mpsc::channel::<Result<Message, ProtocolError>>(8);
+        let old_reader = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                let stream = futures::stream::poll_fn(move |cx| old_read.poll_recv(cx));
+                router.read_worker_messages(stream, Arc::new(Notify::new())).await
+            })
+        };
+        let stranded = {
+            let router = router.clone();
+            tokio::spawn(async move { router.invoke("slow".into(), Bytes::new(), 0, context()).await })
+        };
+        assert!(matches!(old_rx.recv().await, Some(Message::Invoke { .. })));
+
+        let (new_tx, mut new_rx) = mpsc::channel(8);
+        assert_eq!(router.swap_worker(new_tx), 0);
+        let current = {
+            let router = router.clone();
+            tokio::spawn(async move { router.invoke("slow".into(), Bytes::new(), 0, context()).await })
+        };
+        let current_id = match new_rx.recv().await {
+            Some(Message::Invoke { request_id, .. }) => request_id,
+            other => panic!("Expected Invoke, got {:?}", other),
+        };
+        assert_eq!(router.drain_worker(0, Duration::from_millis(20)).await, 1);
+
+        // The retired worker goes away with a request unanswered
+        drop(old_conn);
+        assert!(!old_reader.await.unwrap());
+        assert!(matches!(stranded.await.unwrap(), Err(RouterError::WorkerUnavailable)));
+        assert!(router.is_worker_connected());
+        assert_eq!(router.drain_worker(0, Duration::ZERO).await, 0);
+
+        router
+            .handle_worker_message(Message::InvokeResult {
+                request_id: current_id,
+                result: Bytes::from_static(b"ok"),
+                duration_us: 0,
+            })
+            .await;
+        assert_eq!(current.await.unwrap().unwrap(), Bytes::from_static(b"ok"));
+    }
+
+    #[tokio::test]
+    async fn test_invokes_during_reload_get_retry_hint() {
+        let retry_after = Duration::from_millis(1500);
+        let (router, worker) = echo_router(RouterConfig {
+            reload_retry_after: Some(retry_after),
+            ..Default::default()
+        });
+        router.update_exports(vec![export("user.get")]).await;
+
+        router.begin_reload();
+        let result = router.invoke("user.get".into(), Bytes::new(), 1000, context()).await;
+        match result {
+            Err(RouterError::Reloading { retry_after: hint }) => assert_eq!(hint, retry_after),
+            other => panic!("Expected Reloading, got {:?}", other),
+        }
+
+        router.end_reload();
+        assert!(router.invoke("user.get".into(), Bytes::new(), 1000, context()).await.is_ok());
+
+        // Without a retry hint configured, reloads do not reject invokes
+        let (plain, plain_worker) = echo_router(RouterConfig::default());
+        plain.update_exports(vec![export("user.get")]).await;
+        plain.begin_reload();
+        assert!(plain.invoke("user.get".into(), Bytes::new(), 1000, context()).await.is_ok());
+
+        worker.abort();
+        plain_worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_admitted_ahead_of_queued_low() {
+        use crate::protocol::{PRIORITY_HIGH, PRIORITY_LOW};
+
+        let mut router = Router::new(RouterConfig {
+            max_concurrent_requests: 1,
+            max_queued_requests: 2,
+            ..Default::default()
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("hold"), export("batch-1"), export("batch-2"), export("urgent")]).await;
+
+        // Worker records arrival order and answers each invoke after 100ms
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let worker = {
+            let router = router.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                while let Some(Message::Invoke { request_id, function_name, .. }) = rx.recv().await {
+                    order.lock().unwrap().push(function_name);
+                    let router = router.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        router
+                            .handle_worker_message(Message::InvokeResult {
+                                request_id,
+                                result: Bytes::from_static(b"ok"),
+                                duration_us: 0,
+                            })
+                            .await;
+                    });
+                }
+            })
+        };
+
+        let mut calls = Vec::new();
+        for (name, priority) in [
+            ("hold", PRIORITY_NORMAL),
+            ("batch-1", PRIORITY_LOW),
+            ("batch-2", PRIORITY_LOW),
+            ("urgent", PRIORITY_HIGH),
+        ] {
+            let router = router.clone();
+            calls.push(tokio::spawn(async move {
+                router.invoke_with_priority(name.into(), Bytes::new(), 0, context(), priority).await
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let results: Vec<_> = futures::future::join_all(calls).await.into_iter().map(|r| r.unwrap()).collect();
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        // The full queue shed the newest low-priority waiter to make room
+        assert!(matches!(results[2], Err(RouterError::Overloaded)));
+        assert!(results[3].is_ok());
+        assert_eq!(*order.lock().unwrap(), ["hold", "urgent", "batch-1"]);
+
+        worker.abort();
+    }
+
+    /// Router whose worker answers every invoke after `delay`
+    fn slow_router(config: RouterConfig, delay: Duration) -> (Arc<Router>, mpsc::Receiver<u64>, tokio::task::JoinHandle<()>) {
+        let mut router = Router::new(config);
+        let (tx, mut rx) = mpsc::channel(64);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+
+        let (seen_tx, seen_rx) = mpsc::channel(64);
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    if let Message::Invoke { request_id, .. } = msg {
+                        let _ = seen_tx.send(request_id).await;
+                        let router = router.clone();
+                        tokio::spawn(async move {
+                            tokio::time::sleep(delay).await;
+                            router
+                                .handle_worker_message(Message::InvokeResult {
+                                    request_id,
+                                    result: Bytes::from_static(b"ok"),
+                                    duration_us: 0,
+                                })
+                                .await;
+                        });
+                    }
+                }
+            })
+        };
+        (router, seen_rx, worker)
+    }
+
+    #[tokio::test]
+    async fn test_burst_beyond_concurrency_limit_is_overloaded() {
+        const LIMIT: usize = 4;
+        let (router, mut seen, worker) = slow_router(
+            RouterConfig {
+                max_concurrent_requests: LIMIT,
+                ..Default::default()
+            },
+            Duration::from_millis(200),
+        );
+        router.update_exports(vec![export("slow")]).await;
+
+        let calls: Vec<_> = (0..LIMIT + 10)
+            .map(|_| {
+                let router = router.clone();
+                tokio::spawn(async move { router.invoke("slow".into(), Bytes::new(), 0, context()).await })
+            })
+            .collect();
+
+        for _ in 0..LIMIT {
+            seen.recv().await.unwrap();
+        }
+        assert_eq!(router.active_requests(), LIMIT);
+
+        let results: Vec<_> = futures::future::join_all(calls).await.into_iter().map(|r| r.unwrap()).collect();
+        let overloaded = results.iter().filter(|r| matches!(r, Err(RouterError::Overloaded))).count();
+        assert_eq!(overloaded, 10);
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), LIMIT);
+        assert_eq!(router.active_requests(), 0);
+        assert_eq!(router.total_requests(), LIMIT as u64);
+
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_admission_grace_sheds_queued_invoke() {
+        let (router, mut seen, worker) = slow_router(
+            RouterConfig {
+                max_concurrent_requests: 1,
+                max_queued_requests: 4,
+                admission_grace: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+            Duration::from_millis(300),
+        );
+        router.update_exports(vec![export("slow")]).await;
+
+        let hold = {
+            let router = router.clone();
+            tokio::spawn(async move { router.invoke("slow".into(), Bytes::new(), 0, context()).await })
+        };
+        seen.recv().await.unwrap();
+
+        // Queued, but no slot frees up within the grace period
+        let started = Instant::now();
+        assert!(matches!(
+            router.invoke("slow".into(), Bytes::new(), 0, context()).await,
+            Err(RouterError::Overloaded)
+        ));
+        assert!(started.elapsed() < Duration::from_millis(250));
+        assert!(hold.await.unwrap().is_ok());
+
+        worker.abort();
+    }
+
+    /// Collects formatted tracing output for assertions
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_worker_log_events_filtered_by_level() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::TRACE)
+            .with_ansi(false)
+            .without_time()
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let router = Router::new(RouterConfig {
+            worker_log_level: Level::INFO,
+            ..Default::default()
+        });
+        let event = |level: &str, message: &str| Message::LogEvent {
+            level: level.to_string(),
+            message: message.to_string(),
+            fields: vec![
+                ("request_id".to_string(), "42".to_string()),
+                ("table".to_string(), "users".to_string()),
+            ],
+        };
+        router.handle_worker_message(event("ERROR", "disk full")).await;
+        router.handle_worker_message(event("DEBUG", "cache miss")).await;
+        router.handle_worker_message(event("warning", "slow query")).await;
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("ERROR splice::worker: disk full request_id=42 fields=table=users"), "{}", output);
+        assert!(output.contains("WARN splice::worker: slow query"), "{}", output);
+        assert!(!output.contains("cache miss"), "{}", output);
+    }
+
+    #[tokio::test]
+    async fn test_upload_ack_not_dropped_when_ack_channel_full() {
+        let mut router = Router::new(RouterConfig::default());
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("upload.sink")]).await;
+
+        // Floods stale acks ahead of every real one, overflowing the
+        // uploader's window-sized ack channel
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                let ack = |request_id, ack_sequence| Message::StreamAck { request_id, ack_sequence, window: 1 };
+                while let Some(msg) = rx.recv().await {
+                    match msg {
+                        Message::StreamChunk { request_id, sequence, .. } => {
+                            for _ in 0..4 {
+                                router.handle_worker_message(ack(request_id, sequence)).await;
+                            }
+                            router.handle_worker_message(ack(request_id, sequence + 1)).await;
+                        }
+                        Message::StreamEnd { request_id, total_chunks } => {
+                            let result = Bytes::from(total_chunks.to_string());
+                            router
+                                .handle_worker_message(Message::InvokeResult { request_id, result, duration_us: 0 })
+                                .await;
+                        }
+                        _ => {}
+                    }
+                }
+            })
+        };
+
+        let (body_tx, body_rx) = mpsc::channel(4);
+        for chunk in ["a", "b", "c"] {
+            body_tx.send(Ok(Bytes::from(chunk))).await.unwrap();
+        }
+        drop(body_tx);
+
+        let result = router
+            .invoke_upload(
+                "upload.sink".into(),
+                Bytes::new(),
+                2000,
+                context(),
+                UploadOptions { window: 1, ..Default::default() },
+                body_rx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, Bytes::from("3"));
+        worker.abort();
+    }
+
+    /// Pool worker answering every invoke with its own name
+    fn pool_worker(router: &Arc<Router>, name: &'static str) -> (u64, tokio::task::JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::channel(8);
+        let id = router.add_worker(tx);
+        let router = router.clone();
+        let task = tokio::spawn(async move {
+            while let Some(Message::Invoke { request_id, .. }) = rx.recv().await {
+                let result = Bytes::from(name);
+                router
+                    .handle_worker_message(Message::InvokeResult { request_id, result, duration_us: 0 })
+                    .await;
+            }
+        });
+        (id, task)
+    }
+
+    #[tokio::test]
+    async fn test_sticky_key_routes_invokes_to_same_pool_worker() {
+        let router = Arc::new(Router::new(RouterConfig {
+            sticky: Some(
+                StickyConfig::new(crate::balancer::StickyKey::Header("x-session".into()))
+                    .functions(vec!["cart.*".into()]),
+            ),
+            ..Default::default()
+        }));
+        router.update_exports(vec![export("cart.add"), export("search")]).await;
+        let workers: Vec<_> = ["a", "b", "c"].into_iter().map(|name| pool_worker(&router, name)).collect();
+
+        let invoke = |function: &'static str, session: &str| {
+            let router = router.clone();
+            let context = crate::protocol::RequestContext::builder().header("x-session", session).build();
+            async move { router.invoke(function.into(), Bytes::new(), 1000, context).await.unwrap() }
+        };
+
+        let sessions: Vec<String> = (0..30).map(|i| format!("session-{}", i)).collect();
+        let mut owners = Vec::new();
+        for session in &sessions {
+            let owner = invoke("cart.add", session).await;
+            for _ in 0..5 {
+                assert_eq!(invoke("cart.add", session).await, owner);
+            }
+            owners.push(owner);
+        }
+
+        // Non-sticky functions still spread across the pool
+        let mut picks = HashSet::new();
+        for _ in 0..3 {
+            picks.insert(invoke("search", "session-0").await);
+        }
+        assert_eq!(picks.len(), 3);
+
+        // Removing a worker only moves the sessions it owned
+        let (removed_id, removed_task) = &workers[1];
+        router.remove_worker(*removed_id).await;
+        removed_task.abort();
+        for (session, owner) in sessions.iter().zip(&owners) {
+            let now = invoke("cart.add", session).await;
+            assert_ne!(now, Bytes::from("b"));
+            if owner != &Bytes::from("b") {
+                assert_eq!(&now, owner, "{} moved off a surviving worker", session);
+            }
+        }
+
+        for (_, task) in workers {
+            task.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_start_ramps_new_pool_worker() {
+        let window = Duration::from_millis(300);
+        let router = Arc::new(Router::new(RouterConfig {
+            slow_start: Some(window),
+            ..Default::default()
+        }));
+        router.update_exports(vec![export("search")]).await;
+        let warm = pool_worker(&router, "warm");
+        tokio::time::sleep(window).await;
+        let cold = pool_worker(&router, "cold");
+
+        let cold_hits = |router: Arc<Router>| async move {
+            let mut hits = 0;
+            for _ in 0..100 {
+                if router.invoke("search".into(), Bytes::new(), 1000, context()).await.unwrap() == "cold" {
+                    hits += 1;
+                }
+            }
+            hits
+        };
+
+        let early = cold_hits(router.clone()).await;
+        assert!(early < 25, "cold worker took {} of 100 invokes right away", early);
+
+        tokio::time::sleep(window).await;
+        assert_eq!(cold_hits(router.clone()).await, 50);
+
+        warm.1.abort();
+        cold.1.abort();
+    }
+
+    #[tokio::test]
+    async fn test_invoke_batch_round_trip() {
+        let mut router = Router::new(RouterConfig::default());
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("user.get"), export("user.{id}")]).await;
+
+        // Worker answering each call with the export it reached
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    let Message::InvokeBatch { request_id, calls } = msg else {
+                        panic!("Expected InvokeBatch, got {:?}", msg);
+                    };
+                    let results = calls
+                        .into_iter()
+                        .map(|(function_name, _)| Ok(Bytes::from(function_name)))
+                        .collect();
+                    router.handle_worker_message(Message::InvokeBatchResult { request_id, results }).await;
+                }
+            })
+        };
+
+        let calls = vec![
+            ("user.get".to_string(), Bytes::new()),
+            ("user.get".to_string(), Bytes::new()),
+        ];
+        let results = router.invoke_batch(calls, 1000).await.unwrap();
+        assert_eq!(results, vec![Ok(Bytes::from("user.get")), Ok(Bytes::from("user.get"))]);
+        assert_eq!(router.active_requests(), 0);
+
+        // A pattern export would never learn the invoked name, so it can't
+        // be reached from a batch
+        let calls = vec![
+            ("user.get".to_string(), Bytes::new()),
+            ("user.42".to_string(), Bytes::new()),
+        ];
+        assert!(matches!(
+            router.invoke_batch(calls, 1000).await,
+            Err(RouterError::InvalidParams(message)) if message.contains("user.{id}")
+        ));
+
+        // An unknown call fails the batch before anything is sent
+        let calls = vec![
+            ("user.get".to_string(), Bytes::new()),
+            ("order.get".to_string(), Bytes::new()),
+        ];
+        assert!(matches!(
+            router.invoke_batch(calls, 1000).await,
+            Err(RouterError::FunctionNotFound(name)) if name == "order.get"
+        ));
+        assert_eq!(router.invoke_batch(Vec::new(), 1000).await.unwrap(), Vec::new());
+        worker.abort();
+    }
+
+    #[tokio::test]
+    async fn test_response_stream_relayed_only_when_asked_for() {
+        let mut router = Router::new(RouterConfig::default());
+        let (tx, mut rx) = mpsc::channel(8);
+        router.set_worker_tx(tx);
+        let router = Arc::new(router);
+        router.update_exports(vec![export("rows")]).await;
+
+        // Worker streaming one chunk per invoke, waiting for the ack (or
+        // refusal) before its result
+        let worker = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                while let Some(Message::Invoke { request_id, .. }) = rx.recv().await {
+                    router.handle_worker_message(Message::StreamStart { request_id, window: 1 }).await;
+                    let data = Bytes::from_static(b"row");
+                    router.handle_worker_message(Message::StreamChunk { request_id, sequence: 0, data }).await;
+                    let reply = rx.recv().await.unwrap();
+                    let result = match reply {
+                        Message::StreamAck { request_id: acked, ack_sequence: 1, .. } if acked == request_id => {
+                            router.handle_worker_message(Message::StreamEnd { request_id, total_chunks: 1 }).await;
+                            Bytes::from_static(b"streamed")
+                        }
+                        Message::StreamError { request_id: refused, .. } if refused == request_id => {
+                            Bytes::from_static(b"refused")
+                        }
+                        other => panic!("Expected StreamAck or StreamError, got {:?}", other),
+                    };
+                    router.handle_worker_message(Message::InvokeResult { request_id, result, duration_us: 0 }).await;
+                }
+            })
+        };
+
+        let (frames, mut host) = mpsc::channel(8);
+        let (ack_tx, acks) = mpsc::channel(8);
+        let relay = ResponseRelay { request_id: 77, frames, acks };
+        let invoke = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                router.invoke_streaming("rows".to_string(), Bytes::new(), 1000, context(), PRIORITY_NORMAL, relay).await
+            })
+        };
+        assert!(matches!(host.recv().await, Some(Message::StreamStart { request_id: 77, window: 1 })));
+        assert!(matches!(host.recv().await, Some(Message::StreamChunk { request_id: 77, sequence: 0, .. })));
+        ack_tx.send(Message::StreamAck { request_id: 77, ack_sequence: 1, window: 1 }).await.unwrap();
+        assert_eq!(invoke.await.unwrap().unwrap(), Bytes::from_static(b"streamed"));
+        assert!(matches!(host.try_recv(), Ok(Message::StreamEnd { request_id: 77, total_chunks: 1 })));
+
+        // A plain invoke has nowhere to send the stream
+        let result = router.invoke("rows".to_string(), Bytes::new(), 1000, context()).await;
+        assert_eq!(result.unwrap(), Bytes::from_static(b"refused"));
+        worker.abort();
+    }
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!(parse_log_level("error"), Level::ERROR);
+        assert_eq!(parse_log_level("WARNING"), Level::WARN);
+        assert_eq!(parse_log_level("Trace"), Level::TRACE);
+        assert_eq!(parse_log_level("verbose"), Level::INFO);
+    }
 }
@@ -1,10 +1,14 @@
 use crate::protocol::{Message, Role, PROTOCOL_VERSION, CAP_STREAMING, CAP_CANCELLATION};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Error)]
@@ -21,33 +25,128 @@ pub enum SupervisorError {
     #[error("Max restart attempts exceeded")]
     MaxRestartsExceeded,
 
+    #[deprecated(note = "never returned; an exhausted restart budget is `MaxRestartsExceeded` and leaves `Supervisor::is_failed` set")]
     #[error("Circuit breaker open")]
     CircuitBreakerOpen,
 }
 
 #[derive(Debug, Clone)]
 pub struct SupervisorConfig {
+    /// Restarts allowed within `restart_window`; one more leaves the
+    /// supervisor failed for good
     pub max_restarts: usize,
+    pub restart_window: Duration,
+    /// Delays before consecutive restarts; past the end of the list the
+    /// last delay keeps doubling, up to `max_restart_backoff`
     pub restart_backoff: Vec<Duration>,
+    pub max_restart_backoff: Duration,
     pub health_check_interval: Duration,
+    /// Consecutive failed health checks before the worker is restarted
+    pub health_failure_threshold: u32,
+    /// Consecutive passed health checks that clear earlier failures
+    pub health_success_threshold: u32,
+    /// How often `HealthCheck` is sent over the worker connection (`None`
+    /// disables the heartbeat)
+    pub heartbeat_interval: Option<Duration>,
+    /// Time the worker has to answer a heartbeat with `HealthStatus`
+    pub heartbeat_timeout: Duration,
+    /// Consecutive unanswered heartbeats before the worker is restarted
+    pub heartbeat_miss_threshold: u32,
     pub drain_timeout: Duration,
     pub connect_timeout: Duration,
+    /// Time allowed at each step of the stop sequence (ack, SIGTERM) before escalating
+    pub shutdown_grace: Duration,
+    /// Extra environment variables for the worker; `ZAP_SOCKET` always wins
+    pub env: Vec<(String, String)>,
+    /// Working directory for the worker (inherited when `None`)
+    pub working_dir: Option<PathBuf>,
+    /// OS resource limits applied to the worker (Unix only)
+    pub limits: ResourceLimits,
+    /// Read the worker's stdout/stderr line by line and report each line
+    /// as a tracing event and a `LogEvent` (see
+    /// [`Supervisor::subscribe_logs`]); otherwise the worker inherits the
+    /// runtime's stdio
+    pub capture_output: bool,
+}
+
+/// OS resource limits for the worker process
+///
+/// Applied in the child between `fork` and `exec`, so they never affect the
+/// supervisor itself. Ignored on non-Unix platforms.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Maximum address space in bytes (`RLIMIT_AS`)
+    pub max_memory_bytes: Option<u64>,
+    /// Maximum open file descriptors (`RLIMIT_NOFILE`)
+    pub max_open_files: Option<u64>,
+    /// Scheduling niceness, -20 (highest priority) to 19
+    pub nice: Option<i32>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self == &ResourceLimits::default()
+    }
+
+    /// Apply the limits to the calling process
+    ///
+    /// Only async-signal-safe calls are made, as required inside `pre_exec`.
+    #[cfg(unix)]
+    fn apply(&self) -> std::io::Result<()> {
+        fn set_limit(resource: libc::c_int, value: u64) -> std::io::Result<()> {
+            let limit = libc::rlimit {
+                rlim_cur: value as libc::rlim_t,
+                rlim_max: value as libc::rlim_t,
+            };
+            // SAFETY: `limit` is a valid rlimit for the duration of the call
+            if unsafe { libc::setrlimit(resource as _, &limit) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        if let Some(bytes) = self.max_memory_bytes {
+            set_limit(libc::RLIMIT_AS as libc::c_int, bytes)?;
+        }
+        if let Some(files) = self.max_open_files {
+            set_limit(libc::RLIMIT_NOFILE as libc::c_int, files)?;
+        }
+        if let Some(nice) = self.nice {
+            // SAFETY: setpriority only reads its integer arguments
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for SupervisorConfig {
     fn default() -> Self {
         Self {
             max_restarts: 10,
+            restart_window: Duration::from_secs(60),
             restart_backoff: vec![
-                Duration::from_millis(0),
                 Duration::from_millis(100),
-                Duration::from_millis(500),
-                Duration::from_secs(2),
-                Duration::from_secs(5),
+                Duration::from_millis(200),
+                Duration::from_millis(400),
+                Duration::from_millis(800),
+                Duration::from_millis(1600),
             ],
+            max_restart_backoff: Duration::from_secs(30),
             health_check_interval: Duration::from_secs(5),
+            health_failure_threshold: 3,
+            health_success_threshold: 1,
+            heartbeat_interval: Some(Duration::from_secs(5)),
+            heartbeat_timeout: Duration::from_secs(2),
+            heartbeat_miss_threshold: 3,
             drain_timeout: Duration::from_secs(30),
             connect_timeout: Duration::from_secs(10),
+            shutdown_grace: Duration::from_secs(10),
+            env: Vec::new(),
+            working_dir: None,
+            limits: ResourceLimits::default(),
+            capture_output: true,
         }
     }
 }
@@ -58,9 +157,23 @@ pub enum WorkerState {
     Ready,
     Draining,
     Failed,
+    #[deprecated(note = "never entered; an exhausted restart budget leaves the worker `Failed` and `Supervisor::is_failed` set")]
     CircuitBreaker,
 }
 
+/// How a worker was stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// No worker was running
+    NotRunning,
+    /// Worker acknowledged `Shutdown` and exited on its own
+    Exited,
+    /// Worker exited after SIGTERM
+    Terminated,
+    /// Worker ignored SIGTERM and was killed
+    Killed,
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkerInfo {
     pub pid: u32,
@@ -71,13 +184,218 @@ pub struct WorkerInfo {
     pub total_requests: u64,
 }
 
+/// Debounces health-check results so brief blips don't restart the worker
+///
+/// Reports unhealthy once `failure_threshold` checks fail in a row. Failures
+/// are forgotten after `success_threshold` consecutive passes.
+#[derive(Debug, Clone)]
+pub struct HealthTracker {
+    failure_threshold: u32,
+    success_threshold: u32,
+    failures: u32,
+    successes: u32,
+}
+
+impl HealthTracker {
+    pub fn new(failure_threshold: u32, success_threshold: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            success_threshold: success_threshold.max(1),
+            failures: 0,
+            successes: 0,
+        }
+    }
+
+    /// Record one check, returning true once the failure threshold is reached
+    pub fn record(&mut self, healthy: bool) -> bool {
+        if healthy {
+            self.successes += 1;
+            if self.successes >= self.success_threshold {
+                self.failures = 0;
+            }
+            false
+        } else {
+            self.successes = 0;
+            self.failures += 1;
+            self.failures >= self.failure_threshold
+        }
+    }
+
+    /// Consecutive failures counted so far
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Forget past results, e.g. after the worker was restarted
+    pub fn reset(&mut self) {
+        self.failures = 0;
+        self.successes = 0;
+    }
+}
+
+/// `HealthStatus` replies seen on the worker connection
+///
+/// Shared between the supervisor, which sends heartbeats, and whoever reads
+/// the worker connection, which calls [`Heartbeat::record_reply`].
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    last_reply: std::sync::Mutex<Option<Instant>>,
+}
+
+impl Heartbeat {
+    pub fn record_reply(&self) {
+        *self.last_reply.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub fn last_reply(&self) -> Option<Instant> {
+        *self.last_reply.lock().unwrap()
+    }
+
+    /// Whether a reply arrived within `timeout` of `sent`
+    fn answered(&self, sent: Instant, timeout: Duration) -> bool {
+        self.last_reply()
+            .is_some_and(|at| at >= sent && at.duration_since(sent) <= timeout)
+    }
+}
+
+/// Worker log lines buffered per subscriber before the oldest are dropped
+const LOG_CHANNEL_CAPACITY: usize = 256;
+
+/// Level for a worker output line, from a Rust panic message or the level
+/// token (`ERROR`, `WARN`, optionally bracketed) that opens the line or
+/// follows its timestamp, as `tracing` and `env_logger` print them
+///
+/// Colour codes are ignored.
+pub fn infer_log_level(line: &str) -> &'static str {
+    let line = strip_ansi(line);
+    if line.trim_start().starts_with("thread '") && line.contains("' panicked at") {
+        return "ERROR";
+    }
+
+    let level = line
+        .split_whitespace()
+        .map(|token| token.trim_start_matches('['))
+        .find(|token| !token.starts_with(|c: char| c.is_ascii_digit()))
+        .and_then(|token| token.split([']', ':']).next());
+    match level {
+        Some("ERROR" | "FATAL") => "ERROR",
+        Some("WARN" | "WARNING") => "WARN",
+        _ => "INFO",
+    }
+}
+
+/// `line` without ANSI escape sequences
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        // CSI sequences (`ESC [ ... final`) carry colours; anything else
+        // is a two-character escape
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    stripped
+}
+
+/// Forward `reader` line by line until EOF
+fn spawn_output_reader<R>(reader: R, stream: &'static str, pid: u32, log_tx: broadcast::Sender<Message>) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_string();
+            let level = infer_log_level(&line);
+            match level {
+                "ERROR" => error!(target: "splice::worker", pid, stream, "{}", line),
+                "WARN" => warn!(target: "splice::worker", pid, stream, "{}", line),
+                _ => info!(target: "splice::worker", pid, stream, "{}", line),
+            }
+            // No subscribers is fine; the tracing event already went out
+            let _ = log_tx.send(Message::LogEvent {
+                level: level.to_string(),
+                message: line,
+                fields: vec![
+                    ("stream".to_string(), stream.to_string()),
+                    ("pid".to_string(), pid.to_string()),
+                ],
+            });
+        }
+    })
+}
+
+/// A worker process and the tasks reading its output
+struct WorkerProcess {
+    child: Child,
+    info: WorkerInfo,
+    output_readers: Vec<JoinHandle<()>>,
+}
+
+/// A worker replaced by [`Supervisor::promote_standby`], still running until
+/// it is shut down
+pub struct RetiredWorker {
+    child: Child,
+    pid: u32,
+    worker_tx: Option<mpsc::Sender<Message>>,
+    shutdown_ack: Arc<Notify>,
+}
+
+impl RetiredWorker {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Stop the worker the same way as [`Supervisor::graceful_shutdown`]
+    pub async fn shutdown(mut self, grace: Duration) -> StopOutcome {
+        info!("Stopping retired worker {} (grace {:?})", self.pid, grace);
+        let outcome =
+            Supervisor::stop_child(self.worker_tx.as_ref(), &self.shutdown_ack, &mut self.child, grace).await;
+        info!("Retired worker {} stopped: {:?}", self.pid, outcome);
+        outcome
+    }
+}
+
 pub struct Supervisor {
     config: SupervisorConfig,
+    health: HealthTracker,
+    heartbeat: Arc<Heartbeat>,
+    missed_heartbeats: HealthTracker,
+    /// When the outstanding heartbeat was sent
+    heartbeat_sent: Option<Instant>,
     worker_path: PathBuf,
     socket_path: PathBuf,
     worker: Option<Child>,
     worker_info: Option<WorkerInfo>,
-    circuit_breaker_until: Option<Instant>,
+    /// Restarts within the last `restart_window`
+    recent_restarts: VecDeque<Instant>,
+    /// Restarts since the worker was last ready, which picks the backoff
+    consecutive_restarts: usize,
+    restart_count: usize,
+    /// Set once the restart budget is exhausted
+    exhausted: bool,
+    worker_tx: Option<mpsc::Sender<Message>>,
+    shutdown_ack: Arc<Notify>,
+    log_tx: broadcast::Sender<Message>,
+    /// Tasks reading the current worker's stdout/stderr
+    output_readers: Vec<JoinHandle<()>>,
+    /// Replacement worker started by a reload, not yet serving
+    standby: Option<WorkerProcess>,
 }
 
 impl Supervisor {
@@ -86,13 +404,27 @@ impl Supervisor {
         worker_path: PathBuf,
         socket_path: PathBuf,
     ) -> Self {
+        let health = HealthTracker::new(config.health_failure_threshold, config.health_success_threshold);
+        let missed_heartbeats = HealthTracker::new(config.heartbeat_miss_threshold, 1);
         Self {
             config,
+            health,
+            heartbeat: Arc::new(Heartbeat::default()),
+            missed_heartbeats,
+            heartbeat_sent: None,
             worker_path,
             socket_path,
             worker: None,
             worker_info: None,
-            circuit_breaker_until: None,
+            recent_restarts: VecDeque::new(),
+            consecutive_restarts: 0,
+            restart_count: 0,
+            exhausted: false,
+            worker_tx: None,
+            shutdown_ack: Arc::new(Notify::new()),
+            log_tx: broadcast::channel(LOG_CHANNEL_CAPACITY).0,
+            output_readers: Vec::new(),
+            standby: None,
         }
     }
 
@@ -100,55 +432,76 @@ impl Supervisor {
         self.spawn_worker(0).await
     }
 
-    async fn spawn_worker(&mut self, restart_count: usize) -> Result<WorkerInfo, SupervisorError> {
-        // Check circuit breaker
-        if let Some(until) = self.circuit_breaker_until {
-            if Instant::now() < until {
-                return Err(SupervisorError::CircuitBreakerOpen);
-            } else {
-                // Reset circuit breaker
-                self.circuit_breaker_until = None;
-                info!("Circuit breaker reset");
+    /// Delay before the restart following `attempt` earlier ones
+    fn restart_backoff(&self, attempt: usize) -> Duration {
+        let schedule = &self.config.restart_backoff;
+        let backoff = match schedule.get(attempt) {
+            Some(&backoff) => backoff,
+            None => {
+                let doublings = (attempt + 1 - schedule.len()).min(31) as u32;
+                let last = schedule.last().copied().unwrap_or_default();
+                last.saturating_mul(2u32.pow(doublings))
             }
-        }
+        };
+        backoff.min(self.config.max_restart_backoff)
+    }
 
-        // Check max restarts
-        if restart_count >= self.config.max_restarts {
-            error!("Max restart attempts exceeded");
-            self.circuit_breaker_until = Some(Instant::now() + Duration::from_secs(30));
-            return Err(SupervisorError::MaxRestartsExceeded);
-        }
+    async fn spawn_worker(&mut self, restart_count: usize) -> Result<WorkerInfo, SupervisorError> {
+        let WorkerProcess { child, info, output_readers } = self.launch(restart_count)?;
 
-        // Apply backoff if restarting
-        if restart_count > 0 {
-            let backoff_idx = (restart_count - 1).min(self.config.restart_backoff.len() - 1);
-            let backoff = self.config.restart_backoff[backoff_idx];
-            if !backoff.is_zero() {
-                info!("Restart backoff: {:?}", backoff);
-                tokio::time::sleep(backoff).await;
-            }
+        for reader in std::mem::replace(&mut self.output_readers, output_readers) {
+            reader.abort();
         }
+        self.worker = Some(child);
+        self.worker_info = Some(info.clone());
+        self.missed_heartbeats.reset();
+        self.heartbeat_sent = None;
 
-        // Spawn worker process
+        Ok(info)
+    }
+
+    /// Start a worker process without making it the current one
+    fn launch(&self, restart_count: usize) -> Result<WorkerProcess, SupervisorError> {
         info!(
-            "Spawning worker: {} (attempt {}/{})",
+            "Spawning worker: {} (restart {})",
             self.worker_path.display(),
-            restart_count + 1,
-            self.config.max_restarts
+            restart_count
         );
 
         let mut cmd = Command::new(&self.worker_path);
-        cmd.env("ZAP_SOCKET", &self.socket_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+        cmd.envs(self.config.env.iter().map(|(key, value)| (key, value)))
+            .env("ZAP_SOCKET", &self.socket_path)
             .kill_on_drop(true);
+        if self.config.capture_output {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+        if let Some(dir) = &self.config.working_dir {
+            cmd.current_dir(dir);
+        }
+
+        #[cfg(unix)]
+        if !self.config.limits.is_empty() {
+            let limits = self.config.limits.clone();
+            // SAFETY: the closure only makes async-signal-safe libc calls
+            unsafe {
+                cmd.pre_exec(move || limits.apply());
+            }
+        }
 
         let mut child = cmd.spawn()?;
         let pid = child.id().unwrap_or(0);
 
         info!("Worker spawned with PID {}", pid);
 
-        let worker_info = WorkerInfo {
+        let mut output_readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            output_readers.push(spawn_output_reader(stdout, "stdout", pid, self.log_tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            output_readers.push(spawn_output_reader(stderr, "stderr", pid, self.log_tx.clone()));
+        }
+
+        let info = WorkerInfo {
             pid,
             state: WorkerState::Starting,
             started_at: Instant::now(),
@@ -157,63 +510,258 @@ impl Supervisor {
             total_requests: 0,
         };
 
-        self.worker = Some(child);
-        self.worker_info = Some(worker_info.clone());
+        Ok(WorkerProcess { child, info, output_readers })
+    }
 
-        Ok(worker_info)
+    /// Start a replacement worker alongside the current one
+    ///
+    /// The current worker keeps serving until [`Supervisor::promote_standby`];
+    /// a standby that never becomes ready is stopped with
+    /// [`Supervisor::discard_standby`]. Replaces any earlier standby.
+    pub async fn spawn_standby(&mut self) -> Result<WorkerInfo, SupervisorError> {
+        self.discard_standby().await;
+        let standby = self.launch(self.restart_count)?;
+        let info = standby.info.clone();
+        self.standby = Some(standby);
+        Ok(info)
+    }
+
+    /// Kill the standby worker, if any
+    pub async fn discard_standby(&mut self) {
+        if let Some(mut standby) = self.standby.take() {
+            info!("Discarding standby worker {}", standby.info.pid);
+            for reader in standby.output_readers {
+                reader.abort();
+            }
+            let _ = standby.child.kill().await;
+        }
     }
 
+    /// Make the standby worker current, talking to it over `worker_tx`
+    ///
+    /// Returns the worker it replaces, which keeps running until shut down
+    /// with [`RetiredWorker::shutdown`]. Does nothing and returns `None`
+    /// when there is no standby.
+    pub fn promote_standby(&mut self, worker_tx: mpsc::Sender<Message>) -> Option<RetiredWorker> {
+        let WorkerProcess { child, mut info, output_readers } = self.standby.take()?;
+        info!("Promoting standby worker {}", info.pid);
+        info.state = WorkerState::Ready;
+
+        // The old worker's output readers end on their own when it exits
+        self.output_readers = output_readers;
+        let retired_pid = self.worker_info.replace(info).map(|info| info.pid).unwrap_or(0);
+        let retired_tx = self.worker_tx.replace(worker_tx);
+        let retired_ack = std::mem::replace(&mut self.shutdown_ack, Arc::new(Notify::new()));
+        self.health.reset();
+        self.missed_heartbeats.reset();
+        self.heartbeat_sent = None;
+        self.consecutive_restarts = 0;
+
+        self.worker.replace(child).map(|child| RetiredWorker {
+            child,
+            pid: retired_pid,
+            worker_tx: retired_tx,
+            shutdown_ack: retired_ack,
+        })
+    }
+
+    /// Replace the worker, waiting out the backoff first
+    ///
+    /// Fails with `MaxRestartsExceeded` once `max_restarts` restarts have
+    /// happened within `restart_window`; from then on the supervisor stays
+    /// failed (see [`Supervisor::is_failed`]). Callers that must keep
+    /// serving during the backoff use [`Supervisor::begin_restart`] and
+    /// [`Supervisor::respawn`] instead.
     pub async fn restart(&mut self) -> Result<WorkerInfo, SupervisorError> {
+        let backoff = self.begin_restart().await?;
+        if !backoff.is_zero() {
+            tokio::time::sleep(backoff).await;
+        }
+        self.respawn().await
+    }
+
+    /// Stop the current worker and return the backoff to wait out before
+    /// [`Supervisor::respawn`] starts its replacement
+    ///
+    /// Fails like [`Supervisor::restart`] once the restart budget is
+    /// exhausted.
+    pub async fn begin_restart(&mut self) -> Result<Duration, SupervisorError> {
+        if self.exhausted {
+            return Err(SupervisorError::MaxRestartsExceeded);
+        }
+
+        let now = Instant::now();
+        let window = self.config.restart_window;
+        self.recent_restarts.retain(|at| now.duration_since(*at) < window);
+        if self.recent_restarts.len() >= self.config.max_restarts {
+            error!(
+                "Worker restarted {} times within {:?}, giving up",
+                self.recent_restarts.len(),
+                window
+            );
+            self.exhausted = true;
+            self.update_state(WorkerState::Failed);
+            return Err(SupervisorError::MaxRestartsExceeded);
+        }
+        self.recent_restarts.push_back(now);
+
         // Shutdown current worker if exists
         if let Some(ref mut child) = self.worker {
             info!("Stopping current worker");
             let _ = child.kill().await;
         }
 
-        let restart_count = self
-            .worker_info
-            .as_ref()
-            .map(|w| w.restart_count + 1)
-            .unwrap_or(0);
+        let backoff = self.restart_backoff(self.consecutive_restarts);
+        self.consecutive_restarts += 1;
+        info!("Restart backoff: {:?}", backoff);
+        Ok(backoff)
+    }
 
-        self.spawn_worker(restart_count).await
+    /// Start the replacement for a worker stopped by
+    /// [`Supervisor::begin_restart`]
+    pub async fn respawn(&mut self) -> Result<WorkerInfo, SupervisorError> {
+        self.restart_count += 1;
+        let result = self.spawn_worker(self.restart_count).await;
+        if result.is_err() {
+            self.update_state(WorkerState::Failed);
+        }
+        result
     }
 
-    pub async fn graceful_shutdown(&mut self, timeout: Duration) -> Result<(), SupervisorError> {
-        if let Some(ref mut child) = self.worker {
-            info!("Initiating graceful shutdown");
-
-            // Send SIGTERM
-            #[cfg(unix)]
-            {
-                use nix::sys::signal::{kill, Signal};
-                use nix::unistd::Pid;
-                if let Some(pid) = child.id() {
-                    let _ = kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
-                }
+    /// Whether the restart budget is exhausted and the worker will not be
+    /// restarted again
+    pub fn is_failed(&self) -> bool {
+        self.exhausted
+    }
+
+    /// Stop the worker using the configured `shutdown_grace`
+    pub async fn stop(&mut self) -> Result<StopOutcome, SupervisorError> {
+        let grace = self.config.shutdown_grace;
+        self.graceful_shutdown(grace).await
+    }
+
+    /// Stop the worker, escalating only as far as needed:
+    ///
+    /// 1. Send `Shutdown` and wait up to `grace` for `ShutdownAck` and exit
+    /// 2. Send SIGTERM and wait up to `grace` for exit
+    /// 3. Send SIGKILL
+    pub async fn graceful_shutdown(&mut self, grace: Duration) -> Result<StopOutcome, SupervisorError> {
+        let outcome = match self.worker.take() {
+            Some(mut child) => {
+                info!("Initiating graceful shutdown (grace {:?})", grace);
+                self.update_state(WorkerState::Draining);
+
+                let outcome = Self::stop_child(self.worker_tx.as_ref(), &self.shutdown_ack, &mut child, grace).await;
+                info!("Worker stopped: {:?}", outcome);
+                outcome
             }
+            None => StopOutcome::NotRunning,
+        };
 
-            // Wait for graceful shutdown
-            let shutdown = tokio::time::timeout(timeout, child.wait());
+        self.worker_info = None;
 
-            match shutdown.await {
-                Ok(Ok(status)) => {
-                    info!("Worker exited gracefully: {:?}", status);
-                }
-                Ok(Err(e)) => {
-                    error!("Error waiting for worker: {}", e);
-                }
-                Err(_) => {
-                    warn!("Worker did not exit within timeout, sending SIGKILL");
-                    let _ = child.kill().await;
-                }
+        Ok(outcome)
+    }
+
+    /// Ask over the protocol, then escalate to signals
+    async fn stop_child(
+        worker_tx: Option<&mpsc::Sender<Message>>,
+        shutdown_ack: &Notify,
+        child: &mut Child,
+        grace: Duration,
+    ) -> StopOutcome {
+        match Self::request_shutdown(worker_tx, shutdown_ack, child, grace).await {
+            Some(outcome) => outcome,
+            None => Self::terminate(child, grace).await,
+        }
+    }
+
+    /// Ask the worker to shut down over the protocol; `None` if it is still running
+    async fn request_shutdown(
+        worker_tx: Option<&mpsc::Sender<Message>>,
+        shutdown_ack: &Notify,
+        child: &mut Child,
+        grace: Duration,
+    ) -> Option<StopOutcome> {
+        let tx = worker_tx?;
+
+        let ack = shutdown_ack.notified();
+        tokio::pin!(ack);
+        ack.as_mut().enable();
+
+        if tx.send(Message::Shutdown).await.is_err() {
+            debug!("Worker channel closed, skipping Shutdown message");
+            return None;
+        }
+
+        let deadline = Instant::now() + grace;
+        match tokio::time::timeout(grace, ack).await {
+            Ok(()) => debug!("Worker acknowledged shutdown"),
+            Err(_) => {
+                warn!("No ShutdownAck within {:?}", grace);
+                return None;
             }
         }
 
-        self.worker = None;
-        self.worker_info = None;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        Self::wait_for_exit(child, remaining)
+            .await
+            .then_some(StopOutcome::Exited)
+    }
 
-        Ok(())
+    /// SIGTERM, wait up to `grace`, then SIGKILL
+    async fn terminate(child: &mut Child, grace: Duration) -> StopOutcome {
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::{kill, Signal};
+            use nix::unistd::Pid;
+            if let Some(pid) = child.id() {
+                debug!("Sending SIGTERM to worker {}", pid);
+                let _ = kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
+            }
+        }
+
+        if Self::wait_for_exit(child, grace).await {
+            return StopOutcome::Terminated;
+        }
+
+        warn!("Worker did not exit within {:?}, sending SIGKILL", grace);
+        if let Err(e) = child.kill().await {
+            error!("Failed to kill worker: {}", e);
+        }
+        StopOutcome::Killed
+    }
+
+    /// Wait for the worker process to exit, returning false on timeout
+    async fn wait_for_exit(child: &mut Child, timeout: Duration) -> bool {
+        match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(Ok(status)) => {
+                debug!("Worker exited: {:?}", status);
+                true
+            }
+            Ok(Err(e)) => {
+                error!("Error waiting for worker: {}", e);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Channel used to send protocol messages (e.g. `Shutdown`) to the worker
+    pub fn set_worker_tx(&mut self, tx: mpsc::Sender<Message>) {
+        self.worker_tx = Some(tx);
+    }
+
+    /// Signal that the worker sent `ShutdownAck`
+    ///
+    /// Called by whoever reads the worker connection.
+    pub fn shutdown_ack_notifier(&self) -> Arc<Notify> {
+        self.shutdown_ack.clone()
+    }
+
+    /// Time a new worker has to connect
+    pub fn connect_timeout(&self) -> Duration {
+        self.config.connect_timeout
     }
 
     pub fn worker_info(&self) -> Option<&WorkerInfo> {
@@ -221,13 +769,87 @@ impl Supervisor {
     }
 
     pub fn update_state(&mut self, state: WorkerState) {
+        if state == WorkerState::Ready {
+            self.consecutive_restarts = 0;
+        }
         if let Some(ref mut info) = self.worker_info {
             info.state = state;
         }
     }
 
+    /// Run one health check against the worker's readiness
+    ///
+    /// Returns true when enough consecutive checks have failed that the
+    /// worker should be restarted; the count starts over after that.
+    pub fn check_health(&mut self) -> bool {
+        let ready = self.is_ready();
+        let restart = self.health.record(ready);
+        if restart {
+            self.health.reset();
+        } else if !ready {
+            debug!(
+                "Worker not ready ({}/{} failed checks)",
+                self.health.failures(),
+                self.config.health_failure_threshold
+            );
+        }
+        restart
+    }
+
+    /// Worker output as `LogEvent`s, for forwarding to hosts
+    ///
+    /// Only lines written after subscribing are received; a subscriber that
+    /// falls more than 256 lines behind skips the oldest.
+    pub fn subscribe_logs(&self) -> broadcast::Receiver<Message> {
+        self.log_tx.subscribe()
+    }
+
+    /// Heartbeat state to feed from the worker connection's `HealthStatus`
+    /// replies
+    pub fn heartbeat(&self) -> Arc<Heartbeat> {
+        self.heartbeat.clone()
+    }
+
+    /// Settle the outstanding heartbeat and send the next one
+    ///
+    /// Call every `heartbeat_interval`. A heartbeat counts as missed when no
+    /// `HealthStatus` arrived within `heartbeat_timeout` of sending it, or
+    /// when it could not be queued because the worker stopped reading.
+    /// Returns true, and marks the worker failed, once
+    /// `heartbeat_miss_threshold` heartbeats in a row were missed.
+    pub fn heartbeat_tick(&mut self) -> bool {
+        if let Some(sent) = self.heartbeat_sent.take() {
+            let answered = self.heartbeat.answered(sent, self.config.heartbeat_timeout);
+            if self.missed_heartbeats.record(answered) {
+                self.missed_heartbeats.reset();
+                self.update_state(WorkerState::Failed);
+                warn!(
+                    "Worker missed {} heartbeats in a row",
+                    self.config.heartbeat_miss_threshold
+                );
+                return true;
+            }
+            if !answered {
+                debug!(
+                    "Worker missed heartbeat ({}/{})",
+                    self.missed_heartbeats.failures(),
+                    self.config.heartbeat_miss_threshold
+                );
+            }
+        }
+
+        if let Some(tx) = &self.worker_tx {
+            if tx.try_send(Message::HealthCheck).is_err() {
+                debug!("Heartbeat not queued: worker channel full or closed");
+            }
+            self.heartbeat_sent = Some(Instant::now());
+        }
+        false
+    }
+
     pub fn is_ready(&self) -> bool {
-        self.worker_info
+        !self.exhausted
+            && self.worker_info
             .as_ref()
             .map(|w| w.state == WorkerState::Ready)
             .unwrap_or(false)
@@ -243,5 +865,349 @@ mod tests {
         let config = SupervisorConfig::default();
         assert_eq!(config.max_restarts, 10);
         assert_eq!(config.restart_backoff.len(), 5);
+        assert_eq!(config.shutdown_grace, Duration::from_secs(10));
+        assert!(config.env.is_empty());
+        assert!(config.working_dir.is_none());
+        assert!(config.limits.is_empty());
+    }
+
+    /// Write an executable shell script to use as a dummy worker
+    #[cfg(unix)]
+    fn dummy_worker(name: &str, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("splice-{}-{}.sh", name, std::process::id()));
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    fn test_config() -> SupervisorConfig {
+        SupervisorConfig {
+            shutdown_grace: Duration::from_millis(300),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_infer_log_level() {
+        assert_eq!(infer_log_level("ERROR failed to bind"), "ERROR");
+        assert_eq!(infer_log_level("  [WARN] slow query"), "WARN");
+        assert_eq!(infer_log_level("WARNING: deprecated"), "WARN");
+        assert_eq!(infer_log_level("thread 'main' panicked at src/main.rs:4:5:"), "ERROR");
+        assert_eq!(infer_log_level("listening on /tmp/worker.sock"), "INFO");
+        assert_eq!(infer_log_level("no ERROR here"), "INFO");
+    }
+
+    #[test]
+    fn test_infer_log_level_after_timestamp_and_colours() {
+        // tracing_subscriber's default format, with and without colours
+        assert_eq!(infer_log_level("2026-10-16T12:00:00.123456Z ERROR worker: bind failed"), "ERROR");
+        assert_eq!(
+            infer_log_level("\x1b[2m2026-10-16T12:00:00.123456Z\x1b[0m \x1b[33m WARN\x1b[0m \x1b[2mworker\x1b[0m: slow"),
+            "WARN"
+        );
+        // env_logger
+        assert_eq!(infer_log_level("[2026-10-16T12:00:00Z ERROR worker] bind failed"), "ERROR");
+        // Date and time as separate tokens
+        assert_eq!(infer_log_level("2026-10-16 12:00:00 WARNING: disk nearly full"), "WARN");
+        assert_eq!(infer_log_level("2026-10-16T12:00:00Z INFO worker: ERROR count reset"), "INFO");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_output_forwarded_as_log_events() {
+        let worker = dummy_worker(
+            "chatty",
+            "#!/bin/sh\necho 'starting up'\necho 'ERROR config missing' >&2\nprintf 'WARN no newline'\n",
+        );
+        let mut supervisor = Supervisor::new(test_config(), worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        let mut logs = supervisor.subscribe_logs();
+        let info = supervisor.start().await.unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            match tokio::time::timeout(Duration::from_secs(5), logs.recv()).await.unwrap().unwrap() {
+                Message::LogEvent { level, message, fields } => {
+                    assert!(fields.contains(&("pid".to_string(), info.pid.to_string())));
+                    let stream = fields.iter().find(|(k, _)| k == "stream").unwrap().1.clone();
+                    events.push((level, message, stream));
+                }
+                other => panic!("Expected LogEvent, got {:?}", other),
+            }
+        }
+        events.sort();
+        assert_eq!(
+            events,
+            [
+                ("ERROR".to_string(), "ERROR config missing".to_string(), "stderr".to_string()),
+                ("INFO".to_string(), "starting up".to_string(), "stdout".to_string()),
+                ("WARN".to_string(), "WARN no newline".to_string(), "stdout".to_string()),
+            ]
+        );
+        std::fs::remove_file(worker).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_terminates_cooperative_worker() {
+        let worker = dummy_worker("cooperative", "#!/bin/sh\nexec sleep 30\n");
+        let mut supervisor = Supervisor::new(test_config(), worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        supervisor.start().await.unwrap();
+
+        let start = Instant::now();
+        assert_eq!(supervisor.stop().await.unwrap(), StopOutcome::Terminated);
+        assert!(start.elapsed() < Duration::from_millis(300));
+        assert!(supervisor.worker_info().is_none());
+
+        let _ = std::fs::remove_file(worker);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_kills_stuck_worker_after_grace() {
+        let worker = dummy_worker(
+            "stuck",
+            "#!/bin/sh\ntrap '' TERM\nwhile true; do sleep 0.05; done\n",
+        );
+        let mut supervisor = Supervisor::new(test_config(), worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        supervisor.start().await.unwrap();
+        // Let the shell install its trap
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let start = Instant::now();
+        assert_eq!(supervisor.stop().await.unwrap(), StopOutcome::Killed);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+
+        let _ = std::fs::remove_file(worker);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_uses_shutdown_ack() {
+        let worker = dummy_worker("acking", "#!/bin/sh\nexec sleep 30\n");
+        let mut supervisor = Supervisor::new(test_config(), worker.clone(), PathBuf::from("/tmp/unused.sock"));
+        let (tx, mut rx) = mpsc::channel(1);
+        supervisor.set_worker_tx(tx);
+        let ack = supervisor.shutdown_ack_notifier();
+        supervisor.start().await.unwrap();
+
+        // Acknowledge but never exit: escalates to SIGTERM after the grace period
+        tokio::spawn(async move {
+            if let Some(Message::Shutdown) = rx.recv().await {
+                ack.notify_one();
+            }
+        });
+
+        assert_eq!(supervisor.stop().await.unwrap(), StopOutcome::Terminated);
+
+        let _ = std::fs::remove_file(worker);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_worker_environment_and_limits() {
+        let out = std::env::temp_dir().join(format!("splice-env-out-{}", std::process::id()));
+        let workdir = std::env::temp_dir().join(format!("splice-env-cwd-{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        let worker = dummy_worker(
+            "env",
+            &format!(
+                "#!/bin/sh\nprintf '%s\\n%s\\n%s\\n%s\\n' \"$ZAP_TEST_VAR\" \"$ZAP_SOCKET\" \"$(pwd -P)\" \"$(ulimit -n)\" > {}\nexec sleep 30\n",
+                out.display()
+            ),
+        );
+
+        let config = SupervisorConfig {
+            env: vec![
+                ("ZAP_TEST_VAR".to_string(), "injected".to_string()),
+                // Cannot override the socket the supervisor listens on
+                ("ZAP_SOCKET".to_string(), "/tmp/wrong.sock".to_string()),
+            ],
+            working_dir: Some(workdir.clone()),
+            limits: ResourceLimits {
+                max_open_files: Some(64),
+                ..Default::default()
+            },
+            ..test_config()
+        };
+        let mut supervisor = Supervisor::new(config, worker.clone(), PathBuf::from("/tmp/expected.sock"));
+        supervisor.start().await.unwrap();
+
+        let mut report = String::new();
+        for _ in 0..50 {
+            report = std::fs::read_to_string(&out).unwrap_or_default();
+            if report.lines().count() == 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        supervisor.stop().await.unwrap();
+
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 4, "worker report: {:?}", report);
+        assert_eq!(lines[0], "injected");
+        assert_eq!(lines[1], "/tmp/expected.sock");
+        assert_eq!(PathBuf::from(lines[2]), workdir.canonicalize().unwrap());
+        assert_eq!(lines[3], "64");
+
+        let _ = std::fs::remove_file(worker);
+        let _ = std::fs::remove_file(out);
+        let _ = std::fs::remove_dir(workdir);
+    }
+
+    #[tokio::test]
+    async fn test_stop_without_worker() {
+        let mut supervisor = Supervisor::new(
+            SupervisorConfig::default(),
+            PathBuf::from("/nonexistent"),
+            PathBuf::from("/tmp/unused.sock"),
+        );
+        assert_eq!(supervisor.stop().await.unwrap(), StopOutcome::NotRunning);
+    }
+
+    #[test]
+    fn test_health_tracker_needs_consecutive_failures() {
+        let mut tracker = HealthTracker::new(3, 1);
+        assert!(!tracker.record(false));
+        assert!(!tracker.record(false));
+        // A pass in between starts the count over
+        assert!(!tracker.record(true));
+        assert_eq!(tracker.failures(), 0);
+        assert!(!tracker.record(false));
+        assert!(!tracker.record(false));
+        assert!(tracker.record(false));
+    }
+
+    #[test]
+    fn test_health_tracker_success_threshold() {
+        let mut tracker = HealthTracker::new(3, 2);
+        assert!(!tracker.record(false));
+        assert!(!tracker.record(false));
+        // One pass is not enough to clear the failures
+        assert!(!tracker.record(true));
+        assert_eq!(tracker.failures(), 2);
+        assert!(tracker.record(false));
+
+        tracker.reset();
+        assert!(!tracker.record(false));
+        assert!(!tracker.record(true));
+        assert!(!tracker.record(true));
+        assert_eq!(tracker.failures(), 0);
+    }
+
+    #[test]
+    fn test_check_health_restarts_after_threshold() {
+        let config = SupervisorConfig {
+            health_failure_threshold: 2,
+            ..Default::default()
+        };
+        // Never started, so never ready
+        let mut supervisor = Supervisor::new(config, PathBuf::from("/bin/true"), PathBuf::from("/tmp/unused.sock"));
+        assert!(!supervisor.check_health());
+        assert!(supervisor.check_health());
+        // The count starts over once a restart is called for
+        assert!(!supervisor.check_health());
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_past_schedule() {
+        let supervisor = Supervisor::new(SupervisorConfig::default(), PathBuf::from("/bin/true"), PathBuf::from("/tmp/unused.sock"));
+        let backoffs: Vec<_> = (0..10).map(|attempt| supervisor.restart_backoff(attempt).as_millis()).collect();
+        assert_eq!(backoffs, [100, 200, 400, 800, 1600, 3200, 6400, 12800, 25600, 30000]);
+        assert_eq!(supervisor.restart_backoff(usize::MAX / 2), Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_restart_budget_trips_for_crash_looping_worker() {
+        let config = SupervisorConfig {
+            max_restarts: 4,
+            restart_backoff: vec![Duration::from_millis(10)],
+            max_restart_backoff: Duration::from_millis(30),
+            ..Default::default()
+        };
+        let mut supervisor =
+            Supervisor::new(config, PathBuf::from("/nonexistent/splice-worker"), PathBuf::from("/tmp/unused.sock"));
+        assert!(matches!(supervisor.start().await, Err(SupervisorError::SpawnFailed(_))));
+
+        for expected_ms in [10, 20, 30, 30] {
+            let started = Instant::now();
+            assert!(matches!(supervisor.restart().await, Err(SupervisorError::SpawnFailed(_))));
+            assert!(started.elapsed() >= Duration::from_millis(expected_ms));
+            assert!(!supervisor.is_failed());
+        }
+
+        // The fifth restart within the window trips the budget for good
+        let started = Instant::now();
+        assert!(matches!(supervisor.restart().await, Err(SupervisorError::MaxRestartsExceeded)));
+        assert!(started.elapsed() < Duration::from_millis(10));
+        assert!(supervisor.is_failed());
+        assert!(!supervisor.is_ready());
+        assert!(matches!(supervisor.restart().await, Err(SupervisorError::MaxRestartsExceeded)));
+    }
+
+    #[tokio::test]
+    async fn test_begin_restart_hands_back_the_backoff() {
+        let config = SupervisorConfig {
+            restart_backoff: vec![Duration::from_secs(5)],
+            ..Default::default()
+        };
+        let mut supervisor =
+            Supervisor::new(config, PathBuf::from("/nonexistent/splice-worker"), PathBuf::from("/tmp/unused.sock"));
+
+        // The caller owns the wait, so nothing here sleeps
+        let started = Instant::now();
+        assert_eq!(supervisor.begin_restart().await.unwrap(), Duration::from_secs(5));
+        assert_eq!(supervisor.begin_restart().await.unwrap(), Duration::from_secs(10));
+        assert!(matches!(supervisor.respawn().await, Err(SupervisorError::SpawnFailed(_))));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_restarts_outside_window_do_not_count() {
+        let config = SupervisorConfig {
+            max_restarts: 1,
+            restart_window: Duration::from_millis(50),
+            restart_backoff: vec![Duration::ZERO],
+            ..Default::default()
+        };
+        let mut supervisor =
+            Supervisor::new(config, PathBuf::from("/nonexistent/splice-worker"), PathBuf::from("/tmp/unused.sock"));
+        assert!(matches!(supervisor.restart().await, Err(SupervisorError::SpawnFailed(_))));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(matches!(supervisor.restart().await, Err(SupervisorError::SpawnFailed(_))));
+        assert!(!supervisor.is_failed());
+    }
+
+    #[tokio::test]
+    async fn test_missed_heartbeats_trigger_restart() {
+        let config = SupervisorConfig {
+            heartbeat_timeout: Duration::from_millis(50),
+            heartbeat_miss_threshold: 2,
+            ..Default::default()
+        };
+        let mut supervisor = Supervisor::new(config, PathBuf::from("/bin/true"), PathBuf::from("/tmp/unused.sock"));
+        let (tx, mut rx) = mpsc::channel(8);
+        supervisor.set_worker_tx(tx);
+        let heartbeat = supervisor.heartbeat();
+
+        // Answered heartbeats keep the worker healthy
+        for _ in 0..3 {
+            assert!(!supervisor.heartbeat_tick());
+            assert!(matches!(rx.recv().await, Some(Message::HealthCheck)));
+            heartbeat.record_reply();
+        }
+
+        // A reply after the timeout doesn't count
+        assert!(!supervisor.heartbeat_tick());
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        heartbeat.record_reply();
+        assert!(!supervisor.heartbeat_tick());
+        assert!(supervisor.heartbeat_tick());
+
+        // The count starts over once a restart is called for
+        assert!(!supervisor.heartbeat_tick());
+        assert!(!supervisor.heartbeat_tick());
     }
 }
@@ -0,0 +1,439 @@
+//! Streaming uploads (request direction)
+//!
+//! Mirrors response streaming in reverse so a large request body reaches the
+//! worker incrementally instead of being buffered whole:
+//!
+//! ```text
+//! uploader                         receiver
+//!    │── StreamStart { window } ──────►│  open upload, initial credit
+//!    │── Invoke ──────────────────────►│  function + params
+//!    │── StreamChunk { sequence } ────►│  up to `window` unacknowledged
+//!    │◄──── StreamAck { ack_sequence,  │  `ack_sequence` chunks consumed,
+//!    │                  window } ──────│  `window` more may be in flight
+//!    │── StreamEnd { total_chunks } ──►│
+//!    │◄────────────── InvokeResult ────│
+//! ```
+//!
+//! A `StreamAck` with `window: 0` is an explicit pause: the uploader sends
+//! nothing more, even chunks it still has credit for, until an ack with a
+//! nonzero window resumes it. This lets an overwhelmed receiver push back
+//! without simply going quiet.
+//!
+//! Chunk sequences start at 0. A receiver that cannot accept the upload
+//! answers with `StreamError`. On `StreamEnd` the receiver confirms that the
+//! sequences it saw were contiguous and add up to `total_chunks`; a lost chunk
+//! is reported as `StreamError` with `ERR_EXECUTION_FAILED` rather than
+//! leaving the body silently short. The uploader may also abort with
+//! `StreamError`, after which no `StreamEnd` follows.
+
+use crate::protocol::{Message, ERR_EXECUTION_FAILED, ERR_UNAVAILABLE};
+use crate::window::SendWindow;
+use bytes::Bytes;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+/// Default number of chunks in flight before the uploader waits for an ack
+pub const DEFAULT_UPLOAD_WINDOW: u32 = 16;
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum UploadError {
+    #[error("Out-of-order chunk: expected {expected}, got {got}")]
+    OutOfOrder { expected: u64, got: u64 },
+
+    #[error("Upload incomplete: expected {expected} chunks, received {received}")]
+    Incomplete { expected: u64, received: u64 },
+
+    #[error("Upload rejected ({code}): {message}")]
+    Rejected { code: u16, message: String },
+
+    #[error("Connection closed during upload")]
+    Closed,
+}
+
+impl UploadError {
+    /// Protocol error code reported to the peer
+    pub fn code(&self) -> u16 {
+        match self {
+            UploadError::OutOfOrder { .. } | UploadError::Incomplete { .. } => ERR_EXECUTION_FAILED,
+            UploadError::Rejected { code, .. } => *code,
+            UploadError::Closed => ERR_UNAVAILABLE,
+        }
+    }
+
+    /// `StreamError` reporting this failure for `request_id`
+    pub fn to_stream_error(&self, request_id: u64) -> Message {
+        Message::StreamError {
+            request_id,
+            code: self.code(),
+            message: self.to_string(),
+        }
+    }
+}
+
+/// Sending half of an upload, honouring the receiver's window
+#[derive(Debug)]
+pub struct UploadSender {
+    request_id: u64,
+    tx: mpsc::Sender<Message>,
+    acks: mpsc::Receiver<Message>,
+    window: SendWindow,
+}
+
+impl UploadSender {
+    /// Open an upload by sending `StreamStart`
+    ///
+    /// `acks` must receive the `StreamAck`/`StreamError` messages for
+    /// `request_id`.
+    pub async fn start(
+        request_id: u64,
+        window: u32,
+        tx: mpsc::Sender<Message>,
+        acks: mpsc::Receiver<Message>,
+    ) -> Result<Self, UploadError> {
+        let window = SendWindow::new(window);
+        tx.send(Message::StreamStart { request_id, window: window.window() })
+            .await
+            .map_err(|_| UploadError::Closed)?;
+
+        Ok(Self {
+            request_id,
+            tx,
+            acks,
+            window,
+        })
+    }
+
+    /// Send the next chunk, waiting for credit when the window is full or
+    /// the receiver has paused the upload
+    pub async fn send(&mut self, data: Bytes) -> Result<(), UploadError> {
+        // Take any acks that already arrived so they never pile up
+        while let Ok(msg) = self.acks.try_recv() {
+            self.apply(msg)?;
+        }
+        while !self.window.has_credit() {
+            match self.acks.recv().await {
+                Some(msg) => self.apply(msg)?,
+                None => return Err(UploadError::Closed),
+            }
+        }
+
+        let sequence = self.window.sent();
+        self.tx
+            .send(Message::StreamChunk {
+                request_id: self.request_id,
+                sequence,
+                data,
+            })
+            .await
+            .map_err(|_| UploadError::Closed)?;
+        self.window.record_sent();
+
+        Ok(())
+    }
+
+    /// Close the upload, returning the number of chunks sent
+    pub async fn finish(self) -> Result<u64, UploadError> {
+        self.tx
+            .send(Message::StreamEnd {
+                request_id: self.request_id,
+                total_chunks: self.window.sent(),
+            })
+            .await
+            .map_err(|_| UploadError::Closed)?;
+
+        Ok(self.window.sent())
+    }
+
+    /// Abandon the upload, telling the receiver why
+    pub async fn abort(self, error: UploadError) -> UploadError {
+        let _ = self.tx.send(error.to_stream_error(self.request_id)).await;
+        error
+    }
+
+    /// Chunks sent so far
+    pub fn sent(&self) -> u64 {
+        self.window.sent()
+    }
+
+    /// Whether the receiver has paused the upload with a zero window
+    pub fn is_paused(&self) -> bool {
+        self.window.is_paused()
+    }
+
+    fn apply(&mut self, msg: Message) -> Result<(), UploadError> {
+        match msg {
+            Message::StreamAck { ack_sequence, window, .. } => {
+                self.window.apply_ack(ack_sequence, window);
+                Ok(())
+            }
+            Message::StreamError { code, message, .. } => {
+                Err(UploadError::Rejected { code, message })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Receiving half of an upload: checks ordering and produces acks
+///
+/// Acknowledges every half window so the uploader never stalls while the
+/// receiver keeps up.
+#[derive(Debug)]
+pub struct UploadReceiver {
+    request_id: u64,
+    window: u32,
+    received: u64,
+    acked: u64,
+    paused: bool,
+}
+
+impl UploadReceiver {
+    /// Track the upload opened by `StreamStart { request_id, window }`
+    pub fn new(request_id: u64, window: u32) -> Self {
+        Self {
+            request_id,
+            window: window.max(1),
+            received: 0,
+            acked: 0,
+            paused: false,
+        }
+    }
+
+    /// Record a consumed chunk, returning a `StreamAck` to send if one is due
+    pub fn accept(&mut self, sequence: u64) -> Result<Option<Message>, UploadError> {
+        if sequence != self.received {
+            return Err(UploadError::OutOfOrder {
+                expected: self.received,
+                got: sequence,
+            });
+        }
+        self.received += 1;
+
+        // Chunks already in flight still arrive while paused, but acking
+        // them would hand out credit again
+        if self.paused {
+            return Ok(None);
+        }
+
+        let ack_interval = (self.window as u64 / 2).max(1);
+        if self.received - self.acked < ack_interval {
+            return Ok(None);
+        }
+
+        Ok(Some(self.ack(self.window)))
+    }
+
+    /// Pause the uploader: returns a `StreamAck` with a zero window
+    pub fn pause(&mut self) -> Message {
+        self.paused = true;
+        self.ack(0)
+    }
+
+    /// Resume a paused uploader with the full window
+    pub fn resume(&mut self) -> Message {
+        self.paused = false;
+        self.ack(self.window)
+    }
+
+    /// Whether acks are withheld because of [`pause`](Self::pause)
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn ack(&mut self, window: u32) -> Message {
+        self.acked = self.received;
+        Message::StreamAck {
+            request_id: self.request_id,
+            ack_sequence: self.received,
+            window,
+        }
+    }
+
+    /// Validate `StreamEnd { total_chunks }` against the chunks received
+    pub fn finish(&self, total_chunks: u64) -> Result<(), UploadError> {
+        if total_chunks != self.received {
+            return Err(UploadError::Incomplete {
+                expected: total_chunks,
+                received: self.received,
+            });
+        }
+        Ok(())
+    }
+
+    /// Chunks received so far
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_receiver_acks_every_half_window() {
+        let mut receiver = UploadReceiver::new(7, 4);
+        assert!(receiver.accept(0).unwrap().is_none());
+        match receiver.accept(1).unwrap() {
+            Some(Message::StreamAck { request_id, ack_sequence, window }) => {
+                assert_eq!((request_id, ack_sequence, window), (7, 2, 4));
+            }
+            other => panic!("Expected StreamAck, got {:?}", other),
+        }
+        assert!(receiver.accept(2).unwrap().is_none());
+        assert!(receiver.finish(3).is_ok());
+    }
+
+    #[test]
+    fn test_stream_end_confirms_complete_stream() {
+        let mut receiver = UploadReceiver::new(3, 16);
+        for sequence in 0..10 {
+            receiver.accept(sequence).unwrap();
+        }
+        assert_eq!(receiver.received(), 10);
+        assert!(receiver.finish(10).is_ok());
+    }
+
+    #[test]
+    fn test_missing_sequence_flagged_as_stream_error() {
+        // A chunk lost mid-stream is caught by the next sequence
+        let mut receiver = UploadReceiver::new(3, 16);
+        receiver.accept(0).unwrap();
+        receiver.accept(1).unwrap();
+        let err = receiver.accept(3).unwrap_err();
+        match err.to_stream_error(3) {
+            Message::StreamError { request_id, code, message } => {
+                assert_eq!((request_id, code), (3, ERR_EXECUTION_FAILED));
+                assert_eq!(message, "Out-of-order chunk: expected 2, got 3");
+            }
+            other => panic!("Expected StreamError, got {:?}", other),
+        }
+
+        // A lost final chunk is only visible at StreamEnd
+        let mut receiver = UploadReceiver::new(4, 16);
+        receiver.accept(0).unwrap();
+        receiver.accept(1).unwrap();
+        let err = receiver.finish(3).unwrap_err();
+        assert_eq!(err, UploadError::Incomplete { expected: 3, received: 2 });
+        assert!(matches!(
+            err.to_stream_error(4),
+            Message::StreamError { code: ERR_EXECUTION_FAILED, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sender_abort_notifies_receiver() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (_ack_tx, ack_rx) = mpsc::channel(16);
+        let mut sender = UploadSender::start(9, 4, tx, ack_rx).await.unwrap();
+        sender.send(Bytes::from_static(b"a")).await.unwrap();
+
+        let error = UploadError::Incomplete { expected: 2, received: 1 };
+        assert_eq!(sender.abort(error.clone()).await, error);
+
+        assert!(matches!(rx.recv().await, Some(Message::StreamStart { .. })));
+        assert!(matches!(rx.recv().await, Some(Message::StreamChunk { .. })));
+        assert!(matches!(
+            rx.recv().await,
+            Some(Message::StreamError { request_id: 9, code: ERR_EXECUTION_FAILED, .. })
+        ));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[test]
+    fn test_receiver_rejects_gaps_and_short_uploads() {
+        let mut receiver = UploadReceiver::new(1, 4);
+        receiver.accept(0).unwrap();
+        assert_eq!(
+            receiver.accept(2).unwrap_err(),
+            UploadError::OutOfOrder { expected: 1, got: 2 }
+        );
+        assert_eq!(
+            receiver.finish(5).unwrap_err(),
+            UploadError::Incomplete { expected: 5, received: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sender_waits_for_credit() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (ack_tx, ack_rx) = mpsc::channel(16);
+        let mut sender = UploadSender::start(1, 2, tx, ack_rx).await.unwrap();
+        assert!(matches!(rx.recv().await, Some(Message::StreamStart { window: 2, .. })));
+
+        sender.send(Bytes::from_static(b"a")).await.unwrap();
+        sender.send(Bytes::from_static(b"b")).await.unwrap();
+
+        // Window exhausted: the third chunk waits for an ack
+        let third = tokio::time::timeout(Duration::from_millis(50), sender.send(Bytes::from_static(b"c"))).await;
+        assert!(third.is_err());
+
+        ack_tx
+            .send(Message::StreamAck { request_id: 1, ack_sequence: 2, window: 2 })
+            .await
+            .unwrap();
+        sender.send(Bytes::from_static(b"c")).await.unwrap();
+        assert_eq!(sender.finish().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_zero_window_ack_pauses_until_resumed() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (ack_tx, ack_rx) = mpsc::channel(16);
+        let mut sender = UploadSender::start(1, 4, tx, ack_rx).await.unwrap();
+        let mut receiver = UploadReceiver::new(1, 4);
+        assert!(matches!(rx.recv().await, Some(Message::StreamStart { window: 4, .. })));
+
+        sender.send(Bytes::from_static(b"a")).await.unwrap();
+        assert!(receiver.accept(0).unwrap().is_none());
+
+        // Receiver pauses while the sender still has credit for three chunks
+        ack_tx.send(receiver.pause()).await.unwrap();
+        let blocked = tokio::time::timeout(Duration::from_millis(50), sender.send(Bytes::from_static(b"b"))).await;
+        assert!(blocked.is_err());
+        assert!(sender.is_paused());
+        assert!(matches!(rx.recv().await, Some(Message::StreamChunk { sequence: 0, .. })));
+        assert!(rx.try_recv().is_err());
+
+        // A nonzero window resumes sending
+        ack_tx.send(receiver.resume()).await.unwrap();
+        sender.send(Bytes::from_static(b"b")).await.unwrap();
+        assert!(!sender.is_paused());
+        assert!(matches!(rx.recv().await, Some(Message::StreamChunk { sequence: 1, .. })));
+    }
+
+    #[test]
+    fn test_paused_receiver_withholds_acks() {
+        let mut receiver = UploadReceiver::new(2, 2);
+        assert!(matches!(receiver.pause(), Message::StreamAck { window: 0, ack_sequence: 0, .. }));
+
+        // In-flight chunks are accepted without handing out credit
+        assert!(receiver.accept(0).unwrap().is_none());
+        assert!(receiver.accept(1).unwrap().is_none());
+        assert!(receiver.is_paused());
+
+        assert!(matches!(receiver.resume(), Message::StreamAck { window: 2, ack_sequence: 2, .. }));
+        assert!(matches!(
+            receiver.accept(2).unwrap(),
+            Some(Message::StreamAck { window: 2, ack_sequence: 3, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sender_surfaces_rejection() {
+        let (tx, _rx) = mpsc::channel(16);
+        let (ack_tx, ack_rx) = mpsc::channel(16);
+        let mut sender = UploadSender::start(1, 1, tx, ack_rx).await.unwrap();
+        sender.send(Bytes::from_static(b"a")).await.unwrap();
+
+        ack_tx
+            .send(Message::StreamError { request_id: 1, code: 1000, message: "no".into() })
+            .await
+            .unwrap();
+        assert!(matches!(
+            sender.send(Bytes::from_static(b"b")).await,
+            Err(UploadError::Rejected { code: 1000, .. })
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+//! Credit-based flow control for streamed chunks
+//!
+//! A stream opens with `StreamStart { window }`. The producer may then have
+//! at most `window` chunks sent but not yet covered by a `StreamAck`; once
+//! `sent - acked >= window` it must wait. Each `StreamAck { ack_sequence,
+//! window }` moves the acknowledged count forward and may resize the window,
+//! and a zero window pauses the producer until a later ack reopens it.
+//!
+//! [`SendWindow`] only does the accounting, so any producer that reads acks
+//! its own way can share it; [`crate::upload::UploadSender`] is one.
+
+/// Producer-side view of a stream's window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendWindow {
+    window: u32,
+    sent: u64,
+    acked: u64,
+    /// Receiver asked for a stop with a zero window
+    paused: bool,
+}
+
+impl SendWindow {
+    /// Window advertised in `StreamStart`; at least one chunk is allowed
+    pub fn new(window: u32) -> Self {
+        Self {
+            window: window.max(1),
+            sent: 0,
+            acked: 0,
+            paused: false,
+        }
+    }
+
+    /// Whether another chunk may be sent now
+    pub fn has_credit(&self) -> bool {
+        !self.paused && self.unacked() < self.window as u64
+    }
+
+    /// Record a sent chunk, returning its sequence number
+    pub fn record_sent(&mut self) -> u64 {
+        let sequence = self.sent;
+        self.sent += 1;
+        sequence
+    }
+
+    /// Apply `StreamAck { ack_sequence, window }`
+    ///
+    /// Acks never move backwards or past what was sent, so a stale or
+    /// duplicated ack cannot hand out extra credit.
+    pub fn apply_ack(&mut self, ack_sequence: u64, window: u32) {
+        self.acked = self.acked.max(ack_sequence.min(self.sent));
+        self.paused = window == 0;
+        if window > 0 {
+            self.window = window;
+        }
+    }
+
+    /// Chunks sent but not yet acknowledged
+    pub fn unacked(&self) -> u64 {
+        self.sent - self.acked
+    }
+
+    /// Chunks sent so far
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Current window size
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// Whether the receiver has paused the stream with a zero window
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_of_two_stalls_until_ack() {
+        let mut window = SendWindow::new(2);
+        assert_eq!(window.record_sent(), 0);
+        assert_eq!(window.record_sent(), 1);
+        assert!(!window.has_credit());
+        assert_eq!(window.unacked(), 2);
+
+        window.apply_ack(1, 2);
+        assert!(window.has_credit());
+        assert_eq!(window.record_sent(), 2);
+        assert!(!window.has_credit());
+    }
+
+    #[test]
+    fn test_zero_window_pauses_and_resize_applies() {
+        let mut window = SendWindow::new(4);
+        window.record_sent();
+        window.apply_ack(1, 0);
+        assert!(window.is_paused());
+        assert!(!window.has_credit());
+
+        window.apply_ack(1, 8);
+        assert!(!window.is_paused());
+        assert_eq!(window.window(), 8);
+        assert!(window.has_credit());
+    }
+
+    #[test]
+    fn test_stale_and_excess_acks_grant_no_extra_credit() {
+        let mut window = SendWindow::new(1);
+        window.record_sent();
+        window.record_sent();
+
+        // An ack beyond what was sent is clamped
+        window.apply_ack(5, 1);
+        assert_eq!(window.unacked(), 0);
+        window.record_sent();
+        assert!(!window.has_credit());
+
+        // An older ack does not move the window back
+        window.apply_ack(1, 1);
+        assert_eq!(window.unacked(), 1);
+        assert!(!window.has_credit());
+    }
+
+    #[test]
+    fn test_zero_window_is_raised_to_one() {
+        let window = SendWindow::new(0);
+        assert_eq!(window.window(), 1);
+        assert!(window.has_credit());
+    }
+}
@@ -0,0 +1,94 @@
+//! Allocation checks for zero-copy decoding
+//!
+//! Counting allocations needs a global allocator, so these run in their own
+//! test binary instead of the library's unit tests.
+
+use bytes::{Bytes, BytesMut};
+use splice::protocol::{Message, RequestContext, SpliceCodec, PRIORITY_NORMAL};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Counts bytes allocated on the current thread
+mod alloc_counter {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    struct Counting;
+
+    thread_local! {
+        static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn record(bytes: usize) {
+        let _ = ALLOCATED.try_with(|a| a.set(a.get() + bytes));
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: Counting = Counting;
+
+    /// Run `f`, returning its result and the bytes it allocated
+    pub fn measure<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATED.with(|a| a.get());
+        let result = f();
+        let after = ALLOCATED.with(|a| a.get());
+        (result, after - before)
+    }
+}
+
+fn encode_large_invoke(size: usize) -> BytesMut {
+    let mut buf = BytesMut::new();
+    let msg = Message::Invoke {
+        request_id: 1,
+        function_name: "upload".to_string(),
+        params: Bytes::from(vec![7u8; size]),
+        deadline_ms: 1000,
+        context: RequestContext {
+            trace_id: 1,
+            span_id: 1,
+            headers: vec![],
+            auth: None,
+        },
+        priority: PRIORITY_NORMAL,
+    };
+    SpliceCodec::default().encode(msg, &mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_zero_copy_decode_allocations() {
+    const SIZE: usize = 1024 * 1024;
+
+    // Before: plain deserialization copies `params` out of the frame
+    let frame = encode_large_invoke(SIZE);
+    let payload = frame[5..].to_vec();
+    let (copied, copying_bytes) =
+        alloc_counter::measure(|| rmp_serde::from_slice::<Message>(&payload).unwrap());
+
+    // After: the codec hands out a slice of the frame
+    let mut frame = encode_large_invoke(SIZE);
+    let (shared, zero_copy_bytes) =
+        alloc_counter::measure(|| SpliceCodec::default().decode(&mut frame).unwrap().unwrap());
+
+    assert!(copying_bytes >= SIZE, "copying decode allocated {} bytes", copying_bytes);
+    assert!(zero_copy_bytes < SIZE / 8, "zero-copy decode allocated {} bytes", zero_copy_bytes);
+
+    match (copied, shared) {
+        (Message::Invoke { params: a, .. }, Message::Invoke { params: b, .. }) => assert_eq!(a, b),
+        _ => panic!("Message type mismatch"),
+    }
+}
@@ -1,5 +1,6 @@
 mod splice_mock;
 
+use splice::admin::{AdminReply, WorkerStats};
 use splice::protocol::*;
 use splice_mock::*;
 use serde_json::json;
@@ -19,7 +20,7 @@ fn create_test_export(name: &str) -> ExportMetadata {
     }
 }
 
-// ========== Category 1: Protocol Compliance Tests (8 tests) ==========
+// ========== Category 1: Protocol Compliance Tests (12 tests) ==========
 
 #[tokio::test]
 async fn test_protocol_version_validation() {
@@ -100,6 +101,61 @@ async fn test_frame_size_limit_enforcement() {
     let _ = result;
 }
 
+#[tokio::test]
+async fn test_negotiated_frame_size_rejects_oversized_invoke() {
+    use futures::{SinkExt, StreamExt};
+    use splice::router::{Router, RouterConfig, RouterError};
+
+    const ONE_MB: u32 = 1024 * 1024;
+    let (runtime_io, worker_io) = tokio::io::duplex(64 * 1024);
+    let mut runtime = SpliceCodec::default().framed(runtime_io);
+    let mut worker = SpliceCodec::new(ONE_MB).framed(worker_io);
+
+    worker
+        .send(Message::Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            role: Role::Worker,
+            capabilities: 0,
+            max_frame_size: ONE_MB,
+        })
+        .await
+        .unwrap();
+    let peer_max = match runtime.next().await {
+        Some(Ok(Message::Handshake { max_frame_size, .. })) => max_frame_size,
+        other => panic!("Expected Handshake, got {:?}", other),
+    };
+    assert_eq!(runtime.codec_mut().negotiate_max_frame_size(peer_max), ONE_MB);
+
+    // The router refuses the invoke before it reaches the connection
+    let router = Router::new(RouterConfig::default());
+    router.set_worker_max_frame_size(ONE_MB);
+    let params = Bytes::from(vec![b'x'; 2 * ONE_MB as usize]);
+    let result = router
+        .invoke("upload".to_string(), params.clone(), 0, RequestContext::default())
+        .await;
+    assert!(matches!(
+        result,
+        Err(RouterError::FrameTooLarge { size, limit: ONE_MB }) if size == params.len()
+    ));
+
+    // The encoder refuses it too, without writing anything
+    let invoke = Message::Invoke {
+        request_id: 1,
+        function_name: "upload".to_string(),
+        params,
+        deadline_ms: 0,
+        context: RequestContext::default(),
+        priority: PRIORITY_NORMAL,
+    };
+    assert!(matches!(runtime.send(invoke.clone()).await, Err(ProtocolError::FrameTooLarge(_))));
+
+    // A peer that ignores the limit gets an error at the frame header
+    // instead of the worker buffering the whole frame
+    let mut unlimited = SpliceCodec::default().framed(runtime.into_inner());
+    tokio::spawn(async move { unlimited.send(invoke).await });
+    assert!(matches!(worker.next().await, Some(Err(ProtocolError::FrameTooLarge(_)))));
+}
+
 #[tokio::test]
 async fn test_messagepack_serialization_fidelity() {
     let harness = TestHarness::new();
@@ -235,6 +291,93 @@ async fn test_export_metadata_complete() {
     assert!(host.exports[1].is_async);
 }
 
+#[tokio::test]
+async fn test_admin_stats_command() {
+    let harness = TestHarness::new();
+    let ((host_tx, host_rx), (worker_tx, worker_rx)) = harness.split();
+
+    let worker = MockWorkerBuilder::new()
+        .with_export(create_test_export("echo"))
+        .with_dispatcher(|_name, params| Ok(params))
+        .build(worker_rx, worker_tx);
+
+    tokio::spawn(worker.run());
+
+    let mut host = MockHostBuilder::new().build(host_tx, host_rx);
+    host.connect().await.unwrap();
+    host.invoke("echo", json!({"n": 1})).await.unwrap();
+    host.invoke("echo", json!({"n": 2})).await.unwrap();
+
+    let stats = host.admin("stats").await.unwrap();
+    assert_eq!(
+        stats,
+        AdminReply::Stats(WorkerStats {
+            uptime_ms: 0,
+            active_requests: 0,
+            total_requests: 2,
+            memory_rss_bytes: None,
+        })
+    );
+
+    // Arbitrary commands are refused, and the connection stays usable
+    assert!(matches!(host.admin("exec").await.unwrap(), AdminReply::Refused(_)));
+    assert!(host.health_check().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_handshake_ack_reports_server_id() {
+    let harness = TestHarness::new();
+    let ((host_tx, host_rx), (worker_tx, worker_rx)) = harness.split();
+
+    let worker = MockWorkerBuilder::new()
+        .with_export(create_test_export("test"))
+        .with_server_id([0xAB; 16])
+        .build(worker_rx, worker_tx);
+
+    tokio::spawn(worker.run());
+
+    let mut host = MockHostBuilder::new().build(host_tx, host_rx);
+    host.send_raw(Message::Handshake {
+        protocol_version: PROTOCOL_VERSION,
+        role: Role::Host,
+        capabilities: 0,
+        max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+    })
+    .await
+    .unwrap();
+
+    match host.recv_raw().await.unwrap() {
+        Message::HandshakeAck { server_id, export_count, .. } => {
+            assert_eq!(server_id, [0xAB; 16]);
+            assert_eq!(export_count, 1);
+        }
+        other => panic!("Expected HandshakeAck, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_streamed_upload_roundtrip() {
+    let harness = TestHarness::new();
+    let ((host_tx, host_rx), (worker_tx, worker_rx)) = harness.split();
+
+    let worker = MockWorkerBuilder::new()
+        .with_export(create_test_export("upload"))
+        .with_upload_dispatcher(|_name, params, body| {
+            Ok(json!({ "name": params["name"], "size": body.len() }))
+        })
+        .build(worker_rx, worker_tx);
+
+    tokio::spawn(worker.run());
+
+    let mut host = MockHostBuilder::new().build(host_tx, host_rx);
+    host.connect().await.unwrap();
+
+    let chunks = vec![Bytes::from(vec![1u8; 100]); 8];
+    let result = host.upload("upload", json!({ "name": "file.bin" }), chunks, 2).await.unwrap();
+    assert_eq!(result["name"], "file.bin");
+    assert_eq!(result["size"], 800);
+}
+
 // ========== Category 2: Performance & Throughput Tests (5 tests) ==========
 
 #[tokio::test]
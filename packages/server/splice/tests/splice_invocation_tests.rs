@@ -6,7 +6,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 
 // Import protocol types
-use splice::protocol::{ExportMetadata, CAP_STREAMING, CAP_CANCELLATION};
+use splice::protocol::{ExportMetadata, Message, RequestContext, CAP_STREAMING, CAP_CANCELLATION, PRIORITY_NORMAL};
 
 // ========== Helper Functions ==========
 
@@ -953,3 +953,173 @@ async fn test_empty_function_name() {
     let result = host.invoke("", json!({})).await;
     assert!(result.is_err());
 }
+
+// ========== Category 9: Streaming Upload Tests (4 tests) ==========
+
+fn upload_worker(worker_rx: tokio::sync::mpsc::Receiver<Message>, worker_tx: tokio::sync::mpsc::Sender<Message>) -> MockWorker {
+    MockWorkerBuilder::new()
+        .with_export(create_async_export("upload"))
+        .with_upload_dispatcher(|_name, params, body| {
+            Ok(json!({ "name": params["name"], "size": body.len(), "sum": body.iter().map(|&b| b as u64).sum::<u64>() }))
+        })
+        .build(worker_rx, worker_tx)
+}
+
+fn upload_chunks(count: usize, size: usize) -> Vec<bytes::Bytes> {
+    (0..count)
+        .map(|i| bytes::Bytes::from(vec![(i % 256) as u8; size]))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_multi_chunk_upload_reassembled() {
+    let harness = TestHarness::new();
+    let ((host_tx, host_rx), (worker_tx, worker_rx)) = harness.split();
+
+    tokio::spawn(upload_worker(worker_rx, worker_tx).run());
+
+    let mut host = MockHostBuilder::new().build(host_tx, host_rx);
+    host.connect().await.unwrap();
+
+    let chunks = upload_chunks(50, 1024);
+    let expected_sum: u64 = chunks.iter().flat_map(|c| c.iter()).map(|&b| b as u64).sum();
+
+    let result = host
+        .upload("upload", json!({ "name": "big.bin" }), chunks, 4)
+        .await
+        .unwrap();
+
+    assert_eq!(result["name"], "big.bin");
+    assert_eq!(result["size"], 50 * 1024);
+    assert_eq!(result["sum"], expected_sum);
+}
+
+#[tokio::test]
+async fn test_upload_rejected_without_handler() {
+    let harness = TestHarness::new();
+    let ((host_tx, host_rx), (worker_tx, worker_rx)) = harness.split();
+
+    let worker = MockWorkerBuilder::new()
+        .with_export(create_async_export("upload"))
+        .with_dispatcher(|_name, _params| Ok(json!(null)))
+        .build(worker_rx, worker_tx);
+    tokio::spawn(worker.run());
+
+    let mut host = MockHostBuilder::new().build(host_tx, host_rx);
+    host.connect().await.unwrap();
+
+    let result = host.upload("upload", json!({}), upload_chunks(4, 16), 1).await;
+    assert_eq!(result.unwrap_err(), "Uploads not supported");
+}
+
+#[tokio::test]
+async fn test_router_streams_upload_to_worker() {
+    use splice::protocol::{Role, PROTOCOL_VERSION, DEFAULT_MAX_FRAME_SIZE};
+    use splice::router::{Router, RouterConfig, UploadOptions};
+    use std::sync::Arc;
+
+    let harness = TestHarness::new();
+    let ((host_tx, mut host_rx), (worker_tx, worker_rx)) = harness.split();
+    tokio::spawn(upload_worker(worker_rx, worker_tx).run());
+
+    // Handshake and export discovery on behalf of the router
+    host_tx
+        .send(Message::Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            role: Role::Host,
+            capabilities: CAP_STREAMING | CAP_CANCELLATION,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        })
+        .await
+        .unwrap();
+    assert!(matches!(host_rx.recv().await, Some(Message::HandshakeAck { .. })));
+    host_tx.send(Message::ListExports).await.unwrap();
+    let exports = match host_rx.recv().await {
+        Some(Message::ListExportsResult { exports }) => exports,
+        other => panic!("Expected ListExportsResult, got {:?}", other),
+    };
+
+    let mut router = Router::new(RouterConfig::default());
+    router.set_worker_tx(host_tx);
+    router.update_exports(exports).await;
+    let router = Arc::new(router);
+
+    // Worker → router bridge
+    {
+        let router = router.clone();
+        tokio::spawn(async move {
+            while let Some(msg) = host_rx.recv().await {
+                router.handle_worker_message(msg).await;
+            }
+        });
+    }
+
+    // Feed the body incrementally
+    let (body_tx, body_rx) = tokio::sync::mpsc::channel(2);
+    tokio::spawn(async move {
+        for chunk in upload_chunks(32, 4096) {
+            body_tx.send(Ok(chunk)).await.unwrap();
+        }
+    });
+
+    let params = bytes::Bytes::from(rmp_serde::to_vec(&json!({ "name": "routed" })).unwrap());
+    let context = RequestContext {
+        trace_id: 1,
+        span_id: 1,
+        headers: vec![],
+        auth: None,
+    };
+    let result = router
+        .invoke_upload("upload".to_string(), params, 5000, context, UploadOptions { window: 4, ..Default::default() }, body_rx)
+        .await
+        .unwrap();
+
+    let result: serde_json::Value = rmp_serde::from_slice(&result).unwrap();
+    assert_eq!(result["name"], "routed");
+    assert_eq!(result["size"], 32 * 4096);
+}
+
+#[tokio::test]
+async fn test_upload_missing_chunk_flagged_at_stream_end() {
+    use splice::protocol::ERR_EXECUTION_FAILED;
+
+    let harness = TestHarness::new();
+    let ((host_tx, host_rx), (worker_tx, worker_rx)) = harness.split();
+    tokio::spawn(upload_worker(worker_rx, worker_tx).run());
+
+    let mut host = MockHostBuilder::new().build(host_tx, host_rx);
+    host.connect().await.unwrap();
+
+    // Chunk 2 of 3 never arrives
+    let params = bytes::Bytes::from(rmp_serde::to_vec(&json!({ "name": "short" })).unwrap());
+    let chunks = upload_chunks(2, 8);
+    let frames = vec![
+        Message::StreamStart { request_id: 100, window: 8 },
+        Message::Invoke {
+            request_id: 100,
+            function_name: "upload".to_string(),
+            params,
+            deadline_ms: 5000,
+            context: RequestContext { trace_id: 1, span_id: 1, headers: vec![], auth: None },
+            priority: PRIORITY_NORMAL,
+        },
+        Message::StreamChunk { request_id: 100, sequence: 0, data: chunks[0].clone() },
+        Message::StreamChunk { request_id: 100, sequence: 1, data: chunks[1].clone() },
+        Message::StreamEnd { request_id: 100, total_chunks: 3 },
+    ];
+    for frame in frames {
+        host.send_raw(frame).await.unwrap();
+    }
+
+    match host.recv_raw().await.unwrap() {
+        Message::StreamError { request_id, code, message } => {
+            assert_eq!((request_id, code), (100, ERR_EXECUTION_FAILED));
+            assert!(message.contains("expected 3 chunks, received 2"), "{}", message);
+        }
+        other => panic!("Expected StreamError, got {:?}", other),
+    }
+
+    // The connection survives and a complete upload still succeeds
+    let result = host.upload("upload", json!({ "name": "full" }), upload_chunks(3, 8), 8).await.unwrap();
+    assert_eq!(result["size"], 24);
+}
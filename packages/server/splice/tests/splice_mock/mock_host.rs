@@ -8,8 +8,9 @@ use tokio::time::timeout;
 // Import protocol types
 pub use splice::protocol::{
     Message, ExportMetadata, Role, RequestContext, AuthContext,
-    PROTOCOL_VERSION, DEFAULT_MAX_FRAME_SIZE, CAP_STREAMING, CAP_CANCELLATION,
+    PROTOCOL_VERSION, DEFAULT_MAX_FRAME_SIZE, CAP_STREAMING, CAP_CANCELLATION, PRIORITY_NORMAL,
 };
+use splice::admin::AdminReply;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HostState {
@@ -153,6 +154,7 @@ impl MockHost {
                     headers: vec![],
                     auth: None,
                 },
+                priority: PRIORITY_NORMAL,
             })
             .await
             .map_err(|e| format!("Failed to send invoke: {}", e))?;
@@ -184,6 +186,84 @@ impl MockHost {
         Ok(json)
     }
 
+    /// Invoke a function, streaming `chunks` as the request body
+    ///
+    /// Never has more than the worker's window of chunks unacknowledged.
+    pub async fn upload(
+        &mut self,
+        function_name: &str,
+        params: JsonValue,
+        chunks: Vec<Bytes>,
+        window: u32,
+    ) -> Result<JsonValue, String> {
+        if self.state != HostState::Ready {
+            return Err("Not in ready state".to_string());
+        }
+
+        let request_id = self.next_request_id;
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+
+        let params_bytes = rmp_serde::to_vec(&params)
+            .map_err(|e| format!("Failed to serialize params: {}", e))?;
+
+        let send = |msg: Message| {
+            let tx = self.tx.clone();
+            async move { tx.send(msg).await.map_err(|e| format!("Failed to send: {}", e)) }
+        };
+
+        send(Message::StreamStart { request_id, window }).await?;
+        send(Message::Invoke {
+            request_id,
+            function_name: function_name.to_string(),
+            params: Bytes::from(params_bytes),
+            deadline_ms: 30000,
+            context: RequestContext {
+                trace_id: 1,
+                span_id: 1,
+                headers: vec![],
+                auth: None,
+            },
+            priority: PRIORITY_NORMAL,
+        })
+        .await?;
+
+        let mut window = window as u64;
+        let mut acked = 0u64;
+        let mut sent = 0u64;
+        for data in chunks {
+            while sent >= acked + window {
+                match timeout(Duration::from_secs(5), self.rx.recv()).await {
+                    Ok(Some(Message::StreamAck { ack_sequence, window: w, .. })) => {
+                        acked = acked.max(ack_sequence);
+                        window = w as u64;
+                    }
+                    Ok(Some(Message::StreamError { message, .. }))
+                    | Ok(Some(Message::InvokeError { message, .. })) => return Err(message),
+                    Ok(Some(_)) => {}
+                    Ok(None) => return Err("Channel closed".to_string()),
+                    Err(_) => return Err("Ack timeout".to_string()),
+                }
+            }
+            send(Message::StreamChunk { request_id, sequence: sent, data }).await?;
+            sent += 1;
+        }
+        send(Message::StreamEnd { request_id, total_chunks: sent }).await?;
+
+        loop {
+            match timeout(Duration::from_secs(5), self.rx.recv()).await {
+                Ok(Some(Message::InvokeResult { result, .. })) => {
+                    return rmp_serde::from_slice(&result)
+                        .map_err(|e| format!("Failed to deserialize result: {}", e));
+                }
+                Ok(Some(Message::StreamError { message, .. }))
+                | Ok(Some(Message::InvokeError { message, .. })) => return Err(message),
+                Ok(Some(_)) => {}
+                Ok(None) => return Err("Channel closed".to_string()),
+                Err(_) => return Err("Invoke timeout".to_string()),
+            }
+        }
+    }
+
     /// Cancel a request
     pub async fn cancel(&mut self, request_id: u64) -> Result<(), String> {
         self.tx
@@ -218,6 +298,23 @@ impl MockHost {
         }
     }
 
+    /// Send a raw message, bypassing request bookkeeping
+    pub async fn send_raw(&self, msg: Message) -> Result<(), String> {
+        self.tx
+            .send(msg)
+            .await
+            .map_err(|e| format!("Failed to send: {}", e))
+    }
+
+    /// Receive the next raw message
+    pub async fn recv_raw(&mut self) -> Result<Message, String> {
+        match timeout(Duration::from_secs(1), self.rx.recv()).await {
+            Ok(Some(msg)) => Ok(msg),
+            Ok(None) => Err("Channel closed".to_string()),
+            Err(_) => Err("Receive timeout".to_string()),
+        }
+    }
+
     /// Send a health check
     pub async fn health_check(&mut self) -> Result<(u64, u32, u64), String> {
         self.tx
@@ -238,6 +335,30 @@ impl MockHost {
         }
     }
 
+    /// Send an admin command and decode the worker's reply
+    #[allow(dead_code)] // only the integration tests inspect workers
+    pub async fn admin(&mut self, command: &str) -> Result<AdminReply, String> {
+        let request_id = self.next_request_id;
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+        self.tx
+            .send(Message::AdminCommand {
+                request_id,
+                command: command.to_string(),
+                args: Bytes::new(),
+            })
+            .await
+            .map_err(|e| format!("Failed to send admin command: {}", e))?;
+
+        match timeout(Duration::from_secs(1), self.rx.recv()).await {
+            Ok(Some(Message::AdminResult { request_id: id, data })) if id == request_id => {
+                AdminReply::decode(&data).map_err(|e| e.to_string())
+            }
+            Ok(Some(msg)) => Err(format!("Expected AdminResult {}, got {:?}", request_id, msg)),
+            Ok(None) => Err("Channel closed".to_string()),
+            Err(_) => Err("Admin command timeout".to_string()),
+        }
+    }
+
     /// Handle incoming message
     async fn handle_message(&mut self, msg: Message) -> Result<(), String> {
         match msg {
@@ -1,4 +1,4 @@
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -9,8 +9,20 @@ use tokio::time::timeout;
 pub use splice::protocol::{
     Message, ExportMetadata, Role, ErrorKind, RequestContext, AuthContext,
     PROTOCOL_VERSION, DEFAULT_MAX_FRAME_SIZE, CAP_STREAMING, CAP_CANCELLATION,
-    ERR_INVALID_PARAMS, ERR_EXECUTION_FAILED,
+    ERR_INVALID_PARAMS, ERR_EXECUTION_FAILED, ERR_INVALID_REQUEST,
 };
+use splice::admin::{self, AdminReply, WorkerStats};
+use splice::upload::UploadReceiver;
+
+type Dispatcher = Box<dyn Fn(String, JsonValue) -> Result<JsonValue, String> + Send + Sync>;
+type UploadDispatcher = Box<dyn Fn(String, JsonValue, Bytes) -> Result<JsonValue, String> + Send + Sync>;
+
+/// Upload being reassembled, with the invoke waiting for its body
+struct PendingUpload {
+    receiver: UploadReceiver,
+    body: BytesMut,
+    invoke: Option<(String, JsonValue)>,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WorkerState {
@@ -25,14 +37,18 @@ pub struct MockWorker {
     tx: mpsc::Sender<Message>,
     state: WorkerState,
     exports: Vec<ExportMetadata>,
-    dispatcher: Box<dyn Fn(String, JsonValue) -> Result<JsonValue, String> + Send + Sync>,
+    dispatcher: Dispatcher,
+    upload_dispatcher: Option<UploadDispatcher>,
+    uploads: HashMap<u64, PendingUpload>,
     pending_requests: HashMap<u64, Instant>,
+    total_requests: u64,
     server_id: [u8; 16],
 }
 
 pub struct MockWorkerBuilder {
     exports: Vec<ExportMetadata>,
-    dispatcher: Option<Box<dyn Fn(String, JsonValue) -> Result<JsonValue, String> + Send + Sync>>,
+    dispatcher: Option<Dispatcher>,
+    upload_dispatcher: Option<UploadDispatcher>,
     server_id: [u8; 16],
 }
 
@@ -41,6 +57,7 @@ impl MockWorkerBuilder {
         Self {
             exports: Vec::new(),
             dispatcher: None,
+            upload_dispatcher: None,
             server_id: [0u8; 16],
         }
     }
@@ -63,6 +80,15 @@ impl MockWorkerBuilder {
         self
     }
 
+    /// Handle streamed uploads; receives the reassembled request body
+    pub fn with_upload_dispatcher<F>(mut self, dispatcher: F) -> Self
+    where
+        F: Fn(String, JsonValue, Bytes) -> Result<JsonValue, String> + Send + Sync + 'static,
+    {
+        self.upload_dispatcher = Some(Box::new(dispatcher));
+        self
+    }
+
     pub fn with_server_id(mut self, server_id: [u8; 16]) -> Self {
         self.server_id = server_id;
         self
@@ -85,7 +111,10 @@ impl MockWorkerBuilder {
             state: WorkerState::Init,
             exports: self.exports,
             dispatcher,
+            upload_dispatcher: self.upload_dispatcher,
+            uploads: HashMap::new(),
             pending_requests: HashMap::new(),
+            total_requests: 0,
             server_id: self.server_id,
         }
     }
@@ -204,6 +233,7 @@ impl MockWorker {
 
                 let start = Instant::now();
                 self.pending_requests.insert(request_id, start);
+                self.total_requests += 1;
 
                 // Deserialize params from MessagePack to JSON
                 let params_json: JsonValue = match rmp_serde::from_slice(&params) {
@@ -223,6 +253,12 @@ impl MockWorker {
                     }
                 };
 
+                // Streamed body: dispatch once the upload ends
+                if let Some(upload) = self.uploads.get_mut(&request_id) {
+                    upload.invoke = Some((function_name, params_json));
+                    return Ok(true);
+                }
+
                 // Call dispatcher
                 match (self.dispatcher)(function_name.clone(), params_json) {
                     Ok(result_json) => {
@@ -259,9 +295,98 @@ impl MockWorker {
                 Ok(true)
             }
 
+            Message::StreamStart { request_id, window } => {
+                if self.upload_dispatcher.is_none() {
+                    self.tx
+                        .send(Message::StreamError {
+                            request_id,
+                            code: ERR_INVALID_REQUEST,
+                            message: "Uploads not supported".to_string(),
+                        })
+                        .await?;
+                    return Ok(true);
+                }
+
+                self.uploads.insert(
+                    request_id,
+                    PendingUpload {
+                        receiver: UploadReceiver::new(request_id, window),
+                        body: BytesMut::new(),
+                        invoke: None,
+                    },
+                );
+                Ok(true)
+            }
+
+            Message::StreamChunk { request_id, sequence, data } => {
+                let Some(upload) = self.uploads.get_mut(&request_id) else {
+                    return Err(format!("Chunk for unknown upload {}", request_id).into());
+                };
+
+                match upload.receiver.accept(sequence) {
+                    Ok(ack) => {
+                        upload.body.extend_from_slice(&data);
+                        if let Some(ack) = ack {
+                            self.tx.send(ack).await?;
+                        }
+                    }
+                    Err(e) => {
+                        self.uploads.remove(&request_id);
+                        self.pending_requests.remove(&request_id);
+                        self.tx.send(e.to_stream_error(request_id)).await?;
+                    }
+                }
+                Ok(true)
+            }
+
+            Message::StreamEnd { request_id, total_chunks } => {
+                let Some(upload) = self.uploads.remove(&request_id) else {
+                    return Err(format!("StreamEnd for unknown upload {}", request_id).into());
+                };
+                if let Err(e) = upload.receiver.finish(total_chunks) {
+                    self.pending_requests.remove(&request_id);
+                    self.tx.send(e.to_stream_error(request_id)).await?;
+                    return Ok(true);
+                }
+
+                let Some((function_name, params)) = upload.invoke else {
+                    return Err("StreamEnd before Invoke".into());
+                };
+                let dispatcher = self.upload_dispatcher.as_ref().expect("checked at StreamStart");
+                let start = self.pending_requests.remove(&request_id).unwrap_or_else(Instant::now);
+
+                let response = match dispatcher(function_name, params, upload.body.freeze()) {
+                    Ok(result_json) => Message::InvokeResult {
+                        request_id,
+                        result: Bytes::from(
+                            rmp_serde::to_vec(&result_json)
+                                .map_err(|e| format!("Failed to serialize result: {}", e))?,
+                        ),
+                        duration_us: start.elapsed().as_micros() as u64,
+                    },
+                    Err(message) => Message::InvokeError {
+                        request_id,
+                        code: ERR_EXECUTION_FAILED,
+                        kind: ErrorKind::User,
+                        message,
+                        details: None,
+                    },
+                };
+                self.tx.send(response).await?;
+                Ok(true)
+            }
+
+            Message::StreamError { request_id, .. } => {
+                // Uploader aborted
+                self.uploads.remove(&request_id);
+                self.pending_requests.remove(&request_id);
+                Ok(true)
+            }
+
             Message::Cancel { request_id } => {
                 // Remove from pending if exists
                 self.pending_requests.remove(&request_id);
+                self.uploads.remove(&request_id);
 
                 // Send CancelAck
                 self.tx.send(Message::CancelAck { request_id }).await?;
@@ -292,6 +417,21 @@ impl MockWorker {
                 Ok(true)
             }
 
+            Message::AdminCommand { request_id, command, .. } => {
+                let reply = match command.as_str() {
+                    admin::CMD_STATS => AdminReply::Stats(WorkerStats {
+                        uptime_ms: 0, // Simplified for mock
+                        active_requests: self.pending_requests.len() as u32,
+                        total_requests: self.total_requests,
+                        memory_rss_bytes: None,
+                    }),
+                    other => AdminReply::unknown_command(other),
+                };
+                self.tx.send(reply.to_message(request_id)).await?;
+
+                Ok(true)
+            }
+
             _ => {
                 return Err(format!("Unexpected message type: {:?}", msg).into());
             }
@@ -29,6 +29,10 @@ pub struct ZapConfig {
     /// Unix domain socket path for IPC with TypeScript
     pub ipc_socket_path: String,
 
+    /// Persistent IPC connections shared by all TypeScript routes (default: 4)
+    #[serde(default = "default_ipc_pool_size")]
+    pub ipc_pool_size: usize,
+
     /// Splice protocol socket path for Rust functions runtime
     /// If set, connects to Splice supervisor instead of using inventory
     #[serde(default)]
@@ -66,6 +70,15 @@ pub struct ZapConfig {
     #[serde(default)]
     pub metrics_path: Option<String>,
 
+    /// Fraction of requests traced in detail (0.0 - 1.0, default: 1.0)
+    /// Errors and slow requests are always traced
+    #[serde(default = "default_trace_sample_rate")]
+    pub trace_sample_rate: f64,
+
+    /// Requests slower than this many milliseconds are always traced
+    #[serde(default)]
+    pub slow_request_threshold_ms: Option<u64>,
+
     /// RPC dispatch function (code-only, not in JSON config)
     /// Enables TypeScript handlers to call Rust functions via IPC
     #[serde(skip)]
@@ -78,6 +91,7 @@ impl std::fmt::Debug for ZapConfig {
             .field("port", &self.port)
             .field("hostname", &self.hostname)
             .field("ipc_socket_path", &self.ipc_socket_path)
+            .field("ipc_pool_size", &self.ipc_pool_size)
             .field("splice_socket_path", &self.splice_socket_path)
             .field("max_request_body_size", &self.max_request_body_size)
             .field("request_timeout_secs", &self.request_timeout_secs)
@@ -87,6 +101,8 @@ impl std::fmt::Debug for ZapConfig {
             .field("middleware", &self.middleware)
             .field("health_check_path", &self.health_check_path)
             .field("metrics_path", &self.metrics_path)
+            .field("trace_sample_rate", &self.trace_sample_rate)
+            .field("slow_request_threshold_ms", &self.slow_request_threshold_ms)
             .field("rpc_dispatch", &self.rpc_dispatch.as_ref().map(|_| "<function>"))
             .finish()
     }
@@ -133,6 +149,10 @@ pub struct StaticFileOptions {
     /// Enable gzip compression
     #[serde(default)]
     pub enable_gzip: bool,
+
+    /// Serve files as untrusted user content (download-only, never rendered)
+    #[serde(default)]
+    pub untrusted: bool,
 }
 
 /// Middleware configuration
@@ -157,6 +177,7 @@ impl Default for ZapConfig {
             port: 3000,
             hostname: "127.0.0.1".to_string(),
             ipc_socket_path: "/tmp/zap.sock".to_string(),
+            ipc_pool_size: default_ipc_pool_size(),
             splice_socket_path: None,
             max_request_body_size: 16 * 1024 * 1024, // 16MB
             request_timeout_secs: 30,
@@ -166,6 +187,8 @@ impl Default for ZapConfig {
             middleware: MiddlewareConfig::default(),
             health_check_path: "/health".to_string(),
             metrics_path: None,
+            trace_sample_rate: 1.0,
+            slow_request_threshold_ms: None,
             rpc_dispatch: None,
         }
     }
@@ -199,6 +222,9 @@ impl ZapConfig {
         if self.ipc_socket_path.is_empty() {
             return Err(ZapError::config("IPC socket path cannot be empty"));
         }
+        if self.ipc_pool_size == 0 {
+            return Err(ZapError::config("IPC pool size must be > 0"));
+        }
         if self.request_timeout_secs == 0 {
             return Err(ZapError::config("Request timeout must be > 0"));
         }
@@ -224,9 +250,11 @@ impl ZapConfig {
 // Default function values for serde
 fn default_max_body_size() -> usize { 16 * 1024 * 1024 }
 fn default_request_timeout() -> u64 { 30 }
+fn default_ipc_pool_size() -> usize { crate::connection_pool::DEFAULT_POOL_SIZE }
 fn default_keepalive_timeout() -> u64 { 75 }
 fn default_health_path() -> String { "/health".to_string() }
 fn default_is_typescript() -> bool { true }
+fn default_trace_sample_rate() -> f64 { 1.0 }
 
 /// Legacy ServerConfig for compatibility
 #[derive(Debug, Clone)]
@@ -236,7 +264,10 @@ pub struct ServerConfig {
     pub keep_alive_timeout: Duration,
     pub max_request_body_size: usize,
     pub max_headers: usize,
+    pub max_uri_length: usize,
     pub request_timeout: Duration,
+    pub trace_sample_rate: f64,
+    pub slow_request_threshold: Option<Duration>,
 }
 
 impl Default for ServerConfig {
@@ -247,7 +278,10 @@ impl Default for ServerConfig {
             keep_alive_timeout: Duration::from_secs(75),
             max_request_body_size: 16 * 1024 * 1024,
             max_headers: 100,
+            max_uri_length: zap_core::DEFAULT_MAX_URI_LENGTH,
             request_timeout: Duration::from_secs(30),
+            trace_sample_rate: 1.0,
+            slow_request_threshold: None,
         }
     }
 }
@@ -282,11 +316,26 @@ impl ServerConfig {
         self
     }
 
+    pub fn max_uri_length(mut self, length: usize) -> Self {
+        self.max_uri_length = length;
+        self
+    }
+
     pub fn request_timeout(mut self, timeout: Duration) -> Self {
         self.request_timeout = timeout;
         self
     }
 
+    pub fn trace_sample_rate(mut self, rate: f64) -> Self {
+        self.trace_sample_rate = rate;
+        self
+    }
+
+    pub fn slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_request_threshold = Some(threshold);
+        self
+    }
+
     pub fn socket_addr(&self) -> String {
         format!("{}:{}", self.hostname, self.port)
     }
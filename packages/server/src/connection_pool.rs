@@ -7,20 +7,25 @@
 //! Features:
 //! - Pool of N persistent connections (default: 4)
 //! - Health checks before use
-//! - Automatic reconnection on failure
+//! - Periodic background health probes of idle connections
+//! - Automatic reconnection on failure, with capped exponential backoff
 //! - Connection timeout handling
 //! - Fair connection distribution
+//! - Optional shared retry budget for the reconnect retry
+//! - Optional maximum connection lifetime
 
 use crate::error::{ZapError, ZapResult};
 use crate::ipc::{IpcClient, IpcEncoding, IpcMessage};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{Mutex, Semaphore};
+use crate::reliability::RetryBudget;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, MutexGuard, Semaphore, SemaphorePermit};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, warn};
 
 /// Default number of connections in the pool
-const DEFAULT_POOL_SIZE: usize = 4;
+pub(crate) const DEFAULT_POOL_SIZE: usize = 4;
 
 /// Default connection timeout in seconds
 const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
@@ -28,25 +33,84 @@ const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
 /// Default health check interval in seconds
 const HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
 
+/// Default time to wait for a health check response in seconds
+const HEALTH_CHECK_TIMEOUT_SECS: u64 = 5;
+
+/// Default delay before reconnecting a slot after its first failed attempt
+const DEFAULT_RECONNECT_BACKOFF_MS: u64 = 100;
+
+/// Default upper bound on the reconnect delay
+const DEFAULT_MAX_RECONNECT_BACKOFF_MS: u64 = 5_000;
+
 /// A pooled connection wrapper
 struct PooledConnection {
     client: Option<IpcClient>,
+    created_at: Instant,
     last_used: std::time::Instant,
     healthy: bool,
+    /// Consecutive failed reconnect attempts
+    failures: u32,
+    /// When the last reconnect failed
+    failed_at: Option<Instant>,
+    /// No reconnect is attempted before this instant
+    retry_at: Option<Instant>,
 }
 
 impl PooledConnection {
     fn new() -> Self {
         Self {
             client: None,
+            created_at: Instant::now(),
             last_used: std::time::Instant::now(),
             healthy: false,
+            failures: 0,
+            failed_at: None,
+            retry_at: None,
         }
     }
 
     fn is_valid(&self) -> bool {
         self.client.is_some() && self.healthy
     }
+
+    /// Whether the connection has outlived `max_lifetime`
+    fn expired(&self, max_lifetime: Option<Duration>, now: Instant) -> bool {
+        max_lifetime.is_some_and(|max| now.duration_since(self.created_at) >= max)
+    }
+
+    /// Whether a recent reconnect failure still holds this slot back
+    ///
+    /// `cap` shortens the wait to at most that long after the failure.
+    fn backing_off(&self, now: Instant, cap: Option<Duration>) -> bool {
+        let retry_at = match (self.retry_at, self.failed_at, cap) {
+            (Some(at), Some(failed), Some(cap)) => Some(at.min(failed + cap)),
+            (at, _, _) => at,
+        };
+        retry_at.is_some_and(|at| now < at)
+    }
+
+    fn connected(&mut self, client: IpcClient) {
+        self.client = Some(client);
+        self.healthy = true;
+        self.failures = 0;
+        self.failed_at = None;
+        self.retry_at = None;
+        self.created_at = Instant::now();
+        self.last_used = self.created_at;
+    }
+
+    /// Record a failed reconnect and schedule the next attempt
+    fn reconnect_failed(&mut self, base: Duration, max: Duration) -> Duration {
+        self.client = None;
+        self.healthy = false;
+        self.failures = self.failures.saturating_add(1);
+        let exponent = (self.failures - 1).min(16);
+        let delay = base.saturating_mul(1 << exponent).min(max);
+        let now = Instant::now();
+        self.failed_at = Some(now);
+        self.retry_at = Some(now + delay);
+        delay
+    }
 }
 
 /// Configuration for the connection pool
@@ -62,6 +126,22 @@ pub struct PoolConfig {
     pub encoding: IpcEncoding,
     /// Health check interval
     pub health_check_interval: Duration,
+    /// How long a health probe may wait for its response
+    pub health_check_timeout: Duration,
+    /// Delay before a slot retries after its first failed reconnect
+    ///
+    /// Doubles with each consecutive failure up to `max_reconnect_backoff`.
+    pub reconnect_backoff: Duration,
+    /// Upper bound on the reconnect delay
+    pub max_reconnect_backoff: Duration,
+    /// Connections older than this are re-established on their next use
+    /// (None = keep connections forever)
+    pub max_connection_lifetime: Option<Duration>,
+    /// Retry budget shared with other retry points (None = unlimited)
+    ///
+    /// Every `send_recv` counts as an original request against it; retries
+    /// made by [`ResilientIpc`](crate::reliability::ResilientIpc) do not.
+    pub retry_budget: Option<Arc<RetryBudget>>,
 }
 
 impl Default for PoolConfig {
@@ -72,6 +152,11 @@ impl Default for PoolConfig {
             socket_path: String::new(),
             encoding: IpcEncoding::default(),
             health_check_interval: Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS),
+            health_check_timeout: Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS),
+            reconnect_backoff: Duration::from_millis(DEFAULT_RECONNECT_BACKOFF_MS),
+            max_reconnect_backoff: Duration::from_millis(DEFAULT_MAX_RECONNECT_BACKOFF_MS),
+            max_connection_lifetime: None,
+            retry_budget: None,
         }
     }
 }
@@ -102,6 +187,37 @@ impl PoolConfig {
         self.encoding = encoding;
         self
     }
+
+    /// Set the interval between background health checks
+    pub fn health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = interval;
+        self
+    }
+
+    /// Set the health check response timeout
+    pub fn health_check_timeout(mut self, timeout: Duration) -> Self {
+        self.health_check_timeout = timeout;
+        self
+    }
+
+    /// Set the initial and maximum reconnect backoff
+    pub fn reconnect_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.reconnect_backoff = initial;
+        self.max_reconnect_backoff = max.max(initial);
+        self
+    }
+
+    /// Recycle connections once they are older than `lifetime`
+    pub fn max_connection_lifetime(mut self, lifetime: Duration) -> Self {
+        self.max_connection_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Share a retry budget with this pool
+    pub fn retry_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
 }
 
 /// IPC Connection Pool
@@ -119,6 +235,10 @@ pub struct ConnectionPool {
     next_index: AtomicUsize,
     /// Whether the pool is initialized
     initialized: std::sync::atomic::AtomicBool,
+    /// Requests that acquired a connection since start
+    acquisitions: AtomicU64,
+    /// Connections re-established after being lost since start
+    reconnects: AtomicU64,
 }
 
 impl ConnectionPool {
@@ -134,6 +254,8 @@ impl ConnectionPool {
             config,
             next_index: AtomicUsize::new(0),
             initialized: std::sync::atomic::AtomicBool::new(false),
+            acquisitions: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
         }
     }
 
@@ -155,9 +277,7 @@ impl ConnectionPool {
             let mut conn = conn_mutex.lock().await;
             match self.create_connection().await {
                 Ok(client) => {
-                    conn.client = Some(client);
-                    conn.healthy = true;
-                    conn.last_used = std::time::Instant::now();
+                    conn.connected(client);
                     init_count += 1;
                     debug!("Connection {} initialized", i);
                 }
@@ -204,74 +324,213 @@ impl ConnectionPool {
     /// - Automatic reconnection on failure
     /// - Connection release back to pool
     pub async fn send_recv(&self, message: IpcMessage) -> ZapResult<IpcMessage> {
+        if let Some(budget) = &self.config.retry_budget {
+            budget.record_request();
+        }
+
+        self.send_attempt(message).await
+    }
+
+    /// [`send_recv`](Self::send_recv) without recording a request against
+    /// the retry budget, for callers retrying a request they already
+    /// recorded
+    pub(crate) async fn send_attempt(&self, message: IpcMessage) -> ZapResult<IpcMessage> {
+        let mut pooled = self.acquire().await?;
+
+        let index = pooled.index;
+        let result = self.send_on(&mut pooled.conn, index, message).await;
+        // send_on already dropped the connection if it failed
+        pooled.release();
+        result
+    }
+
+    /// Take exclusive use of a connected pool slot
+    ///
+    /// Use this for exchanges that need more than one read, such as streamed
+    /// responses. The connection goes back to the pool only through
+    /// [`PooledClient::release`]; dropping the guard instead (on an error or a
+    /// cancelled request) discards the connection, since unread replies may
+    /// still be in flight on it.
+    ///
+    /// Slots whose last reconnect failed are skipped until their backoff
+    /// expires. When no slot can be used, an IPC error is returned right away
+    /// instead of waiting for a slot to come back.
+    pub async fn acquire(&self) -> ZapResult<PooledClient<'_>> {
+        self.acquire_capped(None).await
+    }
+
+    /// Acquire a connection, treating no slot as backing off for longer than
+    /// `max_backoff` after its last failed reconnect
+    ///
+    /// For callers that retry on their own schedule: with `max_backoff` no
+    /// longer than their retry window, a later attempt is guaranteed to try
+    /// reconnecting instead of finding every slot still backing off.
+    pub async fn acquire_within(&self, max_backoff: Duration) -> ZapResult<PooledClient<'_>> {
+        self.acquire_capped(Some(max_backoff)).await
+    }
+
+    async fn acquire_capped(&self, max_backoff: Option<Duration>) -> ZapResult<PooledClient<'_>> {
         // Acquire semaphore permit (limits concurrent usage)
-        let _permit = self.semaphore.acquire().await.map_err(|_| {
+        let permit = self.semaphore.acquire().await.map_err(|_| {
             ZapError::ipc("Connection pool semaphore closed")
         })?;
 
-        // Get a connection index
-        let index = self.get_connection_index().await?;
-        let conn_mutex = &self.connections[index];
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
 
-        // Try with the existing connection first
-        let mut conn = conn_mutex.lock().await;
+        // Start at the round-robin index and take the first free slot. The
+        // permit guarantees one is free unless a health probe holds it, so
+        // slots that are locked are only waited for once all others failed.
+        let start = self.get_connection_index().await?;
+        let mut last_error = None;
+        let mut locked = Vec::new();
 
-        // Check if connection is valid
-        if !conn.is_valid() {
-            debug!("Connection {} invalid, reconnecting", index);
-            match self.create_connection().await {
-                Ok(client) => {
-                    conn.client = Some(client);
-                    conn.healthy = true;
-                }
-                Err(e) => {
-                    conn.healthy = false;
-                    return Err(e);
-                }
+        for offset in 0..self.config.size {
+            let index = (start + offset) % self.config.size;
+            let Ok(conn) = self.connections[index].try_lock() else {
+                locked.push(index);
+                continue;
+            };
+            match self.prepare_slot(conn, index, max_backoff).await {
+                Ok(Some((conn, fresh))) => return Ok(PooledClient::new(conn, index, fresh, permit)),
+                Ok(None) => {}
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        for index in locked {
+            let conn = self.connections[index].lock().await;
+            match self.prepare_slot(conn, index, max_backoff).await {
+                Ok(Some((conn, fresh))) => return Ok(PooledClient::new(conn, index, fresh, permit)),
+                Ok(None) => {}
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(match last_error {
+            Some(e) => ZapError::ipc(format!("No pool connection available: {}", e)),
+            None => ZapError::ipc("No pool connection available: all connections are backing off"),
+        })
+    }
+
+    /// Make a locked slot ready for use, reconnecting it if needed
+    ///
+    /// Returns the slot and whether it was connected just now, or None when
+    /// the slot is still backing off.
+    async fn prepare_slot<'p>(
+        &self,
+        mut conn: MutexGuard<'p, PooledConnection>,
+        index: usize,
+        max_backoff: Option<Duration>,
+    ) -> ZapResult<Option<(MutexGuard<'p, PooledConnection>, bool)>> {
+        if conn.is_valid() && conn.expired(self.config.max_connection_lifetime, Instant::now()) {
+            debug!("Connection {} reached its maximum lifetime, recycling", index);
+            conn.client = None;
+            conn.healthy = false;
+        }
+
+        if conn.is_valid() {
+            return Ok(Some((conn, false)));
+        }
+        if conn.backing_off(Instant::now(), max_backoff) {
+            debug!("Connection {} backing off, skipping", index);
+            return Ok(None);
+        }
+
+        debug!("Connection {} invalid, reconnecting", index);
+        match self.create_connection().await {
+            Ok(client) => {
+                self.reconnects.fetch_add(1, Ordering::Relaxed);
+                conn.connected(client);
+                Ok(Some((conn, true)))
+            }
+            Err(e) => {
+                self.record_reconnect_failure(&mut conn, index, &e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Replace the connection held by `pooled` with a new one
+    ///
+    /// For callers whose send failed on a connection that went stale while
+    /// idle; the old connection is dropped either way.
+    pub async fn reconnect(&self, pooled: &mut PooledClient<'_>) -> ZapResult<()> {
+        pooled.conn.client = None;
+        pooled.conn.healthy = false;
+        match self.create_connection().await {
+            Ok(client) => {
+                self.reconnects.fetch_add(1, Ordering::Relaxed);
+                pooled.conn.connected(client);
+                pooled.fresh = true;
+                Ok(())
+            }
+            Err(e) => {
+                self.record_reconnect_failure(&mut pooled.conn, pooled.index, &e);
+                Err(e)
             }
         }
+    }
+
+    /// Send on a connected slot, reconnecting and retrying once on failure
+    async fn send_on(
+        &self,
+        conn: &mut PooledConnection,
+        index: usize,
+        message: IpcMessage,
+    ) -> ZapResult<IpcMessage> {
+        let Some(client) = &mut conn.client else {
+            return Err(ZapError::ipc("No connection available"));
+        };
 
-        // Send and receive
-        if let Some(client) = &mut conn.client {
-            match client.send_recv(message.clone()).await {
-                Ok(response) => {
-                    conn.last_used = std::time::Instant::now();
-                    Ok(response)
+        match client.send_recv(message.clone()).await {
+            Ok(response) => {
+                conn.last_used = Instant::now();
+                Ok(response)
+            }
+            Err(e) => {
+                // Connection failed, mark as unhealthy
+                warn!("Connection {} failed: {}, marking unhealthy", index, e);
+                conn.healthy = false;
+                conn.client = None;
+
+                if let Some(budget) = &self.config.retry_budget {
+                    if !budget.try_retry() {
+                        warn!("Retry budget exhausted, not retrying on connection {}", index);
+                        return Err(e);
+                    }
                 }
-                Err(e) => {
-                    // Connection failed, mark as unhealthy
-                    warn!("Connection {} failed: {}, marking unhealthy", index, e);
-                    conn.healthy = false;
-                    conn.client = None;
-
-                    // Try to reconnect and retry once
-                    match self.create_connection().await {
-                        Ok(mut new_client) => {
-                            match new_client.send_recv(message).await {
-                                Ok(response) => {
-                                    conn.client = Some(new_client);
-                                    conn.healthy = true;
-                                    conn.last_used = std::time::Instant::now();
-                                    Ok(response)
-                                }
-                                Err(retry_err) => {
-                                    error!("Retry also failed: {}", retry_err);
-                                    Err(retry_err)
-                                }
-                            }
+
+                // Try to reconnect and retry once
+                match self.create_connection().await {
+                    Ok(mut new_client) => match new_client.send_recv(message).await {
+                        Ok(response) => {
+                            self.reconnects.fetch_add(1, Ordering::Relaxed);
+                            conn.connected(new_client);
+                            Ok(response)
                         }
-                        Err(reconnect_err) => {
-                            error!("Reconnect failed: {}", reconnect_err);
-                            Err(reconnect_err)
+                        Err(retry_err) => {
+                            error!("Retry also failed: {}", retry_err);
+                            Err(retry_err)
                         }
+                    },
+                    Err(reconnect_err) => {
+                        error!("Reconnect failed: {}", reconnect_err);
+                        self.record_reconnect_failure(conn, index, &reconnect_err);
+                        Err(reconnect_err)
                     }
                 }
             }
-        } else {
-            Err(ZapError::ipc("No connection available"))
         }
     }
 
+    fn record_reconnect_failure(&self, conn: &mut PooledConnection, index: usize, err: &ZapError) {
+        let delay = conn.reconnect_failed(self.config.reconnect_backoff, self.config.max_reconnect_backoff);
+        warn!(
+            "Reconnect of connection {} failed ({} in a row): {}, retrying in {:?}",
+            index, conn.failures, err, delay
+        );
+    }
+
     /// Perform health check on all connections
     pub async fn health_check(&self) -> (usize, usize) {
         let mut healthy = 0;
@@ -288,6 +547,86 @@ impl ConnectionPool {
         (healthy, total)
     }
 
+    /// Probe every idle connection with a `HealthCheck` message
+    ///
+    /// Connections that are currently serving a request are skipped. The
+    /// client is taken out of its slot while it is probed, so the slot is not
+    /// locked for the round trip; a request needing the slot meanwhile opens
+    /// a new connection, which then replaces the probed one. A connection
+    /// whose probe fails, times out or gets an unexpected reply is dropped,
+    /// so the next `send_recv` on the slot reconnects. Returns the number of
+    /// connections dropped by this pass.
+    pub async fn probe_idle_connections(&self) -> usize {
+        let timeout = self.config.health_check_timeout;
+        let mut failed = 0;
+
+        for (index, conn_mutex) in self.connections.iter().enumerate() {
+            let mut client = {
+                let Ok(mut conn) = conn_mutex.try_lock() else {
+                    continue;
+                };
+                if !conn.is_valid() {
+                    continue;
+                }
+                let Some(client) = conn.client.take() else {
+                    continue;
+                };
+                client
+            };
+
+            let result = tokio::time::timeout(timeout, client.send_recv(IpcMessage::HealthCheck)).await;
+            let reason = match result {
+                Ok(Ok(IpcMessage::HealthCheckResponse)) => {
+                    // Unless the slot reconnected or was closed meanwhile
+                    let mut conn = conn_mutex.lock().await;
+                    if conn.client.is_none() && conn.healthy {
+                        conn.client = Some(client);
+                    }
+                    continue;
+                }
+                Ok(Ok(other)) => format!("unexpected reply {:?}", other),
+                Ok(Err(e)) => e.to_string(),
+                Err(_) => format!("no reply within {:?}", timeout),
+            };
+
+            warn!("Health check failed on connection {}: {}, marking unhealthy", index, reason);
+            let mut conn = conn_mutex.lock().await;
+            if conn.client.is_none() {
+                conn.healthy = false;
+            }
+            failed += 1;
+        }
+
+        failed
+    }
+
+    /// Start probing idle connections every `health_check_interval`
+    ///
+    /// The task only holds a weak reference to the pool and exits once the
+    /// pool is dropped.
+    pub fn start_health_checks(self: &Arc<Self>) -> JoinHandle<()> {
+        let pool: Weak<Self> = Arc::downgrade(self);
+        let period = self.config.health_check_interval;
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            // The first tick completes immediately
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                let Some(pool) = pool.upgrade() else {
+                    debug!("Connection pool dropped, stopping health checks");
+                    break;
+                };
+                let failed = pool.probe_idle_connections().await;
+                if failed > 0 {
+                    debug!("Health check marked {} connection(s) unhealthy", failed);
+                }
+            }
+        })
+    }
+
     /// Close all connections in the pool
     pub async fn close(&self) {
         debug!("Closing connection pool");
@@ -307,10 +646,100 @@ impl ConnectionPool {
     }
 
     /// Get pool statistics
+    ///
+    /// `busy` counts requests holding a pool permit. Slots that are not in
+    /// use are counted as `unhealthy` when they have no live connection and
+    /// as `idle` otherwise.
     pub fn stats(&self) -> PoolStats {
+        let size = self.config.size;
+        let busy = size.saturating_sub(self.semaphore.available_permits());
+        let unhealthy = self
+            .connections
+            .iter()
+            .filter(|conn| conn.try_lock().is_ok_and(|conn| !conn.is_valid()))
+            .count();
+
         PoolStats {
-            size: self.config.size,
+            size,
             initialized: self.initialized.load(Ordering::Acquire),
+            idle: size.saturating_sub(busy + unhealthy),
+            busy,
+            unhealthy,
+            total_acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            total_reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Exclusive use of one pooled connection, returned by [`ConnectionPool::acquire`]
+pub struct PooledClient<'p> {
+    conn: MutexGuard<'p, PooledConnection>,
+    index: usize,
+    /// Connected by this acquisition rather than reused
+    fresh: bool,
+    reusable: bool,
+    _permit: SemaphorePermit<'p>,
+}
+
+impl<'p> PooledClient<'p> {
+    fn new(
+        conn: MutexGuard<'p, PooledConnection>,
+        index: usize,
+        fresh: bool,
+        permit: SemaphorePermit<'p>,
+    ) -> Self {
+        Self {
+            conn,
+            index,
+            fresh,
+            reusable: false,
+            _permit: permit,
+        }
+    }
+
+    /// Whether the connection was opened for this acquisition
+    ///
+    /// A reused connection may have been closed by the runtime while idle.
+    pub fn is_fresh(&self) -> bool {
+        self.fresh
+    }
+
+    /// The connected IPC client
+    pub fn client(&mut self) -> &mut IpcClient {
+        self.conn
+            .client
+            .as_mut()
+            .expect("acquired pool slot is always connected")
+    }
+
+    /// Return the connection to the pool for reuse
+    pub fn release(mut self) {
+        self.conn.last_used = Instant::now();
+        self.reusable = true;
+    }
+
+    /// Take the connection out of the pool for a long exchange
+    ///
+    /// The slot is freed right away and reconnects on its next use, so a
+    /// streamed response does not keep a pool slot busy until it ends.
+    pub fn detach(mut self) -> IpcClient {
+        let client = self
+            .conn
+            .client
+            .take()
+            .expect("acquired pool slot is always connected");
+        self.conn.healthy = false;
+        self.reusable = true;
+        client
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        if !self.reusable {
+            debug!("Discarding connection {} after an unfinished exchange", self.index);
+            self.conn.client = None;
+            self.conn.healthy = false;
         }
     }
 }
@@ -320,17 +749,33 @@ impl ConnectionPool {
 pub struct PoolStats {
     pub size: usize,
     pub initialized: bool,
+    /// Healthy connections not serving a request
+    pub idle: usize,
+    /// Connections currently serving a request
+    pub busy: usize,
+    /// Idle slots without a live connection
+    pub unhealthy: usize,
+    pub total_acquisitions: u64,
+    /// Connections re-established after being lost or never opened
+    pub total_reconnects: u64,
 }
 
 /// Global connection pool singleton
 static GLOBAL_POOL: std::sync::OnceLock<Arc<ConnectionPool>> = std::sync::OnceLock::new();
 
 /// Initialize the global connection pool
+///
+/// Background health checks start when called inside a Tokio runtime.
 pub fn init_global_pool(socket_path: String) -> ZapResult<Arc<ConnectionPool>> {
     let pool = Arc::new(ConnectionPool::with_socket(socket_path));
 
     match GLOBAL_POOL.set(pool.clone()) {
-        Ok(()) => Ok(pool),
+        Ok(()) => {
+            if tokio::runtime::Handle::try_current().is_ok() {
+                pool.start_health_checks();
+            }
+            Ok(pool)
+        }
         Err(_) => {
             // Pool already initialized, return existing
             Ok(GLOBAL_POOL.get().unwrap().clone())
@@ -339,11 +784,18 @@ pub fn init_global_pool(socket_path: String) -> ZapResult<Arc<ConnectionPool>> {
 }
 
 /// Initialize the global connection pool with custom config
+///
+/// Background health checks start when called inside a Tokio runtime.
 pub fn init_global_pool_with_config(config: PoolConfig) -> ZapResult<Arc<ConnectionPool>> {
     let pool = Arc::new(ConnectionPool::new(config));
 
     match GLOBAL_POOL.set(pool.clone()) {
-        Ok(()) => Ok(pool),
+        Ok(()) => {
+            if tokio::runtime::Handle::try_current().is_ok() {
+                pool.start_health_checks();
+            }
+            Ok(pool)
+        }
         Err(_) => {
             // Pool already initialized, return existing
             Ok(GLOBAL_POOL.get().unwrap().clone())
@@ -359,6 +811,7 @@ pub fn get_global_pool() -> Option<Arc<ConnectionPool>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::net::UnixStream;
 
     #[test]
     fn test_pool_config_builder() {
@@ -382,6 +835,34 @@ mod tests {
         assert!(!pool.initialized.load(Ordering::Acquire));
     }
 
+    #[tokio::test]
+    async fn test_reconnect_backoff_doubles_and_caps() {
+        let mut conn = PooledConnection::new();
+        let base = Duration::from_millis(100);
+        let max = Duration::from_millis(350);
+
+        assert_eq!(conn.reconnect_failed(base, max), Duration::from_millis(100));
+        assert_eq!(conn.reconnect_failed(base, max), Duration::from_millis(200));
+        assert_eq!(conn.reconnect_failed(base, max), Duration::from_millis(350));
+        assert!(conn.backing_off(Instant::now(), None));
+
+        let (stream, _peer) = UnixStream::pair().unwrap();
+        conn.connected(IpcClient::from_stream(stream, IpcEncoding::MessagePack));
+        assert_eq!(conn.failures, 0);
+        assert!(!conn.backing_off(Instant::now(), None));
+    }
+
+    #[test]
+    fn test_backoff_cap_shortens_the_wait() {
+        let mut conn = PooledConnection::new();
+        conn.reconnect_failed(Duration::from_secs(60), Duration::from_secs(60));
+        let later = Instant::now() + Duration::from_millis(200);
+
+        assert!(conn.backing_off(later, None));
+        assert!(conn.backing_off(later, Some(Duration::from_secs(1))));
+        assert!(!conn.backing_off(later, Some(Duration::from_millis(150))));
+    }
+
     #[test]
     fn test_pool_stats() {
         let pool = ConnectionPool::with_socket("/tmp/test.sock".to_string());
@@ -389,6 +870,10 @@ mod tests {
 
         assert_eq!(stats.size, DEFAULT_POOL_SIZE);
         assert!(!stats.initialized);
+        assert_eq!(stats.busy, 0);
+        assert_eq!(stats.idle, 0);
+        assert_eq!(stats.unhealthy, DEFAULT_POOL_SIZE);
+        assert_eq!(stats.total_acquisitions, 0);
     }
 
     #[tokio::test]
@@ -401,4 +886,337 @@ mod tests {
             assert_eq!(index, expected % 4);
         }
     }
+
+    /// Put a client on `index` whose peer is returned for the test to drive
+    async fn attach_pair(pool: &ConnectionPool, index: usize) -> IpcClient {
+        let (client_stream, server_stream) = UnixStream::pair().unwrap();
+        let mut conn = pool.connections[index].lock().await;
+        conn.client = Some(IpcClient::from_stream(client_stream, IpcEncoding::MessagePack));
+        conn.healthy = true;
+        IpcClient::from_stream(server_stream, IpcEncoding::MessagePack)
+    }
+
+    #[tokio::test]
+    async fn test_probe_marks_closed_connection_unhealthy() {
+        let pool = ConnectionPool::new(
+            PoolConfig::new("/tmp/test.sock".to_string())
+                .size(2)
+                .health_check_timeout(Duration::from_millis(500)),
+        );
+
+        let mut responder = attach_pair(&pool, 0).await;
+        tokio::spawn(async move {
+            while let Ok(Some(IpcMessage::HealthCheck)) = responder.recv_message().await {
+                responder.send_message(IpcMessage::HealthCheckResponse).await.unwrap();
+            }
+        });
+        // Forcibly close the runtime side of the second connection
+        drop(attach_pair(&pool, 1).await);
+
+        assert_eq!(pool.health_check().await, (2, 2));
+        assert_eq!(pool.probe_idle_connections().await, 1);
+        assert_eq!(pool.health_check().await, (1, 2));
+        assert!(pool.connections[1].lock().await.client.is_none());
+
+        // The healthy connection keeps passing
+        assert_eq!(pool.probe_idle_connections().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_probe_times_out_silent_connection() {
+        let pool = ConnectionPool::new(
+            PoolConfig::new("/tmp/test.sock".to_string())
+                .size(1)
+                .health_check_timeout(Duration::from_millis(50)),
+        );
+        let _silent = attach_pair(&pool, 0).await;
+
+        assert_eq!(pool.probe_idle_connections().await, 1);
+        assert_eq!(pool.health_check().await, (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_probe_does_not_lock_slot() {
+        let socket = temp_socket("probe-lock");
+        let accepted = serve_health_checks(tokio::net::UnixListener::bind(&socket).unwrap());
+        let pool = Arc::new(ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(1)
+                .encoding(IpcEncoding::MessagePack)
+                .health_check_timeout(Duration::from_secs(1)),
+        ));
+        // The probed connection answers slowly
+        let mut responder = attach_pair(&pool, 0).await;
+        tokio::spawn(async move {
+            while let Ok(Some(IpcMessage::HealthCheck)) = responder.recv_message().await {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let _ = responder.send_message(IpcMessage::HealthCheckResponse).await;
+            }
+        });
+
+        let probing = pool.clone();
+        let probe = tokio::spawn(async move { probing.probe_idle_connections().await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // A request during the probe goes through on a new connection
+        let started = Instant::now();
+        pool.send_recv(IpcMessage::HealthCheck).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(150));
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        assert_eq!(probe.await.unwrap(), 0);
+        assert_eq!(pool.health_check().await, (1, 1));
+        pool.send_recv(IpcMessage::HealthCheck).await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_task_runs_and_stops_on_drop() {
+        let pool = Arc::new(ConnectionPool::new(
+            PoolConfig::new("/tmp/test.sock".to_string())
+                .size(1)
+                .health_check_interval(Duration::from_millis(20))
+                .health_check_timeout(Duration::from_millis(200)),
+        ));
+        drop(attach_pair(&pool, 0).await);
+
+        let handle = pool.start_health_checks();
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while pool.health_check().await.0 != 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("background health check never ran");
+
+        drop(pool);
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("health check task outlived the pool")
+            .unwrap();
+    }
+
+    fn temp_socket(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("zap-pool-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Answer health checks on every accepted connection
+    ///
+    /// Returns the number of connections accepted so far.
+    fn serve_health_checks(listener: tokio::net::UnixListener) -> Arc<AtomicUsize> {
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut peer = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+                    while let Ok(Some(IpcMessage::HealthCheck)) = peer.recv_message().await {
+                        peer.send_message(IpcMessage::HealthCheckResponse).await.unwrap();
+                    }
+                });
+            }
+        });
+        accepted
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_succeeds_on_second_attempt() {
+        let socket = temp_socket("reconnect");
+        let pool = ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(1)
+                .encoding(IpcEncoding::MessagePack)
+                .reconnect_backoff(Duration::from_millis(50), Duration::from_secs(1)),
+        );
+
+        // Runtime is not up yet
+        assert!(pool.send_recv(IpcMessage::HealthCheck).await.is_err());
+        assert_eq!(pool.connections[0].lock().await.failures, 1);
+
+        serve_health_checks(tokio::net::UnixListener::bind(&socket).unwrap());
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert!(matches!(
+            pool.send_recv(IpcMessage::HealthCheck).await.unwrap(),
+            IpcMessage::HealthCheckResponse
+        ));
+        assert_eq!(pool.connections[0].lock().await.failures, 0);
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_backed_off_slot_is_skipped_for_healthy_one() {
+        let socket = temp_socket("skip");
+        serve_health_checks(tokio::net::UnixListener::bind(&socket).unwrap());
+        let pool = ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(2)
+                .encoding(IpcEncoding::MessagePack)
+                .reconnect_backoff(Duration::from_secs(60), Duration::from_secs(60)),
+        );
+        pool.initialize().await.unwrap();
+        pool.connections[0]
+            .lock()
+            .await
+            .reconnect_failed(Duration::from_secs(60), Duration::from_secs(60));
+
+        for _ in 0..4 {
+            assert!(pool.send_recv(IpcMessage::HealthCheck).await.is_ok());
+        }
+        assert!(pool.connections[0].lock().await.client.is_none());
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_all_slots_dead_fails_fast() {
+        let socket = temp_socket("dead");
+        let pool = ConnectionPool::new(
+            PoolConfig::new(socket)
+                .size(3)
+                .reconnect_backoff(Duration::from_secs(60), Duration::from_secs(60)),
+        );
+
+        let started = Instant::now();
+        let err = pool.send_recv(IpcMessage::HealthCheck).await.unwrap_err();
+        assert!(matches!(err, ZapError::Ipc { .. }));
+
+        // Every slot is now backing off, so no connect is attempted at all
+        let err = pool.send_recv(IpcMessage::HealthCheck).await.unwrap_err();
+        assert!(err.to_string().contains("backing off"));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_stats_track_busy_connections() {
+        let socket = temp_socket("stats");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        // Answer each health check after a delay so requests stay in flight
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut peer = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+                    while let Ok(Some(IpcMessage::HealthCheck)) = peer.recv_message().await {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        peer.send_message(IpcMessage::HealthCheckResponse).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        let pool = Arc::new(ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(4)
+                .encoding(IpcEncoding::MessagePack),
+        ));
+        pool.initialize().await.unwrap();
+        let stats = pool.stats();
+        assert_eq!((stats.idle, stats.busy, stats.unhealthy), (4, 0, 0));
+
+        let requests: Vec<_> = (0..2)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move { pool.send_recv(IpcMessage::HealthCheck).await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let stats = pool.stats();
+        assert_eq!(stats.busy, 2);
+        assert_eq!(stats.idle, 2);
+
+        for request in requests {
+            request.await.unwrap().unwrap();
+        }
+        let stats = pool.stats();
+        assert_eq!(stats.busy, 0);
+        assert_eq!(stats.idle, 4);
+        assert_eq!(stats.total_acquisitions, 2);
+        assert_eq!(stats.total_reconnects, 0);
+
+        // A lost connection shows up as unhealthy until it is re-established
+        pool.connections[0].lock().await.healthy = false;
+        assert_eq!(pool.stats().unhealthy, 1);
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_connection_recycled_after_max_lifetime() {
+        let socket = temp_socket("lifetime");
+        let accepted = serve_health_checks(tokio::net::UnixListener::bind(&socket).unwrap());
+        let pool = ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(1)
+                .encoding(IpcEncoding::MessagePack)
+                .max_connection_lifetime(Duration::from_millis(30)),
+        );
+        pool.initialize().await.unwrap();
+        let first_created = pool.connections[0].lock().await.created_at;
+
+        pool.send_recv(IpcMessage::HealthCheck).await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        pool.send_recv(IpcMessage::HealthCheck).await.unwrap();
+
+        // The stale client was replaced by a fresh connection
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        assert!(pool.connections[0].lock().await.created_at > first_created);
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_skips_locked_slot() {
+        let socket = temp_socket("locked");
+        serve_health_checks(tokio::net::UnixListener::bind(&socket).unwrap());
+        let pool = ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(2)
+                .encoding(IpcEncoding::MessagePack),
+        );
+        pool.initialize().await.unwrap();
+
+        // Slot 0 is held, e.g. by a health probe, and is next in line
+        let _probe = pool.connections[0].lock().await;
+        let pooled = tokio::time::timeout(Duration::from_secs(1), pool.acquire())
+            .await
+            .expect("acquire waited for a locked slot")
+            .unwrap();
+        assert_eq!(pooled.index, 1);
+        assert!(!pooled.is_fresh());
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_unreleased_client_is_discarded() {
+        let socket = temp_socket("guard");
+        let accepted = serve_health_checks(tokio::net::UnixListener::bind(&socket).unwrap());
+        let pool = ConnectionPool::new(
+            PoolConfig::new(socket.clone())
+                .size(1)
+                .encoding(IpcEncoding::MessagePack),
+        );
+
+        let mut pooled = pool.acquire().await.unwrap();
+        pooled.client().send_message(IpcMessage::HealthCheck).await.unwrap();
+        assert!(matches!(
+            pooled.client().recv_message().await.unwrap(),
+            Some(IpcMessage::HealthCheckResponse)
+        ));
+        pooled.release();
+        assert_eq!(pool.stats().idle, 1);
+
+        // Reply never read: the connection must not be handed out again
+        let mut pooled = pool.acquire().await.unwrap();
+        pooled.client().send_message(IpcMessage::HealthCheck).await.unwrap();
+        drop(pooled);
+        assert_eq!(pool.stats().unhealthy, 1);
+
+        pool.send_recv(IpcMessage::HealthCheck).await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+        let _ = std::fs::remove_file(&socket);
+    }
 }
@@ -3,9 +3,14 @@
 //! This module provides the `Context` type that gives user-exported functions
 //! access to request metadata like trace IDs, headers, and authentication information.
 
+use std::sync::{Arc, Mutex};
+
 use splice::protocol::{RequestContext, AuthContext};
 use tokio_util::sync::CancellationToken;
 
+use crate::request_body::RequestBody;
+use crate::response_stream::ResponseStream;
+
 /// Request execution context available to exported functions
 ///
 /// Provides access to request metadata like trace IDs, headers, and authentication.
@@ -50,6 +55,8 @@ use tokio_util::sync::CancellationToken;
 pub struct Context {
     inner: RequestContext,
     cancellation_token: CancellationToken,
+    body: Option<Arc<Mutex<Option<RequestBody>>>>,
+    response_stream: Option<Arc<Mutex<Option<ResponseStream>>>>,
 }
 
 impl Context {
@@ -63,6 +70,8 @@ impl Context {
         Self {
             inner,
             cancellation_token: CancellationToken::new(),
+            body: None,
+            response_stream: None,
         }
     }
 
@@ -75,9 +84,25 @@ impl Context {
         Self {
             inner,
             cancellation_token: token,
+            body: None,
+            response_stream: None,
         }
     }
 
+    /// Attach the streamed request body, if the request has one
+    #[doc(hidden)]
+    pub fn with_body(mut self, body: Option<RequestBody>) -> Self {
+        self.body = body.map(|body| Arc::new(Mutex::new(Some(body))));
+        self
+    }
+
+    /// Attach the stream the response can be sent on, if the invoke has one
+    #[doc(hidden)]
+    pub fn with_response_stream(mut self, stream: Option<ResponseStream>) -> Self {
+        self.response_stream = stream.map(|stream| Arc::new(Mutex::new(Some(stream))));
+        self
+    }
+
     /// Get the distributed trace ID for this request
     ///
     /// Useful for correlating logs and spans across services in a distributed system.
@@ -111,11 +136,7 @@ impl Context {
     /// }
     /// ```
     pub fn header(&self, name: &str) -> Option<&str> {
-        let name_lower = name.to_lowercase();
-        self.inner.headers
-            .iter()
-            .find(|(k, _)| k.to_lowercase() == name_lower)
-            .map(|(_, v)| v.as_str())
+        self.inner.header(name)
     }
 
     /// Get all request headers
@@ -211,4 +232,44 @@ impl Context {
     pub async fn cancelled(&self) {
         self.cancellation_token.cancelled().await
     }
+
+    /// Take the streamed request body
+    ///
+    /// Returns `None` when the request was not sent as an upload, or when
+    /// the body was already taken (clones of this context share it).
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[export]
+    /// pub async fn store(ctx: &Context) -> Result<usize, String> {
+    ///     let body = ctx.take_body().ok_or("Expected a streamed body")?;
+    ///     let bytes = body.collect().await.map_err(|e| e.to_string())?;
+    ///     Ok(bytes.len())
+    /// }
+    /// ```
+    pub fn take_body(&self) -> Option<RequestBody> {
+        self.body
+            .as_ref()
+            .and_then(|body| body.lock().ok().and_then(|mut body| body.take()))
+    }
+
+    /// Take the stream for sending the response in chunks
+    ///
+    /// Returns `None` when the function was not invoked over Splice, or when
+    /// the stream was already taken (clones of this context share it).
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[export]
+    /// pub async fn tail(ctx: &Context) -> Result<u64, String> {
+    ///     let mut stream = ctx.take_response_stream().ok_or("Expected a response stream")?.window(4);
+    ///     stream.send(Bytes::from_static(b"line\n")).await.map_err(|e| e.to_string())?;
+    ///     stream.finish().await.map_err(|e| e.to_string())
+    /// }
+    /// ```
+    pub fn take_response_stream(&self) -> Option<ResponseStream> {
+        self.response_stream
+            .as_ref()
+            .and_then(|stream| stream.lock().ok().and_then(|mut stream| stream.take()))
+    }
 }
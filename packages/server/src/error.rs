@@ -11,6 +11,8 @@
 //! - A unique **digest** for log correlation
 //! - Optional **details** for additional context
 
+use bytes::Bytes;
+use http_body_util::Full;
 use serde::{Deserialize, Serialize};
 use std::io;
 use thiserror::Error;
@@ -77,6 +79,17 @@ pub enum ZapError {
     #[error("Rate limit exceeded")]
     RateLimited { retry_after_secs: u64 },
 
+    /// Temporarily unable to serve, e.g. while a worker reloads (503)
+    #[error("Service unavailable: {message}")]
+    Unavailable {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
+
+    /// Request URI over the configured limit (414)
+    #[error("Request URI too long ({length} bytes, limit {limit})")]
+    UriTooLong { length: usize, limit: usize },
+
     /// Invalid state
     #[error("Invalid state: {0}")]
     InvalidState(String),
@@ -106,6 +119,8 @@ impl ZapError {
             ZapError::Forbidden { .. } => "FORBIDDEN",
             ZapError::Timeout { .. } => "TIMEOUT",
             ZapError::RateLimited { .. } => "RATE_LIMITED",
+            ZapError::Unavailable { .. } => "SERVICE_UNAVAILABLE",
+            ZapError::UriTooLong { .. } => "URI_TOO_LONG",
             ZapError::InvalidState(_) => "INVALID_STATE",
             ZapError::Internal(_) => "INTERNAL_ERROR",
             ZapError::WebSocket { .. } => "WEBSOCKET_ERROR",
@@ -127,6 +142,8 @@ impl ZapError {
             ZapError::Forbidden { .. } => 403,
             ZapError::Timeout { .. } => 504,
             ZapError::RateLimited { .. } => 429,
+            ZapError::Unavailable { .. } => 503,
+            ZapError::UriTooLong { .. } => 414,
             ZapError::InvalidState(_) => 500,
             ZapError::Internal(_) => 500,
             ZapError::WebSocket { .. } => 500,
@@ -147,6 +164,28 @@ impl ZapError {
         }
     }
 
+    /// Seconds the client should wait before retrying, sent as `Retry-After`
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            ZapError::RateLimited { retry_after_secs } => Some(*retry_after_secs),
+            ZapError::Unavailable { retry_after_secs, .. } => *retry_after_secs,
+            _ => None,
+        }
+    }
+
+    /// Convert to a hyper response with the JSON error body
+    pub fn to_hyper_response(&self) -> hyper::Response<Full<Bytes>> {
+        let mut builder = hyper::Response::builder()
+            .status(self.status_code())
+            .header("Content-Type", "application/json");
+        if let Some(secs) = self.retry_after_secs() {
+            builder = builder.header("Retry-After", secs);
+        }
+        builder
+            .body(Full::new(Bytes::from(self.to_error_response().to_json())))
+            .unwrap()
+    }
+
     /// Get additional error-specific details
     fn details(&self) -> Option<serde_json::Value> {
         match self {
@@ -156,6 +195,9 @@ impl ZapError {
             ZapError::RateLimited { retry_after_secs } => {
                 Some(serde_json::json!({ "retryAfter": retry_after_secs }))
             }
+            ZapError::Unavailable { retry_after_secs, .. } => {
+                retry_after_secs.map(|secs| serde_json::json!({ "retryAfter": secs }))
+            }
             ZapError::Timeout { timeout_ms, .. } => {
                 Some(serde_json::json!({ "timeoutMs": timeout_ms }))
             }
@@ -255,6 +297,19 @@ impl ZapError {
         ZapError::RateLimited { retry_after_secs }
     }
 
+    /// Create a service unavailable error
+    pub fn unavailable(message: impl Into<String>, retry_after_secs: Option<u64>) -> Self {
+        ZapError::Unavailable {
+            message: message.into(),
+            retry_after_secs,
+        }
+    }
+
+    /// Create a URI too long error
+    pub fn uri_too_long(length: usize, limit: usize) -> Self {
+        ZapError::UriTooLong { length, limit }
+    }
+
     /// Create a WebSocket error
     pub fn websocket(message: impl Into<String>) -> Self {
         ZapError::WebSocket {
@@ -351,6 +406,22 @@ mod tests {
         assert_eq!(ZapError::forbidden("test").status_code(), 403);
         assert_eq!(ZapError::rate_limited(60).status_code(), 429);
         assert_eq!(ZapError::timeout("test", 5000).status_code(), 504);
+        assert_eq!(ZapError::uri_too_long(9000, 8192).status_code(), 414);
+        assert_eq!(ZapError::unavailable("test", None).status_code(), 503);
+    }
+
+    #[test]
+    fn test_unavailable_sets_retry_after_header() {
+        let response = ZapError::unavailable("Worker reloading", Some(2)).to_hyper_response();
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.headers()["Retry-After"], "2");
+
+        let response = ZapError::rate_limited(60).to_hyper_response();
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers()["Retry-After"], "60");
+
+        let response = ZapError::unavailable("Worker reloading", None).to_hyper_response();
+        assert!(response.headers().get("Retry-After").is_none());
     }
 
     #[test]
@@ -10,8 +10,11 @@
 use crate::error::{ZapError, ZapResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::UnixStream;
+use tokio::sync::Semaphore;
 
 /// IPC encoding format
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -124,6 +127,26 @@ fn default_error_status() -> u16 {
     500
 }
 
+impl IpcMessage {
+    /// Whether this message is a request the peer answers
+    pub fn expects_reply(&self) -> bool {
+        matches!(self, IpcMessage::InvokeHandler { .. } | IpcMessage::HealthCheck)
+    }
+
+    /// Whether this message is the final reply to a request
+    ///
+    /// Streaming replies complete at `StreamEnd`.
+    pub fn completes_request(&self) -> bool {
+        matches!(
+            self,
+            IpcMessage::HandlerResponse { .. }
+                | IpcMessage::HealthCheckResponse
+                | IpcMessage::Error { .. }
+                | IpcMessage::StreamEnd { .. }
+        )
+    }
+}
+
 /// Serialize an IPC message to bytes
 pub fn serialize_message(msg: &IpcMessage, encoding: IpcEncoding) -> ZapResult<Vec<u8>> {
     match encoding {
@@ -180,13 +203,29 @@ pub struct IpcRequest {
     /// HTTP headers
     pub headers: HashMap<String, String>,
 
-    /// Request body as UTF-8 string
+    /// Request body, encoded as described by `body_encoding`
     pub body: String,
 
+    /// How `body` is encoded (UTF-8 text or base64 binary)
+    #[serde(default)]
+    pub body_encoding: BodyEncoding,
+
     /// Cookies parsed from headers
     pub cookies: HashMap<String, String>,
 }
 
+/// Encoding of `IpcRequest::body`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyEncoding {
+    /// Body is the raw request bytes, which were valid UTF-8
+    #[default]
+    Utf8,
+    /// Body is base64 of raw request bytes that were not valid UTF-8 or
+    /// declared a binary content type
+    Base64,
+}
+
 /// IPC Server - receives requests from Rust, forwards to TypeScript
 pub struct IpcServer {
     socket_path: String,
@@ -234,10 +273,99 @@ impl IpcServer {
     }
 }
 
+/// Bound on requests awaiting a reply on one connection
+///
+/// Sending a request takes a permit; its final reply returns it.
+#[derive(Debug)]
+struct PipelineLimit {
+    max_in_flight: usize,
+    permits: Semaphore,
+}
+
+impl PipelineLimit {
+    fn new(max_in_flight: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            max_in_flight,
+            permits: Semaphore::new(max_in_flight),
+        }
+    }
+
+    async fn acquire(&self) {
+        if let Ok(permit) = self.permits.acquire().await {
+            permit.forget();
+        }
+    }
+
+    fn release(&self) {
+        // Unsolicited replies must not raise the limit
+        if self.permits.available_permits() < self.max_in_flight {
+            self.permits.add_permits(1);
+        }
+    }
+
+    fn in_flight(&self) -> usize {
+        self.max_in_flight - self.permits.available_permits()
+    }
+}
+
+/// Write one length-prefixed frame
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, msg: &IpcMessage, encoding: IpcEncoding) -> ZapResult<()> {
+    let payload = serialize_message(msg, encoding)?;
+    let len = payload.len() as u32;
+
+    // ATOMIC: Combine length prefix and payload into single buffer to prevent frame corruption
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(&payload);
+
+    // Single atomic write
+    writer
+        .write_all(&frame)
+        .await
+        .map_err(|e| ZapError::ipc(format!("Write frame error: {}", e)))?;
+
+    writer.flush().await.map_err(|e| {
+        ZapError::ipc(format!("Flush error: {}", e))
+    })?;
+
+    Ok(())
+}
+
+/// Read one length-prefixed frame, returning `None` on a clean EOF
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> ZapResult<Option<IpcMessage>> {
+    // Read 4-byte length prefix
+    let mut len_buf = [0u8; 4];
+    match reader.read_exact(&mut len_buf).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(ZapError::ipc(format!("Read length error: {}", e))),
+    }
+
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > 100 * 1024 * 1024 {
+        // 100MB limit
+        return Err(ZapError::ipc(format!("Message too large: {} bytes", len)));
+    }
+
+    // Read payload
+    let mut buffer = vec![0u8; len];
+    reader
+        .read_exact(&mut buffer)
+        .await
+        .map_err(|e| ZapError::ipc(format!("Read payload error: {}", e)))?;
+
+    // Auto-detect encoding and deserialize
+    let msg = deserialize_message(&buffer)?;
+
+    Ok(Some(msg))
+}
+
 /// IPC Client - connects to TypeScript's IPC server
 pub struct IpcClient {
     stream: UnixStream,
     encoding: IpcEncoding,
+    pipeline: Option<Arc<PipelineLimit>>,
 }
 
 impl IpcClient {
@@ -252,59 +380,70 @@ impl IpcClient {
             ZapError::ipc(format!("Failed to connect to IPC socket: {}", e))
         })?;
 
-        Ok(Self { stream, encoding })
+        Ok(Self::from_stream(stream, encoding))
     }
 
-    /// Send a message over the IPC channel using length-prefixed framing
-    pub async fn send_message(&mut self, msg: IpcMessage) -> ZapResult<()> {
-        let payload = serialize_message(&msg, self.encoding)?;
-        let len = payload.len() as u32;
+    /// Wrap an already-connected stream
+    pub fn from_stream(stream: UnixStream, encoding: IpcEncoding) -> Self {
+        Self {
+            stream,
+            encoding,
+            pipeline: None,
+        }
+    }
 
-        // ATOMIC: Combine length prefix and payload into single buffer to prevent frame corruption
-        let mut frame = Vec::with_capacity(4 + payload.len());
-        frame.extend_from_slice(&len.to_be_bytes());
-        frame.extend_from_slice(&payload);
+    /// Limit requests sent without a reply yet (pipelining depth)
+    ///
+    /// Once `max_in_flight` requests are unanswered, sending another waits
+    /// until a final reply is received. Pipelining needs a concurrent
+    /// reader, so use [`IpcClient::into_split`] when sending ahead.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.pipeline = Some(Arc::new(PipelineLimit::new(max_in_flight)));
+        self
+    }
 
-        // Single atomic write
-        self.stream
-            .write_all(&frame)
-            .await
-            .map_err(|e| ZapError::ipc(format!("Write frame error: {}", e)))?;
+    /// Requests sent that have not had a final reply yet
+    ///
+    /// Always 0 without a pipelining limit.
+    pub fn in_flight(&self) -> usize {
+        self.pipeline.as_ref().map_or(0, |p| p.in_flight())
+    }
 
-        self.stream.flush().await.map_err(|e| {
-            ZapError::ipc(format!("Flush error: {}", e))
-        })?;
+    /// Split into halves that can send and receive from separate tasks
+    ///
+    /// Both halves share the pipelining limit.
+    pub fn into_split(self) -> (IpcSender, IpcReceiver) {
+        let (read, write) = self.stream.into_split();
+        (
+            IpcSender {
+                stream: write,
+                encoding: self.encoding,
+                pipeline: self.pipeline.clone(),
+            },
+            IpcReceiver {
+                stream: read,
+                pipeline: self.pipeline,
+            },
+        )
+    }
 
-        Ok(())
+    /// Send a message over the IPC channel using length-prefixed framing
+    pub async fn send_message(&mut self, msg: IpcMessage) -> ZapResult<()> {
+        if let (Some(pipeline), true) = (&self.pipeline, msg.expects_reply()) {
+            pipeline.acquire().await;
+        }
+        write_frame(&mut self.stream, &msg, self.encoding).await
     }
 
     /// Receive a message from the IPC channel using length-prefixed framing
     pub async fn recv_message(&mut self) -> ZapResult<Option<IpcMessage>> {
-        // Read 4-byte length prefix
-        let mut len_buf = [0u8; 4];
-        match self.stream.read_exact(&mut len_buf).await {
-            Ok(_) => {}
-            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
-            Err(e) => return Err(ZapError::ipc(format!("Read length error: {}", e))),
-        }
-
-        let len = u32::from_be_bytes(len_buf) as usize;
-        if len > 100 * 1024 * 1024 {
-            // 100MB limit
-            return Err(ZapError::ipc(format!("Message too large: {} bytes", len)));
+        let msg = read_frame(&mut self.stream).await?;
+        if let (Some(pipeline), Some(msg)) = (&self.pipeline, &msg) {
+            if msg.completes_request() {
+                pipeline.release();
+            }
         }
-
-        // Read payload
-        let mut buffer = vec![0u8; len];
-        self.stream
-            .read_exact(&mut buffer)
-            .await
-            .map_err(|e| ZapError::ipc(format!("Read payload error: {}", e)))?;
-
-        // Auto-detect encoding and deserialize
-        let msg = deserialize_message(&buffer)?;
-
-        Ok(Some(msg))
+        Ok(msg)
     }
 
     /// Send a message and receive a response (request-response pattern)
@@ -322,6 +461,47 @@ impl IpcClient {
     }
 }
 
+/// Sending half of a split [`IpcClient`]
+pub struct IpcSender {
+    stream: OwnedWriteHalf,
+    encoding: IpcEncoding,
+    pipeline: Option<Arc<PipelineLimit>>,
+}
+
+impl IpcSender {
+    /// Send a message, waiting while the pipelining limit is reached
+    pub async fn send_message(&mut self, msg: IpcMessage) -> ZapResult<()> {
+        if let (Some(pipeline), true) = (&self.pipeline, msg.expects_reply()) {
+            pipeline.acquire().await;
+        }
+        write_frame(&mut self.stream, &msg, self.encoding).await
+    }
+
+    /// Requests sent that have not had a final reply yet
+    pub fn in_flight(&self) -> usize {
+        self.pipeline.as_ref().map_or(0, |p| p.in_flight())
+    }
+}
+
+/// Receiving half of a split [`IpcClient`]
+pub struct IpcReceiver {
+    stream: OwnedReadHalf,
+    pipeline: Option<Arc<PipelineLimit>>,
+}
+
+impl IpcReceiver {
+    /// Receive a message; a final reply frees a pipelining slot
+    pub async fn recv_message(&mut self) -> ZapResult<Option<IpcMessage>> {
+        let msg = read_frame(&mut self.stream).await?;
+        if let (Some(pipeline), Some(msg)) = (&self.pipeline, &msg) {
+            if msg.completes_request() {
+                pipeline.release();
+            }
+        }
+        Ok(msg)
+    }
+}
+
 /// Handle an IPC client connection (for future use)
 async fn handle_ipc_connection(mut _stream: UnixStream) -> ZapResult<()> {
     // Currently, the Rust server only initiates connections to TypeScript
@@ -370,6 +550,7 @@ mod tests {
             },
             headers: HashMap::new(),
             body: String::new(),
+            body_encoding: BodyEncoding::Utf8,
             cookies: HashMap::new(),
         };
 
@@ -396,6 +577,7 @@ mod tests {
             },
             headers: HashMap::new(),
             body: String::new(),
+            body_encoding: BodyEncoding::Utf8,
             cookies: HashMap::new(),
         };
 
@@ -496,4 +678,62 @@ mod tests {
             let _decoded = deserialize_message(&msgpack).unwrap();
         }
     }
+
+    #[tokio::test]
+    async fn test_pipelined_send_blocks_at_max_in_flight() {
+        let (client_stream, mut server_stream) = UnixStream::pair().unwrap();
+        let client = IpcClient::from_stream(client_stream, IpcEncoding::MessagePack).with_max_in_flight(2);
+        let (mut sender, mut receiver) = client.into_split();
+
+        sender.send_message(IpcMessage::HealthCheck).await.unwrap();
+        sender.send_message(IpcMessage::HealthCheck).await.unwrap();
+        assert_eq!(sender.in_flight(), 2);
+
+        // Third request waits for a reply
+        let third = tokio::spawn(async move {
+            sender.send_message(IpcMessage::HealthCheck).await.unwrap();
+            sender
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!third.is_finished());
+
+        // Fire-and-forget messages do not count against the limit
+        assert!(!IpcMessage::WsSend {
+            connection_id: "ws".to_string(),
+            data: String::new(),
+            binary: false,
+        }
+        .expects_reply());
+
+        write_frame(&mut server_stream, &IpcMessage::HealthCheckResponse, IpcEncoding::MessagePack)
+            .await
+            .unwrap();
+        assert!(matches!(
+            receiver.recv_message().await.unwrap(),
+            Some(IpcMessage::HealthCheckResponse)
+        ));
+
+        let sender = tokio::time::timeout(std::time::Duration::from_secs(1), third)
+            .await
+            .expect("send still blocked after a reply")
+            .unwrap();
+        assert_eq!(sender.in_flight(), 2);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                read_frame(&mut server_stream).await.unwrap(),
+                Some(IpcMessage::HealthCheck)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_client_does_not_track_in_flight() {
+        let (client_stream, _server_stream) = UnixStream::pair().unwrap();
+        let mut client = IpcClient::from_stream(client_stream, IpcEncoding::Json);
+        for _ in 0..8 {
+            client.send_message(IpcMessage::HealthCheck).await.unwrap();
+        }
+        assert_eq!(client.in_flight(), 0);
+    }
 }
@@ -98,36 +98,44 @@ pub mod proxy;
 pub mod registry;
 pub mod reliability;
 pub mod request;
+pub mod request_body;
 pub mod request_id;
 pub mod response;
+pub mod response_stream;
 pub mod rpc;
+pub mod sampling;
 pub mod server;
 pub mod shutdown;
+pub mod single_flight;
 pub mod splice_client;
 pub mod splice_worker;
 pub mod r#static;
+pub mod trace_context;
 pub mod utils;
 pub mod websocket;
 
 // Re-export main types for convenient use
 pub use config::{ServerConfig, ZapConfig};
-pub use connection_pool::{ConnectionPool, PoolConfig, PoolStats};
+pub use connection_pool::{ConnectionPool, PoolConfig, PoolStats, PooledClient};
 pub use context::Context;
 pub use error::{ZapError, ZapResult, ErrorResponse};
 pub use handler::{AsyncHandler, BoxedHandler, Handler, SimpleHandler};
-pub use ipc::{IpcMessage, IpcRequest, IpcServer, IpcClient, IpcEncoding};
-pub use proxy::ProxyHandler;
+pub use ipc::{BodyEncoding, IpcMessage, IpcRequest, IpcServer, IpcClient, IpcEncoding, IpcReceiver, IpcSender};
+pub use proxy::{BodyPolicy, ProxyHandler};
 pub use request::RequestData;
+pub use request_body::RequestBody;
+pub use response_stream::ResponseStream;
 pub use response::{Json, ZapResponse};
 pub use rpc::{RpcServerHandle, RpcDispatchFn, RpcCallMessage, RpcResponseMessage, RpcErrorMessage};
+pub use sampling::TraceSampler;
 pub use server::Zap;
-pub use shutdown::{GracefulShutdown, ShutdownConfig, ConnectionGuard};
-pub use r#static::{ETagStrategy, StaticHandler, StaticOptions, handle_static_files_with_headers};
-pub use websocket::{WsConfig, WsHandler, handle_websocket_connection, is_websocket_upgrade};
+pub use shutdown::{GracefulShutdown, ShutdownConfig, ShutdownHook, ConnectionGuard};
+pub use r#static::{ETagIndexReport, ETagStrategy, StaticHandler, StaticOptions, handle_static_files_with_headers};
+pub use websocket::{WsConfig, WsDedup, WsDedupConfig, WsHandler, WsIdentityFn, handle_websocket_connection, is_websocket_upgrade};
 pub use reliability::{
     CircuitBreaker, CircuitBreakerConfig, CircuitBreakerStats, CircuitState,
     HealthChecker, HealthCheckResponse, HealthStatus, ComponentHealth,
-    ResilientIpc, RetryConfig,
+    ResilientIpc, RetryBudget, RetryBudgetConfig, RetryBudgetStats, RetryConfig,
 };
 
 // Re-export important types from core crate for convenience
@@ -140,7 +148,7 @@ pub use zap_macros::export;
 pub use registry::build_rpc_dispatcher;
 
 // Re-export Splice protocol types for user worker code
-pub use splice::protocol::{Message, Role, ExportMetadata, RequestContext, AuthContext};
+pub use splice::protocol::{Message, Role, ExportMetadata, RequestContext, RequestContextBuilder, AuthContext};
 pub use splice_worker::run as splice_worker_run;
 
 // Internal types for macro use - not part of public API
@@ -149,6 +157,9 @@ pub mod __private {
     pub use linkme;
     pub use crate::context::Context;
     pub use crate::registry::{ExportedFunction, FunctionWrapper, EXPORTS};
+    pub use crate::registry::{
+        bytes_from_value, bytes_to_value, optional_bytes_from_value, optional_bytes_to_value,
+    };
 }
 
 #[cfg(test)]
@@ -3,11 +3,12 @@
 //! Provides:
 //! - HTTP request counters, histograms, gauges
 //! - IPC handler metrics
+//! - Proxy request/response body sizes
 //! - Thread-safe global metrics registry
 
 use lazy_static::lazy_static;
 use prometheus::{
-    CounterVec, Encoder, Gauge, HistogramOpts, HistogramVec, Opts, Registry,
+    exponential_buckets, CounterVec, Encoder, Gauge, HistogramOpts, HistogramVec, Opts, Registry,
     TextEncoder,
 };
 use std::sync::Once;
@@ -72,6 +73,34 @@ lazy_static! {
         &["handler_id"]
     ).expect("metric can be created");
 
+    // ========================================================================
+    // Proxy Metrics
+    // ========================================================================
+
+    /// Request body size forwarded to TypeScript handlers, in bytes
+    pub static ref PROXY_REQUEST_BODY_BYTES: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "zap_proxy_request_body_bytes",
+            "Request body size forwarded to TypeScript handlers in bytes"
+        ).buckets(body_size_buckets()),
+        &["handler_id"]
+    ).expect("metric can be created");
+
+    /// Response body size returned by TypeScript handlers, in bytes
+    pub static ref PROXY_RESPONSE_BODY_BYTES: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "zap_proxy_response_body_bytes",
+            "Response body size returned by TypeScript handlers in bytes"
+        ).buckets(body_size_buckets()),
+        &["handler_id"]
+    ).expect("metric can be created");
+
+    /// Responses from TypeScript handlers by delivery mode (`buffered` or `streamed`)
+    pub static ref PROXY_RESPONSES_TOTAL: CounterVec = CounterVec::new(
+        Opts::new("zap_proxy_responses_total", "Responses from TypeScript handlers by delivery mode"),
+        &["handler_id", "mode"]
+    ).expect("metric can be created");
+
     // ========================================================================
     // Server Info Metrics
     // ========================================================================
@@ -114,6 +143,17 @@ pub fn init_metrics() {
             .register(Box::new(IPC_INVOCATIONS_TOTAL.clone()))
             .expect("IPC_INVOCATIONS_TOTAL can be registered");
 
+        // Proxy metrics
+        REGISTRY
+            .register(Box::new(PROXY_REQUEST_BODY_BYTES.clone()))
+            .expect("PROXY_REQUEST_BODY_BYTES can be registered");
+        REGISTRY
+            .register(Box::new(PROXY_RESPONSE_BODY_BYTES.clone()))
+            .expect("PROXY_RESPONSE_BODY_BYTES can be registered");
+        REGISTRY
+            .register(Box::new(PROXY_RESPONSES_TOTAL.clone()))
+            .expect("PROXY_RESPONSES_TOTAL can be registered");
+
         // Server info
         REGISTRY
             .register(Box::new(SERVER_INFO.clone()))
@@ -218,6 +258,28 @@ pub fn record_ipc_invoke(handler_id: &str, duration_secs: f64, error_code: Optio
     }
 }
 
+/// Histogram buckets for body sizes: 64 B to 16 MiB in powers of four
+fn body_size_buckets() -> Vec<f64> {
+    exponential_buckets(64.0, 4.0, 10).expect("valid bucket layout")
+}
+
+/// Record the size of a request body forwarded by the proxy
+pub fn record_proxy_request_size(handler_id: &str, bytes: usize) {
+    PROXY_REQUEST_BODY_BYTES
+        .with_label_values(&[handler_id])
+        .observe(bytes as f64);
+}
+
+/// Record the size and delivery mode of a handler response
+pub fn record_proxy_response_size(handler_id: &str, bytes: usize, streamed: bool) {
+    PROXY_RESPONSE_BODY_BYTES
+        .with_label_values(&[handler_id])
+        .observe(bytes as f64);
+    PROXY_RESPONSES_TOTAL
+        .with_label_values(&[handler_id, if streamed { "streamed" } else { "buffered" }])
+        .inc();
+}
+
 /// Increment in-flight request counter
 pub fn inc_in_flight() {
     HTTP_REQUESTS_IN_FLIGHT.inc();
@@ -7,20 +7,214 @@
 //! 4. Converts response back to HTTP
 //!
 //! Supports both regular and streaming responses from TypeScript handlers.
+//!
+//! Request bodies that are not valid UTF-8 are never lossily converted; see
+//! [`BodyPolicy`] for how they are handled.
+//!
+//! Identical concurrent GET/HEAD requests can optionally be coalesced so the
+//! TypeScript handler runs once and every caller receives the same response.
+//!
+//! Hop-by-hop headers (RFC 7230 §6.1) describe a single connection, so they
+//! are stripped from the forwarded request and from the handler's response
+//! unless the handler is configured to preserve them.
 
-use crate::connection_pool::ConnectionPool;
+use crate::connection_pool::{get_global_pool, ConnectionPool};
 use crate::error::{ZapError, ZapResult};
 use crate::handler::Handler;
-use crate::ipc::{IpcClient, IpcEncoding, IpcMessage, IpcRequest};
+use crate::ipc::{BodyEncoding, IpcClient, IpcEncoding, IpcMessage, IpcRequest};
+use crate::metrics;
 use crate::request_id;
 use crate::response::{StreamingResponse, ZapResponse};
+use crate::single_flight::SingleFlight;
+use crate::trace_context;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 use zap_core::Request;
 
+/// How the proxy forwards request bodies that are not valid UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyPolicy {
+    /// Reject the request with 400 Bad Request
+    Reject,
+    /// Forward the body base64-encoded with `body_encoding: "base64"`
+    #[default]
+    Base64,
+}
+
+/// Content types whose bodies are forwarded base64-encoded when `BodyPolicy`
+/// lets them through
+const BINARY_CONTENT_TYPES: &[&str] = &[
+    "application/octet-stream",
+    "application/protobuf",
+    "application/x-protobuf",
+    "application/vnd.google.protobuf",
+    "application/msgpack",
+    "application/x-msgpack",
+    "application/vnd.msgpack",
+    "application/cbor",
+    "application/zip",
+    "application/gzip",
+    "application/pdf",
+];
+
+/// Whether `content_type` declares a binary body
+pub fn is_binary_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    BINARY_CONTENT_TYPES.contains(&mime.as_str())
+        || ["image/", "audio/", "video/", "font/"]
+            .iter()
+            .any(|prefix| mime.starts_with(prefix) && mime != "image/svg+xml")
+}
+
+/// Encode a request body for IPC according to `policy`
+///
+/// `policy` decides what happens to a body that is not valid UTF-8,
+/// whatever its content type. Bodies that pass and have a binary
+/// `content_type` are base64-encoded, since they are not meant to be read
+/// as text; other valid UTF-8 is forwarded as text.
+pub fn encode_body(
+    body: &[u8],
+    content_type: Option<&str>,
+    policy: BodyPolicy,
+) -> ZapResult<(String, BodyEncoding)> {
+    let text = match std::str::from_utf8(body) {
+        Ok(text) => Some(text),
+        Err(e) => match policy {
+            BodyPolicy::Reject => {
+                return Err(ZapError::validation(format!(
+                    "Request body is not valid UTF-8 (invalid byte at offset {})",
+                    e.valid_up_to()
+                )))
+            }
+            BodyPolicy::Base64 => None,
+        },
+    };
+
+    match text {
+        Some(text) if body.is_empty() || !content_type.is_some_and(is_binary_content_type) => {
+            Ok((text.to_string(), BodyEncoding::Utf8))
+        }
+        _ => Ok((BASE64.encode(body), BodyEncoding::Base64)),
+    }
+}
+
+/// Default number of extra connect attempts before a request fails
+pub const DEFAULT_MAX_CONNECT_RETRIES: u32 = 2;
+
+/// Default delay before the first connect retry (doubles per attempt)
+pub const DEFAULT_CONNECT_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Headers that distinguish otherwise identical requests by default when
+/// coalescing, so per-user responses are never shared
+pub const DEFAULT_COALESCE_VARY: &[&str] = &["accept", "accept-encoding", "authorization", "cookie"];
+
+/// Headers that only apply to a single connection and are never forwarded
+///
+/// Any `Proxy-*` header and every header named in `Connection` is also
+/// hop-by-hop.
+pub const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Remove hop-by-hop headers, including those listed in `Connection`
+pub fn strip_hop_by_hop(headers: &mut std::collections::HashMap<String, String>) {
+    let listed: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("connection"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    headers.retain(|name, _| {
+        let name = name.to_ascii_lowercase();
+        !HOP_BY_HOP_HEADERS.contains(&name.as_str())
+            && !name.starts_with("proxy-")
+            && !listed.contains(&name)
+    });
+}
+
+/// Result shared between coalesced requests
+type SharedResult = Result<ZapResponse, Arc<ZapError>>;
+
+/// Single-flight state for coalescing identical requests
+struct Coalescing {
+    /// Request headers included in the coalescing key
+    vary: Vec<String>,
+    /// In-flight handler invocations
+    group: SingleFlight<SharedResult>,
+}
+
+/// Build the coalescing key for a request, or `None` if it must not coalesce
+///
+/// Only safe, idempotent methods (GET and HEAD) are coalesced.
+pub fn coalesce_key(
+    method: &str,
+    path: &str,
+    headers: &std::collections::HashMap<String, String>,
+    vary: &[String],
+) -> Option<String> {
+    if !method.eq_ignore_ascii_case("GET") && !method.eq_ignore_ascii_case("HEAD") {
+        return None;
+    }
+
+    let mut key = format!("{} {}", method.to_ascii_uppercase(), path);
+    for name in vary {
+        let value = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .unwrap_or("");
+        key.push('\n');
+        key.push_str(&name.to_ascii_lowercase());
+        key.push('=');
+        key.push_str(value);
+    }
+    Some(key)
+}
+
+/// Retry hint from a handler's 503 error details, in whole seconds
+///
+/// Handlers send `retryAfterMs` (forwarded from a reloading worker) or
+/// `retryAfter` in seconds.
+fn retry_after_secs(details: Option<&serde_json::Value>) -> Option<u64> {
+    let details = details?;
+    if let Some(ms) = details.get("retryAfterMs").and_then(|v| v.as_u64()) {
+        return Some(ms.div_ceil(1000).max(1));
+    }
+    details.get("retryAfter").and_then(|v| v.as_u64())
+}
+
+/// Rebuild an owned error from one shared between coalesced requests
+fn unshare_error(error: Arc<ZapError>) -> ZapError {
+    Arc::try_unwrap(error).unwrap_or_else(|error| match error.as_ref() {
+        ZapError::Timeout { message, timeout_ms } => ZapError::timeout(message.clone(), *timeout_ms),
+        ZapError::Ipc { message } => ZapError::ipc(message.clone()),
+        ZapError::Unavailable { message, retry_after_secs } => {
+            ZapError::unavailable(message.clone(), *retry_after_secs)
+        }
+        ZapError::Handler { message, handler_id } => ZapError::Handler {
+            message: message.clone(),
+            handler_id: handler_id.clone(),
+        },
+        other => ZapError::handler(other.to_string()),
+    })
+}
+
 /// Handler that proxies requests to TypeScript via IPC
 pub struct ProxyHandler {
     /// Unique identifier for this handler
@@ -34,6 +228,27 @@ pub struct ProxyHandler {
 
     /// Optional connection pool (if None, uses global pool or creates per-request connections)
     connection_pool: Option<Arc<ConnectionPool>>,
+
+    /// Handling of request bodies that are not valid UTF-8
+    body_policy: BodyPolicy,
+
+    /// Coalescing of identical concurrent GET/HEAD requests (disabled by default)
+    coalescing: Option<Coalescing>,
+
+    /// Longest gap allowed between streamed chunks (defaults to `timeout_secs`)
+    stream_idle_timeout: Option<Duration>,
+
+    /// Cap on the whole streamed response, from `StreamStart` to `StreamEnd`
+    stream_total_timeout: Option<Duration>,
+
+    /// Forward hop-by-hop headers in both directions (default: strip them)
+    preserve_hop_by_hop: bool,
+
+    /// Extra attempts to connect to the runtime before giving up
+    max_connect_retries: u32,
+
+    /// Delay before the first connect retry, doubled for each further one
+    retry_backoff: Duration,
 }
 
 impl ProxyHandler {
@@ -44,6 +259,13 @@ impl ProxyHandler {
             ipc_socket_path: Arc::new(ipc_socket_path),
             timeout_secs: 30,
             connection_pool: None,
+            body_policy: BodyPolicy::default(),
+            coalescing: None,
+            stream_idle_timeout: None,
+            stream_total_timeout: None,
+            preserve_hop_by_hop: false,
+            max_connect_retries: DEFAULT_MAX_CONNECT_RETRIES,
+            retry_backoff: DEFAULT_CONNECT_RETRY_BACKOFF,
         }
     }
 
@@ -58,6 +280,13 @@ impl ProxyHandler {
             ipc_socket_path: Arc::new(ipc_socket_path),
             timeout_secs,
             connection_pool: None,
+            body_policy: BodyPolicy::default(),
+            coalescing: None,
+            stream_idle_timeout: None,
+            stream_total_timeout: None,
+            preserve_hop_by_hop: false,
+            max_connect_retries: DEFAULT_MAX_CONNECT_RETRIES,
+            retry_backoff: DEFAULT_CONNECT_RETRY_BACKOFF,
         }
     }
 
@@ -72,6 +301,13 @@ impl ProxyHandler {
             ipc_socket_path: Arc::new(ipc_socket_path),
             timeout_secs: 30,
             connection_pool: Some(pool),
+            body_policy: BodyPolicy::default(),
+            coalescing: None,
+            stream_idle_timeout: None,
+            stream_total_timeout: None,
+            preserve_hop_by_hop: false,
+            max_connect_retries: DEFAULT_MAX_CONNECT_RETRIES,
+            retry_backoff: DEFAULT_CONNECT_RETRY_BACKOFF,
         }
     }
 
@@ -87,9 +323,79 @@ impl ProxyHandler {
             ipc_socket_path: Arc::new(ipc_socket_path),
             timeout_secs,
             connection_pool: Some(pool),
+            body_policy: BodyPolicy::default(),
+            coalescing: None,
+            stream_idle_timeout: None,
+            stream_total_timeout: None,
+            preserve_hop_by_hop: false,
+            max_connect_retries: DEFAULT_MAX_CONNECT_RETRIES,
+            retry_backoff: DEFAULT_CONNECT_RETRY_BACKOFF,
         }
     }
 
+    /// Set how non-UTF-8 request bodies are handled
+    pub fn body_policy(mut self, policy: BodyPolicy) -> Self {
+        self.body_policy = policy;
+        self
+    }
+
+    /// Fail a streamed response when no chunk arrives within `timeout`
+    ///
+    /// The timer resets on every message, so a slow but steady stream is
+    /// allowed to run. Defaults to the request timeout.
+    pub fn stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail a streamed response that has not ended within `timeout` overall
+    ///
+    /// Unset by default, so only the idle timeout applies.
+    pub fn stream_total_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_total_timeout = Some(timeout);
+        self
+    }
+
+    /// Retry connecting to the runtime up to `retries` more times
+    ///
+    /// Only the connect is retried, never a request that was already
+    /// written, so non-idempotent handlers cannot run twice. This smooths
+    /// over a worker that is restarting. Defaults to
+    /// [`DEFAULT_MAX_CONNECT_RETRIES`]; `0` fails on the first error.
+    pub fn max_connect_retries(mut self, retries: u32) -> Self {
+        self.max_connect_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first connect retry (doubled per attempt)
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Forward hop-by-hop headers verbatim instead of stripping them
+    pub fn preserve_hop_by_hop_headers(mut self) -> Self {
+        self.preserve_hop_by_hop = true;
+        self
+    }
+
+    /// Coalesce identical concurrent GET/HEAD requests into one invocation
+    ///
+    /// Requests are identical when method, path (with query) and the
+    /// [`DEFAULT_COALESCE_VARY`] headers match.
+    pub fn coalesce_requests(self) -> Self {
+        self.coalesce_vary(DEFAULT_COALESCE_VARY.iter().map(|h| h.to_string()).collect())
+    }
+
+    /// Coalesce identical concurrent GET/HEAD requests, keyed on `vary` headers
+    pub fn coalesce_vary(mut self, vary: Vec<String>) -> Self {
+        self.coalescing = Some(Coalescing {
+            vary,
+            group: SingleFlight::new(),
+        });
+        self
+    }
+
     /// Make an IPC request to the TypeScript handler
     /// Returns the response which may be a regular response or a streaming start message
     async fn invoke_handler(&self, request: IpcRequest) -> ZapResult<ZapResponse> {
@@ -104,40 +410,161 @@ impl ProxyHandler {
             request,
         };
 
-        // For streaming support, we need a dedicated connection that we can keep reading from
-        // We can't use the connection pool for this because streaming needs multiple reads
-        // So we create a dedicated connection for the entire request lifecycle
-        let response = self.invoke_with_streaming_support(msg).await?;
+        let response = match self.pool() {
+            Some(pool) => self.invoke_with_pool(&pool, msg).await?,
+            None => self.invoke_with_streaming_support(msg).await?,
+        };
 
         debug!("📥 Received response from TypeScript handler");
 
         Ok(response)
     }
 
+    /// Pool to invoke through: the handler's own, or the global pool when it
+    /// serves the same socket
+    fn pool(&self) -> Option<Arc<ConnectionPool>> {
+        self.connection_pool.clone().or_else(|| {
+            get_global_pool().filter(|pool| pool.config().socket_path == *self.ipc_socket_path)
+        })
+    }
+
+    /// Invoke handler over a pooled connection
+    ///
+    /// Waiting for a free slot counts against the request timeout. A send
+    /// that fails on a reused connection, which the runtime may have closed
+    /// while it sat idle, is retried once on a fresh one.
+    ///
+    /// A streamed response takes its connection out of the pool, so the slot
+    /// is free for other requests while the stream is read. Otherwise the
+    /// connection is returned to the pool only after a complete exchange;
+    /// anything else may leave replies in flight, so it is discarded.
+    ///
+    /// The pool's reconnect backoff is capped at the connect retry window, so
+    /// slots backing off after a runtime restart are tried again before the
+    /// last retry instead of failing every attempt.
+    async fn invoke_with_pool(&self, pool: &ConnectionPool, msg: IpcMessage) -> ZapResult<ZapResponse> {
+        let timeout = Duration::from_secs(self.timeout_secs);
+        let acquire = async {
+            match self.retry_window() {
+                Some(window) => self.retry_connect(|| pool.acquire_within(window)).await,
+                None => self.retry_connect(|| pool.acquire()).await,
+            }
+        };
+        let mut pooled = tokio::time::timeout(timeout, acquire).await.map_err(|_| {
+            warn!(
+                "No pool connection free for handler {} within {}s",
+                self.handler_id, self.timeout_secs
+            );
+            ZapError::timeout(
+                format!(
+                    "No connection to handler {} free within {}s",
+                    self.handler_id, self.timeout_secs
+                ),
+                self.timeout_secs * 1000,
+            )
+        })??;
+
+        if pooled.is_fresh() {
+            self.send(pooled.client(), msg).await?;
+        } else if let Err(e) = pooled.client().send_message(msg.clone()).await {
+            warn!(
+                "Send for handler {} failed on a reused connection: {}, retrying on a fresh one",
+                self.handler_id, e
+            );
+            pool.reconnect(&mut pooled).await?;
+            self.send(pooled.client(), msg).await?;
+        }
+
+        let result = match self.recv_first(pooled.client()).await {
+            Ok(first @ IpcMessage::StreamStart { .. }) => {
+                let mut client = pooled.detach();
+                return self.respond(&mut client, first).await;
+            }
+            Ok(first) => self.respond(pooled.client(), first).await,
+            Err(e) => Err(e),
+        };
+        if matches!(result, Ok(_) | Err(ZapError::Handler { .. })) {
+            pooled.release();
+        }
+        result
+    }
+
     /// Invoke handler with full streaming support
     /// This uses a dedicated connection so we can handle streaming responses
     async fn invoke_with_streaming_support(&self, msg: IpcMessage) -> ZapResult<ZapResponse> {
         // Connect to TypeScript's IPC server
-        let mut client = IpcClient::connect_with_encoding(
-            self.ipc_socket_path.as_str(),
-            IpcEncoding::MessagePack,
-        )
-        .await
-        .map_err(|e| {
-            error!("Failed to connect to IPC: {}", e);
-            e
-        })?;
+        let mut client = self
+            .retry_connect(|| {
+                IpcClient::connect_with_encoding(self.ipc_socket_path.as_str(), IpcEncoding::MessagePack)
+            })
+            .await
+            .map_err(|e| {
+                error!("Failed to connect to IPC: {}", e);
+                e
+            })?;
+
+        self.exchange(&mut client, msg).await
+    }
+
+    /// Total time `retry_connect` sleeps between its first and last attempt
+    ///
+    /// None when connects are not retried.
+    fn retry_window(&self) -> Option<Duration> {
+        (self.max_connect_retries > 0).then(|| {
+            (0..self.max_connect_retries.min(16))
+                .map(|attempt| self.retry_backoff.saturating_mul(1 << attempt))
+                .fold(Duration::ZERO, Duration::saturating_add)
+        })
+    }
 
-        // Send the invocation
+    /// Run `connect` until it succeeds or `max_connect_retries` is used up
+    ///
+    /// Nothing has been sent when `connect` fails, so retrying is safe for
+    /// every method.
+    async fn retry_connect<T, F, Fut>(&self, mut connect: F) -> ZapResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ZapResult<T>>,
+    {
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            match connect().await {
+                Ok(connected) => return Ok(connected),
+                Err(e) if attempt < self.max_connect_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Connect for handler {} failed: {}, retry {}/{} in {:?}",
+                        self.handler_id, e, attempt, self.max_connect_retries, backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Send an invocation and read its response, following a stream to its end
+    async fn exchange(&self, client: &mut IpcClient, msg: IpcMessage) -> ZapResult<ZapResponse> {
+        self.send(client, msg).await?;
+        let first = self.recv_first(client).await?;
+        self.respond(client, first).await
+    }
+
+    /// Send the invocation
+    async fn send(&self, client: &mut IpcClient, msg: IpcMessage) -> ZapResult<()> {
         client.send_message(msg).await.map_err(|e| {
             error!("Failed to send IPC message: {}", e);
             e
-        })?;
+        })
+    }
 
-        // Wait for first response with timeout
+    /// Wait for the first reply to an invocation
+    async fn recv_first(&self, client: &mut IpcClient) -> ZapResult<IpcMessage> {
         let timeout_duration = std::time::Duration::from_secs(self.timeout_secs);
 
-        let first_response = tokio::time::timeout(timeout_duration, client.recv_message())
+        tokio::time::timeout(timeout_duration, client.recv_message())
             .await
             .map_err(|_| {
                 warn!(
@@ -159,18 +586,26 @@ impl ProxyHandler {
             .ok_or_else(|| {
                 error!("Received None from IPC channel");
                 ZapError::ipc("No response from handler")
-            })?;
+            })
+    }
 
+    /// Turn the first reply into a response, reading the rest of a stream
+    /// from `client`
+    async fn respond(&self, client: &mut IpcClient, first_response: IpcMessage) -> ZapResult<ZapResponse> {
         // Handle the response based on type
         match first_response {
             // Regular handler response - return immediately
             IpcMessage::HandlerResponse {
                 handler_id: _,
                 status,
-                headers,
+                mut headers,
                 body,
             } => {
+                if !self.preserve_hop_by_hop {
+                    strip_hop_by_hop(&mut headers);
+                }
                 debug!("Converting IPC response to HTTP response (status: {})", status);
+                metrics::record_proxy_response_size(&self.handler_id, body.len(), false);
 
                 let status_code = zap_core::StatusCode::new(status);
                 let mut zap_response = zap_core::Response::with_status(status_code).body(body);
@@ -186,14 +621,24 @@ impl ProxyHandler {
             IpcMessage::StreamStart {
                 stream_id,
                 status,
-                headers,
+                mut headers,
             } => {
+                if !self.preserve_hop_by_hop {
+                    strip_hop_by_hop(&mut headers);
+                }
                 info!("Starting streaming response: {} (status: {})", stream_id, status);
-                self.handle_streaming_response(&mut client, stream_id, status, headers)
+                self.handle_streaming_response(client, stream_id, status, headers)
                     .await
             }
 
             // Error response
+            IpcMessage::Error { code, message, status: 503, details, .. } => {
+                warn!(
+                    "Handler {} unavailable: {} - {}",
+                    self.handler_id, code, message
+                );
+                Err(ZapError::unavailable(message, retry_after_secs(details.as_ref())))
+            }
             IpcMessage::Error { code, message, .. } => {
                 error!(
                     "Handler {} returned error: {} - {}",
@@ -228,24 +673,51 @@ impl ProxyHandler {
         headers: std::collections::HashMap<String, String>,
     ) -> ZapResult<ZapResponse> {
         let mut streaming_response = StreamingResponse::new(status, headers);
-        let timeout_duration = std::time::Duration::from_secs(self.timeout_secs);
+        let mut total_bytes = 0;
+        let idle_timeout = self
+            .stream_idle_timeout
+            .unwrap_or_else(|| Duration::from_secs(self.timeout_secs));
+        let deadline = self.stream_total_timeout.map(|total| (total, Instant::now() + total));
 
         loop {
-            // Read next message with timeout
-            let msg = tokio::time::timeout(timeout_duration, client.recv_message())
+            // Wait for the next message until the idle or total deadline, whichever is sooner
+            let wait = match deadline {
+                Some((_, at)) => idle_timeout.min(at.saturating_duration_since(Instant::now())),
+                None => idle_timeout,
+            };
+            let msg = tokio::time::timeout(wait, client.recv_message())
                 .await
-                .map_err(|_| {
-                    warn!(
-                        "Streaming response {} timed out after {}s",
-                        stream_id, self.timeout_secs
-                    );
-                    ZapError::timeout(
-                        format!(
-                            "Streaming response {} did not complete within {}s",
-                            stream_id, self.timeout_secs
-                        ),
-                        self.timeout_secs * 1000,
-                    )
+                .map_err(|_| match deadline {
+                    Some((total, at)) if Instant::now() >= at => {
+                        warn!(
+                            "Streaming response {} exceeded total timeout of {}ms",
+                            stream_id,
+                            total.as_millis()
+                        );
+                        ZapError::timeout(
+                            format!(
+                                "Streaming response {} did not complete within {}ms",
+                                stream_id,
+                                total.as_millis()
+                            ),
+                            total.as_millis() as u64,
+                        )
+                    }
+                    _ => {
+                        warn!(
+                            "Streaming response {} idle for {}ms",
+                            stream_id,
+                            idle_timeout.as_millis()
+                        );
+                        ZapError::timeout(
+                            format!(
+                                "Streaming response {} sent no data for {}ms",
+                                stream_id,
+                                idle_timeout.as_millis()
+                            ),
+                            idle_timeout.as_millis() as u64,
+                        )
+                    }
                 })?
                 .map_err(|e| {
                     error!("IPC connection error during streaming: {}", e);
@@ -278,11 +750,13 @@ impl ProxyHandler {
                                 stream_id,
                                 decoded.len()
                             );
+                            total_bytes += decoded.len();
                             streaming_response.add_chunk(decoded);
                         }
                         Err(e) => {
                             error!("Failed to decode base64 chunk: {}", e);
                             // Try treating as raw UTF-8
+                            total_bytes += data.len();
                             streaming_response.add_chunk(data.into_bytes());
                         }
                     }
@@ -304,8 +778,9 @@ impl ProxyHandler {
                         "Streaming response {} completed: {} chunks, {} bytes total",
                         stream_id,
                         streaming_response.chunks.len(),
-                        streaming_response.body_bytes().len()
+                        total_bytes
                     );
+                    metrics::record_proxy_response_size(&self.handler_id, total_bytes, true);
                     return Ok(ZapResponse::Stream(streaming_response));
                 }
 
@@ -332,32 +807,6 @@ impl ProxyHandler {
             }
         }
     }
-
-    /// Invoke handler using connection pool (for non-streaming responses)
-    #[allow(dead_code)]
-    async fn invoke_with_pool(
-        &self,
-        pool: &ConnectionPool,
-        msg: IpcMessage,
-    ) -> ZapResult<IpcMessage> {
-        let timeout_duration = std::time::Duration::from_secs(self.timeout_secs);
-
-        tokio::time::timeout(timeout_duration, pool.send_recv(msg))
-            .await
-            .map_err(|_| {
-                warn!(
-                    "Handler {} timed out after {}s",
-                    self.handler_id, self.timeout_secs
-                );
-                ZapError::timeout(
-                    format!(
-                        "Handler {} did not respond within {}s",
-                        self.handler_id, self.timeout_secs
-                    ),
-                    self.timeout_secs * 1000,
-                )
-            })?
-    }
 }
 
 impl Handler for ProxyHandler {
@@ -366,19 +815,35 @@ impl Handler for ProxyHandler {
         req: Request<'a>,
     ) -> Pin<Box<dyn Future<Output = Result<ZapResponse, ZapError>> + Send + 'a>> {
         Box::pin(async move {
+            metrics::record_proxy_request_size(&self.handler_id, req.body().len());
+
             // Convert Rust request to IPC request format
-            let body_bytes = req.body();
-            let body_string = String::from_utf8_lossy(body_bytes).to_string();
+            let (body, body_encoding) = encode_body(req.body(), req.header("content-type"), self.body_policy)?;
 
             // Use the request data that's already been parsed
             // Get or generate request ID for correlation
-            let headers_map: std::collections::HashMap<String, String> = req
+            let mut headers_map: std::collections::HashMap<String, String> = req
                 .headers()
                 .iter()
                 .map(|(k, v)| (k.to_string(), v.to_string()))
                 .collect();
+            if !self.preserve_hop_by_hop {
+                strip_hop_by_hop(&mut headers_map);
+            }
             let request_id = request_id::get_or_generate(&headers_map);
 
+            // Key on the incoming headers, before per-request trace headers are added
+            let coalesce = self.coalescing.as_ref().and_then(|c| {
+                coalesce_key(req.method().as_str(), req.path(), &headers_map, &c.vary)
+                    .map(|key| (c, key))
+            });
+
+            // Continue the incoming W3C trace (or start a new root) so the
+            // TypeScript handler and any Splice calls it makes join the same trace
+            let trace = trace_context::get_or_generate(&headers_map).child();
+            trace.inject(&mut headers_map);
+            debug!("Trace context for {}: {}", request_id, trace.to_traceparent());
+
             let ipc_request = IpcRequest {
                 request_id,
                 method: req.method().to_string(),
@@ -395,7 +860,8 @@ impl Handler for ProxyHandler {
                     .map(|(k, v)| (k.to_string(), v.to_string()))
                     .collect(),
                 headers: headers_map,
-                body: body_string,
+                body,
+                body_encoding,
                 cookies: req
                     .cookies()
                     .iter()
@@ -404,7 +870,14 @@ impl Handler for ProxyHandler {
             };
 
             // Invoke TypeScript handler via IPC (handles both regular and streaming responses)
-            self.invoke_handler(ipc_request).await
+            match coalesce {
+                Some((coalescing, key)) => coalescing
+                    .group
+                    .run(key, || async { self.invoke_handler(ipc_request).await.map_err(Arc::new) })
+                    .await
+                    .map_err(unshare_error),
+                None => self.invoke_handler(ipc_request).await,
+            }
         })
     }
 }
@@ -413,6 +886,20 @@ impl Handler for ProxyHandler {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_retry_after_secs_from_error_details() {
+        let ms = serde_json::json!({ "retryAfterMs": 1500 });
+        assert_eq!(retry_after_secs(Some(&ms)), Some(2));
+
+        let short = serde_json::json!({ "retryAfterMs": 10 });
+        assert_eq!(retry_after_secs(Some(&short)), Some(1));
+
+        let secs = serde_json::json!({ "retryAfter": 5 });
+        assert_eq!(retry_after_secs(Some(&secs)), Some(5));
+
+        assert_eq!(retry_after_secs(None), None);
+    }
+
     #[test]
     fn test_proxy_handler_creation() {
         let handler = ProxyHandler::new(
@@ -433,4 +920,662 @@ mod tests {
         assert_eq!(handler.handler_id, "handler_1");
         assert_eq!(handler.timeout_secs, 60);
     }
+
+    #[test]
+    fn test_coalesce_key_only_for_safe_methods() {
+        let headers = std::collections::HashMap::new();
+        let vary: Vec<String> = vec![];
+        assert!(coalesce_key("GET", "/items?page=1", &headers, &vary).is_some());
+        assert!(coalesce_key("HEAD", "/items", &headers, &vary).is_some());
+        assert!(coalesce_key("POST", "/items", &headers, &vary).is_none());
+        assert!(coalesce_key("DELETE", "/items", &headers, &vary).is_none());
+    }
+
+    #[test]
+    fn test_coalesce_key_varies_on_headers() {
+        let vary: Vec<String> = DEFAULT_COALESCE_VARY.iter().map(|h| h.to_string()).collect();
+        let mut alice = std::collections::HashMap::new();
+        alice.insert("Authorization".to_string(), "Bearer alice".to_string());
+        let mut bob = std::collections::HashMap::new();
+        bob.insert("authorization".to_string(), "Bearer bob".to_string());
+
+        assert_ne!(
+            coalesce_key("GET", "/me", &alice, &vary),
+            coalesce_key("GET", "/me", &bob, &vary)
+        );
+        assert_eq!(
+            coalesce_key("GET", "/me", &alice, &vary),
+            coalesce_key("GET", "/me", &alice.clone(), &vary)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_gets_invoke_handler_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-coalesce-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        // Runtime that takes a while to answer, so the requests overlap
+        let invocations = Arc::new(AtomicUsize::new(0));
+        let counter = invocations.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+                    while let Ok(Some(IpcMessage::InvokeHandler { handler_id, request })) =
+                        runtime.recv_message().await
+                    {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        let response = IpcMessage::HandlerResponse {
+                            handler_id,
+                            status: 200,
+                            headers: std::collections::HashMap::new(),
+                            body: request.path,
+                        };
+                        runtime.send_message(response).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        let handler = Arc::new(
+            ProxyHandler::new("handler_0".to_string(), socket.display().to_string()).coalesce_requests(),
+        );
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let (parsed, body) = get_request(b"GET /report HTTP/1.1\r\n\r\n");
+                    handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(ZapResponse::Custom(response)) => {
+                    assert!(matches!(response.body, zap_core::ResponseBody::Bytes(ref body) if body == b"/report"));
+                }
+                _ => panic!("Expected the shared handler response"),
+            }
+        }
+        assert_eq!(invocations.load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_unshare_error_preserves_status() {
+        let shared = Arc::new(ZapError::timeout("slow", 5000));
+        let _other = shared.clone();
+        assert_eq!(unshare_error(shared).status_code(), 504);
+    }
+
+    #[test]
+    fn test_valid_utf8_body_passes() {
+        for policy in [BodyPolicy::Reject, BodyPolicy::Base64] {
+            let (body, encoding) = encode_body("héllo".as_bytes(), None, policy).unwrap();
+            assert_eq!(body, "héllo");
+            assert_eq!(encoding, BodyEncoding::Utf8);
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8_rejected_in_strict_mode() {
+        let err = encode_body(&[0x66, 0x6f, 0xff, 0x6f], None, BodyPolicy::Reject).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("offset 2"));
+    }
+
+    #[test]
+    fn test_invalid_utf8_base64_passthrough() {
+        let bytes = [0x89, 0x50, 0x4e, 0x47, 0x00, 0xff];
+        let (body, encoding) = encode_body(&bytes, None, BodyPolicy::Base64).unwrap();
+        assert_eq!(encoding, BodyEncoding::Base64);
+        assert_eq!(BASE64.decode(body).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_binary_content_type_always_base64() {
+        // Valid UTF-8, but declared binary
+        for content_type in ["application/octet-stream", "application/x-protobuf", "image/png; q=1"] {
+            for policy in [BodyPolicy::Reject, BodyPolicy::Base64] {
+                let (body, encoding) = encode_body(b"abc", Some(content_type), policy).unwrap();
+                assert_eq!(encoding, BodyEncoding::Base64);
+                assert_eq!(body, "YWJj");
+            }
+        }
+
+        for content_type in ["application/json", "text/plain; charset=utf-8", "image/svg+xml"] {
+            assert!(!is_binary_content_type(content_type));
+        }
+    }
+
+    #[test]
+    fn test_reject_policy_applies_to_binary_content_types() {
+        let png = [0x89, 0x50, 0x4e, 0x47, 0x00, 0xff];
+        let err = encode_body(&png, Some("image/png"), BodyPolicy::Reject).unwrap_err();
+        assert_eq!(err.status_code(), 400);
+
+        let (body, encoding) = encode_body(&png, Some("image/png"), BodyPolicy::Base64).unwrap();
+        assert_eq!(encoding, BodyEncoding::Base64);
+        assert_eq!(BASE64.decode(body).unwrap(), png);
+    }
+
+    #[tokio::test]
+    async fn test_binary_body_survives_proxy() {
+        use tokio::net::UnixListener;
+        use zap_core::{HttpParser, Params};
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-binary-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        // Fake TypeScript runtime decoding the body the way the client does
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+            let Some(IpcMessage::InvokeHandler { request, .. }) = runtime.recv_message().await.unwrap() else {
+                panic!("expected InvokeHandler");
+            };
+            assert_eq!(request.body_encoding, BodyEncoding::Base64);
+            let received = BASE64.decode(&request.body).unwrap();
+            let response = IpcMessage::HandlerResponse {
+                handler_id: "binary_handler".to_string(),
+                status: 200,
+                headers: std::collections::HashMap::new(),
+                body: String::new(),
+            };
+            runtime.send_message(response).await.unwrap();
+            received
+        });
+
+        let handler = ProxyHandler::new("binary_handler".to_string(), socket.display().to_string());
+        let mut raw = b"POST /upload HTTP/1.1\r\nContent-Length: 3\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0xFF, 0x00, 0xFE]);
+        let parsed = HttpParser::new().parse_request(&raw).unwrap();
+        let body = &raw[parsed.body_offset..];
+        let request = Request::new(&parsed, body, Params::new());
+
+        handler.handle(request).await.unwrap();
+        assert_eq!(server.await.unwrap(), [0xFF, 0x00, 0xFE]);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_body_sizes_recorded() {
+        use tokio::net::UnixListener;
+        use zap_core::{HttpParser, Params};
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-sizes-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        // Fake TypeScript runtime answering with an 11-byte body
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+            let invoke = runtime.recv_message().await.unwrap();
+            assert!(matches!(invoke, Some(IpcMessage::InvokeHandler { .. })));
+            let response = IpcMessage::HandlerResponse {
+                handler_id: "size_test_handler".to_string(),
+                status: 200,
+                headers: std::collections::HashMap::new(),
+                body: "hello world".to_string(),
+            };
+            runtime.send_message(response).await.unwrap();
+        });
+
+        let handler = ProxyHandler::new("size_test_handler".to_string(), socket.display().to_string());
+        let raw = format!("POST /upload HTTP/1.1\r\nContent-Length: 42\r\n\r\n{}", "x".repeat(42));
+        let parsed = HttpParser::new().parse_request(raw.as_bytes()).unwrap();
+        let body = &raw.as_bytes()[parsed.body_offset..];
+        let request = Request::new(&parsed, body, Params::new());
+
+        handler.handle(request).await.unwrap();
+        server.await.unwrap();
+
+        let request_sizes = metrics::PROXY_REQUEST_BODY_BYTES.with_label_values(&["size_test_handler"]);
+        assert_eq!(request_sizes.get_sample_count(), 1);
+        assert_eq!(request_sizes.get_sample_sum(), 42.0);
+
+        let response_sizes = metrics::PROXY_RESPONSE_BODY_BYTES.with_label_values(&["size_test_handler"]);
+        assert_eq!(response_sizes.get_sample_count(), 1);
+        assert_eq!(response_sizes.get_sample_sum(), 11.0);
+
+        let buffered = metrics::PROXY_RESPONSES_TOTAL.with_label_values(&["size_test_handler", "buffered"]);
+        assert_eq!(buffered.get(), 1.0);
+        let streamed = metrics::PROXY_RESPONSES_TOTAL.with_label_values(&["size_test_handler", "streamed"]);
+        assert_eq!(streamed.get(), 0.0);
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_connect_retried_until_runtime_is_up() {
+        use std::sync::atomic::Ordering;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-reconnect-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+
+        // Runtime comes back while the proxy is backing off
+        let runtime_socket = socket.clone();
+        let runtime = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(40)).await;
+            spawn_pool_runtime(&runtime_socket)
+        });
+
+        let handler = ProxyHandler::new("retry_handler".to_string(), socket.display().to_string())
+            .max_connect_retries(3)
+            .retry_backoff(Duration::from_millis(60));
+        let (parsed, body) = get_request(b"POST /orders HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+        let response = handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await;
+        assert!(matches!(response, Ok(ZapResponse::Custom(_))));
+
+        // Only the successful attempt reached the runtime
+        assert_eq!(runtime.await.unwrap().load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_connect_not_retried_when_disabled() {
+        let socket = std::env::temp_dir().join(format!("zap-proxy-noretry-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+
+        let handler = ProxyHandler::new("retry_handler".to_string(), socket.display().to_string())
+            .max_connect_retries(0)
+            .retry_backoff(Duration::from_secs(5));
+        let (parsed, body) = get_request(b"GET /orders HTTP/1.1\r\n\r\n");
+
+        let started = Instant::now();
+        assert!(handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    /// Fake runtime answering every invocation on every connection
+    ///
+    /// Requests to `/stream` get a two-chunk stream, `/slow-stream` the same
+    /// with 100ms before each chunk, everything else a plain response.
+    /// Returns the number of connections accepted so far.
+    fn spawn_pool_runtime(socket: &std::path::Path) -> Arc<std::sync::atomic::AtomicUsize> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = tokio::net::UnixListener::bind(socket).unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+                    while let Ok(Some(IpcMessage::InvokeHandler { handler_id, request })) =
+                        runtime.recv_message().await
+                    {
+                        if request.path.ends_with("stream") {
+                            let delay = (request.path == "/slow-stream").then_some(Duration::from_millis(100));
+                            let stream_id = "s1".to_string();
+                            let mut replies = vec![IpcMessage::StreamStart {
+                                stream_id: stream_id.clone(),
+                                status: 200,
+                                headers: std::collections::HashMap::new(),
+                            }];
+                            for _ in 0..2 {
+                                replies.push(IpcMessage::StreamChunk {
+                                    stream_id: stream_id.clone(),
+                                    data: BASE64.encode(b"tick"),
+                                });
+                            }
+                            replies.push(IpcMessage::StreamEnd { stream_id });
+                            for reply in replies {
+                                if let (Some(delay), IpcMessage::StreamChunk { .. }) = (delay, &reply) {
+                                    tokio::time::sleep(delay).await;
+                                }
+                                runtime.send_message(reply).await.unwrap();
+                            }
+                        } else {
+                            let response = IpcMessage::HandlerResponse {
+                                handler_id,
+                                status: 200,
+                                headers: std::collections::HashMap::new(),
+                                body: request.path,
+                            };
+                            runtime.send_message(response).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        accepted
+    }
+
+    #[tokio::test]
+    async fn test_pool_backoff_capped_to_retry_window() {
+        use crate::connection_pool::PoolConfig;
+        use std::sync::atomic::Ordering;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-pool-backoff-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let socket_path = socket.display().to_string();
+
+        // Each failed reconnect would hold the slot back for a minute
+        let pool = Arc::new(ConnectionPool::new(
+            PoolConfig::new(socket_path.clone())
+                .size(1)
+                .reconnect_backoff(Duration::from_secs(60), Duration::from_secs(60)),
+        ));
+        let handler = ProxyHandler::with_pool("pool_backoff_handler".to_string(), socket_path, pool)
+            .max_connect_retries(2)
+            .retry_backoff(Duration::from_millis(50));
+
+        // Runtime comes back after the first failed attempt
+        let runtime_socket = socket.clone();
+        let runtime = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(25)).await;
+            spawn_pool_runtime(&runtime_socket)
+        });
+
+        let started = Instant::now();
+        let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+        let response = handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await;
+        assert!(matches!(response, Ok(ZapResponse::Custom(_))));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(runtime.await.unwrap().load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_pooled_invocations_reuse_connections() {
+        use crate::connection_pool::PoolConfig;
+        use std::sync::atomic::Ordering;
+
+        const REQUESTS: usize = 10;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-pool-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let accepted = spawn_pool_runtime(&socket);
+        let socket_path = socket.display().to_string();
+
+        // Without a pool every request opens its own connection
+        let dedicated = ProxyHandler::new("pool_handler".to_string(), socket_path.clone());
+        for _ in 0..REQUESTS {
+            let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+            dedicated.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap();
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), REQUESTS);
+
+        let pool = Arc::new(ConnectionPool::new(PoolConfig::new(socket_path.clone()).size(2)));
+        let pooled = ProxyHandler::with_pool("pool_handler".to_string(), socket_path, pool.clone());
+        for _ in 0..REQUESTS {
+            let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+            let response = pooled.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap();
+            assert!(matches!(response, ZapResponse::Custom(_)));
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), REQUESTS + 2);
+
+        // A streamed response takes its connection out of the pool, and the
+        // slot reconnects on its next use
+        let (parsed, body) = get_request(b"GET /stream HTTP/1.1\r\n\r\n");
+        match pooled.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap() {
+            ZapResponse::Stream(stream) => assert_eq!(stream.chunks.len(), 2),
+            _ => panic!("Expected a streamed response"),
+        }
+        for _ in 0..2 {
+            let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+            pooled.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap();
+        }
+
+        assert_eq!(accepted.load(Ordering::SeqCst), REQUESTS + 3);
+        assert_eq!(pool.stats().idle, 2);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_stream_does_not_hold_pool_slot() {
+        use crate::connection_pool::PoolConfig;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-pool-stream-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        spawn_pool_runtime(&socket);
+        let socket_path = socket.display().to_string();
+        let pool = Arc::new(ConnectionPool::new(PoolConfig::new(socket_path.clone()).size(1)));
+        let handler = Arc::new(ProxyHandler::with_pool("pool_handler".to_string(), socket_path, pool));
+
+        let streaming = handler.clone();
+        let stream = tokio::spawn(async move {
+            let (parsed, body) = get_request(b"GET /slow-stream HTTP/1.1\r\n\r\n");
+            streaming.handle(Request::new(&parsed, body, zap_core::Params::new())).await.map(|_| Instant::now())
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The only slot serves this request while the stream is still running
+        let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+        handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap();
+        let plain_done = Instant::now();
+
+        assert!(stream.await.unwrap().unwrap() > plain_done);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_pool_wait_bounded_by_request_timeout() {
+        use crate::connection_pool::PoolConfig;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-pool-wait-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        spawn_pool_runtime(&socket);
+        let socket_path = socket.display().to_string();
+        let pool = Arc::new(ConnectionPool::new(PoolConfig::new(socket_path.clone()).size(1)));
+        let handler = ProxyHandler::with_timeout_and_pool("pool_handler".to_string(), socket_path, 1, pool.clone());
+
+        let _busy = pool.acquire().await.unwrap();
+        let started = Instant::now();
+        let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+        let err = handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap_err();
+        assert!(matches!(err, ZapError::Timeout { .. }));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_send_on_stale_connection_retried_on_fresh_one() {
+        use crate::connection_pool::PoolConfig;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-pool-stale-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        // Runtime that closes every connection after answering once
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+                if let Ok(Some(IpcMessage::InvokeHandler { handler_id, request })) = runtime.recv_message().await {
+                    let response = IpcMessage::HandlerResponse {
+                        handler_id,
+                        status: 200,
+                        headers: std::collections::HashMap::new(),
+                        body: request.path,
+                    };
+                    runtime.send_message(response).await.unwrap();
+                }
+            }
+        });
+        let socket_path = socket.display().to_string();
+        let pool = Arc::new(ConnectionPool::new(PoolConfig::new(socket_path.clone()).size(1)));
+        let handler = ProxyHandler::with_pool("pool_handler".to_string(), socket_path, pool.clone());
+
+        for _ in 0..3 {
+            let (parsed, body) = get_request(b"GET /plain HTTP/1.1\r\n\r\n");
+            handler.handle(Request::new(&parsed, body, zap_core::Params::new())).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(pool.stats().total_reconnects, 3);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    /// Fake runtime that starts a stream and sends a chunk every `interval`
+    async fn spawn_streaming_runtime(
+        name: &str,
+        interval: Duration,
+        chunks: usize,
+    ) -> (std::path::PathBuf, tokio::task::JoinHandle<()>) {
+        use tokio::net::UnixListener;
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+            runtime.recv_message().await.unwrap();
+            let start = IpcMessage::StreamStart {
+                stream_id: "s1".to_string(),
+                status: 200,
+                headers: std::collections::HashMap::new(),
+            };
+            runtime.send_message(start).await.unwrap();
+            for _ in 0..chunks {
+                tokio::time::sleep(interval).await;
+                let chunk = IpcMessage::StreamChunk {
+                    stream_id: "s1".to_string(),
+                    data: BASE64.encode(b"tick"),
+                };
+                if runtime.send_message(chunk).await.is_err() {
+                    return;
+                }
+            }
+            let _ = runtime
+                .send_message(IpcMessage::StreamEnd { stream_id: "s1".to_string() })
+                .await;
+        });
+
+        (socket, server)
+    }
+
+    fn get_request(raw: &[u8]) -> (zap_core::ParsedRequest<'_>, &[u8]) {
+        let parsed = zap_core::HttpParser::new().parse_request(raw).unwrap();
+        let body = &raw[parsed.body_offset..];
+        (parsed, body)
+    }
+
+    #[tokio::test]
+    async fn test_stream_idle_timeout() {
+        let (socket, server) = spawn_streaming_runtime("idle", Duration::from_secs(5), 1).await;
+        let handler = ProxyHandler::new("idle_handler".to_string(), socket.display().to_string())
+            .stream_idle_timeout(Duration::from_millis(100));
+
+        let raw = b"GET /events HTTP/1.1\r\n\r\n";
+        let (parsed, body) = get_request(raw);
+        let request = Request::new(&parsed, body, zap_core::Params::new());
+
+        let started = Instant::now();
+        match handler.handle(request).await {
+            Err(ZapError::Timeout { timeout_ms, .. }) => assert_eq!(timeout_ms, 100),
+            other => panic!("Expected idle timeout, got {:?}", other.map(|_| ())),
+        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        server.abort();
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_stream_total_timeout_caps_steady_stream() {
+        // Each chunk arrives well inside the idle timeout, but the stream never ends in time
+        let (socket, server) = spawn_streaming_runtime("total", Duration::from_millis(50), 100).await;
+        let handler = ProxyHandler::new("total_handler".to_string(), socket.display().to_string())
+            .stream_idle_timeout(Duration::from_millis(500))
+            .stream_total_timeout(Duration::from_millis(300));
+
+        let raw = b"GET /events HTTP/1.1\r\n\r\n";
+        let (parsed, body) = get_request(raw);
+        let request = Request::new(&parsed, body, zap_core::Params::new());
+
+        let started = Instant::now();
+        match handler.handle(request).await {
+            Err(ZapError::Timeout { timeout_ms, .. }) => assert_eq!(timeout_ms, 300),
+            other => panic!("Expected total timeout, got {:?}", other.map(|_| ())),
+        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        server.abort();
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_strip_hop_by_hop_includes_connection_listed() {
+        let mut headers: std::collections::HashMap<String, String> = [
+            ("Connection", "keep-alive, X-Session-Hop"),
+            ("Keep-Alive", "timeout=5"),
+            ("Proxy-Authorization", "Basic abc"),
+            ("x-session-hop", "1"),
+            ("Content-Type", "text/plain"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        strip_hop_by_hop(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key("Content-Type"));
+    }
+
+    #[tokio::test]
+    async fn test_hop_by_hop_headers_stripped_both_ways() {
+        use tokio::net::UnixListener;
+        use zap_core::{HttpParser, Params};
+
+        let socket = std::env::temp_dir().join(format!("zap-proxy-hop-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+            let request = match runtime.recv_message().await.unwrap() {
+                Some(IpcMessage::InvokeHandler { request, .. }) => request,
+                other => panic!("Expected InvokeHandler, got {:?}", other),
+            };
+            let headers: std::collections::HashMap<String, String> = [
+                ("Connection", "close"),
+                ("Transfer-Encoding", "chunked"),
+                ("X-Handler", "ts"),
+            ]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+            let response = IpcMessage::HandlerResponse {
+                handler_id: "hop_handler".to_string(),
+                status: 200,
+                headers,
+                body: "ok".to_string(),
+            };
+            runtime.send_message(response).await.unwrap();
+            request.headers
+        });
+
+        let handler = ProxyHandler::new("hop_handler".to_string(), socket.display().to_string());
+        let raw = b"GET /hop HTTP/1.1\r\nConnection: keep-alive, X-Hop\r\nX-Hop: 1\r\nTransfer-Encoding: chunked\r\nX-Client: web\r\n\r\n0\r\n\r\n";
+        let parsed = HttpParser::new().parse_request(raw).unwrap();
+        let request = Request::new(&parsed, &raw[parsed.body_offset..], Params::new());
+
+        let response = match handler.handle(request).await.unwrap() {
+            ZapResponse::Custom(response) => response,
+            _ => panic!("Expected a buffered response"),
+        };
+        let forwarded = server.await.unwrap();
+
+        let forwarded_has = |name: &str| forwarded.keys().any(|k| k.eq_ignore_ascii_case(name));
+        assert!(!forwarded_has("connection"));
+        assert!(!forwarded_has("transfer-encoding"));
+        assert!(!forwarded_has("x-hop"));
+        assert!(forwarded_has("x-client"));
+
+        assert!(!response.headers.contains_key("Connection"));
+        assert!(!response.headers.contains_key("Transfer-Encoding"));
+        assert_eq!(response.headers.get("X-Handler").map(String::as_str), Some("ts"));
+
+        let _ = std::fs::remove_file(socket);
+    }
 }
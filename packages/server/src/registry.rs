@@ -73,6 +73,38 @@ pub struct ExportedFunction {
     pub wrapper: FunctionWrapper,
 }
 
+/// Decode a `Vec<u8>` parameter, which generated clients send base64-encoded
+///
+/// A plain array of byte values is still accepted for hand-written callers.
+pub fn bytes_from_value(value: Value) -> Result<Vec<u8>, String> {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    match value {
+        Value::String(encoded) => BASE64.decode(encoded).map_err(|e| e.to_string()),
+        other => serde_json::from_value(other).map_err(|e| e.to_string()),
+    }
+}
+
+/// Decode an `Option<Vec<u8>>` parameter, reading `null` as `None`
+pub fn optional_bytes_from_value(value: Value) -> Result<Option<Vec<u8>>, String> {
+    match value {
+        Value::Null => Ok(None),
+        other => bytes_from_value(other).map(Some),
+    }
+}
+
+/// Encode a `Vec<u8>` result as the base64 string generated clients decode
+pub fn bytes_to_value(bytes: &[u8]) -> Value {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    Value::String(BASE64.encode(bytes))
+}
+
+/// Encode an `Option<Vec<u8>>` result, with `None` as `null`
+pub fn optional_bytes_to_value(bytes: Option<&[u8]>) -> Value {
+    bytes.map_or(Value::Null, bytes_to_value)
+}
+
 // linkme distributed slice - collects all ExportedFunction instances at link time
 // linkme automatically creates a slice of &'static references
 #[linkme::distributed_slice]
@@ -160,7 +192,13 @@ pub fn build_rpc_dispatcher() -> crate::rpc::RpcDispatchFn {
         match registry.get(&function_name) {
             Some(func) => {
                 // Convert RequestContext to Context wrapper if provided
-                let context = context_data.map(|c| Context::new(c));
+                // Streamed uploads and response streams are parked by the
+                // worker for the duration of the call
+                let context = context_data.map(|c| {
+                    Context::new(c)
+                        .with_body(crate::request_body::take_pending_body())
+                        .with_response_stream(crate::response_stream::take_pending_stream())
+                });
 
                 // Check if we're in an async context
                 match tokio::runtime::Handle::try_current() {
@@ -178,9 +216,11 @@ pub fn build_rpc_dispatcher() -> crate::rpc::RpcDispatchFn {
                     }
                 }
             }
-            None => {
-                Err(format!("RPC function '{}' not implemented", function_name))
-            }
+            None => Err(crate::rpc::coded_error(
+                splice::protocol::ERR_FUNCTION_NOT_FOUND,
+                splice::protocol::ErrorKind::User,
+                &format!("RPC function '{}' not implemented", function_name),
+            )),
         }
     })
 }
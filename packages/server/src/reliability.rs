@@ -12,6 +12,13 @@
 //! - Max retries: 3 (configurable)
 //! - Formula: min(max_delay, base_delay * 2^attempt) * random(0, 1)
 //!
+//! ## Retry Budget
+//! Retries at every layer draw from one shared token bucket. Each original
+//! request deposits `ratio` tokens (10% by default) and each retry spends a
+//! whole token, with a small per-second floor so low-traffic services can
+//! still retry. When failures spike the bucket drains and further retries
+//! fail fast instead of multiplying load on a struggling backend.
+//!
 //! ## Circuit Breaker States
 //! - CLOSED: Normal operation, requests flow through
 //! - OPEN: Too many failures, requests fail immediately
@@ -24,8 +31,8 @@
 use crate::connection_pool::ConnectionPool;
 use crate::error::{ZapError, ZapResult};
 use crate::ipc::IpcMessage;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
@@ -113,6 +120,172 @@ impl RetryConfig {
     }
 }
 
+// ============================================================================
+// Retry Budget
+// ============================================================================
+
+/// Default share of request volume that may be retried
+const DEFAULT_RETRY_RATIO: f64 = 0.1;
+
+/// Default retries allowed per second regardless of volume
+const DEFAULT_MIN_RETRIES_PER_SEC: u32 = 10;
+
+/// Default cap on banked retry tokens
+const DEFAULT_MAX_RETRY_TOKENS: u32 = 100;
+
+/// Retry budget configuration
+#[derive(Debug, Clone)]
+pub struct RetryBudgetConfig {
+    /// Tokens deposited per original request (0.1 = retry up to 10% of volume)
+    pub ratio: f64,
+    /// Tokens added per second independent of traffic
+    pub min_retries_per_sec: u32,
+    /// Maximum tokens the bucket can hold, bounding retry bursts
+    pub max_tokens: u32,
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            ratio: DEFAULT_RETRY_RATIO,
+            min_retries_per_sec: DEFAULT_MIN_RETRIES_PER_SEC,
+            max_tokens: DEFAULT_MAX_RETRY_TOKENS,
+        }
+    }
+}
+
+impl RetryBudgetConfig {
+    /// Create a new retry budget configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the share of request volume that may be retried
+    pub fn ratio(mut self, ratio: f64) -> Self {
+        self.ratio = ratio.max(0.0);
+        self
+    }
+
+    /// Set the per-second retry floor
+    pub fn min_retries_per_sec(mut self, retries: u32) -> Self {
+        self.min_retries_per_sec = retries;
+        self
+    }
+
+    /// Set the maximum number of banked retry tokens
+    pub fn max_tokens(mut self, tokens: u32) -> Self {
+        self.max_tokens = tokens;
+        self
+    }
+}
+
+/// Token bucket state for [`RetryBudget`]
+struct RetryBudgetState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Shared budget of retries across every retry point
+///
+/// Wrap in an `Arc` and hand the same instance to each layer that retries
+/// (connection pool, [`ResilientIpc`], proxy) so they draw on one allowance.
+/// Callers [`record_request`](Self::record_request) once per original
+/// request and [`try_retry`](Self::try_retry) before each retry.
+pub struct RetryBudget {
+    config: RetryBudgetConfig,
+    state: Mutex<RetryBudgetState>,
+    /// Original requests seen (for metrics)
+    total_requests: AtomicU64,
+    /// Retries allowed (for metrics)
+    total_retries: AtomicU64,
+    /// Retries refused because the budget was exhausted (for metrics)
+    total_rejected: AtomicU64,
+}
+
+impl RetryBudget {
+    /// Create a budget with default configuration
+    pub fn new() -> Self {
+        Self::with_config(RetryBudgetConfig::default())
+    }
+
+    /// Create a budget with custom configuration
+    pub fn with_config(config: RetryBudgetConfig) -> Self {
+        let initial = config.min_retries_per_sec.min(config.max_tokens) as f64;
+        Self {
+            config,
+            state: Mutex::new(RetryBudgetState {
+                tokens: initial,
+                last_refill: Instant::now(),
+            }),
+            total_requests: AtomicU64::new(0),
+            total_retries: AtomicU64::new(0),
+            total_rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Record an original (non-retry) request, earning `ratio` tokens
+    pub fn record_request(&self) {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        state.tokens = (state.tokens + self.config.ratio).min(self.config.max_tokens as f64);
+    }
+
+    /// Spend one token for a retry; `false` means the retry must not be made
+    pub fn try_retry(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            self.total_retries.fetch_add(1, Ordering::Relaxed);
+            true
+        } else {
+            self.total_rejected.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+    }
+
+    /// Add the time-based floor accrued since the last refill
+    fn refill(&self, state: &mut RetryBudgetState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.last_refill = now;
+        let earned = elapsed * self.config.min_retries_per_sec as f64;
+        state.tokens = (state.tokens + earned).min(self.config.max_tokens as f64);
+    }
+
+    /// Get retry budget statistics
+    pub fn stats(&self) -> RetryBudgetStats {
+        let tokens = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            self.refill(&mut state);
+            state.tokens
+        };
+        RetryBudgetStats {
+            available: tokens.floor() as u64,
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            total_retries: self.total_retries.load(Ordering::Relaxed),
+            total_rejected: self.total_rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Retry budget statistics
+#[derive(Debug, Clone)]
+pub struct RetryBudgetStats {
+    /// Retries that could be made right now
+    pub available: u64,
+    pub total_requests: u64,
+    pub total_retries: u64,
+    pub total_rejected: u64,
+}
+
 // ============================================================================
 // Circuit Breaker
 // ============================================================================
@@ -397,6 +570,9 @@ pub struct CircuitBreakerStats {
 // ============================================================================
 
 /// Resilient IPC client with retry and circuit breaker
+///
+/// Retries draw on the pool's [`RetryBudget`] when one is configured, so
+/// they share an allowance with the pool's own reconnect retry.
 pub struct ResilientIpc {
     pool: Arc<ConnectionPool>,
     retry_config: RetryConfig,
@@ -438,11 +614,24 @@ impl ResilientIpc {
             )));
         }
 
+        // Count the original once; retries only spend from the budget
+        if let Some(budget) = &self.pool.config().retry_budget {
+            budget.record_request();
+        }
+
         let mut last_error: Option<ZapError> = None;
+        let mut attempts = 0;
 
         // Attempt with retries
         for attempt in 0..=self.retry_config.max_retries {
             if attempt > 0 {
+                if let Some(budget) = &self.pool.config().retry_budget {
+                    if !budget.try_retry() {
+                        warn!("Retry budget exhausted, failing fast after {} attempts", attempt);
+                        break;
+                    }
+                }
+
                 // Calculate delay with exponential backoff
                 let delay = self.retry_config.delay_for_attempt(attempt - 1);
                 debug!(
@@ -452,7 +641,8 @@ impl ResilientIpc {
                 tokio::time::sleep(delay).await;
             }
 
-            match self.pool.send_recv(message.clone()).await {
+            attempts += 1;
+            match self.pool.send_attempt(message.clone()).await {
                 Ok(response) => {
                     // Check for error responses from TypeScript
                     if let IpcMessage::Error { code, message: _, .. } = &response {
@@ -485,10 +675,7 @@ impl ResilientIpc {
 
         // All retries exhausted
         self.circuit_breaker.record_failure().await;
-        error!(
-            "IPC request failed after {} attempts",
-            self.retry_config.max_retries + 1
-        );
+        error!("IPC request failed after {} attempts", attempts);
 
         Err(last_error.unwrap_or_else(|| ZapError::ipc("Unknown IPC error")))
     }
@@ -511,6 +698,7 @@ fn is_non_retryable_error(error: &ZapError) -> bool {
         ZapError::Unauthorized { .. } => true,
         ZapError::Forbidden { .. } => true,
         ZapError::RateLimited { .. } => true,
+        ZapError::UriTooLong { .. } => true,
         _ => false,
     }
 }
@@ -598,6 +786,7 @@ pub struct HealthChecker {
     version: String,
     pool: Option<Arc<ConnectionPool>>,
     circuit_breaker: Option<Arc<CircuitBreaker>>,
+    ready: Option<Arc<AtomicBool>>,
 }
 
 impl HealthChecker {
@@ -608,6 +797,7 @@ impl HealthChecker {
             version,
             pool: None,
             circuit_breaker: None,
+            ready: None,
         }
     }
 
@@ -623,6 +813,12 @@ impl HealthChecker {
         self
     }
 
+    /// Set a flag that fails readiness when cleared (e.g. during shutdown)
+    pub fn with_ready_flag(mut self, ready: Arc<AtomicBool>) -> Self {
+        self.ready = Some(ready);
+        self
+    }
+
     /// Liveness probe: Is the process alive?
     /// This should always return true if the server can respond at all.
     pub fn liveness(&self) -> HealthCheckResponse {
@@ -645,6 +841,19 @@ impl HealthChecker {
         let mut components = Vec::new();
         let mut overall_status = HealthStatus::Healthy;
 
+        // Shutting down: stop receiving new traffic
+        if let Some(ready) = &self.ready {
+            if !ready.load(Ordering::SeqCst) {
+                overall_status = HealthStatus::Unhealthy;
+                components.push(ComponentHealth {
+                    name: "shutdown".to_string(),
+                    status: HealthStatus::Unhealthy,
+                    message: Some("Server is shutting down".to_string()),
+                    latency_ms: None,
+                });
+            }
+        }
+
         // Check connection pool
         if let Some(pool) = &self.pool {
             let start = Instant::now();
@@ -725,6 +934,7 @@ impl HealthChecker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::connection_pool::PoolConfig;
 
     #[test]
     fn test_retry_config_default() {
@@ -869,6 +1079,78 @@ mod tests {
         assert_eq!(stats.state, CircuitState::Closed);
     }
 
+    #[test]
+    fn test_retry_budget_caps_retries_under_failure() {
+        let budget = RetryBudget::with_config(
+            RetryBudgetConfig::new().ratio(0.1).min_retries_per_sec(0).max_tokens(1000),
+        );
+
+        // Every request fails and wants three retries
+        let mut attempted = 0;
+        for _ in 0..1000 {
+            budget.record_request();
+            attempted += 1;
+            for _ in 0..3 {
+                if !budget.try_retry() {
+                    break;
+                }
+            }
+        }
+
+        let stats = budget.stats();
+        assert_eq!(attempted, 1000);
+        assert_eq!(stats.total_requests, 1000);
+        assert!(stats.total_retries <= 100, "retries {} exceed budget", stats.total_retries);
+        assert!(stats.total_retries >= 99);
+        assert!(stats.total_rejected >= 900);
+    }
+
+    #[test]
+    fn test_retry_budget_floor_and_cap() {
+        let budget = RetryBudget::with_config(
+            RetryBudgetConfig::new().ratio(0.5).min_retries_per_sec(3).max_tokens(4),
+        );
+
+        // Floor allows a few retries before any traffic
+        assert!(budget.try_retry());
+        assert!(budget.try_retry());
+        assert!(budget.try_retry());
+        assert!(!budget.try_retry());
+
+        // Deposits never bank more than max_tokens
+        for _ in 0..100 {
+            budget.record_request();
+        }
+        assert_eq!(budget.stats().available, 4);
+    }
+
+    #[tokio::test]
+    async fn test_resilient_ipc_respects_retry_budget() {
+        let budget = Arc::new(RetryBudget::with_config(
+            RetryBudgetConfig::new().ratio(0.25).min_retries_per_sec(0),
+        ));
+        let pool = Arc::new(ConnectionPool::new(
+            PoolConfig::new("/nonexistent/zap-retry-budget.sock".to_string())
+                .retry_budget(budget.clone()),
+        ));
+        let ipc = ResilientIpc::with_config(
+            pool,
+            RetryConfig::new().base_delay(Duration::ZERO).jitter(false).max_retries(3),
+            CircuitBreakerConfig::new().failure_threshold(100),
+        );
+
+        for _ in 0..8 {
+            assert!(ipc.send_recv(IpcMessage::HealthCheck).await.is_err());
+        }
+
+        // Each original earns a quarter token, so only two retries were made
+        // instead of the 24 the retry policy alone would allow, and the
+        // retries themselves earned nothing
+        let stats = budget.stats();
+        assert_eq!(stats.total_retries, 2);
+        assert_eq!(stats.total_requests, 8);
+    }
+
     #[test]
     fn test_health_status_display() {
         assert_eq!(format!("{}", HealthStatus::Healthy), "healthy");
@@ -0,0 +1,208 @@
+//! Streamed request bodies for exported functions
+//!
+//! When the host opens an upload (`StreamStart`) ahead of an `Invoke`, the
+//! worker hands the chunks to the function as a [`RequestBody`] instead of
+//! buffering them. Acks are only sent as the function consumes chunks, so
+//! the upload window (see [`splice::upload`]) bounds how much of the body
+//! sits in worker memory.
+//!
+//! ```ignore
+//! use zap_server::{export, Context};
+//!
+//! #[export]
+//! pub async fn checksum(ctx: &Context) -> Result<u32, String> {
+//!     let mut body = ctx.take_body().ok_or("Expected a streamed body")?;
+//!     let mut sum = 0u32;
+//!     while let Some(chunk) = body.chunk().await {
+//!         let chunk = chunk.map_err(|e| e.to_string())?;
+//!         sum = chunk.iter().fold(sum, |acc, b| acc.wrapping_add(*b as u32));
+//!     }
+//!     Ok(sum)
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::future::Future;
+
+use bytes::{Bytes, BytesMut};
+use futures::stream::Stream;
+use splice::protocol::Message;
+use splice::upload::{UploadError, UploadReceiver};
+use tokio::sync::mpsc;
+
+tokio::task_local! {
+    /// Body waiting to be attached to the `Context` built by the dispatcher
+    static PENDING_BODY: RefCell<Option<RequestBody>>;
+}
+
+/// Inbound upload for a single invocation
+///
+/// Yields chunks in order until the uploader's `StreamEnd`, then `None`.
+/// A gap, short upload or uploader abort is yielded once as an `Err`.
+#[derive(Debug)]
+pub struct RequestBody {
+    request_id: u64,
+    receiver: UploadReceiver,
+    messages: mpsc::Receiver<Message>,
+    acks: mpsc::Sender<Message>,
+    done: bool,
+}
+
+impl RequestBody {
+    /// Open the body for `StreamStart { request_id, window }`
+    ///
+    /// Returns the sender for the upload's `StreamChunk`/`StreamEnd`/
+    /// `StreamError` messages. It holds exactly one window of chunks, so a
+    /// chunk that finds it full was sent beyond the window. Acks go out on
+    /// `acks`.
+    pub(crate) fn open(
+        request_id: u64,
+        window: u32,
+        acks: mpsc::Sender<Message>,
+    ) -> (mpsc::Sender<Message>, Self) {
+        let receiver = UploadReceiver::new(request_id, window);
+        let (tx, messages) = mpsc::channel(window.max(1) as usize);
+        let body = Self {
+            request_id,
+            receiver,
+            messages,
+            acks,
+            done: false,
+        };
+        (tx, body)
+    }
+
+    /// Next chunk of the body, acknowledging it to the uploader
+    pub async fn chunk(&mut self) -> Option<Result<Bytes, UploadError>> {
+        if self.done {
+            return None;
+        }
+
+        let error = match self.messages.recv().await {
+            Some(Message::StreamChunk { sequence, data, .. }) => match self.receiver.accept(sequence) {
+                Ok(ack) => {
+                    if let Some(ack) = ack {
+                        let _ = self.acks.send(ack).await;
+                    }
+                    return Some(Ok(data));
+                }
+                Err(e) => e,
+            },
+            Some(Message::StreamEnd { total_chunks, .. }) => match self.receiver.finish(total_chunks) {
+                Ok(()) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(e) => e,
+            },
+            Some(Message::StreamError { code, message, .. }) => {
+                // The uploader gave up; nothing to report back
+                self.done = true;
+                return Some(Err(UploadError::Rejected { code, message }));
+            }
+            Some(other) => UploadError::Rejected {
+                code: splice::protocol::ERR_INVALID_REQUEST,
+                message: format!("Unexpected upload message: {:?}", other),
+            },
+            None => UploadError::Closed,
+        };
+
+        self.done = true;
+        if error != UploadError::Closed {
+            let _ = self.acks.send(error.to_stream_error(self.request_id)).await;
+        }
+        Some(Err(error))
+    }
+
+    /// Read the rest of the body into memory
+    pub async fn collect(mut self) -> Result<Bytes, UploadError> {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = self.chunk().await {
+            buf.extend_from_slice(&chunk?);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Chunks as a [`Stream`]
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes, UploadError>> + Send {
+        futures::stream::unfold(self, |mut body| async move {
+            body.chunk().await.map(|chunk| (chunk, body))
+        })
+    }
+}
+
+/// Run `fut` with `body` available to the dispatch it performs
+///
+/// The dispatcher signature carries no body, so the worker parks it here and
+/// [`take_pending_body`] picks it up when the `Context` is built.
+#[doc(hidden)]
+pub async fn with_pending_body<F: Future>(body: RequestBody, fut: F) -> F::Output {
+    PENDING_BODY.scope(RefCell::new(Some(body)), fut).await
+}
+
+/// Take the body parked by [`with_pending_body`], if any
+#[doc(hidden)]
+pub fn take_pending_body() -> Option<RequestBody> {
+    PENDING_BODY
+        .try_with(|body| body.borrow_mut().take())
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(request_id: u64, sequence: u64, data: &'static [u8]) -> Message {
+        Message::StreamChunk {
+            request_id,
+            sequence,
+            data: Bytes::from_static(data),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_body_yields_chunks_until_stream_end() {
+        let (ack_tx, mut ack_rx) = mpsc::channel(16);
+        let (tx, body) = RequestBody::open(1, 4, ack_tx);
+        tx.send(chunk(1, 0, b"he")).await.unwrap();
+        tx.send(chunk(1, 1, b"llo")).await.unwrap();
+        tx.send(Message::StreamEnd { request_id: 1, total_chunks: 2 }).await.unwrap();
+
+        assert_eq!(body.collect().await.unwrap(), Bytes::from_static(b"hello"));
+        assert!(matches!(
+            ack_rx.recv().await,
+            Some(Message::StreamAck { request_id: 1, ack_sequence: 2, window: 4 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_short_upload_reported_to_uploader() {
+        let (ack_tx, mut ack_rx) = mpsc::channel(16);
+        let (tx, mut body) = RequestBody::open(2, 4, ack_tx);
+        tx.send(chunk(2, 0, b"a")).await.unwrap();
+        tx.send(Message::StreamEnd { request_id: 2, total_chunks: 2 }).await.unwrap();
+
+        assert_eq!(body.chunk().await.unwrap().unwrap(), Bytes::from_static(b"a"));
+        assert_eq!(
+            body.chunk().await.unwrap().unwrap_err(),
+            UploadError::Incomplete { expected: 2, received: 1 }
+        );
+        assert!(body.chunk().await.is_none());
+        assert!(matches!(ack_rx.recv().await, Some(Message::StreamError { request_id: 2, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_pending_body_reaches_dispatch() {
+        let (ack_tx, _ack_rx) = mpsc::channel(16);
+        let (_tx, body) = RequestBody::open(3, 1, ack_tx);
+
+        assert!(take_pending_body().is_none());
+        let taken = with_pending_body(body, async {
+            let first = take_pending_body().map(|body| body.request_id);
+            (first, take_pending_body().is_none())
+        })
+        .await;
+        assert_eq!(taken, (Some(3), true));
+    }
+}
@@ -4,12 +4,13 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use bytes::Bytes;
+use http_body_util::Full;
 use serde::Serialize;
 
 use zap_core::{Response, StatusCode, ResponseBody};
 
 /// Streaming response data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StreamingResponse {
     /// HTTP status code
     pub status: u16,
@@ -51,7 +52,7 @@ impl StreamingResponse {
 }
 
 /// Zap response types with auto-serialization
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ZapResponse {
     /// Plain text response
     Text(String),
@@ -91,18 +92,58 @@ impl<T: Serialize> From<Json<T>> for ZapResponse {
 }
 
 impl ZapResponse {
+    /// Add headers set by middleware, keeping any header the handler set
+    /// itself
+    pub fn with_headers(self, headers: Vec<(String, String)>) -> Self {
+        if headers.is_empty() {
+            return self;
+        }
+        match self {
+            ZapResponse::Stream(mut stream) => {
+                merge_headers(&mut stream.headers, headers);
+                ZapResponse::Stream(stream)
+            }
+            other => {
+                let mut response = other.into_custom();
+                merge_headers(&mut response.headers, headers);
+                ZapResponse::Custom(response)
+            }
+        }
+    }
+
+    /// The same response as a [`Response`], for adding headers to
+    fn into_custom(self) -> Response {
+        match self {
+            ZapResponse::Text(text) => Response::new().text(text),
+            ZapResponse::Html(html) => Response::new().html(html),
+            ZapResponse::Json(json) => json_response(&json, StatusCode::OK),
+            ZapResponse::JsonWithStatus(json, status) => json_response(&json, StatusCode(status)),
+            ZapResponse::Bytes(bytes) => Response::new()
+                .content_type("application/octet-stream")
+                .body(bytes.to_vec()),
+            ZapResponse::Custom(response) => response,
+            ZapResponse::Redirect(location) => Response::with_status(StatusCode(302)).header("Location", location),
+            ZapResponse::Status(status) => Response::with_status(status),
+            ZapResponse::File(_) => Response::with_status(StatusCode(501))
+                .body(b"File serving not yet implemented".to_vec()),
+            ZapResponse::Stream(stream) => Response::with_status(StatusCode(stream.status))
+                .headers(stream.headers.clone())
+                .body(stream.body_bytes()),
+        }
+    }
+
     /// Convert ZapResponse to hyper Response
-    pub fn to_hyper_response(&self) -> hyper::Response<String> {
+    pub fn to_hyper_response(&self) -> hyper::Response<Full<Bytes>> {
         match self {
             ZapResponse::Text(text) => hyper::Response::builder()
                 .status(200)
                 .header("Content-Type", "text/plain; charset=utf-8")
-                .body(text.clone())
+                .body(Full::new(Bytes::from(text.clone())))
                 .unwrap(),
             ZapResponse::Html(html) => hyper::Response::builder()
                 .status(200)
                 .header("Content-Type", "text/html; charset=utf-8")
-                .body(html.clone())
+                .body(Full::new(Bytes::from(html.clone())))
                 .unwrap(),
             ZapResponse::Json(json) => {
                 let body = serde_json::to_string(json).unwrap_or_else(|_| {
@@ -111,7 +152,7 @@ impl ZapResponse {
                 hyper::Response::builder()
                     .status(200)
                     .header("Content-Type", "application/json")
-                    .body(body)
+                    .body(Full::new(Bytes::from(body)))
                     .unwrap()
             }
             ZapResponse::JsonWithStatus(json, status) => {
@@ -121,13 +162,13 @@ impl ZapResponse {
                 hyper::Response::builder()
                     .status(*status)
                     .header("Content-Type", "application/json")
-                    .body(body)
+                    .body(Full::new(Bytes::from(body)))
                     .unwrap()
             }
             ZapResponse::Bytes(bytes) => hyper::Response::builder()
                 .status(200)
                 .header("Content-Type", "application/octet-stream")
-                .body(String::from_utf8_lossy(bytes).to_string())
+                .body(Full::new(bytes.clone()))
                 .unwrap(),
             ZapResponse::Custom(response) => {
                 let status = response.status.as_u16();
@@ -137,31 +178,31 @@ impl ZapResponse {
                     builder = builder.header(key, value);
                 }
                 
+                // Bodies are passed through as bytes so binary content
+                // (images, compressed files) survives intact
                 let body = match &response.body {
-                    ResponseBody::Empty => String::new(),
-                    ResponseBody::Text(text) => text.clone(),
-                    ResponseBody::Bytes(bytes) => {
-                        String::from_utf8_lossy(bytes).to_string()
-                    }
+                    ResponseBody::Empty => Bytes::new(),
+                    ResponseBody::Text(text) => Bytes::from(text.clone()),
+                    ResponseBody::Bytes(bytes) => Bytes::from(bytes.clone()),
                 };
-                
-                builder.body(body).unwrap()
+
+                builder.body(Full::new(body)).unwrap()
             }
             ZapResponse::Redirect(location) => hyper::Response::builder()
                 .status(302)
                 .header("Location", location)
-                .body(String::new())
+                .body(Full::default())
                 .unwrap(),
             ZapResponse::Status(status) => hyper::Response::builder()
                 .status(status.as_u16())
-                .body(String::new())
+                .body(Full::default())
                 .unwrap(),
             ZapResponse::File(_path) => {
                 // File serving would be implemented here
                 // For now, return not implemented
                 hyper::Response::builder()
                     .status(501)
-                    .body("File serving not yet implemented".to_string())
+                    .body(Full::new(Bytes::from_static(b"File serving not yet implemented")))
                     .unwrap()
             }
             ZapResponse::Stream(stream_response) => {
@@ -174,9 +215,25 @@ impl ZapResponse {
                 }
 
                 // Convert chunks to body
-                let body = stream_response.body_string();
-                builder.body(body).unwrap()
+                let body = stream_response.body_bytes();
+                builder.body(Full::new(Bytes::from(body))).unwrap()
             }
         }
     }
-} 
\ No newline at end of file
+}
+
+fn json_response(json: &serde_json::Value, status: StatusCode) -> Response {
+    let body = serde_json::to_string(json)
+        .unwrap_or_else(|_| r#"{"error": "Failed to serialize JSON"}"#.to_string());
+    Response::with_status(status).content_type("application/json").body(body)
+}
+
+/// Insert `extra` into `headers` where no header of the same name (in any
+/// case) is set yet
+fn merge_headers(headers: &mut HashMap<String, String>, extra: Vec<(String, String)>) {
+    for (name, value) in extra {
+        if !headers.keys().any(|key| key.eq_ignore_ascii_case(&name)) {
+            headers.insert(name, value);
+        }
+    }
+}
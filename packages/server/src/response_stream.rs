@@ -0,0 +1,128 @@
+//! Streamed responses from exported functions
+//!
+//! A function invoked over Splice can send part of its response as chunks
+//! ahead of its return value, using the same flow control as uploads (see
+//! [`splice::upload`]) in the other direction:
+//!
+//! ```text
+//! worker                           host
+//!    │◄───────────────────── Invoke ──│
+//!    │── StreamStart { window } ─────►│
+//!    │── StreamChunk { sequence } ───►│  up to `window` unacknowledged
+//!    │◄──── StreamAck { ack_sequence,  │
+//!    │                  window } ─────│
+//!    │── StreamEnd { total_chunks } ─►│
+//!    │── InvokeResult ───────────────►│  the function's return value
+//! ```
+//!
+//! The window is tracked with [`splice::window::SendWindow`], so a function
+//! producing faster than the host reads waits in [`ResponseStream::send`]
+//! instead of queueing chunks in worker memory.
+//!
+//! ```ignore
+//! use zap_server::{export, Context};
+//!
+//! #[export]
+//! pub async fn export_rows(ctx: &Context) -> Result<u64, String> {
+//!     let mut stream = ctx.take_response_stream().ok_or("Not invoked over Splice")?;
+//!     for row in load_rows().await {
+//!         stream.send(row.into()).await.map_err(|e| e.to_string())?;
+//!     }
+//!     stream.finish().await.map_err(|e| e.to_string())
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::future::Future;
+
+use bytes::Bytes;
+use splice::protocol::Message;
+use splice::upload::{UploadError, UploadSender};
+use tokio::sync::mpsc;
+
+/// Default number of chunks in flight before the function waits for an ack
+pub const DEFAULT_RESPONSE_WINDOW: u32 = 16;
+
+tokio::task_local! {
+    /// Stream waiting to be attached to the `Context` built by the dispatcher
+    static PENDING_STREAM: RefCell<Option<ResponseStream>>;
+}
+
+/// Outbound response stream for a single invocation
+///
+/// Nothing is sent until the first chunk (or [`finish`](Self::finish)), so
+/// a function that never streams sends no `StreamStart`. Finish the stream
+/// before returning; the host only sees the response complete once
+/// `StreamEnd` arrives. A host that did not negotiate streaming has the
+/// stream refused, and the first send fails with
+/// [`UploadError::Rejected`].
+#[derive(Debug)]
+pub struct ResponseStream {
+    request_id: u64,
+    window: u32,
+    /// Connection and ack channel until the stream is opened
+    unopened: Option<(mpsc::Sender<Message>, mpsc::Receiver<Message>)>,
+    sender: Option<UploadSender>,
+}
+
+impl ResponseStream {
+    /// Stream for the invoke `request_id`, sending on `tx`
+    ///
+    /// `acks` must receive the host's `StreamAck`/`StreamError` messages for
+    /// `request_id`.
+    pub(crate) fn new(request_id: u64, tx: mpsc::Sender<Message>, acks: mpsc::Receiver<Message>) -> Self {
+        Self {
+            request_id,
+            window: DEFAULT_RESPONSE_WINDOW,
+            unopened: Some((tx, acks)),
+            sender: None,
+        }
+    }
+
+    /// Set the window advertised in `StreamStart`
+    ///
+    /// Has no effect once the first chunk was sent.
+    pub fn window(mut self, window: u32) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Send the next chunk, waiting while the host's window is full
+    pub async fn send(&mut self, chunk: Bytes) -> Result<(), UploadError> {
+        self.open().await?.send(chunk).await
+    }
+
+    /// End the stream, returning the number of chunks sent
+    pub async fn finish(mut self) -> Result<u64, UploadError> {
+        self.open().await?;
+        match self.sender.take() {
+            Some(sender) => sender.finish().await,
+            None => Err(UploadError::Closed),
+        }
+    }
+
+    async fn open(&mut self) -> Result<&mut UploadSender, UploadError> {
+        if let Some((tx, acks)) = self.unopened.take() {
+            self.sender = Some(UploadSender::start(self.request_id, self.window, tx, acks).await?);
+        }
+        self.sender.as_mut().ok_or(UploadError::Closed)
+    }
+}
+
+/// Run `fut` with `stream` available to the dispatch it performs
+///
+/// Like [`crate::request_body::with_pending_body`], since the dispatcher
+/// signature carries no stream either.
+#[doc(hidden)]
+pub async fn with_pending_stream<F: Future>(stream: ResponseStream, fut: F) -> F::Output {
+    PENDING_STREAM.scope(RefCell::new(Some(stream)), fut).await
+}
+
+/// Take the stream parked by [`with_pending_stream`], if any
+#[doc(hidden)]
+pub fn take_pending_stream() -> Option<ResponseStream> {
+    PENDING_STREAM
+        .try_with(|stream| stream.borrow_mut().take())
+        .ok()
+        .flatten()
+}
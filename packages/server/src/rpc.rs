@@ -41,13 +41,70 @@
 //!   "error_type": "RpcError"
 //! }
 //! ```
+//!
+//! Errors may also carry the Splice protocol `code` and `kind`; generated
+//! clients use them to pick a specific error class. Dispatchers report them
+//! by returning a message built with [`coded_error`].
 
 use std::sync::Arc;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tracing::{debug, error, info, warn};
 
 use crate::error::{ZapError, ZapResult};
+use crate::trace_context::parse_traceparent;
+use splice::protocol::ErrorKind;
+
+/// Prefix of dispatch errors that carry a Splice error code and kind
+const CODED_ERROR_PREFIX: &str = "__SPLICE_ERROR__:";
+
+/// Dispatch error message carrying a Splice error `code` and `kind`
+///
+/// [`RpcDispatchFn`] can only return a message, so dispatchers that know
+/// why a call failed (a worker's `InvokeError`, an unknown function) encode
+/// it here and the RPC server unpacks it into the error payload.
+pub fn coded_error(code: u16, kind: ErrorKind, message: &str) -> String {
+    encode_coded_error(code, kind, None, message)
+}
+
+/// [`coded_error`] with a hint for when the call may be retried, as sent
+/// with `ERR_RELOADING`
+pub fn retryable_error(code: u16, kind: ErrorKind, retry_after: Duration, message: &str) -> String {
+    encode_coded_error(code, kind, Some(retry_after), message)
+}
+
+fn encode_coded_error(code: u16, kind: ErrorKind, retry_after: Option<Duration>, message: &str) -> String {
+    let retry_after_ms = retry_after.map(|d| d.as_millis().to_string()).unwrap_or_default();
+    format!("{}{}:{}:{}:{}", CODED_ERROR_PREFIX, code, kind.as_str(), retry_after_ms, message)
+}
+
+/// Parts of a [`coded_error`] message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodedError<'a> {
+    pub code: u16,
+    pub kind: ErrorKind,
+    pub retry_after: Option<Duration>,
+    pub message: &'a str,
+}
+
+/// Split a [`coded_error`] or [`retryable_error`] message into its parts
+pub fn parse_coded_error(error: &str) -> Option<CodedError<'_>> {
+    let rest = error.strip_prefix(CODED_ERROR_PREFIX)?;
+    let (code, rest) = rest.split_once(':')?;
+    let (kind, rest) = rest.split_once(':')?;
+    let (retry_after_ms, message) = rest.split_once(':')?;
+    let retry_after = match retry_after_ms {
+        "" => None,
+        ms => Some(Duration::from_millis(ms.parse().ok()?)),
+    };
+    Some(CodedError {
+        code: code.parse().ok()?,
+        kind: ErrorKind::from_name(kind)?,
+        retry_after,
+        message,
+    })
+}
 
 /// User-provided RPC dispatch function
 ///
@@ -64,6 +121,12 @@ pub struct RpcCallMessage {
     pub function_name: String,
     pub params: serde_json::Value,
     pub request_id: String,
+    /// W3C `traceparent` of the calling handler, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    /// W3C `tracestate` of the calling handler, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracestate: Option<String>,
 }
 
 /// RPC success response to TypeScript
@@ -83,6 +146,16 @@ pub struct RpcErrorMessage {
     pub request_id: String,
     pub error: String,
     pub error_type: String,
+    /// Splice protocol error code (`ERR_*`), when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
+    /// Error kind (`user`, `system`, `timeout`, `cancelled`), when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Milliseconds to wait before retrying, for retryable errors such as
+    /// `ERR_RELOADING`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
 }
 
 /// Internal RPC message enum for type-safe handling
@@ -228,8 +301,13 @@ fn dispatch_rpc_call(call: &RpcCallMessage, dispatch_fn: &RpcDispatchFn) -> RpcM
 
     let start = std::time::Instant::now();
 
-    // RPC calls from TypeScript don't have context (only Splice calls do)
-    match dispatch_fn(call.function_name.clone(), call.params.clone(), None) {
+    // Continue the caller's trace when the TypeScript handler forwarded one
+    let context = call.traceparent.as_deref().and_then(parse_traceparent).map(|mut trace| {
+        trace.tracestate = call.tracestate.clone();
+        trace.child().to_request_context(vec![])
+    });
+
+    match dispatch_fn(call.function_name.clone(), call.params.clone(), context) {
         Ok(result) => {
             let duration = start.elapsed();
             debug!(
@@ -245,6 +323,15 @@ fn dispatch_rpc_call(call: &RpcCallMessage, dispatch_fn: &RpcDispatchFn) -> RpcM
         }
         Err(error) => {
             let duration = start.elapsed();
+            let (error, code, kind, retry_after_ms) = match parse_coded_error(&error) {
+                Some(coded) => (
+                    coded.message.to_string(),
+                    Some(coded.code),
+                    Some(coded.kind.as_str().to_string()),
+                    coded.retry_after.map(|d| d.as_millis() as u64),
+                ),
+                None => (error, None, None, None),
+            };
             warn!(
                 "RPC: {} failed in {:?}: {} (request_id: {})",
                 call.function_name, duration, error, call.request_id
@@ -255,6 +342,9 @@ fn dispatch_rpc_call(call: &RpcCallMessage, dispatch_fn: &RpcDispatchFn) -> RpcM
                 request_id: call.request_id.clone(),
                 error,
                 error_type: "RpcError".to_string(),
+                code,
+                kind,
+                retry_after_ms,
             })
         }
     }
@@ -310,6 +400,8 @@ mod tests {
             function_name: "get_benchmarks".to_string(),
             params: json!({"limit": 10, "offset": 0}),
             request_id: "req_123".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let json_bytes = serde_json::to_vec(&call).unwrap();
@@ -346,6 +438,9 @@ mod tests {
             request_id: "req_789".to_string(),
             error: "Function not found".to_string(),
             error_type: "NotFound".to_string(),
+            code: Some(splice::protocol::ERR_FUNCTION_NOT_FOUND),
+            kind: Some("user".to_string()),
+            retry_after_ms: None,
         };
 
         let json_bytes = serde_json::to_vec(&error).unwrap();
@@ -355,6 +450,15 @@ mod tests {
         assert_eq!(decoded.request_id, "req_789");
         assert_eq!(decoded.error, "Function not found");
         assert_eq!(decoded.error_type, "NotFound");
+        assert_eq!(decoded.code, Some(splice::protocol::ERR_FUNCTION_NOT_FOUND));
+        assert_eq!(decoded.kind.as_deref(), Some("user"));
+
+        // Older payloads without code/kind still decode
+        let legacy: RpcErrorMessage = serde_json::from_str(
+            r#"{"type":"rpc_error","request_id":"r","error":"e","error_type":"RpcError"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.code, None);
     }
 
     #[test]
@@ -422,6 +526,8 @@ mod tests {
             function_name: "get_user".to_string(),
             params: json!({"id": "user_123"}),
             request_id: "req_msgpack_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         // Serialize to MessagePack
@@ -443,6 +549,8 @@ mod tests {
             function_name: "test".to_string(),
             params: json!({}),
             request_id: "req_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
         let json_bytes = serde_json::to_vec(&json_call).unwrap();
         assert_eq!(json_bytes[0], b'{');
@@ -484,6 +592,8 @@ mod tests {
             function_name: "ping".to_string(),
             params: json!({}),
             request_id: "req_ping_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response = dispatch_rpc_call(&call, &dispatch);
@@ -515,6 +625,8 @@ mod tests {
             function_name: "add".to_string(),
             params: json!({"a": 10, "b": 32}),
             request_id: "req_add_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response = dispatch_rpc_call(&call, &dispatch);
@@ -541,6 +653,8 @@ mod tests {
             function_name: "invalid_func".to_string(),
             params: json!({}),
             request_id: "req_error_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response = dispatch_rpc_call(&call, &dispatch);
@@ -551,6 +665,65 @@ mod tests {
                 assert_eq!(err.request_id, "req_error_001");
                 assert!(err.error.contains("Unknown RPC method"));
                 assert_eq!(err.error_type, "RpcError");
+                assert_eq!(err.code, None);
+            }
+            _ => panic!("Expected error response"),
+        }
+    }
+
+    #[test]
+    fn test_dispatch_error_carries_code_and_kind() {
+        let dispatch: RpcDispatchFn = Arc::new(|_func, _params, _context| {
+            Err(coded_error(splice::protocol::ERR_TIMEOUT, ErrorKind::Timeout, "took: too long"))
+        });
+
+        let call = RpcCallMessage {
+            msg_type: "rpc_call".to_string(),
+            function_name: "slow".to_string(),
+            params: json!({}),
+            request_id: "req_error_002".to_string(),
+            traceparent: None,
+            tracestate: None,
+        };
+
+        match dispatch_rpc_call(&call, &dispatch) {
+            RpcMessage::Error(err) => {
+                assert_eq!(err.error, "took: too long");
+                assert_eq!(err.code, Some(splice::protocol::ERR_TIMEOUT));
+                assert_eq!(err.kind.as_deref(), Some("timeout"));
+            }
+            _ => panic!("Expected error response"),
+        }
+
+        // Ordinary messages that merely look similar are left alone
+        assert_eq!(parse_coded_error("__SPLICE_ERROR__:abc:user::x"), None);
+    }
+
+    #[test]
+    fn test_dispatch_reloading_error_carries_retry_hint() {
+        let dispatch: RpcDispatchFn = Arc::new(|_func, _params, _context| {
+            Err(retryable_error(
+                splice::protocol::ERR_RELOADING,
+                ErrorKind::System,
+                Duration::from_millis(1500),
+                "Worker reloading",
+            ))
+        });
+
+        let call = RpcCallMessage {
+            msg_type: "rpc_call".to_string(),
+            function_name: "cart.add".to_string(),
+            params: json!({}),
+            request_id: "req_error_003".to_string(),
+            traceparent: None,
+            tracestate: None,
+        };
+
+        match dispatch_rpc_call(&call, &dispatch) {
+            RpcMessage::Error(err) => {
+                assert_eq!(err.error, "Worker reloading");
+                assert_eq!(err.code, Some(splice::protocol::ERR_RELOADING));
+                assert_eq!(err.retry_after_ms, Some(1500));
             }
             _ => panic!("Expected error response"),
         }
@@ -579,6 +752,8 @@ mod tests {
             function_name: "divide".to_string(),
             params: json!({"a": 10}), // Missing 'b'
             request_id: "req_div_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response1 = dispatch_rpc_call(&call1, &dispatch);
@@ -595,6 +770,8 @@ mod tests {
             function_name: "divide".to_string(),
             params: json!({"a": 10, "b": 0}),
             request_id: "req_div_002".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response2 = dispatch_rpc_call(&call2, &dispatch);
@@ -624,6 +801,8 @@ mod tests {
             function_name: "get_user".to_string(),
             params: json!({"id": "user_123"}),
             request_id: "req_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         match dispatch_rpc_call(&call1, &dispatch) {
@@ -639,6 +818,8 @@ mod tests {
             function_name: "get_benchmarks".to_string(),
             params: json!({}),
             request_id: "req_002".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         match dispatch_rpc_call(&call2, &dispatch) {
@@ -654,6 +835,8 @@ mod tests {
             function_name: "list_users".to_string(),
             params: json!({"limit": 50}),
             request_id: "req_003".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         match dispatch_rpc_call(&call3, &dispatch) {
@@ -729,6 +912,8 @@ mod tests {
                 "symbols": "!@#$%^&*()_+-=[]{}|;':\",./<>?"
             }),
             request_id: "req_unicode_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response = dispatch_rpc_call(&call, &dispatch);
@@ -767,6 +952,8 @@ mod tests {
                 }
             }),
             request_id: "req_nested_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response = dispatch_rpc_call(&call, &dispatch);
@@ -793,6 +980,8 @@ mod tests {
                 "empty_object": {}
             }),
             request_id: "req_null_001".to_string(),
+            traceparent: None,
+            tracestate: None,
         };
 
         let response = dispatch_rpc_call(&call, &dispatch);
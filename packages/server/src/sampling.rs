@@ -0,0 +1,211 @@
+//! Request trace sampling
+//!
+//! Creating a tracing span for every request is measurable overhead at very
+//! high request rates. The sampler decides which requests get a detailed
+//! `http.request` span while metrics counters keep counting every request.
+//!
+//! - Head sampling is deterministic per request ID, so every hop that sees
+//!   the same `x-request-id` makes the same decision
+//! - Server errors (5xx) and slow requests are always traced, even when
+//!   they were not sampled up front
+
+use std::time::Duration;
+use tracing::{field, Span};
+
+/// Decides which requests are traced in detail
+#[derive(Debug, Clone)]
+pub struct TraceSampler {
+    /// Fraction of requests to trace (0.0 - 1.0)
+    sample_rate: f64,
+    /// Requests at least this slow are always traced
+    slow_threshold: Option<Duration>,
+}
+
+impl TraceSampler {
+    /// Create a sampler tracing the given fraction of requests
+    ///
+    /// The rate is clamped to `0.0..=1.0`.
+    pub fn new(sample_rate: f64) -> Self {
+        let sample_rate = if sample_rate.is_nan() { 1.0 } else { sample_rate.clamp(0.0, 1.0) };
+        Self {
+            sample_rate,
+            slow_threshold: None,
+        }
+    }
+
+    /// Trace every request
+    pub fn always() -> Self {
+        Self::new(1.0)
+    }
+
+    /// Trace only errors and slow requests
+    pub fn errors_only() -> Self {
+        Self::new(0.0)
+    }
+
+    /// Builder: Always trace requests slower than `threshold`
+    pub fn slow_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_threshold = Some(threshold);
+        self
+    }
+
+    /// Get the configured sample rate
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Whether a request is head-sampled
+    ///
+    /// Deterministic: the same request ID always yields the same answer.
+    pub fn is_sampled(&self, request_id: &str) -> bool {
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        if self.sample_rate <= 0.0 {
+            return false;
+        }
+        (fnv1a(request_id.as_bytes()) as f64 / u64::MAX as f64) < self.sample_rate
+    }
+
+    /// Whether a finished request must be traced regardless of sampling
+    pub fn is_forced(&self, status: u16, duration: Duration) -> bool {
+        status >= 500 || self.slow_threshold.is_some_and(|t| duration >= t)
+    }
+
+    /// Open the request span if the request is head-sampled
+    pub fn request_span(&self, request_id: &str, method: &str, path: &str) -> Option<Span> {
+        if !self.is_sampled(request_id) {
+            return None;
+        }
+        Some(Self::new_span(request_id, method, path))
+    }
+
+    /// Complete a request, creating its span after the fact when the
+    /// request was not sampled but failed or was slow
+    pub fn finish(
+        &self,
+        span: Option<Span>,
+        request_id: &str,
+        method: &str,
+        path: &str,
+        status: u16,
+        duration: Duration,
+    ) {
+        let span = match span {
+            Some(span) => span,
+            None if self.is_forced(status, duration) => Self::new_span(request_id, method, path),
+            None => return,
+        };
+
+        span.record("status", status);
+        span.record("duration_ms", duration.as_millis() as u64);
+
+        let _entered = span.enter();
+        if status >= 500 {
+            tracing::warn!("{} {} -> {} ({:?})", method, path, status, duration);
+        } else {
+            tracing::debug!("{} {} -> {} ({:?})", method, path, status, duration);
+        }
+    }
+
+    fn new_span(request_id: &str, method: &str, path: &str) -> Span {
+        tracing::info_span!(
+            "http.request",
+            request_id = %request_id,
+            method = %method,
+            path = %path,
+            status = field::Empty,
+            duration_ms = field::Empty,
+        )
+    }
+}
+
+impl Default for TraceSampler {
+    fn default() -> Self {
+        Self::always()
+    }
+}
+
+/// FNV-1a hash: stable across processes and releases, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, Layer};
+    use tracing_subscriber::prelude::*;
+
+    struct SpanCounter(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> Layer<S> for SpanCounter {
+        fn on_new_span(&self, _attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Run 100 requests (every 10th a 500) and count the spans created
+    fn count_spans(sampler: &TraceSampler) -> usize {
+        let count = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry().with(SpanCounter(count.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..100 {
+                let request_id = format!("req-{}", i);
+                let status = if i % 10 == 0 { 500 } else { 200 };
+                let span = sampler.request_span(&request_id, "GET", "/items");
+                sampler.finish(span, &request_id, "GET", "/items", status, Duration::from_millis(1));
+            }
+        });
+
+        count.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_zero_rate_traces_only_errors() {
+        assert_eq!(count_spans(&TraceSampler::new(0.0)), 10);
+    }
+
+    #[test]
+    fn test_full_rate_traces_every_request() {
+        assert_eq!(count_spans(&TraceSampler::new(1.0)), 100);
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic_per_request_id() {
+        let sampler = TraceSampler::new(0.5);
+        for i in 0..50 {
+            let id = format!("req-{}", i);
+            assert_eq!(sampler.is_sampled(&id), sampler.is_sampled(&id));
+        }
+
+        let sampled = (0..1000)
+            .filter(|i| sampler.is_sampled(&format!("req-{}", i)))
+            .count();
+        assert!(sampled > 350 && sampled < 650, "sampled {} of 1000", sampled);
+    }
+
+    #[test]
+    fn test_slow_requests_are_forced() {
+        let sampler = TraceSampler::errors_only().slow_threshold(Duration::from_millis(100));
+        assert!(sampler.is_forced(200, Duration::from_millis(150)));
+        assert!(!sampler.is_forced(200, Duration::from_millis(50)));
+        assert!(sampler.is_forced(503, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_rate_is_clamped() {
+        assert_eq!(TraceSampler::new(2.0).sample_rate(), 1.0);
+        assert_eq!(TraceSampler::new(-1.0).sample_rate(), 0.0);
+    }
+}
@@ -1,30 +1,39 @@
 //! Core ZapServer implementation
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use bytes::Bytes;
+use http_body_util::Full;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::{body::Incoming, Request as HyperRequest, Response as HyperResponse};
 use hyper_util::rt::TokioIo;
 use serde::Serialize;
 use tokio::net::TcpListener;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 use zap_core::{
-    HttpParser, Method, MiddlewareChain, Request, Router,
+    Context, HttpParser, Method, MiddlewareChain, MiddlewareError, MiddlewareResponse, Params, ParseError,
+    ParsedRequest, Request, Router, StatusCode,
 };
 
 use crate::config::{ServerConfig, ZapConfig};
+use crate::connection_pool::{ConnectionPool, PoolConfig};
 use crate::error::{ZapError, ZapResult};
 use crate::handler::{AsyncHandler, BoxedHandler, Handler, SimpleHandler};
+use crate::metrics;
 use crate::proxy::ProxyHandler;
 use crate::reliability::{HealthChecker, HealthStatus};
 use crate::request::RequestData;
+use crate::request_id;
 use crate::response::{Json, ZapResponse};
-use crate::shutdown::{GracefulShutdown, ShutdownConfig};
-use crate::r#static::{handle_static_files, StaticHandler, StaticOptions};
+use crate::sampling::TraceSampler;
+use crate::shutdown::{GracefulShutdown, ShutdownConfig, ShutdownHook};
+use crate::r#static::{handle_static_files_with_headers, StaticHandler, StaticOptions};
 use crate::utils::convert_method;
 
 /// Main Zap server - the entry point for building high-performance web applications
@@ -37,6 +46,10 @@ pub struct Zap {
     middleware: MiddlewareChain,
     /// Static file handlers
     static_handlers: Vec<StaticHandler>,
+    /// Readiness flag, cleared when shutdown begins
+    ready: Arc<AtomicBool>,
+    /// Cleanup hooks run after connections drain on shutdown
+    shutdown_hooks: Vec<(String, ShutdownHook)>,
 }
 
 impl Zap {
@@ -47,6 +60,8 @@ impl Zap {
             router: Router::new(),
             middleware: MiddlewareChain::new(),
             static_handlers: Vec::new(),
+            ready: Arc::new(AtomicBool::new(true)),
+            shutdown_hooks: Vec::new(),
         }
     }
 
@@ -80,6 +95,33 @@ impl Zap {
         self
     }
 
+    /// Set the fraction of requests traced in detail (0.0 - 1.0)
+    ///
+    /// Errors and slow requests are always traced; metrics count every request.
+    pub fn trace_sample_rate(mut self, rate: f64) -> Self {
+        self.config.trace_sample_rate = rate;
+        self
+    }
+
+    /// Always trace requests slower than `threshold`
+    pub fn slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.config.slow_request_threshold = Some(threshold);
+        self
+    }
+
+    /// Run an async cleanup hook once connections drain on shutdown
+    ///
+    /// Hooks run in registration order, each bounded by the shutdown
+    /// config's `hook_timeout`.
+    pub fn on_shutdown<F, Fut>(mut self, name: impl Into<String>, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown_hooks.push((name.into(), Box::new(move || Box::pin(hook()))));
+        self
+    }
+
     /// Add middleware to the chain
     pub fn use_middleware<M>(mut self, middleware: M) -> Self
     where
@@ -314,9 +356,12 @@ impl Zap {
     }
 
     /// Enhanced readiness probe (Kubernetes-style)
-    /// Returns 200 if the server can handle requests
+    /// Returns 200 if the server can handle requests, 503 once shutdown begins
     pub fn health_ready(self, path: &str) -> Self {
-        let checker = Arc::new(HealthChecker::new(env!("CARGO_PKG_VERSION").to_string()));
+        let checker = Arc::new(
+            HealthChecker::new(env!("CARGO_PKG_VERSION").to_string())
+                .with_ready_flag(self.ready.clone()),
+        );
         self.get_async(path, move |_req| {
             let checker = checker.clone();
             async move {
@@ -397,12 +442,13 @@ impl Zap {
     ///
     /// This method enables:
     /// - SIGTERM/SIGINT signal handling
+    /// - Optional lame-duck period (readiness fails while still serving)
     /// - Graceful connection draining
     /// - Proper resource cleanup
     /// - Port cascading (tries next port if initial port is in use)
     ///
     /// For production use, prefer this over `listen()`.
-    pub async fn listen_with_shutdown(self, shutdown_config: ShutdownConfig) -> Result<(), ZapError> {
+    pub async fn listen_with_shutdown(mut self, shutdown_config: ShutdownConfig) -> Result<(), ZapError> {
         let initial_port = self.config.port;
         let hostname = self.config.hostname.clone();
 
@@ -414,52 +460,59 @@ impl Zap {
         info!("📊 Router contains {} routes", self.router.total_routes());
         info!("🛡️  Graceful shutdown enabled (drain timeout: {:?})", shutdown_config.drain_timeout);
 
+        let hooks = std::mem::take(&mut self.shutdown_hooks);
         let server = Arc::new(self);
         let shutdown = GracefulShutdown::new(shutdown_config);
+        for (name, hook) in hooks {
+            shutdown.add_hook(name, hook);
+        }
+
+        Self::serve_until_shutdown(server, listener, shutdown).await
+    }
 
+    /// Accept connections until shutdown, then lame-duck and drain
+    async fn serve_until_shutdown(
+        server: Arc<Self>,
+        listener: TcpListener,
+        shutdown: GracefulShutdown,
+    ) -> Result<(), ZapError> {
         loop {
             tokio::select! {
                 // Wait for shutdown signal
                 _ = shutdown.wait() => {
-                    info!("🛑 Shutdown signal received, stopping new connections");
+                    info!("🛑 Shutdown signal received");
                     break;
                 }
                 // Accept new connections
                 result = listener.accept() => {
-                    match result {
-                        Ok((stream, remote_addr)) => {
-                            let server = server.clone();
-                            let shutdown = shutdown.clone();
-
-                            tokio::spawn(async move {
-                                // Track this connection
-                                let _guard = shutdown.connection_guard();
-
-                                let io = TokioIo::new(stream);
-
-                                let service = service_fn(move |req| {
-                                    let server = server.clone();
-                                    async move {
-                                        server.handle_request(req, remote_addr).await
-                                    }
-                                });
-
-                                if let Err(err) = http1::Builder::new()
-                                    .serve_connection(io, service)
-                                    .await
-                                {
-                                    debug!("Connection closed: {:?}", err);
-                                }
-                            });
-                        }
-                        Err(e) => {
-                            error!("Failed to accept connection: {}", e);
-                        }
-                    }
+                    Self::accept_connection(&server, &shutdown, result);
+                }
+            }
+            if !shutdown.is_accepting() {
+                break;
+            }
+        }
+
+        // Fail readiness first, then keep serving through the lame-duck
+        // period so load balancers stop routing here before we drain
+        server.ready.store(false, Ordering::SeqCst);
+        let lame_duck = shutdown.lame_duck();
+        tokio::pin!(lame_duck);
+        while shutdown.is_accepting() {
+            tokio::select! {
+                _ = &mut lame_duck => break,
+                result = listener.accept() => {
+                    Self::accept_connection(&server, &shutdown, result);
                 }
             }
         }
 
+        // Stop accepting before draining, so the connection count can only
+        // fall: close the listener and wind down keep-alive connections
+        info!("🛑 Stopping new connections");
+        shutdown.stop_accepting();
+        drop(listener);
+
         // Drain in-flight connections
         info!("⏳ Draining active connections...");
         let drained = shutdown.drain_connections().await;
@@ -474,6 +527,57 @@ impl Zap {
         Ok(())
     }
 
+    /// Serve an accepted connection on its own task, tracked for draining
+    fn accept_connection(
+        server: &Arc<Self>,
+        shutdown: &GracefulShutdown,
+        result: std::io::Result<(tokio::net::TcpStream, SocketAddr)>,
+    ) {
+        match result {
+            Ok((stream, remote_addr)) => {
+                if !shutdown.is_accepting() {
+                    return;
+                }
+                let server = server.clone();
+                let shutdown = shutdown.clone();
+                // Track this connection from before its task starts, so a
+                // drain can't miss it
+                let guard = shutdown.connection_guard();
+
+                tokio::spawn(async move {
+                    let _guard = guard;
+
+                    let io = TokioIo::new(stream);
+
+                    let service = service_fn(move |req| {
+                        let server = server.clone();
+                        async move {
+                            server.handle_request(req, remote_addr).await
+                        }
+                    });
+
+                    let conn = http1::Builder::new().serve_connection(io, service);
+                    tokio::pin!(conn);
+                    let result = tokio::select! {
+                        result = conn.as_mut() => result,
+                        _ = shutdown.accepting_stopped() => {
+                            // Finish the request in flight, then close
+                            // instead of keeping the connection alive
+                            conn.as_mut().graceful_shutdown();
+                            conn.await
+                        }
+                    };
+                    if let Err(err) = result {
+                        debug!("Connection closed: {:?}", err);
+                    }
+                });
+            }
+            Err(e) => {
+                error!("Failed to accept connection: {}", e);
+            }
+        }
+    }
+
     /// Start the server and listen for connections (without graceful shutdown)
     ///
     /// For production use, prefer `listen_with_shutdown()` which handles signals properly.
@@ -481,23 +585,71 @@ impl Zap {
         self.listen_with_shutdown(ShutdownConfig::production()).await
     }
 
+    /// Build the trace sampler from the server configuration
+    fn trace_sampler(&self) -> TraceSampler {
+        let sampler = TraceSampler::new(self.config.trace_sample_rate);
+        match self.config.slow_request_threshold {
+            Some(threshold) => sampler.slow_threshold(threshold),
+            None => sampler,
+        }
+    }
+
     /// Handle an individual HTTP request
     async fn handle_request(
         &self,
-        hyper_req: HyperRequest<Incoming>,
+        mut hyper_req: HyperRequest<Incoming>,
         remote_addr: SocketAddr,
-    ) -> Result<HyperResponse<String>, hyper::Error> {
-        let response = match self.process_request(hyper_req, remote_addr).await {
+    ) -> Result<HyperResponse<Full<Bytes>>, hyper::Error> {
+        // Make sure every request carries an ID so the sampling decision
+        // made here matches the one made further down the pipeline
+        let request_id = match hyper_req
+            .headers()
+            .get(request_id::REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+        {
+            Some(id) => id.to_string(),
+            None => {
+                let id = request_id::generate();
+                if let Ok(value) = hyper::header::HeaderValue::from_str(&id) {
+                    hyper_req.headers_mut().insert(request_id::REQUEST_ID_HEADER, value);
+                }
+                id
+            }
+        };
+
+        let method = hyper_req.method().to_string();
+        let path = metrics::normalize_path(hyper_req.uri().path(), None);
+        let sampler = self.trace_sampler();
+        let span = sampler.request_span(&request_id, &method, &path);
+        let start = Instant::now();
+
+        metrics::inc_in_flight();
+        let result = match &span {
+            Some(span) => self.process_request(hyper_req, remote_addr).instrument(span.clone()).await,
+            None => self.process_request(hyper_req, remote_addr).await,
+        };
+        metrics::dec_in_flight();
+
+        let response = match result {
             Ok(zap_response) => zap_response.to_hyper_response(),
             Err(error) => {
-                error!("Request processing error: {}", error);
-                hyper::Response::builder()
-                    .status(500)
-                    .body("Internal Server Error".to_string())
-                    .unwrap()
+                let status = error.status_code();
+                if status >= 500 {
+                    error!("Request processing error: {}", error);
+                } else {
+                    debug!("Request rejected: {}", error);
+                }
+                error.to_hyper_response()
             }
         };
 
+        // Counters are exact; only span creation is sampled
+        let duration = start.elapsed();
+        let status = response.status().as_u16();
+        metrics::record_request(&method, &path, status, duration.as_secs_f64());
+        sampler.finish(span, &request_id, &method, &path, status, duration);
+
         Ok(response)
     }
 
@@ -512,6 +664,13 @@ impl Zap {
         // Step 1: Convert Hyper request to raw bytes
         let (parts, body) = hyper_req.into_parts();
 
+        // Reject oversized URIs before reading the body or routing
+        let parser = HttpParser::new().max_uri_length(self.config.max_uri_length);
+        let uri_length = parts.uri.path_and_query().map_or(0, |p| p.as_str().len());
+        if uri_length > parser.uri_limit() {
+            return Err(ZapError::uri_too_long(uri_length, parser.uri_limit()));
+        }
+
         // Collect the body bytes
         let body_bytes = body.collect().await
             .map_err(|e| ZapError::http(format!("Failed to read request body: {}", e)))?
@@ -535,15 +694,27 @@ impl Zap {
         request_bytes.extend_from_slice(&body_bytes);
 
         // Step 3: Parse using our fast HTTP parser
-        let parser = HttpParser::new();
-        let parsed = parser.parse_request(&request_bytes)
-            .map_err(|e| ZapError::http(format!("HTTP parsing failed: {:?}", e)))?;
+        let parsed = parser.parse_request(&request_bytes).map_err(|e| match e {
+            ParseError::UriTooLong => ZapError::uri_too_long(uri_length, parser.uri_limit()),
+            e @ (ParseError::InvalidContentLength
+            | ParseError::InvalidTransferEncoding
+            | ParseError::AmbiguousMessageLength) => ZapError::validation(e.to_string()),
+            e => ZapError::http(format!("HTTP parsing failed: {:?}", e)),
+        })?;
 
         // Step 4: Check for static file handlers first
         let path_for_routing = parsed.path.split('?').next().unwrap_or(parsed.path);
         
-        // Check static handlers
-        if let Some(static_response) = handle_static_files(&self.static_handlers, path_for_routing).await? {
+        // Check static handlers (request headers drive conditional requests
+        // and content negotiation; the query is kept for redirects)
+        let static_headers: HashMap<String, String> = parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        if let Some(static_response) =
+            handle_static_files_with_headers(&self.static_handlers, parsed.path, &static_headers).await?
+        {
             return Ok(static_response);
         }
 
@@ -551,15 +722,33 @@ impl Zap {
         let (handler, route_params) = self.router.at(method, path_for_routing)
             .ok_or_else(|| ZapError::route_not_found(path_for_routing))?;
 
-        // Step 6: Create Request object
+        // Step 6: Run the middleware chain, then the handler
         let body_start = &request_bytes[parsed.body_offset..];
-        let request = Request::new(&parsed, body_start, route_params);
+        self.dispatch(&parsed, body_start, handler, route_params).await
+    }
+
+    /// Run the middleware chain and then `handler`, which sees the
+    /// extensions middleware stored in the request context
+    async fn dispatch<'a>(
+        &'a self,
+        parsed: &'a ParsedRequest<'a>,
+        body: &'a [u8],
+        handler: &'a BoxedHandler,
+        params: Params<'a>,
+    ) -> Result<ZapResponse, ZapError> {
+        let ctx = match self.middleware.run(Context::new(parsed, body)).await {
+            Ok((ctx, None)) => ctx,
+            Ok((_, Some(response))) => return Ok(middleware_response(response)),
+            Err(e) => return Err(middleware_error(e)),
+        };
 
-        // Step 7: Execute the handler (middleware is handled separately in a real implementation)
+        let request = Request::new(parsed, ctx.body(), params).with_extensions(&ctx.extensions);
         let response = handler.handle(request).await
             .map_err(|e| ZapError::handler(format!("Handler execution failed: {}", e)))?;
 
-        Ok(response)
+        // Headers middleware set on the way through (CORS, CSRF cookies,
+        // rate-limit counters) belong on the handler's response
+        Ok(response.with_headers(ctx.response.headers))
     }
 
     /// Get router reference for testing
@@ -590,12 +779,19 @@ impl Zap {
                 .hostname(config.hostname.clone())
                 .max_request_body_size(config.max_request_body_size)
                 .request_timeout(Duration::from_secs(config.request_timeout_secs))
-                .keep_alive_timeout(Duration::from_secs(config.keepalive_timeout_secs)),
+                .keep_alive_timeout(Duration::from_secs(config.keepalive_timeout_secs))
+                .trace_sample_rate(config.trace_sample_rate),
             router: Router::new(),
             middleware: MiddlewareChain::new(),
             static_handlers: Vec::new(),
+            ready: Arc::new(AtomicBool::new(true)),
+            shutdown_hooks: Vec::new(),
         };
 
+        if let Some(ms) = config.slow_request_threshold_ms {
+            server.config = server.config.slow_request_threshold(Duration::from_millis(ms));
+        }
+
         // Add middleware
         if config.middleware.enable_cors {
             info!("✓ CORS middleware enabled");
@@ -607,6 +803,15 @@ impl Zap {
             server = server.logging();
         }
 
+        // TypeScript handlers share one pool of persistent IPC connections
+        if config.ipc_pool_size == 0 {
+            return Err(ZapError::config("IPC pool size must be > 0"));
+        }
+        let ipc_pool = Arc::new(ConnectionPool::new(
+            PoolConfig::new(config.ipc_socket_path.clone()).size(config.ipc_pool_size),
+        ));
+        ipc_pool.start_health_checks();
+
         // Register all routes from configuration
         for route_cfg in &config.routes {
             let method = route_cfg.method.to_uppercase();
@@ -628,10 +833,11 @@ impl Zap {
 
             if route_cfg.is_typescript {
                 // TypeScript handler - use proxy
-                let proxy = ProxyHandler::with_timeout(
+                let proxy = ProxyHandler::with_timeout_and_pool(
                     route_cfg.handler_id.clone(),
                     config.ipc_socket_path.clone(),
                     config.request_timeout_secs,
+                    ipc_pool.clone(),
                 );
                 server.router.insert(method_enum, &route_cfg.path, Box::new(proxy))
                     .map_err(|e| ZapError::config(format!(
@@ -645,7 +851,15 @@ impl Zap {
 
         // Register static files
         for static_cfg in &config.static_files {
-            server = server.static_files(&static_cfg.prefix, &static_cfg.directory);
+            server = if static_cfg.options.untrusted {
+                let options = StaticOptions {
+                    untrusted: true,
+                    ..Default::default()
+                };
+                server.static_files_with_options(&static_cfg.prefix, &static_cfg.directory, options)
+            } else {
+                server.static_files(&static_cfg.prefix, &static_cfg.directory)
+            };
             info!(
                 "✓ Static files: {} -> {}",
                 static_cfg.prefix, static_cfg.directory
@@ -668,17 +882,17 @@ impl Zap {
 
             // Build dispatch function that forwards to Splice
             let splice_client = std::sync::Arc::new(tokio::sync::RwLock::new(splice_client));
-            std::sync::Arc::new(move |function_name: String, params: serde_json::Value, _context: Option<splice::protocol::RequestContext>| {
+            std::sync::Arc::new(move |function_name: String, params: serde_json::Value, context: Option<splice::protocol::RequestContext>| {
                 let splice_client = splice_client.clone();
                 let function_name = function_name.clone();
                 let params = params.clone();
 
                 // Spawn async task and block on result (required by RpcDispatchFn signature)
-                // Note: Context is ignored here as Splice handles context internally
+                // The context carries the caller's trace into the worker
                 tokio::task::block_in_place(|| {
                     tokio::runtime::Handle::current().block_on(async move {
                         splice_client.read().await
-                            .invoke(function_name, params)
+                            .invoke_with_context(function_name, params, context)
                             .await
                     })
                 })
@@ -731,4 +945,196 @@ impl Default for Zap {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+}
+/// Response a middleware answered with, in place of the handler's
+fn middleware_response(response: MiddlewareResponse) -> ZapResponse {
+    ZapResponse::Custom(
+        zap_core::Response::with_status(StatusCode(response.status))
+            .headers(response.headers)
+            .body(response.body),
+    )
+}
+
+fn middleware_error(error: MiddlewareError) -> ZapError {
+    match error {
+        MiddlewareError::BadRequest(message) => ZapError::validation(message),
+        MiddlewareError::Unauthorized(message) => ZapError::unauthorized(message),
+        MiddlewareError::NotFound(message) => ZapError::route_not_found(message),
+        MiddlewareError::InternalError(message) | MiddlewareError::InternalServerError(message) => {
+            ZapError::Internal(message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use zap_core::{Middleware, MiddlewareResult, ResponseBuilder};
+
+    struct UserId(u64);
+
+    /// Middleware storing the caller's user id, or refusing anonymous calls
+    struct Authenticate;
+
+    impl Middleware for Authenticate {
+        fn call<'a>(&'a self, mut ctx: Context<'a>) -> zap_core::middleware::MiddlewareFuture<'a> {
+            Box::pin(async move {
+                match ctx.headers().get("x-user").and_then(|v| v.parse().ok()) {
+                    Some(id) => {
+                        ctx.insert(UserId(id));
+                        Ok((ctx, MiddlewareResult::Continue))
+                    }
+                    None => Ok((ctx, MiddlewareResult::Response(ResponseBuilder::new().status(401).finish()))),
+                }
+            })
+        }
+    }
+
+    /// Handler echoing the user id middleware stored
+    struct WhoAmI;
+
+    impl Handler for WhoAmI {
+        fn handle<'a>(
+            &'a self,
+            req: Request<'a>,
+        ) -> Pin<Box<dyn Future<Output = Result<ZapResponse, ZapError>> + Send + 'a>> {
+            let user = req.extension::<UserId>().map(|user| user.0);
+            Box::pin(async move { Ok(ZapResponse::Text(format!("{:?}", user))) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_sees_middleware_extensions() {
+        let server = Zap::new().use_middleware(Authenticate);
+        let handler: BoxedHandler = Box::new(WhoAmI);
+        let parser = HttpParser::new();
+
+        let raw = b"GET /me HTTP/1.1\r\nX-User: 7\r\n\r\n";
+        let parsed = parser.parse_request(raw).unwrap();
+        let response = server.dispatch(&parsed, &raw[parsed.body_offset..], &handler, Params::new()).await.unwrap();
+        assert!(matches!(response, ZapResponse::Text(ref text) if text == "Some(7)"));
+
+        // A middleware answering itself skips the handler
+        let raw = b"GET /me HTTP/1.1\r\n\r\n";
+        let parsed = parser.parse_request(raw).unwrap();
+        let response = server.dispatch(&parsed, &raw[parsed.body_offset..], &handler, Params::new()).await.unwrap();
+        assert!(matches!(response, ZapResponse::Custom(ref r) if r.status.as_u16() == 401));
+    }
+
+    /// Serve `server` on a local port for the rest of the test
+    async fn serve(server: Zap) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Arc::new(server);
+        let shutdown = GracefulShutdown::new(ShutdownConfig::default().without_signal_handlers());
+        tokio::spawn(async move {
+            loop {
+                Zap::accept_connection(&server, &shutdown, listener.accept().await);
+            }
+        });
+        addr
+    }
+
+    /// Send a raw request on a fresh connection and read the response
+    async fn send_raw(addr: SocketAddr, request: &str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    /// Read one response off a keep-alive connection
+    async fn read_response(stream: &mut tokio::net::TcpStream) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 1024];
+        loop {
+            let text = String::from_utf8_lossy(&response);
+            if let Some(head_end) = text.find("\r\n\r\n") {
+                let length = text[..head_end]
+                    .lines()
+                    .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if response.len() >= head_end + 4 + length {
+                    return text.into_owned();
+                }
+            }
+            let read = stream.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed mid-response");
+            response.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lame_duck_serves_open_connection_then_drains() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let server = Arc::new(Zap::new().use_middleware(Authenticate).get("/me", WhoAmI));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = GracefulShutdown::new(
+            ShutdownConfig::default()
+                .without_signal_handlers()
+                .with_lame_duck_period(Duration::from_millis(300))
+                .with_drain_timeout(Duration::from_secs(2)),
+        );
+        let serving = tokio::spawn(Zap::serve_until_shutdown(server.clone(), listener, shutdown.clone()));
+
+        // A keep-alive connection that is open when shutdown begins
+        let request = "GET /me HTTP/1.1\r\nHost: localhost\r\nX-User: 7\r\n\r\n";
+        let mut open = tokio::net::TcpStream::connect(addr).await.unwrap();
+        open.write_all(request.as_bytes()).await.unwrap();
+        assert!(read_response(&mut open).await.starts_with("HTTP/1.1 200"));
+        assert_eq!(shutdown.active_connection_count(), 1);
+
+        shutdown.trigger();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(shutdown.is_lame_duck());
+        assert!(!server.ready.load(Ordering::SeqCst));
+
+        // The open connection and new ones are still served normally
+        open.write_all(request.as_bytes()).await.unwrap();
+        let response = read_response(&mut open).await;
+        assert!(response.starts_with("HTTP/1.1 200") && response.ends_with("Some(7)"));
+        let response = send_raw(addr, &request.replace("\r\n\r\n", "\r\nConnection: close\r\n\r\n")).await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(!shutdown.is_draining());
+
+        // After the lame-duck period the idle connection is closed and drained
+        tokio::time::timeout(Duration::from_secs(2), serving).await.unwrap().unwrap().unwrap();
+        assert!(shutdown.is_draining());
+        assert_eq!(shutdown.active_connection_count(), 0);
+        let mut rest = Vec::new();
+        assert_eq!(open.read_to_end(&mut rest).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_routed_response_keeps_middleware_headers() {
+        let server = Zap::new()
+            .cors()
+            .use_middleware(zap_core::CsrfMiddleware::development())
+            .use_middleware(Authenticate)
+            .get("/me", WhoAmI);
+        let addr = serve(server).await;
+
+        let response = send_raw(
+            addr,
+            "GET /me HTTP/1.1\r\nHost: localhost\r\nOrigin: https://app.example\r\nX-User: 7\r\nConnection: close\r\n\r\n",
+        )
+        .await
+        .to_ascii_lowercase();
+        assert!(response.starts_with("http/1.1 200"), "{}", response);
+        assert!(response.contains("\r\naccess-control-allow-origin: "), "{}", response);
+        assert!(response.contains("\r\nvary: origin\r\n"), "{}", response);
+        assert!(response.contains("\r\nset-cookie: "), "{}", response);
+        assert!(response.contains("\r\ncontent-type: text/plain; charset=utf-8\r\n"), "{}", response);
+        assert!(response.ends_with("some(7)"), "{}", response);
+    }
+}
@@ -4,8 +4,13 @@
 //!
 //! ## Features
 //! - SIGTERM and SIGINT signal handling
+//! - SIGHUP (Unix only) requests a reload via `wait_reload()`, never a shutdown
+//! - Optional lame-duck period: keep serving while readiness fails so load
+//!   balancers can deregister the instance before draining starts
+//! - Phased shutdown: stop accepting before draining in-flight requests
 //! - Configurable drain period for in-flight requests
 //! - Connection tracking
+//! - Shutdown hooks run after draining, each bounded by a timeout
 //! - Proper resource cleanup
 //!
 //! ## Usage
@@ -28,15 +33,31 @@
 //!                 // Handle connection
 //!             }
 //!         }
+//!         if !shutdown.is_accepting() {
+//!             break;
+//!         }
 //!     }
 //!
-//!     // Drain in-flight requests
+//!     // Stop taking connections, then drain in-flight requests
+//!     shutdown.stop_accepting();
+//!     drop(listener);
 //!     shutdown.drain_connections().await;
 //! }
 //! ```
-
+//!
+//! ## Shutdown sequence
+//! 1. `trigger()` (or a signal): `wait()` completes and the server begins
+//!    shutting down, optionally serving through `lame_duck()` first
+//! 2. `stop_accepting()`: the accept loop sees `is_accepting() == false`
+//!    and breaks, and open connections are told via `accepting_stopped()`
+//!    to close once their current request completes
+//! 3. `drain_connections()`: waits for the remaining connections, which
+//!    can now only fall, then runs the hooks registered with `on_shutdown()`
+
+use futures::future::BoxFuture;
+use std::future::Future;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::Notify;
 use tokio::time::sleep;
@@ -51,14 +72,25 @@ pub struct ShutdownConfig {
     pub enable_signal_handlers: bool,
     /// Poll interval for checking connection count during drain (default: 100ms)
     pub drain_poll_interval: Duration,
+    /// Time to keep serving after the shutdown signal, with readiness
+    /// reporting unhealthy, before draining begins (default: 0 = disabled)
+    pub lame_duck_period: Duration,
+    /// Longest each shutdown hook may run before it is abandoned
+    /// (default: 10s)
+    pub hook_timeout: Duration,
 }
 
+/// Async cleanup callback run once draining completes
+pub type ShutdownHook = Box<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
 impl Default for ShutdownConfig {
     fn default() -> Self {
         Self {
             drain_timeout: Duration::from_secs(30),
             enable_signal_handlers: true,
             drain_poll_interval: Duration::from_millis(100),
+            lame_duck_period: Duration::ZERO,
+            hook_timeout: Duration::from_secs(10),
         }
     }
 }
@@ -83,6 +115,18 @@ impl ShutdownConfig {
         self
     }
 
+    /// Set the lame-duck period before draining begins
+    pub fn with_lame_duck_period(mut self, period: Duration) -> Self {
+        self.lame_duck_period = period;
+        self
+    }
+
+    /// Set how long each shutdown hook may run
+    pub fn with_hook_timeout(mut self, timeout: Duration) -> Self {
+        self.hook_timeout = timeout;
+        self
+    }
+
     /// Disable signal handlers (for testing or custom signal handling)
     pub fn without_signal_handlers(mut self) -> Self {
         self.enable_signal_handlers = false;
@@ -98,12 +142,22 @@ pub struct GracefulShutdown {
     config: ShutdownConfig,
     /// Shutdown signal notifier
     shutdown_notifier: Arc<Notify>,
+    /// Reload request notifier (SIGHUP)
+    reload_notifier: Arc<Notify>,
     /// Whether shutdown has been triggered
     shutdown_triggered: Arc<AtomicBool>,
     /// Count of active connections
     active_connections: Arc<AtomicU64>,
     /// Whether we're currently draining
     draining: Arc<AtomicBool>,
+    /// Whether we're in the lame-duck period
+    lame_duck: Arc<AtomicBool>,
+    /// Whether new connections are still accepted
+    accepting: Arc<AtomicBool>,
+    /// Notified when accepting stops
+    accepting_notifier: Arc<Notify>,
+    /// Cleanup hooks, by name, in registration order
+    hooks: Arc<Mutex<Vec<(String, ShutdownHook)>>>,
 }
 
 impl GracefulShutdown {
@@ -112,9 +166,14 @@ impl GracefulShutdown {
         let shutdown = Self {
             config: config.clone(),
             shutdown_notifier: Arc::new(Notify::new()),
+            reload_notifier: Arc::new(Notify::new()),
             shutdown_triggered: Arc::new(AtomicBool::new(false)),
             active_connections: Arc::new(AtomicU64::new(0)),
             draining: Arc::new(AtomicBool::new(false)),
+            lame_duck: Arc::new(AtomicBool::new(false)),
+            accepting: Arc::new(AtomicBool::new(true)),
+            accepting_notifier: Arc::new(Notify::new()),
+            hooks: Arc::new(Mutex::new(Vec::new())),
         };
 
         if config.enable_signal_handlers {
@@ -124,8 +183,10 @@ impl GracefulShutdown {
         shutdown
     }
 
-    /// Set up signal handlers for SIGTERM and SIGINT
+    /// Set up signal handlers for SIGTERM and SIGINT, and SIGHUP for reloads
     fn setup_signal_handlers(&self) {
+        self.setup_reload_handler();
+
         let shutdown_notifier = self.shutdown_notifier.clone();
         let shutdown_triggered = self.shutdown_triggered.clone();
 
@@ -162,6 +223,43 @@ impl GracefulShutdown {
         });
     }
 
+    /// Notify reload waiters on every SIGHUP
+    ///
+    /// The handler is registered before this returns, so a SIGHUP sent
+    /// right after never falls through to the default action (terminate).
+    #[cfg(unix)]
+    fn setup_reload_handler(&self) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let reload_notifier = self.reload_notifier.clone();
+        let mut sighup = signal(SignalKind::hangup())
+            .expect("Failed to register SIGHUP handler");
+        tokio::spawn(async move {
+            while sighup.recv().await.is_some() {
+                info!("📡 Received SIGHUP, requesting reload");
+                reload_notifier.notify_waiters();
+            }
+        });
+    }
+
+    /// There is no SIGHUP outside Unix; only `trigger_reload()` requests a reload
+    #[cfg(not(unix))]
+    fn setup_reload_handler(&self) {}
+
+    /// Wait for a reload request (SIGHUP or `trigger_reload()`)
+    ///
+    /// Unlike `wait()`, this does not begin shutdown and can fire any number
+    /// of times; call it again after handling each reload.
+    pub async fn wait_reload(&self) {
+        self.reload_notifier.notified().await;
+    }
+
+    /// Request a reload programmatically
+    pub fn trigger_reload(&self) {
+        info!("🔄 Reload triggered programmatically");
+        self.reload_notifier.notify_waiters();
+    }
+
     /// Wait for shutdown signal
     ///
     /// This should be used in a tokio::select! block in the main server loop.
@@ -211,13 +309,113 @@ impl GracefulShutdown {
         }
     }
 
+    /// Run the lame-duck period
+    ///
+    /// Completes after `lame_duck_period`; the caller keeps accepting and
+    /// serving requests until then. Returns immediately when disabled.
+    pub async fn lame_duck(&self) {
+        let period = self.config.lame_duck_period;
+        if period.is_zero() {
+            return;
+        }
+
+        info!("🦆 Entering lame-duck period for {:?}", period);
+        self.lame_duck.store(true, Ordering::SeqCst);
+        sleep(period).await;
+        self.lame_duck.store(false, Ordering::SeqCst);
+        info!("🦆 Lame-duck period over");
+    }
+
+    /// Check if currently in the lame-duck period
+    pub fn is_lame_duck(&self) -> bool {
+        self.lame_duck.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting new connections
+    ///
+    /// The accept loop should break once `is_accepting()` is false, and
+    /// connections waiting on `accepting_stopped()` close after their
+    /// current request. Call this before `drain_connections()`.
+    pub fn stop_accepting(&self) {
+        if self.accepting.swap(false, Ordering::SeqCst) {
+            info!("🛑 No longer accepting new connections");
+        }
+        self.accepting_notifier.notify_waiters();
+    }
+
+    /// Check if new connections are still accepted
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
+    /// Wait until `stop_accepting()` has been called
+    ///
+    /// Completes immediately if accepting already stopped.
+    pub async fn accepting_stopped(&self) {
+        loop {
+            // Registered before the check, so a concurrent stop isn't missed
+            let notified = self.accepting_notifier.notified();
+            if !self.is_accepting() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Register an async cleanup hook, e.g. to flush metrics or close a pool
+    ///
+    /// Hooks run once, in registration order, when `drain_connections()`
+    /// finishes; one that outlives `hook_timeout` is logged and abandoned.
+    pub fn on_shutdown<F, Fut>(&self, name: impl Into<String>, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.add_hook(name.into(), Box::new(move || Box::pin(hook())));
+    }
+
+    /// Register an already boxed hook
+    pub fn add_hook(&self, name: String, hook: ShutdownHook) {
+        self.hooks.lock().unwrap().push((name, hook));
+    }
+
+    /// Run and remove the registered hooks
+    ///
+    /// Called by `drain_connections()`; hooks registered afterwards only
+    /// run if this is called again.
+    pub async fn run_hooks(&self) {
+        let hooks = std::mem::take(&mut *self.hooks.lock().unwrap());
+        for (name, hook) in hooks {
+            info!("🧹 Running shutdown hook '{}'", name);
+            if tokio::time::timeout(self.config.hook_timeout, hook()).await.is_err() {
+                warn!(
+                    "⚠️  Shutdown hook '{}' exceeded {:?} and was abandoned",
+                    name, self.config.hook_timeout
+                );
+            }
+        }
+    }
+
     /// Drain active connections with timeout
     ///
     /// Waits for all in-flight connections to complete, up to the configured timeout.
     /// Returns true if all connections drained successfully, false if timeout occurred.
+    /// Call `stop_accepting()` first, or new connections can keep the count up.
+    /// Shutdown hooks run once draining ends either way.
     pub async fn drain_connections(&self) -> bool {
+        let drained = self.wait_for_connections().await;
+        self.run_hooks().await;
+        drained
+    }
+
+    /// Wait for active connections to finish, up to `drain_timeout`
+    async fn wait_for_connections(&self) -> bool {
         self.draining.store(true, Ordering::SeqCst);
 
+        if self.is_accepting() {
+            warn!("⚠️  Draining while still accepting new connections");
+        }
+
         let active = self.active_connection_count();
         if active == 0 {
             info!("✅ No active connections to drain");
@@ -270,9 +468,14 @@ impl Clone for GracefulShutdown {
         Self {
             config: self.config.clone(),
             shutdown_notifier: self.shutdown_notifier.clone(),
+            reload_notifier: self.reload_notifier.clone(),
             shutdown_triggered: self.shutdown_triggered.clone(),
             active_connections: self.active_connections.clone(),
             draining: self.draining.clone(),
+            lame_duck: self.lame_duck.clone(),
+            accepting: self.accepting.clone(),
+            accepting_notifier: self.accepting_notifier.clone(),
+            hooks: self.hooks.clone(),
         }
     }
 }
@@ -405,6 +608,51 @@ mod tests {
         assert!(shutdown.is_shutdown());
     }
 
+    #[tokio::test]
+    async fn test_reload_does_not_shut_down() {
+        let config = ShutdownConfig::default().without_signal_handlers();
+        let shutdown = GracefulShutdown::new(config);
+
+        for _ in 0..2 {
+            let reloaded = shutdown.wait_reload();
+            tokio::pin!(reloaded);
+            assert!(futures::poll!(&mut reloaded).is_pending());
+            shutdown.trigger_reload();
+            tokio::time::timeout(Duration::from_secs(1), reloaded)
+                .await
+                .expect("wait_reload() did not resolve");
+        }
+
+        assert!(!shutdown.is_shutdown());
+        tokio::select! {
+            _ = shutdown.wait() => panic!("reload triggered shutdown"),
+            _ = sleep(Duration::from_millis(50)) => {}
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sighup_requests_reload() {
+        // The SIGHUP handler is in place once this returns
+        let shutdown = GracefulShutdown::new(ShutdownConfig::default());
+
+        // Polling once registers the waiter, so the signal can't be missed
+        let reloaded = shutdown.wait_reload();
+        tokio::pin!(reloaded);
+        assert!(futures::poll!(&mut reloaded).is_pending());
+
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        tokio::time::timeout(Duration::from_secs(5), reloaded)
+            .await
+            .expect("SIGHUP did not request a reload");
+        assert!(!shutdown.is_shutdown());
+    }
+
     #[tokio::test]
     async fn test_shutdown_select() {
         let config = ShutdownConfig::default().without_signal_handlers();
@@ -431,13 +679,150 @@ mod tests {
         assert!(shutdown.is_shutdown());
     }
 
+    #[tokio::test]
+    async fn test_lame_duck_before_drain() {
+        use crate::reliability::{HealthChecker, HealthStatus};
+
+        let config = ShutdownConfig::default()
+            .without_signal_handlers()
+            .with_lame_duck_period(Duration::from_millis(300));
+        let shutdown = GracefulShutdown::new(config);
+        let ready = Arc::new(AtomicBool::new(true));
+        let checker = HealthChecker::new("1.0.0".to_string()).with_ready_flag(ready.clone());
+
+        assert_eq!(checker.readiness().await.status, HealthStatus::Healthy);
+
+        // Server reacts to the signal by failing readiness, then lame-ducks
+        shutdown.trigger();
+        ready.store(false, Ordering::SeqCst);
+        let lame_duck = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.lame_duck().await }
+        });
+        sleep(Duration::from_millis(50)).await;
+
+        assert!(shutdown.is_lame_duck());
+        assert_eq!(checker.readiness().await.status, HealthStatus::Unhealthy);
+        assert_eq!(checker.liveness().status, HealthStatus::Healthy);
+
+        // Serving through the lame-duck period over a real connection is
+        // covered by the server tests
+        assert!(!shutdown.is_draining());
+
+        lame_duck.await.unwrap();
+        assert!(!shutdown.is_lame_duck());
+
+        assert!(shutdown.drain_connections().await);
+        assert!(shutdown.is_draining());
+    }
+
+    #[tokio::test]
+    async fn test_stop_accepting_before_drain() {
+        let config = ShutdownConfig::default()
+            .without_signal_handlers()
+            .with_drain_timeout(Duration::from_secs(2));
+        let shutdown = GracefulShutdown::new(config);
+        assert!(shutdown.is_accepting());
+
+        let first = shutdown.connection_guard();
+        let second = shutdown.connection_guard();
+
+        // A connection task waiting to be told to wind down
+        let told = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.accepting_stopped().await }
+        });
+        sleep(Duration::from_millis(20)).await;
+        assert!(!told.is_finished());
+
+        shutdown.trigger();
+        shutdown.stop_accepting();
+        assert!(!shutdown.is_accepting());
+        told.await.unwrap();
+        // Already stopped: completes immediately
+        shutdown.accepting_stopped().await;
+
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            drop(first);
+            sleep(Duration::from_millis(100)).await;
+            drop(second);
+        });
+
+        assert_eq!(shutdown.active_connection_count(), 2);
+        assert!(shutdown.drain_connections().await);
+        assert_eq!(shutdown.active_connection_count(), 0);
+        assert!(!shutdown.is_accepting());
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_once_after_drain_in_order() {
+        let config = ShutdownConfig::default()
+            .without_signal_handlers()
+            .with_drain_timeout(Duration::from_secs(2));
+        let shutdown = GracefulShutdown::new(config);
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        for name in ["flush-metrics", "close-pool"] {
+            let calls = calls.clone();
+            let connections = shutdown.clone();
+            shutdown.on_shutdown(name, move || {
+                let calls = calls.clone();
+                let active = connections.active_connection_count();
+                async move { calls.lock().unwrap().push((name, active)) }
+            });
+        }
+
+        let guard = shutdown.connection_guard();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            drop(guard);
+        });
+        assert!(calls.lock().unwrap().is_empty());
+
+        assert!(shutdown.drain_connections().await);
+        // After draining, with no connections left, and in registration order
+        assert_eq!(*calls.lock().unwrap(), vec![("flush-metrics", 0), ("close-pool", 0)]);
+
+        assert!(shutdown.drain_connections().await);
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_slow_hook_bounded_by_timeout() {
+        let config = ShutdownConfig::default()
+            .without_signal_handlers()
+            .with_hook_timeout(Duration::from_millis(100));
+        let shutdown = GracefulShutdown::new(config);
+        let finished = Arc::new(AtomicBool::new(false));
+
+        shutdown.on_shutdown("hangs", || sleep(Duration::from_secs(60)));
+        shutdown.on_shutdown("after", {
+            let finished = finished.clone();
+            move || {
+                let finished = finished.clone();
+                async move { finished.store(true, Ordering::SeqCst) }
+            }
+        });
+
+        let start = std::time::Instant::now();
+        assert!(shutdown.drain_connections().await);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // Later hooks still run after one is abandoned
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn test_config_builder() {
         let config = ShutdownConfig::development()
             .with_drain_timeout(Duration::from_secs(10))
+            .with_lame_duck_period(Duration::from_secs(5))
+            .with_hook_timeout(Duration::from_secs(3))
             .without_signal_handlers();
 
         assert_eq!(config.drain_timeout, Duration::from_secs(10));
+        assert_eq!(config.lame_duck_period, Duration::from_secs(5));
+        assert_eq!(config.hook_timeout, Duration::from_secs(3));
         assert!(!config.enable_signal_handlers);
     }
 }
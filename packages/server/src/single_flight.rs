@@ -0,0 +1,175 @@
+//! Request coalescing (single-flight)
+//!
+//! When many identical requests arrive at once (a cache stampede), only the
+//! first one does the work; the rest wait for its result and receive a
+//! clone. The entry is removed as soon as the work finishes, so later
+//! requests start a fresh call rather than receiving a stale result.
+//!
+//! If the leading call is cancelled before finishing, waiters run the work
+//! themselves instead of failing.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Deduplicates concurrent calls that share a key
+pub struct SingleFlight<T: Clone> {
+    /// In-flight calls, keyed by request identity
+    calls: Mutex<HashMap<String, broadcast::Sender<T>>>,
+}
+
+impl<T: Clone> SingleFlight<T> {
+    /// Create an empty single-flight group
+    pub fn new() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `work` for `key`, or wait for the identical call already in flight
+    pub async fn run<F, Fut>(&self, key: String, work: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let waiter = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get(&key) {
+                Some(tx) => Some(tx.subscribe()),
+                None => {
+                    let (tx, _) = broadcast::channel(1);
+                    calls.insert(key.clone(), tx);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut rx) = waiter {
+            return match rx.recv().await {
+                Ok(value) => value,
+                // Leader was cancelled before producing a result
+                Err(_) => work().await,
+            };
+        }
+
+        let guard = LeaderGuard {
+            calls: &self.calls,
+            key: Some(key),
+        };
+        let value = work().await;
+        if let Some(tx) = guard.finish() {
+            let _ = tx.send(value.clone());
+        }
+        value
+    }
+
+    /// Number of distinct calls currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+}
+
+impl<T: Clone> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes the leader's entry when it finishes or is dropped mid-flight
+struct LeaderGuard<'a, T> {
+    calls: &'a Mutex<HashMap<String, broadcast::Sender<T>>>,
+    key: Option<String>,
+}
+
+impl<T> LeaderGuard<'_, T> {
+    /// Remove the entry and hand back its sender for publishing the result
+    fn finish(mut self) -> Option<broadcast::Sender<T>> {
+        let key = self.key.take()?;
+        self.calls.lock().unwrap().remove(&key)
+    }
+}
+
+impl<T> Drop for LeaderGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            if let Ok(mut calls) = self.calls.lock() {
+                calls.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_identical_calls_run_once() {
+        let group = Arc::new(SingleFlight::<u64>::new());
+        let invocations = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let group = group.clone();
+                let invocations = invocations.clone();
+                tokio::spawn(async move {
+                    group
+                        .run("GET /expensive".to_string(), || async {
+                            invocations.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            42
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 42);
+        }
+        assert_eq!(invocations.load(Ordering::SeqCst), 1);
+        assert_eq!(group.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_run_separately() {
+        let group = SingleFlight::<&'static str>::new();
+        let (a, b) = tokio::join!(
+            group.run("a".to_string(), || async { "a" }),
+            group.run("b".to_string(), || async { "b" }),
+        );
+        assert_eq!((a, b), ("a", "b"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_lets_waiter_run() {
+        let group = Arc::new(SingleFlight::<u64>::new());
+
+        let leader = tokio::spawn({
+            let group = group.clone();
+            async move {
+                group
+                    .run("key".to_string(), || async {
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                        1
+                    })
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let waiter = tokio::spawn({
+            let group = group.clone();
+            async move { group.run("key".to_string(), || async { 2 }).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        leader.abort();
+        assert_eq!(waiter.await.unwrap(), 2);
+        assert_eq!(group.in_flight(), 0);
+    }
+}
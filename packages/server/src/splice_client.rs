@@ -11,7 +11,9 @@ use tracing::{error, info, warn};
 use std::collections::HashMap;
 
 // Import Splice protocol types from canonical source
-use splice::protocol::{Message, ExportMetadata, RequestContext, Role, SpliceCodec};
+use splice::protocol::{parse_retry_after, Message, ExportMetadata, RequestContext, Role, SpliceCodec, CAP_CANCELLATION, PRIORITY_NORMAL};
+use crate::rpc::{coded_error, retryable_error};
+use crate::trace_context::TraceContext;
 
 pub struct SpliceClient {
     tx: mpsc::Sender<ClientRequest>,
@@ -22,6 +24,7 @@ enum ClientRequest {
     Invoke {
         function_name: String,
         params: serde_json::Value,
+        context: Option<RequestContext>,
         response_tx: oneshot::Sender<Result<serde_json::Value, String>>,
     },
     Shutdown,
@@ -38,7 +41,9 @@ impl SpliceClient {
         Self::send_raw_message(&stream, Message::Handshake {
             protocol_version: 0x00010000,
             role: Role::Host,
-            capabilities: 0b11, // Streaming + Cancellation
+            // No streaming: this client has nowhere to put a streamed
+            // response, so zap-splice refuses them for it
+            capabilities: CAP_CANCELLATION,
             max_frame_size: 100 * 1024 * 1024,
         }).await?;
 
@@ -84,6 +89,19 @@ impl SpliceClient {
         &self,
         function_name: String,
         params: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        self.invoke_with_context(function_name, params, None).await
+    }
+
+    /// Invoke a Rust function with a request context
+    ///
+    /// When no context is given a new root trace is started so every
+    /// invocation carries a valid `traceparent`.
+    pub async fn invoke_with_context(
+        &self,
+        function_name: String,
+        params: serde_json::Value,
+        context: Option<RequestContext>,
     ) -> Result<serde_json::Value, String> {
         let (response_tx, response_rx) = oneshot::channel();
 
@@ -91,6 +109,7 @@ impl SpliceClient {
             .send(ClientRequest::Invoke {
                 function_name,
                 params,
+                context,
                 response_tx,
             })
             .await
@@ -135,6 +154,7 @@ impl SpliceClient {
                         ClientRequest::Invoke {
                             function_name,
                             params,
+                            context,
                             response_tx,
                         } => {
                             let request_id = next_request_id;
@@ -150,12 +170,10 @@ impl SpliceClient {
                                 function_name,
                                 params: Bytes::from(params_bytes),
                                 deadline_ms: 30000, // 30 second timeout
-                                context: RequestContext {
-                                    trace_id: 0,
-                                    span_id: 0,
-                                    headers: vec![],
-                                    auth: None,
-                                },
+                                context: context.unwrap_or_else(|| {
+                                    TraceContext::new_root().to_request_context(vec![])
+                                }),
+                                priority: PRIORITY_NORMAL,
                             };
 
                             framed.send(msg).await.map_err(|e| e.to_string())?;
@@ -178,9 +196,13 @@ impl SpliceClient {
                                 let _ = response_tx.send(Ok(result_json));
                             }
                         }
-                        Some(Ok(Message::InvokeError { request_id, message, .. })) => {
+                        Some(Ok(Message::InvokeError { request_id, code, kind, message, details })) => {
                             if let Some(response_tx) = pending_requests.remove(&request_id) {
-                                let _ = response_tx.send(Err(message));
+                                let error = match details.as_deref().and_then(parse_retry_after) {
+                                    Some(retry_after) => retryable_error(code, kind, retry_after, &message),
+                                    None => coded_error(code, kind, &message),
+                                };
+                                let _ = response_tx.send(Err(error));
                             }
                         }
                         Some(Ok(Message::ShutdownAck)) => {
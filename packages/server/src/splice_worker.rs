@@ -5,23 +5,36 @@
 
 use std::collections::HashMap;
 use std::env;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::UnixStream;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
 use tokio_util::codec::Framed;
 use tokio_util::sync::CancellationToken;
-use tracing::{info, debug, warn, error};
+use tracing::{info, debug, warn, error, Instrument};
 use bytes::Bytes;
 use futures::stream::StreamExt;
 use futures::sink::SinkExt;
 
 // Import Splice protocol types from the canonical source
-use splice::protocol::{Message, Role, SpliceCodec, ExportMetadata, ErrorKind};
+use splice::protocol::{
+    Message, Role, SpliceCodec, ExportMetadata, ErrorKind, RequestContext,
+    CAP_BATCH, CAP_CANCELLATION, CAP_COMPRESSION, CAP_STREAMING, Compression, ERR_CANCELLED, ERR_EXECUTION_FAILED, ERR_INVALID_REQUEST, ERR_PANIC,
+    retry_after_details,
+};
+use splice::admin::{self, AdminReply, WorkerStats};
+use splice::outbound::{self, OutboundConfig};
 
 // Import registry for function dispatch and Context wrapper
+use crate::config::RpcDispatchFn;
 use crate::registry::build_rpc_dispatcher;
 use crate::context::Context;
+use crate::request_body::{self, RequestBody};
+use crate::response_stream::{self, ResponseStream};
+use crate::rpc::parse_coded_error;
+use crate::trace_context::TraceContext;
 
 /// Tracks an in-flight request that can be cancelled
 struct InFlightRequest {
@@ -29,6 +42,114 @@ struct InFlightRequest {
     function_name: String,
     cancellation_token: CancellationToken,
     task_handle: JoinHandle<()>,
+    /// Receives the host's `StreamAck`/`StreamError` for the response stream
+    stream_acks: Option<mpsc::Sender<Message>>,
+}
+
+/// Acks buffered for a response stream before delivery is deferred
+const STREAM_ACK_CAPACITY: usize = 8;
+
+/// How long a body opened by `StreamStart` waits for its `Invoke`
+const PENDING_BODY_TTL: Duration = Duration::from_secs(30);
+
+/// Uploads opened by `StreamStart`, keyed by request id
+#[derive(Default)]
+struct Uploads {
+    /// Where each open upload's messages are delivered
+    senders: HashMap<u64, mpsc::Sender<Message>>,
+    /// Bodies whose `Invoke` has not arrived yet, with when they were opened
+    pending: HashMap<u64, (Instant, RequestBody)>,
+}
+
+impl Uploads {
+    /// Open the body for `StreamStart { request_id, window }`
+    fn open(&mut self, request_id: u64, window: u32, acks: &mpsc::Sender<Message>) {
+        let now = Instant::now();
+        self.expire(now);
+        let (tx, body) = RequestBody::open(request_id, window, acks.clone());
+        self.senders.insert(request_id, tx);
+        self.pending.insert(request_id, (now, body));
+    }
+
+    /// Claim the body for the `Invoke` with `request_id`
+    fn take_body(&mut self, request_id: u64) -> Option<RequestBody> {
+        let body = self.pending.remove(&request_id).map(|(_, body)| body);
+        self.expire(Instant::now());
+        body
+    }
+
+    /// Drop bodies whose `Invoke` has not arrived within
+    /// [`PENDING_BODY_TTL`] of `StreamStart`
+    ///
+    /// Further messages for them are refused as unknown uploads.
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, (opened, _))| now.duration_since(*opened) >= PENDING_BODY_TTL)
+            .map(|(request_id, _)| *request_id)
+            .collect();
+        for request_id in expired {
+            warn!("Dropping upload {}: no Invoke within {:?}", request_id, PENDING_BODY_TTL);
+            self.cancel(request_id);
+        }
+    }
+
+    /// Deliver a `StreamChunk`, `StreamEnd` or `StreamError` to its body
+    ///
+    /// Returns a `StreamError` for the host when the upload is unknown or
+    /// the uploader sent more than its window allows.
+    fn route(&mut self, request_id: u64, msg: Message) -> Option<Message> {
+        let terminal = !matches!(msg, Message::StreamChunk { .. });
+        let Some(tx) = self.senders.get(&request_id) else {
+            if matches!(msg, Message::StreamError { .. }) {
+                return None;
+            }
+            return Some(Message::StreamError {
+                request_id,
+                code: ERR_INVALID_REQUEST,
+                message: format!("Unknown upload {}", request_id),
+            });
+        };
+
+        let reply = match tx.try_send(msg) {
+            Ok(()) => None,
+            // The channel holds a window of chunks, so an uploader honouring
+            // it may still find it full when it ends; nothing follows the
+            // end, so it can be delivered once the function reads on
+            Err(mpsc::error::TrySendError::Full(msg)) if terminal => {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let _ = tx.send(msg).await;
+                });
+                None
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                // Dropping the sender ends the body with `Closed`
+                self.cancel(request_id);
+                return Some(Message::StreamError {
+                    request_id,
+                    code: ERR_INVALID_REQUEST,
+                    message: "Upload window exceeded".to_string(),
+                });
+            }
+            // The function returned without reading the rest of its body
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.cancel(request_id);
+                return None;
+            }
+        };
+        if terminal {
+            self.senders.remove(&request_id);
+        }
+        reply
+    }
+
+    /// Drop an upload and its unclaimed body
+    fn cancel(&mut self, request_id: u64) {
+        self.senders.remove(&request_id);
+        self.pending.remove(&request_id);
+    }
 }
 
 /// Run the Splice worker runtime
@@ -51,53 +172,54 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // Connect to zap-splice
     let stream = UnixStream::connect(&socket_path).await?;
-    let mut framed = create_framed_stream(stream);
+    let outbound = outbound_config();
+    let mut framed = create_framed_stream(stream, &outbound);
 
     // Build RPC dispatcher from linkme exports
     let dispatcher = Arc::new(build_rpc_dispatcher());
     let exports = collect_exports();
 
-    // Send handshake
-    send_message(&mut framed, Message::Handshake {
-        protocol_version: 0x00010000,
-        role: Role::Worker,
-        capabilities: 0b11, // Streaming + Cancellation
-        max_frame_size: 100 * 1024 * 1024,
-    }).await?;
+    handshake(&mut framed, compression()).await?;
+    serve(framed, &outbound, dispatcher, exports).await;
 
-    // Wait for handshake ack
-    match receive_message(&mut framed).await? {
-        Message::HandshakeAck { .. } => {
-            info!("Handshake complete");
-        }
-        _ => {
-            return Err("Expected HandshakeAck".into());
-        }
-    }
+    info!("Worker runtime shutting down");
+    Ok(())
+}
 
+/// Serve zap-splice's requests on a connection that completed its
+/// handshake, until it closes or zap-splice asks for a shutdown
+async fn serve(
+    framed: Framed<UnixStream, SpliceCodec>,
+    outbound: &OutboundConfig,
+    dispatcher: Arc<RpcDispatchFn>,
+    exports: Vec<ExportMetadata>,
+) {
     // Split framed stream for concurrent access
     let (write_half, mut read_half) = framed.split();
 
-    // Channel for sending responses from tasks back to write loop
-    let (response_tx, mut response_rx) = mpsc::channel::<Message>(256);
+    // Bounded channel for sending responses from tasks back to write loop;
+    // a slow host blocks senders instead of growing the queue
+    let (response_tx, response_rx) = outbound.channel();
 
     // Track in-flight requests for cancellation
     let in_flight: Arc<RwLock<HashMap<u64, InFlightRequest>>> =
         Arc::new(RwLock::new(HashMap::new()));
 
+    // Streamed request bodies, fed from this loop
+    let mut uploads = Uploads::default();
+
+    // Reported in HealthStatus
+    let started_at = std::time::Instant::now();
+    let mut total_requests: u64 = 0;
+    let admin_enabled = admin_enabled();
+
     // Spawn write loop task to handle all outgoing messages
-    let write_task = {
-        let mut write_half = write_half;
-        tokio::spawn(async move {
-            while let Some(msg) = response_rx.recv().await {
-                if let Err(e) = write_half.send(msg).await {
-                    error!("Failed to send message: {}", e);
-                    break;
-                }
-            }
-            debug!("Write loop terminated");
-        })
-    };
+    let write_task = tokio::spawn(async move {
+        if let Err(e) = outbound::write_loop(response_rx, write_half).await {
+            error!("Failed to send message: {}", e);
+        }
+        debug!("Write loop terminated");
+    });
 
     // Main read loop - processes incoming messages
     loop {
@@ -127,11 +249,32 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                 params,
                 deadline_ms: _,
                 context,
+                priority: _,
             } => {
                 debug!("Invoking function: {} (request_id: {})", function_name, request_id);
+                total_requests += 1;
+
+                // Continue the caller's trace with a span for this invocation,
+                // or start a new root when the host sent no trace context
+                let trace = TraceContext::from_request_context(&context)
+                    .map(|parent| parent.child())
+                    .unwrap_or_else(TraceContext::new_root);
+                let span = tracing::info_span!(
+                    "splice.invoke",
+                    function = %function_name,
+                    request_id,
+                    trace_id = %format!("{:032x}", trace.trace_id),
+                    span_id = %format!("{:016x}", trace.span_id),
+                );
+                let auth = context.auth;
+                let mut context = trace.to_request_context(context.headers);
+                context.auth = auth;
 
                 // Create cancellation token for this request
                 let cancellation_token = CancellationToken::new();
+                let body = uploads.take_body(request_id);
+                let (stream_acks, acks) = mpsc::channel(STREAM_ACK_CAPACITY);
+                let stream = ResponseStream::new(request_id, response_tx.clone(), acks);
 
                 // Clone resources for the spawned task
                 let dispatcher = dispatcher.clone();
@@ -142,70 +285,22 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
                 // Spawn task to handle invocation
                 let task_handle = tokio::spawn(async move {
-                    let start = std::time::Instant::now();
-
-                    // Deserialize params from MessagePack to JSON
-                    let params_json: serde_json::Value = rmp_serde::from_slice(&params)
-                        .unwrap_or_else(|_| serde_json::json!({}));
-
-                    // Execute function with automatic cancellation via tokio::select!
-                    let result = tokio::select! {
-                        // Function execution path
-                        res = async {
-                            dispatcher(function_name_for_task.clone(), params_json, Some(context))
-                        } => res,
-
-                        // Cancellation path - triggers when token is cancelled
-                        _ = token.cancelled() => {
-                            debug!("Function {} cancelled during execution", function_name_for_task);
-                            Err("Request cancelled".to_string())
-                        }
-                    };
-
-                    let duration_us = start.elapsed().as_micros() as u64;
-
-                    // Send result back via channel
-                    let response = match result {
-                        Ok(result_json) => {
-                            // Serialize result to MessagePack
-                            match rmp_serde::to_vec(&result_json) {
-                                Ok(result_bytes) => Message::InvokeResult {
-                                    request_id,
-                                    result: Bytes::from(result_bytes),
-                                    duration_us,
-                                },
-                                Err(e) => Message::InvokeError {
-                                    request_id,
-                                    code: 2000, // ERR_EXECUTION_FAILED
-                                    kind: ErrorKind::System,
-                                    message: format!("Failed to serialize result: {}", e),
-                                    details: None,
-                                },
-                            }
-                        }
-                        Err(error_msg) => {
-                            // Determine error kind based on cancellation
-                            let (code, kind) = if token.is_cancelled() {
-                                (2002, ErrorKind::Cancelled) // ERR_CANCELLED
-                            } else {
-                                (2000, ErrorKind::User) // ERR_EXECUTION_FAILED
-                            };
-
-                            Message::InvokeError {
-                                request_id,
-                                code,
-                                kind,
-                                message: error_msg,
-                                details: None,
-                            }
-                        }
-                    };
+                    let invoke = execute_invoke(
+                        &dispatcher,
+                        request_id,
+                        function_name_for_task,
+                        params,
+                        context,
+                        body,
+                        token,
+                    );
+                    let response = response_stream::with_pending_stream(stream, invoke).await;
 
                     // Send response and cleanup
                     let _ = response_tx.send(response).await;
                     in_flight_clone.write().await.remove(&request_id);
                     debug!("Request {} completed", request_id);
-                });
+                }.instrument(span));
 
                 // Track in-flight request
                 in_flight.write().await.insert(request_id, InFlightRequest {
@@ -213,11 +308,42 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                     function_name,
                     cancellation_token,
                     task_handle,
+                    stream_acks: Some(stream_acks),
+                });
+            }
+
+            Message::InvokeBatch { request_id, calls } => {
+                debug!("Invoking batch of {} calls (request_id: {})", calls.len(), request_id);
+                total_requests += calls.len() as u64;
+
+                // One token cancels every call in the batch
+                let cancellation_token = CancellationToken::new();
+                let function_name = format!("batch of {}", calls.len());
+
+                let dispatcher = dispatcher.clone();
+                let response_tx = response_tx.clone();
+                let token = cancellation_token.clone();
+                let in_flight_clone = in_flight.clone();
+
+                let task_handle = tokio::spawn(async move {
+                    let response = execute_batch(&dispatcher, request_id, calls, token).await;
+                    let _ = response_tx.send(response).await;
+                    in_flight_clone.write().await.remove(&request_id);
+                    debug!("Batch {} completed", request_id);
+                }.instrument(tracing::info_span!("splice.invoke_batch", request_id)));
+
+                in_flight.write().await.insert(request_id, InFlightRequest {
+                    request_id,
+                    function_name,
+                    cancellation_token,
+                    task_handle,
+                    stream_acks: None,
                 });
             }
 
             Message::Cancel { request_id } => {
                 debug!("Cancel request: {}", request_id);
+                uploads.cancel(request_id);
 
                 // Trigger cancellation token for this request
                 if let Some(req) = in_flight.read().await.get(&request_id) {
@@ -251,6 +377,67 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
                 break;
             }
 
+            Message::HealthCheck => {
+                let _ = response_tx.send(Message::HealthStatus {
+                    uptime_ms: started_at.elapsed().as_millis() as u64,
+                    active_requests: in_flight.read().await.len() as u32,
+                    total_requests,
+                }).await;
+            }
+
+            Message::AdminCommand { request_id, command, .. } => {
+                debug!("Admin command: {}", command);
+                let reply = if !admin_enabled {
+                    AdminReply::disabled()
+                } else {
+                    match command.as_str() {
+                        admin::CMD_STATS => AdminReply::Stats(WorkerStats {
+                            uptime_ms: started_at.elapsed().as_millis() as u64,
+                            active_requests: in_flight.read().await.len() as u32,
+                            total_requests,
+                            memory_rss_bytes: admin::memory_rss_bytes(),
+                        }),
+                        admin::CMD_HANDLERS => AdminReply::Handlers(
+                            in_flight.read().await.values().map(|req| req.function_name.clone()).collect(),
+                        ),
+                        other => AdminReply::unknown_command(other),
+                    }
+                };
+                let _ = response_tx.send(reply.to_message(request_id)).await;
+            }
+
+            Message::StreamStart { request_id, window } => {
+                debug!("Upload opened for request {} (window {})", request_id, window);
+                uploads.open(request_id, window, &response_tx);
+            }
+
+            msg @ (Message::StreamChunk { .. } | Message::StreamEnd { .. } | Message::StreamError { .. }) => {
+                let request_id = match &msg {
+                    Message::StreamChunk { request_id, .. }
+                    | Message::StreamEnd { request_id, .. }
+                    | Message::StreamError { request_id, .. } => *request_id,
+                    _ => unreachable!(),
+                };
+                // A StreamError for no open upload aborts the response stream
+                if matches!(msg, Message::StreamError { .. }) && !uploads.senders.contains_key(&request_id) {
+                    deliver_stream_ack(&in_flight, request_id, msg).await;
+                    continue;
+                }
+                if let Some(reply) = uploads.route(request_id, msg) {
+                    warn!("Rejecting upload message for request {}", request_id);
+                    let _ = response_tx.send(reply).await;
+                }
+            }
+
+            Message::StreamAck { request_id, ack_sequence, window } => {
+                let ack = Message::StreamAck { request_id, ack_sequence, window };
+                deliver_stream_ack(&in_flight, request_id, ack).await;
+            }
+
+            Message::Unknown { msg_type, .. } => {
+                debug!("Ignoring unknown message type 0x{:02x}", msg_type);
+            }
+
             msg => {
                 warn!("Unexpected message: {:?}", msg);
             }
@@ -262,9 +449,171 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // Wait for write task to finish
     let _ = write_task.await;
+}
 
-    info!("Worker runtime shutting down");
-    Ok(())
+/// Run a single invocation and build the response message
+///
+/// A panic in the dispatcher is caught and reported as `ERR_PANIC` for this
+/// request only, so the worker keeps serving other requests. This relies on
+/// `panic = "unwind"`; a build with `panic = "abort"` exits on the first
+/// panic and the supervisor restarts the worker instead. A streamed
+/// `body` is made available through [`Context::take_body`].
+async fn execute_invoke(
+    dispatcher: &RpcDispatchFn,
+    request_id: u64,
+    function_name: String,
+    params: Bytes,
+    context: RequestContext,
+    body: Option<RequestBody>,
+    token: CancellationToken,
+) -> Message {
+    let start = std::time::Instant::now();
+
+    // Deserialize params from MessagePack to JSON
+    let params_json: serde_json::Value = rmp_serde::from_slice(&params)
+        .unwrap_or_else(|_| serde_json::json!({}));
+
+    // Execute function with automatic cancellation via tokio::select!
+    let result = tokio::select! {
+        // Function execution path
+        res = async {
+            let call = async {
+                std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    dispatcher(function_name.clone(), params_json, Some(context))
+                }))
+            };
+            match body {
+                Some(body) => request_body::with_pending_body(body, call).await,
+                None => call.await,
+            }
+        } => res,
+
+        // Cancellation path - triggers when token is cancelled
+        _ = token.cancelled() => {
+            debug!("Function {} cancelled during execution", function_name);
+            Ok(Err("Request cancelled".to_string()))
+        }
+    };
+
+    let duration_us = start.elapsed().as_micros() as u64;
+
+    match result {
+        Ok(Ok(result_json)) => {
+            // Serialize result to MessagePack
+            match rmp_serde::to_vec(&result_json) {
+                Ok(result_bytes) => Message::InvokeResult {
+                    request_id,
+                    result: Bytes::from(result_bytes),
+                    duration_us,
+                },
+                Err(e) => Message::InvokeError {
+                    request_id,
+                    code: ERR_EXECUTION_FAILED,
+                    kind: ErrorKind::System,
+                    message: format!("Failed to serialize result: {}", e),
+                    details: None,
+                },
+            }
+        }
+        Ok(Err(error_msg)) => {
+            // Determine error kind based on cancellation, unless the
+            // dispatcher already said what went wrong
+            let (code, kind, message, details) = if token.is_cancelled() {
+                (ERR_CANCELLED, ErrorKind::Cancelled, error_msg, None)
+            } else if let Some(coded) = parse_coded_error(&error_msg) {
+                let details = coded.retry_after.map(retry_after_details);
+                (coded.code, coded.kind, coded.message.to_string(), details)
+            } else {
+                (ERR_EXECUTION_FAILED, ErrorKind::User, error_msg, None)
+            };
+
+            Message::InvokeError {
+                request_id,
+                code,
+                kind,
+                message,
+                details,
+            }
+        }
+        Err(payload) => {
+            let message = panic_message(payload.as_ref());
+            error!("Function {} panicked: {}", function_name, message);
+
+            Message::InvokeError {
+                request_id,
+                code: ERR_PANIC,
+                kind: ErrorKind::System,
+                message,
+                details: None,
+            }
+        }
+    }
+}
+
+/// Hand a host `StreamAck`/`StreamError` to the response stream of
+/// `request_id`
+///
+/// Never blocks the read loop on a function that is not sending, but never
+/// drops an ack either: the stream would wait for it forever. Acks are
+/// cumulative, so a late one is harmless.
+async fn deliver_stream_ack(
+    in_flight: &RwLock<HashMap<u64, InFlightRequest>>,
+    request_id: u64,
+    msg: Message,
+) {
+    let stream_acks = in_flight
+        .read()
+        .await
+        .get(&request_id)
+        .and_then(|req| req.stream_acks.clone());
+    match stream_acks {
+        Some(tx) => {
+            if let Err(mpsc::error::TrySendError::Full(msg)) = tx.try_send(msg) {
+                tokio::spawn(async move {
+                    let _ = tx.send(msg).await;
+                });
+            }
+        }
+        None => debug!("Stream message for unknown response stream {}", request_id),
+    }
+}
+
+/// Run every call of a batch concurrently and collect results in call order
+///
+/// Each call starts its own root trace, as batches carry no request context.
+async fn execute_batch(
+    dispatcher: &RpcDispatchFn,
+    request_id: u64,
+    calls: Vec<(String, Bytes)>,
+    token: CancellationToken,
+) -> Message {
+    let invocations = calls.into_iter().map(|(function_name, params)| {
+        let context = TraceContext::new_root().to_request_context(Vec::new());
+        execute_invoke(dispatcher, request_id, function_name, params, context, None, token.clone())
+    });
+
+    let results = futures::future::join_all(invocations)
+        .await
+        .into_iter()
+        .map(|response| match response {
+            Message::InvokeResult { result, .. } => Ok(result),
+            Message::InvokeError { code, message, .. } => Err((code, message)),
+            other => Err((ERR_EXECUTION_FAILED, format!("Unexpected response: {:?}", other))),
+        })
+        .collect();
+
+    Message::InvokeBatchResult { request_id, results }
+}
+
+/// Extract a readable message from a panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Function panicked".to_string()
+    }
 }
 
 /// Collect exported functions from linkme distributed slice
@@ -284,8 +633,68 @@ fn collect_exports() -> Vec<ExportMetadata> {
 }
 
 // Create framed stream using Splice protocol codec
-fn create_framed_stream(stream: UnixStream) -> Framed<UnixStream, SpliceCodec> {
-    Framed::new(stream, SpliceCodec::default())
+fn create_framed_stream(stream: UnixStream, outbound: &OutboundConfig) -> Framed<UnixStream, SpliceCodec> {
+    outbound.framed(stream, SpliceCodec::default())
+}
+
+/// Outbound limits, overridable with `ZAP_SPLICE_OUTBOUND_BUFFER`
+fn outbound_config() -> OutboundConfig {
+    let mut config = OutboundConfig::default();
+    if let Some(buffer) = env::var("ZAP_SPLICE_OUTBOUND_BUFFER")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        config.buffer = buffer;
+    }
+    config
+}
+
+/// Frame compression offered to zap-splice, overridable with
+/// `ZAP_SPLICE_COMPRESSION` (`gzip`, `zstd` or `off`; default: zstd)
+///
+/// Frames are only compressed once zap-splice enables compression too.
+fn compression() -> Option<Compression> {
+    match env::var("ZAP_SPLICE_COMPRESSION").as_deref() {
+        Err(_) => Some(Compression::Zstd),
+        Ok("off") | Ok("none") => None,
+        Ok(name) => match name.parse() {
+            Ok(compression) => Some(compression),
+            Err(e) => {
+                warn!("Ignoring ZAP_SPLICE_COMPRESSION: {}", e);
+                Some(Compression::Zstd)
+            }
+        },
+    }
+}
+
+/// Exchange handshakes with zap-splice, enabling the compression both
+/// sides support
+async fn handshake(
+    framed: &mut Framed<UnixStream, SpliceCodec>,
+    compression: Option<Compression>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let capabilities = CAP_STREAMING | CAP_CANCELLATION | CAP_BATCH | compression.map_or(0, |_| CAP_COMPRESSION);
+    send_message(framed, Message::Handshake {
+        protocol_version: 0x00010000,
+        role: Role::Worker,
+        capabilities,
+        max_frame_size: 100 * 1024 * 1024,
+    }).await?;
+
+    // Wait for handshake ack
+    match receive_message(framed).await? {
+        Message::HandshakeAck { capabilities: acked, .. } => {
+            framed.codec_mut().set_compression(Compression::negotiate(compression, capabilities, acked));
+            info!("Handshake complete");
+            Ok(())
+        }
+        _ => Err("Expected HandshakeAck".into()),
+    }
+}
+
+/// Whether operators enabled admin commands with `ZAP_SPLICE_ADMIN=1`
+fn admin_enabled() -> bool {
+    matches!(env::var("ZAP_SPLICE_ADMIN").as_deref(), Ok("1") | Ok("true"))
 }
 
 async fn send_message(
@@ -305,3 +714,361 @@ async fn receive_message(
         .ok_or("Connection closed")?
         .map_err(Into::into)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dispatcher() -> RpcDispatchFn {
+        Arc::new(|name: String, params: serde_json::Value, _ctx: Option<RequestContext>| {
+            match name.as_str() {
+                "boom" => panic!("something went wrong"),
+                "echo" => Ok(params),
+                _ => Err(format!("Function not found: {}", name)),
+            }
+        })
+    }
+
+    fn empty_context() -> RequestContext {
+        RequestContext {
+            trace_id: 0,
+            span_id: 0,
+            headers: vec![],
+            auth: None,
+        }
+    }
+
+    /// Handshake against a zap-splice acking `runtime_capabilities`,
+    /// returning the compression the worker's codec ends up with
+    async fn negotiated_compression(worker: Option<Compression>, runtime_capabilities: u32) -> Option<Compression> {
+        let (worker_stream, runtime_stream) = UnixStream::pair().unwrap();
+        let mut worker_framed = create_framed_stream(worker_stream, &OutboundConfig::default());
+        let mut runtime_framed = Framed::new(runtime_stream, SpliceCodec::default());
+
+        let runtime = tokio::spawn(async move {
+            let offered = match runtime_framed.next().await {
+                Some(Ok(Message::Handshake { capabilities, .. })) => capabilities,
+                other => panic!("Expected Handshake, got {:?}", other),
+            };
+            runtime_framed.send(Message::HandshakeAck {
+                protocol_version: 0x00010000,
+                capabilities: offered & runtime_capabilities,
+                server_id: [0; 16],
+                export_count: 0,
+            }).await.unwrap();
+            offered
+        });
+
+        handshake(&mut worker_framed, worker).await.unwrap();
+        let offered = runtime.await.unwrap();
+        assert_eq!(offered & CAP_COMPRESSION != 0, worker.is_some());
+        worker_framed.codec().compression()
+    }
+
+    #[tokio::test]
+    async fn test_handshake_enables_negotiated_compression() {
+        assert_eq!(negotiated_compression(Some(Compression::Zstd), CAP_COMPRESSION).await, Some(Compression::Zstd));
+        // zap-splice without compression configured, or a worker with it off
+        assert_eq!(negotiated_compression(Some(Compression::Zstd), 0).await, None);
+        assert_eq!(negotiated_compression(None, CAP_COMPRESSION).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_panic_returns_err_panic_and_worker_survives() {
+        let dispatcher = test_dispatcher();
+
+        let response = execute_invoke(
+            &dispatcher,
+            1,
+            "boom".to_string(),
+            Bytes::from(rmp_serde::to_vec(&serde_json::json!({})).unwrap()),
+            empty_context(),
+            None,
+            CancellationToken::new(),
+        ).await;
+
+        match response {
+            Message::InvokeError { request_id, code, kind, message, .. } => {
+                assert_eq!(request_id, 1);
+                assert_eq!(code, ERR_PANIC);
+                assert_eq!(kind, ErrorKind::System);
+                assert_eq!(message, "something went wrong");
+            }
+            other => panic!("Expected InvokeError, got {:?}", other),
+        }
+
+        // The same dispatcher keeps serving subsequent requests
+        let params = serde_json::json!({ "x": 1 });
+        let response = execute_invoke(
+            &dispatcher,
+            2,
+            "echo".to_string(),
+            Bytes::from(rmp_serde::to_vec(&params).unwrap()),
+            empty_context(),
+            None,
+            CancellationToken::new(),
+        ).await;
+
+        match response {
+            Message::InvokeResult { request_id, result, .. } => {
+                assert_eq!(request_id, 2);
+                let value: serde_json::Value = rmp_serde::from_slice(&result).unwrap();
+                assert_eq!(value, params);
+            }
+            other => panic!("Expected InvokeResult, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_error_is_execution_failed() {
+        let response = execute_invoke(
+            &test_dispatcher(),
+            3,
+            "missing".to_string(),
+            Bytes::new(),
+            empty_context(),
+            None,
+            CancellationToken::new(),
+        ).await;
+
+        assert!(matches!(
+            response,
+            Message::InvokeError { code: ERR_EXECUTION_FAILED, kind: ErrorKind::User, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_batch_results_follow_call_order() {
+        let params = |value: serde_json::Value| Bytes::from(rmp_serde::to_vec(&value).unwrap());
+        let calls = vec![
+            ("echo".to_string(), params(serde_json::json!({ "n": 1 }))),
+            ("missing".to_string(), params(serde_json::json!({}))),
+            ("boom".to_string(), params(serde_json::json!({}))),
+            ("echo".to_string(), params(serde_json::json!({ "n": 2 }))),
+        ];
+
+        let response = execute_batch(&test_dispatcher(), 9, calls, CancellationToken::new()).await;
+        let Message::InvokeBatchResult { request_id, results } = response else {
+            panic!("Expected InvokeBatchResult, got {:?}", response);
+        };
+        assert_eq!(request_id, 9);
+        assert_eq!(results.len(), 4);
+
+        let decode = |bytes: &Bytes| rmp_serde::from_slice::<serde_json::Value>(bytes).unwrap();
+        assert_eq!(decode(results[0].as_ref().unwrap()), serde_json::json!({ "n": 1 }));
+        assert_eq!(results[1].as_ref().unwrap_err().0, ERR_EXECUTION_FAILED);
+        assert_eq!(results[2].as_ref().unwrap_err(), &(ERR_PANIC, "something went wrong".to_string()));
+        assert_eq!(decode(results[3].as_ref().unwrap()), serde_json::json!({ "n": 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_upload_waits_for_ack_between_chunks() {
+        use splice::upload::UploadSender;
+        use std::time::Duration;
+
+        // Host side: uploader writes to `wire`, worker acks arrive on `acks`
+        let (wire_tx, mut wire) = mpsc::channel(16);
+        let (acks_tx, acks) = mpsc::channel(16);
+        let mut sender = UploadSender::start(5, 1, wire_tx, acks).await.unwrap();
+        let Some(Message::StreamStart { request_id, window }) = wire.recv().await else {
+            panic!("Expected StreamStart");
+        };
+
+        let mut uploads = Uploads::default();
+        uploads.open(request_id, window, &acks_tx);
+        let mut body = uploads.take_body(5).unwrap();
+
+        for data in [&b"one"[..], b"two", b"three"] {
+            let send = sender.send(Bytes::from_static(data));
+            tokio::time::timeout(Duration::from_millis(200), send).await.unwrap().unwrap();
+            let chunk = wire.recv().await.unwrap();
+            assert!(uploads.route(5, chunk).is_none());
+
+            // Window of one: the next chunk waits until this one is consumed
+            let blocked = tokio::time::timeout(Duration::from_millis(50), sender.send(Bytes::from_static(b"x"))).await;
+            assert!(blocked.is_err());
+            assert!(wire.try_recv().is_err());
+
+            assert_eq!(body.chunk().await.unwrap().unwrap(), Bytes::from_static(data));
+        }
+
+        assert_eq!(sender.finish().await.unwrap(), 3);
+        assert!(uploads.route(5, wire.recv().await.unwrap()).is_none());
+        assert!(body.chunk().await.is_none());
+        assert!(uploads.senders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upload_beyond_window_is_rejected() {
+        let (acks_tx, _acks) = mpsc::channel(16);
+        let mut uploads = Uploads::default();
+        uploads.open(6, 1, &acks_tx);
+
+        let chunk = |sequence| Message::StreamChunk { request_id: 6, sequence, data: Bytes::from_static(b"x") };
+        assert!(uploads.route(6, chunk(0)).is_none());
+        assert!(matches!(
+            uploads.route(6, chunk(1)),
+            Some(Message::StreamError { request_id: 6, code: ERR_INVALID_REQUEST, .. })
+        ));
+        assert!(matches!(
+            uploads.route(7, chunk(0)),
+            Some(Message::StreamError { request_id: 7, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_full_window_still_ends() {
+        let (acks_tx, _acks) = mpsc::channel(16);
+        let mut uploads = Uploads::default();
+        uploads.open(9, 2, &acks_tx);
+        let mut body = uploads.take_body(9).unwrap();
+
+        for sequence in 0..2 {
+            let chunk = Message::StreamChunk { request_id: 9, sequence, data: Bytes::from_static(b"x") };
+            assert!(uploads.route(9, chunk).is_none());
+        }
+        assert!(uploads.route(9, Message::StreamEnd { request_id: 9, total_chunks: 2 }).is_none());
+        assert!(uploads.senders.is_empty());
+
+        assert!(body.chunk().await.unwrap().is_ok());
+        assert!(body.chunk().await.unwrap().is_ok());
+        assert!(body.chunk().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unclaimed_body_expires() {
+        let (acks_tx, _acks) = mpsc::channel(16);
+        let mut uploads = Uploads::default();
+        uploads.open(10, 4, &acks_tx);
+        uploads.open(11, 4, &acks_tx);
+
+        uploads.expire(Instant::now() + PENDING_BODY_TTL / 2);
+        assert_eq!(uploads.pending.len(), 2);
+
+        uploads.expire(Instant::now() + PENDING_BODY_TTL);
+        assert!(uploads.pending.is_empty());
+        assert!(uploads.senders.is_empty());
+        assert!(uploads.take_body(10).is_none());
+        let chunk = Message::StreamChunk { request_id: 11, sequence: 0, data: Bytes::from_static(b"x") };
+        assert!(matches!(uploads.route(11, chunk), Some(Message::StreamError { request_id: 11, .. })));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_response_stream_longer_than_window_reaches_host() {
+        use splice::router::{ResponseRelay, Router, RouterConfig};
+        use tokio::sync::Notify;
+
+        // The function streams five chunks through a window of two, run
+        // the way the registry runs async exports
+        let dispatcher: RpcDispatchFn = Arc::new(|_name, _params, _ctx| {
+            let mut stream = response_stream::take_pending_stream().ok_or("No stream")?.window(2);
+            let handle = tokio::runtime::Handle::current();
+            let sent = tokio::task::block_in_place(|| handle.block_on(async {
+                for data in [&b"a"[..], b"b", b"c", b"d", b"e"] {
+                    stream.send(Bytes::from_static(data)).await?;
+                }
+                stream.finish().await
+            }));
+            sent.map(|chunks| serde_json::json!(chunks)).map_err(|e| e.to_string())
+        });
+
+        // Worker runtime on one end, zap-splice's router on the other
+        let (worker_stream, runtime_stream) = UnixStream::pair().unwrap();
+        tokio::spawn(async move {
+            let outbound = OutboundConfig::default();
+            serve(create_framed_stream(worker_stream, &outbound), &outbound, Arc::new(dispatcher), Vec::new()).await;
+        });
+        let (mut runtime_write, runtime_read) = Framed::new(runtime_stream, SpliceCodec::default()).split();
+        let (worker_tx, mut worker_rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Some(msg) = worker_rx.recv().await {
+                runtime_write.send(msg).await.unwrap();
+            }
+        });
+        let mut router = Router::new(RouterConfig::default());
+        router.set_worker_tx(worker_tx);
+        let router = Arc::new(router);
+        router
+            .update_exports(vec![ExportMetadata {
+                name: "rows".to_string(),
+                is_async: false,
+                is_streaming: true,
+                params_schema: "{}".to_string(),
+                return_schema: "{}".to_string(),
+            }])
+            .await;
+        {
+            let router = router.clone();
+            tokio::spawn(async move { router.read_worker_messages(runtime_read, Arc::new(Notify::new())).await });
+        }
+
+        // Host side: frames arrive under the host's own request id
+        let (frames_tx, mut frames) = mpsc::channel(16);
+        let (acks, acks_rx) = mpsc::channel(16);
+        let relay = ResponseRelay { request_id: 5, frames: frames_tx, acks: acks_rx };
+        let invoke = {
+            let router = router.clone();
+            tokio::spawn(async move {
+                router.invoke_streaming("rows".to_string(), Bytes::new(), 5000, empty_context(), 0, relay).await
+            })
+        };
+
+        assert!(matches!(frames.recv().await, Some(Message::StreamStart { request_id: 5, window: 2 })));
+        let mut received = Vec::new();
+        loop {
+            match frames.recv().await {
+                Some(Message::StreamChunk { request_id: 5, sequence, data }) => {
+                    received.push(data);
+                    if sequence % 2 == 1 {
+                        // Window full: nothing more until the host acks
+                        assert!(tokio::time::timeout(Duration::from_millis(100), frames.recv()).await.is_err());
+                        let ack = Message::StreamAck { request_id: 5, ack_sequence: sequence + 1, window: 2 };
+                        acks.send(ack).await.unwrap();
+                    }
+                }
+                Some(Message::StreamEnd { request_id: 5, total_chunks }) => {
+                    assert_eq!(total_chunks, 5);
+                    break;
+                }
+                other => panic!("Expected a stream frame, got {:?}", other),
+            }
+        }
+        assert_eq!(received.concat(), b"abcde");
+
+        let result = invoke.await.unwrap().unwrap();
+        assert_eq!(rmp_serde::from_slice::<u64>(&result).unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_reaches_function() {
+        let (acks_tx, _acks) = mpsc::channel(16);
+        let mut uploads = Uploads::default();
+        uploads.open(8, 4, &acks_tx);
+        for (sequence, data) in [&b"ab"[..], b"cd", b"e"].into_iter().enumerate() {
+            let chunk = Message::StreamChunk { request_id: 8, sequence: sequence as u64, data: Bytes::from_static(data) };
+            assert!(uploads.route(8, chunk).is_none());
+        }
+        assert!(uploads.route(8, Message::StreamEnd { request_id: 8, total_chunks: 3 }).is_none());
+
+        let dispatcher: RpcDispatchFn = Arc::new(|_name, _params, _ctx| {
+            let body = request_body::take_pending_body().ok_or("No body")?;
+            let bytes = futures::executor::block_on(body.collect()).map_err(|e| e.to_string())?;
+            Ok(serde_json::json!(String::from_utf8_lossy(&bytes)))
+        });
+        let response = execute_invoke(
+            &dispatcher,
+            8,
+            "upload".to_string(),
+            Bytes::new(),
+            empty_context(),
+            uploads.take_body(8),
+            CancellationToken::new(),
+        ).await;
+
+        let Message::InvokeResult { result, .. } = response else {
+            panic!("Expected InvokeResult, got {:?}", response);
+        };
+        let value: serde_json::Value = rmp_serde::from_slice(&result).unwrap();
+        assert_eq!(value, serde_json::json!("abcde"));
+    }
+}
@@ -1,15 +1,21 @@
 //! Static file serving functionality for ZapServer
 //!
 //! Provides high-performance static file serving with:
-//! - ETag generation (weak or strong)
+//! - ETag generation (weak or strong), with an optional precomputed index
 //! - Last-Modified headers
 //! - Conditional request handling (304 Not Modified)
 //! - Cache-Control configuration
 //! - Content-Type detection
 //! - Directory traversal protection
+//! - Optional on-the-fly Brotli/gzip compression with an LRU of encoded variants
+//! - Precompressed `.br`/`.gz` sibling files
+//! - Optional HTML/JSON directory listings
+//! - Single byte-range requests (206 Partial Content)
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::SystemTime;
 use zap_core::{Response, StatusCode};
 use crate::error::ZapError;
@@ -38,23 +44,58 @@ pub struct StaticHandler {
     pub directory: PathBuf,
     /// Options for static serving
     pub options: StaticOptions,
+    /// Strong ETags by file path, shared between clones
+    etag_cache: Arc<RwLock<HashMap<PathBuf, CachedETag>>>,
+    /// Files hashed for strong ETags
+    hashed: Arc<AtomicUsize>,
+    /// Compressed variants served on the fly, shared between clones
+    compressed_cache: Arc<Mutex<CompressionCache>>,
+    /// Files compressed on the fly
+    compressed: Arc<AtomicUsize>,
 }
 
 /// Static file serving options
 #[derive(Debug, Clone)]
 pub struct StaticOptions {
-    /// Enable directory listing
+    /// List a directory without an index file as HTML, or JSON when the
+    /// client asks for it (default: false)
     pub directory_listing: bool,
     /// Set Cache-Control header
     pub cache_control: Option<String>,
     /// Custom headers
     pub headers: HashMap<String, String>,
-    /// Enable compression
+    /// Compress compressible responses with Brotli or gzip, as negotiated
+    /// by `Accept-Encoding` (default: true)
     pub compress: bool,
+    /// Smallest file compressed; smaller files are always served as-is
+    /// (default: 1KB)
+    pub compression_min_size: u64,
+    /// Serve a `<file>.br` or `<file>.gz` sibling, when one exists and the
+    /// client accepts its encoding, instead of the file itself
+    /// (default: true)
+    pub precompressed: bool,
     /// ETag generation strategy (default: Weak)
     pub etag_strategy: ETagStrategy,
     /// Enable Last-Modified header (default: true)
     pub enable_last_modified: bool,
+    /// Largest file hashed by `precompute_etags` (default: 16MB)
+    pub precompute_max_file_size: u64,
+    /// Compressed variants kept when compressing on the fly; 0 compresses
+    /// every response anew (default: 0)
+    pub compression_cache_entries: usize,
+    /// Treat the directory as untrusted user content: every file is served
+    /// as `application/octet-stream` with `Content-Disposition: attachment`
+    /// and `X-Content-Type-Options: nosniff`, whatever its extension, so an
+    /// uploaded page or script cannot run in the site's origin
+    /// (default: false)
+    pub untrusted: bool,
+    /// Serve files reached through a symlink below the directory; when
+    /// false, any symlinked path component is rejected with 403 wherever it
+    /// points (default: true)
+    pub follow_symlinks: bool,
+    /// Files tried in order for a request naming a directory; the first
+    /// that exists is served (default: `["index.html"]`)
+    pub index_files: Vec<String>,
 }
 
 impl Default for StaticOptions {
@@ -64,8 +105,15 @@ impl Default for StaticOptions {
             cache_control: Some("public, max-age=3600".to_string()),
             headers: HashMap::new(),
             compress: true,
+            compression_min_size: 1024,
+            precompressed: true,
             etag_strategy: ETagStrategy::default(),
             enable_last_modified: true,
+            precompute_max_file_size: 16 * 1024 * 1024,
+            compression_cache_entries: 0,
+            untrusted: false,
+            follow_symlinks: true,
+            index_files: vec!["index.html".to_string()],
         }
     }
 }
@@ -77,14 +125,349 @@ struct FileMetadata {
     modified: SystemTime,
 }
 
+/// Strong ETag, valid while the file's size and mtime are unchanged
+#[derive(Debug, Clone)]
+struct CachedETag {
+    size: u64,
+    modified: SystemTime,
+    etag: String,
+}
+
+/// What a request path resolves to (see [`StaticHandler::resolve_index`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum IndexLookup {
+    /// A file path (not necessarily existing) to serve
+    File(String),
+    /// A directory named without its trailing slash
+    Redirect,
+    /// A directory without any of the configured index files
+    Missing,
+}
+
+/// Portion of a file selected by a `Range` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteRange {
+    /// No usable range; serve the whole file
+    Full,
+    /// Inclusive first and last byte offsets
+    Partial(u64, u64),
+    /// The range starts past the end of the file
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header against a file of `len` bytes
+///
+/// Only a single `bytes` range is honoured: other units, multiple ranges
+/// and malformed values fall back to the full file, as RFC 7233 allows.
+fn parse_range(header: &str, len: u64) -> ByteRange {
+    let spec = match header.trim().strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return ByteRange::Full,
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        // Suffix range: the last `end` bytes
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial(len.saturating_sub(suffix), len - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return ByteRange::Full,
+    };
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end.min(len - 1))
+}
+
+/// Read `first..=last` from a file without loading the rest of it
+async fn read_range(path: &Path, first: u64, last: u64) -> std::io::Result<Vec<u8>> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut file = tokio::fs::File::open(path).await?;
+    file.seek(std::io::SeekFrom::Start(first)).await?;
+    let mut buf = vec![0; (last - first + 1) as usize];
+    file.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Entry shown in a directory listing
+#[derive(Debug, Clone, serde::Serialize)]
+struct ListingEntry {
+    name: String,
+    href: String,
+    is_dir: bool,
+    size: u64,
+    /// Seconds since the Unix epoch
+    modified: u64,
+}
+
+/// Percent-encode a path segment for use in a listing link
+fn encode_path_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whether an `Accept` header prefers JSON over HTML
+fn prefers_json(accept: &str) -> bool {
+    let accept = accept.to_ascii_lowercase();
+    accept.contains("application/json") && !accept.contains("text/html")
+}
+
+/// Content encoding applied by on-the-fly compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// `Content-Encoding` header value
+    fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// Extension of a precompressed sibling file
+    fn extension(self) -> &'static str {
+        match self {
+            Encoding::Brotli => ".br",
+            Encoding::Gzip => ".gz",
+        }
+    }
+
+    /// Preferred encoding allowed by an `Accept-Encoding` header
+    fn negotiate(accept_encoding: &str) -> Option<Self> {
+        Self::preferences(accept_encoding).into_iter().next()
+    }
+
+    /// Encodings allowed by an `Accept-Encoding` header, most preferred first
+    ///
+    /// Codings with `q=0` are refused; on equal weight Brotli wins.
+    fn preferences(accept_encoding: &str) -> Vec<Self> {
+        let (mut br, mut gzip, mut any) = (None, None, None);
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';');
+            let coding = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            match coding.as_str() {
+                "br" => br = Some(q),
+                "gzip" | "x-gzip" => gzip = Some(q),
+                "*" => any = Some(q),
+                _ => {}
+            }
+        }
+
+        let br = br.or(any).unwrap_or(0.0);
+        let gzip = gzip.or(any).unwrap_or(0.0);
+        let mut allowed: Vec<(Self, f32)> = [(Encoding::Brotli, br), (Encoding::Gzip, gzip)]
+            .into_iter()
+            .filter(|(_, q)| *q > 0.0)
+            .collect();
+        allowed.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        allowed.into_iter().map(|(encoding, _)| encoding).collect()
+    }
+
+    /// Compress `data` with this encoding
+    fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        use std::io::Write;
+
+        match self {
+            Encoding::Brotli => {
+                let mut out = Vec::new();
+                {
+                    let mut encoder = brotli::CompressorWriter::new(&mut out, 4096, 5, 22);
+                    encoder.write_all(data)?;
+                    encoder.flush()?;
+                }
+                Ok(out)
+            }
+            Encoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Compressed file body, valid while the file's size and mtime are unchanged
+#[derive(Debug, Clone)]
+struct CompressedVariant {
+    size: u64,
+    modified: SystemTime,
+    etag: Option<String>,
+    body: Arc<Vec<u8>>,
+    last_used: u64,
+}
+
+/// Bounded least-recently-used cache of compressed variants
+#[derive(Debug)]
+struct CompressionCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<(PathBuf, Encoding), CompressedVariant>,
+}
+
+impl CompressionCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Variant for `path`, dropping it if the file changed since it was cached
+    fn get(&mut self, path: &Path, encoding: Encoding, meta: &FileMetadata) -> Option<CompressedVariant> {
+        let key = (path.to_path_buf(), encoding);
+        let entry = self.entries.get_mut(&key)?;
+        if entry.size != meta.size || entry.modified != meta.modified {
+            self.entries.remove(&key);
+            return None;
+        }
+
+        self.tick += 1;
+        entry.last_used = self.tick;
+        Some(entry.clone())
+    }
+
+    /// Cache a variant, evicting the least recently used one when full
+    fn insert(&mut self, path: PathBuf, encoding: Encoding, mut variant: CompressedVariant) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (path, encoding);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        variant.last_used = self.tick;
+        self.entries.insert(key, variant);
+    }
+}
+
+/// Whether any component of `relative` below `root` is a symlink
+///
+/// `root` itself may be a symlink; only paths inside it are checked.
+async fn has_symlink_component(root: &Path, relative: &str) -> bool {
+    let mut path = root.to_path_buf();
+    for component in Path::new(relative).components() {
+        path.push(component);
+        if let Ok(meta) = tokio::fs::symlink_metadata(&path).await {
+            if meta.file_type().is_symlink() {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Content type for every file in an untrusted directory
+const UNTRUSTED_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Headers an untrusted directory sets itself and custom headers may not override
+fn is_untrusted_guard_header(name: &str) -> bool {
+    ["content-type", "content-disposition", "x-content-type-options"]
+        .iter()
+        .any(|guarded| name.eq_ignore_ascii_case(guarded))
+}
+
+/// Whether a content type benefits from compression
+///
+/// Formats that are already compressed (most images, audio, video, fonts
+/// and archives) are served as-is.
+fn is_compressible(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    if essence == "image/svg+xml" {
+        return true;
+    }
+    if essence.starts_with("image/") || essence.starts_with("video/") || essence.starts_with("audio/") {
+        return false;
+    }
+    !matches!(
+        essence,
+        "font/woff"
+            | "font/woff2"
+            | "application/zip"
+            | "application/gzip"
+            | "application/x-gzip"
+            | "application/x-brotli"
+            | "application/zstd"
+            | "application/x-7z-compressed"
+            | "application/x-rar-compressed"
+            | "application/x-bzip2"
+            | "application/x-xz"
+            | "application/pdf"
+    )
+}
+
+/// Outcome of [`StaticHandler::precompute_etags`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ETagIndexReport {
+    /// Files hashed and cached
+    pub indexed: usize,
+    /// Files over `precompute_max_file_size`, left to be hashed on demand
+    pub skipped: usize,
+}
+
 impl StaticHandler {
     /// Create a new static handler
     pub fn new<P: Into<PathBuf>>(prefix: &str, directory: P) -> Self {
-        Self {
-            prefix: prefix.to_string(),
-            directory: directory.into(),
-            options: StaticOptions::default(),
-        }
+        Self::new_with_options(prefix, directory, StaticOptions::default())
     }
 
     /// Create a new static handler with options
@@ -93,45 +476,169 @@ impl StaticHandler {
         directory: P,
         options: StaticOptions,
     ) -> Self {
+        let compressed_cache = CompressionCache::new(options.compression_cache_entries);
         Self {
             prefix: prefix.to_string(),
             directory: directory.into(),
             options,
+            etag_cache: Arc::new(RwLock::new(HashMap::new())),
+            hashed: Arc::new(AtomicUsize::new(0)),
+            compressed_cache: Arc::new(Mutex::new(compressed_cache)),
+            compressed: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Hash every file under the directory ahead of time
+    ///
+    /// With `ETagStrategy::Strong`, walks the tree once and caches each
+    /// file's ETag so first requests skip hashing. Files larger than
+    /// `precompute_max_file_size` are skipped; symlinks are not followed.
+    /// Does nothing for other strategies.
+    pub async fn precompute_etags(&self) -> Result<ETagIndexReport, ZapError> {
+        let mut report = ETagIndexReport::default();
+        if self.options.etag_strategy != ETagStrategy::Strong {
+            return Ok(report);
+        }
+
+        let mut pending = vec![self.directory.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let metadata = tokio::fs::symlink_metadata(&path).await?;
+                if metadata.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                if !metadata.is_file() {
+                    continue;
+                }
+                if metadata.len() > self.options.precompute_max_file_size {
+                    report.skipped += 1;
+                    continue;
+                }
+
+                let meta = FileMetadata {
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                };
+                if self.strong_etag(&meta, &path).await.is_some() {
+                    report.indexed += 1;
+                }
+            }
+        }
+
+        tracing::info!(
+            "Indexed {} static ETags under {} ({} skipped)",
+            report.indexed,
+            self.directory.display(),
+            report.skipped
+        );
+        Ok(report)
+    }
+
     /// Handle a static file request with conditional request support
     pub async fn handle(&self, path: &str) -> Result<Option<ZapResponse>, ZapError> {
         self.handle_with_headers(path, &HashMap::new()).await
     }
 
+    /// Map a request path naming a directory to its first existing index file
+    ///
+    /// Other paths are returned unchanged. A directory requested without its
+    /// trailing slash is redirected first, so relative links in its index
+    /// resolve against the directory rather than its parent.
+    async fn resolve_index(&self, path: &str, file_path: &str) -> IndexLookup {
+        let is_dir = file_path.is_empty()
+            || tokio::fs::metadata(self.directory.join(file_path))
+                .await
+                .map(|m| m.is_dir())
+                .unwrap_or(false);
+        if !is_dir {
+            return IndexLookup::File(file_path.to_string());
+        }
+        if !path.ends_with('/') {
+            return IndexLookup::Redirect;
+        }
+
+        let dir = file_path.trim_end_matches('/');
+        for index in &self.options.index_files {
+            let candidate = if dir.is_empty() {
+                index.clone()
+            } else {
+                format!("{}/{}", dir, index)
+            };
+            let found = tokio::fs::metadata(self.directory.join(&candidate))
+                .await
+                .map(|m| m.is_file())
+                .unwrap_or(false);
+            if found {
+                return IndexLookup::File(candidate);
+            }
+        }
+        IndexLookup::Missing
+    }
+
+    /// Forbidden response if `relative` escapes the root, or passes through
+    /// a symlink while symlinks are not followed
+    async fn deny_escape(&self, canonical_dir: &Path, relative: &str) -> Option<ZapResponse> {
+        if let Ok(canonical) = self.directory.join(relative).canonicalize() {
+            if !canonical.starts_with(canonical_dir) {
+                return Some(ZapResponse::Custom(Response::forbidden("Access denied")));
+            }
+        }
+        if !self.options.follow_symlinks && has_symlink_component(&self.directory, relative).await {
+            return Some(ZapResponse::Custom(Response::forbidden("Access denied")));
+        }
+        None
+    }
+
     /// Handle a static file request with request headers for conditional handling
+    ///
+    /// `path` may carry the request's query string, which is only used to
+    /// keep it on the redirect to a directory's trailing-slash form.
     pub async fn handle_with_headers(
         &self,
         path: &str,
         request_headers: &HashMap<String, String>,
     ) -> Result<Option<ZapResponse>, ZapError> {
+        let (path, query) = match path.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path, None),
+        };
         if !path.starts_with(&self.prefix) {
             return Ok(None);
         }
 
-        let file_path = path.strip_prefix(&self.prefix).unwrap_or("");
-        // Handle empty path or root
-        let file_path = if file_path.is_empty() || file_path == "/" {
-            "index.html"
-        } else {
-            file_path.trim_start_matches('/')
-        };
-        let full_path = self.directory.join(file_path);
+        let file_path = path.strip_prefix(&self.prefix).unwrap_or("").trim_start_matches('/');
 
-        // Security check: ensure path doesn't escape the directory
+        // Security check: ensure path doesn't escape the directory, before
+        // anything is learned about what it names
         let canonical_dir = self.directory.canonicalize().unwrap_or_else(|_| self.directory.clone());
-        let canonical_path = full_path.canonicalize();
+        if let Some(denied) = self.deny_escape(&canonical_dir, file_path).await {
+            return Ok(Some(denied));
+        }
 
-        if let Ok(canonical) = &canonical_path {
-            if !canonical.starts_with(&canonical_dir) {
-                return Ok(Some(ZapResponse::Custom(Response::forbidden("Access denied"))));
+        // The root and other directories are served by their index file
+        let file_path = match self.resolve_index(path, file_path).await {
+            IndexLookup::File(file_path) => file_path,
+            IndexLookup::Redirect => {
+                let location = match query {
+                    Some(query) => format!("{}/?{}", path, query),
+                    None => format!("{}/", path),
+                };
+                return Ok(Some(ZapResponse::Custom(Response::new().redirect_permanent(location))));
+            }
+            IndexLookup::Missing if self.options.directory_listing => {
+                return Ok(self.directory_listing(file_path, request_headers).await);
             }
+            IndexLookup::Missing => return Ok(None),
+        };
+        let file_path = file_path.as_str();
+        let full_path = self.directory.join(file_path);
+
+        // The index file itself may be a link out of the directory
+        if let Some(denied) = self.deny_escape(&canonical_dir, file_path).await {
+            return Ok(Some(denied));
         }
 
         // Get file metadata
@@ -145,8 +652,50 @@ impl StaticHandler {
             modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
         };
 
-        // Generate ETag if enabled
-        let etag = self.generate_etag(&file_meta, &full_path).await;
+        let content_type = if self.options.untrusted {
+            UNTRUSTED_CONTENT_TYPE.to_string()
+        } else {
+            mime_guess::from_path(&full_path)
+                .first_or_octet_stream()
+                .to_string()
+        };
+
+        let mut range = request_headers.get("range")
+            .or_else(|| request_headers.get("Range"))
+            .map(|header| parse_range(header, file_meta.size))
+            .unwrap_or(ByteRange::Full);
+
+        // Prefer a precompressed sibling, else pick an encoding for
+        // on-the-fly compression and reuse a cached variant (with its ETag)
+        // when the file is unchanged; ranges always refer to the identity
+        // encoding
+        let precompressed = match range {
+            ByteRange::Full => self.precompressed_sibling(&full_path, &canonical_dir, request_headers).await,
+            _ => None,
+        };
+        let encoding = match range {
+            ByteRange::Full if precompressed.is_none() => {
+                self.compression_encoding(&content_type, &file_meta, request_headers)
+            }
+            _ => None,
+        };
+        let cached = encoding.and_then(|encoding| {
+            self.compressed_cache.lock().unwrap().get(&full_path, encoding, &file_meta)
+        });
+
+        // Generate ETag if enabled; a compressed body, precompressed or not,
+        // is a representation of its own and gets its own ETag
+        let served_encoding = precompressed.as_ref().map(|(encoding, _)| *encoding).or(encoding);
+        let etag = match &cached {
+            Some(variant) => variant.etag.clone(),
+            None => self
+                .generate_etag(&file_meta, &full_path)
+                .await
+                .map(|etag| match served_encoding {
+                    Some(encoding) => encoded_etag(&etag, encoding),
+                    None => etag,
+                }),
+        };
 
         // Generate Last-Modified header value
         let last_modified = if self.options.enable_last_modified {
@@ -181,39 +730,69 @@ impl StaticHandler {
             }
         }
 
+        // If-Range: a stale validator means the client gets the whole file
+        if let Some(if_range) = request_headers.get("if-range")
+            .or_else(|| request_headers.get("If-Range"))
+        {
+            let if_range = if_range.trim();
+            let current = match &etag {
+                Some(etag_value) if !etag_value.starts_with("W/") => etag_value == if_range,
+                _ => last_modified.as_deref() == Some(if_range),
+            };
+            if !current {
+                range = ByteRange::Full;
+            }
+        }
+
+        match range {
+            ByteRange::Full => {}
+            ByteRange::Unsatisfiable => {
+                let response = Response::new()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header("Content-Range", format!("bytes */{}", file_meta.size));
+                return Ok(Some(ZapResponse::Custom(response)));
+            }
+            ByteRange::Partial(first, last) => {
+                return Ok(Some(
+                    self.partial_response(&full_path, (first, last), &file_meta, content_type, etag, last_modified)
+                        .await,
+                ));
+            }
+        }
+
+        // Serve a compressed variant, compressing on first request
+        let compressed = match (encoding, cached) {
+            (Some(encoding), Some(variant)) => Some((encoding, variant.body)),
+            (Some(encoding), None) => self
+                .compress_file(&full_path, encoding, &file_meta, &etag)
+                .await
+                .map(|body| (encoding, body)),
+            _ => None,
+        };
+
         // Read file and serve
-        match tokio::fs::read(&full_path).await {
+        let (content_encoding, contents) = match (precompressed, compressed) {
+            (Some((encoding, sibling)), _) => (Some(encoding), tokio::fs::read(&sibling).await),
+            (None, Some((encoding, body))) => (Some(encoding), Ok(body.as_ref().clone())),
+            (None, None) => (None, tokio::fs::read(&full_path).await),
+        };
+        match contents {
             Ok(contents) => {
-                let content_type = mime_guess::from_path(&full_path)
-                    .first_or_octet_stream()
-                    .to_string();
-
                 let mut response = Response::new()
                     .status(StatusCode::OK)
                     .content_type(content_type)
+                    .header("Accept-Ranges", "bytes")
                     .body(contents);
 
-                // Add cache control if specified
-                if let Some(cache_control) = &self.options.cache_control {
-                    response = response.cache_control(cache_control);
+                // Compressible responses vary by negotiated encoding
+                if encoding.is_some() || content_encoding.is_some() {
+                    response = response.header("Vary", "Accept-Encoding");
                 }
-
-                // Add ETag header
-                if let Some(etag_value) = etag {
-                    response = response.header("ETag", etag_value);
+                if let Some(encoding) = content_encoding {
+                    response = response.header("Content-Encoding", encoding.as_str());
                 }
 
-                // Add Last-Modified header
-                if let Some(last_mod) = last_modified {
-                    response = response.header("Last-Modified", last_mod);
-                }
-
-                // Add custom headers
-                for (key, value) in &self.options.headers {
-                    response = response.header(key, value);
-                }
-
-                Ok(Some(ZapResponse::Custom(response)))
+                Ok(Some(ZapResponse::Custom(self.file_headers(response, etag, last_modified))))
             }
             Err(_) => Ok(Some(ZapResponse::Custom(
                 Response::internal_server_error("Failed to read file"),
@@ -221,6 +800,271 @@ impl StaticHandler {
         }
     }
 
+    /// 206 response carrying bytes `first..=last` of the file
+    async fn partial_response(
+        &self,
+        path: &Path,
+        (first, last): (u64, u64),
+        meta: &FileMetadata,
+        content_type: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> ZapResponse {
+        match read_range(path, first, last).await {
+            Ok(contents) => {
+                let response = Response::new()
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .content_type(content_type)
+                    .header("Accept-Ranges", "bytes")
+                    .header("Content-Range", format!("bytes {}-{}/{}", first, last, meta.size))
+                    .body(contents);
+                ZapResponse::Custom(self.file_headers(response, etag, last_modified))
+            }
+            Err(_) => ZapResponse::Custom(Response::internal_server_error("Failed to read file")),
+        }
+    }
+
+    /// Caching, custom and untrusted-content headers shared by full and
+    /// partial file responses
+    fn file_headers(
+        &self,
+        mut response: Response,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Response {
+        // Add cache control if specified
+        if let Some(cache_control) = &self.options.cache_control {
+            response = response.cache_control(cache_control);
+        }
+
+        // Add ETag header
+        if let Some(etag_value) = etag {
+            response = response.header("ETag", etag_value);
+        }
+
+        // Add Last-Modified header
+        if let Some(last_mod) = last_modified {
+            response = response.header("Last-Modified", last_mod);
+        }
+
+        // Add custom headers; untrusted content keeps its safe
+        // type and disposition
+        for (key, value) in &self.options.headers {
+            if self.options.untrusted && is_untrusted_guard_header(key) {
+                continue;
+            }
+            response = response.header(key, value);
+        }
+
+        if self.options.untrusted {
+            response = response
+                .header("Content-Disposition", "attachment")
+                .header("X-Content-Type-Options", "nosniff");
+        }
+
+        response
+    }
+
+    /// Listing of a directory without an index file
+    ///
+    /// The directory must lie inside the root, and symlinked entries are
+    /// only listed when they are followed and resolve inside it. Dotfiles
+    /// are left out. The listing gets the same custom and untrusted-content
+    /// headers as files, but is never cached.
+    async fn directory_listing(
+        &self,
+        dir_path: &str,
+        request_headers: &HashMap<String, String>,
+    ) -> Option<ZapResponse> {
+        let dir_path = dir_path.trim_end_matches('/');
+        let canonical_dir = self.directory.canonicalize().ok()?;
+        let full_path = self.directory.join(dir_path);
+        if !full_path.canonicalize().ok()?.starts_with(&canonical_dir) {
+            return Some(ZapResponse::Custom(Response::forbidden("Access denied")));
+        }
+        if !self.options.follow_symlinks && has_symlink_component(&self.directory, dir_path).await {
+            return Some(ZapResponse::Custom(Response::forbidden("Access denied")));
+        }
+
+        let base = if dir_path.is_empty() {
+            format!("{}/", self.prefix.trim_end_matches('/'))
+        } else {
+            let encoded: Vec<String> = dir_path.split('/').map(encode_path_segment).collect();
+            format!("{}/{}/", self.prefix.trim_end_matches('/'), encoded.join("/"))
+        };
+
+        let mut entries = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(&full_path).await.ok()?;
+        while let Ok(Some(entry)) = read_dir.next_entry().await {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                let inside = path
+                    .canonicalize()
+                    .map(|target| target.starts_with(&canonical_dir))
+                    .unwrap_or(false);
+                if !self.options.follow_symlinks || !inside {
+                    continue;
+                }
+            }
+            let Ok(meta) = tokio::fs::metadata(&path).await else {
+                continue;
+            };
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let is_dir = meta.is_dir();
+            let href = format!("{}{}{}", base, encode_path_segment(&name), if is_dir { "/" } else { "" });
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            entries.push(ListingEntry {
+                name,
+                href,
+                is_dir,
+                size: if is_dir { 0 } else { meta.len() },
+                modified,
+            });
+        }
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        let wants_json = request_headers.get("accept")
+            .or_else(|| request_headers.get("Accept"))
+            .map(|accept| prefers_json(accept))
+            .unwrap_or(false);
+        let response = if wants_json {
+            let body = serde_json::json!({ "path": base, "entries": entries });
+            Response::new()
+                .status(StatusCode::OK)
+                .content_type("application/json")
+                .body(body.to_string())
+        } else {
+            let title = escape_html(&base);
+            let mut html = format!(
+                "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n<body>\n<h1>Index of {title}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n",
+            );
+            for entry in &entries {
+                let name = if entry.is_dir { format!("{}/", entry.name) } else { entry.name.clone() };
+                let size = if entry.is_dir { "-".to_string() } else { entry.size.to_string() };
+                let modified = format_http_date(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(entry.modified));
+                html.push_str(&format!(
+                    "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+                    escape_html(&entry.href),
+                    escape_html(&name),
+                    size,
+                    modified,
+                ));
+            }
+            html.push_str("</table>\n</body>\n</html>\n");
+            Response::new()
+                .status(StatusCode::OK)
+                .content_type("text/html; charset=utf-8")
+                .body(html)
+        };
+
+        let response = self.file_headers(response, None, None);
+        Some(ZapResponse::Custom(response.header("Vary", "Accept").header("Cache-Control", "no-cache")))
+    }
+
+    /// Precompressed sibling of `full_path` in an encoding the client
+    /// accepts, tried in the client's order of preference
+    ///
+    /// Siblings pass the same containment and symlink checks as the file.
+    async fn precompressed_sibling(
+        &self,
+        full_path: &Path,
+        canonical_dir: &Path,
+        request_headers: &HashMap<String, String>,
+    ) -> Option<(Encoding, PathBuf)> {
+        if !self.options.precompressed {
+            return None;
+        }
+        let accept_encoding = request_headers.get("accept-encoding")
+            .or_else(|| request_headers.get("Accept-Encoding"))?;
+
+        for encoding in Encoding::preferences(accept_encoding) {
+            let mut name = full_path.as_os_str().to_owned();
+            name.push(encoding.extension());
+            let sibling = PathBuf::from(name);
+
+            let Ok(meta) = tokio::fs::symlink_metadata(&sibling).await else {
+                continue;
+            };
+            if meta.file_type().is_symlink() && !self.options.follow_symlinks {
+                continue;
+            }
+            let contained = sibling
+                .canonicalize()
+                .map(|canonical| canonical.starts_with(canonical_dir))
+                .unwrap_or(false);
+            let is_file = tokio::fs::metadata(&sibling).await.map(|m| m.is_file()).unwrap_or(false);
+            if contained && is_file {
+                return Some((encoding, sibling));
+            }
+        }
+        None
+    }
+
+    /// Encoding to compress this response with, if on-the-fly compression
+    /// applies
+    fn compression_encoding(
+        &self,
+        content_type: &str,
+        meta: &FileMetadata,
+        request_headers: &HashMap<String, String>,
+    ) -> Option<Encoding> {
+        if !self.options.compress {
+            return None;
+        }
+        if meta.size == 0 || meta.size < self.options.compression_min_size || !is_compressible(content_type) {
+            return None;
+        }
+
+        let accept_encoding = request_headers.get("accept-encoding")
+            .or_else(|| request_headers.get("Accept-Encoding"))?;
+        Encoding::negotiate(accept_encoding)
+    }
+
+    /// Compress a file off the async runtime and cache the result
+    ///
+    /// Returns `None` if the file could not be read or compressed, in which
+    /// case it is served uncompressed.
+    async fn compress_file(
+        &self,
+        path: &Path,
+        encoding: Encoding,
+        meta: &FileMetadata,
+        etag: &Option<String>,
+    ) -> Option<Arc<Vec<u8>>> {
+        let owned = path.to_path_buf();
+        let body = tokio::task::spawn_blocking(move || encoding.compress(&std::fs::read(&owned)?))
+            .await
+            .ok()?
+            .ok()?;
+        let body = Arc::new(body);
+        self.compressed.fetch_add(1, Ordering::Relaxed);
+
+        self.compressed_cache.lock().unwrap().insert(
+            path.to_path_buf(),
+            encoding,
+            CompressedVariant {
+                size: meta.size,
+                modified: meta.modified,
+                etag: etag.clone(),
+                body: body.clone(),
+                last_used: 0,
+            },
+        );
+        Some(body)
+    }
+
     /// Generate ETag based on configured strategy
     async fn generate_etag(&self, meta: &FileMetadata, path: &PathBuf) -> Option<String> {
         match self.options.etag_strategy {
@@ -233,24 +1077,34 @@ impl StaticHandler {
                     .unwrap_or(0);
                 Some(format!("W/\"{:x}-{:x}\"", meta.size, mtime_secs))
             }
-            ETagStrategy::Strong => {
-                // Strong ETag using SHA256 hash of content
-                match tokio::fs::read(path).await {
-                    Ok(contents) => {
-                        use sha2::{Digest, Sha256};
-                        let mut hasher = Sha256::new();
-                        hasher.update(&contents);
-                        let hash = hasher.finalize();
-                        // Use first 16 bytes (32 hex chars) for reasonable length
-                        Some(format!("\"{}\"", hex::encode(&hash[..16])))
-                    }
-                    Err(_) => None,
-                }
-            }
+            ETagStrategy::Strong => self.strong_etag(meta, path).await,
             ETagStrategy::None => None,
         }
     }
 
+    /// Strong ETag from the cache, hashing the file if it changed or is new
+    async fn strong_etag(&self, meta: &FileMetadata, path: &Path) -> Option<String> {
+        if let Some(cached) = self.etag_cache.read().unwrap().get(path) {
+            if cached.size == meta.size && cached.modified == meta.modified {
+                return Some(cached.etag.clone());
+            }
+        }
+
+        let owned = path.to_path_buf();
+        let etag = tokio::task::spawn_blocking(move || hash_file(&owned)).await.ok()?.ok()?;
+        self.hashed.fetch_add(1, Ordering::Relaxed);
+
+        self.etag_cache.write().unwrap().insert(
+            path.to_path_buf(),
+            CachedETag {
+                size: meta.size,
+                modified: meta.modified,
+                etag: etag.clone(),
+            },
+        );
+        Some(etag)
+    }
+
     /// Generate a 304 Not Modified response
     fn not_modified_response(
         &self,
@@ -278,6 +1132,18 @@ impl StaticHandler {
     }
 }
 
+/// Strong ETag using SHA256 hash of content
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    let hash = hasher.finalize();
+    // Use first 16 bytes (32 hex chars) for reasonable length
+    Ok(format!("\"{}\"", hex::encode(&hash[..16])))
+}
+
 /// Handle static file requests from a list of handlers
 pub async fn handle_static_files(
     handlers: &[StaticHandler],
@@ -447,6 +1313,17 @@ fn ymd_to_days(year: i32, month: u32, day: u32) -> Option<i64> {
 
 /// Check if an If-None-Match header value matches an ETag
 /// Handles multiple ETags separated by commas and weak/strong comparison
+/// ETag of the `encoding` representation of a file whose ETag is `etag`
+///
+/// The encoding is appended inside the quotes, so a compressed body never
+/// shares an ETag with the file or with another encoding.
+fn encoded_etag(etag: &str, encoding: Encoding) -> String {
+    match etag.strip_suffix('"') {
+        Some(opaque) => format!("{}-{}\"", opaque, encoding.as_str()),
+        None => format!("{}-{}", etag, encoding.as_str()),
+    }
+}
+
 fn etags_match(if_none_match: &str, etag: &str) -> bool {
     // Handle wildcard
     if if_none_match.trim() == "*" {
@@ -495,6 +1372,7 @@ mod tests {
         assert!(opts.enable_last_modified);
         assert_eq!(opts.etag_strategy, ETagStrategy::Weak);
         assert_eq!(opts.cache_control, Some("public, max-age=3600".to_string()));
+        assert_eq!(opts.index_files, vec!["index.html".to_string()]);
     }
 
     #[test]
@@ -569,4 +1447,547 @@ mod tests {
         assert_eq!(handler.options.etag_strategy, ETagStrategy::Strong);
         assert!(!handler.options.enable_last_modified);
     }
-} 
\ No newline at end of file
+
+    fn strong_handler(dir: &Path, max_file_size: u64) -> StaticHandler {
+        let options = StaticOptions {
+            etag_strategy: ETagStrategy::Strong,
+            precompute_max_file_size: max_file_size,
+            ..Default::default()
+        };
+        StaticHandler::new_with_options("/assets", dir, options)
+    }
+
+    fn response_etag(response: Option<ZapResponse>) -> String {
+        match response {
+            Some(ZapResponse::Custom(response)) => response.headers.get("ETag").cloned().expect("ETag header"),
+            _ => panic!("Expected a file response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_precompute_etags_avoids_hashing_on_first_request() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("css")).unwrap();
+        std::fs::write(dir.path().join("app.js"), "console.log(1)").unwrap();
+        std::fs::write(dir.path().join("css/site.css"), "body {}").unwrap();
+        std::fs::write(dir.path().join("big.bin"), vec![0u8; 4096]).unwrap();
+
+        let handler = strong_handler(dir.path(), 1024);
+        let report = handler.precompute_etags().await.unwrap();
+        assert_eq!(report, ETagIndexReport { indexed: 2, skipped: 1 });
+        assert_eq!(handler.hashed.load(Ordering::Relaxed), 2);
+
+        let etag = response_etag(handler.handle("/assets/css/site.css").await.unwrap());
+        handler.handle("/assets/app.js").await.unwrap();
+        assert_eq!(handler.hashed.load(Ordering::Relaxed), 2, "served file was hashed again");
+        assert_eq!(etag, hash_file(&dir.path().join("css/site.css")).unwrap());
+
+        // Files over the cap are hashed on demand, once
+        handler.handle("/assets/big.bin").await.unwrap();
+        handler.handle("/assets/big.bin").await.unwrap();
+        assert_eq!(handler.hashed.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cached_etag_refreshed_when_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.html");
+        std::fs::write(&path, "v1").unwrap();
+
+        let handler = strong_handler(dir.path(), 1024);
+        handler.precompute_etags().await.unwrap();
+        let before = response_etag(handler.handle("/assets/index.html").await.unwrap());
+
+        std::fs::write(&path, "version 2").unwrap();
+        let after = response_etag(handler.handle("/assets/index.html").await.unwrap());
+        assert_ne!(before, after);
+    }
+
+    #[tokio::test]
+    async fn test_precompute_etags_skipped_for_weak_strategy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+
+        let handler = StaticHandler::new("/assets", dir.path());
+        assert_eq!(handler.precompute_etags().await.unwrap(), ETagIndexReport::default());
+    }
+
+    fn compressing_handler(dir: &Path, entries: usize) -> StaticHandler {
+        let options = StaticOptions {
+            compression_cache_entries: entries,
+            compression_min_size: 0,
+            ..Default::default()
+        };
+        StaticHandler::new_with_options("/assets", dir, options)
+    }
+
+    fn accept(encoding: &str) -> HashMap<String, String> {
+        HashMap::from([("accept-encoding".to_string(), encoding.to_string())])
+    }
+
+    fn response_parts(response: Option<ZapResponse>) -> (Option<String>, Vec<u8>) {
+        match response {
+            Some(ZapResponse::Custom(response)) => {
+                let body = match response.body {
+                    zap_core::ResponseBody::Bytes(bytes) => bytes,
+                    other => panic!("Expected a byte body, got {:?}", other),
+                };
+                (response.headers.get("Content-Encoding").cloned(), body)
+            }
+            _ => panic!("Expected a file response"),
+        }
+    }
+
+    fn gunzip(data: &[u8]) -> String {
+        use std::io::Read;
+        let mut out = String::new();
+        flate2::read::GzDecoder::new(data).read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_accept_encoding_negotiation() {
+        assert_eq!(Encoding::negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+        assert_eq!(Encoding::negotiate("gzip"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("br;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("br;q=0.5, gzip;q=0.8"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("*"), Some(Encoding::Brotli));
+        assert_eq!(Encoding::negotiate("identity"), None);
+        assert_eq!(Encoding::negotiate("gzip;q=0"), None);
+        assert_eq!(Encoding::preferences("gzip, br"), vec![Encoding::Brotli, Encoding::Gzip]);
+        assert_eq!(Encoding::preferences("br;q=0.2, gzip"), vec![Encoding::Gzip, Encoding::Brotli]);
+    }
+
+    #[test]
+    fn test_already_compressed_types_skipped() {
+        assert!(is_compressible("text/html; charset=utf-8"));
+        assert!(is_compressible("application/javascript"));
+        assert!(is_compressible("image/svg+xml"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("video/mp4"));
+        assert!(!is_compressible("font/woff2"));
+        assert!(!is_compressible("application/zip"));
+    }
+
+    #[tokio::test]
+    async fn test_first_request_compresses_and_second_hits_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = "body { color: red; }\n".repeat(100);
+        std::fs::write(dir.path().join("site.css"), &text).unwrap();
+        std::fs::write(dir.path().join("logo.png"), vec![7u8; 2048]).unwrap();
+
+        let handler = compressing_handler(dir.path(), 8);
+        let (encoding, first) = response_parts(handler.handle_with_headers("/assets/site.css", &accept("gzip")).await.unwrap());
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert!(first.len() < text.len());
+        assert_eq!(gunzip(&first), text);
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 1);
+
+        let (_, second) = response_parts(handler.handle_with_headers("/assets/site.css", &accept("gzip")).await.unwrap());
+        assert_eq!(second, first);
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 1, "cached variant was recompressed");
+
+        // Each encoding is cached separately
+        let (encoding, _) = response_parts(handler.handle_with_headers("/assets/site.css", &accept("br")).await.unwrap());
+        assert_eq!(encoding.as_deref(), Some("br"));
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 2);
+
+        // Images are already compressed; clients without Accept-Encoding get identity
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/logo.png", &accept("gzip")).await.unwrap());
+        assert_eq!((encoding, body.len()), (None, 2048));
+        let (encoding, body) = response_parts(handler.handle("/assets/site.css").await.unwrap());
+        assert_eq!((encoding, body.len()), (None, text.len()));
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_default_options_compress_json_but_not_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = format!("[{}]", vec!["{\"id\": 1, \"name\": \"zap\"}"; 100].join(","));
+        std::fs::write(dir.path().join("data.json"), &json).unwrap();
+        std::fs::write(dir.path().join("tiny.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("logo.png"), vec![7u8; 4096]).unwrap();
+        let handler = StaticHandler::new("/assets", dir.path());
+
+        let strong = StaticHandler::new_with_options(
+            "/assets",
+            dir.path(),
+            StaticOptions { etag_strategy: ETagStrategy::Strong, ..Default::default() },
+        );
+        let response = match strong.handle_with_headers("/assets/data.json", &accept("gzip")).await.unwrap() {
+            Some(ZapResponse::Custom(response)) => response,
+            _ => panic!("Expected a file response"),
+        };
+        assert_eq!(response.headers.get("Content-Encoding").map(String::as_str), Some("gzip"));
+        assert_eq!(response.headers.get("Vary").map(String::as_str), Some("Accept-Encoding"));
+        let compressed_len: usize = response.headers.get("Content-Length").unwrap().parse().unwrap();
+        assert!(compressed_len < json.len());
+        // The compressed body has its own ETag, derived from the file's
+        let identity_etag = response_etag(strong.handle("/assets/data.json").await.unwrap());
+        let gzip_etag = response.headers.get("ETag").unwrap().clone();
+        assert_eq!(gzip_etag, encoded_etag(&identity_etag, Encoding::Gzip));
+        assert!(gzip_etag.ends_with("-gzip\"") && !gzip_etag.starts_with("W/"));
+        let br = strong.handle_with_headers("/assets/data.json", &accept("br")).await.unwrap();
+        assert_eq!(response_etag(br), encoded_etag(&identity_etag, Encoding::Brotli));
+
+        // A validator only revalidates the representation it came from
+        let revalidate = |etag: &str| {
+            let mut headers = accept("gzip");
+            headers.insert("if-none-match".to_string(), etag.to_string());
+            headers
+        };
+        let not_modified = strong.handle_with_headers("/assets/data.json", &revalidate(&gzip_etag)).await.unwrap();
+        assert!(matches!(not_modified, Some(ZapResponse::Custom(r)) if r.status == StatusCode::NOT_MODIFIED));
+        let (encoding, _) = response_parts(strong.handle_with_headers("/assets/data.json", &revalidate(&identity_etag)).await.unwrap());
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/data.json", &accept("gzip")).await.unwrap());
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert_eq!(gunzip(&body), json);
+
+        // Images are already compressed and small files are not worth it
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/logo.png", &accept("gzip")).await.unwrap());
+        assert_eq!((encoding, body.len()), (None, 4096));
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/tiny.json", &accept("gzip")).await.unwrap());
+        assert_eq!((encoding, body), (None, b"{}".to_vec()));
+
+        let uncompressed = StaticHandler::new_with_options(
+            "/assets",
+            dir.path(),
+            StaticOptions { compress: false, ..Default::default() },
+        );
+        let (encoding, _) = response_parts(uncompressed.handle_with_headers("/assets/data.json", &accept("gzip")).await.unwrap());
+        assert_eq!(encoding, None);
+    }
+
+    #[tokio::test]
+    async fn test_precompressed_gz_sibling_served_when_present() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = "export const answer = 42;\n".repeat(100);
+        std::fs::write(dir.path().join("app.js"), &text).unwrap();
+        std::fs::write(dir.path().join("other.js"), &text).unwrap();
+        let sibling = Encoding::Gzip.compress(b"precompressed").unwrap();
+        std::fs::write(dir.path().join("app.js.gz"), &sibling).unwrap();
+
+        let options = StaticOptions {
+            compress: false,
+            ..Default::default()
+        };
+        let handler = StaticHandler::new_with_options("/assets", dir.path(), options);
+        let identity = match handler.handle("/assets/app.js").await.unwrap() {
+            Some(ZapResponse::Custom(response)) => response,
+            _ => panic!("Expected a file response"),
+        };
+
+        let response = match handler.handle_with_headers("/assets/app.js", &accept("br, gzip")).await.unwrap() {
+            Some(ZapResponse::Custom(response)) => response,
+            _ => panic!("Expected a file response"),
+        };
+        assert_eq!(response.headers.get("Content-Encoding").map(String::as_str), Some("gzip"));
+        assert_eq!(response.headers.get("Content-Type"), identity.headers.get("Content-Type"));
+        let identity_etag = identity.headers.get("ETag").unwrap();
+        assert_eq!(response.headers.get("ETag"), Some(&encoded_etag(identity_etag, Encoding::Gzip)));
+        assert_eq!(response.headers.get("Last-Modified"), identity.headers.get("Last-Modified"));
+        let (_, body) = response_parts(Some(ZapResponse::Custom(response)));
+        assert_eq!(gunzip(&body), "precompressed");
+
+        // No sibling, or a client that does not accept gzip: the file itself
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/other.js", &accept("br, gzip")).await.unwrap());
+        assert_eq!((encoding, body), (None, text.as_bytes().to_vec()));
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/app.js", &accept("br")).await.unwrap());
+        assert_eq!((encoding, body), (None, text.as_bytes().to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_compressed_variant_invalidated_when_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.js");
+        std::fs::write(&path, "console.log('v1');").unwrap();
+
+        let handler = compressing_handler(dir.path(), 8);
+        let (_, before) = response_parts(handler.handle_with_headers("/assets/app.js", &accept("gzip")).await.unwrap());
+        assert_eq!(gunzip(&before), "console.log('v1');");
+
+        std::fs::write(&path, "console.log('version 2');").unwrap();
+        let (_, after) = response_parts(handler.handle_with_headers("/assets/app.js", &accept("gzip")).await.unwrap());
+        assert_eq!(gunzip(&after), "console.log('version 2');");
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_compression_cache_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            std::fs::write(dir.path().join(name), name.repeat(64)).unwrap();
+        }
+
+        let handler = compressing_handler(dir.path(), 2);
+        let gzip = accept("gzip");
+        handler.handle_with_headers("/assets/a.txt", &gzip).await.unwrap();
+        handler.handle_with_headers("/assets/b.txt", &gzip).await.unwrap();
+        handler.handle_with_headers("/assets/a.txt", &gzip).await.unwrap();
+        // b.txt is now least recently used and makes room for c.txt
+        handler.handle_with_headers("/assets/c.txt", &gzip).await.unwrap();
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 3);
+
+        handler.handle_with_headers("/assets/a.txt", &gzip).await.unwrap();
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 3);
+        handler.handle_with_headers("/assets/b.txt", &gzip).await.unwrap();
+        assert_eq!(handler.compressed.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_html_served_as_attachment() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("upload.html"), "<script>alert(1)</script>").unwrap();
+        let mut options = StaticOptions {
+            untrusted: true,
+            ..Default::default()
+        };
+        options.headers.insert("content-type".to_string(), "text/html".to_string());
+        let handler = StaticHandler::new_with_options("/uploads", dir.path(), options);
+
+        let response = match handler.handle("/uploads/upload.html").await.unwrap() {
+            Some(ZapResponse::Custom(response)) => response,
+            _ => panic!("Expected a file response"),
+        };
+        assert_eq!(response.headers.get("Content-Type").map(String::as_str), Some("application/octet-stream"));
+        assert_eq!(response.headers.get("Content-Disposition").map(String::as_str), Some("attachment"));
+        assert_eq!(response.headers.get("X-Content-Type-Options").map(String::as_str), Some("nosniff"));
+        assert!(!response.headers.contains_key("content-type"));
+
+        // Trusted mounts still use the extension
+        let trusted = StaticHandler::new("/assets", dir.path());
+        match trusted.handle("/assets/upload.html").await.unwrap() {
+            Some(ZapResponse::Custom(response)) => {
+                assert_eq!(response.headers.get("Content-Type").map(String::as_str), Some("text/html"));
+                assert!(!response.headers.contains_key("Content-Disposition"));
+            }
+            _ => panic!("Expected a file response"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_inside_directory_follows_option() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("real")).unwrap();
+        std::fs::write(dir.path().join("real/app.js"), "console.log(1)").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("real/app.js"), dir.path().join("link.js")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("linked")).unwrap();
+
+        let status = |response: Option<ZapResponse>| match response {
+            Some(ZapResponse::Custom(response)) => response.status.as_u16(),
+            _ => panic!("Expected a response"),
+        };
+
+        let following = StaticHandler::new("/assets", dir.path());
+        assert_eq!(status(following.handle("/assets/link.js").await.unwrap()), 200);
+        assert_eq!(status(following.handle("/assets/linked/app.js").await.unwrap()), 200);
+
+        let options = StaticOptions {
+            follow_symlinks: false,
+            ..Default::default()
+        };
+        let strict = StaticHandler::new_with_options("/assets", dir.path(), options);
+        assert_eq!(status(strict.handle("/assets/link.js").await.unwrap()), 403);
+        assert_eq!(status(strict.handle("/assets/linked/app.js").await.unwrap()), 403);
+        assert_eq!(status(strict.handle("/assets/real/app.js").await.unwrap()), 200);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_directory_listing_html_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/guide.txt"), "guide").unwrap();
+        std::fs::write(dir.path().join("docs/a <b>.txt"), "12345").unwrap();
+        std::fs::create_dir(dir.path().join("docs/nested")).unwrap();
+        std::fs::write(dir.path().join("docs/.env"), "SECRET=1").unwrap();
+        std::fs::create_dir(dir.path().join("docs/.git")).unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret.txt"), dir.path().join("docs/escape.txt")).unwrap();
+
+        let options = StaticOptions {
+            directory_listing: true,
+            cache_control: Some("public, max-age=3600".to_string()),
+            headers: HashMap::from([("X-Frame-Options".to_string(), "DENY".to_string())]),
+            ..Default::default()
+        };
+        let handler = StaticHandler::new_with_options("/files", dir.path(), options);
+
+        let listing = custom(handler.handle("/files/docs/").await.unwrap());
+        assert_eq!(listing.headers.get("X-Frame-Options").map(String::as_str), Some("DENY"));
+        assert_eq!(listing.headers.get("Cache-Control").map(String::as_str), Some("no-cache"));
+
+        let (_, html) = response_parts(handler.handle("/files/docs/").await.unwrap());
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("<a href=\"/files/docs/guide.txt\">guide.txt</a>"));
+        assert!(html.contains("<a href=\"/files/docs/a%20%3Cb%3E.txt\">a &lt;b&gt;.txt</a>"));
+        assert!(html.contains("<a href=\"/files/docs/nested/\">nested/</a>"));
+        assert!(!html.contains("escape.txt"), "symlink escaping the root was listed");
+        assert!(!html.contains(".env") && !html.contains(".git"), "dotfiles were listed");
+
+        let headers = HashMap::from([("accept".to_string(), "application/json".to_string())]);
+        let (_, json) = response_parts(handler.handle_with_headers("/files/docs/", &headers).await.unwrap());
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let names: Vec<&str> = json["entries"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["nested", "a <b>.txt", "guide.txt"]);
+        assert_eq!(json["entries"][2]["size"], 5);
+        assert_eq!(json["entries"][2]["href"], "/files/docs/guide.txt");
+
+        // Traversal is still refused, and listings stay off by default
+        let escaped = handler.handle("/files/docs/../..").await.unwrap();
+        assert!(matches!(escaped, Some(ZapResponse::Custom(ref r)) if r.status.as_u16() == 403));
+        assert!(StaticHandler::new("/files", dir.path()).handle("/files/docs/").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_index_files_tried_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("legacy")).unwrap();
+        std::fs::write(dir.path().join("legacy/index.htm"), "legacy").unwrap();
+        std::fs::create_dir(dir.path().join("both")).unwrap();
+        std::fs::write(dir.path().join("both/index.htm"), "htm").unwrap();
+        std::fs::write(dir.path().join("both/index.html"), "html").unwrap();
+        std::fs::create_dir(dir.path().join("empty")).unwrap();
+
+        let options = StaticOptions {
+            compress: false,
+            index_files: vec!["index.htm".to_string(), "index.html".to_string()],
+            ..Default::default()
+        };
+        let handler = StaticHandler::new_with_options("/site", dir.path(), options);
+        let body = |response| response_parts(response).1;
+
+        assert_eq!(body(handler.handle("/site/legacy/").await.unwrap()), b"legacy");
+        assert_eq!(body(handler.handle("/site/both/").await.unwrap()), b"htm");
+        assert!(handler.handle("/site/empty/").await.unwrap().is_none());
+
+        // The default list only knows index.html
+        let default = StaticHandler::new("/site", dir.path());
+        assert!(default.handle("/site/legacy/").await.unwrap().is_none());
+        assert_eq!(body(default.handle("/site/both/").await.unwrap()), b"html");
+    }
+
+    #[tokio::test]
+    async fn test_directory_without_slash_redirects() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/index.html"), "docs").unwrap();
+        let handler = StaticHandler::new("/site", dir.path());
+
+        let response = custom(handler.handle("/site/docs").await.unwrap());
+        assert_eq!(response.status.as_u16(), 301);
+        assert_eq!(response.headers.get("Location").map(String::as_str), Some("/site/docs/"));
+
+        // The query string survives the redirect
+        let response = custom(handler.handle("/site/docs?lang=en&page=2").await.unwrap());
+        assert_eq!(response.headers.get("Location").map(String::as_str), Some("/site/docs/?lang=en&page=2"));
+
+        // Escapes are refused before the target is looked at
+        let escaped = custom(handler.handle("/site/docs/../..").await.unwrap());
+        assert_eq!(escaped.status.as_u16(), 403);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(parse_range("bytes=900-2000", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(parse_range("bytes=-100", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(parse_range("bytes=-5000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-0", 0), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-10,20-30", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=50-10", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-10", 1000), ByteRange::Full);
+    }
+
+    fn range(value: &str) -> HashMap<String, String> {
+        HashMap::from([("range".to_string(), value.to_string())])
+    }
+
+    fn custom(response: Option<ZapResponse>) -> Response {
+        match response {
+            Some(ZapResponse::Custom(response)) => response,
+            _ => panic!("Expected a file response"),
+        }
+    }
+
+    fn body_bytes(response: &Response) -> &[u8] {
+        match &response.body {
+            zap_core::ResponseBody::Bytes(bytes) => bytes,
+            other => panic!("Expected a byte body, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_range_request_returns_partial_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(dir.path().join("video.bin"), &data).unwrap();
+        let handler = compressing_handler(dir.path(), 4);
+
+        let response = custom(handler.handle_with_headers("/assets/video.bin", &range("bytes=200-299")).await.unwrap());
+        assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers.get("Content-Range").unwrap(), "bytes 200-299/1000");
+        assert_eq!(response.headers.get("Content-Length").unwrap(), "100");
+        assert_eq!(body_bytes(&response), &data[200..300]);
+
+        let response = custom(handler.handle_with_headers("/assets/video.bin", &range("bytes=100-")).await.unwrap());
+        assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers.get("Content-Range").unwrap(), "bytes 100-999/1000");
+        assert_eq!(body_bytes(&response), &data[100..]);
+
+        // Multiple ranges fall back to the whole file
+        let response = custom(handler.handle_with_headers("/assets/video.bin", &range("bytes=0-1,5-6")).await.unwrap());
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.headers.get("Accept-Ranges").unwrap(), "bytes");
+        assert_eq!(body_bytes(&response), &data[..]);
+    }
+
+    #[tokio::test]
+    async fn test_range_skips_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = "const value = 1;\n".repeat(100);
+        std::fs::write(dir.path().join("app.js"), &text).unwrap();
+        let handler = compressing_handler(dir.path(), 4);
+
+        let mut headers = accept("gzip");
+        headers.extend(range("bytes=0-9"));
+        let (encoding, body) = response_parts(handler.handle_with_headers("/assets/app.js", &headers).await.unwrap());
+        assert_eq!(encoding, None);
+        assert_eq!(body, text.as_bytes()[..10]);
+    }
+
+    #[tokio::test]
+    async fn test_unsatisfiable_range_returns_416() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("small.txt"), "hello").unwrap();
+        let handler = compressing_handler(dir.path(), 0);
+
+        let response = custom(handler.handle_with_headers("/assets/small.txt", &range("bytes=10-20")).await.unwrap());
+        assert_eq!(response.status, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers.get("Content-Range").unwrap(), "bytes */5");
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_range_serves_full_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("doc.txt"), "0123456789").unwrap();
+        let handler = strong_handler(dir.path(), 1024);
+
+        let etag = response_etag(handler.handle("/assets/doc.txt").await.unwrap());
+        let mut headers = range("bytes=2-4");
+        headers.insert("if-range".to_string(), etag);
+        let response = custom(handler.handle_with_headers("/assets/doc.txt", &headers).await.unwrap());
+        assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body_bytes(&response), b"234");
+
+        headers.insert("if-range".to_string(), "\"stale\"".to_string());
+        let response = custom(handler.handle_with_headers("/assets/doc.txt", &headers).await.unwrap());
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(body_bytes(&response), b"0123456789");
+    }
+}
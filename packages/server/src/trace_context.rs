@@ -0,0 +1,314 @@
+//! W3C Trace Context propagation
+//!
+//! Carries a distributed trace across the HTTP → IPC → Splice boundary so a
+//! single trace covers the HTTP request, the TypeScript handler and the Rust
+//! function invocation.
+//!
+//! The `traceparent` header is the source of truth and is forwarded verbatim
+//! in `RequestContext::headers` so the full 128-bit trace ID survives the hop.
+//! `RequestContext::trace_id` holds the low 64 bits of the trace ID and
+//! `RequestContext::span_id` holds the ID of the current span.
+//!
+//! Format: `{version}-{trace_id}-{parent_id}-{flags}`
+//! e.g. `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+
+use splice::protocol::RequestContext;
+use std::collections::HashMap;
+
+/// Standard header name for the W3C trace parent
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Standard header name for vendor-specific trace state
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Trace flag bit indicating the trace is sampled
+pub const FLAG_SAMPLED: u8 = 0x01;
+
+/// Parsed W3C trace context for a single span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 128-bit trace identifier shared by every span in the trace
+    pub trace_id: u128,
+    /// 64-bit identifier of the current span
+    pub span_id: u64,
+    /// Trace flags (bit 0 = sampled)
+    pub flags: u8,
+    /// Opaque vendor trace state, forwarded unchanged
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Start a new root trace with random IDs
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: non_zero_u128(),
+            span_id: non_zero_u64(),
+            flags: FLAG_SAMPLED,
+            tracestate: None,
+        }
+    }
+
+    /// Create a child span within the same trace
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id,
+            span_id: non_zero_u64(),
+            flags: self.flags,
+            tracestate: self.tracestate.clone(),
+        }
+    }
+
+    /// Whether the sampled flag is set
+    pub fn is_sampled(&self) -> bool {
+        self.flags & FLAG_SAMPLED != 0
+    }
+
+    /// Format as a `traceparent` header value
+    pub fn to_traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+
+    /// Write `traceparent` (and `tracestate` if present) into a header map,
+    /// replacing any existing values regardless of case
+    pub fn inject(&self, headers: &mut HashMap<String, String>) {
+        headers.retain(|k, _| {
+            !k.eq_ignore_ascii_case(TRACEPARENT_HEADER) && !k.eq_ignore_ascii_case(TRACESTATE_HEADER)
+        });
+        headers.insert(TRACEPARENT_HEADER.to_string(), self.to_traceparent());
+        if let Some(state) = &self.tracestate {
+            headers.insert(TRACESTATE_HEADER.to_string(), state.clone());
+        }
+    }
+
+    /// Build a Splice `RequestContext` carrying this span
+    ///
+    /// Existing `traceparent`/`tracestate` entries in `headers` are replaced.
+    pub fn to_request_context(&self, headers: Vec<(String, String)>) -> RequestContext {
+        let mut context = RequestContext::builder()
+            .trace_id(self.trace_id as u64)
+            .span_id(self.span_id)
+            .headers(headers)
+            .traceparent(self.to_traceparent())
+            .build();
+
+        context.remove_header(TRACESTATE_HEADER);
+        if let Some(state) = &self.tracestate {
+            context.set_header(TRACESTATE_HEADER, state.clone());
+        }
+        context
+    }
+
+    /// Recover the trace context from a Splice `RequestContext`
+    ///
+    /// Prefers the forwarded `traceparent` header; falls back to the numeric
+    /// `trace_id`/`span_id` fields when they are non-zero.
+    pub fn from_request_context(context: &RequestContext) -> Option<Self> {
+        if let Some(mut parsed) = context.traceparent().and_then(parse_traceparent) {
+            parsed.tracestate = context.tracestate().map(str::to_string);
+            return Some(parsed);
+        }
+
+        if context.trace_id != 0 && context.span_id != 0 {
+            return Some(Self {
+                trace_id: context.trace_id as u128,
+                span_id: context.span_id,
+                flags: FLAG_SAMPLED,
+                tracestate: None,
+            });
+        }
+
+        None
+    }
+}
+
+/// Parse a `traceparent` header value
+///
+/// Returns `None` for malformed values, the reserved version `ff`, and
+/// all-zero trace or parent IDs, as required by the W3C specification.
+pub fn parse_traceparent(value: &str) -> Option<TraceContext> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if version.len() != 2 || trace_id.len() != 32 || parent_id.len() != 16 || flags.len() != 2 {
+        return None;
+    }
+    // Version 00 defines exactly four fields; later versions may append more
+    if version == "00" && parts.next().is_some() {
+        return None;
+    }
+
+    let is_lower_hex = |s: &str| s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    if ![version, trace_id, parent_id, flags].iter().all(|s| is_lower_hex(s)) {
+        return None;
+    }
+    if version == "ff" {
+        return None;
+    }
+
+    let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
+    let span_id = u64::from_str_radix(parent_id, 16).ok()?;
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+
+    if trace_id == 0 || span_id == 0 {
+        return None;
+    }
+
+    Some(TraceContext {
+        trace_id,
+        span_id,
+        flags,
+        tracestate: None,
+    })
+}
+
+/// Extract the trace context from HTTP headers (case-insensitive)
+pub fn extract(headers: &HashMap<String, String>) -> Option<TraceContext> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+
+    let mut context = header(TRACEPARENT_HEADER).and_then(parse_traceparent)?;
+    context.tracestate = header(TRACESTATE_HEADER)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    Some(context)
+}
+
+/// Extract the trace context from HTTP headers or start a new root trace
+pub fn get_or_generate(headers: &HashMap<String, String>) -> TraceContext {
+    extract(headers).unwrap_or_else(TraceContext::new_root)
+}
+
+fn non_zero_u64() -> u64 {
+    loop {
+        let id = fastrand::u64(..);
+        if id != 0 {
+            return id;
+        }
+    }
+}
+
+fn non_zero_u128() -> u128 {
+    loop {
+        let id = fastrand::u128(..);
+        if id != 0 {
+            return id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+
+    const SAMPLE: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_parse_traceparent() {
+        let ctx = parse_traceparent(SAMPLE).unwrap();
+        assert_eq!(ctx.trace_id, 0x4bf92f3577b34da6a3ce929d0e0e4736);
+        assert_eq!(ctx.span_id, 0x00f067aa0ba902b7);
+        assert!(ctx.is_sampled());
+        assert_eq!(ctx.to_traceparent(), SAMPLE);
+    }
+
+    #[test]
+    fn test_parse_traceparent_rejects_invalid() {
+        assert!(parse_traceparent("").is_none());
+        assert!(parse_traceparent("garbage").is_none());
+        // All-zero trace ID
+        assert!(parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        // All-zero parent ID
+        assert!(parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01").is_none());
+        // Reserved version
+        assert!(parse_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        // Uppercase hex is not allowed
+        assert!(parse_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+        // Extra fields in version 00
+        assert!(parse_traceparent(&format!("{}-extra", SAMPLE)).is_none());
+    }
+
+    #[test]
+    fn test_get_or_generate_creates_root() {
+        let headers = HashMap::new();
+        let ctx = get_or_generate(&headers);
+        assert_ne!(ctx.trace_id, 0);
+        assert_ne!(ctx.span_id, 0);
+        assert!(parse_traceparent(&ctx.to_traceparent()).is_some());
+    }
+
+    #[test]
+    fn test_child_keeps_trace_id() {
+        let parent = parse_traceparent(SAMPLE).unwrap();
+        let child = parent.child();
+        assert_eq!(child.trace_id, parent.trace_id);
+        assert_ne!(child.span_id, parent.span_id);
+        assert_eq!(child.flags, parent.flags);
+    }
+
+    #[test]
+    fn test_inject_replaces_existing_headers() {
+        let mut headers = HashMap::new();
+        headers.insert("Traceparent".to_string(), "stale".to_string());
+        let ctx = parse_traceparent(SAMPLE).unwrap();
+        ctx.inject(&mut headers);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get(TRACEPARENT_HEADER).map(String::as_str), Some(SAMPLE));
+    }
+
+    #[test]
+    fn test_incoming_traceparent_propagates_to_worker() {
+        // HTTP request arrives with a traceparent
+        let mut http_headers = HashMap::new();
+        http_headers.insert("Traceparent".to_string(), SAMPLE.to_string());
+        http_headers.insert("tracestate".to_string(), "vendor=abc".to_string());
+
+        // Proxy continues the trace and builds the Splice RequestContext
+        let proxy_span = get_or_generate(&http_headers).child();
+        let request_context = proxy_span.to_request_context(vec![]);
+
+        // Worker recovers the trace and wraps it in the user-facing Context
+        let worker_span = TraceContext::from_request_context(&request_context).unwrap();
+        let ctx = Context::new(request_context);
+
+        let incoming = parse_traceparent(SAMPLE).unwrap();
+        assert_eq!(worker_span.trace_id, incoming.trace_id);
+        assert_eq!(worker_span.span_id, proxy_span.span_id);
+        assert_eq!(worker_span.tracestate.as_deref(), Some("vendor=abc"));
+        assert_eq!(ctx.trace_id(), incoming.trace_id as u64);
+        assert_eq!(ctx.span_id(), proxy_span.span_id);
+        assert!(ctx
+            .header(TRACEPARENT_HEADER)
+            .unwrap()
+            .contains("4bf92f3577b34da6a3ce929d0e0e4736"));
+    }
+
+    #[test]
+    fn test_from_request_context_numeric_fallback() {
+        let context = RequestContext {
+            trace_id: 42,
+            span_id: 7,
+            headers: vec![],
+            auth: None,
+        };
+        let ctx = TraceContext::from_request_context(&context).unwrap();
+        assert_eq!(ctx.trace_id, 42);
+        assert_eq!(ctx.span_id, 7);
+
+        let empty = RequestContext {
+            trace_id: 0,
+            span_id: 0,
+            headers: vec![],
+            auth: None,
+        };
+        assert!(TraceContext::from_request_context(&empty).is_none());
+    }
+}
@@ -16,16 +16,46 @@
 //! - WsMessage: Message received from client (Rust -> TS)
 //! - WsSend: Message to send to client (TS -> Rust)
 //! - WsClose: Connection closed (bidirectional)
+//!
+//! Connection Limit:
+//! With `max_connections` set, upgrades beyond the limit are refused with
+//! HTTP 503 before the handshake completes. The active count is shared by
+//! every clone of the handler's [`WsConfig`].
+//!
+//! Message Size:
+//! Messages larger than `max_message_size` are rejected by the WebSocket
+//! protocol layer; the client is closed with 1009 (Message Too Big) and
+//! TypeScript receives `WsClose`. Nothing oversized is forwarded over IPC.
+//!
+//! Keepalive:
+//! Every `ping_interval_secs` the server pings the client. A client that has
+//! not answered with a pong for two intervals is treated as dead (e.g. a
+//! half-open TCP connection): it is closed and TypeScript receives `WsClose`.
+//!
+//! Replay Protection:
+//! With [`WsConfig::dedup`] set, text messages carrying a client-supplied id
+//! (e.g. `{"messageId": "m-42", ...}`) are remembered per client. A message
+//! replayed within the window, typically after a reconnect, is answered with
+//! `{"type":"ack","messageId":"m-42","duplicate":true}` and not forwarded.
 
 use crate::error::{ZapError, ZapResult};
 use crate::ipc::{IpcClient, IpcEncoding, IpcMessage};
 use futures::{SinkExt, StreamExt};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
 use tokio_tungstenite::{
-    accept_async,
-    tungstenite::{Error as WsError, Message as WsMessage},
+    accept_async_with_config, accept_hdr_async_with_config,
+    tungstenite::{
+        error::CapacityError,
+        handshake::server::{ErrorResponse, Request as HandshakeRequest, Response as HandshakeResponse},
+        http::StatusCode,
+        protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig},
+        Error as WsError, Message as WsMessage,
+    },
     WebSocketStream,
 };
 use tracing::{debug, error, info, warn};
@@ -40,8 +70,14 @@ pub struct WsConfig {
     pub handler_id: String,
     /// Maximum message size (default: 64KB)
     pub max_message_size: usize,
-    /// Ping interval in seconds (default: 30)
+    /// Ping interval in seconds (default: 30, 0 disables pings)
     pub ping_interval_secs: u64,
+    /// Suppress replayed client messages (disabled if `None`)
+    pub dedup: Option<Arc<WsDedup>>,
+    /// Maximum simultaneous connections for this handler (unlimited if `None`)
+    pub max_connections: Option<usize>,
+    /// Active connections, shared by every clone of this config
+    pub connection_count: Arc<AtomicUsize>,
 }
 
 impl Default for WsConfig {
@@ -51,6 +87,9 @@ impl Default for WsConfig {
             handler_id: String::new(),
             max_message_size: 64 * 1024, // 64KB
             ping_interval_secs: 30,
+            dedup: None,
+            max_connections: None,
+            connection_count: Arc::new(AtomicUsize::new(0)),
         }
     }
 }
@@ -64,6 +103,227 @@ impl WsConfig {
             ..Default::default()
         }
     }
+
+    /// Suppress replayed client messages, sharing `dedup` across connections
+    pub fn with_dedup(mut self, dedup: Arc<WsDedup>) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    /// Refuse upgrades once `max` connections are open
+    pub fn with_max_connections(mut self, max: usize) -> Self {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// Number of currently open connections
+    pub fn active_connections(&self) -> usize {
+        self.connection_count.load(Ordering::Acquire)
+    }
+
+    /// Reserve a connection slot, or `None` if the limit is reached
+    fn try_reserve_connection(&self) -> Option<ConnectionSlot> {
+        let max = self.max_connections.unwrap_or(usize::MAX);
+        self.connection_count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| (count < max).then_some(count + 1))
+            .ok()
+            .map(|_| ConnectionSlot(self.connection_count.clone()))
+    }
+
+    /// Protocol limits applied to the accepted WebSocket
+    fn protocol_config(&self) -> WebSocketConfig {
+        WebSocketConfig {
+            max_message_size: Some(self.max_message_size),
+            max_frame_size: Some(self.max_message_size),
+            ..Default::default()
+        }
+    }
+
+    fn ping_interval(&self) -> Option<Duration> {
+        (self.ping_interval_secs > 0).then(|| Duration::from_secs(self.ping_interval_secs))
+    }
+}
+
+/// A reserved connection, released when dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Close code reported to TypeScript when a client stops answering pings
+const PING_TIMEOUT_CLOSE_CODE: u16 = 1006;
+
+/// Reason sent with 1009 when a client message exceeds `max_message_size`
+const MESSAGE_TOO_BIG_REASON: &str = "Message too big";
+
+/// How long the outbound task may keep flushing after the inbound side ends
+const OUTBOUND_CLOSE_GRACE: Duration = Duration::from_secs(1);
+
+/// Ping timer for a connection, if pings are enabled
+fn ping_timer(period: Option<Duration>) -> Option<Interval> {
+    period.map(|period| {
+        let mut timer = tokio::time::interval_at(Instant::now() + period, period);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        timer
+    })
+}
+
+/// Wait for the next ping, or forever when pings are disabled
+async fn next_ping(timer: &mut Option<Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Replay protection settings
+#[derive(Debug, Clone)]
+pub struct WsDedupConfig {
+    /// Message ids remembered per client (default: 256)
+    pub window: usize,
+    /// JSON field holding the message id (default: "messageId")
+    pub id_field: String,
+    /// Clients tracked before the least recently active one is forgotten
+    /// (default: 10,000)
+    pub max_identities: usize,
+}
+
+impl Default for WsDedupConfig {
+    fn default() -> Self {
+        Self {
+            window: 256,
+            id_field: "messageId".to_string(),
+            max_identities: 10_000,
+        }
+    }
+}
+
+/// Recently seen message ids for one client, oldest first
+#[derive(Debug, Default)]
+struct RecentIds {
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct DedupState {
+    clients: HashMap<String, RecentIds>,
+    /// Clients by their `last_used` tick, least recently active first
+    by_use: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+/// Resolves the authenticated client behind a WebSocket upgrade from its
+/// request headers, e.g. by verifying a session cookie
+pub type WsIdentityFn = Arc<dyn Fn(&HashMap<String, String>) -> Option<String> + Send + Sync>;
+
+/// Recently seen client message ids, shared by the connections of a route
+pub struct WsDedup {
+    config: WsDedupConfig,
+    identify: Option<WsIdentityFn>,
+    state: Mutex<DedupState>,
+}
+
+impl WsDedup {
+    pub fn new(config: WsDedupConfig) -> Self {
+        Self {
+            config,
+            identify: None,
+            state: Mutex::new(DedupState::default()),
+        }
+    }
+
+    /// Remember ids per authenticated client, across reconnects
+    ///
+    /// Without it ids are only remembered per connection. Never derive the
+    /// identity from a header the client may set freely: anyone could then
+    /// have another client's messages suppressed.
+    pub fn with_identity(mut self, identify: WsIdentityFn) -> Self {
+        self.identify = Some(identify);
+        self
+    }
+
+    /// Key ids are remembered under: the authenticated client when it can be
+    /// resolved, else the connection
+    pub fn identity(&self, connection_id: &str, headers: &HashMap<String, String>) -> String {
+        self.identify
+            .as_ref()
+            .and_then(|identify| identify(headers))
+            .map(|client| format!("client:{}", client))
+            .unwrap_or_else(|| format!("connection:{}", connection_id))
+    }
+
+    /// Forget a closed connection's ids
+    ///
+    /// Ids of an authenticated client are kept for its reconnect.
+    pub fn connection_closed(&self, identity: &str) {
+        if identity.starts_with("connection:") {
+            let mut state = self.state.lock().unwrap();
+            if let Some(recent) = state.clients.remove(identity) {
+                state.by_use.remove(&recent.last_used);
+            }
+        }
+    }
+
+    /// Client-supplied id of a text message, if it has one
+    pub fn message_id(&self, text: &str) -> Option<String> {
+        let value: serde_json::Value = serde_json::from_str(text).ok()?;
+        match value.get(&self.config.id_field)? {
+            serde_json::Value::String(id) if !id.is_empty() => Some(id.clone()),
+            serde_json::Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Record `message_id` for `identity`, returning false if it was seen
+    /// within the window
+    pub fn first_seen(&self, identity: &str, message_id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        if !state.clients.contains_key(identity) && state.clients.len() >= self.config.max_identities.max(1) {
+            if let Some((_, idle)) = state.by_use.pop_first() {
+                state.clients.remove(&idle);
+            }
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        let recent = state.clients.entry(identity.to_string()).or_default();
+        state.by_use.remove(&recent.last_used);
+        state.by_use.insert(tick, identity.to_string());
+        recent.last_used = tick;
+
+        if recent.seen.contains(message_id) {
+            return false;
+        }
+        if recent.order.len() >= self.config.window.max(1) {
+            if let Some(oldest) = recent.order.pop_front() {
+                recent.seen.remove(&oldest);
+            }
+        }
+        recent.order.push_back(message_id.to_string());
+        recent.seen.insert(message_id.to_string());
+        true
+    }
+
+    /// Id of `text` if it replays a message already seen from `identity`
+    fn replayed(&self, identity: &str, text: &str) -> Option<String> {
+        let message_id = self.message_id(text)?;
+        (!self.first_seen(identity, &message_id)).then_some(message_id)
+    }
+}
+
+/// Acknowledgement sent in place of forwarding a replayed message
+fn duplicate_ack(message_id: &str) -> WsMessage {
+    WsMessage::Text(
+        serde_json::json!({ "type": "ack", "messageId": message_id, "duplicate": true }).to_string(),
+    )
 }
 
 /// Handle a WebSocket connection
@@ -79,8 +339,27 @@ pub async fn handle_websocket_connection<S>(
 where
     S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
+    // Refuse the upgrade outright when the handler is at capacity
+    let Some(_slot) = config.try_reserve_connection() else {
+        warn!(
+            "WebSocket handler {} at its limit of {} connections, refusing {}",
+            config.handler_id,
+            config.max_connections.unwrap_or_default(),
+            path
+        );
+        // The error type is fixed by tungstenite's handshake callback
+        #[allow(clippy::result_large_err)]
+        let refuse = |_: &HandshakeRequest, _: HandshakeResponse| -> Result<HandshakeResponse, ErrorResponse> {
+            let mut response = ErrorResponse::new(Some("Too many WebSocket connections".to_string()));
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            Err(response)
+        };
+        let _ = accept_hdr_async_with_config(stream, refuse, None).await;
+        return Err(ZapError::websocket("Connection limit reached"));
+    };
+
     // Accept the WebSocket connection
-    let ws_stream = accept_async(stream).await.map_err(|e| {
+    let ws_stream = accept_async_with_config(stream, Some(config.protocol_config())).await.map_err(|e| {
         error!("WebSocket handshake failed: {}", e);
         ZapError::websocket(format!("Handshake failed: {}", e))
     })?;
@@ -119,30 +398,49 @@ where
     let connection_id_clone = connection_id.clone();
     let config_clone = config.clone();
 
-    // Task 1: Handle incoming WebSocket messages from client
-    let inbound_handle = tokio::spawn(async move {
-        handle_inbound_messages(ws_stream, ipc_client, connection_id_clone, config_clone).await
+    // Replayed messages are remembered per client identity
+    let identity = config
+        .dedup
+        .as_ref()
+        .map(|dedup| dedup.identity(&connection_id, &headers));
+    let inbound_identity = identity.clone();
+    let inbound_tx = outbound_tx.clone();
+
+    // Task 1: Handle incoming WebSocket messages from client and keepalive pings
+    let mut inbound_handle = tokio::spawn(async move {
+        handle_inbound_messages(ws_stream, ipc_client, connection_id_clone, config_clone, inbound_identity, inbound_tx).await
     });
 
     // Task 2: Handle outbound messages to client
-    let outbound_handle = tokio::spawn(async move {
+    let mut outbound_handle = tokio::spawn(async move {
         handle_outbound_messages(ws_sink, outbound_rx).await
     });
 
-    // Wait for either task to complete
+    // Wait for either task to complete, then stop the other so a dead peer
+    // cannot keep it alive
     tokio::select! {
-        result = inbound_handle => {
+        result = &mut inbound_handle => {
             if let Err(e) = result {
                 error!("Inbound handler error: {}", e);
             }
+            // Let a queued close frame go out, unless the peer stopped reading
+            drop(outbound_tx);
+            if tokio::time::timeout(OUTBOUND_CLOSE_GRACE, &mut outbound_handle).await.is_err() {
+                outbound_handle.abort();
+            }
         }
-        result = outbound_handle => {
+        result = &mut outbound_handle => {
             if let Err(e) = result {
                 error!("Outbound handler error: {}", e);
             }
+            inbound_handle.abort();
         }
     }
 
+    if let (Some(dedup), Some(identity)) = (&config.dedup, &identity) {
+        dedup.connection_closed(identity);
+    }
+
     info!("WebSocket connection closed: {}", connection_id);
     Ok(())
 }
@@ -153,11 +451,45 @@ async fn handle_inbound_messages<S>(
     mut ipc_client: IpcClient,
     connection_id: String,
     config: WsConfig,
+    identity: Option<String>,
+    outbound_tx: mpsc::Sender<WsMessage>,
 ) -> ZapResult<()>
 where
     S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
 {
-    while let Some(msg_result) = ws_stream.next().await {
+    let ping_interval = config.ping_interval();
+    let mut pings = ping_timer(ping_interval);
+    let mut last_pong = Instant::now();
+
+    loop {
+        let msg_result = tokio::select! {
+            msg = ws_stream.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = next_ping(&mut pings) => {
+                let period = ping_interval.unwrap_or_default();
+                if last_pong.elapsed() >= period * 2 {
+                    warn!("WebSocket {} missed pongs for {:?}, closing", connection_id, last_pong.elapsed());
+                    // A peer that stopped answering has likely stopped reading
+                    // too, so a full queue must not hold up the close
+                    queue_close(&outbound_tx, &connection_id, None);
+
+                    let close_msg = IpcMessage::WsClose {
+                        connection_id: connection_id.clone(),
+                        handler_id: config.handler_id.clone(),
+                        code: Some(PING_TIMEOUT_CLOSE_CODE),
+                        reason: Some("Ping timeout".to_string()),
+                    };
+                    let _ = ipc_client.send_message(close_msg).await;
+                    break;
+                }
+                // Never wait on a full queue here; the next tick checks again
+                let _ = outbound_tx.try_send(WsMessage::Ping(Vec::new()));
+                continue;
+            }
+        };
+
         match msg_result {
             Ok(msg) => {
                 match msg {
@@ -168,6 +500,20 @@ where
                             text.len()
                         );
 
+                        if text.len() > config.max_message_size {
+                            close_oversized(&mut ipc_client, &outbound_tx, &connection_id, &config, text.len()).await;
+                            break;
+                        }
+
+                        // Acknowledge replays without forwarding them again
+                        if let (Some(dedup), Some(identity)) = (&config.dedup, &identity) {
+                            if let Some(message_id) = dedup.replayed(identity, &text) {
+                                debug!("Suppressed replayed message {} from {}", message_id, connection_id);
+                                let _ = outbound_tx.send(duplicate_ack(&message_id)).await;
+                                continue;
+                            }
+                        }
+
                         // Forward to TypeScript
                         let ipc_msg = IpcMessage::WsMessage {
                             connection_id: connection_id.clone(),
@@ -187,6 +533,12 @@ where
                             data.len()
                         );
 
+                        // Checked before base64 inflates it by a third
+                        if data.len() > config.max_message_size {
+                            close_oversized(&mut ipc_client, &outbound_tx, &connection_id, &config, data.len()).await;
+                            break;
+                        }
+
                         // Forward to TypeScript (base64 encoded)
                         use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
                         let encoded = BASE64.encode(&data);
@@ -208,6 +560,7 @@ where
                     }
                     WsMessage::Pong(_) => {
                         debug!("Received pong from {}", connection_id);
+                        last_pong = Instant::now();
                     }
                     WsMessage::Close(frame) => {
                         let (code, reason) = frame
@@ -234,6 +587,10 @@ where
                     }
                 }
             }
+            Err(WsError::Capacity(CapacityError::MessageTooLong { size, .. })) => {
+                close_oversized(&mut ipc_client, &outbound_tx, &connection_id, &config, size).await;
+                break;
+            }
             Err(e) => {
                 match e {
                     WsError::ConnectionClosed | WsError::AlreadyClosed => {
@@ -260,6 +617,44 @@ where
     Ok(())
 }
 
+/// Queue a close frame without waiting for room in the outbound queue
+///
+/// When the queue is full the frame is dropped; the connection is torn down
+/// regardless once the inbound task returns.
+fn queue_close(outbound_tx: &mpsc::Sender<WsMessage>, connection_id: &str, frame: Option<CloseFrame<'static>>) {
+    if let Err(mpsc::error::TrySendError::Full(_)) = outbound_tx.try_send(WsMessage::Close(frame)) {
+        debug!("Outbound queue for {} is full, dropping close frame", connection_id);
+    }
+}
+
+/// Close a client that sent more than `max_message_size` with 1009
+async fn close_oversized(
+    ipc_client: &mut IpcClient,
+    outbound_tx: &mpsc::Sender<WsMessage>,
+    connection_id: &str,
+    config: &WsConfig,
+    size: usize,
+) {
+    warn!(
+        "WebSocket {} sent a {} byte message, limit is {}; closing",
+        connection_id, size, config.max_message_size
+    );
+
+    let frame = CloseFrame {
+        code: CloseCode::Size,
+        reason: MESSAGE_TOO_BIG_REASON.into(),
+    };
+    queue_close(outbound_tx, connection_id, Some(frame));
+
+    let close_msg = IpcMessage::WsClose {
+        connection_id: connection_id.to_string(),
+        handler_id: config.handler_id.clone(),
+        code: Some(CloseCode::Size.into()),
+        reason: Some(MESSAGE_TOO_BIG_REASON.to_string()),
+    };
+    let _ = ipc_client.send_message(close_msg).await;
+}
+
 /// Handle outbound WebSocket messages to the client
 async fn handle_outbound_messages<S>(
     mut ws_sink: futures::stream::SplitSink<WebSocketStream<S>, WsMessage>,
@@ -330,6 +725,80 @@ impl WsHandler {
         }
     }
 
+    /// Send `message` to every registered connection
+    ///
+    /// Returns the connections the message could not be delivered to. Those
+    /// whose receiver is gone are unregistered; those whose outbound queue is
+    /// full miss this message but stay registered.
+    pub async fn broadcast(&self, message: WsMessage) -> Vec<(String, ZapError)> {
+        let targets: Vec<_> = {
+            let senders = self.senders.read().await;
+            senders
+                .iter()
+                .map(|(id, sender)| (id.clone(), sender.clone()))
+                .collect()
+        };
+        self.fan_out(targets, Vec::new(), message).await
+    }
+
+    /// Send `message` to each of `connection_ids`
+    ///
+    /// Unknown connections are reported alongside failed sends instead of
+    /// stopping the broadcast.
+    pub async fn broadcast_to(&self, connection_ids: &[String], message: WsMessage) -> Vec<(String, ZapError)> {
+        let mut targets = Vec::with_capacity(connection_ids.len());
+        let mut errors = Vec::new();
+        {
+            let senders = self.senders.read().await;
+            for id in connection_ids {
+                match senders.get(id) {
+                    Some(sender) => targets.push((id.clone(), sender.clone())),
+                    None => errors.push((id.clone(), ZapError::websocket(format!("Connection {} not found", id)))),
+                }
+            }
+        }
+        self.fan_out(targets, errors, message).await
+    }
+
+    /// Queue `message` for all `targets` without waiting, pruning closed
+    /// connections
+    ///
+    /// A slow client whose queue is full is reported instead of stalling
+    /// delivery to everyone else.
+    async fn fan_out(
+        &self,
+        targets: Vec<(String, mpsc::Sender<WsMessage>)>,
+        mut errors: Vec<(String, ZapError)>,
+        message: WsMessage,
+    ) -> Vec<(String, ZapError)> {
+        let mut closed = Vec::new();
+        for (id, sender) in targets {
+            match sender.try_send(message.clone()) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    errors.push((id.clone(), ZapError::websocket(format!("Outbound queue for {} is full", id))));
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    errors.push((id.clone(), ZapError::websocket(format!("Connection {} is closed", id))));
+                    closed.push((id, sender));
+                }
+            }
+        }
+
+        if !closed.is_empty() {
+            let mut senders = self.senders.write().await;
+            for (id, sender) in closed {
+                // Keep a connection that re-registered under the same id meanwhile
+                if senders.get(&id).is_some_and(|current| current.same_channel(&sender)) {
+                    debug!("Pruning closed WebSocket connection {}", id);
+                    senders.remove(&id);
+                }
+            }
+        }
+
+        errors
+    }
+
     /// Handle an IPC message for WebSocket (from TypeScript)
     pub async fn handle_ipc_message(&self, msg: IpcMessage) -> ZapResult<()> {
         match msg {
@@ -426,4 +895,342 @@ mod tests {
         assert_eq!(config.ipc_socket_path, "/tmp/test.sock");
         assert_eq!(config.handler_id, "ws_handler_0");
     }
+
+    #[test]
+    fn test_dedup_suppresses_replays_within_window() {
+        let dedup = WsDedup::new(WsDedupConfig {
+            window: 2,
+            ..Default::default()
+        });
+        let client = "connection:a";
+
+        assert_eq!(dedup.replayed(client, r#"{"messageId":"m1","text":"hi"}"#), None);
+        // Replayed after a reconnect: suppressed
+        assert_eq!(dedup.replayed(client, r#"{"messageId":"m1","text":"hi"}"#), Some("m1".to_string()));
+        // A new id is forwarded, as are messages without one
+        assert_eq!(dedup.replayed(client, r#"{"messageId":"m2"}"#), None);
+        assert_eq!(dedup.replayed(client, "plain text"), None);
+        assert_eq!(dedup.replayed(client, "plain text"), None);
+        // Other clients have their own window
+        assert_eq!(dedup.replayed("connection:b", r#"{"messageId":"m1"}"#), None);
+
+        // Once m1 falls out of the window it is forwarded again
+        assert_eq!(dedup.replayed(client, r#"{"messageId":3}"#), None);
+        assert_eq!(dedup.replayed(client, r#"{"messageId":"m1"}"#), None);
+        assert_eq!(dedup.replayed(client, r#"{"messageId":3}"#), Some("3".to_string()));
+    }
+
+    #[test]
+    fn test_dedup_forgets_least_recently_active_client() {
+        let dedup = WsDedup::new(WsDedupConfig {
+            max_identities: 2,
+            ..Default::default()
+        });
+
+        assert!(dedup.first_seen("client:a", "m1"));
+        assert!(dedup.first_seen("client:b", "m1"));
+        // a stays active, so b is the one forgotten to make room for c
+        assert!(dedup.first_seen("client:a", "m2"));
+        assert!(dedup.first_seen("client:c", "m1"));
+
+        assert!(!dedup.first_seen("client:a", "m1"));
+        assert!(dedup.first_seen("client:b", "m1"));
+    }
+
+    #[test]
+    fn test_dedup_identity_survives_reconnect() {
+        // Stands in for verifying a session token
+        let dedup = WsDedup::new(WsDedupConfig::default()).with_identity(Arc::new(|headers| {
+            headers.get("authorization").and_then(|token| token.strip_prefix("Bearer valid-")).map(str::to_string)
+        }));
+        let headers = HashMap::from([("authorization".to_string(), "Bearer valid-user-7".to_string())]);
+
+        let first = dedup.identity("conn-1", &headers);
+        let reconnected = dedup.identity("conn-2", &headers);
+        assert_eq!(first, reconnected);
+        let forged = HashMap::from([("authorization".to_string(), "Bearer user-7".to_string())]);
+        assert_ne!(dedup.identity("conn-3", &forged), first);
+        assert_ne!(dedup.identity("conn-1", &HashMap::new()), dedup.identity("conn-2", &HashMap::new()));
+
+        assert!(dedup.first_seen(&first, "m1"));
+        dedup.connection_closed(&first);
+        assert!(!dedup.first_seen(&reconnected, "m1"));
+
+        // A connection's ids go away with it
+        let connection = dedup.identity("conn-4", &HashMap::new());
+        assert!(dedup.first_seen(&connection, "m1"));
+        dedup.connection_closed(&connection);
+        assert!(dedup.first_seen(&connection, "m1"));
+        assert_eq!(dedup.state.lock().unwrap().by_use.len(), 2);
+
+        match duplicate_ack("m1") {
+            WsMessage::Text(text) => {
+                let ack: serde_json::Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(ack["type"], "ack");
+                assert_eq!(ack["messageId"], "m1");
+                assert_eq!(ack["duplicate"], true);
+            }
+            other => panic!("Expected a text ack, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unresponsive_client_closed_after_missed_pongs() {
+        let socket = std::env::temp_dir().join(format!("zap-ws-ping-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+        // Fake TypeScript runtime recording what it hears about the connection
+        let runtime = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+            assert!(matches!(runtime.recv_message().await.unwrap(), Some(IpcMessage::WsConnect { .. })));
+            runtime.recv_message().await.unwrap()
+        });
+
+        let mut config = WsConfig::new(socket.display().to_string(), "ws_handler".to_string());
+        config.ping_interval_secs = 1;
+
+        let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(handle_websocket_connection(
+            server_io,
+            config,
+            "/ws".to_string(),
+            HashMap::new(),
+        ));
+        // The client completes the handshake and then never reads, so pings go unanswered
+        let (_client, _) = tokio_tungstenite::client_async("ws://localhost/ws", client_io)
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("connection outlived the pong deadline")
+            .unwrap()
+            .unwrap();
+
+        match runtime.await.unwrap() {
+            Some(IpcMessage::WsClose { code, reason, .. }) => {
+                assert_eq!(code, Some(PING_TIMEOUT_CLOSE_CODE));
+                assert_eq!(reason.as_deref(), Some("Ping timeout"));
+            }
+            other => panic!("Expected WsClose, got {:?}", other),
+        }
+        let _ = std::fs::remove_file(socket);
+    }
+
+    /// Serve one WebSocket over an in-memory stream, with a fake runtime
+    /// reporting every IPC message it receives after `WsConnect`
+    async fn connect_ws(
+        name: &str,
+        config: impl FnOnce(WsConfig) -> WsConfig,
+    ) -> (
+        WebSocketStream<tokio::io::DuplexStream>,
+        mpsc::UnboundedReceiver<IpcMessage>,
+        std::path::PathBuf,
+    ) {
+        let socket = std::env::temp_dir().join(format!("zap-ws-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+        let (seen_tx, seen_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+            assert!(matches!(runtime.recv_message().await.unwrap(), Some(IpcMessage::WsConnect { .. })));
+            while let Ok(Some(msg)) = runtime.recv_message().await {
+                let _ = seen_tx.send(msg);
+            }
+        });
+
+        let config = config(WsConfig::new(socket.display().to_string(), "ws_handler".to_string()));
+        let (server_io, client_io) = tokio::io::duplex(256 * 1024);
+        tokio::spawn(handle_websocket_connection(server_io, config, "/ws".to_string(), HashMap::new()));
+        let (client, _) = tokio_tungstenite::client_async("ws://localhost/ws", client_io)
+            .await
+            .unwrap();
+
+        (client, seen_rx, socket)
+    }
+
+    #[tokio::test]
+    async fn test_message_under_limit_forwarded() {
+        let (mut client, mut seen, socket) = connect_ws("small", |mut config| {
+            config.max_message_size = 64;
+            config
+        })
+        .await;
+
+        client.send(WsMessage::Text("x".repeat(64))).await.unwrap();
+        client.send(WsMessage::Binary(vec![0xff; 64])).await.unwrap();
+
+        match seen.recv().await {
+            Some(IpcMessage::WsMessage { data, binary: false, .. }) => assert_eq!(data.len(), 64),
+            other => panic!("Expected text WsMessage, got {:?}", other),
+        }
+        match seen.recv().await {
+            Some(IpcMessage::WsMessage { binary: true, .. }) => {}
+            other => panic!("Expected binary WsMessage, got {:?}", other),
+        }
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_message_over_limit_closes_with_1009() {
+        let (mut client, mut seen, socket) = connect_ws("big", |mut config| {
+            config.max_message_size = 64;
+            config
+        })
+        .await;
+
+        client.send(WsMessage::Binary(vec![0xff; 65])).await.unwrap();
+
+        let frame = loop {
+            match client.next().await {
+                Some(Ok(WsMessage::Close(frame))) => break frame,
+                Some(Ok(_)) => continue,
+                other => panic!("Expected a close frame, got {:?}", other),
+            }
+        };
+        let frame = frame.expect("close frame without a code");
+        assert_eq!(frame.code, CloseCode::Size);
+
+        // TypeScript hears about the close, never about the message
+        match seen.recv().await {
+            Some(IpcMessage::WsClose { code, .. }) => assert_eq!(code, Some(1009)),
+            other => panic!("Expected WsClose, got {:?}", other),
+        }
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reaches_registered_connections() {
+        let handler = WsHandler::new(WsConfig::default());
+        let mut receivers = Vec::new();
+        for id in ["a", "b", "c", "gone"] {
+            let (tx, rx) = mpsc::channel(4);
+            handler.register_connection(id.to_string(), tx).await;
+            receivers.push(rx);
+        }
+        let mut gone = receivers.pop().unwrap();
+        handler.unregister_connection("gone").await;
+
+        let errors = handler.broadcast(WsMessage::Text("hello".to_string())).await;
+        assert!(errors.is_empty());
+        for rx in &mut receivers {
+            assert_eq!(rx.recv().await, Some(WsMessage::Text("hello".to_string())));
+        }
+        assert!(gone.try_recv().is_err());
+
+        // A subset, including an unknown connection
+        let ids = vec!["a".to_string(), "gone".to_string()];
+        let errors = handler.broadcast_to(&ids, WsMessage::Text("hi a".to_string())).await;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "gone");
+        assert_eq!(receivers[0].recv().await, Some(WsMessage::Text("hi a".to_string())));
+        assert!(receivers[1].try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_prunes_closed_connections() {
+        let handler = WsHandler::new(WsConfig::default());
+        let (live_tx, mut live_rx) = mpsc::channel(4);
+        let (dead_tx, dead_rx) = mpsc::channel(4);
+        handler.register_connection("live".to_string(), live_tx).await;
+        handler.register_connection("dead".to_string(), dead_tx).await;
+        drop(dead_rx);
+
+        let errors = handler.broadcast(WsMessage::Text("ping".to_string())).await;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "dead");
+        assert!(live_rx.recv().await.is_some());
+
+        assert!(!handler.senders.read().await.contains_key("dead"));
+        assert!(handler.broadcast(WsMessage::Text("again".to_string())).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reports_full_queue_without_waiting() {
+        let handler = WsHandler::new(WsConfig::default());
+        let (slow_tx, mut slow_rx) = mpsc::channel(1);
+        let (fast_tx, mut fast_rx) = mpsc::channel(4);
+        handler.register_connection("slow".to_string(), slow_tx).await;
+        handler.register_connection("fast".to_string(), fast_tx).await;
+
+        assert!(handler.broadcast(WsMessage::Text("one".to_string())).await.is_empty());
+
+        // "slow" never drained its queue; the broadcast must not wait for it
+        let errors = tokio::time::timeout(
+            Duration::from_secs(1),
+            handler.broadcast(WsMessage::Text("two".to_string())),
+        )
+        .await
+        .expect("broadcast waited on a full queue");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "slow");
+        assert!(errors[0].1.to_string().contains("full"));
+
+        assert_eq!(fast_rx.recv().await, Some(WsMessage::Text("one".to_string())));
+        assert_eq!(fast_rx.recv().await, Some(WsMessage::Text("two".to_string())));
+        assert_eq!(slow_rx.recv().await, Some(WsMessage::Text("one".to_string())));
+
+        // A full queue is not a closed connection
+        assert!(handler.senders.read().await.contains_key("slow"));
+        assert!(handler.broadcast(WsMessage::Text("three".to_string())).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit_refuses_extra_upgrades() {
+        let socket = std::env::temp_dir().join(format!("zap-ws-limit-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+
+        // Fake runtime keeping every connection's IPC stream open
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut runtime = IpcClient::from_stream(stream, IpcEncoding::MessagePack);
+                    while let Ok(Some(_)) = runtime.recv_message().await {}
+                });
+            }
+        });
+
+        let config = WsConfig::new(socket.display().to_string(), "ws_handler".to_string()).with_max_connections(2);
+        let open = |config: WsConfig| async move {
+            let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+            let server = tokio::spawn(handle_websocket_connection(server_io, config, "/ws".to_string(), HashMap::new()));
+            let client = tokio_tungstenite::client_async("ws://localhost/ws", client_io).await;
+            (client, server)
+        };
+
+        let (first, first_server) = open(config.clone()).await;
+        let (second, second_server) = open(config.clone()).await;
+        let (mut first, _) = first.unwrap();
+        let (_second, _) = second.unwrap();
+        assert_eq!(config.active_connections(), 2);
+
+        let (third, third_server) = open(config.clone()).await;
+        match third {
+            Err(WsError::Http(response)) => assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE),
+            other => panic!("Expected a 503 refusal, got {:?}", other.map(|_| ())),
+        }
+        assert!(third_server.await.unwrap().is_err());
+
+        // The first two are unaffected
+        assert!(!first_server.is_finished());
+        assert!(!second_server.is_finished());
+        first.send(WsMessage::Text("still here".to_string())).await.unwrap();
+
+        // Closing one frees its slot
+        first.close(None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(2), first_server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.active_connections(), 1);
+        let (fourth, _fourth_server) = open(config.clone()).await;
+        assert!(fourth.is_ok());
+        let _ = std::fs::remove_file(socket);
+    }
 }
@@ -34,6 +34,29 @@ pub async fn async_divide(a: f64, b: f64) -> Result<f64, String> {
     }
 }
 
+// Test trailing Option parameter
+#[export]
+pub fn greet_optional(name: String, title: Option<String>) -> String {
+    match title {
+        Some(title) => format!("Hello, {} {}!", title, name),
+        None => format!("Hello, {}!", name),
+    }
+}
+
+// Test binary parameters and returns
+#[export]
+pub fn reverse_bytes(data: Vec<u8>) -> Vec<u8> {
+    data.into_iter().rev().collect()
+}
+
+#[export]
+pub fn checked_bytes(data: Option<Vec<u8>>) -> Result<Option<Vec<u8>>, String> {
+    match data {
+        Some(data) if data.is_empty() => Err("empty".to_string()),
+        other => Ok(other),
+    }
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_registry_builds() {
     // Simply building the dispatcher should collect all registered functions
@@ -136,6 +159,79 @@ async fn test_missing_parameter() {
     assert!(error_msg.contains("Missing parameter"), "Error should mention missing parameter");
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_omitted_option_parameter_is_none() {
+    let dispatcher = zap_server::build_rpc_dispatcher();
+
+    // JSON.stringify drops undefined members, so the key is simply absent
+    let result = dispatcher(
+        "greet_optional".to_string(),
+        json!({"name": "Ada"}),
+        None
+    );
+    assert_eq!(result.unwrap(), json!("Hello, Ada!"));
+
+    let result = dispatcher(
+        "greet_optional".to_string(),
+        json!({"name": "Ada", "title": null}),
+        None
+    );
+    assert_eq!(result.unwrap(), json!("Hello, Ada!"));
+
+    let result = dispatcher(
+        "greet_optional".to_string(),
+        json!({"name": "Ada", "title": "Dr."}),
+        None
+    );
+    assert_eq!(result.unwrap(), json!("Hello, Dr. Ada!"));
+
+    // Required parameters are still required
+    let result = dispatcher(
+        "greet_optional".to_string(),
+        json!({"title": "Dr."}),
+        None
+    );
+    assert!(result.unwrap_err().contains("Missing parameter"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bytes_cross_the_wrapper_as_base64() {
+    let dispatcher = zap_server::build_rpc_dispatcher();
+
+    // [1, 2, 3] as sent by the generated client's encodeBytes
+    let result = dispatcher(
+        "reverse_bytes".to_string(),
+        json!({"data": "AQID"}),
+        None
+    );
+    assert_eq!(result.unwrap(), json!("AwIB"));
+
+    // A plain byte array is still accepted
+    let result = dispatcher(
+        "reverse_bytes".to_string(),
+        json!({"data": [1, 2, 3]}),
+        None
+    );
+    assert_eq!(result.unwrap(), json!("AwIB"));
+
+    let result = dispatcher(
+        "reverse_bytes".to_string(),
+        json!({"data": "not base64!"}),
+        None
+    );
+    assert!(result.unwrap_err().contains("Failed to deserialize parameter 'data'"));
+
+    let result = dispatcher(
+        "checked_bytes".to_string(),
+        json!({"data": "AQID"}),
+        None
+    );
+    assert_eq!(result.unwrap(), json!("AQID"));
+
+    let result = dispatcher("checked_bytes".to_string(), json!({}), None);
+    assert_eq!(result.unwrap(), json!(null));
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_wrong_parameter_type() {
     let dispatcher = zap_server::build_rpc_dispatcher();